@@ -0,0 +1,87 @@
+//! Scheduled automatic history clearing
+//!
+//! The `auto_clear_schedule` setting is `off`, `daily_at(HH:MM)` (local time)
+//! or `on_quit`. A background thread periodically checks whether the
+//! next daily run has come due since the last one. The check compares wall
+//! clock time, so a run missed while the machine was asleep (or the app was
+//! closed) executes at the first check after wake. Clearing uses the same
+//! logic as the clear-history command and emits `history-cleared`.
+
+pub mod schedule;
+
+pub use schedule::AutoClearSchedule;
+
+use crate::error::ClipsterError;
+use crate::events::{self, HistoryClearedPayload};
+use crate::storage::Database;
+use crate::AppState;
+use chrono::{DateTime, Local, Utc};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the scheduler checks whether a run is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Start the scheduler thread
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        if let Some(state) = app.try_state::<AppState>() {
+            if let Err(e) = run_if_due(&app, &state.db) {
+                eprintln!("[auto_clear] Scheduled clear failed: {}", e);
+            }
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+/// Next scheduled clear (None unless a daily schedule is set)
+pub fn next_run(db: &Database) -> Option<DateTime<Utc>> {
+    let schedule = db.get_auto_clear_schedule().ok()?;
+    let last_run = db.get_auto_clear_last_run().ok()?.unwrap_or_else(Utc::now);
+    schedule.next_run_after(last_run, &Local)
+}
+
+/// Clear history if the daily run is due
+fn run_if_due(app: &AppHandle, db: &Database) -> Result<(), ClipsterError> {
+    let schedule = db.get_auto_clear_schedule()?;
+    if !matches!(schedule, AutoClearSchedule::DailyAt(_)) {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    match db.get_auto_clear_last_run()? {
+        Some(last_run) if schedule.is_due(last_run, now, &Local) => {
+            clear(app, db)?;
+            db.set_auto_clear_last_run(now)
+        }
+        Some(_) => Ok(()),
+        // Schedule set without a start time (e.g. through update_setting): start now
+        None => db.set_auto_clear_last_run(now),
+    }
+}
+
+/// Exit hook: clear history when the schedule is `on_quit`
+pub fn on_quit(app: &AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    if state.db.get_auto_clear_schedule().ok() != Some(AutoClearSchedule::OnQuit) {
+        return;
+    }
+    if let Err(e) = clear(app, &state.db) {
+        eprintln!("[auto_clear] Clear on quit failed: {}", e);
+    }
+}
+
+fn clear(app: &AppHandle, db: &Database) -> Result<usize, ClipsterError> {
+    let deleted = db.clear_history()?;
+    println!("[auto_clear] Cleared {} items", deleted);
+
+    if let Some(state) = app.try_state::<AppState>() {
+        state.spotlight.sync();
+    }
+    events::emit(app, &HistoryClearedPayload { deleted });
+
+    Ok(deleted)
+}
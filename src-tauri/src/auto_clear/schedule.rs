@@ -0,0 +1,246 @@
+//! Auto-clear schedule parsing and next-run computation
+//!
+//! Kept free of Tauri so the time zone handling can be tested directly.
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::fmt;
+
+/// Longest DST gap searched for a valid local time (some zones have skipped a whole day)
+const MAX_GAP_MINUTES: i64 = 24 * 60;
+
+/// Value of the `auto_clear_schedule` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AutoClearSchedule {
+    /// Never clear automatically
+    #[default]
+    Off,
+    /// Clear every day at this local time
+    DailyAt(NaiveTime),
+    /// Clear when the app quits
+    OnQuit,
+}
+
+impl AutoClearSchedule {
+    /// Parse `off`, `on_quit` or `daily_at(HH:MM)`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "off" => Some(Self::Off),
+            "on_quit" => Some(Self::OnQuit),
+            other => {
+                let time = other.strip_prefix("daily_at(")?.strip_suffix(')')?;
+                NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                    .ok()
+                    .map(Self::DailyAt)
+            }
+        }
+    }
+
+    /// First daily run strictly after `after`, with local time taken in `tz`
+    /// None unless the schedule is `daily_at`
+    pub fn next_run_after<Tz: TimeZone>(
+        &self,
+        after: DateTime<Utc>,
+        tz: &Tz,
+    ) -> Option<DateTime<Utc>> {
+        match self {
+            Self::DailyAt(time) => Some(next_occurrence(*time, &after.with_timezone(tz))),
+            Self::Off | Self::OnQuit => None,
+        }
+    }
+
+    /// Whether a daily run has come due since `last_run`
+    /// A run missed while the machine slept is due as soon as it wakes
+    pub fn is_due<Tz: TimeZone>(
+        &self,
+        last_run: DateTime<Utc>,
+        now: DateTime<Utc>,
+        tz: &Tz,
+    ) -> bool {
+        self.next_run_after(last_run, tz)
+            .map(|next| next <= now)
+            .unwrap_or(false)
+    }
+}
+
+impl fmt::Display for AutoClearSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::DailyAt(time) => write!(f, "daily_at({})", time.format("%H:%M")),
+            Self::OnQuit => write!(f, "on_quit"),
+        }
+    }
+}
+
+/// Next occurrence of local `time` strictly after `after`, in UTC
+///
+/// A time skipped when clocks go forward runs at the first valid local time
+/// after it; a time repeated when clocks go back runs at its first occurrence
+/// only.
+pub fn next_occurrence<Tz: TimeZone>(time: NaiveTime, after: &DateTime<Tz>) -> DateTime<Utc> {
+    let tz = after.timezone();
+    let mut date = after.date_naive();
+    loop {
+        if let Some(at) = resolve_local(&tz, date.and_time(time)) {
+            if at > *after {
+                return at.with_timezone(&Utc);
+            }
+        }
+        date += Duration::days(1);
+    }
+}
+
+/// Map a local time to an instant, moving times inside a DST gap past the gap
+fn resolve_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(at) => Some(at),
+        LocalResult::Ambiguous(earliest, _) => Some(earliest),
+        LocalResult::None => (1..=MAX_GAP_MINUTES).find_map(|minutes| {
+            tz.from_local_datetime(&(local + Duration::minutes(minutes)))
+                .earliest()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use chrono_tz::America::New_York;
+    use chrono_tz::Asia::Tokyo;
+    use chrono_tz::Europe::Paris;
+
+    fn at(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn utc(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(
+            AutoClearSchedule::parse("off"),
+            Some(AutoClearSchedule::Off)
+        );
+        assert_eq!(
+            AutoClearSchedule::parse("on_quit"),
+            Some(AutoClearSchedule::OnQuit)
+        );
+        assert_eq!(
+            AutoClearSchedule::parse("daily_at(18:30)"),
+            Some(AutoClearSchedule::DailyAt(at(18, 30)))
+        );
+        assert_eq!(
+            AutoClearSchedule::parse("daily_at(7:05)")
+                .unwrap()
+                .to_string(),
+            "daily_at(07:05)"
+        );
+
+        for invalid in [
+            "",
+            "weekly",
+            "daily_at(25:00)",
+            "daily_at(9)",
+            "daily_at 09:00",
+        ] {
+            assert_eq!(AutoClearSchedule::parse(invalid), None, "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_later_today_or_tomorrow() {
+        let nine = at(9, 0);
+
+        // Paris is UTC+2 in summer
+        let before = Paris.with_ymd_and_hms(2024, 6, 1, 8, 0, 0).unwrap();
+        assert_eq!(next_occurrence(nine, &before), utc(2024, 6, 1, 7, 0));
+
+        let after = Paris.with_ymd_and_hms(2024, 6, 1, 10, 0, 0).unwrap();
+        assert_eq!(next_occurrence(nine, &after), utc(2024, 6, 2, 7, 0));
+
+        // Exactly at the scheduled time means the next day
+        let exactly = Paris.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap();
+        assert_eq!(next_occurrence(nine, &exactly), utc(2024, 6, 2, 7, 0));
+
+        // Winter offset is UTC+1
+        let winter = Paris.with_ymd_and_hms(2024, 1, 15, 8, 0, 0).unwrap();
+        assert_eq!(next_occurrence(nine, &winter), utc(2024, 1, 15, 8, 0));
+    }
+
+    #[test]
+    fn test_same_instant_in_different_zones() {
+        let midnight = at(0, 0);
+        let instant = utc(2024, 6, 1, 12, 0);
+
+        // 21:00 in Tokyo: local midnight is 15:00 UTC the same day
+        let tokyo = instant.with_timezone(&Tokyo);
+        assert_eq!(next_occurrence(midnight, &tokyo), utc(2024, 6, 1, 15, 0));
+
+        // 08:00 in New York: local midnight is 04:00 UTC the next day
+        let new_york = instant.with_timezone(&New_York);
+        assert_eq!(next_occurrence(midnight, &new_york), utc(2024, 6, 2, 4, 0));
+
+        let fixed = instant.with_timezone(&FixedOffset::east_opt(5 * 3600 + 1800).unwrap());
+        assert_eq!(next_occurrence(midnight, &fixed), utc(2024, 6, 1, 18, 30));
+    }
+
+    #[test]
+    fn test_time_skipped_by_spring_forward() {
+        // New York skips 02:00-03:00 on 2024-03-10
+        let evening = New_York.with_ymd_and_hms(2024, 3, 9, 22, 0, 0).unwrap();
+        let next = next_occurrence(at(2, 30), &evening);
+        assert_eq!(next, utc(2024, 3, 10, 7, 0));
+        assert_eq!(next.with_timezone(&New_York).time(), at(3, 0));
+
+        // The day after runs at the normal time again (now UTC-4)
+        assert_eq!(
+            next_occurrence(at(2, 30), &next.with_timezone(&New_York)),
+            utc(2024, 3, 11, 6, 30)
+        );
+    }
+
+    #[test]
+    fn test_time_repeated_by_fall_back() {
+        // New York repeats 01:00-02:00 on 2024-11-03; only the first 01:30 (EDT) runs
+        let evening = New_York.with_ymd_and_hms(2024, 11, 2, 22, 0, 0).unwrap();
+        let first = next_occurrence(at(1, 30), &evening);
+        assert_eq!(first, utc(2024, 11, 3, 5, 30));
+
+        // After the first run, the repeated 01:30 (EST) does not run again
+        let after_first = (first + Duration::minutes(1)).with_timezone(&New_York);
+        assert_eq!(
+            next_occurrence(at(1, 30), &after_first),
+            utc(2024, 11, 4, 6, 30)
+        );
+    }
+
+    #[test]
+    fn test_missed_run_is_due_on_wake() {
+        let schedule = AutoClearSchedule::DailyAt(at(9, 0));
+        let last_run = utc(2024, 6, 1, 7, 0); // 09:00 in Paris
+
+        assert!(!schedule.is_due(last_run, utc(2024, 6, 2, 6, 59), &Paris));
+        assert!(schedule.is_due(last_run, utc(2024, 6, 2, 7, 0), &Paris));
+
+        // Asleep for several days: due once on wake, then back on schedule
+        let wake = utc(2024, 6, 5, 14, 0);
+        assert!(schedule.is_due(last_run, wake, &Paris));
+        assert_eq!(
+            schedule.next_run_after(wake, &Paris),
+            Some(utc(2024, 6, 6, 7, 0))
+        );
+    }
+
+    #[test]
+    fn test_non_daily_schedules_never_due() {
+        let last_run = utc(2024, 6, 1, 7, 0);
+        let now = utc(2024, 7, 1, 7, 0);
+        for schedule in [AutoClearSchedule::Off, AutoClearSchedule::OnQuit] {
+            assert_eq!(schedule.next_run_after(last_run, &Paris), None);
+            assert!(!schedule.is_due(last_run, now, &Paris));
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Command line mode for scripts
+//!
+//! `clipster get <id> [--raw|--json]` prints one item to stdout and exits
+//! without starting the app or taking the instance lock. The database and the
+//! images of the active profile are opened read-only, so this works while the
+//! app is running.
+//!
+//! - Text and links print their text
+//! - Images print the path of the stored image; with `--raw` the PNG bytes
+//!   (`clipster get <id> --raw > shot.png`)
+//! - Files, audio and documents print one path per line
+//! - `--json` prints the item as the app sends it to the frontend
+//!
+//! `--raw` prints text without the trailing newline. Exit codes are
+//! `EXIT_USAGE` for bad arguments, `EXIT_NOT_FOUND` for an unknown item and
+//! `EXIT_IO` when the database or a file can't be read or written.
+
+use crate::error::ClipsterError;
+use crate::models::{ClipboardItem, ContentType};
+use crate::storage::{profiles, Database, FileStorage};
+use std::io::{self, Write};
+use std::path::Path;
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_USAGE: i32 = 1;
+pub const EXIT_NOT_FOUND: i32 = 2;
+pub const EXIT_IO: i32 = 3;
+
+const USAGE: &str = "Usage: clipster get <id> [--raw|--json]";
+
+/// How `get` prints an item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    Plain,
+    Raw,
+    Json,
+}
+
+/// What a command line asks for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliCommand {
+    Get { id: String, mode: OutputMode },
+}
+
+/// Whether `argv` (executable path first) runs the command line mode rather
+/// than the app
+pub fn is_cli(argv: &[String]) -> bool {
+    argv.get(1).is_some_and(|arg| arg == "get")
+}
+
+/// Parse a command line mode `argv`; the error is the message to print
+pub fn parse(argv: &[String]) -> Result<CliCommand, String> {
+    let args = argv.get(1..).unwrap_or_default();
+    let Some((command, rest)) = args.split_first() else {
+        return Err("Missing command".to_string());
+    };
+    if command != "get" {
+        return Err(format!("Unknown command: {}", command));
+    }
+
+    let mut id = None;
+    let mut mode = OutputMode::Plain;
+    for arg in rest {
+        let flag_mode = match arg.as_str() {
+            "--raw" => OutputMode::Raw,
+            "--json" => OutputMode::Json,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option: {}", flag)),
+            _ if id.is_some() => return Err(format!("Unexpected argument: {}", arg)),
+            _ => {
+                id = Some(arg.clone());
+                continue;
+            }
+        };
+        if mode != OutputMode::Plain && mode != flag_mode {
+            return Err("--raw and --json can't be combined".to_string());
+        }
+        mode = flag_mode;
+    }
+
+    let id = id.ok_or_else(|| "Missing item id".to_string())?;
+    Ok(CliCommand::Get { id, mode })
+}
+
+/// Entry point of the command line mode, on the active profile
+/// Returns the process exit code
+pub fn main(argv: &[String]) -> i32 {
+    if let Err(e) = profiles::init() {
+        eprintln!("Failed to load profiles, using default: {}", e);
+    }
+    let data_dir = match profiles::active_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            eprintln!("Failed to locate data directory: {}", e);
+            return EXIT_IO;
+        }
+    };
+    run(
+        argv,
+        &data_dir,
+        &mut io::stdout().lock(),
+        &mut io::stderr().lock(),
+    )
+}
+
+/// Run `argv` against the profile in `data_dir`; returns the exit code
+pub fn run(argv: &[String], data_dir: &Path, out: &mut dyn Write, err: &mut dyn Write) -> i32 {
+    let CliCommand::Get { id, mode } = match parse(argv) {
+        Ok(command) => command,
+        Err(message) => {
+            let _ = writeln!(err, "{}\n{}", message, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    match get(data_dir, &id, mode, out) {
+        Ok(Some(())) => EXIT_OK,
+        Ok(None) => {
+            let _ = writeln!(err, "Item not found: {}", id);
+            EXIT_NOT_FOUND
+        }
+        Err(e) => {
+            let _ = writeln!(err, "{}", e);
+            EXIT_IO
+        }
+    }
+}
+
+/// Print item `id`; None if there is no such item
+fn get(
+    data_dir: &Path,
+    id: &str,
+    mode: OutputMode,
+    out: &mut dyn Write,
+) -> Result<Option<()>, ClipsterError> {
+    let db = Database::open_read_only(data_dir)?;
+    let Some(item) = db.get_item(id)? else {
+        return Ok(None);
+    };
+
+    let storage = FileStorage::read_only(data_dir.join("images"));
+    out.write_all(&item_output(&item, mode, &storage)?)?;
+    out.flush()?;
+    Ok(Some(()))
+}
+
+/// Bytes `get` prints for `item`
+fn item_output(
+    item: &ClipboardItem,
+    mode: OutputMode,
+    storage: &FileStorage,
+) -> Result<Vec<u8>, ClipsterError> {
+    if mode == OutputMode::Json {
+        let mut json = serde_json::to_vec(item)?;
+        json.push(b'\n');
+        return Ok(json);
+    }
+
+    let text = match item.content_type {
+        ContentType::Text | ContentType::Link => item.content_text.clone().unwrap_or_default(),
+        ContentType::Image => {
+            let image_path = item
+                .image_path
+                .as_deref()
+                .ok_or_else(|| ClipsterError::Io("No image path for item".to_string()))?;
+            if mode == OutputMode::Raw {
+                return storage.read_png(Path::new(image_path));
+            }
+            image_path.to_string()
+        }
+        ContentType::Files | ContentType::Audio | ContentType::Documents => {
+            item.get_file_paths().unwrap_or_default().join("\n")
+        }
+    };
+
+    let mut bytes = text.into_bytes();
+    if mode == OutputMode::Plain {
+        bytes.push(b'\n');
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, TempDir};
+    use image::{Rgb, RgbImage};
+    use std::fs;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        std::iter::once("clipster")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    fn temp_data_dir() -> TempDir {
+        let dir = temp_dir("cli");
+        fs::create_dir_all(dir.join("images")).unwrap();
+        dir
+    }
+
+    /// Exit code, stdout and stderr of a run
+    fn run_cli(data_dir: &Path, args: &[&str]) -> (i32, Vec<u8>, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = run(&argv(args), data_dir, &mut out, &mut err);
+        (code, out, String::from_utf8(err).unwrap())
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert!(is_cli(&argv(&["get", "abc"])));
+        assert!(!is_cli(&argv(&["--show"])));
+        assert!(!is_cli(&argv(&[])));
+
+        let get = |id: &str, mode| CliCommand::Get {
+            id: id.to_string(),
+            mode,
+        };
+        assert_eq!(
+            parse(&argv(&["get", "abc"])),
+            Ok(get("abc", OutputMode::Plain))
+        );
+        assert_eq!(
+            parse(&argv(&["get", "--raw", "abc"])),
+            Ok(get("abc", OutputMode::Raw))
+        );
+        assert_eq!(
+            parse(&argv(&["get", "abc", "--json"])),
+            Ok(get("abc", OutputMode::Json))
+        );
+
+        assert!(parse(&argv(&["get"])).is_err());
+        assert!(parse(&argv(&["get", "a", "b"])).is_err());
+        assert!(parse(&argv(&["get", "a", "--raw", "--json"])).is_err());
+        assert!(parse(&argv(&["get", "a", "--verbose"])).is_err());
+    }
+
+    #[test]
+    fn test_get_text_and_files() {
+        let dir = temp_data_dir();
+        let db = Database::open(&dir).unwrap();
+        let text = ClipboardItem::new_text("hello\nworld".to_string(), None, None);
+        db.insert_item(&text).unwrap();
+        let paths = vec!["/p/a.txt".to_string(), "/p/b.txt".to_string()];
+        let files = ClipboardItem::new_files(paths, None, None);
+        db.insert_item(&files).unwrap();
+        drop(db);
+
+        let (code, out, _) = run_cli(&dir, &["get", &text.id]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, b"hello\nworld\n");
+        let (_, out, _) = run_cli(&dir, &["get", &text.id, "--raw"]);
+        assert_eq!(out, b"hello\nworld");
+
+        let (code, out, _) = run_cli(&dir, &["get", &files.id]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, b"/p/a.txt\n/p/b.txt\n");
+
+        let (code, out, _) = run_cli(&dir, &["get", &text.id, "--json"]);
+        assert_eq!(code, EXIT_OK);
+        let json: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["id"], text.id.as_str());
+        assert_eq!(json["content_text"], "hello\nworld");
+    }
+
+    #[test]
+    fn test_get_image_raw_streams_png() {
+        let dir = temp_data_dir();
+        let db = Database::open(&dir).unwrap();
+        let path = dir.join("images").join("shot.png");
+        RgbImage::from_pixel(4, 3, Rgb([1, 2, 3]))
+            .save(&path)
+            .unwrap();
+        let image_path = path.to_string_lossy().to_string();
+        let item = ClipboardItem::new_image(None, image_path.clone(), None, None);
+        db.insert_item(&item).unwrap();
+        drop(db);
+
+        let (code, out, _) = run_cli(&dir, &["get", &item.id, "--raw"]);
+        assert_eq!(code, EXIT_OK);
+        assert_eq!(out, fs::read(&path).unwrap());
+
+        let (_, out, _) = run_cli(&dir, &["get", &item.id]);
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", image_path));
+
+        // A stored image that is gone is an IO error, not a missing item
+        fs::remove_file(&path).unwrap();
+        let (code, out, _) = run_cli(&dir, &["get", &item.id, "--raw"]);
+        assert_eq!(code, EXIT_IO);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let dir = temp_data_dir();
+        drop(Database::open(&dir).unwrap());
+
+        let (code, out, err) = run_cli(&dir, &["get", "missing"]);
+        assert_eq!(code, EXIT_NOT_FOUND);
+        assert!(out.is_empty());
+        assert!(err.contains("missing"), "{}", err);
+
+        let (code, _, err) = run_cli(&dir, &["get"]);
+        assert_eq!(code, EXIT_USAGE);
+        assert!(err.contains(USAGE), "{}", err);
+
+        // No database in the directory: nothing is created
+        let empty = temp_data_dir();
+        let (code, _, _) = run_cli(&empty, &["get", "missing"]);
+        assert_eq!(code, EXIT_IO);
+        assert!(!empty.join("clipster.db").exists());
+    }
+}
@@ -0,0 +1,187 @@
+//! Background extraction of source app icons
+//!
+//! Extracting an executable's icon (shell lookup, GDI drawing, PNG encoding)
+//! used to run inside the clipboard callback on every capture, even when the
+//! same app was copied from over and over. The monitor now only looks the icon
+//! up in the `app_icons` cache; on a miss it saves the item without one and
+//! queues the executable path here. The worker extracts each path once and
+//! hands the icon back to be cached and filled in on the waiting items.
+
+use std::collections::HashMap;
+use std::sync::mpsc::Receiver;
+
+/// An item saved without its source app icon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconRequest {
+    /// Executable of the app the item was copied from
+    pub app_path: String,
+    pub item_id: String,
+}
+
+/// Receives the icons extracted by the worker
+pub trait IconSink {
+    /// `icon` (base64 PNG) was extracted for `app_path`: cache it and fill it
+    /// in on `item_ids`
+    fn icon_ready(&self, app_path: &str, icon: &str, item_ids: &[String]);
+}
+
+/// Extracts each app's icon once per session
+#[derive(Debug)]
+pub struct IconWorker<E> {
+    extract: E,
+    /// Extraction result by app path; failures are not retried
+    extracted: HashMap<String, Option<String>>,
+}
+
+impl<E: Fn(&str) -> Option<String>> IconWorker<E> {
+    pub fn new(extract: E) -> Self {
+        Self {
+            extract,
+            extracted: HashMap::new(),
+        }
+    }
+
+    /// Handle queued requests, grouped by app so each icon is extracted once
+    pub fn handle<S: IconSink>(&mut self, requests: Vec<IconRequest>, sink: &S) {
+        let mut by_app: Vec<(String, Vec<String>)> = Vec::new();
+        for request in requests {
+            match by_app
+                .iter_mut()
+                .find(|(path, _)| *path == request.app_path)
+            {
+                Some((_, item_ids)) => item_ids.push(request.item_id),
+                None => by_app.push((request.app_path, vec![request.item_id])),
+            }
+        }
+
+        for (app_path, item_ids) in by_app {
+            let extract = &self.extract;
+            let icon = self.extracted.entry(app_path.clone()).or_insert_with(|| {
+                let icon = extract(&app_path);
+                if icon.is_none() {
+                    eprintln!("[app_icon_worker] No icon for {}", app_path);
+                }
+                icon
+            });
+            if let Some(icon) = icon {
+                sink.icon_ready(&app_path, icon, &item_ids);
+            }
+        }
+    }
+}
+
+/// Serve icon requests sent by the monitor until the sender is dropped
+pub fn run_worker<E, S>(extract: E, sink: &S, requests: Receiver<IconRequest>)
+where
+    E: Fn(&str) -> Option<String>,
+    S: IconSink,
+{
+    let mut worker = IconWorker::new(extract);
+    while let Ok(first) = requests.recv() {
+        // Captures that queued up during the last extraction are handled together
+        let batch: Vec<IconRequest> = std::iter::once(first).chain(requests.try_iter()).collect();
+        worker.handle(batch, sink);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::{Cell, RefCell};
+    use std::sync::mpsc;
+
+    #[derive(Default)]
+    struct CapturingSink {
+        ready: RefCell<Vec<(String, String, Vec<String>)>>,
+    }
+
+    impl IconSink for CapturingSink {
+        fn icon_ready(&self, app_path: &str, icon: &str, item_ids: &[String]) {
+            self.ready.borrow_mut().push((
+                app_path.to_string(),
+                icon.to_string(),
+                item_ids.to_vec(),
+            ));
+        }
+    }
+
+    fn request(app_path: &str, item_id: &str) -> IconRequest {
+        IconRequest {
+            app_path: app_path.to_string(),
+            item_id: item_id.to_string(),
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_each_app_is_extracted_once() {
+        let calls = Cell::new(0);
+        let mut worker = IconWorker::new(|path: &str| {
+            calls.set(calls.get() + 1);
+            Some(format!("icon of {}", path))
+        });
+        let sink = CapturingSink::default();
+
+        worker.handle(
+            vec![
+                request("editor.exe", "1"),
+                request("browser.exe", "2"),
+                request("editor.exe", "3"),
+            ],
+            &sink,
+        );
+        assert_eq!(calls.get(), 2);
+        assert_eq!(
+            *sink.ready.borrow(),
+            vec![
+                (
+                    "editor.exe".to_string(),
+                    "icon of editor.exe".to_string(),
+                    ids(&["1", "3"])
+                ),
+                (
+                    "browser.exe".to_string(),
+                    "icon of browser.exe".to_string(),
+                    ids(&["2"])
+                ),
+            ]
+        );
+
+        // Items queued before the cache was written reuse the extracted icon
+        worker.handle(vec![request("editor.exe", "4")], &sink);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(sink.ready.borrow()[2].2, ids(&["4"]));
+    }
+
+    #[test]
+    fn test_failed_extraction_is_not_retried() {
+        let calls = Cell::new(0);
+        let mut worker = IconWorker::new(|_: &str| {
+            calls.set(calls.get() + 1);
+            None
+        });
+        let sink = CapturingSink::default();
+
+        worker.handle(vec![request("service.exe", "1")], &sink);
+        worker.handle(vec![request("service.exe", "2")], &sink);
+        assert_eq!(calls.get(), 1);
+        assert!(sink.ready.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_worker_stops_when_monitor_drops_sender() {
+        let (requests, receiver) = mpsc::channel();
+        requests.send(request("editor.exe", "1")).unwrap();
+        requests.send(request("editor.exe", "2")).unwrap();
+        drop(requests);
+
+        let sink = CapturingSink::default();
+        run_worker(|_: &str| Some("icon".to_string()), &sink, receiver);
+        let ready = sink.ready.borrow();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].2, ids(&["1", "2"]));
+    }
+}
@@ -0,0 +1,41 @@
+//! Source app identifiers in per-app settings
+//!
+//! Per-app settings (`app_rate_limits`, `app_default_pinboards`) are keyed by
+//! what the OS reports at capture time: the bundle identifier on macOS
+//! ("com.apple.Terminal") and the executable path on Windows. Display names
+//! are localized and can be shared by unrelated apps, and resolving them
+//! costs a lookup per capture.
+
+use std::collections::HashMap;
+
+/// Find the entry configured for the app identified by `app_id`
+/// Entries match case-insensitively; an executable path is also matched by
+/// its file name, so "WindowsTerminal.exe" covers every install location
+pub fn find_entry<'a, V>(entries: &'a HashMap<String, V>, app_id: &str) -> Option<&'a V> {
+    // Backslashes aren't separators outside Windows; the tests run everywhere
+    let file_name = app_id.rsplit(['\\', '/']).next().unwrap_or(app_id);
+    entries
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(app_id) || key.eq_ignore_ascii_case(file_name))
+        .map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_entry() {
+        let entries = HashMap::from([
+            ("com.figma.Desktop".to_string(), "design"),
+            ("Code.exe".to_string(), "snippets"),
+        ]);
+        assert_eq!(find_entry(&entries, "com.figma.desktop"), Some(&"design"));
+        assert_eq!(
+            find_entry(&entries, r"C:\Apps\VS Code\code.exe"),
+            Some(&"snippets")
+        );
+        // Display names are not identifiers
+        assert_eq!(find_entry(&entries, "Figma"), None);
+    }
+}
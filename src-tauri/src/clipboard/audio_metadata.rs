@@ -0,0 +1,215 @@
+//! Background reading of audio file metadata
+//!
+//! A copied audio file used to show only its file name. Reading its duration
+//! and tags means opening and parsing the file, which is too slow for the
+//! clipboard callback on a network share, so the monitor saves the item first
+//! and queues the path here. The worker reads it with lofty and hands the
+//! metadata back to be stored in `media_metadata` and sent with
+//! `item-updated`. Missing or corrupt tags give partial metadata or none;
+//! the capture itself never depends on it.
+
+use crate::models::{ClipboardItem, ContentType, MediaMetadata};
+use lofty::file::{AudioFile, TaggedFileExt};
+use lofty::tag::Accessor;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+
+/// An audio item saved without its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetadataRequest {
+    pub item_id: String,
+    pub path: PathBuf,
+}
+
+/// Receives the metadata read by the worker
+pub trait MetadataSink {
+    /// `metadata` was read for the file of `item_id`: store it and tell the frontend
+    fn metadata_ready(&self, item_id: &str, metadata: &MediaMetadata);
+}
+
+/// File to read the metadata of: that of a single-file audio item
+/// A copy of several tracks keeps showing the file count
+pub fn metadata_path(item: &ClipboardItem) -> Option<PathBuf> {
+    if item.content_type != ContentType::Audio || item.media_metadata.is_some() {
+        return None;
+    }
+    match item.get_file_paths()?.as_slice() {
+        [path] => Some(PathBuf::from(path)),
+        _ => None,
+    }
+}
+
+/// Duration and tags of an audio file; None when the file can't be parsed
+/// or has neither
+pub fn read(path: &Path) -> Option<MediaMetadata> {
+    let tagged_file = match lofty::read_from_path(path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("[audio_metadata] Can't read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let duration = tagged_file.properties().duration();
+    let mut metadata = MediaMetadata {
+        duration_ms: (!duration.is_zero()).then_some(duration.as_millis() as u64),
+        ..Default::default()
+    };
+    if let Some(tag) = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag())
+    {
+        metadata.title = tag_value(tag.title());
+        metadata.artist = tag_value(tag.artist());
+        metadata.album = tag_value(tag.album());
+    }
+
+    (!metadata.is_empty()).then_some(metadata)
+}
+
+/// A tag value with surrounding whitespace and NULs removed; None when blank
+fn tag_value(value: Option<Cow<'_, str>>) -> Option<String> {
+    let value = value?;
+    let trimmed = value.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// Serve metadata requests sent by the monitor until the sender is dropped
+pub fn run_worker<R, S>(read: R, sink: &S, requests: Receiver<MetadataRequest>)
+where
+    R: Fn(&Path) -> Option<MediaMetadata>,
+    S: MetadataSink,
+{
+    for request in requests {
+        if let Some(metadata) = read(&request.path) {
+            sink.metadata_ready(&request.item_id, &metadata);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::cell::RefCell;
+    use std::sync::mpsc;
+
+    /// One second of silence (28 MPEG-1 Layer III frames at 32 kHz) with an
+    /// ID3v2.3 tag: title "Tiny Tone", artist "Clipster", album "Fixtures"
+    const TINY_MP3: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny.mp3");
+
+    /// Size of the ID3v2 tag at the start of the fixture
+    const TINY_MP3_TAG_LEN: usize = 68;
+
+    fn temp_file(dir: &Path, name: &str, bytes: &[u8]) -> PathBuf {
+        std::fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[derive(Default)]
+    struct CapturingSink {
+        ready: RefCell<Vec<(String, MediaMetadata)>>,
+    }
+
+    impl MetadataSink for CapturingSink {
+        fn metadata_ready(&self, item_id: &str, metadata: &MediaMetadata) {
+            self.ready
+                .borrow_mut()
+                .push((item_id.to_string(), metadata.clone()));
+        }
+    }
+
+    #[test]
+    fn test_read_tagged_mp3() {
+        let metadata = read(Path::new(TINY_MP3)).unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Tiny Tone"));
+        assert_eq!(metadata.artist.as_deref(), Some("Clipster"));
+        assert_eq!(metadata.album.as_deref(), Some("Fixtures"));
+        // 28 frames of 1152 samples at 32 kHz
+        let duration_ms = metadata.duration_ms.unwrap();
+        assert!((950..=1100).contains(&duration_ms), "{} ms", duration_ms);
+    }
+
+    #[test]
+    fn test_read_untagged_mp3_keeps_duration() {
+        let bytes = std::fs::read(TINY_MP3).unwrap();
+        assert_eq!(&bytes[..3], b"ID3");
+        let dir = temp_dir("audio");
+        let path = temp_file(&dir, "untagged.mp3", &bytes[TINY_MP3_TAG_LEN..]);
+
+        let metadata = read(&path).unwrap();
+        assert_eq!(metadata.title, None);
+        assert_eq!(metadata.artist, None);
+        assert!(metadata.duration_ms.is_some());
+    }
+
+    #[test]
+    fn test_unreadable_files_have_no_metadata() {
+        let dir = temp_dir("audio");
+        let corrupt = temp_file(
+            &dir,
+            "corrupt.mp3",
+            b"ID3\x03\x00\x00\x7f\x7f\x7f\x7fnot audio",
+        );
+        assert_eq!(read(&corrupt), None);
+        let text = temp_file(&dir, "notes.mp3", b"just some text named like a song");
+        assert_eq!(read(&text), None);
+        assert_eq!(read(Path::new("/nonexistent/song.mp3")), None);
+    }
+
+    #[test]
+    fn test_metadata_path() {
+        let song = ClipboardItem::new_audio(vec!["/m/song.mp3".to_string()], None, None);
+        assert_eq!(metadata_path(&song), Some(PathBuf::from("/m/song.mp3")));
+
+        let album = ClipboardItem::new_audio(
+            vec!["/m/a.mp3".to_string(), "/m/b.mp3".to_string()],
+            None,
+            None,
+        );
+        assert_eq!(metadata_path(&album), None);
+        let files = ClipboardItem::new_files(vec!["/m/song.mp3.zip".to_string()], None, None);
+        assert_eq!(metadata_path(&files), None);
+
+        let mut read_already = song.clone();
+        read_already.media_metadata = Some(MediaMetadata {
+            duration_ms: Some(1000),
+            ..Default::default()
+        });
+        assert_eq!(metadata_path(&read_already), None);
+    }
+
+    #[test]
+    fn test_tag_value() {
+        assert_eq!(
+            tag_value(Some(Cow::Borrowed("  Song \0"))),
+            Some("Song".to_string())
+        );
+        assert_eq!(tag_value(Some(Cow::Borrowed(" \0 "))), None);
+        assert_eq!(tag_value(None), None);
+    }
+
+    #[test]
+    fn test_worker_skips_files_without_metadata() {
+        let (sender, receiver) = mpsc::channel();
+        for (item_id, path) in [("1", TINY_MP3), ("2", "/nonexistent/song.mp3")] {
+            sender
+                .send(MetadataRequest {
+                    item_id: item_id.to_string(),
+                    path: PathBuf::from(path),
+                })
+                .unwrap();
+        }
+        drop(sender);
+
+        let sink = CapturingSink::default();
+        run_worker(read, &sink, receiver);
+        let ready = sink.ready.into_inner();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0, "1");
+        assert_eq!(ready[0].1.title.as_deref(), Some("Tiny Tone"));
+    }
+}
@@ -0,0 +1,95 @@
+//! Pasteboard bitmap formats that need converting before PNG encoding
+//!
+//! Some apps put 16-bit, floating point or CMYK TIFFs on the pasteboard.
+//! Handing those straight to NSBitmapImageRep's PNG encoder produced images
+//! that render black, so anything other than 8-bit RGB or gray is redrawn into
+//! an 8-bit sRGB bitmap first. TIFFs that can't be converted at all are kept
+//! as-is.
+
+/// Color spaces whose 8-bit bitmaps convert to PNG as they are
+const DIRECT_COLOR_SPACES: &[&str] = &[
+    "NSCalibratedRGBColorSpace",
+    "NSDeviceRGBColorSpace",
+    "NSCalibratedWhiteColorSpace",
+    "NSDeviceWhiteColorSpace",
+];
+
+/// How a pasteboard bitmap is turned into PNG
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitmapConversion {
+    /// 8-bit RGB or gray: encode directly
+    Direct,
+    /// Any other depth or color space: redraw into 8-bit sRGB first
+    RedrawSrgb,
+}
+
+/// Pick the conversion for a bitmap's `bitsPerSample` and `colorSpaceName`
+pub fn conversion_for(bits_per_sample: isize, color_space_name: &str) -> BitmapConversion {
+    if bits_per_sample == 8 && DIRECT_COLOR_SPACES.contains(&color_space_name) {
+        BitmapConversion::Direct
+    } else {
+        BitmapConversion::RedrawSrgb
+    }
+}
+
+/// Check for a TIFF header (little- or big-endian)
+pub fn is_tiff(data: &[u8]) -> bool {
+    data.starts_with(b"II*\0") || data.starts_with(b"MM\0*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{DynamicImage, ImageBuffer, ImageOutputFormat, Rgb};
+    use BitmapConversion::{Direct, RedrawSrgb};
+
+    /// bitsPerSample and colorSpaceName reported for pasteboard TIFFs
+    #[rustfmt::skip]
+    const FIXTURES: &[(&str, isize, &str, BitmapConversion)] = &[
+        ("Preview copy", 8, "NSCalibratedRGBColorSpace", Direct),
+        ("Screenshot", 8, "NSDeviceRGBColorSpace", Direct),
+        ("Grayscale scan", 8, "NSCalibratedWhiteColorSpace", Direct),
+        ("16-bit Photoshop layer", 16, "NSCalibratedRGBColorSpace", RedrawSrgb),
+        ("16-bit gray", 16, "NSDeviceWhiteColorSpace", RedrawSrgb),
+        ("Print layout (CMYK)", 8, "NSDeviceCMYKColorSpace", RedrawSrgb),
+        ("Float HDR render", 32, "NSCalibratedRGBColorSpace", RedrawSrgb),
+        ("Embedded ICC profile", 8, "NSCustomColorSpace", RedrawSrgb),
+        ("Indexed", 4, "NSDeviceRGBColorSpace", RedrawSrgb),
+    ];
+
+    /// 2x2 16-bit RGB TIFF, the depth that used to render black
+    fn rgb16_tiff(value: u16) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb16(ImageBuffer::from_pixel(2, 2, Rgb([value; 3])));
+        let mut tiff = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut tiff, ImageOutputFormat::Tiff).unwrap();
+        tiff.into_inner()
+    }
+
+    #[test]
+    fn test_conversion_for_fixtures() {
+        for (name, bits, color_space, expected) in FIXTURES {
+            assert_eq!(conversion_for(*bits, color_space), *expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_is_tiff() {
+        assert!(is_tiff(&rgb16_tiff(0x8000)));
+        assert!(is_tiff(b"MM\0*\0\0\0\x08"));
+
+        let mut png = std::io::Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(2, 2)
+            .write_to(&mut png, ImageOutputFormat::Png)
+            .unwrap();
+        assert!(!is_tiff(png.get_ref()));
+        assert!(!is_tiff(b"II"));
+    }
+
+    #[test]
+    fn test_raw_tiff_fallback_still_decodes() {
+        // Stored .tiff files are read back through the image crate (thumbnails, export)
+        let decoded = image::load_from_memory(&rgb16_tiff(u16::MAX)).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (2, 2));
+        assert_eq!(decoded.to_rgb8().get_pixel(1, 1).0, [255, 255, 255]);
+    }
+}
@@ -0,0 +1,139 @@
+//! URL canonicalization for Link deduplication
+//!
+//! The same article copied from a social feed and from the site itself often
+//! differs only by tracking parameters. The canonical form is used as the
+//! dedupe key (`canonical_url` column); the displayed URL is left untouched.
+
+use url::Url;
+
+/// Query parameters dropped by default (`*` suffix matches a prefix)
+pub const DEFAULT_TRACKING_PARAMS: &[&str] = &[
+    "utm_*", "fbclid", "gclid", "dclid", "msclkid", "mc_cid", "mc_eid", "igshid", "ref_src",
+    "_hsenc", "_hsmi",
+];
+
+/// Whether a query parameter name matches one of the tracking patterns
+fn is_tracking_param(name: &str, tracking_params: &[String]) -> bool {
+    let name = name.to_ascii_lowercase();
+    tracking_params.iter().any(|pattern| {
+        let pattern = pattern.to_ascii_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }
+    })
+}
+
+/// Canonical form of a URL used as its dedupe key
+///
+/// Lowercases scheme and host (IDN hosts become punycode), strips default
+/// ports, drops tracking parameters and empty fragments, and trims a trailing
+/// slash from the path. Scheme-less `www.` URLs are treated as http.
+/// Unparseable input is returned trimmed.
+pub fn canonicalize_url(input: &str, tracking_params: &[String]) -> String {
+    let trimmed = input.trim();
+    let parsed = if trimmed.to_ascii_lowercase().starts_with("www.") {
+        Url::parse(&format!("http://{}", trimmed))
+    } else {
+        Url::parse(trimmed)
+    };
+    let Ok(mut url) = parsed else {
+        return trimmed.to_string();
+    };
+
+    // Parsing already lowercases the host, encodes IDN and drops default ports
+    if url.query().is_some() {
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking_param(name, tracking_params))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        if kept.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(kept);
+        }
+    }
+
+    if url.fragment() == Some("") {
+        url.set_fragment(None);
+    }
+
+    let path = url.path();
+    if path.len() > 1 && path.ends_with('/') {
+        let trimmed_path = path.trim_end_matches('/').to_string();
+        url.set_path(&trimmed_path);
+    }
+
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Vec<String> {
+        DEFAULT_TRACKING_PARAMS.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_canonicalize_table() {
+        let cases = [
+            // Tracking parameters
+            (
+                "https://example.com/article?utm_source=twitter&utm_medium=social",
+                "https://example.com/article",
+            ),
+            (
+                "https://example.com/article?id=42&utm_campaign=x&fbclid=abc",
+                "https://example.com/article?id=42",
+            ),
+            ("https://example.com/a?UTM_Source=x&b=2", "https://example.com/a?b=2"),
+            // Host case and default ports
+            ("HTTPS://Example.COM/Path", "https://example.com/Path"),
+            ("https://example.com:443/a", "https://example.com/a"),
+            ("http://example.com:80/a", "http://example.com/a"),
+            ("http://example.com:8080/a", "http://example.com:8080/a"),
+            // Trailing slash
+            ("https://example.com/article/", "https://example.com/article"),
+            ("https://example.com/", "https://example.com/"),
+            ("https://example.com", "https://example.com/"),
+            // Fragments: empty ones dropped, meaningful ones kept
+            ("https://example.com/a#", "https://example.com/a"),
+            ("https://example.com/a#section-2", "https://example.com/a#section-2"),
+            ("https://example.com/a/?utm_source=x#top", "https://example.com/a#top"),
+            // IDN hosts become punycode
+            ("https://Bücher.example/katalog", "https://xn--bcher-kva.example/katalog"),
+            ("https://xn--bcher-kva.example/katalog", "https://xn--bcher-kva.example/katalog"),
+            // Scheme-less www URLs
+            ("www.Example.com/a/", "http://www.example.com/a"),
+            // Whitespace and unparseable input
+            ("  https://example.com/a  ", "https://example.com/a"),
+            ("not a url", "not a url"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(canonicalize_url(input, &defaults()), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_twitter_and_site_copies_collapse() {
+        let from_twitter = "https://blog.example.com/post/?utm_source=twitter&ref_src=twsrc";
+        let from_site = "https://blog.example.com/post";
+        assert_eq!(
+            canonicalize_url(from_twitter, &defaults()),
+            canonicalize_url(from_site, &defaults())
+        );
+    }
+
+    #[test]
+    fn test_custom_tracking_params() {
+        let params = vec!["ref".to_string(), "src_*".to_string()];
+        assert_eq!(
+            canonicalize_url("https://example.com/?ref=hn&src_a=1&utm_source=x", &params),
+            "https://example.com/?utm_source=x"
+        );
+        assert_eq!(canonicalize_url("https://example.com/?ref=hn", &[]), "https://example.com/?ref=hn");
+    }
+}
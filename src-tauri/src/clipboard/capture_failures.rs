@@ -0,0 +1,170 @@
+//! Log of clipboard captures that could not be saved
+//!
+//! A copy can be lost when the clipboard is locked by another app, its image
+//! fails to decode or be written, or the database insert errors. Each failure
+//! is kept in a small ring buffer (time, stage, error code and content type,
+//! never the content itself) returned by `get_capture_failures`, counted per
+//! stage in `get_monitoring_status`, and announced with a `capture-failure`
+//! event so the UI can show that a copy was missed.
+
+use crate::models::ContentType;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+
+/// Failures kept in the log; older ones are dropped
+pub const MAX_CAPTURE_FAILURES: usize = 50;
+
+/// Step of the capture pipeline that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStage {
+    /// Reading the clipboard
+    Read,
+    /// Decoding a copied image
+    Decode,
+    /// Writing an image or large text to disk
+    Store,
+    /// Inserting the item into the database
+    Insert,
+    /// Queueing the item's event for the frontend
+    Notify,
+}
+
+/// What went wrong, as a stable code the frontend can map to a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCode {
+    /// Another app held the clipboard open
+    ClipboardLocked,
+    /// The clipboard announced content that could not be read
+    Unreadable,
+    /// The source app was still writing the clipboard after every retry
+    StillWriting,
+    /// The image was over the `max_image_capture_mb` setting and was not read
+    TooLarge,
+    /// The image could not be decoded; its raw bytes were saved instead
+    DecodeFailed,
+    /// The disk is full
+    DiskFull,
+    /// Writing the file failed for another reason
+    WriteFailed,
+    /// The database stayed locked; the item was spooled for the next startup
+    DatabaseBusy,
+    /// The database rejected the insert
+    DatabaseError,
+    /// The item was saved but the frontend was not told
+    EventQueueClosed,
+}
+
+/// One failed capture
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct CaptureFailure {
+    pub timestamp: DateTime<Utc>,
+    pub stage: CaptureStage,
+    pub code: FailureCode,
+    /// None when the clipboard could not be read far enough to tell
+    pub content_type: Option<ContentType>,
+}
+
+/// Ring buffer of recent failures with running counts per stage
+#[derive(Debug, Default)]
+pub struct CaptureFailureLog {
+    recent: VecDeque<CaptureFailure>,
+    /// Failures since launch, including those dropped from `recent`
+    counts: BTreeMap<CaptureStage, u64>,
+}
+
+impl CaptureFailureLog {
+    pub const fn new() -> Self {
+        Self {
+            recent: VecDeque::new(),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Add a failure, dropping the oldest once the log is full
+    pub fn record(&mut self, failure: CaptureFailure) {
+        *self.counts.entry(failure.stage).or_default() += 1;
+        if self.recent.len() == MAX_CAPTURE_FAILURES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(failure);
+    }
+
+    /// Logged failures, newest first
+    pub fn recent(&self) -> Vec<CaptureFailure> {
+        self.recent.iter().rev().cloned().collect()
+    }
+
+    /// Failures since launch per stage
+    pub fn counts(&self) -> &BTreeMap<CaptureStage, u64> {
+        &self.counts
+    }
+
+    /// Failures since launch
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(stage: CaptureStage, code: FailureCode, second: i64) -> CaptureFailure {
+        CaptureFailure {
+            timestamp: DateTime::from_timestamp(second, 0).unwrap(),
+            stage,
+            code,
+            content_type: Some(ContentType::Image),
+        }
+    }
+
+    #[test]
+    fn test_log_keeps_last_failures_newest_first() {
+        let mut log = CaptureFailureLog::new();
+        for second in 0..MAX_CAPTURE_FAILURES as i64 + 5 {
+            log.record(failure(
+                CaptureStage::Insert,
+                FailureCode::DatabaseError,
+                second,
+            ));
+        }
+
+        let recent = log.recent();
+        assert_eq!(recent.len(), MAX_CAPTURE_FAILURES);
+        assert_eq!(
+            recent[0].timestamp.timestamp(),
+            MAX_CAPTURE_FAILURES as i64 + 4
+        );
+        assert_eq!(recent.last().unwrap().timestamp.timestamp(), 5);
+        // Counts include the failures dropped from the buffer
+        assert_eq!(log.total(), MAX_CAPTURE_FAILURES as u64 + 5);
+    }
+
+    #[test]
+    fn test_counts_per_stage() {
+        let mut log = CaptureFailureLog::new();
+        log.record(failure(CaptureStage::Read, FailureCode::ClipboardLocked, 1));
+        log.record(failure(CaptureStage::Store, FailureCode::DiskFull, 2));
+        log.record(failure(CaptureStage::Read, FailureCode::Unreadable, 3));
+
+        assert_eq!(log.counts().get(&CaptureStage::Read), Some(&2));
+        assert_eq!(log.counts().get(&CaptureStage::Store), Some(&1));
+        assert_eq!(log.counts().get(&CaptureStage::Insert), None);
+        assert_eq!(log.total(), 3);
+    }
+
+    #[test]
+    fn test_failure_serializes_without_content() {
+        let json =
+            serde_json::to_value(failure(CaptureStage::Decode, FailureCode::DecodeFailed, 0))
+                .unwrap();
+        assert_eq!(json["stage"], "decode");
+        assert_eq!(json["code"], "decode_failed");
+        assert_eq!(json["content_type"], "image");
+        assert_eq!(json.as_object().unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,200 @@
+//! Merging of rapid text captures
+//!
+//! Terminals copy a multi-line or block selection as several clipboard writes
+//! in quick succession, which would otherwise land as one item per piece.
+//! With the `merge_rapid_text_captures` setting on, plain text copied from the
+//! same app within `merge_window_ms` of the previous text capture is appended
+//! to that item on a new line, and the item is announced with `item-updated`
+//! instead of `clipboard-changed`. Each merge restarts the window, so a burst
+//! of any length ends up in one item.
+//!
+//! Text from an unknown app, links, contacts and events, find pasteboard text
+//! and a repeat of the previous piece (the same selection copied twice) are
+//! never merged, and any other capture in between ends the run.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Default value of the `merge_window_ms` setting
+pub const DEFAULT_MERGE_WINDOW_MS: u64 = 500;
+
+/// `fragment` appended to `existing` on a new line
+/// A line break already ending `existing` is not doubled
+pub fn join(existing: &str, fragment: &str) -> String {
+    format!("{}\n{}", existing.trim_end_matches(['\r', '\n']), fragment)
+}
+
+#[derive(Debug)]
+struct LastCapture {
+    item_id: String,
+    source_app: String,
+    fragment_hash: u64,
+    at: Instant,
+}
+
+/// The text capture the next one may be merged into
+///
+/// Callers pass the current time explicitly so the window can be tested with
+/// a mock clock.
+#[derive(Debug, Default)]
+pub struct RapidMerge {
+    last: Option<LastCapture>,
+}
+
+impl RapidMerge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember `fragment`, copied from `source_app` at `now`, as the latest
+    /// text of item `item_id`; text from an unknown app can't be merged into
+    pub fn record(
+        &mut self,
+        item_id: &str,
+        source_app: Option<&str>,
+        fragment: &str,
+        now: Instant,
+    ) {
+        self.last = source_app.map(|source_app| LastCapture {
+            item_id: item_id.to_string(),
+            source_app: source_app.to_string(),
+            fragment_hash: hash(fragment),
+            at: now,
+        });
+    }
+
+    /// Forget the latest capture, so the next one starts a new item
+    pub fn clear(&mut self) {
+        self.last = None;
+    }
+
+    /// The item `fragment` copied from `source_app` at `now` is appended to,
+    /// if the latest capture came from the same app less than `window` ago
+    /// The latest capture is forgotten either way: `record` the outcome
+    pub fn take_target(
+        &mut self,
+        source_app: Option<&str>,
+        fragment: &str,
+        now: Instant,
+        window: Duration,
+    ) -> Option<String> {
+        let last = self.last.take()?;
+        let same_app = source_app == Some(last.source_app.as_str());
+        let in_window = now.saturating_duration_since(last.at) < window;
+        let repeated = hash(fragment) == last.fragment_hash;
+        (same_app && in_window && !repeated).then_some(last.item_id)
+    }
+}
+
+fn hash(fragment: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    fragment.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_millis(DEFAULT_MERGE_WINDOW_MS);
+
+    #[test]
+    fn test_join_adds_one_line_break() {
+        assert_eq!(join("first line", "second line"), "first line\nsecond line");
+        assert_eq!(
+            join("first line\n", "second line\n"),
+            "first line\nsecond line\n"
+        );
+        assert_eq!(
+            join("first line\r\n", "second line"),
+            "first line\nsecond line"
+        );
+        assert_eq!(join("  indented", "  block"), "  indented\n  block");
+    }
+
+    #[test]
+    fn test_captures_within_the_window_merge() {
+        let now = Instant::now();
+        let mut merge = RapidMerge::new();
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 1", now, WINDOW),
+            None
+        );
+
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        let at = now + Duration::from_millis(200);
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 2", at, WINDOW),
+            Some("item-1".to_string())
+        );
+
+        // Each merge restarts the window
+        merge.record("item-1", Some("Terminal"), "line 2", at);
+        let later = at + WINDOW - Duration::from_millis(1);
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 3", later, WINDOW),
+            Some("item-1".to_string())
+        );
+        // The target is taken: without a new record nothing merges
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 4", later, WINDOW),
+            None
+        );
+    }
+
+    #[test]
+    fn test_window_expires() {
+        let now = Instant::now();
+        let mut merge = RapidMerge::new();
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 2", now + WINDOW, WINDOW),
+            None
+        );
+
+        // A longer window lets the same gap merge
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        let window = Duration::from_secs(2);
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 2", now + WINDOW, window),
+            Some("item-1".to_string())
+        );
+
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        merge.clear();
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "line 2", now, WINDOW),
+            None
+        );
+    }
+
+    #[test]
+    fn test_different_apps_never_merge() {
+        let now = Instant::now();
+        let mut merge = RapidMerge::new();
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        assert_eq!(
+            merge.take_target(Some("iTerm2"), "line 2", now, WINDOW),
+            None
+        );
+
+        merge.record("item-1", Some("Terminal"), "line 1", now);
+        assert_eq!(merge.take_target(None, "line 2", now, WINDOW), None);
+
+        // Text from an unknown app is never a target
+        merge.record("item-2", None, "line 1", now);
+        assert_eq!(merge.take_target(None, "line 2", now, WINDOW), None);
+    }
+
+    #[test]
+    fn test_repeated_copy_does_not_merge() {
+        let now = Instant::now();
+        let mut merge = RapidMerge::new();
+        merge.record("item-1", Some("Terminal"), "ls -la", now);
+        assert_eq!(
+            merge.take_target(Some("Terminal"), "ls -la", now, WINDOW),
+            None
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! One-shot capture override
+//!
+//! An app whose `app_rate_limits` entry is 0 is never captured (password
+//! managers, usually), and others are dropped past their limit.
+//! `allow_next_capture` arms an override for `OVERRIDE_TTL`: the next
+//! clipboard change is captured whatever its source app's limit, without
+//! counting against it. The override only lifts the per-app limit; the
+//! other checks still apply, so empty text, a duplicate or an image over
+//! `max_image_capture_mb` is handled as usual. Any change with content
+//! consumes it, even one its limit would have let through, or one that then
+//! fails to save, and `override-consumed` is emitted. Clearing the clipboard
+//! and find pasteboard changes leave it armed.
+
+use std::time::{Duration, Instant};
+
+/// How long an unused override stays armed
+pub const OVERRIDE_TTL: Duration = Duration::from_secs(30);
+
+/// Armed state of the override
+///
+/// Callers pass the current time explicitly so expiry can be tested with a
+/// mock clock.
+#[derive(Debug, Default)]
+pub struct CaptureOverride {
+    expires: Option<Instant>,
+}
+
+impl CaptureOverride {
+    pub const fn new() -> Self {
+        Self { expires: None }
+    }
+
+    /// Arm the override until `now + OVERRIDE_TTL`; arming it again restarts
+    /// the countdown
+    pub fn arm(&mut self, now: Instant) {
+        self.expires = Some(now + OVERRIDE_TTL);
+    }
+
+    /// Whether the override is armed and unexpired at `now`
+    pub fn is_armed(&self, now: Instant) -> bool {
+        self.expires.is_some_and(|expires| now < expires)
+    }
+
+    /// Disarm the override; returns whether it was armed and unexpired
+    pub fn take(&mut self, now: Instant) -> bool {
+        let armed = self.is_armed(now);
+        self.expires = None;
+        armed
+    }
+}
+
+/// Whether a capture goes ahead: always when `overridden`, in which case
+/// `is_rate_limited` (which counts the capture) is not consulted
+pub fn admit(overridden: bool, is_rate_limited: impl FnOnce() -> bool) -> bool {
+    overridden || !is_rate_limited()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::rate_limiter::AppRateLimiter;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_override_is_one_shot() {
+        let now = Instant::now();
+        let mut capture_override = CaptureOverride::new();
+        assert!(!capture_override.take(now));
+
+        capture_override.arm(now);
+        assert!(capture_override.is_armed(now));
+        assert!(capture_override.take(now + Duration::from_secs(1)));
+        assert!(!capture_override.is_armed(now + Duration::from_secs(1)));
+        assert!(!capture_override.take(now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_override_expires_unused() {
+        let now = Instant::now();
+        let mut capture_override = CaptureOverride::new();
+        capture_override.arm(now);
+        assert!(capture_override.is_armed(now + OVERRIDE_TTL - Duration::from_millis(1)));
+        assert!(!capture_override.is_armed(now + OVERRIDE_TTL));
+        assert!(!capture_override.take(now + OVERRIDE_TTL));
+
+        // Arming again restarts the countdown
+        capture_override.arm(now);
+        capture_override.arm(now + Duration::from_secs(20));
+        assert!(capture_override.take(now + Duration::from_secs(40)));
+    }
+
+    #[test]
+    fn test_override_lifts_app_limits_without_counting() {
+        let now = Instant::now();
+        let mut limiter = AppRateLimiter::default();
+        let consulted = Cell::new(0);
+        let mut is_rate_limited = |limit: u32| {
+            consulted.set(consulted.get() + 1);
+            !limiter.allow("1Password", limit, now)
+        };
+
+        // Excluded app (limit 0): dropped unless overridden
+        assert!(!admit(false, || is_rate_limited(0)));
+        assert!(admit(true, || is_rate_limited(0)));
+        assert_eq!(consulted.get(), 1);
+
+        // Limited app: the overridden capture doesn't use up the limit
+        assert!(admit(true, || is_rate_limited(1)));
+        assert!(admit(false, || is_rate_limited(1)));
+        assert!(!admit(false, || is_rate_limited(1)));
+        assert_eq!(consulted.get(), 3);
+
+        // Only the drops without an override were counted
+        assert_eq!(limiter.dropped().get("1Password"), Some(&2));
+    }
+}
@@ -3,117 +3,899 @@
 //! Windows: Uses clipboard-master crate for native clipboard notifications
 //! macOS: Uses polling with arboard
 
+#[cfg(target_os = "windows")]
+use crate::clipboard::app_icon_worker::{self, IconRequest, IconSink};
+use crate::clipboard::audio_metadata::{self, MetadataRequest, MetadataSink};
+use crate::clipboard::canonical_url::{canonicalize_url, DEFAULT_TRACKING_PARAMS};
+use crate::clipboard::capture_failures::{
+    CaptureFailure, CaptureFailureLog, CaptureStage, FailureCode,
+};
+use crate::clipboard::capture_merge::{RapidMerge, DEFAULT_MERGE_WINDOW_MS};
+use crate::clipboard::capture_override::{self, CaptureOverride, OVERRIDE_TTL};
 use crate::clipboard::clipboard_reader::{self, ClipboardContent};
+use crate::clipboard::contact_event;
+use crate::clipboard::event_coalescer::{self, CaptureSink};
+use crate::clipboard::file_group;
+use crate::clipboard::folder_summary::{self, DIRECTORY_SCAN_CAP};
+use crate::clipboard::language::{self, LanguageRequest, LanguageSink};
 use crate::clipboard::og_fetcher;
-use crate::models::{ClipboardItem, ContentType};
-use crate::storage::{file_storage, Database, FileStorage};
+use crate::clipboard::palette::{self, PaletteRequest, PaletteSink};
+use crate::clipboard::payload_limit;
+use crate::clipboard::prune_scheduler;
+use crate::clipboard::rate_limiter::{self, AppRateLimiter};
+use crate::clipboard::screenshot;
+use crate::clipboard::source_app_cache::{
+    SourceApp, SourceAppCache, SourceAppCacheStats, SOURCE_APP_TTL,
+};
+use crate::clipboard::storage_guard::{self, GuardTransition, StorageGuard, StorageLowPayload};
+use crate::clipboard::text_normalize::{self, NormalizeMode};
+#[cfg(target_os = "windows")]
+use crate::clipboard::windows_icons::OwnerProcess;
+use crate::error::ClipsterError;
+use crate::events::{
+    self, CapturedItem, ItemUpdatedPayload, OverrideConsumedPayload, StorageRelocatedPayload,
+    ThumbnailUpdatedPayload,
+};
+use crate::models::clipboard_item::truncate_window_title;
+use crate::models::{ClipboardItem, ContentType, MediaMetadata};
+use crate::permissions::{self, PermissionKind};
+use crate::preview::{self, PREVIEW_MAX_CHARS};
+use crate::startup::timings::PhaseTiming;
+use crate::storage::database::DedupeScope;
+use crate::storage::file_storage::{self, ImageSaveError};
+use crate::storage::pending_spool::{self, INSERT_RETRY_DELAYS};
+use crate::storage::{profiles, Database, FileStorage};
+use crate::AppState;
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::thread::{self, JoinHandle};
-use tauri::{AppHandle, Emitter};
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
 
 /// Global monitor instance
 static MONITOR_HANDLE: OnceLock<Mutex<Option<JoinHandle<()>>>> = OnceLock::new();
 static SHOULD_STOP: AtomicBool = AtomicBool::new(false);
 static LAST_IMAGE_HASH: AtomicU64 = AtomicU64::new(0);
+/// Unix time (ms) at which the monitor last reported in (0 = never)
+static LAST_HEARTBEAT_MS: AtomicI64 = AtomicI64::new(0);
+/// Per-app capture rate limiter (survives monitor restarts)
+static RATE_LIMITER: OnceLock<Mutex<AppRateLimiter>> = OnceLock::new();
+/// Recent source app lookups (survives monitor restarts)
+static SOURCE_APP_CACHE: OnceLock<Mutex<SourceAppCache>> = OnceLock::new();
+/// Low disk space state (survives monitor restarts)
+static STORAGE_GUARD: Mutex<StorageGuard> = Mutex::new(StorageGuard::new());
+/// Images are being saved to the fallback directory (see `FileStorage::repair`)
+static STORAGE_RELOCATED: AtomicBool = AtomicBool::new(false);
+/// The panel is on screen (the macOS poll scheduler polls fast meanwhile)
+static PANEL_VISIBLE: AtomicBool = AtomicBool::new(false);
+/// Recent captures that could not be saved (survives monitor restarts)
+static CAPTURE_FAILURES: Mutex<CaptureFailureLog> = Mutex::new(CaptureFailureLog::new());
+/// One-shot lift of the app rate limits (see `capture_override`)
+static CAPTURE_OVERRIDE: Mutex<CaptureOverride> = Mutex::new(CaptureOverride::new());
+
+/// Record that the monitor thread is alive
+fn record_heartbeat() {
+    LAST_HEARTBEAT_MS.store(chrono::Utc::now().timestamp_millis(), Ordering::SeqCst);
+}
 
-/// Event payload for clipboard changes
+/// Monitor state returned by `get_monitoring_status`
 #[derive(Clone, serde::Serialize)]
-pub struct ClipboardChangedPayload {
-    pub item: ClipboardItem,
-    /// If this item replaced an existing one (move to top), this contains the old item's ID
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub replaced_item_id: Option<String>,
+pub struct MonitoringStatus {
+    pub running: bool,
+    pub heartbeat_age_ms: Option<u64>,
+    /// Captures dropped by `app_rate_limits`, per source app identifier
+    pub dropped_captures: HashMap<String, u64>,
+    pub total_dropped: u64,
+    /// Next scheduled history clear (set by `get_monitoring_status`)
+    pub next_auto_clear: Option<chrono::DateTime<chrono::Utc>>,
+    /// Free disk space is below `min_free_space_mb` and images are not being
+    /// saved, or the images directory is unwritable and images are being saved
+    /// to a temporary fallback directory until `repair_storage`
+    pub storage_degraded: bool,
+    /// Captures that failed since launch, per pipeline stage
+    pub capture_failures: BTreeMap<CaptureStage, u64>,
+    pub total_capture_failures: u64,
+    /// Source app lookups served from the cache since launch
+    pub source_app_cache: SourceAppCacheStats,
+    /// Frontend events waiting for the emitter task
+    pub queued_events: usize,
+    /// Frontend events dropped because too many were waiting
+    pub dropped_events: u64,
+    /// Durations of the startup phases (set by `get_monitoring_status`)
+    pub startup_phases: Vec<PhaseTiming>,
 }
 
-/// Event payload for async thumbnail updates (e.g., OG image for links)
-#[derive(Clone, serde::Serialize)]
-pub struct ThumbnailUpdatedPayload {
-    pub id: String,
-    pub thumbnail_base64: String,
+/// Announces coalesced captures once per burst
+struct MonitorEventSink {
+    app_handle: AppHandle,
+    db: Arc<Database>,
+}
+
+impl MonitorEventSink {
+    fn latest_seq(&self) -> i64 {
+        self.db.latest_change_seq().unwrap_or(0)
+    }
+}
+
+impl CaptureSink<CapturedItem> for MonitorEventSink {
+    fn after_flush(&self) {
+        // Index into Spotlight and rebuild the recent items menu off the capture path
+        if let Some(state) = self.app_handle.try_state::<AppState>() {
+            state.spotlight.sync();
+            state.recent_items.refresh();
+        }
+    }
+
+    fn emit_one(&self, capture: CapturedItem) {
+        let item_id = capture.item.id.clone();
+        if !events::emit_item_added(&self.app_handle, capture, self.latest_seq()) {
+            clipster_log!(
+                Error,
+                "[clipboard_monitor] Item {} saved to DB but frontend not notified!",
+                item_id
+            );
+        }
+    }
+
+    fn emit_batch(&self, captures: Vec<CapturedItem>) {
+        clipster_log!(
+            Info,
+            "[clipboard_monitor] Emitting {} captures as one batch",
+            captures.len()
+        );
+        let count = captures.len();
+        if !events::emit_items_added(&self.app_handle, captures, self.latest_seq()) {
+            clipster_log!(
+                Error,
+                "[clipboard_monitor] {} items saved to DB but frontend not notified!",
+                count
+            );
+        }
+    }
+}
+
+/// Caches icons from the icon worker and fills them in on waiting items
+#[cfg(target_os = "windows")]
+struct AppIconSink {
+    app_handle: AppHandle,
+    db: Arc<Database>,
+}
+
+#[cfg(target_os = "windows")]
+impl IconSink for AppIconSink {
+    fn icon_ready(&self, app_path: &str, icon: &str, item_ids: &[String]) {
+        if let Err(e) = self.db.set_app_icon(app_path, icon) {
+            clipster_log!(
+                Warn,
+                "[app_icon_worker] Failed to cache icon of {}: {}",
+                app_path, e
+            );
+        }
+
+        for item_id in item_ids {
+            match self.db.fill_source_app_icon(item_id, icon) {
+                Ok(true) => {
+                    let payload = ItemUpdatedPayload {
+                        id: item_id.clone(),
+                        source_app_icon: Some(icon.to_string()),
+                        ..Default::default()
+                    };
+                    events::emit_item_updated(&self.app_handle, &payload);
+                }
+                // Deleted in the meantime
+                Ok(false) => {}
+                Err(e) => clipster_log!(
+                    Warn,
+                    "[app_icon_worker] Failed to update {}: {}",
+                    item_id,
+                    e
+                ),
+            }
+        }
+    }
+}
+
+/// Stores the metadata read from audio items' files
+struct AudioMetadataSink {
+    app_handle: AppHandle,
+    db: Arc<Database>,
+}
+
+impl MetadataSink for AudioMetadataSink {
+    fn metadata_ready(&self, item_id: &str, metadata: &MediaMetadata) {
+        match self.db.set_media_metadata(item_id, metadata) {
+            Ok(true) => {
+                let payload = ItemUpdatedPayload {
+                    id: item_id.to_string(),
+                    media_metadata: Some(metadata.clone()),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&self.app_handle, &payload);
+            }
+            // Deleted in the meantime
+            Ok(false) => {}
+            Err(e) => clipster_log!(Warn, "[audio_metadata] Failed to update {}: {}", item_id, e),
+        }
+    }
+}
+
+/// Stores the languages detected for text items
+struct LanguageDetectionSink {
+    app_handle: AppHandle,
+    db: Arc<Database>,
+}
+
+impl LanguageSink for LanguageDetectionSink {
+    fn language_ready(&self, item_id: &str, language: &str) {
+        match self.db.set_item_language(item_id, language) {
+            Ok(true) => {
+                let payload = ItemUpdatedPayload {
+                    id: item_id.to_string(),
+                    language: Some(language.to_string()),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&self.app_handle, &payload);
+            }
+            // Deleted in the meantime
+            Ok(false) => {}
+            Err(e) => clipster_log!(Warn, "[language] Failed to update {}: {}", item_id, e),
+        }
+    }
+}
+
+/// Stores the dominant colors of image items
+struct PaletteExtractionSink {
+    app_handle: AppHandle,
+    db: Arc<Database>,
+}
+
+impl PaletteSink for PaletteExtractionSink {
+    fn palette_ready(&self, item_id: &str, palette: &[String]) {
+        match self.db.set_item_palette(item_id, palette) {
+            Ok(true) => {
+                let payload = ItemUpdatedPayload {
+                    id: item_id.to_string(),
+                    palette: Some(palette.to_vec()),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&self.app_handle, &payload);
+            }
+            // Deleted in the meantime
+            Ok(false) => {}
+            Err(e) => clipster_log!(Warn, "[palette] Failed to update {}: {}", item_id, e),
+        }
+    }
 }
 
 /// Clipboard handler that processes clipboard changes
 struct ClipboardMonitorHandler {
     app_handle: AppHandle,
     db: Arc<Database>,
-    file_storage: FileStorage,
+    /// Shared with `AppState`, see `repair_storage`
+    file_storage: Arc<FileStorage>,
+    /// Saved captures for the event coalescer; dropping it flushes and stops the worker
+    captures: Sender<CapturedItem>,
+    /// One message per saved capture for the prune worker; dropping it stops the worker
+    prune_requests: Sender<()>,
+    /// Single-file audio items waiting for their metadata; dropping it stops the worker
+    audio_metadata: Sender<MetadataRequest>,
+    /// Text items waiting for their language; dropping it stops the worker
+    language_detection: Sender<LanguageRequest>,
+    /// Image items waiting for their palette; dropping it stops the worker
+    palette_extraction: Sender<PaletteRequest>,
+    /// Latest text capture a rapid follow-up may be merged into (see `capture_merge`)
+    rapid_merge: Mutex<RapidMerge>,
+    /// Items saved before their source app icon was cached; dropping it stops the worker
+    #[cfg(target_os = "windows")]
+    app_icons: Sender<IconRequest>,
+    /// Executable whose icon the capture being saved is missing
+    #[cfg(target_os = "windows")]
+    pending_icon_path: Mutex<Option<String>>,
 }
 
 impl ClipboardMonitorHandler {
-    fn new(app_handle: AppHandle, db: Arc<Database>) -> Self {
-        let file_storage = FileStorage::new().expect("Failed to initialize file storage");
+    fn new(app_handle: AppHandle, db: Arc<Database>, file_storage: Arc<FileStorage>) -> Self {
+        let (captures, receiver) = mpsc::channel();
+        let sink = MonitorEventSink {
+            app_handle: app_handle.clone(),
+            db: Arc::clone(&db),
+        };
+        thread::spawn(move || event_coalescer::run_worker(&sink, receiver));
+
+        // History is pruned in the background, at most every PRUNE_INTERVAL,
+        // and expired items are deleted there too
+        let (prune_requests, receiver) = mpsc::channel();
+        let prune_db = Arc::clone(&db);
+        let prune_app = app_handle.clone();
+        thread::spawn(move || {
+            // Drop deleted items from Spotlight and the recent items menu
+            let sync_menus = |app: &AppHandle| {
+                if let Some(state) = app.try_state::<AppState>() {
+                    state.spotlight.sync();
+                    state.recent_items.refresh();
+                }
+            };
+            prune_scheduler::run_worker(
+                prune_db.as_ref(),
+                receiver,
+                |pruned| {
+                    clipster_log!(
+                        Info,
+                        "[clipboard_monitor] Pruned {} items from history",
+                        pruned
+                    );
+                    sync_menus(&prune_app);
+                },
+                |expired| {
+                    clipster_log!(
+                        Info,
+                        "[clipboard_monitor] Deleted {} expired items",
+                        expired.len()
+                    );
+                    for id in expired {
+                        events::emit_item_deleted(&prune_app, id);
+                    }
+                    sync_menus(&prune_app);
+                },
+            )
+        });
+
+        // Audio tags are read off the capture path
+        let (audio_metadata, receiver) = mpsc::channel();
+        let sink = AudioMetadataSink {
+            app_handle: app_handle.clone(),
+            db: Arc::clone(&db),
+        };
+        thread::spawn(move || audio_metadata::run_worker(audio_metadata::read, &sink, receiver));
+
+        // So is language detection
+        let (language_detection, receiver) = mpsc::channel();
+        let sink = LanguageDetectionSink {
+            app_handle: app_handle.clone(),
+            db: Arc::clone(&db),
+        };
+        thread::spawn(move || language::run_worker(&sink, receiver));
+
+        // And palette extraction, from the thumbnail
+        let (palette_extraction, receiver) = mpsc::channel();
+        let sink = PaletteExtractionSink {
+            app_handle: app_handle.clone(),
+            db: Arc::clone(&db),
+        };
+        thread::spawn(move || palette::run_worker(&sink, receiver));
+
+        #[cfg(target_os = "windows")]
+        let app_icons = {
+            let (app_icons, receiver) = mpsc::channel();
+            let sink = AppIconSink {
+                app_handle: app_handle.clone(),
+                db: Arc::clone(&db),
+            };
+            thread::spawn(move || {
+                app_icon_worker::run_worker(extract_app_icon_base64, &sink, receiver)
+            });
+            app_icons
+        };
+
         Self {
             app_handle,
             db,
             file_storage,
+            captures,
+            prune_requests,
+            audio_metadata,
+            language_detection,
+            palette_extraction,
+            rapid_merge: Mutex::new(RapidMerge::new()),
+            #[cfg(target_os = "windows")]
+            app_icons,
+            #[cfg(target_os = "windows")]
+            pending_icon_path: Mutex::new(None),
         }
     }
 
     /// Process new clipboard content
+    #[cfg(target_os = "windows")]
     fn process_clipboard_change(&self) {
-        let content = clipboard_reader::read_clipboard();
+        let max_image_bytes = self.max_image_capture_bytes();
+        self.process_content(clipboard_reader::read_clipboard(max_image_bytes));
+    }
+
+    /// Capture content read from the clipboard
+    fn process_content(&self, content: ClipboardContent) {
+        if matches!(content, ClipboardContent::Empty) {
+            return;
+        }
+
+        let overridden = CAPTURE_OVERRIDE
+            .lock()
+            .map(|mut capture_override| capture_override.take(Instant::now()))
+            .unwrap_or(false);
+        if !capture_override::admit(overridden, || self.is_rate_limited()) {
+            return;
+        }
+        if overridden {
+            clipster_log!(Info, "[clipboard_monitor] Capture override consumed");
+            events::emit(&self.app_handle, &OverrideConsumedPayload {});
+        }
+        // Only consecutive text captures are merged
+        if !matches!(content, ClipboardContent::Text(_)) {
+            self.forget_text_capture();
+        }
 
         match content {
-            ClipboardContent::Text(text) => self.process_text(text),
+            ClipboardContent::Text(text) => self.process_text(text, None),
             ClipboardContent::Image(image_data) => self.process_image(image_data),
             ClipboardContent::Files(files) => self.process_files(files),
+            ClipboardContent::Unreadable {
+                content_type,
+                locked,
+            } => {
+                let code = if locked {
+                    FailureCode::ClipboardLocked
+                } else {
+                    FailureCode::Unreadable
+                };
+                self.record_failure(CaptureStage::Read, code, Some(content_type));
+            }
+            ClipboardContent::TooLarge(too_large) => {
+                self.record_failure(
+                    CaptureStage::Read,
+                    FailureCode::TooLarge,
+                    Some(ContentType::Image),
+                );
+                events::emit(&self.app_handle, &too_large);
+            }
             ClipboardContent::Empty => {}
         }
     }
 
+    /// Log a capture that could not be saved and tell the frontend
+    /// Every failed step of the capture pipeline reports through here
+    fn record_failure(
+        &self,
+        stage: CaptureStage,
+        code: FailureCode,
+        content_type: Option<ContentType>,
+    ) {
+        let failure = CaptureFailure {
+            timestamp: chrono::Utc::now(),
+            stage,
+            code,
+            content_type,
+        };
+        clipster_log!(Warn, "[clipboard_monitor] Capture failed: {:?}", failure);
+        if let Ok(mut log) = CAPTURE_FAILURES.lock() {
+            log.record(failure.clone());
+        }
+        events::emit(&self.app_handle, &failure);
+    }
+
+    /// Process new text on the find pasteboard (`capture_find_pasteboard` setting)
+    #[cfg(target_os = "macos")]
+    fn process_find_pasteboard_change(&self) {
+        let enabled = self
+            .db
+            .get_setting("capture_find_pasteboard")
+            .ok()
+            .flatten()
+            .as_deref()
+            == Some("true");
+        if !enabled || self.is_rate_limited() {
+            return;
+        }
+        if let Some(text) = clipboard_reader::read_find_text() {
+            self.process_text(text, Some(crate::models::clipboard_item::FIND_PASTEBOARD));
+        }
+    }
+
+    /// Dedupe key of a copied URL, using the `url_tracking_params` setting
+    fn canonical_url(&self, url: &str) -> String {
+        let tracking_params = self.db.get_url_tracking_params().unwrap_or_else(|e| {
+            clipster_log!(Info, "[clipboard_monitor] {}, using default tracking params", e);
+            DEFAULT_TRACKING_PARAMS.iter().map(|p| p.to_string()).collect()
+        });
+        canonicalize_url(url, &tracking_params)
+    }
+
+    /// Scope of "move to top" deduplication, read at capture time (`dedupe_scope` setting)
+    fn dedupe_scope(&self) -> DedupeScope {
+        self.db.get_dedupe_scope().unwrap_or_else(|e| {
+            clipster_log!(Warn, "[clipboard_monitor] Failed to read dedupe scope: {}", e);
+            DedupeScope::default()
+        })
+    }
+
+    /// Size above which text is spilled to disk (`large_text_threshold_bytes` setting)
+    fn large_text_threshold(&self) -> usize {
+        self.db
+            .get_setting("large_text_threshold_bytes")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(file_storage::DEFAULT_LARGE_TEXT_THRESHOLD)
+    }
+
+    /// Encoding of image thumbnails (`thumbnail_format` setting, PNG by default)
+    fn thumbnail_format(&self) -> file_storage::ExportFormat {
+        self.db
+            .get_setting("thumbnail_format")
+            .ok()
+            .flatten()
+            .and_then(|v| file_storage::ExportFormat::parse(&v))
+            .unwrap_or(file_storage::ExportFormat::Png)
+    }
+
+    /// Time a single qlmanage run may take (`quicklook_timeout_ms` setting)
+    #[cfg(target_os = "macos")]
+    fn quicklook_timeout(&self) -> std::time::Duration {
+        let timeout_ms = self
+            .db
+            .get_setting("quicklook_timeout_ms")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .filter(|&ms| ms > 0)
+            .unwrap_or(file_storage::DEFAULT_QUICKLOOK_TIMEOUT_MS);
+        std::time::Duration::from_millis(timeout_ms)
+    }
+
+    /// Longest gap between two text captures that are merged, or None when
+    /// the `merge_rapid_text_captures` setting is off (`merge_window_ms` setting)
+    fn rapid_merge_window(&self) -> Option<std::time::Duration> {
+        let enabled = self
+            .db
+            .get_setting("merge_rapid_text_captures")
+            .ok()
+            .flatten()
+            .as_deref()
+            == Some("true");
+        if !enabled {
+            return None;
+        }
+        let window_ms = self
+            .db
+            .get_setting("merge_window_ms")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .filter(|&ms| ms > 0)
+            .unwrap_or(DEFAULT_MERGE_WINDOW_MS);
+        Some(std::time::Duration::from_millis(window_ms))
+    }
+
+    /// Append `text` to the previous text capture if it came from the same app
+    /// within the merge window; returns whether it was merged
+    /// A capture that isn't `mergeable` ends the run
+    fn merge_rapid_capture(&self, text: &str, mergeable: bool) -> bool {
+        let window = self.rapid_merge_window();
+        let Ok(mut rapid_merge) = self.rapid_merge.lock() else {
+            return false;
+        };
+        let Some(window) = window.filter(|_| mergeable) else {
+            rapid_merge.clear();
+            return false;
+        };
+
+        let now = Instant::now();
+        let (source_app, _) = self.get_source_app_info();
+        let Some(target) = rapid_merge.take_target(source_app.as_deref(), text, now, window) else {
+            return false;
+        };
+        match self.db.append_item_text(&target, text) {
+            Ok(Some(item)) => {
+                clipster_log!(Debug, "║   MERGED into {} (app: {:?})", item.id, source_app);
+                rapid_merge.record(&item.id, source_app.as_deref(), text, now);
+                let payload = ItemUpdatedPayload {
+                    id: item.id,
+                    content_text: item.content_text,
+                    preview: Some(item.preview),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&self.app_handle, &payload);
+                true
+            }
+            // Deleted or no longer plain text: capture as usual
+            Ok(None) => false,
+            Err(e) => {
+                clipster_log!(Warn, "║   Warning: merge into {} failed: {}", target, e);
+                false
+            }
+        }
+    }
+
+    /// Remember a new text item as the one the next rapid capture merges into
+    fn remember_text_capture(&self, item: &ClipboardItem) {
+        if let Ok(mut rapid_merge) = self.rapid_merge.lock() {
+            let text = item.content_text.as_deref().unwrap_or_default();
+            rapid_merge.record(&item.id, item.source_app.as_deref(), text, Instant::now());
+        }
+    }
+
+    /// Start the next text capture in a new item
+    fn forget_text_capture(&self) {
+        if let Ok(mut rapid_merge) = self.rapid_merge.lock() {
+            rapid_merge.clear();
+        }
+    }
+
+    /// Largest side of stored images in pixels (`max_image_dimension` setting, 0 = no limit)
+    fn max_image_dimension(&self) -> u32 {
+        self.db
+            .get_setting("max_image_dimension")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// Largest clipboard image read in bytes (`max_image_capture_mb` setting)
+    fn max_image_capture_bytes(&self) -> u64 {
+        let max_image_capture_mb = self
+            .db
+            .get_setting("max_image_capture_mb")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(payload_limit::DEFAULT_MAX_IMAGE_CAPTURE_MB);
+        payload_limit::limit_bytes(max_image_capture_mb)
+    }
+
+    /// Free space threshold in bytes (`min_free_space_mb` setting)
+    fn min_free_bytes(&self) -> u64 {
+        let min_free_space_mb = self
+            .db
+            .get_setting("min_free_space_mb")
+            .ok()
+            .flatten()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(storage_guard::DEFAULT_MIN_FREE_SPACE_MB);
+        storage_guard::min_free_bytes(min_free_space_mb)
+    }
+
+    /// Check free space on the data volume against the `min_free_space_mb` setting
+    /// Returns false while images should not be saved
+    fn has_space_for_images(&self) -> bool {
+        let min_free_bytes = self.min_free_bytes();
+        let free_bytes = match self.file_storage.available_space() {
+            Ok(free_bytes) => free_bytes,
+            // Unknown free space: try the save rather than drop the capture
+            Err(e) => {
+                clipster_log!(Info, "[clipboard_monitor] {}", e);
+                return true;
+            }
+        };
+
+        let Ok(mut guard) = STORAGE_GUARD.lock() else {
+            return true;
+        };
+        let transition = guard.record_free_space(free_bytes, min_free_bytes);
+        let degraded = guard.is_degraded();
+        drop(guard);
+
+        self.handle_storage_transition(transition, Some(free_bytes), min_free_bytes);
+        !degraded
+    }
+
+    /// Suspend image saving after a write failed with a full disk
+    fn record_disk_full(&self) {
+        let transition = STORAGE_GUARD
+            .lock()
+            .ok()
+            .and_then(|mut guard| guard.record_disk_full());
+        self.handle_storage_transition(transition, None, self.min_free_bytes());
+    }
+
+    /// Emit `storage-low` when space runs low; log when it comes back
+    fn handle_storage_transition(
+        &self,
+        transition: Option<GuardTransition>,
+        free_bytes: Option<u64>,
+        min_free_bytes: u64,
+    ) {
+        match transition {
+            Some(GuardTransition::BecameLow) => {
+                clipster_log!(
+                    Info,
+                    "[clipboard_monitor] Low disk space ({:?} bytes free), not saving images",
+                    free_bytes
+                );
+                let payload = StorageLowPayload {
+                    free_bytes,
+                    min_free_bytes,
+                };
+                events::emit(&self.app_handle, &payload);
+            }
+            Some(GuardTransition::Recovered) => {
+                clipster_log!(
+                    Info,
+                    "[clipboard_monitor] Disk space recovered, saving images again"
+                );
+            }
+            None => {}
+        }
+    }
+
+    /// Check the source app against the `app_rate_limits` setting
+    /// Apps are keyed by `source_app_id`; unlisted apps are unlimited
+    fn is_rate_limited(&self) -> bool {
+        let limits = match self.db.get_app_rate_limits() {
+            Ok(limits) if !limits.is_empty() => limits,
+            _ => return false,
+        };
+        let Some(source_app_id) = source_app_id() else {
+            return false;
+        };
+        let Some(limit) = rate_limiter::limit_for(&limits, &source_app_id) else {
+            return false;
+        };
+
+        let limiter = RATE_LIMITER.get_or_init(|| Mutex::new(AppRateLimiter::default()));
+        let allowed = match limiter.lock() {
+            Ok(mut limiter) => limiter.allow(&source_app_id, limit, Instant::now()),
+            Err(_) => true,
+        };
+        if !allowed {
+            clipster_log!(
+                Info,
+                "[clipboard_monitor] Rate limit reached for {}, dropping capture",
+                source_app_id
+            );
+        }
+        !allowed
+    }
+
     /// Process text clipboard content
     /// Uses "move to top" behavior: if content exists, delete old and create new
     /// Pinned items are preserved - only unpinned history items are affected
-    fn process_text(&self, text: String) {
-        eprintln!("╔═══════════════════════════════════════════════════════════");
-        eprintln!("║ [DEBUG process_text] NEW TEXT FROM CLIPBOARD");
-        let preview = if text.len() > 100 { format!("{}...", &text[..100]) } else { text.clone() };
-        eprintln!("║   text: {} ({} chars)", preview.replace('\n', "\\n"), text.len());
+    /// `source_pasteboard` is None for the general pasteboard; items only
+    /// replace earlier copies from the same pasteboard
+    fn process_text(&self, text: String, source_pasteboard: Option<&str>) {
+        // Contacts and events are kept as copied so they paste back into their apps
+        let structured = contact_event::detect(&text);
+        // Normalize before dedupe so the stored content is already clean
+        let text = if structured.is_some() {
+            text
+        } else {
+            self.normalize_captured_text(text)
+        };
+
+        clipster_log!(Debug, "╔═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║ [DEBUG process_text] NEW TEXT FROM CLIPBOARD");
+        let preview = preview::preview_text(&text, PREVIEW_MAX_CHARS);
+        clipster_log!(Debug, "║   text: {} ({} chars)", preview, text.chars().count());
 
         if text.trim().is_empty() {
-            eprintln!("║   EMPTY/WHITESPACE - skipping");
-            eprintln!("╚═══════════════════════════════════════════════════════════");
+            clipster_log!(Debug, "║   EMPTY/WHITESPACE - skipping");
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+            return;
+        }
+
+        // A terminal selection copied piece by piece joins the previous item
+        let mergeable = structured.is_none()
+            && source_pasteboard.is_none()
+            && ContentType::detect_from_text(&text) == ContentType::Text;
+        if self.merge_rapid_capture(&text, mergeable) {
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
             return;
         }
 
         // "Move to top" behavior: delete existing unpinned item, then create new
         // This ensures the most recent copy is always at the top
         // Pinned items are NOT affected - they stay in their pinboards
-        let (replaced_item_id, original_source_app, original_source_icon) =
-            match self.db.delete_unpinned_by_content(&text) {
-                Ok(Some((id, app, icon))) => {
-                    eprintln!("║   MOVE TO TOP: deleted existing item {} (app: {:?})", id, app);
-                    (Some(id), app, icon)
-                }
-                Ok(None) => {
-                    eprintln!("║   New content (not in unpinned history)");
-                    (None, None, None)
-                }
-                Err(e) => {
-                    eprintln!("║   Warning: delete_unpinned_by_content failed: {}", e);
-                    (None, None, None)
-                }
-            };
+        // Links dedupe on their canonical URL so tracking-parameter variants collapse
+        // With the per_app scope only copies from the same app are replaced
+        let dedupe_scope = self.dedupe_scope();
+        let current_app = (dedupe_scope == DedupeScope::PerApp).then(|| self.get_source_app_info());
+        let dedupe_app = current_app.as_ref().and_then(|(app, _)| app.as_deref());
+        let canonical_url = (ContentType::detect_from_text(&text) == ContentType::Link)
+            .then(|| self.canonical_url(&text));
+        if self.move_favorite_to_top(
+            &text,
+            canonical_url.as_deref(),
+            source_pasteboard,
+            dedupe_scope,
+            dedupe_app,
+        ) {
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+            return;
+        }
+        let existing = match &canonical_url {
+            Some(canonical) => self.db.delete_unpinned_by_canonical_url(
+                canonical,
+                &text,
+                source_pasteboard,
+                dedupe_scope,
+                dedupe_app,
+            ),
+            None => self.db.delete_unpinned_by_content(
+                &text,
+                source_pasteboard,
+                dedupe_scope,
+                dedupe_app,
+            ),
+        };
+        let (
+            replaced_item_id,
+            original_source_app,
+            original_source_icon,
+            original_window_title,
+            first_copied_at,
+        ) = match existing {
+            Ok(Some((id, app, icon, title, first_copied_at))) => {
+                clipster_log!(
+                    Debug,
+                    "║   MOVE TO TOP: deleted existing item {} (app: {:?})",
+                    id,
+                    app
+                );
+                (Some(id), app, icon, title, first_copied_at)
+            }
+            Ok(None) => {
+                clipster_log!(Debug, "║   New content (not in unpinned history)");
+                (None, None, None, None, None)
+            }
+            Err(e) => {
+                clipster_log!(Warn, "║   Warning: move-to-top dedupe failed: {}", e);
+                (None, None, None, None, None)
+            }
+        };
 
         // Preserve original source app icon when re-copying from within the app
-        let (source_app, source_app_icon) = if original_source_app.is_some() {
-            (original_source_app, original_source_icon)
+        let (source_app, source_app_icon, source_window_title) = if original_source_app.is_some() {
+            (original_source_app, original_source_icon, original_window_title)
         } else {
-            self.get_source_app_info()
+            let (app, icon) = current_app.unwrap_or_else(|| self.get_source_app_info());
+            (app, icon, self.get_source_window_title())
         };
-        eprintln!("║   source_app: {:?}", source_app);
-        eprintln!("╚═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║   source_app: {:?}", source_app);
+        clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
 
-        let item = ClipboardItem::new_text(text, source_app, source_app_icon);
+        let mut item = ClipboardItem::new_text(text, source_app, source_app_icon);
+        if mergeable {
+            self.remember_text_capture(&item);
+        }
+        item.source_window_title = source_window_title;
+        item.source_pasteboard = source_pasteboard.map(str::to_string);
+        if let Some(first_copied_at) = first_copied_at {
+            item.first_copied_at = first_copied_at;
+        }
+        item.canonical_url = canonical_url;
+        if let Some(payload) = &structured {
+            clipster_log!(Debug, "║   {} payload", payload.subtype().as_str());
+            item.content_subtype = Some(payload.subtype());
+            item.title = Some(payload.title());
+            item.refresh_preview();
+        }
+        // Keep very large text out of SQLite; only a preview stays in the row
+        // (unless it is compressed in the database instead)
+        let compress = self.db.get_setting("compress_large_text").ok().flatten().as_deref()
+            == Some("true");
+        if !compress {
+            let threshold = self.large_text_threshold();
+            if let Err(e) = self.file_storage.spill_large_text(&mut item, threshold) {
+                clipster_log!(
+                    Warn,
+                    "[process_text] Failed to spill large text, storing inline: {}",
+                    e
+                );
+                self.record_failure(
+                    CaptureStage::Store,
+                    FailureCode::WriteFailed,
+                    Some(item.content_type),
+                );
+            }
+        }
         let is_link = item.content_type == ContentType::Link;
         let item_id = item.id.clone();
         let item_url = item.content_text.clone();
         self.save_and_emit(item, replaced_item_id);
 
-        // Asynchronously fetch OG preview image for links
+        // Asynchronously fetch OG preview image (or favicon) for links
         if is_link {
             if let Some(url) = item_url {
                 let db = Arc::clone(&self.db);
@@ -124,22 +906,24 @@ impl ClipboardMonitorHandler {
                         .build();
                     if let Ok(rt) = rt {
                         rt.block_on(async {
-                            eprintln!("[OG] Fetching preview for {}", url);
-                            if let Some(thumbnail) = og_fetcher::fetch_og_image_base64(&url).await {
-                                eprintln!("[OG] Got thumbnail for {} ({} chars)", item_id, thumbnail.len());
-                                if let Err(e) = db.update_thumbnail(&item_id, &thumbnail) {
-                                    eprintln!("[OG] DB update failed: {}", e);
+                            clipster_log!(Debug, "[OG] Fetching preview for {}", item_id);
+                            if let Some(preview) = og_fetcher::fetch_link_preview(&url).await {
+                                let thumbnail = preview.thumbnail_base64;
+                                clipster_log!(Debug, "[OG] Got thumbnail for {} ({} chars)", item_id, thumbnail.len());
+                                if let Err(e) =
+                                    db.update_thumbnail(&item_id, &thumbnail, preview.is_favicon)
+                                {
+                                    clipster_log!(Warn, "[OG] DB update failed: {}", e);
                                     return;
                                 }
                                 let payload = ThumbnailUpdatedPayload {
                                     id: item_id,
                                     thumbnail_base64: thumbnail,
+                                    thumbnail_is_favicon: preview.is_favicon,
                                 };
-                                if let Err(e) = app_handle.emit("clipboard-item-thumbnail-updated", &payload) {
-                                    eprintln!("[OG] Event emit failed: {}", e);
-                                }
+                                events::emit(&app_handle, &payload);
                             } else {
-                                eprintln!("[OG] No OG image found for {}", url);
+                                clipster_log!(Debug, "[OG] No preview image found for {}", item_id);
                             }
                         });
                     }
@@ -148,6 +932,29 @@ impl ClipboardMonitorHandler {
         }
     }
 
+    /// Apply the `normalize_text` setting to captured text
+    /// A preview of the original is logged with `debug_capture_logging`
+    fn normalize_captured_text(&self, text: String) -> String {
+        let mode = self
+            .db
+            .get_setting("normalize_text")
+            .ok()
+            .flatten()
+            .map(|v| NormalizeMode::from_setting(&v))
+            .unwrap_or(NormalizeMode::Off);
+
+        let normalized = text_normalize::normalize_text(&text, mode);
+        if normalized != text {
+            clipster_log!(
+                Debug,
+                "[process_text] original before {}: {}",
+                mode.as_str(),
+                preview::preview_text(&text, PREVIEW_MAX_CHARS)
+            );
+        }
+        normalized
+    }
+
     /// Calculate hash of bytes for deduplication
     fn hash_bytes(data: &[u8]) -> u64 {
         let mut hasher = DefaultHasher::new();
@@ -159,97 +966,201 @@ impl ClipboardMonitorHandler {
     /// CRITICAL: This function MUST NEVER silently drop an image.
     /// Even if decoding fails, we save the raw PNG bytes.
     fn process_image(&self, image_data: clipboard_reader::ImageData) {
-        eprintln!("╔═══════════════════════════════════════════════════════════");
-        eprintln!("║ [DEBUG process_image] NEW IMAGE FROM CLIPBOARD");
-        eprintln!("║   png_data size: {} bytes", image_data.png_data.len());
+        clipster_log!(Debug, "╔═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║ [DEBUG process_image] NEW IMAGE FROM CLIPBOARD");
+        clipster_log!(Debug, "║   png_data size: {} bytes", image_data.png_data.len());
 
         // Deduplicate images using hash of first 10KB
         let hash_len = std::cmp::min(10240, image_data.png_data.len());
         let image_hash = Self::hash_bytes(&image_data.png_data[..hash_len]);
 
-        eprintln!("║   hash (first {}B): {}", hash_len, image_hash);
+        clipster_log!(Debug, "║   hash (first {}B): {}", hash_len, image_hash);
 
         if image_hash == LAST_IMAGE_HASH.load(Ordering::SeqCst) {
-            eprintln!("║   DUPLICATE - skipping");
-            eprintln!("╚═══════════════════════════════════════════════════════════");
+            clipster_log!(Debug, "║   DUPLICATE - skipping");
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
             return;
         }
+        // Not recorded as seen, so the same image is captured once space is back
+        if !self.has_space_for_images() {
+            clipster_log!(Debug, "║   LOW DISK SPACE - skipping image");
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+            self.record_failure(
+                CaptureStage::Store,
+                FailureCode::DiskFull,
+                Some(ContentType::Image),
+            );
+            return;
+        }
+
         LAST_IMAGE_HASH.store(image_hash, Ordering::SeqCst);
 
         let id = uuid::Uuid::new_v4().to_string();
-        eprintln!("║   Generated UUID: {}", id);
+        clipster_log!(Debug, "║   Generated UUID: {}", id);
+
+        self.file_storage.apply_settings(&self.db);
 
         // Try to decode image - but DON'T fail if this doesn't work
         match image::load_from_memory(&image_data.png_data) {
             Ok(image) => {
-                eprintln!("║   Image decoded: {}x{}", image.width(), image.height());
+                clipster_log!(Debug, "║   Image decoded: {}x{}", image.width(), image.height());
+
+                // Downscale before the thumbnail and the saved file are made from it
+                let original_size = (image.width(), image.height());
+                let max_dimension = self.max_image_dimension();
+                let (image, downscaled) =
+                    match file_storage::downscale_to_fit(&image, max_dimension) {
+                        Some(smaller) => {
+                            let (width, height) = (smaller.width(), smaller.height());
+                            clipster_log!(Debug, "║   Downscaled to {}x{}", width, height);
+                            (smaller, true)
+                        }
+                        None => (image, false),
+                    };
 
                 // Generate thumbnail (continue even if this fails)
-                let thumbnail_base64 = match file_storage::generate_thumbnail_default(&image) {
-                    Ok(png_bytes) => {
-                        eprintln!("║   Thumbnail generated: {} bytes", png_bytes.len());
-                        Some(file_storage::thumbnail_to_base64(&png_bytes))
+                let thumbnail =
+                    file_storage::generate_thumbnail_as(&image, self.thumbnail_format());
+                let thumbnail_base64 = match thumbnail {
+                    Ok(bytes) => {
+                        clipster_log!(Debug, "║   Thumbnail generated: {} bytes", bytes.len());
+                        Some(file_storage::thumbnail_to_base64(&bytes))
                     }
                     Err(e) => {
-                        eprintln!("║   Thumbnail FAILED: {} (continuing with placeholder)", e);
+                        clipster_log!(
+                            Warn,
+                            "║   Thumbnail FAILED: {} (continuing with placeholder)",
+                            e
+                        );
                         None
                     }
                 };
 
                 // Save full image to disk
-                let image_path = match self.file_storage.save_image(&id, &image) {
+                let saved = self.file_storage.save_image(&id, &image);
+                self.check_storage_relocation();
+                let image_path = match saved {
                     Ok(path) => {
                         let path_str = path.to_string_lossy().to_string();
                         // Verify the saved file
                         if let Ok(meta) = std::fs::metadata(&path) {
-                            eprintln!("║   Image SAVED: {}", path_str);
-                            eprintln!("║   Saved file size: {} bytes", meta.len());
+                            clipster_log!(Debug, "║   Image SAVED: {}", path_str);
+                            clipster_log!(Debug, "║   Saved file size: {} bytes", meta.len());
                         }
                         path_str
                     }
+                    Err(e @ ImageSaveError::DiskFull(_)) => {
+                        // The raw fallback would fail the same way
+                        clipster_log!(Warn, "║   Image save FAILED, disk full: {}", e);
+                        clipster_log!(
+                            Debug,
+                            "╚═══════════════════════════════════════════════════════════"
+                        );
+                        self.record_failure(
+                            CaptureStage::Store,
+                            FailureCode::DiskFull,
+                            Some(ContentType::Image),
+                        );
+                        self.record_disk_full();
+                        self.emit_storage_error(&id, e);
+                        return;
+                    }
                     Err(e) => {
-                        eprintln!("║   Image save via image crate FAILED: {}", e);
+                        clipster_log!(Warn, "║   Image save via image crate FAILED: {}", e);
+                        self.record_failure(
+                            CaptureStage::Store,
+                            FailureCode::WriteFailed,
+                            Some(ContentType::Image),
+                        );
                         // FALLBACK: Save raw PNG bytes directly
-                        self.save_raw_png_and_emit(&id, &image_data.png_data, None);
+                        self.save_raw_image_and_emit(&id, &image_data, None);
                         return;
                     }
                 };
 
-                let (source_app, source_app_icon) = self.get_source_app_info();
-                eprintln!("║   source_app: {:?}", source_app);
-                eprintln!("╚═══════════════════════════════════════════════════════════");
+                let ((source_app, source_app_icon), title) =
+                    self.image_source(image_data.is_screenshot);
+                clipster_log!(Debug, "║   source_app: {:?}", source_app);
+                clipster_log!(
+                    Debug,
+                    "╚═══════════════════════════════════════════════════════════"
+                );
 
-                let item =
+                let mut item =
                     ClipboardItem::new_image(thumbnail_base64, image_path, source_app, source_app_icon);
+                item.source_window_title = self.get_source_window_title();
+                item.title = title;
+                item.refresh_preview();
+                if downscaled {
+                    item.original_width = Some(original_size.0);
+                    item.original_height = Some(original_size.1);
+                }
                 // Images use hash-based deduplication, not "move to top"
                 self.save_and_emit(item, None);
             }
             Err(e) => {
-                eprintln!("║   Image decode FAILED: {}", e);
-                eprintln!("║   FALLBACK: Saving raw PNG bytes directly...");
+                clipster_log!(Warn, "║   Image decode FAILED: {}", e);
+                clipster_log!(Debug, "║   FALLBACK: Saving raw PNG bytes directly...");
+                self.record_failure(
+                    CaptureStage::Decode,
+                    FailureCode::DecodeFailed,
+                    Some(ContentType::Image),
+                );
                 // CRITICAL FALLBACK: Even if we can't decode the image, save the raw bytes
                 // This ensures NO clipboard capture is ever lost
-                self.save_raw_png_and_emit(&id, &image_data.png_data, Some(e.to_string()));
+                self.save_raw_image_and_emit(&id, &image_data, Some(e.to_string()));
             }
         }
     }
 
-    /// Fallback: Save raw PNG bytes when image decoding fails
+    /// Fallback: Save the raw image bytes when image decoding fails
+    /// Raw TIFFs from the pasteboard keep their own extension
     /// This ensures we NEVER lose a clipboard capture
-    fn save_raw_png_and_emit(&self, id: &str, png_data: &[u8], decode_error: Option<String>) {
-        eprintln!("║   [FALLBACK] Saving raw PNG ({} bytes)...", png_data.len());
+    fn save_raw_image_and_emit(
+        &self,
+        id: &str,
+        image_data: &clipboard_reader::ImageData,
+        decode_error: Option<String>,
+    ) {
+        let png_data = &image_data.png_data;
+        clipster_log!(
+            Debug,
+            "║   [FALLBACK] Saving raw {:?} ({} bytes)...",
+            image_data.format,
+            png_data.len()
+        );
 
-        // Try to save raw PNG bytes to disk
-        let image_path = match self.file_storage.save_png_bytes(id, png_data) {
+        // Try to save raw bytes to disk
+        let saved = match image_data.format {
+            image::ImageFormat::Png => self.file_storage.save_png_bytes(id, png_data),
+            format => {
+                let extension = format.extensions_str().first().copied().unwrap_or("bin");
+                self.file_storage.save_original_bytes(id, png_data, extension)
+            }
+        };
+        self.check_storage_relocation();
+        let image_path = match saved {
             Ok(path) => {
                 let path_str = path.to_string_lossy().to_string();
-                eprintln!("║   [FALLBACK] Raw PNG saved: {}", path_str);
+                clipster_log!(Debug, "║   [FALLBACK] Raw image saved: {}", path_str);
                 path_str
             }
             Err(e) => {
-                eprintln!("║   [FALLBACK] CRITICAL: Even raw save failed: {}", e);
-                eprintln!("║   ITEM LOST - this should never happen!");
-                eprintln!("╚═══════════════════════════════════════════════════════════");
+                clipster_log!(Error, "║   [FALLBACK] CRITICAL: Even raw save failed: {}", e);
+                clipster_log!(Error, "║   ITEM LOST - this should never happen!");
+                clipster_log!(
+                    Debug,
+                    "╚═══════════════════════════════════════════════════════════"
+                );
+                let code = match e {
+                    ImageSaveError::DiskFull(_) => FailureCode::DiskFull,
+                    _ => FailureCode::WriteFailed,
+                };
+                self.record_failure(CaptureStage::Store, code, Some(ContentType::Image));
+                if code == FailureCode::DiskFull {
+                    self.record_disk_full();
+                }
+                self.emit_storage_error(id, e);
                 return;
             }
         };
@@ -257,18 +1168,33 @@ impl ClipboardMonitorHandler {
         // Create thumbnail from raw data (might work even if full decode failed)
         let thumbnail_base64 = image::load_from_memory(png_data)
             .ok()
-            .and_then(|img| file_storage::generate_thumbnail_default(&img).ok())
+            .and_then(|img| file_storage::generate_thumbnail_as(&img, self.thumbnail_format()).ok())
             .map(|bytes| file_storage::thumbnail_to_base64(&bytes));
 
-        let (source_app, source_app_icon) = self.get_source_app_info();
+        let ((source_app, source_app_icon), title) = self.image_source(image_data.is_screenshot);
 
         if let Some(err) = decode_error {
-            eprintln!("║   [FALLBACK] Original decode error: {}", err);
+            clipster_log!(Warn, "║   [FALLBACK] Original decode error: {}", err);
+        }
+        clipster_log!(Debug, "║   [FALLBACK] source_app: {:?}", source_app);
+        clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+
+        let mut item = ClipboardItem::new_image(thumbnail_base64, image_path, source_app, source_app_icon);
+        item.source_window_title = self.get_source_window_title();
+        item.title = title;
+        item.refresh_preview();
+        // Raw bytes can't be downscaled; note when they are over the limit
+        let dimensions = file_storage::encoded_dimensions(png_data);
+        if file_storage::exceeds_dimension_limit(dimensions, self.max_image_dimension()) {
+            clipster_log!(
+                Debug,
+                "║   [FALLBACK] Over max_image_dimension, kept as copied: {:?}",
+                dimensions
+            );
+            item.downscale_skipped = true;
+            item.original_width = dimensions.map(|(width, _)| width);
+            item.original_height = dimensions.map(|(_, height)| height);
         }
-        eprintln!("║   [FALLBACK] source_app: {:?}", source_app);
-        eprintln!("╚═══════════════════════════════════════════════════════════");
-
-        let item = ClipboardItem::new_image(thumbnail_base64, image_path, source_app, source_app_icon);
         // Images use hash-based deduplication, not "move to top"
         self.save_and_emit(item, None);
     }
@@ -278,65 +1204,118 @@ impl ClipboardMonitorHandler {
     /// Even if thumbnail generation fails, we still save the item.
     /// Uses "move to top" behavior for duplicates.
     fn process_files(&self, files: Vec<String>) {
-        eprintln!("╔═══════════════════════════════════════════════════════════");
-        eprintln!("║ [DEBUG process_files] Processing {} files", files.len());
+        clipster_log!(Debug, "╔═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║ [DEBUG process_files] Processing {} files", files.len());
         for (i, f) in files.iter().enumerate() {
             let exists = std::path::Path::new(f).exists();
-            eprintln!("║   [{}] {} (exists: {})", i, f, exists);
+            let name = preview::preview_paths(std::slice::from_ref(f));
+            clipster_log!(Debug, "║   [{}] {} (exists: {})", i, name, exists);
         }
 
         if files.is_empty() {
-            eprintln!("║   EMPTY - skipping");
-            eprintln!("╚═══════════════════════════════════════════════════════════");
+            clipster_log!(Debug, "║   EMPTY - skipping");
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
             return;
         }
 
         // "Move to top" behavior: delete existing unpinned item, then create new
+        // Files are attributed to the first file's app, which per_app dedupe compares
         let files_json = serde_json::to_string(&files).unwrap_or_default();
+        let dedupe_scope = self.dedupe_scope();
+        let file_app =
+            (dedupe_scope == DedupeScope::PerApp).then(|| self.get_file_app_info(&files[0]));
+        let dedupe_app = file_app.as_ref().and_then(|(app, _)| app.as_deref());
+        if self.move_favorite_to_top(&files_json, None, None, dedupe_scope, dedupe_app) {
+            clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+            return;
+        }
 
-        let (replaced_item_id, original_source_app, original_source_icon) =
-            match self.db.delete_unpinned_by_content(&files_json) {
-                Ok(Some((id, app, icon))) => {
-                    eprintln!("║   MOVE TO TOP: deleted existing item {} (app: {:?})", id, app);
-                    (Some(id), app, icon)
-                }
-                Ok(None) => {
-                    eprintln!("║   New content (not in unpinned history)");
-                    (None, None, None)
-                }
-                Err(e) => {
-                    eprintln!("║   Warning: delete_unpinned_by_content failed: {}", e);
-                    (None, None, None)
-                }
-            };
+        let (
+            replaced_item_id,
+            original_source_app,
+            original_source_icon,
+            original_window_title,
+            first_copied_at,
+        ) = match self
+            .db
+            .delete_unpinned_by_content(&files_json, None, dedupe_scope, dedupe_app)
+        {
+            Ok(Some((id, app, icon, title, first_copied_at))) => {
+                clipster_log!(
+                    Debug,
+                    "║   MOVE TO TOP: deleted existing item {} (app: {:?})",
+                    id,
+                    app
+                );
+                (Some(id), app, icon, title, first_copied_at)
+            }
+            Ok(None) => {
+                clipster_log!(Debug, "║   New content (not in unpinned history)");
+                (None, None, None, None, None)
+            }
+            Err(e) => {
+                clipster_log!(Warn, "║   Warning: delete_unpinned_by_content failed: {}", e);
+                (None, None, None, None, None)
+            }
+        };
 
-        // Generate thumbnail for the first file (if possible)
+        // A copied folder gets the platform folder icon (what QuickLook and the
+        // shell render for a directory), never a collage
+        let is_directory = folder_summary::is_directory_copy(&files);
+
+        // Generate a collage of the images, or a thumbnail for the first file (if possible)
         // IMPORTANT: Thumbnail failure MUST NOT prevent item creation
-        eprintln!("║   Generating thumbnail (failure OK)...");
-        let thumbnail_base64 = self.generate_file_thumbnail(&files);
+        clipster_log!(Debug, "║   Generating thumbnail (failure OK)...");
+        let thumbnail_base64 = if is_directory {
+            self.generate_file_thumbnail(&files)
+        } else {
+            self.generate_files_collage(&files)
+                .or_else(|| self.generate_file_thumbnail(&files))
+        };
         match &thumbnail_base64 {
-            Some(t) => eprintln!("║   Thumbnail: {} chars", t.len()),
-            None => eprintln!("║   Thumbnail: None (will use file icon)"),
+            Some(t) => clipster_log!(Debug, "║   Thumbnail: {} chars", t.len()),
+            None => clipster_log!(Debug, "║   Thumbnail: None (will use file icon)"),
         }
 
         // Preserve original source app icon when re-copying from within the app
-        let (source_app, source_app_icon) = if original_source_app.is_some() {
-            (original_source_app, original_source_icon)
+        let (source_app, source_app_icon, source_window_title) = if original_source_app.is_some() {
+            (original_source_app, original_source_icon, original_window_title)
         } else {
             // For new files, use the file's own icon instead of source app
             // This is more informative (shows PDF icon, Word icon, etc.)
-            let first_file = &files[0];
-            self.get_file_app_info(first_file)
+            let (app, icon) = file_app.unwrap_or_else(|| self.get_file_app_info(&files[0]));
+            (app, icon, self.get_source_window_title())
         };
-        eprintln!("║   source_app: {:?}", source_app);
-        eprintln!("╚═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║   source_app: {:?}", source_app);
+        clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
 
-        let item = ClipboardItem::new_files_with_thumbnail(
+        let files_summary = if is_directory {
+            let path = std::path::Path::new(&files[0]);
+            match folder_summary::scan_directory(path, DIRECTORY_SCAN_CAP) {
+                Ok(summary) => Some(folder_summary::describe(path, &summary)),
+                Err(e) => {
+                    clipster_log!(Warn, "║   Folder scan failed: {}", e);
+                    None
+                }
+            }
+        } else {
+            file_group::summarize(&files, dirs::home_dir().as_deref())
+        };
+        clipster_log!(Debug, "║   files_summary: {:?}", files_summary);
+
+        let mut item = ClipboardItem::new_files_with_thumbnail(
             files,
             source_app,
             source_app_icon,
             thumbnail_base64,
         );
+        item.files_summary = files_summary;
+        item.refresh_preview();
+        item.is_directory = is_directory;
+        item.source_window_title = source_window_title;
+        if let Some(first_copied_at) = first_copied_at {
+            item.first_copied_at = first_copied_at;
+        }
         self.save_and_emit(item, replaced_item_id);
     }
 
@@ -418,6 +1397,27 @@ impl ClipboardMonitorHandler {
         (Some(extension), icon)
     }
 
+    /// Collage of the first image files of a multi-file copy
+    /// None when fewer than two of them can be decoded
+    fn generate_files_collage(&self, files: &[String]) -> Option<String> {
+        let images: Vec<image::DynamicImage> = file_group::collage_image_paths(files)
+            .into_iter()
+            .filter_map(|path| image::open(path).ok())
+            .collect();
+        if images.len() < 2 {
+            return None;
+        }
+
+        let collage = file_group::render_collage(&images);
+        let bytes = file_storage::generate_thumbnail_as(&collage, self.thumbnail_format()).ok()?;
+        clipster_log!(
+            Info,
+            "[generate_files_collage] Collage of {} images",
+            images.len()
+        );
+        Some(file_storage::thumbnail_to_base64(&bytes))
+    }
+
     /// Generate a thumbnail for the first file in the list
     fn generate_file_thumbnail(&self, files: &[String]) -> Option<String> {
         if files.is_empty() {
@@ -429,7 +1429,8 @@ impl ClipboardMonitorHandler {
 
         // Use platform-specific thumbnail generation
         #[cfg(target_os = "macos")]
-        let thumbnail_bytes = file_storage::generate_file_thumbnail_macos(path, 400)?;
+        let thumbnail_bytes =
+            file_storage::generate_file_thumbnail_macos(path, 400, self.quicklook_timeout())?;
 
         #[cfg(target_os = "windows")]
         let thumbnail_bytes = file_storage::generate_file_thumbnail_windows(path, 400)?;
@@ -443,81 +1444,417 @@ impl ClipboardMonitorHandler {
         // Check thumbnail size (skip if too large, > 300KB)
         let size_kb = thumbnail_bytes.len() / 1024;
         if thumbnail_bytes.len() > 300 * 1024 {
-            eprintln!("[generate_file_thumbnail] Thumbnail too large: {}KB > 300KB, skipping", size_kb);
+            clipster_log!(Debug, "[generate_file_thumbnail] Thumbnail too large: {}KB > 300KB, skipping", size_kb);
             return None;
         }
-        eprintln!("[generate_file_thumbnail] Thumbnail size OK: {}KB", size_kb);
+        clipster_log!(Debug, "[generate_file_thumbnail] Thumbnail size OK: {}KB", size_kb);
 
         Some(file_storage::thumbnail_to_base64(&thumbnail_bytes))
     }
 
-    /// Save item to database and emit event to frontend
+    /// Tell the frontend a capture was lost because its image could not be written
+    fn emit_storage_error(&self, item_id: &str, error: ImageSaveError) {
+        events::emit_storage_error(&self.app_handle, item_id.to_string(), error);
+    }
+
+    /// Emit `storage-relocated` once when image saves move to the fallback
+    /// directory; called after each image save
+    fn check_storage_relocation(&self) {
+        let relocated = self.file_storage.is_relocated();
+        if STORAGE_RELOCATED.swap(relocated, Ordering::SeqCst) || !relocated {
+            return;
+        }
+        let storage = &self.file_storage;
+        let payload = StorageRelocatedPayload {
+            images_dir: storage.images_dir().to_string_lossy().to_string(),
+            fallback_dir: storage.fallback_dir().to_string_lossy().to_string(),
+        };
+        clipster_log!(
+            Warn,
+            "[clipboard_monitor] Images directory {} is unwritable, saving images to {}",
+            payload.images_dir,
+            payload.fallback_dir
+        );
+        events::emit(&self.app_handle, &payload);
+    }
+
+    /// Re-copying a favorited item moves that item to the top instead of
+    /// replacing it, so it keeps its star; see `Database::move_favorite_to_top`
+    /// Returns true if a favorite matched and its event was queued, with its own
+    /// id as `replaced_item_id` so the frontend moves the existing card
+    fn move_favorite_to_top(
+        &self,
+        content_text: &str,
+        canonical_url: Option<&str>,
+        source_pasteboard: Option<&str>,
+        scope: DedupeScope,
+        source_app: Option<&str>,
+    ) -> bool {
+        let item = match self.db.move_favorite_to_top(
+            content_text,
+            canonical_url,
+            source_pasteboard,
+            scope,
+            source_app,
+        ) {
+            Ok(Some(item)) => item,
+            Ok(None) => return false,
+            Err(e) => {
+                clipster_log!(Warn, "║   Warning: move_favorite_to_top failed: {}", e);
+                return false;
+            }
+        };
+
+        clipster_log!(Debug, "║   MOVE TO TOP: favorite {} kept", item.id);
+        let content_type = item.content_type;
+        let capture = CapturedItem {
+            replaced_item_id: Some(item.id.clone()),
+            item,
+        };
+        if self.captures.send(capture).is_err() {
+            clipster_log!(Warn, "║   ✗ EVENT QUEUE CLOSED");
+            self.record_failure(
+                CaptureStage::Notify,
+                FailureCode::EventQueueClosed,
+                Some(content_type),
+            );
+        }
+        true
+    }
+
+    /// Save item to database and queue its event for the frontend
     /// CRITICAL: This is the final step - if this fails, the item is lost
     /// replaced_item_id: If this item replaced an existing one (move to top), pass the old ID
-    fn save_and_emit(&self, item: ClipboardItem, replaced_item_id: Option<String>) {
-        eprintln!("╔═══════════════════════════════════════════════════════════");
-        eprintln!("║ [save_and_emit] SAVING TO DATABASE");
-        eprintln!("║   id: {}", item.id);
-        eprintln!("║   content_type: {:?}", item.content_type);
-        eprintln!("║   content_text: {:?}", item.content_text.as_ref().map(|s| {
-            if s.len() > 50 { format!("{}... ({} chars)", &s[..50], s.len()) } else { s.clone() }
-        }));
-        eprintln!("║   thumbnail_base64: {} chars", item.thumbnail_base64.as_ref().map(|s| s.len()).unwrap_or(0));
-        eprintln!("║   image_path: {:?}", item.image_path);
-        eprintln!("║   source_app: {:?}", item.source_app);
-        eprintln!("║   replaced_item_id: {:?}", replaced_item_id);
-
-        match self.db.insert_item(&item) {
+    /// The event is coalesced with captures arriving right after; pruning is
+    /// only flagged here and runs from `prune_scheduler`
+    fn save_and_emit(&self, mut item: ClipboardItem, replaced_item_id: Option<String>) {
+        #[cfg(target_os = "windows")]
+        let pending_icon_path = self.take_pending_icon_path();
+
+        clipster_log!(Debug, "╔═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "║ [save_and_emit] SAVING TO DATABASE");
+        clipster_log!(Debug, "║   id: {}", item.id);
+        clipster_log!(Debug, "║   content_type: {:?}", item.content_type);
+        clipster_log!(Debug, "║   preview: {}", preview::preview_item(&item, 50));
+        clipster_log!(Debug, "║   thumbnail_base64: {} chars", item.thumbnail_base64.as_ref().map(|s| s.len()).unwrap_or(0));
+        clipster_log!(Debug, "║   image_path: {:?}", item.image_path);
+        clipster_log!(Debug, "║   source_app: {:?}", item.source_app);
+        clipster_log!(Debug, "║   replaced_item_id: {:?}", replaced_item_id);
+
+        // Another connection may hold the lock for a moment (backup, bulk edits)
+        let inserted = pending_spool::retry_busy(&INSERT_RETRY_DELAYS, thread::sleep, || {
+            self.db.insert_capture(&item)
+        });
+        match inserted {
             Ok(()) => {
-                eprintln!("║   ✓ DATABASE INSERT SUCCESS");
+                clipster_log!(Debug, "║   ✓ DATABASE INSERT SUCCESS");
+                // Clear the low-space flag as soon as a save succeeds with space back
+                if STORAGE_GUARD.lock().map(|g| g.is_degraded()).unwrap_or(false) {
+                    self.has_space_for_images();
+                }
+            }
+            Err(e) if pending_spool::is_busy(&e) => {
+                clipster_log!(Warn, "║   ✗ DATABASE STILL LOCKED: {}", e);
+                match self.spool(&item) {
+                    Ok(path) => clipster_log!(
+                        Debug,
+                        "║   Spooled for the next startup: {}",
+                        path.display()
+                    ),
+                    Err(e) => clipster_log!(
+                        Error,
+                        "║   CRITICAL: Item {} is LOST, spool failed: {}",
+                        item.id, e
+                    ),
+                }
+                clipster_log!(
+                    Debug,
+                    "╚═══════════════════════════════════════════════════════════"
+                );
+                self.record_failure(
+                    CaptureStage::Insert,
+                    FailureCode::DatabaseBusy,
+                    Some(item.content_type),
+                );
+                return;
             }
             Err(e) => {
-                eprintln!("║   ✗ DATABASE INSERT FAILED: {}", e);
-                eprintln!("║   CRITICAL: Item {} is LOST!", item.id);
-                eprintln!("╚═══════════════════════════════════════════════════════════");
+                clipster_log!(Error, "║   ✗ DATABASE INSERT FAILED: {}", e);
+                clipster_log!(Error, "║   CRITICAL: Item {} is LOST!", item.id);
+                clipster_log!(
+                    Debug,
+                    "╚═══════════════════════════════════════════════════════════"
+                );
+                self.record_failure(
+                    CaptureStage::Insert,
+                    FailureCode::DatabaseError,
+                    Some(item.content_type),
+                );
                 return;
             }
         }
 
-        if let Ok(limit) = self.db.get_history_limit() {
-            if let Err(e) = self.db.prune_oldest(limit) {
-                eprintln!("║   Warning: prune_oldest failed: {}", e);
+        // Pruning is deferred to the prune worker; a stopped worker has nothing to do
+        let _ = self.prune_requests.send(());
+
+        // Smart pinboards: auto-assign before pruning so matches are protected
+        match self.db.apply_pinboard_rules(&item) {
+            Ok(Some(pinboard_id)) => {
+                clipster_log!(Debug, "║   Auto-assigned to smart pinboard {}", pinboard_id);
+                item.pinboard_id = Some(pinboard_id);
+            }
+            Ok(None) => {}
+            Err(e) => clipster_log!(Warn, "║   Warning: apply_pinboard_rules failed: {}", e),
+        }
+
+        // Per-app default pinboard: file the item while keeping it in history
+        let app_default = source_app_id()
+            .map(|app_id| self.db.apply_app_default_pinboard(&item, &app_id))
+            .unwrap_or(Ok(None));
+        match app_default {
+            Ok(Some(pinboard_id)) => {
+                clipster_log!(Debug, "║   Auto-filed into default pinboard {}", pinboard_id);
+                item.auto_pinboard_id = Some(pinboard_id);
+            }
+            Ok(None) => {}
+            Err(e) => clipster_log!(Warn, "║   Warning: apply_app_default_pinboard failed: {}", e),
+        }
+
+        #[cfg(target_os = "windows")]
+        if let (Some(app_path), None) = (pending_icon_path, &item.source_app_icon) {
+            let request = IconRequest {
+                app_path,
+                item_id: item.id.clone(),
+            };
+            if self.app_icons.send(request).is_err() {
+                clipster_log!(Warn, "║   Warning: icon worker stopped, item keeps no app icon");
+            }
+        }
+
+        if let Some(path) = audio_metadata::metadata_path(&item) {
+            let request = MetadataRequest {
+                item_id: item.id.clone(),
+                path,
+            };
+            if self.audio_metadata.send(request).is_err() {
+                clipster_log!(
+                    Warn,
+                    "║   Warning: audio metadata worker stopped, item keeps no metadata"
+                );
+            }
+        }
+
+        if let Some(request) = language::detect_request(&item) {
+            if self.language_detection.send(request).is_err() {
+                clipster_log!(
+                    Warn,
+                    "║   Warning: language worker stopped, item keeps no language"
+                );
             }
         }
 
-        eprintln!("║   Emitting clipboard-changed event...");
-        let payload = ClipboardChangedPayload {
-            item: item.clone(),
+        if let Some(request) = palette::palette_request(&item) {
+            if self.palette_extraction.send(request).is_err() {
+                clipster_log!(
+                    Warn,
+                    "║   Warning: palette worker stopped, item keeps no palette"
+                );
+            }
+        }
+
+        clipster_log!(Debug, "║   Queueing clipboard-changed event...");
+        let content_type = item.content_type;
+        let capture = CapturedItem {
+            item,
             replaced_item_id,
         };
-        match self.app_handle.emit("clipboard-changed", &payload) {
+        match self.captures.send(capture) {
             Ok(()) => {
-                eprintln!("║   ✓ EVENT EMITTED SUCCESSFULLY");
+                clipster_log!(Debug, "║   ✓ EVENT QUEUED");
             }
-            Err(e) => {
-                eprintln!("║   ✗ EVENT EMIT FAILED: {}", e);
-                eprintln!("║   Item saved to DB but frontend not notified!");
+            Err(_) => {
+                clipster_log!(Warn, "║   ✗ EVENT QUEUE CLOSED");
+                clipster_log!(Error, "║   Item saved to DB but frontend not notified!");
+                self.record_failure(
+                    CaptureStage::Notify,
+                    FailureCode::EventQueueClosed,
+                    Some(content_type),
+                );
             }
         }
-        eprintln!("╚═══════════════════════════════════════════════════════════");
+        clipster_log!(Debug, "╚═══════════════════════════════════════════════════════════");
+    }
+
+    /// Write an item the locked database could not take to the pending spool
+    fn spool(&self, item: &ClipboardItem) -> Result<std::path::PathBuf, ClipsterError> {
+        let data_dir = profiles::active_data_dir().map_err(ClipsterError::Io)?;
+        pending_spool::spool(&pending_spool::pending_dir(&data_dir), item)
     }
 
     /// Try to get the source application name and icon
+    /// Lookups are cached per process for a few seconds
+    #[cfg(target_os = "windows")]
+    fn get_source_app_info(&self) -> SourceApp {
+        let Some(owner) = get_clipboard_owner() else {
+            return (None, None);
+        };
+        cached_source_app(&owner.pid.to_string(), Some(&owner.exe_path), || {
+            self.resolve_source_app(&owner)
+        })
+    }
+
+    /// Name and icon of the app run by `owner`
+    /// Icons come from the `app_icons` cache only; on a miss the icon worker
+    /// extracts it once the item is saved
+    #[cfg(target_os = "windows")]
+    fn resolve_source_app(&self, owner: &OwnerProcess) -> SourceApp {
+        let exe_path = owner.exe_path.as_str();
+        let app_name = owner.app_name();
+        match self.db.get_app_icon(exe_path) {
+            Ok(Some(icon)) => (app_name, Some(icon)),
+            Ok(None) => {
+                if let Ok(mut pending) = self.pending_icon_path.lock() {
+                    *pending = Some(exe_path.to_string());
+                }
+                (app_name, None)
+            }
+            Err(e) => {
+                clipster_log!(Warn, "[clipboard_monitor] Failed to read icon cache: {}", e);
+                (app_name, None)
+            }
+        }
+    }
+
+    /// Executable recorded by the last `get_source_app_info` cache miss
     #[cfg(target_os = "windows")]
-    fn get_source_app_info(&self) -> (Option<String>, Option<String>) {
-        get_clipboard_owner_app_info()
+    fn take_pending_icon_path(&self) -> Option<String> {
+        self.pending_icon_path.lock().ok()?.take()
     }
 
     /// Try to get the source application name and icon (macOS)
+    /// Lookups are cached per bundle identifier for a few seconds
     #[cfg(target_os = "macos")]
-    fn get_source_app_info(&self) -> (Option<String>, Option<String>) {
+    fn get_source_app_info(&self) -> SourceApp {
         get_frontmost_app_info()
     }
+
+    /// Source app and generated title of an image capture
+    /// Screenshots are attributed to the Screenshot app rather than the frontmost
+    /// app, and titled with the time they were taken
+    fn image_source(&self, is_screenshot: bool) -> (SourceApp, Option<String>) {
+        if !is_screenshot {
+            return (self.get_source_app_info(), None);
+        }
+
+        #[cfg(target_os = "macos")]
+        let icon = self.get_file_app_info(screenshot::SCREENSHOT_APP_PATH).1;
+        #[cfg(not(target_os = "macos"))]
+        let icon = None;
+        let app = Some(screenshot::SCREENSHOT_APP.to_string());
+        ((app, icon), Some(screenshot::title(&chrono::Local::now())))
+    }
+
+    /// Title of the focused window, when the `capture_window_titles` setting is on
+    /// Off by default: titles can contain document names, email subjects, etc.
+    fn get_source_window_title(&self) -> Option<String> {
+        let enabled = self
+            .db
+            .get_setting("capture_window_titles")
+            .ok()
+            .flatten()
+            .as_deref()
+            == Some("true");
+        // Other apps' window names are hidden without Screen Recording
+        if !enabled || !permissions::is_granted(PermissionKind::ScreenRecording) {
+            return None;
+        }
+        get_focused_window_title().and_then(|title| truncate_window_title(&title))
+    }
+}
+
+/// Title of the frontmost app's topmost window on macOS
+/// Window names are only visible with the Screen Recording permission;
+/// without it this returns None
+#[cfg(target_os = "macos")]
+fn get_focused_window_title() -> Option<String> {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::CFString;
+    use core_graphics::window::{
+        copy_window_info, kCGNullWindowID, kCGWindowLayer, kCGWindowListExcludeDesktopElements,
+        kCGWindowListOptionOnScreenOnly, kCGWindowName, kCGWindowOwnerPID,
+    };
+    use objc2_app_kit::NSWorkspace;
+
+    let pid = unsafe {
+        NSWorkspace::sharedWorkspace()
+            .frontmostApplication()?
+            .processIdentifier()
+    };
+    let windows = copy_window_info(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+        kCGNullWindowID,
+    )?;
+
+    let (owner_key, layer_key, name_key) = unsafe {
+        (
+            CFString::wrap_under_get_rule(kCGWindowOwnerPID),
+            CFString::wrap_under_get_rule(kCGWindowLayer),
+            CFString::wrap_under_get_rule(kCGWindowName),
+        )
+    };
+    let number = |window: &CFDictionary<CFString, CFType>, key: &CFString| {
+        window
+            .find(key)
+            .and_then(|value| value.downcast::<CFNumber>())
+            .and_then(|n| n.to_i64())
+    };
+
+    // Windows are listed front to back; normal windows are on layer 0
+    windows.iter().find_map(|window| {
+        let window: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(*window as CFDictionaryRef) };
+        if number(&window, &owner_key) != Some(pid as i64) || number(&window, &layer_key) != Some(0)
+        {
+            return None;
+        }
+        window
+            .find(&name_key)
+            .and_then(|name| name.downcast::<CFString>())
+            .map(|name| name.to_string())
+            .filter(|name| !name.trim().is_empty())
+    })
+}
+
+/// Title of the foreground window on Windows
+#[cfg(target_os = "windows")]
+fn get_focused_window_title() -> Option<String> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW,
+    };
+
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0.is_null() {
+            return None;
+        }
+        let len = GetWindowTextLengthW(hwnd);
+        if len <= 0 {
+            return None;
+        }
+        let mut buffer = vec![0u16; len as usize + 1];
+        let copied = GetWindowTextW(hwnd, &mut buffer);
+        if copied <= 0 {
+            return None;
+        }
+        Some(String::from_utf16_lossy(&buffer[..copied as usize]))
+    }
 }
 
 /// Get the frontmost application name and icon on macOS using NSWorkspace
 #[cfg(target_os = "macos")]
-fn get_frontmost_app_info() -> (Option<String>, Option<String>) {
+fn get_frontmost_app_info() -> SourceApp {
     use objc2_app_kit::{NSRunningApplication, NSWorkspace};
 
     unsafe {
@@ -526,20 +1863,56 @@ fn get_frontmost_app_info() -> (Option<String>, Option<String>) {
             workspace.frontmostApplication();
 
         if let Some(app) = frontmost_app {
-            // Get app name
-            let name = app
-                .localizedName()
-                .map(|n| n.to_string());
+            let resolve = || {
+                // Get app name
+                let name = app.localizedName().map(|n| n.to_string());
 
-            // Get app icon
-            let icon = get_app_icon_base64(&app);
+                // Get app icon
+                let icon = get_app_icon_base64(&app);
 
-            return (name, icon);
+                (name, icon)
+            };
+            return match app.bundleIdentifier() {
+                Some(bundle_id) => cached_source_app(&bundle_id.to_string(), None, resolve),
+                // Command-line tools have no bundle to cache by
+                None => resolve(),
+            };
         }
         (None, None)
     }
 }
 
+/// Bundle identifier of the frontmost app, or its executable path for
+/// command-line tools, which have no bundle
+/// Read straight from NSWorkspace, without resolving a name or icon
+#[cfg(target_os = "macos")]
+fn source_app_id() -> Option<String> {
+    use objc2_app_kit::NSWorkspace;
+
+    unsafe {
+        let app = NSWorkspace::sharedWorkspace().frontmostApplication()?;
+        app.bundleIdentifier().map(|id| id.to_string()).or_else(|| {
+            app.executableURL()
+                .and_then(|url| url.path())
+                .map(|path| path.to_string())
+        })
+    }
+}
+
+/// Source app lookup through `SOURCE_APP_CACHE`
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn cached_source_app(
+    key: &str,
+    path: Option<&str>,
+    resolve: impl FnOnce() -> SourceApp,
+) -> SourceApp {
+    let cache = SOURCE_APP_CACHE.get_or_init(|| Mutex::new(SourceAppCache::new(SOURCE_APP_TTL)));
+    match cache.lock() {
+        Ok(mut cache) => cache.get_or_resolve(key, path, Instant::now(), resolve),
+        Err(_) => resolve(),
+    }
+}
+
 /// Extract application icon as base64-encoded PNG (32x32)
 #[cfg(target_os = "macos")]
 fn get_app_icon_base64(app: &objc2_app_kit::NSRunningApplication) -> Option<String> {
@@ -605,259 +1978,139 @@ fn get_app_icon_base64(app: &objc2_app_kit::NSRunningApplication) -> Option<Stri
 // Windows Source App Detection
 // ============================================================================
 
-/// Get the clipboard owner application name on Windows
+/// Process owning the clipboard on Windows
 #[cfg(target_os = "windows")]
-fn get_clipboard_owner_app_info() -> (Option<String>, Option<String>) {
-    use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+fn get_clipboard_owner() -> Option<OwnerProcess> {
     use windows::Win32::System::DataExchange::GetClipboardOwner;
-    use windows::Win32::System::ProcessStatus::GetModuleFileNameExW;
-    use windows::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ};
-    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
-
-    unsafe {
-        // Get the clipboard owner window handle
-        let hwnd: HWND = GetClipboardOwner();
-        if hwnd.0.is_null() {
-            return (None, None);
-        }
-
-        // Get the process ID from the window handle
-        let mut process_id: u32 = 0;
-        GetWindowThreadProcessId(hwnd, Some(&mut process_id));
-        if process_id == 0 {
-            return (None, None);
-        }
 
-        // Open the process to query its module name
-        let process_handle: windows::core::Result<HANDLE> =
-            OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, false, process_id);
-
-        let process_handle = match process_handle {
-            Ok(handle) if !handle.is_invalid() => handle,
-            _ => return (None, None),
-        };
-
-        // Get the executable path
-        let mut exe_path_buffer = [0u16; 260]; // MAX_PATH
-        let path_len = GetModuleFileNameExW(Some(process_handle), None, &mut exe_path_buffer);
-
-        // Close the process handle
-        let _ = CloseHandle(process_handle);
-
-        if path_len == 0 {
-            return (None, None);
-        }
-
-        // Convert UTF-16 to String
-        let exe_path = String::from_utf16_lossy(&exe_path_buffer[..path_len as usize]);
-
-        // Extract the executable name from the path
-        let app_name = extract_app_name_from_path(&exe_path);
-
-        // Extract the application icon as base64 PNG
-        let icon_base64 = extract_app_icon_base64(&exe_path);
-
-        (app_name, icon_base64)
-    }
+    window_process(unsafe { GetClipboardOwner() })
 }
 
-/// Extract a friendly application name from an executable path
+/// Executable path of the clipboard owner, without resolving a name or icon
 #[cfg(target_os = "windows")]
-fn extract_app_name_from_path(exe_path: &str) -> Option<String> {
-    use std::path::Path;
-
-    let path = Path::new(exe_path);
-    let file_name = path.file_stem()?.to_str()?;
-
-    // Convert to friendly name
-    let friendly_name = match file_name.to_lowercase().as_str() {
-        "chrome" => "Chrome",
-        "firefox" => "Firefox",
-        "msedge" => "Edge",
-        "code" => "Visual Studio Code",
-        "notepad" => "Notepad",
-        "notepad++" => "Notepad++",
-        "explorer" => "Explorer",
-        "outlook" => "Outlook",
-        "excel" => "Excel",
-        "winword" => "Word",
-        "powerpnt" => "PowerPoint",
-        "teams" => "Teams",
-        "slack" => "Slack",
-        "discord" => "Discord",
-        "spotify" => "Spotify",
-        "terminal" => "Terminal",
-        "windowsterminal" => "Windows Terminal",
-        "powershell" => "PowerShell",
-        "cmd" => "Command Prompt",
-        _ => {
-            // Capitalize first letter for unknown apps
-            let mut chars = file_name.chars();
-            match chars.next() {
-                Some(first) => {
-                    let capitalized: String =
-                        first.to_uppercase().chain(chars).collect();
-                    return Some(capitalized);
-                }
-                None => return None,
-            }
-        }
-    };
-
-    Some(friendly_name.to_string())
+fn source_app_id() -> Option<String> {
+    get_clipboard_owner().map(|owner| owner.exe_path)
 }
 
-/// Extract application icon as base64-encoded PNG (32x32) from executable path
+/// Process handle, closed on drop
 #[cfg(target_os = "windows")]
-fn extract_app_icon_base64(exe_path: &str) -> Option<String> {
-    use base64::Engine;
-    use std::mem::MaybeUninit;
-    use windows::Win32::Foundation::HWND;
-    use windows::Win32::Graphics::Gdi::{
-        CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDIBits,
-        SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
-    };
-    use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
-    use windows::Win32::UI::WindowsAndMessaging::{
-        DestroyIcon, DrawIconEx, GetDC, ReleaseDC, DI_NORMAL,
-    };
+struct ProcessHandle(windows::Win32::Foundation::HANDLE);
 
-    unsafe {
-        // Convert path to wide string
-        let wide_path: Vec<u16> = exe_path.encode_utf16().chain(std::iter::once(0)).collect();
-
-        // Get the icon handle using SHGetFileInfoW
-        let mut file_info: SHFILEINFOW = std::mem::zeroed();
-        let result = SHGetFileInfoW(
-            windows::core::PCWSTR::from_raw(wide_path.as_ptr()),
-            windows::Win32::Storage::FileSystem::FILE_ATTRIBUTE_NORMAL,
-            Some(&mut file_info),
-            std::mem::size_of::<SHFILEINFOW>() as u32,
-            SHGFI_ICON | SHGFI_LARGEICON,
-        );
-
-        if result == 0 || file_info.hIcon.is_invalid() {
-            return None;
+#[cfg(target_os = "windows")]
+impl ProcessHandle {
+    fn open(
+        process_id: u32,
+        access: windows::Win32::System::Threading::PROCESS_ACCESS_RIGHTS,
+    ) -> Option<Self> {
+        use windows::Win32::System::Threading::OpenProcess;
+
+        match unsafe { OpenProcess(access, false, process_id) } {
+            Ok(handle) if !handle.is_invalid() => Some(Self(handle)),
+            _ => None,
         }
+    }
+
+    /// Executable path through the module list; needs PROCESS_VM_READ
+    fn module_file_name(&self) -> Option<String> {
+        use windows::Win32::System::ProcessStatus::GetModuleFileNameExW;
 
-        let hicon = file_info.hIcon;
+        let mut buffer = [0u16; 260]; // MAX_PATH
+        let len = unsafe { GetModuleFileNameExW(Some(self.0), None, &mut buffer) };
+        (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+    }
 
-        // Target size: 32x32 (matching macOS implementation)
-        let icon_size: i32 = 32;
+    /// Executable path with only PROCESS_QUERY_LIMITED_INFORMATION, which
+    /// packaged (MSIX) and elevated processes still grant
+    fn full_image_name(&self) -> Option<String> {
+        use windows::core::PWSTR;
+        use windows::Win32::System::Threading::{QueryFullProcessImageNameW, PROCESS_NAME_WIN32};
 
-        // Get device context for the screen
-        let screen_dc = GetDC(HWND::default());
-        if screen_dc.is_invalid() {
-            DestroyIcon(hicon).ok();
-            return None;
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as u32;
+        unsafe {
+            QueryFullProcessImageNameW(
+                self.0,
+                PROCESS_NAME_WIN32,
+                PWSTR(buffer.as_mut_ptr()),
+                &mut len,
+            )
         }
+        .ok()?;
+        (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+    }
 
-        // Create a compatible DC for drawing
-        let mem_dc = CreateCompatibleDC(Some(screen_dc));
-        if mem_dc.is_invalid() {
-            ReleaseDC(HWND::default(), screen_dc);
-            DestroyIcon(hicon).ok();
+    /// AppX package full name; None for unpackaged processes
+    fn package_full_name(&self) -> Option<String> {
+        use windows::core::PWSTR;
+        use windows::Win32::Foundation::ERROR_SUCCESS;
+        use windows::Win32::Storage::Packaging::Appx::GetPackageFullName;
+
+        // PACKAGE_FULL_NAME_MAX_LENGTH plus the terminating NUL
+        let mut buffer = [0u16; 128];
+        let mut len = buffer.len() as u32;
+        let result = unsafe { GetPackageFullName(self.0, &mut len, PWSTR(buffer.as_mut_ptr())) };
+        if result != ERROR_SUCCESS || len == 0 {
             return None;
         }
+        // The length counts the terminating NUL
+        let name = String::from_utf16_lossy(&buffer[..len as usize - 1]);
+        (!name.is_empty()).then_some(name)
+    }
+}
 
-        // Create a bitmap to draw into
-        let bitmap = CreateCompatibleBitmap(screen_dc, icon_size, icon_size);
-        if bitmap.is_invalid() {
-            DeleteDC(mem_dc).ok();
-            ReleaseDC(HWND::default(), screen_dc);
-            DestroyIcon(hicon).ok();
-            return None;
+#[cfg(target_os = "windows")]
+impl Drop for ProcessHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::Foundation::CloseHandle(self.0);
         }
+    }
+}
 
-        // Select the bitmap into the DC
-        let old_bitmap = SelectObject(mem_dc, bitmap);
-
-        // Draw the icon onto the bitmap
-        let draw_result = DrawIconEx(
-            mem_dc,
-            0,
-            0,
-            hicon,
-            icon_size,
-            icon_size,
-            0,
-            None,
-            DI_NORMAL,
-        );
+/// Process owning `hwnd`, with its executable path and package name
+/// The module list can't be read from packaged apps, so the path falls back
+/// to QueryFullProcessImageNameW with limited access
+#[cfg(target_os = "windows")]
+pub(crate) fn window_process(hwnd: windows::Win32::Foundation::HWND) -> Option<OwnerProcess> {
+    use windows::Win32::System::Threading::{
+        PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
 
-        if draw_result.is_err() {
-            SelectObject(mem_dc, old_bitmap);
-            DeleteObject(bitmap).ok();
-            DeleteDC(mem_dc).ok();
-            ReleaseDC(HWND::default(), screen_dc);
-            DestroyIcon(hicon).ok();
-            return None;
-        }
+    if hwnd.0.is_null() {
+        return None;
+    }
 
-        // Prepare BITMAPINFO for extracting pixels
-        let mut bmi: BITMAPINFO = std::mem::zeroed();
-        bmi.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
-        bmi.bmiHeader.biWidth = icon_size;
-        bmi.bmiHeader.biHeight = -icon_size; // Negative for top-down DIB
-        bmi.bmiHeader.biPlanes = 1;
-        bmi.bmiHeader.biBitCount = 32; // RGBA
-        bmi.bmiHeader.biCompression = BI_RGB.0;
-
-        // Allocate buffer for pixel data (BGRA format)
-        let buffer_size = (icon_size * icon_size * 4) as usize;
-        let mut pixels: Vec<u8> = vec![0; buffer_size];
-
-        // Get the bitmap bits
-        let scanlines = GetDIBits(
-            mem_dc,
-            bitmap,
-            0,
-            icon_size as u32,
-            Some(pixels.as_mut_ptr() as *mut _),
-            &mut bmi,
-            DIB_RGB_COLORS,
-        );
+    // Get the process ID from the window handle
+    let mut pid: u32 = 0;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+    if pid == 0 {
+        return None;
+    }
 
-        // Clean up GDI objects
-        SelectObject(mem_dc, old_bitmap);
-        DeleteObject(bitmap).ok();
-        DeleteDC(mem_dc).ok();
-        ReleaseDC(HWND::default(), screen_dc);
-        DestroyIcon(hicon).ok();
+    let exe_path = ProcessHandle::open(pid, PROCESS_QUERY_INFORMATION | PROCESS_VM_READ)
+        .and_then(|process| process.module_file_name());
+    let limited = ProcessHandle::open(pid, PROCESS_QUERY_LIMITED_INFORMATION)?;
+    let exe_path = exe_path.or_else(|| limited.full_image_name())?;
 
-        if scanlines == 0 {
-            return None;
-        }
+    Some(OwnerProcess {
+        pid,
+        exe_path,
+        package_full_name: limited.package_full_name(),
+    })
+}
 
-        // Convert BGRA to RGBA
-        for i in (0..buffer_size).step_by(4) {
-            pixels.swap(i, i + 2); // Swap B and R
-        }
-
-        // Create PNG using the image crate
-        let img_buffer = image::ImageBuffer::<image::Rgba<u8>, Vec<u8>>::from_raw(
-            icon_size as u32,
-            icon_size as u32,
-            pixels,
-        )?;
-
-        // Encode as PNG
-        let mut png_data: Vec<u8> = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut png_data);
-        let encoder = image::codecs::png::PngEncoder::new(&mut cursor);
-        encoder
-            .encode(
-                img_buffer.as_raw(),
-                icon_size as u32,
-                icon_size as u32,
-                image::ExtendedColorType::Rgba8,
-            )
-            .ok()?;
+/// Extract application icon as base64-encoded PNG (32x32) from executable path
+/// Runs on the icon worker, off the clipboard callback
+#[cfg(target_os = "windows")]
+fn extract_app_icon_base64(exe_path: &str) -> Option<String> {
+    use crate::gdi;
 
-        // Encode as base64
-        let base64_str = base64::engine::general_purpose::STANDARD.encode(&png_data);
-        Some(base64_str)
-    }
+    let icon = gdi::Icon::for_path(std::ffi::OsStr::new(exe_path))?;
+    // Target size: 32x32 (matching macOS implementation)
+    let pixels = gdi::draw_icon(&icon, 32)?;
+    let png_data = file_storage::encode_png(&image::DynamicImage::ImageRgba8(pixels)).ok()?;
+    Some(file_storage::thumbnail_to_base64(&png_data))
 }
 
 // ============================================================================
@@ -873,12 +2126,13 @@ mod platform {
             if SHOULD_STOP.load(Ordering::SeqCst) {
                 return CallbackResult::Stop;
             }
+            record_heartbeat();
             self.process_clipboard_change();
             CallbackResult::Next
         }
 
         fn on_clipboard_error(&mut self, error: std::io::Error) -> CallbackResult {
-            eprintln!("Clipboard monitor error: {}", error);
+            clipster_log!(Warn, "Clipboard monitor error: {}", error);
             if SHOULD_STOP.load(Ordering::SeqCst) {
                 return CallbackResult::Stop;
             }
@@ -889,13 +2143,15 @@ mod platform {
     pub fn start_monitoring_impl(
         app_handle: AppHandle,
         db: Arc<Database>,
+        file_storage: Arc<FileStorage>,
     ) -> Result<JoinHandle<()>, String> {
         let handle = thread::spawn(move || {
-            let handler = ClipboardMonitorHandler::new(app_handle, db);
+            let handler = ClipboardMonitorHandler::new(app_handle, db, file_storage);
             let mut master = Master::new(handler);
+            record_heartbeat();
 
             if let Err(e) = master.run() {
-                eprintln!("Clipboard monitor stopped with error: {}", e);
+                clipster_log!(Warn, "Clipboard monitor stopped with error: {}", e);
             }
         });
 
@@ -909,40 +2165,141 @@ mod platform {
 #[cfg(target_os = "macos")]
 mod platform {
     use super::*;
+    use crate::clipboard::poll_scheduler::{ActivitySignals, PollScheduler};
+    use crate::clipboard::read_retry::{self, Pasteboard, ReadOutcome};
+    use std::ptr::NonNull;
+    use std::sync::Once;
     use std::time::Duration;
 
+    /// The general pasteboard read by the monitor
+    struct GeneralPasteboard {
+        /// See `payload_limit`
+        max_image_bytes: u64,
+    }
+
+    impl Pasteboard for GeneralPasteboard {
+        type Content = ClipboardContent;
+
+        fn change_count(&mut self) -> isize {
+            clipboard_reader::get_change_count()
+        }
+
+        fn has_content(&mut self) -> bool {
+            clipboard_reader::pasteboard_has_content()
+        }
+
+        fn read(&mut self) -> Option<ClipboardContent> {
+            match clipboard_reader::read_clipboard(self.max_image_bytes) {
+                ClipboardContent::Empty => None,
+                content => Some(content),
+            }
+        }
+    }
+
+    /// App activations seen by the workspace observer
+    static ACTIVATION_SEQ: AtomicU64 = AtomicU64::new(0);
+    static OBSERVER: Once = Once::new();
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state_id: i32, event_type: u32) -> f64;
+    }
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+    /// kCGAnyInputEventType
+    const ANY_INPUT_EVENT: u32 = !0;
+
+    /// Count app switches via NSWorkspaceDidActivateApplicationNotification
+    /// Registered once per process; the block runs on the main thread
+    fn register_activation_observer() {
+        use block2::RcBlock;
+        use objc2_app_kit::{NSWorkspace, NSWorkspaceDidActivateApplicationNotification};
+        use objc2_foundation::NSNotification;
+
+        OBSERVER.call_once(|| unsafe {
+            let block = RcBlock::new(|_: NonNull<NSNotification>| {
+                ACTIVATION_SEQ.fetch_add(1, Ordering::SeqCst);
+            });
+            let observer = NSWorkspace::sharedWorkspace()
+                .notificationCenter()
+                .addObserverForName_object_queue_usingBlock(
+                    Some(NSWorkspaceDidActivateApplicationNotification),
+                    None,
+                    None,
+                    &block,
+                );
+            // Observes for the lifetime of the app
+            std::mem::forget(observer);
+        });
+    }
+
+    fn activity_signals() -> ActivitySignals {
+        let idle_secs = unsafe {
+            CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT)
+        };
+        ActivitySignals {
+            activation_seq: ACTIVATION_SEQ.load(Ordering::SeqCst),
+            panel_visible: PANEL_VISIBLE.load(Ordering::SeqCst),
+            idle_for: (idle_secs.is_finite() && idle_secs >= 0.0)
+                .then(|| Duration::from_secs_f64(idle_secs)),
+        }
+    }
+
     pub fn start_monitoring_impl(
         app_handle: AppHandle,
         db: Arc<Database>,
+        file_storage: Arc<FileStorage>,
     ) -> Result<JoinHandle<()>, String> {
+        register_activation_observer();
+
         let handle = thread::spawn(move || {
-            let handler = ClipboardMonitorHandler::new(app_handle, db);
+            let handler = ClipboardMonitorHandler::new(app_handle, db, file_storage);
 
             // Use pasteboard changeCount for reliable change detection
             // This increments every time the clipboard changes, even for same content
             let mut last_change_count = clipboard_reader::get_change_count();
+            // The find pasteboard (Cmd+F text) is checked on the same ticks
+            let mut last_find_change_count = clipboard_reader::get_find_change_count();
 
             // Poll for clipboard changes using changeCount
-            // 100ms polling for responsive UX
+            // 50ms after app switches and copies or with the panel open, 1s when idle
+            let mut scheduler = PollScheduler::new(ACTIVATION_SEQ.load(Ordering::SeqCst));
             while !SHOULD_STOP.load(Ordering::SeqCst) {
-                thread::sleep(Duration::from_millis(100));
+                thread::sleep(scheduler.next_interval(Instant::now(), activity_signals()));
+                record_heartbeat();
 
                 let current_change_count = clipboard_reader::get_change_count();
                 if current_change_count != last_change_count {
-                    last_change_count = current_change_count;
-
-                    // Small delay to let the source app finish writing to clipboard
-                    // Some apps write to clipboard asynchronously
-                    thread::sleep(Duration::from_millis(50));
-
-                    // Check if pasteboard actually has content before processing
-                    // This filters out clipboard clears and transient states
-                    if clipboard_reader::pasteboard_has_content() {
-                        handler.process_clipboard_change();
-                    } else {
-                        eprintln!("[ClipboardMonitor] changeCount {} but pasteboard is empty - skipping (likely a clear or transient state)", current_change_count);
+                    scheduler.on_change(Instant::now());
+
+                    // The source app may still be writing: read until changeCount
+                    // holds still across a read (see read_retry)
+                    let (outcome, settled_count) = read_retry::read_verified(
+                        &mut GeneralPasteboard {
+                            max_image_bytes: handler.max_image_capture_bytes(),
+                        },
+                        current_change_count,
+                        thread::sleep,
+                    );
+                    last_change_count = settled_count;
+                    match outcome {
+                        ReadOutcome::Read(Some(content)) => handler.process_content(content),
+                        ReadOutcome::Read(None) => {
+                            clipster_log!(Info, "[ClipboardMonitor] changeCount {} but pasteboard is empty - skipping (likely a clear)", settled_count);
+                        }
+                        ReadOutcome::GaveUp => handler.record_failure(
+                            CaptureStage::Read,
+                            FailureCode::StillWriting,
+                            None,
+                        ),
                     }
                 }
+
+                let current_find_change_count = clipboard_reader::get_find_change_count();
+                if current_find_change_count != last_find_change_count {
+                    last_find_change_count = current_find_change_count;
+                    handler.process_find_pasteboard_change();
+                }
             }
         });
 
@@ -955,7 +2312,11 @@ mod platform {
 // ============================================================================
 
 /// Start the clipboard monitor
-pub fn start_monitoring(app_handle: AppHandle, db: Arc<Database>) -> Result<(), String> {
+pub fn start_monitoring(
+    app_handle: AppHandle,
+    db: Arc<Database>,
+    file_storage: Arc<FileStorage>,
+) -> Result<(), String> {
     let monitor_mutex = MONITOR_HANDLE.get_or_init(|| Mutex::new(None));
 
     let mut guard = monitor_mutex
@@ -968,7 +2329,7 @@ pub fn start_monitoring(app_handle: AppHandle, db: Arc<Database>) -> Result<(),
 
     SHOULD_STOP.store(false, Ordering::SeqCst);
 
-    let handle = platform::start_monitoring_impl(app_handle, db)?;
+    let handle = platform::start_monitoring_impl(app_handle, db, file_storage)?;
     *guard = Some(handle);
 
     Ok(())
@@ -987,6 +2348,80 @@ pub fn stop_monitoring() {
     }
 }
 
+/// Milliseconds since the monitor last reported in (None if it never has)
+/// On Windows the monitor is event-driven, so this is the time since the last
+/// clipboard event
+pub fn heartbeat_age_ms() -> Option<u64> {
+    let last = LAST_HEARTBEAT_MS.load(Ordering::SeqCst);
+    if last == 0 {
+        return None;
+    }
+    Some((chrono::Utc::now().timestamp_millis() - last).max(0) as u64)
+}
+
+/// Current monitor state, including captures dropped by rate limiting
+pub fn monitoring_status() -> MonitoringStatus {
+    let (dropped_captures, total_dropped) = RATE_LIMITER
+        .get()
+        .and_then(|limiter| limiter.lock().ok().map(|l| (l.dropped().clone(), l.total_dropped())))
+        .unwrap_or_default();
+
+    let (capture_failures, total_capture_failures) = CAPTURE_FAILURES
+        .lock()
+        .map(|log| (log.counts().clone(), log.total()))
+        .unwrap_or_default();
+
+    MonitoringStatus {
+        running: is_monitoring(),
+        heartbeat_age_ms: heartbeat_age_ms(),
+        dropped_captures,
+        total_dropped,
+        next_auto_clear: None,
+        storage_degraded: STORAGE_RELOCATED.load(Ordering::SeqCst)
+            || STORAGE_GUARD
+                .lock()
+                .map(|guard| guard.is_degraded())
+                .unwrap_or(false),
+        capture_failures,
+        total_capture_failures,
+        source_app_cache: SOURCE_APP_CACHE
+            .get()
+            .and_then(|cache| cache.lock().ok().map(|cache| cache.stats()))
+            .unwrap_or_default(),
+        queued_events: events::queued_events(),
+        dropped_events: events::dropped_events(),
+        startup_phases: Vec::new(),
+    }
+}
+
+/// Images saved to the fallback directory were moved back by `repair_storage`
+pub fn storage_repaired() {
+    STORAGE_RELOCATED.store(false, Ordering::SeqCst);
+}
+
+/// Recent captures that could not be saved, newest first
+pub fn capture_failures() -> Vec<CaptureFailure> {
+    CAPTURE_FAILURES
+        .lock()
+        .map(|log| log.recent())
+        .unwrap_or_default()
+}
+
+/// Capture the next clipboard change whatever the app rate limits say, if it
+/// comes within `OVERRIDE_TTL`; returns when the override expires
+pub fn allow_next_capture() -> chrono::DateTime<chrono::Utc> {
+    if let Ok(mut capture_override) = CAPTURE_OVERRIDE.lock() {
+        capture_override.arm(Instant::now());
+    }
+    clipster_log!(Info, "[clipboard_monitor] Next capture allowed");
+    chrono::Utc::now() + OVERRIDE_TTL
+}
+
+/// Record whether the panel is on screen
+pub fn set_panel_visible(visible: bool) {
+    PANEL_VISIBLE.store(visible, Ordering::SeqCst);
+}
+
 /// Check if monitor is running
 pub fn is_monitoring() -> bool {
     if let Some(monitor_mutex) = MONITOR_HANDLE.get() {
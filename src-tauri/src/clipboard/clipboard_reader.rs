@@ -3,7 +3,17 @@
 //! Provides unified API for reading clipboard content on Windows and macOS.
 //! Supports: Text, Images, and Files
 
-use crate::models::ContentType;
+use crate::clipboard::contact_event;
+use crate::clipboard::copy_back::CopyContent;
+use crate::clipboard::payload_limit::{self, TooLarge};
+use crate::error::{ClipsterError, Context};
+use crate::models::{ContentSubtype, ContentType};
+use crate::storage::file_storage;
+
+/// Map a clipboard API error to `ClipboardAccess`, prefixed with what failed
+fn access_error<E: std::fmt::Display>(what: &'static str) -> impl Fn(E) -> ClipsterError {
+    move |e| ClipsterError::ClipboardAccess(format!("{}: {}", what, e))
+}
 
 /// Result of reading clipboard content
 #[derive(Debug)]
@@ -11,18 +21,30 @@ pub enum ClipboardContent {
     Text(String),
     Image(ImageData),
     Files(Vec<String>),
+    /// The clipboard announced content that could not be read
+    Unreadable {
+        content_type: ContentType,
+        /// Another app held the clipboard open
+        locked: bool,
+    },
+    /// An image over the size limit, skipped before it was copied
+    TooLarge(TooLarge),
     Empty,
 }
 
 /// Image data from clipboard
 #[derive(Debug)]
 pub struct ImageData {
-    /// PNG-encoded image data
+    /// PNG-encoded image data (the original bytes when `format` is not PNG)
     pub png_data: Vec<u8>,
+    /// PNG, or TIFF for a pasteboard TIFF that could only be kept as-is
+    pub format: image::ImageFormat,
     /// Width in pixels
     pub width: u32,
     /// Height in pixels
     pub height: u32,
+    /// Put on the pasteboard by the macOS screenshot tool, see `screenshot`
+    pub is_screenshot: bool,
 }
 
 // ============================================================================
@@ -31,12 +53,16 @@ pub struct ImageData {
 #[cfg(target_os = "windows")]
 mod platform {
     use super::*;
+    use crate::clipboard::{dib, os_history};
     use clipboard_win::{formats, get_clipboard, is_format_avail, raw::is_format_avail as is_raw_avail};
+    use windows::core::PCWSTR;
     use windows::Win32::Foundation::{HANDLE, HGLOBAL, HWND};
     use windows::Win32::System::DataExchange::{
-        CloseClipboard, GetClipboardData, OpenClipboard,
+        CloseClipboard, GetClipboardData, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{
+        GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
     };
-    use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
 
     /// Windows clipboard format constants
     pub mod clipboard_formats {
@@ -71,42 +97,42 @@ mod platform {
     }
 
     /// Read image data from clipboard (CF_DIB)
-    pub fn read_image() -> Option<ImageData> {
-        if let Some(data) = read_dib_format(clipboard_formats::CF_DIB) {
-            return Some(data);
-        }
-        if let Some(data) = read_dib_format(clipboard_formats::CF_DIBV5) {
-            return Some(data);
+    /// Err when the DIB is over `max_bytes` (CF_DIBV5 is the same image)
+    pub fn read_image(max_bytes: u64) -> Result<Option<ImageData>, TooLarge> {
+        if let Some(data) = read_dib_format(clipboard_formats::CF_DIB, max_bytes)? {
+            return Ok(Some(data));
         }
-        None
+        read_dib_format(clipboard_formats::CF_DIBV5, max_bytes)
     }
 
     /// Read DIB data and convert to PNG
-    fn read_dib_format(format: u32) -> Option<ImageData> {
+    /// The size is checked against `max_bytes` before the data is copied
+    fn read_dib_format(format: u32, max_bytes: u64) -> Result<Option<ImageData>, TooLarge> {
         unsafe {
             if OpenClipboard(HWND::default()).is_err() {
-                return None;
+                return Ok(None);
             }
 
-            let result = (|| {
+            let result = (|| -> Result<Option<ImageData>, TooLarge> {
                 let handle: HANDLE = match GetClipboardData(format) {
                     Ok(h) => h,
-                    Err(_) => return None,
+                    Err(_) => return Ok(None),
                 };
 
                 if handle.0.is_null() {
-                    return None;
+                    return Ok(None);
                 }
 
                 let hglobal = HGLOBAL(handle.0);
                 let size = GlobalSize(hglobal);
                 if size == 0 {
-                    return None;
+                    return Ok(None);
                 }
+                payload_limit::check(size as u64, max_bytes)?;
 
                 let ptr = GlobalLock(hglobal);
                 if ptr.is_null() {
-                    return None;
+                    return Ok(None);
                 }
 
                 let data = std::slice::from_raw_parts(ptr as *const u8, size);
@@ -114,7 +140,7 @@ mod platform {
                 let _ = GlobalUnlock(hglobal);
 
                 if dib_data.len() < 12 {
-                    return None;
+                    return Ok(None);
                 }
 
                 let width = u32::from_le_bytes([dib_data[4], dib_data[5], dib_data[6], dib_data[7]]);
@@ -122,13 +148,13 @@ mod platform {
                 let height = height_raw.unsigned_abs();
 
                 // Convert DIB to PNG
-                let png_data = dib_to_png(&dib_data)?;
-
-                Some(ImageData {
+                Ok(dib_to_png(&dib_data).map(|png_data| ImageData {
                     png_data,
+                    format: image::ImageFormat::Png,
                     width,
                     height,
-                })
+                    is_screenshot: false,
+                }))
             })();
 
             let _ = CloseClipboard();
@@ -138,29 +164,10 @@ mod platform {
 
     /// Convert DIB data to PNG format
     fn dib_to_png(dib_data: &[u8]) -> Option<Vec<u8>> {
-        // Create BMP from DIB
-        let file_header_size = 14;
-        let file_size = file_header_size + dib_data.len();
-        let mut bmp = Vec::with_capacity(file_size);
-
-        bmp.extend_from_slice(b"BM");
-        bmp.extend_from_slice(&(file_size as u32).to_le_bytes());
-        bmp.extend_from_slice(&[0u8; 4]);
-
-        let dib_header_size = if dib_data.len() >= 4 {
-            u32::from_le_bytes([dib_data[0], dib_data[1], dib_data[2], dib_data[3]])
-        } else {
-            40
-        };
-        let pixel_offset = file_header_size as u32 + dib_header_size;
-        bmp.extend_from_slice(&pixel_offset.to_le_bytes());
-        bmp.extend_from_slice(dib_data);
-
-        // Decode BMP and encode as PNG
+        // Decode as a BMP file and encode as PNG
+        let bmp = dib::bmp_file(dib_data);
         let img = image::load_from_memory_with_format(&bmp, image::ImageFormat::Bmp).ok()?;
-        let mut png_bytes = Vec::new();
-        img.write_to(&mut std::io::Cursor::new(&mut png_bytes), image::ImageFormat::Png).ok()?;
-        Some(png_bytes)
+        file_storage::encode_png(&img).ok()
     }
 
     /// Read file list from clipboard
@@ -172,115 +179,200 @@ mod platform {
     }
 
     /// Read clipboard content based on detected format
-    pub fn read_clipboard() -> ClipboardContent {
+    /// Images over `max_image_bytes` are reported as `TooLarge` without being read
+    pub fn read_clipboard(max_image_bytes: u64) -> ClipboardContent {
         let content_type = detect_format();
 
         match content_type {
-            ContentType::Text => {
-                if let Some(text) = read_text() {
-                    ClipboardContent::Text(text)
-                } else {
-                    ClipboardContent::Empty
-                }
-            }
-            ContentType::Image => {
-                if let Some(image) = read_image() {
-                    ClipboardContent::Image(image)
-                } else {
-                    ClipboardContent::Empty
-                }
-            }
+            ContentType::Text => match get_clipboard::<String, _>(formats::Unicode) {
+                Ok(text) if !text.is_empty() => ClipboardContent::Text(text),
+                Err(_) if is_format_avail(formats::CF_UNICODETEXT) => unreadable(ContentType::Text),
+                _ => ClipboardContent::Empty,
+            },
+            ContentType::Image => match read_image(max_image_bytes) {
+                Ok(Some(image)) => ClipboardContent::Image(image),
+                Ok(None) => unreadable(ContentType::Image),
+                Err(too_large) => ClipboardContent::TooLarge(too_large),
+            },
             ContentType::Files => {
                 if let Some(files) = read_files() {
                     ClipboardContent::Files(files)
                 } else {
-                    ClipboardContent::Empty
+                    unreadable(ContentType::Files)
                 }
             }
         }
     }
 
+    /// Announced content that failed to read; locked if the clipboard can't be opened
+    fn unreadable(content_type: ContentType) -> ClipboardContent {
+        ClipboardContent::Unreadable {
+            content_type,
+            locked: clipboard_win::Clipboard::new().is_err(),
+        }
+    }
+
     /// Get clipboard text (simple API)
-    pub fn get_clipboard_text() -> Result<String, String> {
-        read_text().ok_or_else(|| "No text in clipboard".to_string())
+    pub fn get_clipboard_text() -> Result<String, ClipsterError> {
+        read_text().ok_or_else(|| ClipsterError::NotFound("No text in clipboard".to_string()))
     }
 
     /// Set clipboard text
-    pub fn set_clipboard_text(text: &str) -> Result<(), String> {
-        clipboard_win::set_clipboard(formats::Unicode, text)
-            .map_err(|e| format!("Failed to set clipboard: {}", e))
+    /// With `exclude_from_os_history` it stays out of the Win+V history and cloud sync
+    pub fn set_clipboard_text(
+        text: &str,
+        exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let bytes: Vec<u8> = text
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        write_clipboard(
+            &[(clipboard_formats::CF_UNICODETEXT, &bytes)],
+            exclude_from_os_history,
+        )
+    }
+
+    /// Set a contact or event payload as text and as its registered MIME format
+    /// (UTF-8), which calendar and address book apps paste from
+    pub fn set_clipboard_structured(
+        text: &str,
+        subtype: ContentSubtype,
+        exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let name = os_history::wide_name(contact_event::mime_type(subtype));
+        let format = unsafe { RegisterClipboardFormatW(PCWSTR(name.as_ptr())) };
+        if format == 0 {
+            return Err(ClipsterError::ClipboardAccess(format!(
+                "Failed to register clipboard format {}",
+                contact_event::mime_type(subtype)
+            )));
+        }
+        let unicode: Vec<u8> = text
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        write_clipboard(
+            &[
+                (clipboard_formats::CF_UNICODETEXT, &unicode),
+                (format, text.as_bytes()),
+            ],
+            exclude_from_os_history,
+        )
     }
 
     /// Set clipboard image from a file path
-    pub fn set_clipboard_image(image_path: &str) -> Result<(), String> {
-        let img = image::open(image_path)
-            .map_err(|e| format!("Failed to open image: {}", e))?;
+    /// Placed as CF_DIBV5 (with alpha) and CF_DIB, the formats apps paste from;
+    /// both hold pixels only, so there is no metadata to strip
+    pub fn set_clipboard_image(
+        image_path: &str,
+        _strip_metadata: bool,
+        exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let img = image::open(image_path).context("Failed to open image")?;
         let rgba = img.to_rgba8();
-        let (w, h) = rgba.dimensions();
-        let mut clipboard = arboard::Clipboard::new()
-            .map_err(|e| format!("Failed to access clipboard: {}", e))?;
-        clipboard
-            .set_image(arboard::ImageData {
-                width: w as usize,
-                height: h as usize,
-                bytes: std::borrow::Cow::Owned(rgba.into_raw()),
+        let dibv5 = dib::encode_dibv5(&rgba);
+        let dib = dib::encode_dib(&rgba);
+
+        write_clipboard(
+            &[
+                (clipboard_formats::CF_DIBV5, &dibv5),
+                (clipboard_formats::CF_DIB, &dib),
+            ],
+            exclude_from_os_history,
+        )
+    }
+
+    /// Replace the clipboard contents with `contents` (format ID and data)
+    /// With `exclude_from_os_history` the history exclusion formats are added
+    fn write_clipboard(
+        contents: &[(u32, &[u8])],
+        exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let exclusions = if exclude_from_os_history {
+            os_history::register_exclusion_formats(|name| unsafe {
+                RegisterClipboardFormatW(PCWSTR(name.as_ptr()))
             })
-            .map_err(|e| format!("Failed to set clipboard image: {}", e))
+            .map_err(ClipsterError::ClipboardAccess)?
+        } else {
+            Vec::new()
+        };
+
+        unsafe {
+            clipboard_win::raw::open().map_err(access_error("Failed to open clipboard"))?;
+            let _ = clipboard_win::raw::empty();
+            let result = contents
+                .iter()
+                .copied()
+                .chain(exclusions.iter().map(|(format, data)| (*format, &data[..])))
+                .try_for_each(|(format, bytes)| set_global_data(format, bytes));
+            clipboard_win::raw::close();
+            result
+        }
+    }
+
+    /// Copy `bytes` into a global allocation and place it on the open clipboard
+    /// The clipboard owns the allocation once placed
+    unsafe fn set_global_data(format: u32, bytes: &[u8]) -> Result<(), ClipsterError> {
+        let hmem =
+            GlobalAlloc(GMEM_MOVEABLE, bytes.len()).map_err(access_error("GlobalAlloc failed"))?;
+
+        let ptr = GlobalLock(hmem);
+        if ptr.is_null() {
+            let _ = GlobalFree(hmem);
+            return Err(ClipsterError::ClipboardAccess(
+                "GlobalLock failed".to_string(),
+            ));
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
+        let _ = GlobalUnlock(hmem);
+
+        if let Err(e) = SetClipboardData(format, HANDLE(hmem.0)) {
+            let _ = GlobalFree(hmem);
+            return Err(access_error("SetClipboardData failed")(e));
+        }
+        Ok(())
     }
 
     /// Set clipboard to file paths
-    pub fn set_clipboard_files(file_paths: &[String]) -> Result<(), String> {
-        // On Windows, use CF_HDROP via clipboard-win
-        use clipboard_win::raw;
+    pub fn set_clipboard_files(
+        file_paths: &[String],
+        exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        // On Windows, use CF_HDROP
         use std::ffi::OsStr;
         use std::os::windows::ffi::OsStrExt;
 
-        unsafe {
-            raw::open().map_err(|e| format!("Failed to open clipboard: {}", e))?;
-            let _ = raw::empty();
-
-            // CF_HDROP format: DROPFILES header + null-terminated wide strings + double null
-            let mut data: Vec<u16> = Vec::new();
-            // DROPFILES struct: 20 bytes header
-            // offset to file list (20), point (0,0), fNC (0), fWide (1)
-            let header: [u8; 20] = [
-                20, 0, 0, 0, // pFiles offset
-                0, 0, 0, 0,  // pt.x
-                0, 0, 0, 0,  // pt.y
-                0, 0, 0, 0,  // fNC
-                1, 0, 0, 0,  // fWide (Unicode)
-            ];
-
-            let mut bytes: Vec<u8> = header.to_vec();
-            for path in file_paths {
-                let wide: Vec<u16> = OsStr::new(path).encode_wide().chain(std::iter::once(0)).collect();
-                for w in &wide {
-                    bytes.push(*w as u8);
-                    bytes.push((*w >> 8) as u8);
-                }
-            }
-            // Double null terminator
-            bytes.push(0);
-            bytes.push(0);
-
-            let hmem = windows::Win32::System::Memory::GlobalAlloc(
-                windows::Win32::System::Memory::GMEM_MOVEABLE,
-                bytes.len(),
-            ).map_err(|e| format!("GlobalAlloc failed: {}", e))?;
-
-            let ptr = windows::Win32::System::Memory::GlobalLock(hmem);
-            if !ptr.is_null() {
-                std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr as *mut u8, bytes.len());
-                windows::Win32::System::Memory::GlobalUnlock(hmem);
+        // CF_HDROP format: DROPFILES header + null-terminated wide strings + double null
+        let mut data: Vec<u16> = Vec::new();
+        // DROPFILES struct: 20 bytes header
+        // offset to file list (20), point (0,0), fNC (0), fWide (1)
+        let header: [u8; 20] = [
+            20, 0, 0, 0, // pFiles offset
+            0, 0, 0, 0,  // pt.x
+            0, 0, 0, 0,  // pt.y
+            0, 0, 0, 0,  // fNC
+            1, 0, 0, 0,  // fWide (Unicode)
+        ];
+
+        let mut bytes: Vec<u8> = header.to_vec();
+        for path in file_paths {
+            let wide: Vec<u16> = OsStr::new(path).encode_wide().chain(std::iter::once(0)).collect();
+            for w in &wide {
+                bytes.push(*w as u8);
+                bytes.push((*w >> 8) as u8);
             }
-
-            // CF_HDROP = 15
-            windows::Win32::System::DataExchange::SetClipboardData(15, windows::Win32::Foundation::HANDLE(hmem.0))
-                .map_err(|e| format!("SetClipboardData failed: {}", e))?;
-
-            raw::close();
         }
-        Ok(())
+        // Double null terminator
+        bytes.push(0);
+        bytes.push(0);
+
+        write_clipboard(
+            &[(clipboard_formats::CF_HDROP, &bytes)],
+            exclude_from_os_history,
+        )
     }
 }
 
@@ -290,9 +382,11 @@ mod platform {
 #[cfg(target_os = "macos")]
 mod platform {
     use super::*;
+    use crate::clipboard::screenshot;
+    use crate::preview;
     use arboard::Clipboard;
-    use objc2_app_kit::NSPasteboard;
-    use objc2_foundation::{NSString, NSURL};
+    use objc2_app_kit::{NSPasteboard, NSPasteboardNameFind, NSPasteboardTypeString};
+    use objc2_foundation::{NSData, NSString, NSURL};
 
     /// Get the pasteboard change count (increments on every clipboard change)
     /// This is the most reliable way to detect clipboard changes on macOS
@@ -303,6 +397,25 @@ mod platform {
         }
     }
 
+    /// Get the find pasteboard's change count (Cmd+F text shared between apps)
+    pub fn get_find_change_count() -> isize {
+        unsafe {
+            let pasteboard = NSPasteboard::pasteboardWithName(NSPasteboardNameFind);
+            pasteboard.changeCount()
+        }
+    }
+
+    /// Read the search string from the find pasteboard
+    pub fn read_find_text() -> Option<String> {
+        unsafe {
+            let pasteboard = NSPasteboard::pasteboardWithName(NSPasteboardNameFind);
+            pasteboard
+                .stringForType(NSPasteboardTypeString)
+                .map(|text| text.to_string())
+                .filter(|text| !text.is_empty())
+        }
+    }
+
     /// Check if the pasteboard has any content (types > 0)
     /// Used to filter out clipboard clears and transient states
     pub fn pasteboard_has_content() -> bool {
@@ -322,24 +435,24 @@ mod platform {
         unsafe {
             let pasteboard = NSPasteboard::generalPasteboard();
             let change_count = pasteboard.changeCount();
-            eprintln!("│   [PASTEBOARD changeCount: {}]", change_count);
+            clipster_log!(Debug, "│   [PASTEBOARD changeCount: {}]", change_count);
 
             let types = pasteboard.types();
 
-            eprintln!("│   [PASTEBOARD TYPES AVAILABLE]:");
+            clipster_log!(Debug, "│   [PASTEBOARD TYPES AVAILABLE]:");
             if let Some(types) = types {
                 let count = types.count();
-                eprintln!("│     (count: {})", count);
+                clipster_log!(Debug, "│     (count: {})", count);
                 for i in 0..count {
                     let t: &NSString = &types.objectAtIndex(i);
-                    eprintln!("│     - {}", t.to_string());
+                    clipster_log!(Debug, "│     - {}", t.to_string());
                 }
             } else {
-                eprintln!("│     (none - types() returned None)");
+                clipster_log!(Debug, "│     (none - types() returned None)");
             }
 
             // Also check specific common types directly
-            eprintln!("│   [DIRECT TYPE CHECKS]:");
+            clipster_log!(Debug, "│   [DIRECT TYPE CHECKS]:");
             let check_types = [
                 "public.utf8-plain-text",
                 "public.tiff",
@@ -353,11 +466,24 @@ mod platform {
                 let data = pasteboard.dataForType(&ns_type);
                 let has_data = data.is_some();
                 let data_len = data.map(|d| d.len()).unwrap_or(0);
-                eprintln!("│     {} : {} ({} bytes)", type_str, if has_data { "YES" } else { "NO" }, data_len);
+                clipster_log!(Debug, "│     {} : {} ({} bytes)", type_str, if has_data { "YES" } else { "NO" }, data_len);
             }
         }
     }
 
+    /// Types currently on the general pasteboard
+    fn pasteboard_types() -> Vec<String> {
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            let Some(types) = pasteboard.types() else {
+                return Vec::new();
+            };
+            (0..types.count())
+                .map(|i| types.objectAtIndex(i).to_string())
+                .collect()
+        }
+    }
+
     /// Check if files are available on the pasteboard
     fn has_files_on_pasteboard() -> bool {
         unsafe {
@@ -406,25 +532,25 @@ mod platform {
 
     /// Read text from clipboard
     pub fn read_text() -> Option<String> {
-        eprintln!("[DEBUG read_text] Attempting to read text...");
+        clipster_log!(Debug, "[DEBUG read_text] Attempting to read text...");
         let mut clipboard = match Clipboard::new() {
             Ok(c) => c,
             Err(e) => {
-                eprintln!("[DEBUG read_text]   Failed to create clipboard: {:?}", e);
+                clipster_log!(Warn, "[DEBUG read_text]   Failed to create clipboard: {:?}", e);
                 return None;
             }
         };
         match clipboard.get_text() {
             Ok(text) if !text.is_empty() => {
-                eprintln!("[DEBUG read_text]   Got text: {} chars", text.len());
+                clipster_log!(Debug, "[DEBUG read_text]   Got text: {} chars", text.len());
                 Some(text)
             }
             Ok(_) => {
-                eprintln!("[DEBUG read_text]   Got empty text");
+                clipster_log!(Debug, "[DEBUG read_text]   Got empty text");
                 None
             }
             Err(e) => {
-                eprintln!("[DEBUG read_text]   Error: {:?}", e);
+                clipster_log!(Warn, "[DEBUG read_text]   Error: {:?}", e);
                 None
             }
         }
@@ -435,26 +561,56 @@ mod platform {
     /// 1. arboard (cross-platform, handles most cases)
     /// 2. Native NSPasteboard TIFF data (for apps that only provide TIFF)
     /// 3. Native NSPasteboard PNG data (for PNG-specific sources)
-    pub fn read_image() -> Option<ImageData> {
-        eprintln!("[DEBUG read_image] Attempting to read image from clipboard...");
+    /// 4. The TIFF bytes as-is, so an unconvertible TIFF is still captured
+    ///
+    /// Err when the pasteboard image is over `max_bytes`
+    pub fn read_image(max_bytes: u64) -> Result<Option<ImageData>, TooLarge> {
+        clipster_log!(Debug, "[DEBUG read_image] Attempting to read image from clipboard...");
+
+        // Checked once up front: every method below copies or decodes the data
+        check_image_size(max_bytes)?;
 
         // Method 1: Try arboard first (handles most cases)
         if let Some(img_data) = read_image_arboard() {
-            return Some(img_data);
+            return Ok(Some(img_data));
         }
 
         // Method 2: Try native NSPasteboard for TIFF data
         if let Some(img_data) = read_image_native_tiff() {
-            return Some(img_data);
+            return Ok(Some(img_data));
         }
 
         // Method 3: Try native NSPasteboard for PNG data
         if let Some(img_data) = read_image_native_png() {
-            return Some(img_data);
+            return Ok(Some(img_data));
+        }
+
+        // Method 4: Keep the TIFF bytes as they are
+        if let Some(img_data) = read_image_raw_tiff() {
+            return Ok(Some(img_data));
         }
 
-        eprintln!("[DEBUG read_image]   All methods failed - no image captured");
-        None
+        clipster_log!(Warn, "[DEBUG read_image]   All methods failed - no image captured");
+        Ok(None)
+    }
+
+    /// Check the NSData length of the pasteboard TIFF and PNG against `max_bytes`
+    fn check_image_size(max_bytes: u64) -> Result<(), TooLarge> {
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            for uti in ["public.tiff", "public.png"] {
+                if let Some(data) = pasteboard.dataForType(&NSString::from_str(uti)) {
+                    payload_limit::check(data.len() as u64, max_bytes).inspect_err(|e| {
+                        clipster_log!(
+                            Debug,
+                            "[DEBUG read_image]   {}: {} bytes, over the limit",
+                            uti, e.size_bytes
+                        );
+                    })?;
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Try reading image via arboard
@@ -462,11 +618,11 @@ mod platform {
         let mut clipboard = Clipboard::new().ok()?;
         let img_data = match clipboard.get_image() {
             Ok(data) => {
-                eprintln!("[DEBUG read_image]   arboard: Got image {}x{}, {} bytes RGBA", data.width, data.height, data.bytes.len());
+                clipster_log!(Debug, "[DEBUG read_image]   arboard: Got image {}x{}, {} bytes RGBA", data.width, data.height, data.bytes.len());
                 data
             }
             Err(e) => {
-                eprintln!("[DEBUG read_image]   arboard: No image - {:?}", e);
+                clipster_log!(Debug, "[DEBUG read_image]   arboard: No image - {:?}", e);
                 return None;
             }
         };
@@ -480,26 +636,31 @@ mod platform {
         let dynamic_img = image::DynamicImage::ImageRgba8(img);
 
         // Encode as PNG
-        let mut png_data = Vec::new();
-        dynamic_img
-            .write_to(&mut std::io::Cursor::new(&mut png_data), image::ImageFormat::Png)
-            .ok()?;
+        let png_data = file_storage::encode_png(&dynamic_img).ok()?;
 
-        eprintln!("[DEBUG read_image]   arboard: Encoded to PNG: {} bytes", png_data.len());
+        clipster_log!(
+            Debug,
+            "[DEBUG read_image]   arboard: Encoded to PNG: {} bytes",
+            png_data.len()
+        );
 
         Some(ImageData {
             png_data,
+            format: image::ImageFormat::Png,
             width,
             height,
+            is_screenshot: false,
         })
     }
 
     /// Try reading image via native NSPasteboard TIFF data
     /// Some apps (like Preview, some browsers) provide TIFF format
+    /// 16-bit, float and CMYK bitmaps are redrawn into 8-bit sRGB first (see `bitmap_format`)
     fn read_image_native_tiff() -> Option<ImageData> {
+        use crate::clipboard::bitmap_format::{self, BitmapConversion};
         use objc2_app_kit::NSBitmapImageRep;
 
-        eprintln!("[DEBUG read_image]   Trying NSPasteboard TIFF...");
+        clipster_log!(Debug, "[DEBUG read_image]   Trying NSPasteboard TIFF...");
 
         unsafe {
             let pasteboard = NSPasteboard::generalPasteboard();
@@ -509,26 +670,50 @@ mod platform {
             let data = match pasteboard.dataForType(&tiff_type) {
                 Some(d) => d,
                 None => {
-                    eprintln!("[DEBUG read_image]   NSPasteboard TIFF: No data available");
+                    clipster_log!(
+                        Debug,
+                        "[DEBUG read_image]   NSPasteboard TIFF: No data available"
+                    );
                     return None;
                 }
             };
 
-            eprintln!("[DEBUG read_image]   NSPasteboard TIFF: Found {} bytes", data.len());
+            clipster_log!(
+                Debug,
+                "[DEBUG read_image]   NSPasteboard TIFF: Found {} bytes",
+                data.len()
+            );
 
             // Create bitmap rep from TIFF data
             let bitmap_rep = match NSBitmapImageRep::imageRepWithData(&data) {
                 Some(rep) => rep,
                 None => {
-                    eprintln!("[DEBUG read_image]   NSPasteboard TIFF: Failed to create bitmap rep");
+                    clipster_log!(Warn, "[DEBUG read_image]   NSPasteboard TIFF: Failed to create bitmap rep");
                     return None;
                 }
             };
 
             let width = bitmap_rep.pixelsWide() as u32;
             let height = bitmap_rep.pixelsHigh() as u32;
-
-            eprintln!("[DEBUG read_image]   NSPasteboard TIFF: Decoded {}x{}", width, height);
+            let bits_per_sample = bitmap_rep.bitsPerSample();
+            let color_space = bitmap_rep.colorSpaceName().to_string();
+
+            clipster_log!(
+                Debug,
+                "[DEBUG read_image]   NSPasteboard TIFF: Decoded {}x{}, {} bits/sample, {}",
+                width, height, bits_per_sample, color_space
+            );
+
+            let bitmap_rep = match bitmap_format::conversion_for(bits_per_sample, &color_space) {
+                BitmapConversion::Direct => bitmap_rep,
+                BitmapConversion::RedrawSrgb => match redraw_srgb(&bitmap_rep) {
+                    Some(rep) => rep,
+                    None => {
+                        clipster_log!(Warn, "[DEBUG read_image]   NSPasteboard TIFF: Failed to redraw into sRGB");
+                        return None;
+                    }
+                },
+            };
 
             // Convert to PNG
             use objc2_app_kit::NSBitmapImageFileType;
@@ -540,25 +725,105 @@ mod platform {
             ) {
                 Some(d) => d,
                 None => {
-                    eprintln!("[DEBUG read_image]   NSPasteboard TIFF: Failed to convert to PNG");
+                    clipster_log!(
+                        Warn,
+                        "[DEBUG read_image]   NSPasteboard TIFF: Failed to convert to PNG"
+                    );
                     return None;
                 }
             };
 
             let png_bytes = png_data.bytes().to_vec();
-            eprintln!("[DEBUG read_image]   NSPasteboard TIFF: Encoded to PNG: {} bytes", png_bytes.len());
+            clipster_log!(Debug, "[DEBUG read_image]   NSPasteboard TIFF: Encoded to PNG: {} bytes", png_bytes.len());
 
             Some(ImageData {
                 png_data: png_bytes,
+                format: image::ImageFormat::Png,
+                width,
+                height,
+                is_screenshot: false,
+            })
+        }
+    }
+
+    /// Redraw a bitmap into a new 8-bit RGBA bitmap in sRGB
+    unsafe fn redraw_srgb(
+        source: &objc2_app_kit::NSBitmapImageRep,
+    ) -> Option<objc2::rc::Retained<objc2_app_kit::NSBitmapImageRep>> {
+        use objc2::ClassType;
+        use objc2_app_kit::{
+            NSBitmapImageRep, NSCalibratedRGBColorSpace, NSColorSpace, NSGraphicsContext,
+        };
+        use objc2_foundation::{NSPoint, NSRect, NSSize};
+
+        let (width, height) = (source.pixelsWide(), source.pixelsHigh());
+        let target = NSBitmapImageRep::initWithBitmapDataPlanes_pixelsWide_pixelsHigh_bitsPerSample_samplesPerPixel_hasAlpha_isPlanar_colorSpaceName_bytesPerRow_bitsPerPixel(
+            NSBitmapImageRep::alloc(),
+            std::ptr::null_mut(),
+            width,
+            height,
+            8,
+            4,
+            true,
+            false,
+            NSCalibratedRGBColorSpace,
+            0,
+            0,
+        )?;
+        // Tagged sRGB before drawing, so drawing converts the source colors to sRGB
+        let srgb = NSColorSpace::sRGBColorSpace();
+        let target = target.bitmapImageRepByRetaggingWithColorSpace(&srgb)?;
+        let context = NSGraphicsContext::graphicsContextWithBitmapImageRep(&target)?;
+
+        NSGraphicsContext::saveGraphicsState_class();
+        NSGraphicsContext::setCurrentContext(Some(&context));
+        let drawn = source.drawInRect(NSRect::new(
+            NSPoint::new(0.0, 0.0),
+            NSSize::new(width as f64, height as f64),
+        ));
+        context.flushGraphics();
+        NSGraphicsContext::restoreGraphicsState_class();
+
+        drawn.then_some(target)
+    }
+
+    /// Last resort: the pasteboard TIFF bytes unconverted, stored as a .tiff file
+    fn read_image_raw_tiff() -> Option<ImageData> {
+        use crate::clipboard::bitmap_format;
+
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            let data = pasteboard.dataForType(&NSString::from_str("public.tiff"))?;
+            let tiff_bytes = data.bytes().to_vec();
+            if !bitmap_format::is_tiff(&tiff_bytes) {
+                clipster_log!(Debug, "[DEBUG read_image]   Raw TIFF: Data has no TIFF header");
+                return None;
+            }
+
+            // Dimensions are informational; 0 when the image crate can't read them either
+            let (width, height) =
+                image::load_from_memory_with_format(&tiff_bytes, image::ImageFormat::Tiff)
+                    .map(|img| (img.width(), img.height()))
+                    .unwrap_or((0, 0));
+            clipster_log!(
+                Debug,
+                "[DEBUG read_image]   Raw TIFF: Keeping {} bytes as-is",
+                tiff_bytes.len()
+            );
+
+            Some(ImageData {
+                png_data: tiff_bytes,
+                format: image::ImageFormat::Tiff,
                 width,
                 height,
+                is_screenshot: false,
             })
         }
     }
 
     /// Try reading image via native NSPasteboard PNG data
     fn read_image_native_png() -> Option<ImageData> {
-        eprintln!("[DEBUG read_image]   Trying NSPasteboard PNG...");
+        clipster_log!(Debug, "[DEBUG read_image]   Trying NSPasteboard PNG...");
 
         unsafe {
             let pasteboard = NSPasteboard::generalPasteboard();
@@ -568,31 +833,49 @@ mod platform {
             let data = match pasteboard.dataForType(&png_type) {
                 Some(d) => d,
                 None => {
-                    eprintln!("[DEBUG read_image]   NSPasteboard PNG: No data available");
+                    clipster_log!(
+                        Debug,
+                        "[DEBUG read_image]   NSPasteboard PNG: No data available"
+                    );
                     return None;
                 }
             };
             let png_bytes = data.bytes().to_vec();
 
-            eprintln!("[DEBUG read_image]   NSPasteboard PNG: Found {} bytes", png_bytes.len());
+            clipster_log!(
+                Debug,
+                "[DEBUG read_image]   NSPasteboard PNG: Found {} bytes",
+                png_bytes.len()
+            );
 
             // Decode to get dimensions
             let img = match image::load_from_memory(&png_bytes) {
                 Ok(i) => i,
                 Err(e) => {
-                    eprintln!("[DEBUG read_image]   NSPasteboard PNG: Failed to decode: {}", e);
+                    clipster_log!(
+                        Warn,
+                        "[DEBUG read_image]   NSPasteboard PNG: Failed to decode: {}",
+                        e
+                    );
                     return None;
                 }
             };
             let width = img.width();
             let height = img.height();
 
-            eprintln!("[DEBUG read_image]   NSPasteboard PNG: Decoded {}x{}", width, height);
+            clipster_log!(
+                Debug,
+                "[DEBUG read_image]   NSPasteboard PNG: Decoded {}x{}",
+                width,
+                height
+            );
 
             Some(ImageData {
                 png_data: png_bytes,
+                format: image::ImageFormat::Png,
                 width,
                 height,
+                is_screenshot: false,
             })
         }
     }
@@ -601,7 +884,7 @@ mod platform {
     pub fn read_files() -> Option<Vec<String>> {
         use percent_encoding::percent_decode_str;
 
-        eprintln!("[DEBUG read_files] Attempting to read files...");
+        clipster_log!(Debug, "[DEBUG read_files] Attempting to read files...");
 
         unsafe {
             let pasteboard = NSPasteboard::generalPasteboard();
@@ -611,13 +894,13 @@ mod platform {
             let items = match pasteboard.pasteboardItems() {
                 Some(i) => i,
                 None => {
-                    eprintln!("[DEBUG read_files]   No pasteboard items");
+                    clipster_log!(Debug, "[DEBUG read_files]   No pasteboard items");
                     return None;
                 }
             };
 
             let item_count = items.count();
-            eprintln!("[DEBUG read_files]   Found {} pasteboard items", item_count);
+            clipster_log!(Debug, "[DEBUG read_files]   Found {} pasteboard items", item_count);
 
             let mut file_paths: Vec<String> = Vec::new();
 
@@ -627,17 +910,17 @@ mod platform {
                 // Log all types available for this item
                 let types = item.types();
                 let type_count = types.count();
-                eprintln!("[DEBUG read_files]   Item {} has {} types", i, type_count);
+                clipster_log!(Debug, "[DEBUG read_files]   Item {} has {} types", i, type_count);
                 for j in 0..type_count {
                     let t: &NSString = &types.objectAtIndex(j);
-                    eprintln!("[DEBUG read_files]     - {}", t.to_string());
+                    clipster_log!(Debug, "[DEBUG read_files]     - {}", t.to_string());
                 }
 
                 // Get the file URL string from the item
                 if let Some(url_string) = item.stringForType(&file_url_type) {
                     // Convert file:// URL to path
                     let url_str: String = url_string.to_string();
-                    eprintln!("[DEBUG read_files]   Item {} file URL: {}", i, url_str);
+                    clipster_log!(Debug, "[DEBUG read_files]   Item {} has a file URL", i);
                     if let Some(nsurl) = NSURL::URLWithString(&NSString::from_str(&url_str)) {
                         if let Some(path) = nsurl.path() {
                             let path_str: String = path.to_string();
@@ -648,21 +931,26 @@ mod platform {
                                     .ok()
                                     .map(|s| s.to_string())
                                     .unwrap_or(path_str);
-                                eprintln!("[DEBUG read_files]   Decoded path: {}", decoded_path);
                                 file_paths.push(decoded_path);
                             }
                         }
                     }
                 } else {
-                    eprintln!("[DEBUG read_files]   Item {} has no public.file-url", i);
+                    clipster_log!(Debug, "[DEBUG read_files]   Item {} has no public.file-url", i);
                 }
             }
 
             if file_paths.is_empty() {
-                eprintln!("[DEBUG read_files]   No file paths found");
+                clipster_log!(Debug, "[DEBUG read_files]   No file paths found");
                 None
             } else {
-                eprintln!("[DEBUG read_files]   Found {} files: {:?}", file_paths.len(), file_paths);
+                let preview = preview::preview_paths(&file_paths);
+                clipster_log!(
+                    Debug,
+                    "[DEBUG read_files]   Found {} files: {}",
+                    file_paths.len(),
+                    preview
+                );
                 Some(file_paths)
             }
         }
@@ -672,100 +960,204 @@ mod platform {
     /// Priority order depends on content:
     /// - If files exist on disk → treat as FILES (preserves original filename)
     /// - Otherwise → Image -> Text
-    pub fn read_clipboard() -> ClipboardContent {
-        eprintln!("┌─────────────────────────────────────────────────────────────");
-        eprintln!("│ [DEBUG read_clipboard] Checking clipboard content...");
+    /// Images over `max_image_bytes` are reported as `TooLarge` without being read
+    pub fn read_clipboard(max_image_bytes: u64) -> ClipboardContent {
+        clipster_log!(Debug, "┌─────────────────────────────────────────────────────────────");
+        clipster_log!(Debug, "│ [DEBUG read_clipboard] Checking clipboard content...");
 
         // LOG ALL AVAILABLE TYPES for debugging capture failures
         log_pasteboard_types();
 
         // Check what's available
         let has_files = read_files();
-        let has_image = read_image();
+        let (mut has_image, too_large) = match read_image(max_image_bytes) {
+            Ok(image) => (image, None),
+            Err(too_large) => (None, Some(too_large)),
+        };
+        let is_screenshot = screenshot::is_screenshot(&pasteboard_types());
 
-        eprintln!("│   has_files: {:?}", has_files.as_ref().map(|f| f.clone()));
-        eprintln!("│   has_image: {}", has_image.is_some());
+        clipster_log!(Debug, "│   has_files: {:?}", has_files.as_ref().map(|f| f.len()));
+        clipster_log!(Debug, "│   has_image: {}", has_image.is_some());
+        clipster_log!(Debug, "│   is_screenshot: {}", is_screenshot);
+        if let Some(img) = has_image.as_mut() {
+            img.is_screenshot = is_screenshot;
+        }
 
         // If we have FILES that exist on disk, prioritize them (preserves original filename)
         // This handles the case of copying a file from Finder
-        if let Some(ref file_list) = has_files {
+        // A screenshot's temporary file is not what was copied: the image is
+        if let Some(file_list) = has_files.as_ref().filter(|_| !is_screenshot) {
             // Check if files actually exist on disk
             let files_exist = file_list.iter().all(|f| std::path::Path::new(f).exists());
-            eprintln!("│   files_exist on disk: {}", files_exist);
+            clipster_log!(Debug, "│   files_exist on disk: {}", files_exist);
 
             if files_exist && !file_list.is_empty() {
-                eprintln!("│ → Using FILES (original paths preserved): {:?}", file_list);
-                eprintln!("└─────────────────────────────────────────────────────────────");
+                let preview = preview::preview_paths(file_list);
+                clipster_log!(Debug, "│ → Using FILES (original paths preserved): {}", preview);
+                clipster_log!(
+                    Debug,
+                    "└─────────────────────────────────────────────────────────────"
+                );
                 return ClipboardContent::Files(file_list.clone());
             } else if !files_exist {
-                eprintln!("│   WARNING: Files detected but don't exist on disk!");
+                clipster_log!(Warn, "│   WARNING: Files detected but don't exist on disk!");
                 for f in file_list {
-                    eprintln!("│     - {} (exists: {})", f, std::path::Path::new(f).exists());
+                    clipster_log!(
+                        Debug,
+                        "│     - {} (exists: {})",
+                        preview::preview_paths(std::slice::from_ref(f)),
+                        std::path::Path::new(f).exists()
+                    );
                 }
             }
         }
 
         // Otherwise, check for image data (screenshots, copied images from apps)
         if let Some(img) = has_image {
-            eprintln!("│ → Found IMAGE: {}x{}, {} bytes PNG", img.width, img.height, img.png_data.len());
-            eprintln!("└─────────────────────────────────────────────────────────────");
+            clipster_log!(Debug, "│ → Found IMAGE: {}x{}, {} bytes PNG", img.width, img.height, img.png_data.len());
+            clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
             return ClipboardContent::Image(img);
         }
+        if let Some(too_large) = too_large {
+            clipster_log!(Debug, "│ → IMAGE over the size limit: {} bytes", too_large.size_bytes);
+            clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
+            return ClipboardContent::TooLarge(too_large);
+        }
 
         // Fallback to files even if they don't exist (edge case)
         if let Some(file_list) = has_files {
-            eprintln!("│ → Found FILES (fallback): {:?}", file_list);
-            eprintln!("└─────────────────────────────────────────────────────────────");
+            let preview = preview::preview_paths(&file_list);
+            clipster_log!(Debug, "│ → Found FILES (fallback): {}", preview);
+            clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
             return ClipboardContent::Files(file_list);
         }
 
+        // Contacts and events: the plain text flavor is only their name
+        if let Some(text) = read_contact_or_event() {
+            clipster_log!(Debug, "│ → Found CONTACT/EVENT: {} chars", text.chars().count());
+            clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
+            return ClipboardContent::Text(text);
+        }
+
         // Check text last
         if let Some(text) = read_text() {
-            let preview = if text.len() > 50 { &text[..50] } else { &text };
-            eprintln!("│ → Found TEXT: {}... ({} chars)", preview, text.len());
-            eprintln!("└─────────────────────────────────────────────────────────────");
+            let preview = preview::preview_text(&text, 50);
+            clipster_log!(Debug, "│ → Found TEXT: {} ({} chars)", preview, text.chars().count());
+            clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
             return ClipboardContent::Text(text);
         }
 
-        eprintln!("│ → EMPTY clipboard (no files, no image, no text)");
-        eprintln!("│   This may indicate an unsupported UTI type - check types above");
-        eprintln!("└─────────────────────────────────────────────────────────────");
+        clipster_log!(Debug, "│ → EMPTY clipboard (no files, no image, no text)");
+        clipster_log!(Debug, "│   This may indicate an unsupported UTI type - check types above");
+        clipster_log!(Debug, "└─────────────────────────────────────────────────────────────");
         ClipboardContent::Empty
     }
 
+    /// vCard or iCalendar payload put on the pasteboard by Contacts or Calendar
+    fn read_contact_or_event() -> Option<String> {
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            contact_event::PASTEBOARD_UTIS.iter().find_map(|uti| {
+                let data = pasteboard.dataForType(&NSString::from_str(uti))?;
+                let text = contact_event::decode_payload(data.bytes())?;
+                contact_event::detect(&text).map(|_| text)
+            })
+        }
+    }
+
     /// Get clipboard text (simple API)
-    pub fn get_clipboard_text() -> Result<String, String> {
-        read_text().ok_or_else(|| "No text in clipboard".to_string())
+    pub fn get_clipboard_text() -> Result<String, ClipsterError> {
+        read_text().ok_or_else(|| ClipsterError::NotFound("No text in clipboard".to_string()))
     }
 
     /// Set clipboard text
-    pub fn set_clipboard_text(text: &str) -> Result<(), String> {
-        let mut clipboard = Clipboard::new()
-            .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    /// macOS has no system clipboard history, so `_exclude_from_os_history` is unused
+    pub fn set_clipboard_text(
+        text: &str,
+        _exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let mut clipboard = Clipboard::new().map_err(access_error("Failed to access clipboard"))?;
         clipboard
             .set_text(text)
-            .map_err(|e| format!("Failed to set clipboard: {}", e))
+            .map_err(access_error("Failed to set clipboard"))
+    }
+
+    /// Set a contact or event payload as plain text and as `public.vcard` or
+    /// `com.apple.ical.ics`, so Contacts and Calendar paste it as a card or event
+    pub fn set_clipboard_structured(
+        text: &str,
+        subtype: ContentSubtype,
+        _exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let uti = contact_event::pasteboard_uti(subtype);
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            pasteboard.clearContents();
+
+            let types = objc2_foundation::NSArray::from_id_slice(&[
+                NSString::from_str("public.utf8-plain-text"),
+                NSString::from_str(uti),
+            ]);
+            pasteboard.declareTypes_owner(&types, None);
+
+            let ns_data = NSData::with_bytes(text.as_bytes());
+            for uti in ["public.utf8-plain-text", uti] {
+                if !pasteboard.setData_forType(Some(&ns_data), &NSString::from_str(uti)) {
+                    return Err(ClipsterError::ClipboardAccess(format!(
+                        "Failed to set {} on the pasteboard",
+                        uti
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Set clipboard image from a file path
-    pub fn set_clipboard_image(image_path: &str) -> Result<(), String> {
-        let img = image::open(image_path)
-            .map_err(|e| format!("Failed to open image: {}", e))?;
-        let rgba = img.to_rgba8();
-        let (w, h) = rgba.dimensions();
-        let mut clipboard = Clipboard::new()
-            .map_err(|e| format!("Failed to access clipboard: {}", e))?;
-        clipboard
-            .set_image(arboard::ImageData {
-                width: w as usize,
-                height: h as usize,
-                bytes: std::borrow::Cow::Owned(rgba.into_raw()),
-            })
-            .map_err(|e| format!("Failed to set clipboard image: {}", e))
+    /// Written as both public.png and public.tiff, since some apps only read TIFF
+    pub fn set_clipboard_image(
+        image_path: &str,
+        strip_metadata: bool,
+        _exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
+        let img = image::open(image_path).context("Failed to open image")?;
+        let png =
+            file_storage::clipboard_png(std::path::Path::new(image_path), &img, strip_metadata)?;
+        let mut tiff = Vec::new();
+        img.write_to(
+            &mut std::io::Cursor::new(&mut tiff),
+            image::ImageOutputFormat::Tiff,
+        )
+        .context("Failed to encode TIFF")?;
+
+        unsafe {
+            let pasteboard = NSPasteboard::generalPasteboard();
+            pasteboard.clearContents();
+
+            let types = objc2_foundation::NSArray::from_id_slice(&[
+                NSString::from_str("public.png"),
+                NSString::from_str("public.tiff"),
+            ]);
+            pasteboard.declareTypes_owner(&types, None);
+
+            for (data, uti) in [(&png, "public.png"), (&tiff, "public.tiff")] {
+                let ns_data = NSData::with_bytes(data);
+                if !pasteboard.setData_forType(Some(&ns_data), &NSString::from_str(uti)) {
+                    return Err(ClipsterError::ClipboardAccess(format!(
+                        "Failed to set {} on the pasteboard",
+                        uti
+                    )));
+                }
+            }
+        }
+        Ok(())
     }
 
     /// Set clipboard to file paths (macOS: NSPasteboard with file URLs)
-    pub fn set_clipboard_files(file_paths: &[String]) -> Result<(), String> {
+    pub fn set_clipboard_files(
+        file_paths: &[String],
+        _exclude_from_os_history: bool,
+    ) -> Result<(), ClipsterError> {
         unsafe {
             let pasteboard = NSPasteboard::generalPasteboard();
             pasteboard.clearContents();
@@ -777,7 +1169,9 @@ mod platform {
             for path in file_paths {
                 let ns_path = NSString::from_str(path);
                 let url = NSURL::fileURLWithPath(&ns_path);
-                let url_string = url.absoluteString().ok_or("Failed to get URL string")?;
+                let url_string = url.absoluteString().ok_or_else(|| {
+                    ClipsterError::ClipboardAccess("Failed to get URL string".to_string())
+                })?;
                 pasteboard.setString_forType(&url_string, &NSString::from_str("public.file-url"));
             }
         }
@@ -790,3 +1184,21 @@ mod platform {
 // ============================================================================
 
 pub use platform::*;
+
+/// Write what `copy_back::copy_item` resolved for an item to the clipboard
+pub fn write_copy_content(
+    content: CopyContent,
+    strip_metadata: bool,
+    exclude_from_os_history: bool,
+) -> Result<(), ClipsterError> {
+    match content {
+        CopyContent::Text(text) => set_clipboard_text(text, exclude_from_os_history),
+        CopyContent::Structured(text, subtype) => {
+            set_clipboard_structured(text, subtype, exclude_from_os_history)
+        }
+        CopyContent::Image(image_path) => {
+            set_clipboard_image(image_path, strip_metadata, exclude_from_os_history)
+        }
+        CopyContent::Files(file_paths) => set_clipboard_files(&file_paths, exclude_from_os_history),
+    }
+}
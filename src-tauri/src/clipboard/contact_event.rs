@@ -0,0 +1,543 @@
+//! Contacts and calendar events copied as vCard / iCalendar text
+//!
+//! Copying a contact in Contacts.app or an event in Calendar puts a
+//! `public.vcard` or iCalendar payload on the pasteboard (the plain text
+//! flavor, if any, is just the name); other apps and platforms copy the same
+//! blocks as plain text. `detect` recognizes both by their BEGIN/END lines and
+//! reads a one-line summary (name and email, or event title and start) that
+//! becomes the item's title. The payload itself is stored unchanged, and
+//! copy-back puts it on the clipboard again with its type.
+
+use crate::models::ContentSubtype;
+use chrono::{NaiveDate, NaiveDateTime};
+
+/// Pasteboard types read (before plain text) on macOS
+pub const PASTEBOARD_UTIS: &[&str] = &["public.vcard", "com.apple.ical.ics"];
+
+/// macOS pasteboard type the payload is written back as, next to plain text
+pub fn pasteboard_uti(subtype: ContentSubtype) -> &'static str {
+    match subtype {
+        ContentSubtype::Contact => "public.vcard",
+        ContentSubtype::Event => "com.apple.ical.ics",
+    }
+}
+
+/// MIME type, also the name of the registered Windows clipboard format
+pub fn mime_type(subtype: ContentSubtype) -> &'static str {
+    match subtype {
+        ContentSubtype::Contact => "text/vcard",
+        ContentSubtype::Event => "text/calendar",
+    }
+}
+
+/// A copied contact (first card of the payload)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContactSummary {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    /// Cards in the payload
+    pub count: usize,
+}
+
+/// A copied event (first event of the payload)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventSummary {
+    pub title: Option<String>,
+    /// "2024-05-12" for all-day events, "2024-05-12 14:00" otherwise, with
+    /// " UTC" for UTC times
+    pub start: Option<String>,
+    /// Events in the payload
+    pub count: usize,
+}
+
+/// Contact or event payload found in copied text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuredPayload {
+    Contact(ContactSummary),
+    Event(EventSummary),
+}
+
+impl StructuredPayload {
+    pub fn subtype(&self) -> ContentSubtype {
+        match self {
+            StructuredPayload::Contact(_) => ContentSubtype::Contact,
+            StructuredPayload::Event(_) => ContentSubtype::Event,
+        }
+    }
+
+    /// One-line summary stored as the item title
+    /// ("Jane Doe — jane@example.com", "Team sync — 2024-05-12 14:00")
+    pub fn title(&self) -> String {
+        let (parts, count, kind) = match self {
+            StructuredPayload::Contact(contact) => (
+                [contact.name.as_deref(), contact.email.as_deref()],
+                contact.count,
+                "contacts",
+            ),
+            StructuredPayload::Event(event) => (
+                [event.title.as_deref(), event.start.as_deref()],
+                event.count,
+                "events",
+            ),
+        };
+        let summary = parts.into_iter().flatten().collect::<Vec<_>>().join(" — ");
+        let summary = match (summary.is_empty(), self) {
+            (false, _) => summary,
+            (true, StructuredPayload::Contact(_)) => "Contact".to_string(),
+            (true, StructuredPayload::Event(_)) => "Event".to_string(),
+        };
+        if count > 1 {
+            format!("{} {}: {}", count, kind, summary)
+        } else {
+            summary
+        }
+    }
+}
+
+/// Recognize a vCard or iCalendar payload; None for any other text
+/// The text has to start with the BEGIN line, so a message quoting a vCard
+/// stays plain text
+pub fn detect(text: &str) -> Option<StructuredPayload> {
+    let trimmed = text.trim_start_matches('\u{feff}').trim_start();
+    if starts_with_ignore_case(trimmed, "BEGIN:VCARD") {
+        parse_vcard(trimmed).map(StructuredPayload::Contact)
+    } else if starts_with_ignore_case(trimmed, "BEGIN:VCALENDAR") {
+        parse_icalendar(trimmed).map(StructuredPayload::Event)
+    } else {
+        None
+    }
+}
+
+/// Text of a pasteboard payload: UTF-8, or UTF-16 with a byte order mark
+/// (older Contacts versions)
+pub fn decode_payload(bytes: &[u8]) -> Option<String> {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).ok()
+    };
+    let text = match bytes {
+        [0xFF, 0xFE, rest @ ..] => utf16(rest, u16::from_le_bytes)?,
+        [0xFE, 0xFF, rest @ ..] => utf16(rest, u16::from_be_bytes)?,
+        [0xEF, 0xBB, 0xBF, rest @ ..] => String::from_utf8(rest.to_vec()).ok()?,
+        _ => String::from_utf8(bytes.to_vec()).ok()?,
+    };
+    (!text.trim().is_empty()).then_some(text)
+}
+
+/// First contact of a vCard payload; None unless a card is complete
+pub fn parse_vcard(text: &str) -> Option<ContactSummary> {
+    let mut summary = ContactSummary::default();
+    let mut structured_name = None;
+    let mut organization = None;
+    let mut in_card = false;
+    let mut complete = false;
+
+    for line in content_lines(text) {
+        let Some(property) = Property::parse(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" if property.value.eq_ignore_ascii_case("VCARD") => {
+                in_card = true;
+                summary.count += 1;
+            }
+            "END" if property.value.eq_ignore_ascii_case("VCARD") && in_card => {
+                in_card = false;
+                complete = true;
+            }
+            // Only the first card is summarized
+            _ if !in_card || summary.count > 1 => {}
+            "FN" => summary.name = summary.name.or_else(|| non_empty(unescape(property.value))),
+            "N" => structured_name = structured_name.or_else(|| name_from_parts(property.value)),
+            "ORG" => {
+                let first_unit = split_unescaped(property.value, ';').next().unwrap_or("");
+                organization = organization.or_else(|| non_empty(unescape(first_unit)));
+            }
+            "EMAIL" => {
+                summary.email = summary
+                    .email
+                    .or_else(|| non_empty(unescape(property.value)))
+            }
+            _ => {}
+        }
+    }
+
+    if !complete {
+        return None;
+    }
+    summary.name = summary.name.or(structured_name).or(organization);
+    Some(summary)
+}
+
+/// First event of an iCalendar payload; None without a complete VEVENT
+pub fn parse_icalendar(text: &str) -> Option<EventSummary> {
+    let mut summary = EventSummary::default();
+    // Open components, innermost last: alarms inside an event have their own SUMMARY
+    let mut components: Vec<String> = Vec::new();
+    let mut complete = false;
+
+    for line in content_lines(text) {
+        let Some(property) = Property::parse(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_ascii_uppercase();
+                if component == "VEVENT" {
+                    summary.count += 1;
+                }
+                components.push(component);
+            }
+            "END" => {
+                let closed = components.pop();
+                complete |= closed.as_deref() == Some("VEVENT");
+            }
+            _ if components.last().map(String::as_str) != Some("VEVENT") || summary.count > 1 => {}
+            "SUMMARY" => {
+                summary.title = summary
+                    .title
+                    .or_else(|| non_empty(unescape(property.value)))
+            }
+            "DTSTART" => {
+                summary.start = summary
+                    .start
+                    .or_else(|| format_start(property.value, property.params));
+            }
+            _ => {}
+        }
+    }
+
+    complete.then_some(summary)
+}
+
+/// One property line: `group.NAME;PARAM=x:value`
+struct Property<'a> {
+    /// Upper case, without the group
+    name: String,
+    params: &'a str,
+    /// Still escaped
+    value: &'a str,
+}
+
+impl<'a> Property<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        // The value starts at the first colon outside a quoted parameter value
+        let mut quoted = false;
+        let colon = line.char_indices().find_map(|(i, c)| match c {
+            '"' => {
+                quoted = !quoted;
+                None
+            }
+            ':' if !quoted => Some(i),
+            _ => None,
+        })?;
+        let (head, value) = (&line[..colon], &line[colon + 1..]);
+        let (name, params) = head.split_once(';').unwrap_or((head, ""));
+        let name = name.rsplit('.').next().unwrap_or(name).trim();
+        if name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            name: name.to_ascii_uppercase(),
+            params,
+            value: value.trim(),
+        })
+    }
+}
+
+/// Lines of a payload with folded lines (continued by a leading space or tab)
+/// joined back
+fn content_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in text.lines() {
+        match (raw.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(previous)) => previous.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parts of a value separated by `separator`, escaped separators left in place
+fn split_unescaped(value: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut escaped = false;
+    let mut start = 0;
+    let mut parts = Vec::new();
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == separator => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts.into_iter()
+}
+
+/// Undo text value escaping; line breaks become spaces in a summary
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push(' '),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+/// "Jane Doe" from `N:Doe;Jane;Q.;Dr.;Jr.` (family; given; additional; prefix; suffix)
+fn name_from_parts(value: &str) -> Option<String> {
+    let parts: Vec<String> = split_unescaped(value, ';').map(unescape).collect();
+    let part = |i: usize| parts.get(i).map(|p| p.trim()).unwrap_or("");
+    let name = [part(3), part(1), part(2), part(0), part(4)]
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    non_empty(name)
+}
+
+/// Readable DTSTART: a date, or a date and time ("Z" times marked UTC)
+fn format_start(value: &str, params: &str) -> Option<String> {
+    let all_day = params.to_ascii_uppercase().contains("VALUE=DATE")
+        && !params.to_ascii_uppercase().contains("VALUE=DATE-TIME");
+    if all_day || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(date.format("%Y-%m-%d").to_string());
+    }
+
+    let (local, utc) = match value.strip_suffix(['Z', 'z']) {
+        Some(local) => (local, true),
+        None => (value, false),
+    };
+    let start = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S").ok()?;
+    let formatted = start.format("%Y-%m-%d %H:%M").to_string();
+    Some(if utc {
+        format!("{} UTC", formatted)
+    } else {
+        formatted
+    })
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Contacts.app (vCard 3.0) with a folded NOTE and a grouped email
+    const CONTACTS_APP_VCARD: &str = "BEGIN:VCARD\r\n\
+        VERSION:3.0\r\n\
+        PRODID:-//Apple Inc.//macOS 14.4//EN\r\n\
+        N:Doe;Jane;;;\r\n\
+        FN:Jane Doe\r\n\
+        ORG:Example Corp;\r\n\
+        item1.EMAIL;type=INTERNET;type=pref:jane@example.com\r\n\
+        item1.X-ABLabel:_$!<Work>!$_\r\n\
+        TEL;type=CELL;type=VOICE;type=pref:+33 6 12 34 56 78\r\n\
+        NOTE:Met at the conference\\, see the\r\n  slides\r\n\
+        END:VCARD\r\n";
+
+    /// Outlook-style vCard 2.1 without FN
+    const VCARD_21: &str = "BEGIN:VCARD\n\
+        VERSION:2.1\n\
+        N:Martin;Paul;Henri;Dr.;\n\
+        EMAIL;PREF;INTERNET:paul.martin@example.org\n\
+        END:VCARD\n";
+
+    /// Calendar.app event with a time zone and an alarm
+    const CALENDAR_APP_ICS: &str = "BEGIN:VCALENDAR\r\n\
+        VERSION:2.0\r\n\
+        PRODID:-//Apple Inc.//macOS 14.4//EN\r\n\
+        BEGIN:VTIMEZONE\r\n\
+        TZID:Europe/Paris\r\n\
+        BEGIN:DAYLIGHT\r\n\
+        DTSTART:19810329T020000\r\n\
+        TZNAME:CEST\r\n\
+        END:DAYLIGHT\r\n\
+        END:VTIMEZONE\r\n\
+        BEGIN:VEVENT\r\n\
+        UID:1B2C3D4E-0000-4000-8000-000000000001\r\n\
+        DTSTART;TZID=Europe/Paris:20240512T140000\r\n\
+        DTEND;TZID=Europe/Paris:20240512T150000\r\n\
+        SUMMARY:Team sync\\; Q2 planning\r\n\
+        LOCATION:Room \"Atlas\": 3rd floor\r\n\
+        BEGIN:VALARM\r\n\
+        ACTION:EMAIL\r\n\
+        SUMMARY:Alarm notification\r\n\
+        TRIGGER:-PT15M\r\n\
+        END:VALARM\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parse_contacts_app_vcard() {
+        let contact = parse_vcard(CONTACTS_APP_VCARD).unwrap();
+        assert_eq!(contact.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(contact.email.as_deref(), Some("jane@example.com"));
+        assert_eq!(contact.count, 1);
+
+        let payload = detect(CONTACTS_APP_VCARD).unwrap();
+        assert_eq!(payload.subtype(), ContentSubtype::Contact);
+        assert_eq!(payload.title(), "Jane Doe — jane@example.com");
+    }
+
+    #[test]
+    fn test_vcard_name_fallbacks() {
+        // Built from N when there is no FN
+        let contact = parse_vcard(VCARD_21).unwrap();
+        assert_eq!(contact.name.as_deref(), Some("Dr. Paul Henri Martin"));
+        assert_eq!(contact.email.as_deref(), Some("paul.martin@example.org"));
+
+        // A company card only has ORG
+        let company = "BEGIN:VCARD\nVERSION:3.0\nFN:\nORG:Acme\\, Inc.;Sales\nEND:VCARD";
+        let contact = parse_vcard(company).unwrap();
+        assert_eq!(contact.name.as_deref(), Some("Acme, Inc."));
+        assert_eq!(contact.email, None);
+
+        let empty = "BEGIN:VCARD\nVERSION:4.0\nEND:VCARD";
+        assert_eq!(detect(empty).unwrap().title(), "Contact");
+    }
+
+    #[test]
+    fn test_several_contacts() {
+        let two = format!("{}{}", CONTACTS_APP_VCARD, VCARD_21);
+        let contact = parse_vcard(&two).unwrap();
+        assert_eq!(contact.count, 2);
+        // The second card doesn't fill in what the first lacks
+        assert_eq!(contact.name.as_deref(), Some("Jane Doe"));
+        assert_eq!(
+            detect(&two).unwrap().title(),
+            "2 contacts: Jane Doe — jane@example.com"
+        );
+    }
+
+    #[test]
+    fn test_parse_calendar_app_event() {
+        let event = parse_icalendar(CALENDAR_APP_ICS).unwrap();
+        // The alarm's SUMMARY and the time zone's DTSTART are not the event's
+        assert_eq!(event.title.as_deref(), Some("Team sync; Q2 planning"));
+        assert_eq!(event.start.as_deref(), Some("2024-05-12 14:00"));
+        assert_eq!(event.count, 1);
+
+        let payload = detect(CALENDAR_APP_ICS).unwrap();
+        assert_eq!(payload.subtype(), ContentSubtype::Event);
+        assert_eq!(payload.title(), "Team sync; Q2 planning — 2024-05-12 14:00");
+    }
+
+    #[test]
+    fn test_event_start_formats() {
+        assert_eq!(
+            format_start("20240512", "VALUE=DATE").as_deref(),
+            Some("2024-05-12")
+        );
+        assert_eq!(format_start("20240512", "").as_deref(), Some("2024-05-12"));
+        assert_eq!(
+            format_start("20240512T083000Z", "").as_deref(),
+            Some("2024-05-12 08:30 UTC")
+        );
+        assert_eq!(
+            format_start("20240512T083000", "VALUE=DATE-TIME;TZID=America/New_York").as_deref(),
+            Some("2024-05-12 08:30")
+        );
+        assert_eq!(format_start("20241332", "VALUE=DATE"), None);
+        assert_eq!(format_start("tomorrow", ""), None);
+
+        let all_day = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20241225\n\
+                       END:VEVENT\nEND:VCALENDAR";
+        assert_eq!(detect(all_day).unwrap().title(), "2024-12-25");
+    }
+
+    #[test]
+    fn test_folded_lines_are_joined() {
+        let folded = "BEGIN:VEVENT\r\nSUMMARY:Quarterly business\r\n  review\r\nEND:VEVENT";
+        assert_eq!(
+            content_lines(folded),
+            vec![
+                "BEGIN:VEVENT",
+                "SUMMARY:Quarterly business review",
+                "END:VEVENT"
+            ]
+        );
+        let ics = format!("BEGIN:VCALENDAR\r\n{}\r\nEND:VCALENDAR\r\n", folded);
+        let event = parse_icalendar(&ics).unwrap();
+        assert_eq!(event.title.as_deref(), Some("Quarterly business review"));
+        assert_eq!(event.start, None);
+        assert_eq!(detect(&ics).unwrap().title(), "Quarterly business review");
+    }
+
+    #[test]
+    fn test_plain_text_is_not_detected() {
+        for text in [
+            "",
+            "Jane Doe <jane@example.com>",
+            "Here is my card:\nBEGIN:VCARD\nFN:Jane\nEND:VCARD",
+            // Truncated payloads
+            "BEGIN:VCARD\nVERSION:3.0\nFN:Jane Doe",
+            "BEGIN:VCALENDAR\nVERSION:2.0\nBEGIN:VEVENT\nSUMMARY:Cut off",
+            // A calendar holding only a to-do
+            "BEGIN:VCALENDAR\nBEGIN:VTODO\nSUMMARY:Buy milk\nEND:VTODO\nEND:VCALENDAR",
+        ] {
+            assert_eq!(detect(text), None, "{:?}", text);
+        }
+        // Leading whitespace, a byte order mark and lower case are fine
+        let padded = format!("\u{feff}\n  {}", VCARD_21.to_lowercase());
+        assert_eq!(detect(&padded).unwrap().subtype(), ContentSubtype::Contact);
+    }
+
+    #[test]
+    fn test_decode_payload() {
+        assert_eq!(
+            decode_payload(b"BEGIN:VCARD").as_deref(),
+            Some("BEGIN:VCARD")
+        );
+        assert_eq!(
+            decode_payload(b"\xEF\xBB\xBFBEGIN:VCARD").as_deref(),
+            Some("BEGIN:VCARD")
+        );
+        let utf16le: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("FN:Zoë".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        assert_eq!(decode_payload(&utf16le).as_deref(), Some("FN:Zoë"));
+        let utf16be: Vec<u8> = [0xFE, 0xFF]
+            .into_iter()
+            .chain("FN:Zoë".encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+        assert_eq!(decode_payload(&utf16be).as_deref(), Some("FN:Zoë"));
+        assert_eq!(decode_payload(b"\xC3\x28"), None);
+        assert_eq!(decode_payload(b"  \n"), None);
+    }
+
+    #[test]
+    fn test_quoted_params_and_groups() {
+        let property = Property::parse("item2.EMAIL;LABEL=\"a:b\":x@y.z").unwrap();
+        assert_eq!(property.name, "EMAIL");
+        assert_eq!(property.params, "LABEL=\"a:b\"");
+        assert_eq!(property.value, "x@y.z");
+        assert!(Property::parse("no colon here").is_none());
+        assert!(Property::parse(":value only").is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! Copying a saved item back to the system clipboard
+//!
+//! `copy_item` works out what to write for an item, hands it to a writer (the
+//! platform clipboard in the app) and counts one use once the write
+//! succeeded. A copy-back can place several flavors on the clipboard (an image
+//! goes on as DIBV5 and DIB, or PNG and TIFF, a contact as text and vCard)
+//! but is still a single use.
+//! `copy_filled_item` copies a template with its placeholders filled in.
+//! Text is written through the transform resolved by `transforms::resolve`.
+
+use crate::clipboard::transforms::{self, Transform};
+use crate::error::{ClipsterError, Context};
+use crate::models::{ClipboardItem, ContentSubtype, ContentType, Pinboard};
+use crate::storage::Database;
+use crate::templates;
+use std::collections::HashMap;
+
+/// What a copy-back writes
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyContent<'a> {
+    Text(&'a str),
+    /// The raw payload of a contact or event, written as text and with its own type
+    Structured(&'a str, ContentSubtype),
+    /// Path of the stored image
+    Image(&'a str),
+    Files(Vec<String>),
+}
+
+/// Copy the item `id` with `write`, then count the use
+/// Text goes through `transform`, or else the `default_transform` of the
+/// item's pinboard unless `skip_transform` is set.
+/// `write` also receives whether the item stays out of the OS clipboard history
+pub fn copy_item(
+    db: &Database,
+    id: &str,
+    transform: Option<Transform>,
+    skip_transform: bool,
+    write: impl FnOnce(CopyContent, bool) -> Result<(), ClipsterError>,
+) -> Result<(), ClipsterError> {
+    let item = db.require_item(id)?;
+    let pinboard = item_pinboard(db, &item)?;
+    let exclude_from_os_history = excluded_from_os_history(db, pinboard.as_ref())?;
+    let pinboard_default = pinboard.and_then(|pinboard| pinboard.default_transform);
+
+    let transformed;
+    let content = match item.content_type {
+        ContentType::Text | ContentType::Link => {
+            let text = item
+                .content_text
+                .as_deref()
+                .ok_or_else(|| ClipsterError::Validation("No text content in item".to_string()))?;
+            match (
+                transforms::resolve(transform, pinboard_default, skip_transform),
+                item.content_subtype,
+            ) {
+                // A transformed contact or event is plain text
+                (Some(transform), _) => {
+                    transformed = transform.apply(text);
+                    CopyContent::Text(&transformed)
+                }
+                (None, Some(subtype)) => CopyContent::Structured(text, subtype),
+                (None, None) => CopyContent::Text(text),
+            }
+        }
+        // Written without metadata when `strip_image_metadata` is on (see
+        // `file_storage::clipboard_png`)
+        ContentType::Image => {
+            let image_path = item
+                .image_path
+                .as_deref()
+                .ok_or_else(|| ClipsterError::NotFound("No image path for item".to_string()))?;
+            CopyContent::Image(image_path)
+        }
+        ContentType::Files | ContentType::Audio | ContentType::Documents => {
+            let content_text = item
+                .content_text
+                .as_deref()
+                .ok_or_else(|| ClipsterError::NotFound("No file paths for item".to_string()))?;
+            let file_paths =
+                serde_json::from_str(content_text).context("Failed to parse file paths")?;
+            CopyContent::Files(file_paths)
+        }
+    };
+
+    write(content, exclude_from_os_history)?;
+    db.record_item_use(&item.id)
+}
+
+/// Copy the text item `id` with its placeholders replaced by `values` (or
+/// their defaults), then count the use; the stored item is left unchanged
+pub fn copy_filled_item(
+    db: &Database,
+    id: &str,
+    values: &HashMap<String, String>,
+    write: impl FnOnce(&str, bool) -> Result<(), ClipsterError>,
+) -> Result<(), ClipsterError> {
+    let item = db.require_item(id)?;
+    let template = templates::template_text(&item)
+        .ok_or_else(|| ClipsterError::Validation("Only text items can be filled in".to_string()))?;
+    let text = templates::fill(template, values)?;
+
+    let pinboard = item_pinboard(db, &item)?;
+    write(&text, excluded_from_os_history(db, pinboard.as_ref())?)?;
+    db.record_item_use(&item.id)
+}
+
+/// The pinboard an item is filed in, if any: the one it was pinned to, else
+/// the one it was auto-filed in, as `get_pinboards` counts it
+fn item_pinboard(db: &Database, item: &ClipboardItem) -> Result<Option<Pinboard>, ClipsterError> {
+    match item.filed_pinboard_id() {
+        Some(pinboard_id) => db.get_pinboard(pinboard_id),
+        None => Ok(None),
+    }
+}
+
+/// Whether a copied-back item is kept out of the Windows clipboard history:
+/// always for items in locked pinboards, for every item with `exclude_from_os_history`
+fn excluded_from_os_history(
+    db: &Database,
+    pinboard: Option<&Pinboard>,
+) -> Result<bool, ClipsterError> {
+    if db.get_setting("exclude_from_os_history")?.as_deref() == Some("true") {
+        return Ok(true);
+    }
+    Ok(pinboard.is_some_and(|pinboard| pinboard.is_locked))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rich_copy_counts_one_use() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_image(None, "/tmp/shot.png".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        // An image is written as two flavors, like the platform writers do
+        let mut flavors = Vec::new();
+        copy_item(&db, &item.id, None, false, |content, _| {
+            assert_eq!(content, CopyContent::Image("/tmp/shot.png"));
+            flavors.extend(["public.png", "public.tiff"]);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(flavors.len(), 2);
+        assert_eq!(db.require_item(&item.id).unwrap().use_count, 1);
+
+        copy_item(&db, &item.id, None, false, |_, _| Ok(())).unwrap();
+        assert_eq!(db.require_item(&item.id).unwrap().use_count, 2);
+    }
+
+    #[test]
+    fn test_contact_is_copied_as_its_payload() {
+        let db = Database::new_in_memory().unwrap();
+        let vcard = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Jane Doe\r\nEND:VCARD\r\n";
+        let mut item = ClipboardItem::new_text(vcard.to_string(), None, None);
+        item.content_subtype = Some(ContentSubtype::Contact);
+        item.title = Some("Jane Doe".to_string());
+        db.insert_item(&item).unwrap();
+
+        copy_item(&db, &item.id, None, false, |content, _| {
+            assert_eq!(
+                content,
+                CopyContent::Structured(vcard, ContentSubtype::Contact)
+            );
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(db.require_item(&item.id).unwrap().use_count, 1);
+    }
+
+    #[test]
+    fn test_failed_write_is_not_counted() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("hello".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        let result = copy_item(&db, &item.id, None, false, |_, _| {
+            Err(ClipsterError::ClipboardAccess("locked".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(db.require_item(&item.id).unwrap().use_count, 0);
+    }
+
+    #[test]
+    fn test_locked_pinboard_items_are_excluded_from_os_history() {
+        let db = Database::new_in_memory().unwrap();
+        let pinboard = Pinboard::new("Secrets".to_string(), None, 0);
+        db.insert_pinboard(&pinboard).unwrap();
+        let mut item = ClipboardItem::new_text("hunter2".to_string(), None, None);
+        item.pinboard_id = Some(pinboard.id.clone());
+        db.insert_item(&item).unwrap();
+        let plain = ClipboardItem::new_text("hello".to_string(), None, None);
+        db.insert_item(&plain).unwrap();
+
+        let excluded = |id: &str| {
+            let mut excluded = None;
+            copy_item(&db, id, None, false, |_, exclude| {
+                excluded = Some(exclude);
+                Ok(())
+            })
+            .unwrap();
+            excluded.unwrap()
+        };
+        assert!(!excluded(&item.id));
+
+        db.set_pinboard_lock_hash(&pinboard.id, Some("$argon2id$hash"))
+            .unwrap();
+        assert!(excluded(&item.id));
+        assert!(!excluded(&plain.id));
+
+        db.set_setting("exclude_from_os_history", "true").unwrap();
+        assert!(excluded(&plain.id));
+    }
+
+    #[test]
+    fn test_pinboard_default_transform() {
+        let db = Database::new_in_memory().unwrap();
+        let mut sql = Pinboard::new("SQL snippets".to_string(), None, 0);
+        sql.default_transform = Some(Transform::LowercaseTrimmed);
+        db.insert_pinboard(&sql).unwrap();
+        let mut item = ClipboardItem::new_text("  SELECT 1\n".to_string(), None, None);
+        item.pinboard_id = Some(sql.id.clone());
+        db.insert_item(&item).unwrap();
+        let loose = ClipboardItem::new_text("  SELECT 2\n".to_string(), None, None);
+        db.insert_item(&loose).unwrap();
+
+        let written = |id: &str, transform, skip| {
+            let mut written = None;
+            copy_item(&db, id, transform, skip, |content, _| {
+                written = Some(format!("{:?}", content));
+                Ok(())
+            })
+            .unwrap();
+            written.unwrap()
+        };
+        assert_eq!(written(&item.id, None, false), r#"Text("select 1")"#);
+        // An explicit transform wins, even when the default is skipped
+        assert_eq!(
+            written(&item.id, Some(Transform::Trim), false),
+            r#"Text("SELECT 1")"#
+        );
+        assert_eq!(
+            written(&item.id, Some(Transform::Trim), true),
+            r#"Text("SELECT 1")"#
+        );
+        assert_eq!(written(&item.id, None, true), r#"Text("  SELECT 1\n")"#);
+        // Items outside the pinboard are copied as stored
+        assert_eq!(written(&loose.id, None, false), r#"Text("  SELECT 2\n")"#);
+
+        // The stored item is unchanged, and every copy counts
+        let stored = db.require_item(&item.id).unwrap();
+        assert_eq!(stored.content_text.as_deref(), Some("  SELECT 1\n"));
+        assert_eq!(stored.use_count, 4);
+    }
+
+    #[test]
+    fn test_auto_filed_item_gets_default_transform() {
+        let db = Database::new_in_memory().unwrap();
+        let mut sql = Pinboard::new("SQL snippets".to_string(), None, 0);
+        sql.default_transform = Some(Transform::LowercaseTrimmed);
+        db.insert_pinboard(&sql).unwrap();
+        let mut item = ClipboardItem::new_text("  SELECT 1\n".to_string(), None, None);
+        item.auto_pinboard_id = Some(sql.id.clone());
+        db.insert_item(&item).unwrap();
+
+        let mut written = None;
+        copy_item(&db, &item.id, None, false, |content, _| {
+            written = Some(format!("{:?}", content));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(written.as_deref(), Some(r#"Text("select 1")"#));
+    }
+
+    #[test]
+    fn test_plain_text_transform_drops_the_subtype() {
+        let db = Database::new_in_memory().unwrap();
+        let mut emails = Pinboard::new("Emails".to_string(), None, 0);
+        emails.default_transform = Some(Transform::PlainText);
+        db.insert_pinboard(&emails).unwrap();
+        let vcard = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Jane Doe\r\nEND:VCARD\r\n";
+        let mut item = ClipboardItem::new_text(vcard.to_string(), None, None);
+        item.content_subtype = Some(ContentSubtype::Contact);
+        item.pinboard_id = Some(emails.id.clone());
+        db.insert_item(&item).unwrap();
+
+        copy_item(&db, &item.id, None, false, |content, _| {
+            assert_eq!(content, CopyContent::Text(vcard));
+            Ok(())
+        })
+        .unwrap();
+        copy_item(&db, &item.id, None, true, |content, _| {
+            assert_eq!(
+                content,
+                CopyContent::Structured(vcard, ContentSubtype::Contact)
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_filled_copy_keeps_stored_template() {
+        let db = Database::new_in_memory().unwrap();
+        let template = "Hi {name}, your order {order_id} shipped";
+        let item = ClipboardItem::new_text(template.to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        let values = HashMap::from([
+            ("name".to_string(), "Ada".to_string()),
+            ("order_id".to_string(), "42".to_string()),
+        ]);
+        let mut written = None;
+        copy_filled_item(&db, &item.id, &values, |text, _| {
+            written = Some(text.to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(written.as_deref(), Some("Hi Ada, your order 42 shipped"));
+
+        let stored = db.require_item(&item.id).unwrap();
+        assert_eq!(stored.content_text.as_deref(), Some(template));
+        assert_eq!(stored.use_count, 1);
+
+        // A missing value fails before anything is written or counted
+        let result = copy_filled_item(&db, &item.id, &HashMap::new(), |_, _| {
+            panic!("nothing to write")
+        });
+        assert!(matches!(result, Err(ClipsterError::Validation(_))));
+        assert_eq!(db.require_item(&item.id).unwrap().use_count, 1);
+    }
+}
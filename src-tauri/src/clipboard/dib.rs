@@ -0,0 +1,226 @@
+//! Device-independent bitmaps for the Windows clipboard
+//!
+//! Copying an image back puts it on the clipboard twice: as CF_DIB, a
+//! BITMAPINFOHEADER followed by 24-bit BGR pixels that every app can paste,
+//! and as CF_DIBV5, a BITMAPV5HEADER followed by 32-bit BGRA pixels so apps
+//! that understand alpha keep transparency. Both store rows bottom-up, and
+//! each row is padded to a multiple of 4 bytes.
+
+use image::RgbaImage;
+
+/// Size of BITMAPINFOHEADER
+pub const INFO_HEADER_SIZE: u32 = 40;
+/// Size of BITMAPV5HEADER
+pub const V5_HEADER_SIZE: u32 = 124;
+/// Size of the BITMAPFILEHEADER that turns a DIB into a .bmp file
+const FILE_HEADER_SIZE: u32 = 14;
+
+/// Uncompressed pixels
+const BI_RGB: u32 = 0;
+/// Uncompressed pixels laid out by the header's channel masks
+const BI_BITFIELDS: u32 = 3;
+/// 'sRGB' color space tag
+const LCS_SRGB: u32 = 0x7352_4742;
+/// Rendering intent for photographs and images
+const LCS_GM_IMAGES: u32 = 4;
+
+/// Bytes per row for `width` pixels of `bits_per_pixel`, padded to 4 bytes
+pub fn stride(width: u32, bits_per_pixel: u16) -> usize {
+    (width as usize * bits_per_pixel as usize).div_ceil(32) * 4
+}
+
+/// CF_DIB data: BITMAPINFOHEADER and 24-bit BGR rows, bottom-up
+/// Alpha is dropped; transparent pixels keep their color
+pub fn encode_dib(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let stride = stride(width, 24);
+    let pixels_size = stride * height as usize;
+
+    let mut dib = Vec::with_capacity(INFO_HEADER_SIZE as usize + pixels_size);
+    push_info_header(&mut dib, INFO_HEADER_SIZE, image, 24, BI_RGB, pixels_size);
+
+    for row in image.rows().rev() {
+        let start = dib.len();
+        for pixel in row {
+            let [r, g, b, _] = pixel.0;
+            dib.extend_from_slice(&[b, g, r]);
+        }
+        dib.resize(start + stride, 0);
+    }
+    dib
+}
+
+/// CF_DIBV5 data: BITMAPV5HEADER with sRGB channel masks and 32-bit BGRA rows,
+/// bottom-up, straight (not premultiplied) alpha
+pub fn encode_dibv5(image: &RgbaImage) -> Vec<u8> {
+    let (width, height) = image.dimensions();
+    let pixels_size = stride(width, 32) * height as usize;
+
+    let mut dib = Vec::with_capacity(V5_HEADER_SIZE as usize + pixels_size);
+    push_info_header(
+        &mut dib,
+        V5_HEADER_SIZE,
+        image,
+        32,
+        BI_BITFIELDS,
+        pixels_size,
+    );
+    // Red, green, blue and alpha masks
+    for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+        dib.extend_from_slice(&mask.to_le_bytes());
+    }
+    dib.extend_from_slice(&LCS_SRGB.to_le_bytes());
+    // Endpoints (CIEXYZTRIPLE) and gamma, unused for sRGB
+    dib.extend_from_slice(&[0u8; 36 + 12]);
+    dib.extend_from_slice(&LCS_GM_IMAGES.to_le_bytes());
+    // Profile data, profile size, reserved
+    dib.extend_from_slice(&[0u8; 12]);
+
+    for row in image.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    dib
+}
+
+/// The BITMAPINFOHEADER fields shared by both header versions
+fn push_info_header(
+    dib: &mut Vec<u8>,
+    header_size: u32,
+    image: &RgbaImage,
+    bits_per_pixel: u16,
+    compression: u32,
+    pixels_size: usize,
+) {
+    let (width, height) = image.dimensions();
+    dib.extend_from_slice(&header_size.to_le_bytes());
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    // A positive height means bottom-up rows
+    dib.extend_from_slice(&(height as i32).to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&bits_per_pixel.to_le_bytes());
+    dib.extend_from_slice(&compression.to_le_bytes());
+    dib.extend_from_slice(&(pixels_size as u32).to_le_bytes());
+    // Resolution, palette size and important colors: unspecified
+    dib.extend_from_slice(&[0u8; 16]);
+}
+
+/// Prefix DIB data with a BITMAPFILEHEADER so it decodes as a .bmp file
+pub fn bmp_file(dib: &[u8]) -> Vec<u8> {
+    let file_size = FILE_HEADER_SIZE as usize + dib.len();
+    let header_size = match dib.get(..4) {
+        Some(bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        None => INFO_HEADER_SIZE,
+    };
+
+    let mut bmp = Vec::with_capacity(file_size);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&(file_size as u32).to_le_bytes());
+    bmp.extend_from_slice(&[0u8; 4]);
+    bmp.extend_from_slice(&(FILE_HEADER_SIZE + header_size).to_le_bytes());
+    bmp.extend_from_slice(dib);
+    bmp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// 3x2 fixture: odd width so 24-bit rows need padding
+    /// Top row red, green, blue; bottom row white, black, half-transparent gray
+    fn fixture() -> RgbaImage {
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [255, 255, 255, 255],
+            [0, 0, 0, 255],
+            [128, 128, 128, 64],
+        ];
+        RgbaImage::from_fn(3, 2, |x, y| Rgba(pixels[(y * 3 + x) as usize]))
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u16_at(data: &[u8], offset: usize) -> u16 {
+        u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_stride_pads_rows_to_four_bytes() {
+        assert_eq!(stride(3, 24), 12);
+        assert_eq!(stride(4, 24), 12);
+        assert_eq!(stride(5, 24), 16);
+        assert_eq!(stride(1, 24), 4);
+        assert_eq!(stride(3, 32), 12);
+    }
+
+    #[test]
+    fn test_encode_dib_header_and_rows() {
+        let dib = encode_dib(&fixture());
+        assert_eq!(dib.len(), 40 + 2 * 12);
+
+        assert_eq!(u32_at(&dib, 0), INFO_HEADER_SIZE);
+        assert_eq!(u32_at(&dib, 4), 3);
+        assert_eq!(u32_at(&dib, 8), 2);
+        assert_eq!(u16_at(&dib, 12), 1);
+        assert_eq!(u16_at(&dib, 14), 24);
+        assert_eq!(u32_at(&dib, 16), BI_RGB);
+        assert_eq!(u32_at(&dib, 20), 24);
+
+        // Bottom row first, BGR, padded with 3 zero bytes
+        #[rustfmt::skip]
+        let expected: [u8; 24] = [
+            255, 255, 255,  0, 0, 0,  128, 128, 128,  0, 0, 0,
+            0, 0, 255,  0, 255, 0,  255, 0, 0,  0, 0, 0,
+        ];
+        assert_eq!(&dib[40..], &expected);
+    }
+
+    #[test]
+    fn test_encode_dibv5_header_and_rows() {
+        let dib = encode_dibv5(&fixture());
+        assert_eq!(dib.len(), 124 + 2 * 12);
+
+        assert_eq!(u32_at(&dib, 0), V5_HEADER_SIZE);
+        assert_eq!(u16_at(&dib, 14), 32);
+        assert_eq!(u32_at(&dib, 16), BI_BITFIELDS);
+        assert_eq!(u32_at(&dib, 20), 24);
+        assert_eq!(u32_at(&dib, 40), 0x00FF_0000);
+        assert_eq!(u32_at(&dib, 44), 0x0000_FF00);
+        assert_eq!(u32_at(&dib, 48), 0x0000_00FF);
+        assert_eq!(u32_at(&dib, 52), 0xFF00_0000);
+        assert_eq!(u32_at(&dib, 56), LCS_SRGB);
+        assert_eq!(u32_at(&dib, 108), LCS_GM_IMAGES);
+
+        #[rustfmt::skip]
+        let expected: [u8; 24] = [
+            255, 255, 255, 255,  0, 0, 0, 255,  128, 128, 128, 64,
+            0, 0, 255, 255,  0, 255, 0, 255,  255, 0, 0, 255,
+        ];
+        assert_eq!(&dib[124..], &expected);
+    }
+
+    #[test]
+    fn test_dibs_decode_back_to_the_fixture() {
+        let image = fixture();
+
+        let dib = image::load_from_memory(&bmp_file(&encode_dib(&image)))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(dib.dimensions(), (3, 2));
+        for (decoded, original) in dib.pixels().zip(image.pixels()) {
+            assert_eq!(decoded.0[..3], original.0[..3]);
+        }
+
+        let dibv5 = image::load_from_memory(&bmp_file(&encode_dibv5(&image)))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(dibv5, image);
+    }
+}
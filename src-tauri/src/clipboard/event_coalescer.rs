@@ -0,0 +1,240 @@
+//! Coalescing of capture notifications during copy bursts
+//!
+//! Scripted copies (a macro copying 50 cells) used to emit one
+//! `clipboard-changed` event and refresh Spotlight and the recent items menu
+//! once per item. Captures are still saved immediately, but notifying the
+//! frontend and the follow-up work wait until no new capture has arrived for
+//! `COALESCE_WINDOW`. A lone capture is then emitted as the usual single-item
+//! event and a burst as one batch. History pruning is deferred separately, see
+//! `prune_scheduler`.
+
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Quiet time after the last capture before pending captures are flushed
+pub const COALESCE_WINDOW: Duration = Duration::from_millis(150);
+
+/// Longest a capture waits during a continuous burst
+pub const MAX_COALESCE_DELAY: Duration = Duration::from_secs(1);
+
+/// Time source, replaced by a fake clock in tests
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// Wall clock used by the monitor
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Receives the work deferred until a burst ends
+pub trait CaptureSink<T> {
+    /// Follow-up work done once per flush instead of once per capture
+    fn after_flush(&self);
+
+    /// A single capture: the usual `clipboard-changed` event
+    fn emit_one(&self, capture: T);
+
+    /// Several captures within the window, oldest first
+    fn emit_batch(&self, captures: Vec<T>);
+}
+
+/// Collects captures until the burst they belong to is over
+#[derive(Debug)]
+pub struct EventCoalescer<T, C: Clock = SystemClock> {
+    clock: C,
+    pending: Vec<T>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl<T, C: Clock> EventCoalescer<T, C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            pending: Vec::new(),
+            first_at: None,
+            last_at: None,
+        }
+    }
+
+    /// Queue a capture that was just saved
+    pub fn push(&mut self, capture: T) {
+        let now = self.clock.now();
+        self.first_at.get_or_insert(now);
+        self.last_at = Some(now);
+        self.pending.push(capture);
+    }
+
+    /// When the pending captures are due (None if nothing is pending)
+    pub fn deadline(&self) -> Option<Instant> {
+        let (first_at, last_at) = (self.first_at?, self.last_at?);
+        Some((last_at + COALESCE_WINDOW).min(first_at + MAX_COALESCE_DELAY))
+    }
+
+    /// Flush if the deadline has passed; returns whether anything was flushed
+    pub fn flush_due<S: CaptureSink<T>>(&mut self, sink: &S) -> bool {
+        match self.deadline() {
+            Some(deadline) if self.clock.now() >= deadline => {
+                self.flush(sink);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Flush regardless of the deadline (e.g. when the monitor stops)
+    pub fn flush<S: CaptureSink<T>>(&mut self, sink: &S) {
+        self.first_at = None;
+        self.last_at = None;
+        let mut captures = std::mem::take(&mut self.pending);
+        if captures.is_empty() {
+            return;
+        }
+
+        sink.after_flush();
+        if captures.len() == 1 {
+            sink.emit_one(captures.remove(0));
+        } else {
+            sink.emit_batch(captures);
+        }
+    }
+}
+
+/// Run a coalescer over captures sent by the monitor until the sender is dropped
+pub fn run_worker<T, S: CaptureSink<T>>(sink: &S, captures: Receiver<T>) {
+    let mut coalescer = EventCoalescer::new(SystemClock);
+    loop {
+        let received = match coalescer.deadline() {
+            Some(deadline) => {
+                captures.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => captures.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok(capture) => coalescer.push(capture),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                coalescer.flush(sink);
+                return;
+            }
+        }
+        // Also checked after a capture so a continuous burst can't postpone forever
+        coalescer.flush_due(sink);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+    use std::cell::{Cell, RefCell};
+
+    #[derive(Debug, PartialEq)]
+    enum Emitted {
+        One(u32),
+        Batch(Vec<u32>),
+    }
+
+    #[derive(Default)]
+    struct CapturingSink {
+        flushes: Cell<usize>,
+        emitted: RefCell<Vec<Emitted>>,
+    }
+
+    impl CaptureSink<u32> for CapturingSink {
+        fn after_flush(&self) {
+            self.flushes.set(self.flushes.get() + 1);
+        }
+
+        fn emit_one(&self, capture: u32) {
+            self.emitted.borrow_mut().push(Emitted::One(capture));
+        }
+
+        fn emit_batch(&self, captures: Vec<u32>) {
+            self.emitted.borrow_mut().push(Emitted::Batch(captures));
+        }
+    }
+
+    #[test]
+    fn test_single_capture_emits_single_event() {
+        let clock = FakeClock::new();
+        let sink = CapturingSink::default();
+        let mut coalescer = EventCoalescer::new(clock.clone());
+
+        assert!(!coalescer.flush_due(&sink));
+        coalescer.push(1);
+        clock.advance(COALESCE_WINDOW - Duration::from_millis(1));
+        assert!(!coalescer.flush_due(&sink));
+        assert_eq!(sink.flushes.get(), 0);
+
+        clock.advance(Duration::from_millis(1));
+        assert!(coalescer.flush_due(&sink));
+        assert_eq!(*sink.emitted.borrow(), vec![Emitted::One(1)]);
+        assert_eq!(sink.flushes.get(), 1);
+        assert_eq!(coalescer.deadline(), None);
+    }
+
+    #[test]
+    fn test_burst_emits_one_batch_and_one_follow_up() {
+        let clock = FakeClock::new();
+        let sink = CapturingSink::default();
+        let mut coalescer = EventCoalescer::new(clock.clone());
+
+        for i in 0..50 {
+            coalescer.push(i);
+            clock.advance(Duration::from_millis(10));
+            assert!(!coalescer.flush_due(&sink));
+        }
+        clock.advance(COALESCE_WINDOW);
+        assert!(coalescer.flush_due(&sink));
+
+        assert_eq!(
+            *sink.emitted.borrow(),
+            vec![Emitted::Batch((0..50).collect())]
+        );
+        assert_eq!(sink.flushes.get(), 1);
+    }
+
+    #[test]
+    fn test_continuous_burst_flushes_after_max_delay() {
+        let clock = FakeClock::new();
+        let sink = CapturingSink::default();
+        let mut coalescer = EventCoalescer::new(clock.clone());
+        let start = clock.now();
+
+        let mut i = 0;
+        while !coalescer.flush_due(&sink) {
+            coalescer.push(i);
+            i += 1;
+            clock.advance(Duration::from_millis(100));
+        }
+        assert_eq!(clock.now() - start, MAX_COALESCE_DELAY);
+        assert_eq!(sink.emitted.borrow().len(), 1);
+
+        // Captures after the flush start a new burst
+        coalescer.push(i);
+        clock.advance(COALESCE_WINDOW);
+        assert!(coalescer.flush_due(&sink));
+        assert_eq!(sink.emitted.borrow()[1], Emitted::One(i));
+    }
+
+    #[test]
+    fn test_worker_flushes_on_disconnect() {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for i in 0..3 {
+            sender.send(i).unwrap();
+        }
+        drop(sender);
+
+        let sink = CapturingSink::default();
+        run_worker(&sink, receiver);
+        assert_eq!(*sink.emitted.borrow(), vec![Emitted::Batch(vec![0, 1, 2])]);
+    }
+}
@@ -0,0 +1,172 @@
+//! Frontend events sent off the capture path
+//!
+//! `AppHandle::emit` can stall for hundreds of milliseconds while the webview
+//! is busy or the window is being recreated. The monitor and its workers push
+//! events onto an `EventQueue` instead, and a dedicated emitter task sends them
+//! in order, so saving a capture never waits on the frontend. The queue has no
+//! bound of its own, but past `MAX_QUEUED_EVENTS` waiting events new ones are
+//! dropped and counted; the frontend catches up on missed captures with
+//! `get_changes_since`.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+
+/// Waiting events past which new events are dropped
+pub const MAX_QUEUED_EVENTS: usize = 10_000;
+
+/// An event waiting to be emitted
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedEvent {
+    pub name: &'static str,
+    pub payload: Value,
+}
+
+#[derive(Debug, Default)]
+struct QueueStats {
+    queued: AtomicUsize,
+    dropped: AtomicU64,
+}
+
+/// Sending side, cheap to clone
+#[derive(Debug, Clone)]
+pub struct EventQueue {
+    sender: Sender<QueuedEvent>,
+    stats: Arc<QueueStats>,
+    max_queued: usize,
+}
+
+/// Receiving side, handed to `run_emitter`
+pub struct EventReceiver {
+    receiver: Receiver<QueuedEvent>,
+    stats: Arc<QueueStats>,
+}
+
+impl EventQueue {
+    /// Queue dropping events once `max_queued` are waiting
+    pub fn new(max_queued: usize) -> (Self, EventReceiver) {
+        let (sender, receiver) = mpsc::channel();
+        let stats = Arc::new(QueueStats::default());
+        let queue = Self {
+            sender,
+            stats: Arc::clone(&stats),
+            max_queued,
+        };
+        (queue, EventReceiver { receiver, stats })
+    }
+
+    /// Queue `payload` for the event `name` without waiting for it to be sent
+    /// Returns false when the event was dropped (queue full or emitter stopped)
+    pub fn push<P: Serialize>(&self, name: &'static str, payload: &P) -> bool {
+        if self.stats.queued.load(Ordering::Relaxed) >= self.max_queued {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        let payload = match serde_json::to_value(payload) {
+            Ok(payload) => payload,
+            Err(e) => {
+                clipster_log!(Warn, "[event_queue] Failed to serialize {}: {}", name, e);
+                return false;
+            }
+        };
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+        if self.sender.send(QueuedEvent { name, payload }).is_err() {
+            self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    /// Events dropped since the queue was created
+    pub fn dropped(&self) -> u64 {
+        self.stats.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Events waiting to be emitted
+    pub fn queued(&self) -> usize {
+        self.stats.queued.load(Ordering::Relaxed)
+    }
+}
+
+/// Send queued events with `emit`, in order, until every `EventQueue` is dropped
+pub fn run_emitter<E>(events: EventReceiver, mut emit: E)
+where
+    E: FnMut(&QueuedEvent) -> Result<(), String>,
+{
+    for event in events.receiver.iter() {
+        events.stats.queued.fetch_sub(1, Ordering::Relaxed);
+        if let Err(e) = emit(&event) {
+            clipster_log!(Warn, "[event_queue] Failed to emit {}: {}", event.name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardItem;
+    use crate::storage::Database;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_slow_emitter_does_not_delay_inserts() {
+        let db = Database::new_in_memory().unwrap();
+        let (queue, receiver) = EventQueue::new(MAX_QUEUED_EVENTS);
+        let emitter = thread::spawn(move || {
+            let mut emitted = Vec::new();
+            run_emitter(receiver, |event| {
+                // A webview busy for 50ms per event
+                thread::sleep(Duration::from_millis(50));
+                emitted.push(event.payload["id"].as_str().unwrap().to_string());
+                Ok(())
+            });
+            emitted
+        });
+
+        let started = Instant::now();
+        let mut ids = Vec::new();
+        for i in 0..20 {
+            let item = ClipboardItem::new_text(format!("capture {}", i), None, None);
+            db.insert_item(&item).unwrap();
+            assert!(queue.push("clipboard-changed", &serde_json::json!({ "id": item.id })));
+            ids.push(item.id);
+        }
+        // Emitting them takes a second; saving them must not wait for it
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        drop(queue);
+        assert_eq!(emitter.join().unwrap(), ids);
+    }
+
+    #[test]
+    fn test_full_queue_drops_and_counts() {
+        let (queue, receiver) = EventQueue::new(2);
+        assert!(queue.push("a", &1));
+        assert!(queue.push("b", &2));
+        assert!(!queue.push("c", &3));
+        assert_eq!(queue.queued(), 2);
+        assert_eq!(queue.dropped(), 1);
+
+        let sender = queue.clone();
+        drop(queue);
+        let emitter = thread::spawn(move || {
+            let mut names = Vec::new();
+            run_emitter(receiver, |event| {
+                names.push(event.name);
+                Err("window closed".to_string())
+            });
+            names
+        });
+        // Room again once the emitter catches up
+        while sender.queued() > 0 {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(sender.push("d", &4));
+        drop(sender);
+        assert_eq!(emitter.join().unwrap(), vec!["a", "b", "d"]);
+    }
+}
@@ -0,0 +1,385 @@
+//! Favicon previews for links without an Open Graph image
+//!
+//! Docs pages and internal tools often have no og:image, so their Link items
+//! used to render as bare text. Their icon is used instead: the largest
+//! `<link rel="icon">` / `apple-touch-icon` the page declares, else
+//! `/favicon.ico`, centered on a neutral card the size of an OG thumbnail.
+//! `.ico` files hold several sizes; the largest readable one is picked here
+//! rather than by the decoder, so a broken entry falls back to the next.
+
+use crate::error::{ClipsterError, Context};
+use crate::storage::file_storage;
+use image::{DynamicImage, ImageFormat, Rgba};
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Size of the preview card (the 1.91:1 shape of OG images)
+pub const PREVIEW_WIDTH: u32 = 400;
+pub const PREVIEW_HEIGHT: u32 = 210;
+
+/// Largest side of the icon on the card
+const ICON_MAX_SIZE: u32 = 128;
+
+/// Neutral card color, readable in light and dark themes
+const BACKGROUND: Rgba<u8> = Rgba([236, 236, 240, 255]);
+
+/// Size assumed for an apple-touch-icon without a `sizes` attribute
+const APPLE_TOUCH_ICON_SIZE: u32 = 180;
+
+/// ICONDIR header and ICONDIRENTRY sizes
+const ICO_HEADER_LEN: usize = 6;
+const ICO_ENTRY_LEN: usize = 16;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Icon URLs to try for a page, best first; `/favicon.ico` comes last
+pub fn icon_candidates(html: &str, page_url: &str) -> Vec<String> {
+    let Ok(base) = Url::parse(page_url) else {
+        return Vec::new();
+    };
+    let document = Html::parse_document(html);
+    let Ok(selector) = Selector::parse("link[rel][href]") else {
+        return Vec::new();
+    };
+
+    let mut declared: Vec<(u32, String)> = document
+        .select(&selector)
+        .filter_map(|link| {
+            let attrs = link.value();
+            let rel = attrs.attr("rel")?.to_ascii_lowercase();
+            let is_touch_icon = rel
+                .split_whitespace()
+                .any(|token| token.starts_with("apple-touch-icon"));
+            if !is_touch_icon && !rel.split_whitespace().any(|token| token == "icon") {
+                return None;
+            }
+
+            // SVG icons can't be decoded
+            let href = attrs.attr("href")?.trim();
+            if href.is_empty() || attrs.attr("type") == Some("image/svg+xml") {
+                return None;
+            }
+            let url = base.join(href).ok()?;
+            if url.path().to_ascii_lowercase().ends_with(".svg") {
+                return None;
+            }
+
+            let size = attrs
+                .attr("sizes")
+                .and_then(largest_declared_size)
+                .unwrap_or(if is_touch_icon {
+                    APPLE_TOUCH_ICON_SIZE
+                } else {
+                    0
+                });
+            Some((size, url.to_string()))
+        })
+        .collect();
+    // Stable: equally sized icons keep document order
+    declared.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+
+    let mut candidates: Vec<String> = Vec::new();
+    let fallback = base.join("/favicon.ico").ok().map(|url| url.to_string());
+    for url in declared.into_iter().map(|(_, url)| url).chain(fallback) {
+        if !candidates.contains(&url) {
+            candidates.push(url);
+        }
+    }
+    candidates
+}
+
+/// Largest side in a `sizes` attribute ("16x16 32x32"); None for "any"
+fn largest_declared_size(sizes: &str) -> Option<u32> {
+    sizes
+        .split_whitespace()
+        .filter_map(|size| {
+            let (width, height) = size
+                .to_ascii_lowercase()
+                .split_once('x')
+                .map(|(w, h)| (w.parse::<u32>(), h.parse::<u32>()))?;
+            Some(width.ok()?.max(height.ok()?))
+        })
+        .max()
+}
+
+/// Decode a downloaded icon: ICO, or any format the image crate reads
+pub fn decode_icon(data: &[u8]) -> Result<DynamicImage, ClipsterError> {
+    if is_ico(data) {
+        return decode_ico(data);
+    }
+    image::load_from_memory(data).context("Failed to decode icon")
+}
+
+/// Center the icon on the preview card
+pub fn preview_card(icon: &DynamicImage) -> DynamicImage {
+    file_storage::pad_onto_canvas(
+        icon,
+        PREVIEW_WIDTH,
+        PREVIEW_HEIGHT,
+        ICON_MAX_SIZE,
+        BACKGROUND,
+    )
+}
+
+/// Check for the ICONDIR header of an icon (not cursor) file
+fn is_ico(data: &[u8]) -> bool {
+    data.starts_with(&[0, 0, 1, 0])
+}
+
+/// One image of an ICO file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcoEntry {
+    pub width: u32,
+    pub height: u32,
+    pub bit_count: u16,
+    /// Byte range of the entry's PNG or BMP data in the file
+    pub offset: usize,
+    pub len: usize,
+}
+
+/// Parse the ICO directory; entries pointing outside the file are skipped
+pub fn ico_entries(data: &[u8]) -> Result<Vec<IcoEntry>, ClipsterError> {
+    if data.len() < ICO_HEADER_LEN || !is_ico(data) {
+        return Err(ClipsterError::ImageDecode("Not an ICO file".to_string()));
+    }
+    let count = u16::from_le_bytes([data[4], data[5]]) as usize;
+
+    let entries: Vec<IcoEntry> = (0..count)
+        .filter_map(|i| {
+            let start = ICO_HEADER_LEN + i * ICO_ENTRY_LEN;
+            let entry = data.get(start..start + ICO_ENTRY_LEN)?;
+            let read_u32 = |at: usize| {
+                u32::from_le_bytes([entry[at], entry[at + 1], entry[at + 2], entry[at + 3]])
+            };
+            let (offset, len) = (read_u32(12) as usize, read_u32(8) as usize);
+            if len == 0 || offset.checked_add(len)? > data.len() {
+                return None;
+            }
+            Some(IcoEntry {
+                // 0 means 256
+                width: if entry[0] == 0 { 256 } else { entry[0] as u32 },
+                height: if entry[1] == 0 { 256 } else { entry[1] as u32 },
+                bit_count: u16::from_le_bytes([entry[6], entry[7]]),
+                offset,
+                len,
+            })
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return Err(ClipsterError::ImageDecode(
+            "ICO file has no images".to_string(),
+        ));
+    }
+    Ok(entries)
+}
+
+/// Decode the largest (then deepest) image of an ICO file, falling back to
+/// smaller ones when an entry is unreadable
+pub fn decode_ico(data: &[u8]) -> Result<DynamicImage, ClipsterError> {
+    let mut entries = ico_entries(data)?;
+    entries.sort_by_key(|entry| std::cmp::Reverse((entry.width * entry.height, entry.bit_count)));
+
+    let mut last_error = None;
+    for entry in &entries {
+        match decode_ico_entry(data, entry) {
+            Ok(image) => return Ok(image),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| ClipsterError::ImageDecode("ICO file has no images".to_string())))
+}
+
+/// Decode one entry: embedded PNG as is, BMP data through a one-entry ICO
+/// (its DIB has the doubled height and AND mask only the ICO decoder handles)
+fn decode_ico_entry(data: &[u8], entry: &IcoEntry) -> Result<DynamicImage, ClipsterError> {
+    let bytes = &data[entry.offset..entry.offset + entry.len];
+    if bytes.starts_with(PNG_SIGNATURE) {
+        return image::load_from_memory_with_format(bytes, ImageFormat::Png)
+            .context("Failed to decode ICO entry");
+    }
+
+    // 256 is stored as 0
+    let dimension = |size: u32| if size >= 256 { 0 } else { size as u8 };
+    let mut single = Vec::with_capacity(ICO_HEADER_LEN + ICO_ENTRY_LEN + bytes.len());
+    single.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+    single.extend_from_slice(&[dimension(entry.width), dimension(entry.height), 0, 0]);
+    single.extend_from_slice(&1u16.to_le_bytes());
+    single.extend_from_slice(&entry.bit_count.to_le_bytes());
+    single.extend_from_slice(&(entry.len as u32).to_le_bytes());
+    single.extend_from_slice(&((ICO_HEADER_LEN + ICO_ENTRY_LEN) as u32).to_le_bytes());
+    single.extend_from_slice(bytes);
+    image::load_from_memory_with_format(&single, ImageFormat::Ico)
+        .context("Failed to decode ICO entry")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, ImageOutputFormat};
+
+    fn png(size: u32, color: [u8; 4]) -> Vec<u8> {
+        let image = DynamicImage::ImageRgba8(ImageBuffer::from_pixel(size, size, Rgba(color)));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, ImageOutputFormat::Png).unwrap();
+        bytes.into_inner()
+    }
+
+    /// 32-bit BMP ICO entry data: BITMAPINFOHEADER with doubled height,
+    /// bottom-up BGRA pixels, then an all-zero (opaque) AND mask
+    fn bmp_entry(size: u32, bgra: [u8; 4]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&40u32.to_le_bytes());
+        data.extend_from_slice(&(size as i32).to_le_bytes());
+        data.extend_from_slice(&(size as i32 * 2).to_le_bytes());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&32u16.to_le_bytes());
+        data.extend_from_slice(&[0; 24]);
+        for _ in 0..size * size {
+            data.extend_from_slice(&bgra);
+        }
+        let mask_row = (size.div_ceil(32) * 4) as usize;
+        data.extend(std::iter::repeat_n(0, mask_row * size as usize));
+        data
+    }
+
+    /// ICO file from (size, bit count, entry data) in directory order
+    fn ico(images: &[(u32, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut file = vec![0, 0, 1, 0];
+        file.extend_from_slice(&(images.len() as u16).to_le_bytes());
+        let mut offset = ICO_HEADER_LEN + images.len() * ICO_ENTRY_LEN;
+        for (size, bit_count, data) in images {
+            let dimension = if *size >= 256 { 0 } else { *size as u8 };
+            file.extend_from_slice(&[dimension, dimension, 0, 0]);
+            file.extend_from_slice(&1u16.to_le_bytes());
+            file.extend_from_slice(&bit_count.to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += data.len();
+        }
+        for (_, _, data) in images {
+            file.extend_from_slice(data);
+        }
+        file
+    }
+
+    #[test]
+    fn test_multi_size_ico_picks_largest() {
+        let file = ico(&[
+            (16, 32, bmp_entry(16, [0, 0, 255, 255])),
+            (48, 32, bmp_entry(48, [0, 255, 0, 255])),
+            (32, 32, png(32, [0, 0, 255, 255])),
+        ]);
+
+        let entries = ico_entries(&file).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.width).collect::<Vec<_>>(),
+            vec![16, 48, 32]
+        );
+
+        let image = decode_icon(&file).unwrap();
+        assert_eq!((image.width(), image.height()), (48, 48));
+        assert_eq!(image.to_rgba8().get_pixel(0, 0).0, [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn test_ico_with_embedded_png_and_256px_entry() {
+        let file = ico(&[
+            (16, 32, bmp_entry(16, [0, 0, 0, 255])),
+            (256, 32, png(256, [1, 2, 3, 255])),
+        ]);
+        assert_eq!(ico_entries(&file).unwrap()[1].width, 256);
+
+        let image = decode_icon(&file).unwrap();
+        assert_eq!(image.width(), 256);
+        assert_eq!(image.to_rgba8().get_pixel(10, 10).0, [1, 2, 3, 255]);
+    }
+
+    #[test]
+    fn test_broken_ico_entry_falls_back_to_smaller() {
+        let mut broken = png(64, [0, 0, 0, 255]);
+        broken.truncate(40);
+        let file = ico(&[(64, 32, broken), (16, 32, bmp_entry(16, [255, 0, 0, 255]))]);
+
+        let image = decode_icon(&file).unwrap();
+        assert_eq!(image.width(), 16);
+        // BGRA in the BMP: blue
+        assert_eq!(image.to_rgba8().get_pixel(0, 0).0, [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_malformed_ico_errors() {
+        assert!(decode_icon(&[0, 0, 1, 0, 0, 0]).is_err());
+        // Entry pointing past the end of the file
+        let mut file = ico(&[(16, 32, bmp_entry(16, [0, 0, 0, 255]))]);
+        file.truncate(100);
+        assert_eq!(decode_icon(&file).unwrap_err().code(), "image_decode");
+        assert!(decode_icon(b"<html>not an icon</html>").is_err());
+    }
+
+    #[test]
+    fn test_decode_plain_png_icon() {
+        let image = decode_icon(&png(32, [9, 9, 9, 255])).unwrap();
+        assert_eq!(image.width(), 32);
+    }
+
+    #[test]
+    fn test_preview_card_size() {
+        let card = preview_card(&decode_icon(&png(16, [9, 9, 9, 255])).unwrap());
+        assert_eq!(
+            (card.width(), card.height()),
+            (PREVIEW_WIDTH, PREVIEW_HEIGHT)
+        );
+        assert_eq!(card.to_rgba8().get_pixel(0, 0).0, BACKGROUND.0);
+        assert_eq!(card.to_rgba8().get_pixel(200, 105).0, [9, 9, 9, 255]);
+    }
+
+    #[test]
+    fn test_icon_candidates() {
+        let html = r#"<html><head>
+            <link rel="icon" href="/favicon-16.png" sizes="16x16">
+            <link rel="shortcut icon" href="/static/favicon.ico">
+            <link rel="icon" type="image/svg+xml" href="/icon.svg">
+            <link rel="mask-icon" href="/mask.svg">
+            <link rel="apple-touch-icon" href="touch.png">
+            <link rel="ICON" href="https://cdn.example.com/icon-64.png" sizes="32x32 64x64">
+            <link rel="stylesheet" href="/style.css">
+        </head></html>"#;
+
+        assert_eq!(
+            icon_candidates(html, "https://docs.example.com/guide/intro"),
+            vec![
+                "https://docs.example.com/guide/touch.png",
+                "https://cdn.example.com/icon-64.png",
+                "https://docs.example.com/favicon-16.png",
+                "https://docs.example.com/static/favicon.ico",
+                "https://docs.example.com/favicon.ico",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_icon_candidates_fall_back_to_favicon_ico() {
+        assert_eq!(
+            icon_candidates("<html></html>", "https://intranet.local:8080/a/b?c=d"),
+            vec!["https://intranet.local:8080/favicon.ico"]
+        );
+        // Declared /favicon.ico isn't listed twice
+        assert_eq!(
+            icon_candidates(
+                r#"<link rel="icon" href="/favicon.ico">"#,
+                "https://example.com/"
+            ),
+            vec!["https://example.com/favicon.ico"]
+        );
+        assert!(icon_candidates("", "not a url").is_empty());
+    }
+
+    #[test]
+    fn test_largest_declared_size() {
+        assert_eq!(largest_declared_size("16x16 32x32"), Some(32));
+        assert_eq!(largest_declared_size("192X96"), Some(192));
+        assert_eq!(largest_declared_size("any"), None);
+    }
+}
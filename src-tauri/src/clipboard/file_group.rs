@@ -0,0 +1,337 @@
+//! Summary and collage preview of multi-file copies
+//!
+//! Copying 40 photos from one folder used to show up as just "40 files". A
+//! multi-file copy now gets a summary of where the files are and what they
+//! are ("40 files from ~/Photos/Trip — 38 jpg, 2 mov") and, when several of
+//! them are images, a collage of the first four as its thumbnail.
+
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+/// Side of the square collage, before the usual thumbnail resize
+pub const COLLAGE_SIZE: u32 = 400;
+
+/// Space between collage cells
+const COLLAGE_GAP: u32 = 4;
+
+/// Behind the gaps (and the edges of images that don't fill their cell)
+const COLLAGE_BACKGROUND: Rgba<u8> = Rgba([40, 40, 44, 255]);
+
+/// Images shown in a collage
+pub const COLLAGE_MAX_IMAGES: usize = 4;
+
+/// Extensions listed by name in a summary; the rest are counted as "other"
+const MAX_LISTED_EXTENSIONS: usize = 3;
+
+/// Extensions the image crate can decode for a collage
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tif", "tiff", "ico", "tga",
+];
+
+/// Position and size of one image in the collage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollageCell {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Summary of a multi-file copy (None for a single file, which shows its name)
+pub fn summarize(paths: &[String], home: Option<&Path>) -> Option<String> {
+    if paths.len() < 2 {
+        return None;
+    }
+
+    let mut summary = format!("{} files", paths.len());
+    if let Some(dir) = common_parent(paths) {
+        summary.push_str(&format!(" from {}", display_dir(&dir, home)));
+    }
+
+    let listed: Vec<(String, usize)> = extension_counts(paths)
+        .into_iter()
+        .filter(|(extension, _)| !extension.is_empty())
+        .take(MAX_LISTED_EXTENSIONS)
+        .collect();
+    let listed_files: usize = listed.iter().map(|(_, count)| count).sum();
+    let mut parts: Vec<String> = listed
+        .iter()
+        .map(|(extension, count)| format!("{} {}", count, extension))
+        .collect();
+    if listed_files < paths.len() {
+        // Rarer extensions and files without one
+        parts.push(format!("{} other", paths.len() - listed_files));
+    }
+    summary.push_str(&format!(" — {}", parts.join(", ")));
+
+    Some(summary)
+}
+
+/// Deepest directory containing all of `paths` (None if they only share the
+/// filesystem root, e.g. files from different drives)
+pub fn common_parent(paths: &[String]) -> Option<PathBuf> {
+    let mut parents = paths.iter().map(|path| Path::new(path).parent());
+    let mut common: Vec<_> = parents.next()??.components().collect();
+    for parent in parents {
+        let shared = common
+            .iter()
+            .zip(parent?.components())
+            .take_while(|(a, b)| **a == *b)
+            .count();
+        common.truncate(shared);
+    }
+
+    let dir: PathBuf = common.into_iter().collect();
+    (dir.file_name().is_some()).then_some(dir)
+}
+
+/// Number of files per lowercase extension, most common first ("" for files
+/// without one)
+pub fn extension_counts(paths: &[String]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for path in paths {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        *counts.entry(extension).or_default() += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+/// `dir` with the home directory shortened to `~`
+fn display_dir(dir: &Path, home: Option<&Path>) -> String {
+    match home.and_then(|home| dir.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~{}{}", MAIN_SEPARATOR, rest.display()),
+        None => dir.display().to_string(),
+    }
+}
+
+/// The first image files of a copy, in copy order, for the collage
+pub fn collage_image_paths(paths: &[String]) -> Vec<&str> {
+    paths
+        .iter()
+        .filter(|path| is_image_path(path))
+        .take(COLLAGE_MAX_IMAGES)
+        .map(String::as_str)
+        .collect()
+}
+
+fn is_image_path(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Cells of a collage of `count` images in a `size` square: side by side for
+/// two, one tall and two stacked for three, a 2x2 grid for four
+pub fn collage_cells(count: usize, size: u32) -> Vec<CollageCell> {
+    let half = (size - COLLAGE_GAP) / 2;
+    let far = size - half;
+    let cell = |x, y, width, height| CollageCell {
+        x,
+        y,
+        width,
+        height,
+    };
+
+    match count {
+        0 => Vec::new(),
+        1 => vec![cell(0, 0, size, size)],
+        2 => vec![cell(0, 0, half, size), cell(far, 0, half, size)],
+        3 => vec![
+            cell(0, 0, half, size),
+            cell(far, 0, half, half),
+            cell(far, far, half, half),
+        ],
+        _ => vec![
+            cell(0, 0, half, half),
+            cell(far, 0, half, half),
+            cell(0, far, half, half),
+            cell(far, far, half, half),
+        ],
+    }
+}
+
+/// Collage of up to four images, each cropped to fill its cell
+pub fn render_collage(images: &[DynamicImage]) -> DynamicImage {
+    let images = &images[..images.len().min(COLLAGE_MAX_IMAGES)];
+    let mut canvas = RgbaImage::from_pixel(COLLAGE_SIZE, COLLAGE_SIZE, COLLAGE_BACKGROUND);
+    for (image, cell) in images.iter().zip(collage_cells(images.len(), COLLAGE_SIZE)) {
+        let tile = image.resize_to_fill(cell.width, cell.height, FilterType::Triangle);
+        imageops::overlay(
+            &mut canvas,
+            &tile.to_rgba8(),
+            i64::from(cell.x),
+            i64::from(cell.y),
+        );
+    }
+    DynamicImage::ImageRgba8(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<String> {
+        paths.iter().map(|path| path.to_string()).collect()
+    }
+
+    #[test]
+    fn test_summary_of_photo_folder() {
+        let mut files: Vec<String> = (0..38)
+            .map(|i| format!("/Users/me/Photos/Trip/IMG_{:04}.JPG", i))
+            .collect();
+        files.push("/Users/me/Photos/Trip/clip1.mov".to_string());
+        files.push("/Users/me/Photos/Trip/clip2.MOV".to_string());
+
+        assert_eq!(
+            summarize(&files, Some(Path::new("/Users/me"))).as_deref(),
+            Some("40 files from ~/Photos/Trip — 38 jpg, 2 mov")
+        );
+        // Outside the home directory the full path is shown
+        assert_eq!(
+            summarize(&files, Some(Path::new("/Users/other"))).as_deref(),
+            Some("40 files from /Users/me/Photos/Trip — 38 jpg, 2 mov")
+        );
+    }
+
+    #[test]
+    fn test_summary_single_file_keeps_name() {
+        assert_eq!(summarize(&paths(&["/tmp/a.txt"]), None), None);
+        assert_eq!(summarize(&[], None), None);
+    }
+
+    #[test]
+    fn test_summary_counts_rare_extensions_as_other() {
+        let files = paths(&[
+            "/data/a.csv",
+            "/data/b.csv",
+            "/data/c.json",
+            "/data/d.md",
+            "/data/e.png",
+            "/data/Makefile",
+        ]);
+        assert_eq!(
+            summarize(&files, None).as_deref(),
+            Some("6 files from /data — 2 csv, 1 json, 1 md, 2 other")
+        );
+    }
+
+    #[test]
+    fn test_common_parent() {
+        assert_eq!(
+            common_parent(&paths(&["/a/b/c/1.txt", "/a/b/2.txt", "/a/b/c/d/3.txt"])),
+            Some(PathBuf::from("/a/b"))
+        );
+        // Sibling folders share their parent
+        assert_eq!(
+            common_parent(&paths(&["/a/x/1.txt", "/a/y/2.txt"])),
+            Some(PathBuf::from("/a"))
+        );
+        // Only the root in common
+        assert_eq!(common_parent(&paths(&["/x/1.txt", "/y/2.txt"])), None);
+        assert_eq!(common_parent(&paths(&["1.txt", "2.txt"])), None);
+    }
+
+    #[test]
+    fn test_summary_without_common_parent() {
+        let files = paths(&["/x/1.txt", "/y/2.txt"]);
+        assert_eq!(summarize(&files, None).as_deref(), Some("2 files — 2 txt"));
+    }
+
+    #[test]
+    fn test_collage_image_paths() {
+        let files = paths(&[
+            "/p/notes.txt",
+            "/p/1.jpg",
+            "/p/2.PNG",
+            "/p/clip.mov",
+            "/p/3.webp",
+            "/p/4.gif",
+            "/p/5.jpeg",
+        ]);
+        assert_eq!(
+            collage_image_paths(&files),
+            vec!["/p/1.jpg", "/p/2.PNG", "/p/3.webp", "/p/4.gif"]
+        );
+        assert!(collage_image_paths(&paths(&["/p/a.pdf", "/p/b.docx"])).is_empty());
+    }
+
+    #[test]
+    fn test_collage_cells_layout() {
+        let size = 400;
+        let half = (size - COLLAGE_GAP) / 2;
+
+        let grid = collage_cells(4, size);
+        assert_eq!(grid.len(), 4);
+        assert_eq!(
+            grid[0],
+            CollageCell {
+                x: 0,
+                y: 0,
+                width: half,
+                height: half
+            }
+        );
+        assert_eq!(grid[3].x + grid[3].width, size);
+        assert_eq!(grid[3].y + grid[3].height, size);
+        // Cells never overlap and keep the gap between them
+        assert_eq!(grid[1].x - (grid[0].x + grid[0].width), COLLAGE_GAP);
+        assert_eq!(grid[2].y - (grid[0].y + grid[0].height), COLLAGE_GAP);
+
+        let three = collage_cells(3, size);
+        assert_eq!(three[0].height, size);
+        assert_eq!((three[1].height, three[2].height), (half, half));
+
+        let two = collage_cells(2, size);
+        assert!(two
+            .iter()
+            .all(|cell| cell.height == size && cell.width == half));
+
+        assert_eq!(
+            collage_cells(1, size),
+            vec![CollageCell {
+                x: 0,
+                y: 0,
+                width: size,
+                height: size
+            }]
+        );
+        assert!(collage_cells(0, size).is_empty());
+        // More images than cells still fill a 2x2 grid
+        assert_eq!(collage_cells(9, size), grid);
+    }
+
+    #[test]
+    fn test_render_collage() {
+        let colors = [
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 255, 0, 255]),
+            Rgba([0, 0, 255, 255]),
+            Rgba([255, 255, 0, 255]),
+        ];
+        let images: Vec<DynamicImage> = colors
+            .iter()
+            .map(|color| DynamicImage::ImageRgba8(RgbaImage::from_pixel(300, 120, *color)))
+            .collect();
+
+        let collage = render_collage(&images).to_rgba8();
+        assert_eq!(collage.dimensions(), (COLLAGE_SIZE, COLLAGE_SIZE));
+        for (cell, color) in collage_cells(4, COLLAGE_SIZE).iter().zip(colors) {
+            let center = collage.get_pixel(cell.x + cell.width / 2, cell.y + cell.height / 2);
+            assert_eq!(*center, color);
+        }
+        // The gap between cells shows the background
+        let gap_x = (COLLAGE_SIZE - COLLAGE_GAP) / 2 + 1;
+        assert_eq!(*collage.get_pixel(gap_x, 10), COLLAGE_BACKGROUND);
+    }
+}
@@ -0,0 +1,220 @@
+//! Summary of a copied folder
+//!
+//! Copying a folder in Finder or Explorer gives a file list with the folder
+//! as its only path. Such items are flagged `is_directory` and summarized by
+//! what the folder holds ("Folder Trip — 124 items, 3.2 MB"). The scan
+//! counts every entry below the folder, doesn't follow symlinks and stops
+//! after `DIRECTORY_SCAN_CAP` entries so copying a huge tree stays cheap.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Entries looked at before a scan stops
+pub const DIRECTORY_SCAN_CAP: usize = 2000;
+
+/// What a scan found
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirectorySummary {
+    /// Files, folders and links below the folder
+    pub entries: usize,
+    /// Bytes in the files counted
+    pub total_size: u64,
+    /// The scan stopped at the cap; both numbers are lower bounds
+    pub truncated: bool,
+}
+
+/// Whether a file copy is a single folder
+pub fn is_directory_copy(files: &[String]) -> bool {
+    matches!(files, [path] if Path::new(path).is_dir())
+}
+
+/// Count the entries and file sizes below `root`, up to `cap` entries
+/// Links are counted but not followed; subfolders that can't be read are skipped
+pub fn scan_directory(root: &Path, cap: usize) -> io::Result<DirectorySummary> {
+    let mut summary = DirectorySummary::default();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == root => return Err(e),
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            if summary.entries == cap {
+                summary.truncated = true;
+                return Ok(summary);
+            }
+            summary.entries += 1;
+
+            // DirEntry::metadata doesn't traverse links
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else if metadata.is_file() {
+                summary.total_size += metadata.len();
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// One-line description of the folder at `path` ("Folder Trip — 124 items, 3.2 MB")
+pub fn describe(path: &Path, summary: &DirectorySummary) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.display().to_string());
+    if summary.entries == 0 {
+        return format!("Folder {} — empty", name);
+    }
+
+    let more = if summary.truncated { "+" } else { "" };
+    let items = if summary.entries == 1 {
+        "item"
+    } else {
+        "items"
+    };
+    format!(
+        "Folder {} — {}{} {}, {}{}",
+        name,
+        summary.entries,
+        more,
+        items,
+        format_size(summary.total_size),
+        more
+    )
+}
+
+/// Size in the largest unit that keeps it at 1 or more ("3.2 MB")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, TempDir};
+    use std::path::PathBuf;
+
+    fn temp_root() -> TempDir {
+        let root = temp_dir("folder");
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    /// Folder with three files (10, 20 and 30 bytes) and an empty subfolder,
+    /// one file nested two levels down
+    fn fixture(root: &Path) -> PathBuf {
+        let dir = root.join("Trip");
+        fs::create_dir_all(dir.join("day1/raw")).unwrap();
+        fs::create_dir_all(dir.join("empty")).unwrap();
+        fs::write(dir.join("notes.txt"), [b'a'; 10]).unwrap();
+        fs::write(dir.join("day1/a.jpg"), [b'b'; 20]).unwrap();
+        fs::write(dir.join("day1/raw/a.raw"), [b'c'; 30]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan_counts_nested_entries() {
+        let root = temp_root();
+        let dir = fixture(&root);
+
+        let summary = scan_directory(&dir, DIRECTORY_SCAN_CAP).unwrap();
+        // notes.txt, day1, empty, day1/a.jpg, day1/raw, day1/raw/a.raw
+        assert_eq!(
+            summary,
+            DirectorySummary {
+                entries: 6,
+                total_size: 60,
+                truncated: false,
+            }
+        );
+        assert_eq!(describe(&dir, &summary), "Folder Trip — 6 items, 60 B");
+    }
+
+    #[test]
+    fn test_scan_stops_at_cap() {
+        let root = temp_root();
+        let dir = root.join("Big");
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..25 {
+            fs::write(dir.join(format!("{}.txt", i)), [b'x'; 1024]).unwrap();
+        }
+
+        let summary = scan_directory(&dir, 10).unwrap();
+        assert_eq!(summary.entries, 10);
+        assert_eq!(summary.total_size, 10 * 1024);
+        assert!(summary.truncated);
+        assert_eq!(describe(&dir, &summary), "Folder Big — 10+ items, 10.0 KB+");
+
+        // Exactly at the cap with nothing left is complete
+        let summary = scan_directory(&dir, 25).unwrap();
+        assert!(!summary.truncated);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_does_not_follow_symlinks() {
+        let root = temp_root();
+        let dir = fixture(&root);
+        // A link back to the folder itself would loop if followed
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+        let outside = root.join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("big.bin"), [0u8; 4096]).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("elsewhere")).unwrap();
+
+        let summary = scan_directory(&dir, DIRECTORY_SCAN_CAP).unwrap();
+        assert_eq!(summary.entries, 8);
+        assert_eq!(summary.total_size, 60);
+    }
+
+    #[test]
+    fn test_directory_copy_detection() {
+        let root = temp_root();
+        let dir = fixture(&root);
+        let file = dir.join("notes.txt").to_string_lossy().to_string();
+        let folder = dir.to_string_lossy().to_string();
+
+        assert!(is_directory_copy(std::slice::from_ref(&folder)));
+        assert!(!is_directory_copy(std::slice::from_ref(&file)));
+        assert!(!is_directory_copy(&[folder, file]));
+        assert!(!is_directory_copy(&[]));
+    }
+
+    #[test]
+    fn test_describe_and_format_size() {
+        let empty = DirectorySummary::default();
+        assert_eq!(
+            describe(Path::new("/tmp/New"), &empty),
+            "Folder New — empty"
+        );
+        let one = DirectorySummary {
+            entries: 1,
+            total_size: 3_355_443,
+            truncated: false,
+        };
+        assert_eq!(
+            describe(Path::new("/tmp/One/"), &one),
+            "Folder One — 1 item, 3.2 MB"
+        );
+
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
@@ -0,0 +1,175 @@
+//! On-device language detection of text items
+//!
+//! Text items of at least `MIN_DETECT_CHARS` characters get the language they
+//! are written in, as an ISO 639-3 code (`eng`, `fra`, `deu`, ...) in the
+//! `language` column, so history can be filtered by it. Detection runs with
+//! whatlang, locally, on the first `MAX_DETECT_CHARS` characters. The monitor
+//! saves the item first and queues its text here, so a capture never waits for
+//! it. Results below `MIN_CONFIDENCE` stay NULL: short or mixed snippets, code
+//! and gibberish are better left without a language than given a wrong one.
+//! `reclassify_items` fills in items saved before detection existed.
+
+use crate::models::{ClipboardItem, ContentType};
+use std::sync::mpsc::Receiver;
+use whatlang::{Detector, Lang};
+
+/// Shorter text is left without a language
+pub const MIN_DETECT_CHARS: usize = 20;
+
+/// Characters analyzed from the start of the text
+pub const MAX_DETECT_CHARS: usize = 2_000;
+
+/// Confidence (0 to 1) below which no language is stored
+pub const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Languages told apart; with every language whatlang knows, short English
+/// snippets rarely reach `MIN_CONFIDENCE` against its close relatives
+const LANGUAGES: [Lang; 20] = [
+    Lang::Eng,
+    Lang::Fra,
+    Lang::Deu,
+    Lang::Spa,
+    Lang::Ita,
+    Lang::Por,
+    Lang::Nld,
+    Lang::Swe,
+    Lang::Dan,
+    Lang::Pol,
+    Lang::Ces,
+    Lang::Tur,
+    Lang::Rus,
+    Lang::Ukr,
+    Lang::Ell,
+    Lang::Ara,
+    Lang::Heb,
+    Lang::Jpn,
+    Lang::Cmn,
+    Lang::Kor,
+];
+
+/// A text item saved without its language
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageRequest {
+    pub item_id: String,
+    pub text: String,
+}
+
+/// Receives the languages detected by the worker
+pub trait LanguageSink {
+    /// `language` was detected for `item_id`: store it and tell the frontend
+    fn language_ready(&self, item_id: &str, language: &str);
+}
+
+/// ISO 639-3 code of the language `text` is written in
+/// None for text that is too short or not recognized confidently
+pub fn detect(text: &str) -> Option<String> {
+    let info = Detector::with_allowlist(LANGUAGES.to_vec()).detect(sample(text)?)?;
+    (info.confidence() >= MIN_CONFIDENCE).then(|| info.lang().code().to_string())
+}
+
+/// The part of `text` detection looks at; None when it is too short
+fn sample(text: &str) -> Option<&str> {
+    let text = text.trim();
+    if text.chars().take(MIN_DETECT_CHARS).count() < MIN_DETECT_CHARS {
+        return None;
+    }
+    Some(match text.char_indices().nth(MAX_DETECT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    })
+}
+
+/// Text to detect the language of: that of a text item without one
+pub fn detect_request(item: &ClipboardItem) -> Option<LanguageRequest> {
+    if item.content_type != ContentType::Text || item.language.is_some() {
+        return None;
+    }
+    let text = sample(item.content_text.as_deref()?)?;
+    Some(LanguageRequest {
+        item_id: item.id.clone(),
+        text: text.to_string(),
+    })
+}
+
+/// Serve detection requests sent by the monitor until the sender is dropped
+pub fn run_worker<S: LanguageSink>(sink: &S, requests: Receiver<LanguageRequest>) {
+    for request in requests {
+        if let Some(language) = detect(&request.text) {
+            sink.language_ready(&request.item_id, &language);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_detects_unambiguous_sentences() {
+        let cases = [
+            ("Could you please check whether the build passes on the main branch today?", "eng"),
+            ("I think we should move the launch to next week instead.", "eng"),
+            ("Je voudrais réserver une table pour deux personnes ce soir.", "fra"),
+            ("Le chat dort sur le canapé pendant que nous regardons la télévision.", "fra"),
+            ("Ich möchte heute Abend mit meinen Freunden ins Kino gehen.", "deu"),
+            ("Kannst du mir bitte die Unterlagen bis Freitag schicken?", "deu"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(detect(text).as_deref(), Some(expected), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_low_confidence_and_short_text_stay_null() {
+        assert_eq!(detect("zxqv kjwp trmn bdfg hjkl wqzx vbnm"), None);
+        assert_eq!(detect("asdf qwer zxcv 1234 jkl; uiop"), None);
+        assert_eq!(detect("fn main() { println!(\"{}\", x); }"), None);
+        // Below MIN_DETECT_CHARS, however clear
+        assert_eq!(detect("Guten Morgen!"), None);
+    }
+
+    #[derive(Default)]
+    struct CapturingSink {
+        ready: RefCell<Vec<(String, String)>>,
+    }
+
+    impl LanguageSink for CapturingSink {
+        fn language_ready(&self, item_id: &str, language: &str) {
+            self.ready
+                .borrow_mut()
+                .push((item_id.to_string(), language.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_worker_reports_detected_text_items() {
+        let french = ClipboardItem::new_text(
+            "Je voudrais réserver une table pour deux personnes ce soir.".to_string(),
+            None,
+            None,
+        );
+        let gibberish =
+            ClipboardItem::new_text("zxqv kjwp trmn bdfg hjkl wqzx vbnm".to_string(), None, None);
+        let link = ClipboardItem::new_link(
+            "https://example.com/a/long/enough/path".to_string(),
+            None,
+            None,
+        );
+        assert!(detect_request(&link).is_none());
+        assert!(detect_request(&ClipboardItem::new_text("short".to_string(), None, None)).is_none());
+
+        let (sender, receiver) = mpsc::channel();
+        for item in [&french, &gibberish] {
+            sender.send(detect_request(item).unwrap()).unwrap();
+        }
+        drop(sender);
+        let sink = CapturingSink::default();
+        run_worker(&sink, receiver);
+        assert_eq!(
+            sink.ready.into_inner(),
+            vec![(french.id.clone(), "fra".to_string())]
+        );
+    }
+}
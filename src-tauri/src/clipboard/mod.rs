@@ -1,6 +1,41 @@
 // Cross-platform clipboard module
 // Provides unified API for clipboard operations on Windows and macOS
 
+#[cfg(any(target_os = "windows", test))]
+pub mod app_icon_worker;
+pub mod app_id;
+pub mod audio_metadata;
+pub mod bitmap_format;
+pub mod canonical_url;
+pub mod capture_failures;
+pub mod capture_merge;
+pub mod capture_override;
 pub mod clipboard_monitor;
 pub mod clipboard_reader;
+pub mod contact_event;
+pub mod copy_back;
+#[cfg(any(target_os = "windows", test))]
+pub mod dib;
+pub mod event_coalescer;
+pub mod event_queue;
+pub mod file_group;
+pub mod folder_summary;
+pub mod language;
+pub mod favicon;
 pub mod og_fetcher;
+pub mod palette;
+pub mod payload_limit;
+#[cfg(any(target_os = "windows", test))]
+pub mod os_history;
+pub mod poll_scheduler;
+pub mod prune_scheduler;
+pub mod rate_limiter;
+#[cfg(any(target_os = "macos", test))]
+pub mod read_retry;
+pub mod screenshot;
+pub mod source_app_cache;
+pub mod storage_guard;
+pub mod text_normalize;
+pub mod transforms;
+#[cfg(any(target_os = "windows", test))]
+pub mod windows_icons;
@@ -1,3 +1,5 @@
+use crate::clipboard::favicon;
+use crate::storage::file_storage;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use image::imageops::FilterType;
 use image::ImageFormat;
@@ -9,9 +11,19 @@ use std::time::Duration;
 const THUMBNAIL_MAX_WIDTH: u32 = 400;
 const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
 
-/// Fetch the Open Graph preview image for a URL and return it as a base64-encoded JPEG thumbnail.
-/// Returns `None` on any failure (network, parsing, missing OG image, etc.).
-pub async fn fetch_og_image_base64(url: &str) -> Option<String> {
+/// Preview image found for a link
+#[derive(Debug, Clone)]
+pub struct LinkPreview {
+    /// Base64-encoded JPEG (OG image) or PNG (favicon card)
+    pub thumbnail_base64: String,
+    /// Made from the site icon because the page has no usable OG image
+    pub is_favicon: bool,
+}
+
+/// Fetch a preview image for a URL: its Open Graph image as a base64-encoded JPEG thumbnail,
+/// or else its favicon centered on a plain card.
+/// Returns `None` on any failure (network, parsing, no usable image, etc.).
+pub async fn fetch_link_preview(url: &str) -> Option<LinkPreview> {
     let client = reqwest::Client::builder()
         .timeout(FETCH_TIMEOUT)
         .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
@@ -49,39 +61,54 @@ pub async fn fetch_og_image_base64(url: &str) -> Option<String> {
     };
 
     // Parse OG image URL from meta tags
-    let image_url = match extract_og_image_url(&html_text, url) {
-        Some(u) => {
-            eprintln!("[OG] Found image URL: {}", u);
-            u
-        }
-        None => {
-            eprintln!("[OG] No og:image or twitter:image found for {}", url);
-            return None;
+    match extract_og_image_url(&html_text, url) {
+        Some(image_url) => {
+            eprintln!("[OG] Found image URL: {}", image_url);
+            if let Some(thumbnail) = fetch_og_thumbnail(&client, &image_url).await {
+                return Some(LinkPreview {
+                    thumbnail_base64: thumbnail,
+                    is_favicon: false,
+                });
+            }
         }
-    };
+        None => eprintln!("[OG] No og:image or twitter:image found for {}", url),
+    }
+
+    // No usable OG image: fall back to the site icon
+    let thumbnail = fetch_favicon_thumbnail(&client, &html_text, url).await?;
+    Some(LinkPreview {
+        thumbnail_base64: thumbnail,
+        is_favicon: true,
+    })
+}
 
-    // Fetch the image
-    let img_response = match client.get(&image_url).send().await {
+/// Download the bytes at `url`, logging failures
+async fn fetch_bytes(client: &reqwest::Client, url: &str) -> Option<Vec<u8>> {
+    let response = match client.get(url).send().await {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("[OG] Failed to fetch image {}: {}", image_url, e);
+            eprintln!("[OG] Failed to fetch image {}: {}", url, e);
             return None;
         }
     };
 
-    if !img_response.status().is_success() {
-        eprintln!("[OG] Image HTTP {} for {}", img_response.status(), image_url);
+    if !response.status().is_success() {
+        eprintln!("[OG] Image HTTP {} for {}", response.status(), url);
         return None;
     }
 
-    let img_bytes = match img_response.bytes().await {
-        Ok(b) => b,
+    match response.bytes().await {
+        Ok(b) => Some(b.to_vec()),
         Err(e) => {
-            eprintln!("[OG] Failed to read image bytes from {}: {}", image_url, e);
-            return None;
+            eprintln!("[OG] Failed to read image bytes from {}: {}", url, e);
+            None
         }
-    };
+    }
+}
 
+/// Fetch the OG image and return it as a base64-encoded JPEG thumbnail
+async fn fetch_og_thumbnail(client: &reqwest::Client, image_url: &str) -> Option<String> {
+    let img_bytes = fetch_bytes(client, image_url).await?;
     eprintln!("[OG] Downloaded image: {} bytes", img_bytes.len());
 
     // Decode, resize, and encode as JPEG
@@ -114,6 +141,41 @@ pub async fn fetch_og_image_base64(url: &str) -> Option<String> {
     Some(BASE64.encode(&jpeg_bytes))
 }
 
+/// Fetch the first decodable site icon and return it on a preview card as base64 PNG
+async fn fetch_favicon_thumbnail(
+    client: &reqwest::Client,
+    html: &str,
+    page_url: &str,
+) -> Option<String> {
+    for icon_url in favicon::icon_candidates(html, page_url) {
+        let Some(bytes) = fetch_bytes(client, &icon_url).await else {
+            continue;
+        };
+        let icon = match favicon::decode_icon(&bytes) {
+            Ok(icon) => icon,
+            Err(e) => {
+                eprintln!("[OG] Failed to decode icon {}: {}", icon_url, e);
+                continue;
+            }
+        };
+
+        eprintln!(
+            "[OG] Using icon {} ({}x{})",
+            icon_url,
+            icon.width(),
+            icon.height()
+        );
+        match file_storage::encode_png(&favicon::preview_card(&icon)) {
+            Ok(png) => return Some(BASE64.encode(&png)),
+            Err(e) => {
+                eprintln!("[OG] Failed to encode icon preview: {}", e);
+                return None;
+            }
+        }
+    }
+    None
+}
+
 /// Extract the OG image URL from HTML meta tags.
 /// Tries og:image first, then twitter:image as fallback.
 /// Resolves relative URLs against the page URL.
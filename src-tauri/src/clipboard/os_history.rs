@@ -0,0 +1,94 @@
+//! Keeping copied-back items out of the Windows clipboard history
+//!
+//! Windows 10+ keeps its own clipboard history (Win+V) and can sync it to the
+//! cloud. Content placed with these registered formats alongside it is left
+//! out: `ExcludeClipboardContentFromMonitorProcessing` tells clipboard
+//! monitors to ignore it, and a DWORD 0 under `CanIncludeInClipboardHistory`
+//! and `CanUploadToCloudClipboard` keeps it out of the history and the cloud.
+//! Clipster adds them when copying back items from locked pinboards, or every
+//! item when the `exclude_from_os_history` setting is on.
+
+/// Clipboard monitors skip content carrying this format (any data)
+pub const EXCLUDE_FROM_MONITOR_PROCESSING: &str = "ExcludeClipboardContentFromMonitorProcessing";
+/// DWORD 0: not added to the Win+V history
+pub const CAN_INCLUDE_IN_CLIPBOARD_HISTORY: &str = "CanIncludeInClipboardHistory";
+/// DWORD 0: not synced to other devices
+pub const CAN_UPLOAD_TO_CLOUD_CLIPBOARD: &str = "CanUploadToCloudClipboard";
+
+/// Data placed under each format
+const DWORD_ZERO: [u8; 4] = 0u32.to_le_bytes();
+
+/// The exclusion formats with their data
+pub const EXCLUSION_FORMATS: [(&str, [u8; 4]); 3] = [
+    (EXCLUDE_FROM_MONITOR_PROCESSING, DWORD_ZERO),
+    (CAN_INCLUDE_IN_CLIPBOARD_HISTORY, DWORD_ZERO),
+    (CAN_UPLOAD_TO_CLOUD_CLIPBOARD, DWORD_ZERO),
+];
+
+/// Null-terminated UTF-16 name, as RegisterClipboardFormatW expects
+pub fn wide_name(name: &str) -> Vec<u16> {
+    name.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Register the exclusion formats through `register` (which returns 0 on
+/// failure, like RegisterClipboardFormatW) and pair each ID with its data
+pub fn register_exclusion_formats(
+    mut register: impl FnMut(&[u16]) -> u32,
+) -> Result<Vec<(u32, [u8; 4])>, String> {
+    EXCLUSION_FORMATS
+        .iter()
+        .map(|(name, data)| match register(&wide_name(name)) {
+            0 => Err(format!("Failed to register clipboard format {}", name)),
+            id => Ok((id, *data)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_name_is_null_terminated() {
+        let wide = wide_name("Ab");
+        assert_eq!(wide, vec![b'A' as u16, b'b' as u16, 0]);
+    }
+
+    #[test]
+    fn test_register_exclusion_formats() {
+        let mut registered = Vec::new();
+        let formats = register_exclusion_formats(|name| {
+            registered.push(String::from_utf16(&name[..name.len() - 1]).unwrap());
+            0xC000 + registered.len() as u32
+        })
+        .unwrap();
+
+        assert_eq!(
+            registered,
+            vec![
+                "ExcludeClipboardContentFromMonitorProcessing",
+                "CanIncludeInClipboardHistory",
+                "CanUploadToCloudClipboard",
+            ]
+        );
+        assert_eq!(
+            formats,
+            vec![(0xC001, [0; 4]), (0xC002, [0; 4]), (0xC003, [0; 4])]
+        );
+    }
+
+    #[test]
+    fn test_register_exclusion_formats_fails_on_zero() {
+        let mut calls = 0;
+        let result = register_exclusion_formats(|_| {
+            calls += 1;
+            if calls == 2 {
+                0
+            } else {
+                0xC001
+            }
+        });
+        let error = result.unwrap_err();
+        assert!(error.contains("CanIncludeInClipboardHistory"), "{}", error);
+    }
+}
@@ -0,0 +1,329 @@
+//! Dominant colors of image items
+//!
+//! After an image item is saved, the monitor queues its thumbnail here and the
+//! worker stores up to `PALETTE_SIZE` dominant colors, most common first, as
+//! `#rrggbb` strings in the `palette` column. Colors come from a median cut
+//! over the thumbnail scaled down to `SAMPLE_SIZE` with nearest-neighbour
+//! sampling, so no blended colors are made up; transparent pixels are left
+//! out. `get_items_by_color` finds images with a palette color within an RGB
+//! distance of the one asked for.
+
+use crate::models::{ClipboardItem, ContentType};
+use crate::storage::file_storage;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::sync::mpsc::Receiver;
+
+/// Colors kept per image
+pub const PALETTE_SIZE: usize = 4;
+
+/// Largest side of the copy colors are taken from
+pub const SAMPLE_SIZE: u32 = 64;
+
+/// RGB distance under which two colors match when none is given
+pub const DEFAULT_COLOR_TOLERANCE: f64 = 60.0;
+
+/// Alpha below which a pixel is left out
+const MIN_ALPHA: u8 = 128;
+
+type Rgb = [u8; 3];
+
+/// An image item saved without its palette
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaletteRequest {
+    pub item_id: String,
+    pub thumbnail_base64: String,
+}
+
+/// Receives the palettes extracted by the worker
+pub trait PaletteSink {
+    /// `palette` was extracted for `item_id`: store it and tell the frontend
+    fn palette_ready(&self, item_id: &str, palette: &[String]);
+}
+
+/// `#rrggbb` form of a color
+pub fn to_hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+/// Parse `#rrggbb`, `rrggbb` or the `#rgb` shorthand (any case)
+pub fn parse_hex(hex: &str) -> Option<Rgb> {
+    let digits = hex.trim().trim_start_matches('#');
+    if !digits.is_ascii() {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match digits.len() {
+        6 => Some([
+            channel(&digits[0..2])?,
+            channel(&digits[2..4])?,
+            channel(&digits[4..6])?,
+        ]),
+        3 => {
+            let mut color = [0; 3];
+            for (i, c) in digits.chars().enumerate() {
+                color[i] = channel(&c.to_string())? * 17;
+            }
+            Some(color)
+        }
+        _ => None,
+    }
+}
+
+/// Euclidean distance between two colors (0 to about 441)
+pub fn distance(a: Rgb, b: Rgb) -> f64 {
+    let squared: i32 = (0..3)
+        .map(|i| (i32::from(a[i]) - i32::from(b[i])).pow(2))
+        .sum();
+    f64::from(squared).sqrt()
+}
+
+/// Whether a palette holds a color within `tolerance` of `color`
+pub fn matches(palette: &[String], color: Rgb, tolerance: f64) -> bool {
+    palette
+        .iter()
+        .filter_map(|hex| parse_hex(hex))
+        .any(|entry| distance(entry, color) <= tolerance)
+}
+
+/// Up to `PALETTE_SIZE` dominant colors of `image`, most common first
+/// Empty when every pixel is transparent
+pub fn extract(image: &DynamicImage) -> Vec<String> {
+    let sample = if image.width() > SAMPLE_SIZE || image.height() > SAMPLE_SIZE {
+        image.resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Nearest)
+    } else {
+        image.clone()
+    };
+    let pixels: Vec<Rgb> = sample
+        .to_rgba8()
+        .pixels()
+        .filter(|pixel| pixel[3] >= MIN_ALPHA)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+
+    let mut boxes = median_cut(pixels, PALETTE_SIZE);
+    boxes.sort_by_key(|pixels| std::cmp::Reverse(pixels.len()));
+    boxes.iter().map(|pixels| to_hex(average(pixels))).collect()
+}
+
+/// Split `pixels` into at most `count` boxes, each time cutting the box with
+/// the widest channel at its median; boxes of a single color are not cut
+fn median_cut(pixels: Vec<Rgb>, count: usize) -> Vec<Vec<Rgb>> {
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+    let mut boxes = vec![pixels];
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, pixels)| (i, widest_channel(pixels)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(i, (_, range))| (*range, boxes[*i].len()));
+        let Some((index, (channel, _))) = widest else {
+            break;
+        };
+
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|pixel| pixel[channel]);
+        let cut = split_point(&pixels, channel);
+        let upper = pixels.split_off(cut);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+    boxes
+}
+
+/// Channel with the widest range of values, and that range
+fn widest_channel(pixels: &[Rgb]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = pixels.iter().map(|pixel| pixel[channel]);
+            let min = values.clone().min().unwrap_or(0);
+            let max = values.max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Index nearest the median where `channel` changes value, so pixels of one
+/// color never end up in both halves. `pixels` is sorted on `channel` and
+/// holds at least two values of it
+fn split_point(pixels: &[Rgb], channel: usize) -> usize {
+    let changes_at = |i: usize| pixels[i - 1][channel] != pixels[i][channel];
+    let median = pixels.len() / 2;
+    let after = (median..pixels.len()).find(|&i| changes_at(i));
+    let before = (1..median).rev().find(|&i| changes_at(i));
+    match (before, after) {
+        (Some(before), Some(after)) if median - before < after - median => before,
+        (_, Some(after)) => after,
+        (Some(before), None) => before,
+        (None, None) => median,
+    }
+}
+
+fn average(pixels: &[Rgb]) -> Rgb {
+    let mut sums = [0u64; 3];
+    for pixel in pixels {
+        for (sum, value) in sums.iter_mut().zip(pixel) {
+            *sum += u64::from(*value);
+        }
+    }
+    let count = pixels.len().max(1) as u64;
+    sums.map(|sum| ((sum + count / 2) / count) as u8)
+}
+
+/// Thumbnail to take the palette of: that of an image item without one
+pub fn palette_request(item: &ClipboardItem) -> Option<PaletteRequest> {
+    if item.content_type != ContentType::Image || item.palette.is_some() {
+        return None;
+    }
+    Some(PaletteRequest {
+        item_id: item.id.clone(),
+        thumbnail_base64: item.thumbnail_base64.clone()?,
+    })
+}
+
+/// Serve palette requests sent by the monitor until the sender is dropped
+pub fn run_worker<S: PaletteSink>(sink: &S, requests: Receiver<PaletteRequest>) {
+    for request in requests {
+        let image = file_storage::base64_to_thumbnail(&request.thumbnail_base64)
+            .and_then(|bytes| image::load_from_memory(&bytes).map_err(Into::into));
+        match image {
+            Ok(image) => {
+                let palette = extract(&image);
+                if !palette.is_empty() {
+                    sink.palette_ready(&request.item_id, &palette);
+                }
+            }
+            Err(e) => clipster_log!(
+                Warn,
+                "[palette] Failed to read thumbnail of {}: {}",
+                request.item_id,
+                e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::cell::RefCell;
+    use std::sync::mpsc;
+
+    const RED: Rgb = [230, 20, 20];
+    const BLUE: Rgb = [20, 40, 200];
+
+    /// `width` x 100 image whose first `left` columns are `left_color`
+    fn two_colors(width: u32, left: u32, left_color: Rgb, right_color: Rgb) -> DynamicImage {
+        let image = RgbaImage::from_fn(width, 100, |x, _| {
+            let [r, g, b] = if x < left { left_color } else { right_color };
+            Rgba([r, g, b, 255])
+        });
+        DynamicImage::ImageRgba8(image)
+    }
+
+    #[test]
+    fn test_two_color_image_gives_both_colors_most_common_first() {
+        let palette = extract(&two_colors(400, 300, RED, BLUE));
+        assert_eq!(palette, vec![to_hex(RED), to_hex(BLUE)]);
+
+        let palette = extract(&two_colors(400, 100, RED, BLUE));
+        assert_eq!(palette, vec![to_hex(BLUE), to_hex(RED)]);
+    }
+
+    #[test]
+    fn test_palette_is_capped_and_skips_transparent_pixels() {
+        let bands = [
+            RED,
+            BLUE,
+            [0, 0, 0],
+            [255, 255, 255],
+            [20, 200, 40],
+            [240, 220, 30],
+        ];
+        let image = RgbaImage::from_fn(60, 10, |x, _| {
+            let [r, g, b] = bands[(x / 10) as usize];
+            Rgba([r, g, b, 255])
+        });
+        let palette = extract(&DynamicImage::ImageRgba8(image));
+        assert_eq!(palette.len(), PALETTE_SIZE);
+
+        let mut image = two_colors(100, 50, RED, BLUE).to_rgba8();
+        for x in 50..100 {
+            for y in 0..100 {
+                image.put_pixel(x, y, Rgba([0, 0, 255, 0]));
+            }
+        }
+        assert_eq!(extract(&DynamicImage::ImageRgba8(image)), vec![to_hex(RED)]);
+        assert!(extract(&DynamicImage::new_rgba8(10, 10)).is_empty());
+    }
+
+    #[test]
+    fn test_hex_and_distance_matching() {
+        assert_eq!(parse_hex("#E61414"), Some(RED));
+        assert_eq!(parse_hex("e61414"), Some(RED));
+        assert_eq!(parse_hex("#f00"), Some([255, 0, 0]));
+        assert_eq!(parse_hex("#ff00"), None);
+        assert_eq!(parse_hex("#gg0000"), None);
+        assert_eq!(parse_hex("#ééé"), None);
+        assert_eq!(to_hex(RED), "#e61414");
+
+        assert_eq!(distance(RED, RED), 0.0);
+        assert_eq!(distance([0, 0, 0], [3, 4, 0]), 5.0);
+
+        let palette = vec![to_hex(RED), to_hex(BLUE)];
+        assert!(matches(&palette, [255, 0, 0], DEFAULT_COLOR_TOLERANCE));
+        assert!(matches(&palette, BLUE, 0.0));
+        assert!(!matches(&palette, [20, 200, 40], DEFAULT_COLOR_TOLERANCE));
+        assert!(!matches(&[], RED, DEFAULT_COLOR_TOLERANCE));
+    }
+
+    #[derive(Default)]
+    struct CapturingSink {
+        ready: RefCell<Vec<(String, Vec<String>)>>,
+    }
+
+    impl PaletteSink for CapturingSink {
+        fn palette_ready(&self, item_id: &str, palette: &[String]) {
+            self.ready
+                .borrow_mut()
+                .push((item_id.to_string(), palette.to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_worker_reads_thumbnails() {
+        let image = two_colors(400, 200, RED, BLUE);
+        let thumbnail = file_storage::generate_thumbnail_default(&image).unwrap();
+        let item = ClipboardItem::new_image(
+            Some(file_storage::thumbnail_to_base64(&thumbnail)),
+            "/images/a.png".to_string(),
+            None,
+            None,
+        );
+        assert!(palette_request(&ClipboardItem::new_text("red".to_string(), None, None)).is_none());
+
+        let (sender, receiver) = mpsc::channel();
+        sender.send(palette_request(&item).unwrap()).unwrap();
+        sender
+            .send(PaletteRequest {
+                item_id: "broken".to_string(),
+                thumbnail_base64: "not an image".to_string(),
+            })
+            .unwrap();
+        drop(sender);
+        let sink = CapturingSink::default();
+        run_worker(&sink, receiver);
+
+        let ready = sink.ready.into_inner();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].0, item.id);
+        let colors: Vec<Rgb> = ready[0].1.iter().filter_map(|hex| parse_hex(hex)).collect();
+        assert!(colors.iter().any(|color| distance(*color, RED) < 10.0));
+        assert!(colors.iter().any(|color| distance(*color, BLUE) < 10.0));
+    }
+}
@@ -0,0 +1,74 @@
+//! Size limit on clipboard images read into memory
+//!
+//! Some apps put huge bitmaps on the clipboard (a DIB of a whole plotter
+//! sheet, a TIFF from a scientific viewer). Reading one copies it into our
+//! memory and decoding it takes several times more. The readers check the
+//! announced size first (`GlobalSize` on Windows, the `NSData` length on
+//! macOS) and skip images over the `max_image_capture_mb` setting; the monitor
+//! records a capture failure and emits `capture-skipped-too-large`.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Default value of the `max_image_capture_mb` setting
+pub const DEFAULT_MAX_IMAGE_CAPTURE_MB: u64 = 100;
+
+/// Payload of the `capture-skipped-too-large` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct TooLarge {
+    /// Size of the clipboard data as announced by the OS
+    pub size_bytes: u64,
+    pub limit_bytes: u64,
+}
+
+/// Limit in bytes for a `max_image_capture_mb` value (0 = no limit)
+pub fn limit_bytes(max_image_capture_mb: u64) -> u64 {
+    if max_image_capture_mb == 0 {
+        return u64::MAX;
+    }
+    max_image_capture_mb.saturating_mul(1024 * 1024)
+}
+
+/// Check a clipboard payload size before the data is copied
+pub fn check(size_bytes: u64, limit_bytes: u64) -> Result<(), TooLarge> {
+    if size_bytes > limit_bytes {
+        return Err(TooLarge {
+            size_bytes,
+            limit_bytes,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_limit_bytes() {
+        assert_eq!(limit_bytes(DEFAULT_MAX_IMAGE_CAPTURE_MB), 100 * MB);
+        assert_eq!(limit_bytes(1), MB);
+        assert_eq!(limit_bytes(0), u64::MAX);
+        assert_eq!(limit_bytes(u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_check_payload_size() {
+        let limit = limit_bytes(DEFAULT_MAX_IMAGE_CAPTURE_MB);
+        assert_eq!(check(0, limit), Ok(()));
+        // Exactly at the limit is still read
+        assert_eq!(check(limit, limit), Ok(()));
+        assert_eq!(
+            check(limit + 1, limit),
+            Err(TooLarge {
+                size_bytes: limit + 1,
+                limit_bytes: limit,
+            })
+        );
+        // A 4 GB DIB
+        assert!(check(4096 * MB, limit).is_err());
+        assert_eq!(check(u64::MAX, limit_bytes(0)), Ok(()));
+    }
+}
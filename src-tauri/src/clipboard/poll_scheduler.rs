@@ -0,0 +1,172 @@
+//! Adaptive polling interval for the macOS clipboard monitor
+//!
+//! macOS has no clipboard-change notification, so the monitor polls the
+//! pasteboard's changeCount. Copies cluster right after an app switch, while
+//! the panel is open and just after another copy, and never happen while the
+//! machine sits idle. The scheduler polls fast in the first cases and slowly in
+//! the last instead of waking at a fixed rate.
+
+use std::time::{Duration, Instant};
+
+/// Interval right after an app switch or copy, and while the panel is visible
+pub const FAST_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Interval during normal use
+pub const NORMAL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Interval once the user has been idle for `IDLE_THRESHOLD`
+pub const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long polling stays fast after an app switch or copy
+pub const BURST_DURATION: Duration = Duration::from_secs(2);
+
+/// Time without keyboard or mouse input after which polling slows down
+pub const IDLE_THRESHOLD: Duration = Duration::from_secs(60);
+
+/// Activity readings taken before each poll
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActivitySignals {
+    /// Number of app activations seen so far; a change means the user switched apps
+    pub activation_seq: u64,
+    /// The Clipster panel is on screen
+    pub panel_visible: bool,
+    /// Time since the last keyboard or mouse event (None if unknown)
+    pub idle_for: Option<Duration>,
+}
+
+/// Picks the delay before the next pasteboard poll
+///
+/// Time and activity readings are passed in by the caller so the schedule can
+/// be tested without a real clock or window server.
+#[derive(Debug)]
+pub struct PollScheduler {
+    last_activation_seq: u64,
+    burst_until: Option<Instant>,
+}
+
+impl PollScheduler {
+    /// Scheduler starting from the current activation count
+    pub fn new(activation_seq: u64) -> Self {
+        Self {
+            last_activation_seq: activation_seq,
+            burst_until: None,
+        }
+    }
+
+    /// A clipboard change was seen; follow-up copies are likely
+    pub fn on_change(&mut self, now: Instant) {
+        self.burst_until = Some(now + BURST_DURATION);
+    }
+
+    /// Delay before the next poll
+    pub fn next_interval(&mut self, now: Instant, signals: ActivitySignals) -> Duration {
+        if signals.activation_seq != self.last_activation_seq {
+            self.last_activation_seq = signals.activation_seq;
+            self.burst_until = Some(now + BURST_DURATION);
+        }
+
+        let in_burst = self.burst_until.is_some_and(|until| now < until);
+        if signals.panel_visible || in_burst {
+            FAST_INTERVAL
+        } else if signals.idle_for.is_some_and(|idle| idle >= IDLE_THRESHOLD) {
+            IDLE_INTERVAL
+        } else {
+            NORMAL_INTERVAL
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn active() -> ActivitySignals {
+        ActivitySignals {
+            idle_for: Some(Duration::from_secs(1)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normal_and_idle() {
+        let start = Instant::now();
+        let mut scheduler = PollScheduler::new(0);
+
+        assert_eq!(scheduler.next_interval(start, active()), NORMAL_INTERVAL);
+
+        let idle = ActivitySignals {
+            idle_for: Some(IDLE_THRESHOLD),
+            ..active()
+        };
+        assert_eq!(scheduler.next_interval(start, idle), IDLE_INTERVAL);
+
+        // Unknown idle time never slows polling down
+        let unknown = ActivitySignals {
+            idle_for: None,
+            ..active()
+        };
+        assert_eq!(scheduler.next_interval(start, unknown), NORMAL_INTERVAL);
+    }
+
+    #[test]
+    fn test_app_switch_bursts_then_decays() {
+        let start = Instant::now();
+        let mut scheduler = PollScheduler::new(7);
+
+        // The count the scheduler started from is not a switch
+        let signals = ActivitySignals {
+            activation_seq: 7,
+            ..active()
+        };
+        assert_eq!(scheduler.next_interval(start, signals), NORMAL_INTERVAL);
+
+        let switched = ActivitySignals {
+            activation_seq: 8,
+            ..active()
+        };
+        assert_eq!(scheduler.next_interval(start, switched), FAST_INTERVAL);
+        let later = start + BURST_DURATION - Duration::from_millis(1);
+        assert_eq!(scheduler.next_interval(later, switched), FAST_INTERVAL);
+        assert_eq!(
+            scheduler.next_interval(start + BURST_DURATION, switched),
+            NORMAL_INTERVAL
+        );
+    }
+
+    #[test]
+    fn test_switch_wakes_idle_polling() {
+        let start = Instant::now();
+        let mut scheduler = PollScheduler::new(0);
+        let idle = ActivitySignals {
+            idle_for: Some(IDLE_THRESHOLD * 2),
+            ..Default::default()
+        };
+        assert_eq!(scheduler.next_interval(start, idle), IDLE_INTERVAL);
+
+        let switched = ActivitySignals {
+            activation_seq: 1,
+            ..idle
+        };
+        assert_eq!(scheduler.next_interval(start, switched), FAST_INTERVAL);
+    }
+
+    #[test]
+    fn test_panel_and_copies_poll_fast() {
+        let start = Instant::now();
+        let mut scheduler = PollScheduler::new(0);
+
+        let panel = ActivitySignals {
+            panel_visible: true,
+            idle_for: Some(IDLE_THRESHOLD * 2),
+            ..Default::default()
+        };
+        assert_eq!(scheduler.next_interval(start, panel), FAST_INTERVAL);
+
+        scheduler.on_change(start);
+        assert_eq!(scheduler.next_interval(start, active()), FAST_INTERVAL);
+        assert_eq!(
+            scheduler.next_interval(start + BURST_DURATION, active()),
+            NORMAL_INTERVAL
+        );
+    }
+}
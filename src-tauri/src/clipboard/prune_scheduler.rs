@@ -0,0 +1,338 @@
+//! Deferred history pruning
+//!
+//! Saving a capture only marks history as dirty. A maintenance thread prunes
+//! at most once every `PRUNE_INTERVAL`, so a paste storm doesn't run a DELETE
+//! scan per capture. History that has grown more than `OVERFLOW_PERCENT` past
+//! the limit is pruned at the next check instead. One pass trims history to
+//! `history_limit` and removes the image and text files of the pruned items.
+//! When the monitor stops, pending work gets a last pass.
+//!
+//! The same thread deletes items past their `expires_at` every
+//! `EXPIRY_CHECK_INTERVAL`, captures or not. Items that expired while the app
+//! was closed are deleted at launch.
+
+use crate::clipboard::event_coalescer::{Clock, SystemClock};
+use crate::error::ClipsterError;
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Shortest time between two passes
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Growth past the limit, in percent, that is pruned without waiting
+pub const OVERFLOW_PERCENT: usize = 10;
+
+/// Shortest time between two looks for expired items
+pub const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the maintenance thread checks for captures
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// History as seen by the scheduler, mocked in tests
+pub trait PruneTarget {
+    fn history_limit(&self) -> Result<usize, ClipsterError>;
+
+    /// Items a pass could delete
+    fn prunable_count(&self) -> Result<usize, ClipsterError>;
+
+    /// Trim history to `limit`; returns the items removed
+    fn prune_to(&self, limit: usize) -> Result<usize, ClipsterError>;
+
+    /// Delete the items expired at `now`; returns their IDs
+    fn delete_expired(&self, now: DateTime<Utc>) -> Result<Vec<String>, ClipsterError>;
+}
+
+impl PruneTarget for Database {
+    fn history_limit(&self) -> Result<usize, ClipsterError> {
+        self.get_history_limit()
+    }
+
+    fn prunable_count(&self) -> Result<usize, ClipsterError> {
+        self.count_prunable_items()
+    }
+
+    fn prune_to(&self, limit: usize) -> Result<usize, ClipsterError> {
+        self.prune_oldest(limit)
+    }
+
+    fn delete_expired(&self, now: DateTime<Utc>) -> Result<Vec<String>, ClipsterError> {
+        self.delete_expired_items(now)
+    }
+}
+
+/// Count above which history is pruned without waiting for the interval
+pub fn overflow_threshold(limit: usize) -> usize {
+    limit + limit * OVERFLOW_PERCENT / 100
+}
+
+/// Decides when captures turn into a pass
+#[derive(Debug)]
+pub struct PruneScheduler<C: Clock = SystemClock> {
+    clock: C,
+    dirty: bool,
+    last_run: Instant,
+    /// None until the first look for expired items
+    last_expiry_check: Option<Instant>,
+}
+
+impl<C: Clock> PruneScheduler<C> {
+    /// The interval counts from creation, so launch doesn't start with a pass
+    pub fn new(clock: C) -> Self {
+        let last_run = clock.now();
+        Self {
+            clock,
+            dirty: false,
+            last_run,
+            last_expiry_check: None,
+        }
+    }
+
+    /// Note that history changed and may need pruning
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Run a pass if one is due; returns the items removed, None if no pass ran
+    /// A failed pass leaves history dirty so the next check tries again
+    pub fn tick<T: PruneTarget>(&mut self, target: &T) -> Result<Option<usize>, ClipsterError> {
+        if !self.dirty {
+            return Ok(None);
+        }
+
+        let limit = target.history_limit()?;
+        let interval_passed = self.clock.now().duration_since(self.last_run) >= PRUNE_INTERVAL;
+        if !interval_passed && target.prunable_count()? <= overflow_threshold(limit) {
+            return Ok(None);
+        }
+        self.run(target, limit).map(Some)
+    }
+
+    /// Run a pass now if history is dirty, regardless of the interval
+    pub fn flush<T: PruneTarget>(&mut self, target: &T) -> Result<Option<usize>, ClipsterError> {
+        if !self.dirty {
+            return Ok(None);
+        }
+        let limit = target.history_limit()?;
+        self.run(target, limit).map(Some)
+    }
+
+    /// Delete the items expired at `now` if a look is due; returns their IDs
+    /// The first look runs right away
+    pub fn expire<T: PruneTarget>(
+        &mut self,
+        target: &T,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<String>, ClipsterError> {
+        let instant = self.clock.now();
+        let due = self
+            .last_expiry_check
+            .is_none_or(|last| instant.duration_since(last) >= EXPIRY_CHECK_INTERVAL);
+        if !due {
+            return Ok(Vec::new());
+        }
+        self.last_expiry_check = Some(instant);
+        target.delete_expired(now)
+    }
+
+    fn run<T: PruneTarget>(&mut self, target: &T, limit: usize) -> Result<usize, ClipsterError> {
+        self.last_run = self.clock.now();
+        let pruned = target.prune_to(limit)?;
+        self.dirty = false;
+        Ok(pruned)
+    }
+}
+
+/// Prune `target` as captures arrive on `captures` until the sender is dropped
+/// `on_pruned` is called after each pass that removed items, with their count,
+/// and `on_expired` with the IDs of the expired items deleted
+pub fn run_worker<T: PruneTarget>(
+    target: &T,
+    captures: Receiver<()>,
+    mut on_pruned: impl FnMut(usize),
+    mut on_expired: impl FnMut(Vec<String>),
+) {
+    let mut scheduler = PruneScheduler::new(SystemClock);
+    loop {
+        thread::sleep(CHECK_INTERVAL);
+        let stopped = loop {
+            match captures.try_recv() {
+                Ok(()) => scheduler.mark_dirty(),
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+
+        let result = if stopped {
+            scheduler.flush(target)
+        } else {
+            scheduler.tick(target)
+        };
+        match result {
+            Ok(Some(pruned)) if pruned > 0 => on_pruned(pruned),
+            Ok(_) => {}
+            Err(e) => eprintln!("[prune_scheduler] Prune failed: {}", e),
+        }
+        if stopped {
+            return;
+        }
+
+        match scheduler.expire(target, Utc::now()) {
+            Ok(expired) if !expired.is_empty() => on_expired(expired),
+            Ok(_) => {}
+            Err(e) => eprintln!("[prune_scheduler] Deleting expired items failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+    use std::cell::Cell;
+
+    /// History of `count` prunable items with a limit of 100
+    struct FakeHistory {
+        count: Cell<usize>,
+        passes: Cell<usize>,
+        fail: Cell<bool>,
+        expiry_checks: Cell<usize>,
+    }
+
+    impl FakeHistory {
+        fn new(count: usize) -> Self {
+            Self {
+                count: Cell::new(count),
+                passes: Cell::new(0),
+                fail: Cell::new(false),
+                expiry_checks: Cell::new(0),
+            }
+        }
+    }
+
+    impl PruneTarget for FakeHistory {
+        fn history_limit(&self) -> Result<usize, ClipsterError> {
+            Ok(100)
+        }
+
+        fn prunable_count(&self) -> Result<usize, ClipsterError> {
+            Ok(self.count.get())
+        }
+
+        fn prune_to(&self, limit: usize) -> Result<usize, ClipsterError> {
+            if self.fail.get() {
+                return Err(ClipsterError::Busy("database is locked".to_string()));
+            }
+            self.passes.set(self.passes.get() + 1);
+            let removed = self.count.get().saturating_sub(limit);
+            self.count.set(self.count.get() - removed);
+            Ok(removed)
+        }
+
+        fn delete_expired(&self, _now: DateTime<Utc>) -> Result<Vec<String>, ClipsterError> {
+            self.expiry_checks.set(self.expiry_checks.get() + 1);
+            Ok(vec![format!("expired-{}", self.expiry_checks.get())])
+        }
+    }
+
+    #[test]
+    fn test_clean_history_is_not_pruned() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(500);
+        let mut scheduler = PruneScheduler::new(clock.clone());
+
+        clock.advance(PRUNE_INTERVAL * 2);
+        assert_eq!(scheduler.tick(&history).unwrap(), None);
+        assert_eq!(scheduler.flush(&history).unwrap(), None);
+        assert_eq!(history.passes.get(), 0);
+    }
+
+    #[test]
+    fn test_burst_prunes_once_per_interval() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(105);
+        let mut scheduler = PruneScheduler::new(clock.clone());
+
+        // A capture every 100ms for a minute, staying under the overflow threshold
+        for _ in 0..600 {
+            scheduler.mark_dirty();
+            clock.advance(Duration::from_millis(100));
+            scheduler.tick(&history).unwrap();
+        }
+        assert_eq!(history.passes.get(), 2);
+    }
+
+    #[test]
+    fn test_overflow_prunes_immediately() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(overflow_threshold(100));
+        let mut scheduler = PruneScheduler::new(clock.clone());
+        scheduler.mark_dirty();
+
+        assert_eq!(scheduler.tick(&history).unwrap(), None);
+        history.count.set(overflow_threshold(100) + 1);
+        assert_eq!(scheduler.tick(&history).unwrap(), Some(11));
+        assert_eq!(history.count.get(), 100);
+
+        // Nothing captured since
+        history.count.set(200);
+        assert_eq!(scheduler.tick(&history).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_pass_is_retried() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(150);
+        history.fail.set(true);
+        let mut scheduler = PruneScheduler::new(clock.clone());
+        scheduler.mark_dirty();
+
+        assert!(scheduler.tick(&history).is_err());
+        history.fail.set(false);
+        assert_eq!(scheduler.tick(&history).unwrap(), Some(50));
+    }
+
+    #[test]
+    fn test_flush_ignores_interval() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(103);
+        let mut scheduler = PruneScheduler::new(clock.clone());
+        scheduler.mark_dirty();
+
+        assert_eq!(scheduler.tick(&history).unwrap(), None);
+        assert_eq!(scheduler.flush(&history).unwrap(), Some(3));
+    }
+
+    #[test]
+    fn test_expiry_checks_run_at_start_then_per_interval() {
+        let clock = FakeClock::new();
+        let history = FakeHistory::new(0);
+        let mut scheduler = PruneScheduler::new(clock.clone());
+
+        // Without any capture: items that expired while the app was closed go first
+        assert_eq!(
+            scheduler.expire(&history, Utc::now()).unwrap(),
+            vec!["expired-1"]
+        );
+        assert!(scheduler.expire(&history, Utc::now()).unwrap().is_empty());
+
+        clock.advance(EXPIRY_CHECK_INTERVAL - Duration::from_millis(1));
+        assert!(scheduler.expire(&history, Utc::now()).unwrap().is_empty());
+        clock.advance(Duration::from_millis(1));
+        assert_eq!(
+            scheduler.expire(&history, Utc::now()).unwrap(),
+            vec!["expired-2"]
+        );
+        assert_eq!(history.expiry_checks.get(), 2);
+        assert_eq!(history.passes.get(), 0);
+    }
+
+    #[test]
+    fn test_overflow_threshold() {
+        assert_eq!(overflow_threshold(1000), 1100);
+        assert_eq!(overflow_threshold(5), 5);
+        assert_eq!(overflow_threshold(0), 0);
+    }
+}
@@ -0,0 +1,169 @@
+//! Per-app capture rate limiting
+//!
+//! Some apps (password managers, terminals with copy-on-select) write to the
+//! clipboard many times per second. The `app_rate_limits` setting caps how many
+//! captures per minute each app may produce; excess events are dropped and
+//! counted so the frontend can surface them. Apps are identified as described
+//! in `app_id`.
+
+use crate::clipboard::app_id;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Length of the sliding window limits are expressed over
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Sliding-window capture counter keyed by source app identifier
+///
+/// Callers pass the current time explicitly so the window can be driven by a
+/// mock clock in tests.
+#[derive(Debug)]
+pub struct AppRateLimiter {
+    window: Duration,
+    captures: HashMap<String, VecDeque<Instant>>,
+    dropped: HashMap<String, u64>,
+}
+
+impl AppRateLimiter {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            captures: HashMap::new(),
+            dropped: HashMap::new(),
+        }
+    }
+
+    /// Record a capture from `app` at `now` if it is within `limit` captures
+    /// per window; returns false (and counts a drop) otherwise
+    pub fn allow(&mut self, app: &str, limit: u32, now: Instant) -> bool {
+        let captures = self.captures.entry(app.to_string()).or_default();
+        while let Some(&oldest) = captures.front() {
+            if now.saturating_duration_since(oldest) < self.window {
+                break;
+            }
+            captures.pop_front();
+        }
+
+        if captures.len() >= limit as usize {
+            *self.dropped.entry(app.to_string()).or_insert(0) += 1;
+            return false;
+        }
+
+        captures.push_back(now);
+        true
+    }
+
+    /// Dropped capture counts per app since startup
+    pub fn dropped(&self) -> &HashMap<String, u64> {
+        &self.dropped
+    }
+
+    /// Total dropped captures across all apps
+    pub fn total_dropped(&self) -> u64 {
+        self.dropped.values().sum()
+    }
+}
+
+impl Default for AppRateLimiter {
+    fn default() -> Self {
+        Self::new(RATE_LIMIT_WINDOW)
+    }
+}
+
+/// Find the limit configured for the app identified by `app_id`
+/// Matched as in `app_id::find_entry`
+pub fn limit_for(limits: &HashMap<String, u32>, app_id: &str) -> Option<u32> {
+    app_id::find_entry(limits, app_id).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+
+    #[test]
+    fn test_drops_over_limit() {
+        let clock = FakeClock::new();
+        let mut limiter = AppRateLimiter::default();
+
+        for _ in 0..3 {
+            assert!(limiter.allow("Terminal", 3, clock.now()));
+        }
+        assert!(!limiter.allow("Terminal", 3, clock.now()));
+        assert!(!limiter.allow("Terminal", 3, clock.now()));
+        assert_eq!(limiter.dropped().get("Terminal"), Some(&2));
+        assert_eq!(limiter.total_dropped(), 2);
+    }
+
+    #[test]
+    fn test_window_slides() {
+        let clock = FakeClock::new();
+        let mut limiter = AppRateLimiter::default();
+
+        assert!(limiter.allow("Terminal", 2, clock.now()));
+        clock.advance(Duration::from_secs(30));
+        assert!(limiter.allow("Terminal", 2, clock.now()));
+        clock.advance(Duration::from_secs(20));
+        assert!(!limiter.allow("Terminal", 2, clock.now()));
+
+        // First capture leaves the window at t=60, second one at t=90
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.allow("Terminal", 2, clock.now()));
+        assert!(!limiter.allow("Terminal", 2, clock.now()));
+        clock.advance(Duration::from_secs(30));
+        assert!(limiter.allow("Terminal", 2, clock.now()));
+    }
+
+    #[test]
+    fn test_dropped_events_do_not_extend_window() {
+        let clock = FakeClock::new();
+        let mut limiter = AppRateLimiter::default();
+
+        assert!(limiter.allow("Terminal", 1, clock.now()));
+        for _ in 0..5 {
+            clock.advance(Duration::from_secs(10));
+            assert!(!limiter.allow("Terminal", 1, clock.now()));
+        }
+        clock.advance(Duration::from_secs(10));
+        assert!(limiter.allow("Terminal", 1, clock.now()));
+    }
+
+    #[test]
+    fn test_apps_are_independent() {
+        let clock = FakeClock::new();
+        let mut limiter = AppRateLimiter::default();
+
+        assert!(limiter.allow("Terminal", 1, clock.now()));
+        assert!(!limiter.allow("Terminal", 1, clock.now()));
+        assert!(limiter.allow("Safari", 1, clock.now()));
+        assert_eq!(limiter.dropped().get("Safari"), None);
+    }
+
+    #[test]
+    fn test_zero_limit_drops_everything() {
+        let clock = FakeClock::new();
+        let mut limiter = AppRateLimiter::default();
+        assert!(!limiter.allow("Noisy", 0, clock.now()));
+    }
+
+    #[test]
+    fn test_limit_for_is_case_insensitive() {
+        let limits = HashMap::from([("com.apple.Terminal".to_string(), 5)]);
+        assert_eq!(limit_for(&limits, "com.apple.terminal"), Some(5));
+        assert_eq!(limit_for(&limits, "com.apple.Safari"), None);
+    }
+
+    #[test]
+    fn test_limit_for_matches_executable_paths() {
+        let limits = HashMap::from([
+            ("WindowsTerminal.exe".to_string(), 5),
+            (r"C:\Tools\copyq.exe".to_string(), 2),
+        ]);
+        let terminal = r"C:\Program Files\WindowsApps\Terminal\windowsterminal.exe";
+        assert_eq!(limit_for(&limits, terminal), Some(5));
+        assert_eq!(limit_for(&limits, r"C:\Tools\CopyQ.exe"), Some(2));
+        // Only the full path matches an entry with a directory
+        assert_eq!(limit_for(&limits, r"D:\copyq.exe"), None);
+        assert_eq!(limit_for(&limits, r"C:\Windows\notepad.exe"), None);
+    }
+}
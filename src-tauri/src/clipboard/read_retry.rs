@@ -0,0 +1,248 @@
+//! Verified pasteboard reads for the macOS clipboard monitor
+//!
+//! A changeCount bump means an app started writing, not that it finished:
+//! slow writers (Photoshop, remote desktop clients) can still be adding types
+//! when the monitor reads. After each read the monitor checks changeCount
+//! again. If it moved during the read, or the read came back empty while the
+//! pasteboard has types, the read is retried after a growing delay. After
+//! `RETRY_DELAYS` runs out the capture is given up and logged as a failure.
+
+use std::time::Duration;
+
+/// Wait after a changeCount bump before the first read
+pub const INITIAL_DELAY: Duration = Duration::from_millis(50);
+
+/// Wait before each retry; one retry per entry
+pub const RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+];
+
+/// The general pasteboard, as seen by the retry loop
+pub trait Pasteboard {
+    type Content;
+
+    fn change_count(&mut self) -> isize;
+
+    /// Whether the pasteboard lists any types
+    fn has_content(&mut self) -> bool;
+
+    /// Read the pasteboard; `None` when nothing could be read
+    fn read(&mut self) -> Option<Self::Content>;
+}
+
+/// What one read attempt saw
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    /// The read returned nothing
+    pub empty: bool,
+    /// The pasteboard still lists types (only checked after an empty read)
+    pub has_content: bool,
+    /// changeCount moved while reading
+    pub changed_during_read: bool,
+}
+
+/// What to do after a read attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// Use the read (an empty read of an empty pasteboard is a clear)
+    Accept,
+    /// Read again after the delay
+    Retry(Duration),
+    /// The pasteboard never settled
+    GiveUp,
+}
+
+/// Result of `read_verified`
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReadOutcome<T> {
+    /// The content read (None when the pasteboard was cleared)
+    Read(Option<T>),
+    /// Every retry saw the pasteboard still changing or not yet readable
+    GaveUp,
+}
+
+/// Decide what follows attempt number `retries` (0 for the first read)
+pub fn next_step(retries: usize, attempt: Attempt) -> Step {
+    let unsettled = attempt.changed_during_read || (attempt.empty && attempt.has_content);
+    if !unsettled {
+        return Step::Accept;
+    }
+    match RETRY_DELAYS.get(retries) {
+        Some(delay) => Step::Retry(*delay),
+        None => Step::GiveUp,
+    }
+}
+
+/// Read `pasteboard` until a read is not overtaken by another write
+///
+/// `change_count` is the count that triggered the read. Returns the outcome
+/// with the changeCount last seen, so the monitor doesn't capture the same
+/// write again on its next poll.
+pub fn read_verified<P: Pasteboard>(
+    pasteboard: &mut P,
+    mut change_count: isize,
+    mut sleep: impl FnMut(Duration),
+) -> (ReadOutcome<P::Content>, isize) {
+    sleep(INITIAL_DELAY);
+    let mut retries = 0;
+    loop {
+        let content = pasteboard.read();
+        let after = pasteboard.change_count();
+        let attempt = Attempt {
+            empty: content.is_none(),
+            has_content: content.is_none() && pasteboard.has_content(),
+            changed_during_read: after != change_count,
+        };
+        change_count = after;
+
+        match next_step(retries, attempt) {
+            Step::Accept => return (ReadOutcome::Read(content), change_count),
+            Step::Retry(delay) => {
+                eprintln!(
+                    "[read_retry] Pasteboard not settled ({:?}), retrying in {:?}",
+                    attempt, delay
+                );
+                sleep(delay);
+                retries += 1;
+            }
+            Step::GiveUp => return (ReadOutcome::GaveUp, change_count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// One scripted read: what it returns, the changeCount right after it and
+    /// whether the pasteboard lists types
+    struct State {
+        content: Option<&'static str>,
+        change_count: isize,
+        has_content: bool,
+    }
+
+    fn state(content: Option<&'static str>, change_count: isize, has_content: bool) -> State {
+        State {
+            content,
+            change_count,
+            has_content,
+        }
+    }
+
+    struct Scripted {
+        states: VecDeque<State>,
+        current: Option<State>,
+    }
+
+    impl Scripted {
+        fn new(states: Vec<State>) -> Self {
+            Self {
+                states: states.into(),
+                current: None,
+            }
+        }
+    }
+
+    impl Pasteboard for Scripted {
+        type Content = &'static str;
+
+        fn change_count(&mut self) -> isize {
+            self.current.as_ref().unwrap().change_count
+        }
+
+        fn has_content(&mut self) -> bool {
+            self.current.as_ref().unwrap().has_content
+        }
+
+        fn read(&mut self) -> Option<&'static str> {
+            self.current = self.states.pop_front();
+            self.current.as_ref().expect("read past the script").content
+        }
+    }
+
+    fn run(states: Vec<State>) -> (ReadOutcome<&'static str>, isize, Vec<Duration>) {
+        let mut pasteboard = Scripted::new(states);
+        let mut sleeps = Vec::new();
+        let (outcome, count) = read_verified(&mut pasteboard, 1, |delay| sleeps.push(delay));
+        (outcome, count, sleeps)
+    }
+
+    #[test]
+    fn test_settled_read_is_accepted() {
+        let (outcome, count, sleeps) = run(vec![state(Some("hello"), 1, true)]);
+        assert_eq!(outcome, ReadOutcome::Read(Some("hello")));
+        assert_eq!(count, 1);
+        assert_eq!(sleeps, vec![INITIAL_DELAY]);
+    }
+
+    #[test]
+    fn test_empty_read_with_types_is_retried() {
+        // Photoshop announces its types before the image data is written
+        let (outcome, count, sleeps) = run(vec![
+            state(None, 1, true),
+            state(None, 1, true),
+            state(Some("image"), 1, true),
+        ]);
+        assert_eq!(outcome, ReadOutcome::Read(Some("image")));
+        assert_eq!(count, 1);
+        assert_eq!(
+            sleeps,
+            vec![INITIAL_DELAY, RETRY_DELAYS[0], RETRY_DELAYS[1]]
+        );
+    }
+
+    #[test]
+    fn test_write_during_read_is_retried() {
+        let (outcome, count, sleeps) = run(vec![
+            state(Some("partial"), 2, true),
+            state(Some("complete"), 2, true),
+        ]);
+        assert_eq!(outcome, ReadOutcome::Read(Some("complete")));
+        assert_eq!(count, 2);
+        assert_eq!(sleeps.len(), 2);
+    }
+
+    #[test]
+    fn test_cleared_pasteboard_is_not_retried() {
+        let (outcome, _, sleeps) = run(vec![state(None, 1, false)]);
+        assert_eq!(outcome, ReadOutcome::Read(None));
+        assert_eq!(sleeps, vec![INITIAL_DELAY]);
+    }
+
+    #[test]
+    fn test_gives_up_after_three_retries() {
+        let (outcome, count, sleeps) = run(vec![
+            state(Some("a"), 2, true),
+            state(Some("b"), 3, true),
+            state(None, 3, true),
+            state(Some("c"), 4, true),
+        ]);
+        assert_eq!(outcome, ReadOutcome::GaveUp);
+        assert_eq!(count, 4);
+        assert_eq!(
+            sleeps,
+            vec![
+                INITIAL_DELAY,
+                RETRY_DELAYS[0],
+                RETRY_DELAYS[1],
+                RETRY_DELAYS[2]
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_delays_increase() {
+        assert!(RETRY_DELAYS.windows(2).all(|pair| pair[0] < pair[1]));
+        let unsettled = Attempt {
+            empty: false,
+            has_content: false,
+            changed_during_read: true,
+        };
+        assert_eq!(next_step(0, unsettled), Step::Retry(RETRY_DELAYS[0]));
+        assert_eq!(next_step(RETRY_DELAYS.len(), unsettled), Step::GiveUp);
+    }
+}
@@ -0,0 +1,86 @@
+//! Screenshots copied to the clipboard on macOS
+//!
+//! Cmd+Ctrl+Shift+3/4 puts the image on the pasteboard together with a
+//! `com.apple.is-screenshot` marker type, sometimes with a file URL of a
+//! temporary file as well. Such captures are kept as images, attributed to
+//! "Screenshot" rather than the frontmost app, and titled with the time they
+//! were taken so they can be found by date.
+
+use chrono::{DateTime, TimeZone};
+use std::fmt::Display;
+
+/// Pasteboard type macOS adds to screenshots
+pub const SCREENSHOT_MARKER_UTI: &str = "com.apple.is-screenshot";
+
+/// Source app shown for screenshots
+pub const SCREENSHOT_APP: &str = "Screenshot";
+
+/// App whose icon is shown for screenshots
+pub const SCREENSHOT_APP_PATH: &str = "/System/Applications/Utilities/Screenshot.app";
+
+/// Image types a screenshot is put on the pasteboard as
+const IMAGE_UTIS: &[&str] = &["public.png", "public.tiff"];
+
+/// Whether the pasteboard types of a copy are those of a screenshot
+/// Needs the marker itself next to an image; copying a PNG from any app never
+/// adds the marker
+pub fn is_screenshot<S: AsRef<str>>(types: &[S]) -> bool {
+    let has = |uti: &str| types.iter().any(|t| t.as_ref() == uti);
+    has(SCREENSHOT_MARKER_UTI) && IMAGE_UTIS.iter().any(|uti| has(uti))
+}
+
+/// Title of a screenshot taken at `taken_at` ("Screenshot 2024-05-12 14:03")
+pub fn title<Tz: TimeZone>(taken_at: &DateTime<Tz>) -> String
+where
+    Tz::Offset: Display,
+{
+    format!("Screenshot {}", taken_at.format("%Y-%m-%d %H:%M"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    #[test]
+    fn test_screenshot_types() {
+        assert!(is_screenshot(&["public.png", "com.apple.is-screenshot"]));
+        // With the temporary file of a screenshot saved to disk as well
+        assert!(is_screenshot(&[
+            "public.file-url",
+            "public.tiff",
+            "com.apple.is-screenshot",
+        ]));
+    }
+
+    #[test]
+    fn test_ordinary_images_are_not_screenshots() {
+        let no_types: [&str; 0] = [];
+        assert!(!is_screenshot(&no_types));
+        // Copy Image in Safari, a PNG copied in Preview, a file copied in Finder
+        assert!(!is_screenshot(&["public.tiff", "public.html"]));
+        assert!(!is_screenshot(&[
+            "public.png",
+            "public.tiff",
+            "com.apple.PDFKit.PDFPasteboardType",
+        ]));
+        assert!(!is_screenshot(&["public.file-url", "public.png"]));
+        // The marker has to match exactly and come with an image
+        assert!(!is_screenshot(&[
+            "public.png",
+            "com.apple.is-screenshot.v2"
+        ]));
+        assert!(!is_screenshot(&["com.apple.is-screenshot"]));
+    }
+
+    #[test]
+    fn test_title_uses_local_time() {
+        let taken_at = Utc.with_ymd_and_hms(2024, 5, 12, 12, 3, 59).unwrap();
+        assert_eq!(title(&taken_at), "Screenshot 2024-05-12 12:03");
+        let paris = FixedOffset::east_opt(2 * 3600).unwrap();
+        assert_eq!(
+            title(&taken_at.with_timezone(&paris)),
+            "Screenshot 2024-05-12 14:03"
+        );
+    }
+}
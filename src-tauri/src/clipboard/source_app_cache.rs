@@ -0,0 +1,194 @@
+//! Short-lived cache of source app lookups
+//!
+//! Every capture looks up the app that owns the clipboard: its name and icon.
+//! On Windows that means reading the icon cache for the owning executable,
+//! on macOS rendering the app icon to PNG. Bursts of copies from the same app
+//! reuse the last result for `SOURCE_APP_TTL`.
+//!
+//! Entries are keyed by process ID on Windows, with the executable path kept
+//! alongside: when the ID comes back with another path (the process exited
+//! and the ID was reused) the entry is replaced. On macOS the key is the
+//! bundle identifier and there is no path.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a lookup is reused
+pub const SOURCE_APP_TTL: Duration = Duration::from_secs(10);
+
+/// Source app name and base64 PNG icon
+pub type SourceApp = (Option<String>, Option<String>);
+
+/// Cache hits and misses since launch, for `get_monitoring_status`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SourceAppCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct Entry {
+    path: Option<String>,
+    app: SourceApp,
+    cached_at: Instant,
+}
+
+/// Source app lookups by process ID or bundle identifier
+pub struct SourceAppCache {
+    entries: HashMap<String, Entry>,
+    ttl: Duration,
+    stats: SourceAppCacheStats,
+}
+
+impl SourceAppCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            stats: SourceAppCacheStats::default(),
+        }
+    }
+
+    /// Cached app for `key`, or the result of `resolve`
+    ///
+    /// An entry is reused while it is younger than the TTL and was cached for
+    /// the same `path`. Results without an icon are not cached, so an icon
+    /// that becomes available (Windows extracts them in the background) is
+    /// picked up by the next capture.
+    pub fn get_or_resolve(
+        &mut self,
+        key: &str,
+        path: Option<&str>,
+        now: Instant,
+        resolve: impl FnOnce() -> SourceApp,
+    ) -> SourceApp {
+        if let Some(entry) = self.entries.get(key) {
+            if entry.path.as_deref() == path && now.duration_since(entry.cached_at) < self.ttl {
+                self.stats.hits += 1;
+                return entry.app.clone();
+            }
+        }
+
+        self.stats.misses += 1;
+        let app = resolve();
+        let ttl = self.ttl;
+        self.entries
+            .retain(|_, entry| now.duration_since(entry.cached_at) < ttl);
+        if app.1.is_some() {
+            let entry = Entry {
+                path: path.map(str::to_string),
+                app: app.clone(),
+                cached_at: now,
+            };
+            self.entries.insert(key.to_string(), entry);
+        } else {
+            self.entries.remove(key);
+        }
+        app
+    }
+
+    pub fn stats(&self) -> SourceAppCacheStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    const CHROME: &str = r"C:\Program Files\Google\Chrome\chrome.exe";
+    const NOTEPAD: &str = r"C:\Windows\notepad.exe";
+
+    fn app(name: &str) -> SourceApp {
+        (Some(name.to_string()), Some(format!("{}-icon", name)))
+    }
+
+    /// Look up `key` with a resolver that counts its calls
+    fn lookup(
+        cache: &mut SourceAppCache,
+        calls: &Cell<u32>,
+        key: &str,
+        path: Option<&str>,
+        now: Instant,
+        resolved: SourceApp,
+    ) -> SourceApp {
+        cache.get_or_resolve(key, path, now, || {
+            calls.set(calls.get() + 1);
+            resolved
+        })
+    }
+
+    #[test]
+    fn test_burst_reuses_lookup() {
+        let mut cache = SourceAppCache::new(SOURCE_APP_TTL);
+        let calls = Cell::new(0);
+        let start = Instant::now();
+
+        for i in 0..5 {
+            let now = start + Duration::from_secs(i);
+            let found = lookup(&mut cache, &calls, "42", Some(CHROME), now, app("Chrome"));
+            assert_eq!(found, app("Chrome"));
+        }
+        assert_eq!(calls.get(), 1);
+        assert_eq!(cache.stats(), SourceAppCacheStats { hits: 4, misses: 1 });
+    }
+
+    #[test]
+    fn test_entry_expires_after_ttl() {
+        let mut cache = SourceAppCache::new(SOURCE_APP_TTL);
+        let calls = Cell::new(0);
+        let start = Instant::now();
+
+        lookup(&mut cache, &calls, "42", Some(CHROME), start, app("Chrome"));
+        let later = start + SOURCE_APP_TTL;
+        lookup(&mut cache, &calls, "42", Some(CHROME), later, app("Chrome"));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_reused_pid_with_other_path_misses() {
+        let mut cache = SourceAppCache::new(SOURCE_APP_TTL);
+        let calls = Cell::new(0);
+        let now = Instant::now();
+
+        lookup(&mut cache, &calls, "42", Some(CHROME), now, app("Chrome"));
+        let found = lookup(&mut cache, &calls, "42", Some(NOTEPAD), now, app("Notepad"));
+        assert_eq!(found, app("Notepad"));
+        // The new path replaced the old entry
+        let found = lookup(&mut cache, &calls, "42", Some(NOTEPAD), now, app("Other"));
+        assert_eq!(found, app("Notepad"));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_results_without_icon_are_not_cached() {
+        let mut cache = SourceAppCache::new(SOURCE_APP_TTL);
+        let calls = Cell::new(0);
+        let now = Instant::now();
+        let no_icon = (Some("Chrome".to_string()), None);
+
+        lookup(&mut cache, &calls, "42", Some(CHROME), now, no_icon.clone());
+        lookup(&mut cache, &calls, "42", Some(CHROME), now, app("Chrome"));
+        lookup(&mut cache, &calls, "42", Some(CHROME), now, no_icon);
+        assert_eq!(calls.get(), 2);
+        assert_eq!(cache.stats(), SourceAppCacheStats { hits: 1, misses: 2 });
+    }
+
+    #[test]
+    fn test_bundle_ids_are_separate_entries() {
+        let mut cache = SourceAppCache::new(SOURCE_APP_TTL);
+        let calls = Cell::new(0);
+        let now = Instant::now();
+
+        let (safari, notes) = ("com.apple.Safari", "com.apple.Notes");
+
+        let found = lookup(&mut cache, &calls, safari, None, now, app("Safari"));
+        assert_eq!(found, app("Safari"));
+        let found = lookup(&mut cache, &calls, notes, None, now, app("Notes"));
+        assert_eq!(found, app("Notes"));
+        let found = lookup(&mut cache, &calls, safari, None, now, app("Other"));
+        assert_eq!(found, app("Safari"));
+        assert_eq!(calls.get(), 2);
+    }
+}
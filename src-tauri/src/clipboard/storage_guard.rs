@@ -0,0 +1,146 @@
+//! Low disk space guard
+//!
+//! Before an image capture is saved, the monitor checks free space on the data
+//! volume. Below the `min_free_space_mb` threshold images are skipped (text is
+//! tiny and is still saved), `storage_degraded` is reported by
+//! `get_monitoring_status` and a single `storage-low` event is emitted. The
+//! flag clears on the first check that finds enough space again.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Default value of the `min_free_space_mb` setting
+pub const DEFAULT_MIN_FREE_SPACE_MB: u64 = 200;
+
+/// Change of the degraded state caused by a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GuardTransition {
+    /// Free space dropped below the threshold; notify the user once
+    BecameLow,
+    /// Enough space again; images are saved normally
+    Recovered,
+}
+
+/// Payload of the `storage-low` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StorageLowPayload {
+    /// None when a write failed with a full disk before a reading was taken
+    pub free_bytes: Option<u64>,
+    pub min_free_bytes: u64,
+}
+
+/// Tracks whether image saving is suspended for lack of disk space
+///
+/// Callers pass free-space readings in explicitly so the logic can be tested
+/// without filling a disk.
+#[derive(Debug, Default)]
+pub struct StorageGuard {
+    degraded: bool,
+}
+
+impl StorageGuard {
+    pub const fn new() -> Self {
+        Self { degraded: false }
+    }
+
+    /// Whether image saving is currently suspended
+    pub fn is_degraded(&self) -> bool {
+        self.degraded
+    }
+
+    /// Record a free-space reading against the threshold
+    pub fn record_free_space(
+        &mut self,
+        free_bytes: u64,
+        min_free_bytes: u64,
+    ) -> Option<GuardTransition> {
+        self.set_degraded(free_bytes < min_free_bytes)
+    }
+
+    /// Record a write that failed because the disk is full
+    pub fn record_disk_full(&mut self) -> Option<GuardTransition> {
+        self.set_degraded(true)
+    }
+
+    fn set_degraded(&mut self, degraded: bool) -> Option<GuardTransition> {
+        if degraded == self.degraded {
+            return None;
+        }
+        self.degraded = degraded;
+        Some(if degraded {
+            GuardTransition::BecameLow
+        } else {
+            GuardTransition::Recovered
+        })
+    }
+}
+
+/// Threshold in bytes for a `min_free_space_mb` value
+pub fn min_free_bytes(min_free_space_mb: u64) -> u64 {
+    min_free_space_mb.saturating_mul(1024 * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_low_space_notifies_once_and_recovers() {
+        let mut guard = StorageGuard::new();
+        let min = min_free_bytes(DEFAULT_MIN_FREE_SPACE_MB);
+
+        assert_eq!(guard.record_free_space(5_000 * MB, min), None);
+        assert!(!guard.is_degraded());
+
+        assert_eq!(
+            guard.record_free_space(150 * MB, min),
+            Some(GuardTransition::BecameLow)
+        );
+        assert!(guard.is_degraded());
+
+        // Still low: no repeated notification
+        assert_eq!(guard.record_free_space(100 * MB, min), None);
+        assert_eq!(guard.record_free_space(0, min), None);
+        assert!(guard.is_degraded());
+
+        // Exactly at the threshold is enough
+        assert_eq!(
+            guard.record_free_space(min, min),
+            Some(GuardTransition::Recovered)
+        );
+        assert!(!guard.is_degraded());
+
+        // Dropping again notifies again
+        assert_eq!(
+            guard.record_free_space(min - 1, min),
+            Some(GuardTransition::BecameLow)
+        );
+    }
+
+    #[test]
+    fn test_disk_full_write_degrades_until_space_returns() {
+        let mut guard = StorageGuard::new();
+        let min = min_free_bytes(50);
+
+        // The reading passed but the write still hit a full disk
+        assert_eq!(guard.record_free_space(60 * MB, min), None);
+        assert_eq!(guard.record_disk_full(), Some(GuardTransition::BecameLow));
+        assert_eq!(guard.record_disk_full(), None);
+
+        assert_eq!(guard.record_free_space(10 * MB, min), None);
+        assert_eq!(
+            guard.record_free_space(80 * MB, min),
+            Some(GuardTransition::Recovered)
+        );
+    }
+
+    #[test]
+    fn test_zero_threshold_never_degrades() {
+        let mut guard = StorageGuard::new();
+        assert_eq!(guard.record_free_space(0, min_free_bytes(0)), None);
+        assert!(!guard.is_degraded());
+        assert_eq!(min_free_bytes(u64::MAX), u64::MAX);
+    }
+}
@@ -0,0 +1,127 @@
+//! Normalization of captured text
+//!
+//! Text copied from Windows apps often carries CRLF line endings, a leading
+//! BOM or zero-width characters that break pasting into terminals. These pure
+//! functions clean it up according to the `normalize_text` setting.
+
+/// Characters removed in `Full` mode
+///
+/// ZWJ (U+200D) and ZWNJ (U+200C) are deliberately kept: they are part of
+/// emoji sequences and of several scripts.
+const INVISIBLE_CHARS: &[char] = &[
+    '\u{FEFF}', // BOM / zero-width no-break space
+    '\u{200B}', // zero-width space
+    '\u{2060}', // word joiner
+    '\u{180E}', // Mongolian vowel separator
+];
+
+/// Normalization level from the `normalize_text` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalizeMode {
+    /// Store text exactly as captured
+    Off,
+    /// Convert CRLF and lone CR to LF
+    LineEndings,
+    /// Line endings plus BOM and zero-width character removal
+    Full,
+}
+
+impl NormalizeMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NormalizeMode::Off => "off",
+            NormalizeMode::LineEndings => "line_endings",
+            NormalizeMode::Full => "full",
+        }
+    }
+
+    /// Parse a setting value (unknown values disable normalization)
+    pub fn from_setting(value: &str) -> Self {
+        match value {
+            "line_endings" => NormalizeMode::LineEndings,
+            "full" => NormalizeMode::Full,
+            _ => NormalizeMode::Off,
+        }
+    }
+}
+
+/// Convert CRLF and lone CR line endings to LF
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Remove BOMs and zero-width characters (keeps ZWJ/ZWNJ)
+pub fn strip_invisible_chars(text: &str) -> String {
+    text.chars().filter(|c| !INVISIBLE_CHARS.contains(c)).collect()
+}
+
+/// Apply the given normalization mode to captured text
+pub fn normalize_text(text: &str, mode: NormalizeMode) -> String {
+    match mode {
+        NormalizeMode::Off => text.to_string(),
+        NormalizeMode::LineEndings => normalize_line_endings(text),
+        NormalizeMode::Full => strip_invisible_chars(&normalize_line_endings(text)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mode_from_setting() {
+        for mode in [NormalizeMode::Off, NormalizeMode::LineEndings, NormalizeMode::Full] {
+            assert_eq!(NormalizeMode::from_setting(mode.as_str()), mode);
+        }
+        assert_eq!(NormalizeMode::from_setting("bogus"), NormalizeMode::Off);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_eq!(normalize_line_endings("a\r\nb"), "a\nb");
+        assert_eq!(normalize_line_endings("a\rb"), "a\nb");
+        assert_eq!(normalize_line_endings("a\nb"), "a\nb");
+        // Mixed endings, including CR CR LF (one lone CR then a CRLF)
+        assert_eq!(normalize_line_endings("1\r\n2\r3\n4\r\r\n5"), "1\n2\n3\n4\n\n5");
+        assert_eq!(normalize_line_endings("\r\n\r\n"), "\n\n");
+        assert_eq!(normalize_line_endings("trailing\r"), "trailing\n");
+        assert_eq!(normalize_line_endings(""), "");
+    }
+
+    #[test]
+    fn test_off_keeps_original() {
+        let text = "\u{FEFF}a\r\nb\u{200B}";
+        assert_eq!(normalize_text(text, NormalizeMode::Off), text);
+    }
+
+    #[test]
+    fn test_line_endings_mode_keeps_invisible_chars() {
+        assert_eq!(
+            normalize_text("\u{FEFF}a\r\nb\u{200B}", NormalizeMode::LineEndings),
+            "\u{FEFF}a\nb\u{200B}"
+        );
+    }
+
+    #[test]
+    fn test_full_strips_bom_and_zero_width() {
+        assert_eq!(normalize_text("\u{FEFF}echo hi\r\n", NormalizeMode::Full), "echo hi\n");
+        assert_eq!(normalize_text("ls\u{200B} -la", NormalizeMode::Full), "ls -la");
+        assert_eq!(normalize_text("a\u{2060}b\u{180E}c", NormalizeMode::Full), "abc");
+        assert_eq!(normalize_text("\u{200B}\u{FEFF}", NormalizeMode::Full), "");
+    }
+
+    #[test]
+    fn test_full_keeps_zwj_in_emoji() {
+        // Family emoji and rainbow flag are ZWJ sequences
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let flag = "\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}";
+        assert_eq!(normalize_text(family, NormalizeMode::Full), family);
+        assert_eq!(normalize_text(flag, NormalizeMode::Full), flag);
+
+        let mixed = format!("\u{FEFF}{}\u{200B} ok\r\n", family);
+        assert_eq!(normalize_text(&mixed, NormalizeMode::Full), format!("{} ok\n", family));
+
+        // ZWNJ is part of Persian text and is kept too
+        assert_eq!(normalize_text("می\u{200C}خواهم", NormalizeMode::Full), "می\u{200C}خواهم");
+    }
+}
@@ -0,0 +1,115 @@
+//! Transforms applied to text as it is copied back
+//!
+//! A transform rewrites the text written to the clipboard; the stored item is
+//! left unchanged. `copy_to_clipboard` takes one explicitly, and a pinboard can
+//! set a `default_transform` for the items filed in it. An explicit transform
+//! wins over the pinboard's, and `skip_transform` copies the item as stored
+//! unless one is passed explicitly. Only text and links are transformed.
+
+use serde::{Deserialize, Serialize};
+
+/// A transform, stored by its `as_str` identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Contacts and events are written as text only, without their own type
+    PlainText,
+    /// Leading and trailing whitespace removed
+    Trim,
+    Lowercase,
+    /// Lowercased, then trimmed
+    LowercaseTrimmed,
+}
+
+impl Transform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Transform::PlainText => "plain_text",
+            Transform::Trim => "trim",
+            Transform::Lowercase => "lowercase",
+            Transform::LowercaseTrimmed => "lowercase_trimmed",
+        }
+    }
+
+    /// Transform stored in the database; unknown values are ignored
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "plain_text" => Some(Transform::PlainText),
+            "trim" => Some(Transform::Trim),
+            "lowercase" => Some(Transform::Lowercase),
+            "lowercase_trimmed" => Some(Transform::LowercaseTrimmed),
+            _ => None,
+        }
+    }
+
+    /// `text` as it is written to the clipboard
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Transform::PlainText => text.to_string(),
+            Transform::Trim => text.trim().to_string(),
+            Transform::Lowercase => text.to_lowercase(),
+            Transform::LowercaseTrimmed => text.trim().to_lowercase(),
+        }
+    }
+}
+
+/// Transform of a copy: `explicit` if given, otherwise the pinboard's
+/// `default_transform` unless `skip_default` is set
+pub fn resolve(
+    explicit: Option<Transform>,
+    pinboard_default: Option<Transform>,
+    skip_default: bool,
+) -> Option<Transform> {
+    explicit.or(pinboard_default.filter(|_| !skip_default))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Transform; 4] = [
+        Transform::PlainText,
+        Transform::Trim,
+        Transform::Lowercase,
+        Transform::LowercaseTrimmed,
+    ];
+
+    #[test]
+    fn test_identifiers_round_trip() {
+        for transform in ALL {
+            assert_eq!(Transform::parse(transform.as_str()), Some(transform));
+            let json = serde_json::to_string(&transform).unwrap();
+            assert_eq!(json, format!("\"{}\"", transform.as_str()));
+        }
+        assert_eq!(Transform::parse("uppercase"), None);
+        assert_eq!(Transform::parse(""), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let text = "  SELECT * FROM Items\n";
+        assert_eq!(Transform::PlainText.apply(text), text);
+        assert_eq!(Transform::Trim.apply(text), "SELECT * FROM Items");
+        assert_eq!(Transform::Lowercase.apply(text), "  select * from items\n");
+        assert_eq!(
+            Transform::LowercaseTrimmed.apply(text),
+            "select * from items"
+        );
+        assert_eq!(Transform::LowercaseTrimmed.apply("ÉTÉ "), "été");
+    }
+
+    #[test]
+    fn test_explicit_transform_wins() {
+        let lower = Some(Transform::LowercaseTrimmed);
+        let plain = Some(Transform::PlainText);
+
+        assert_eq!(resolve(None, None, false), None);
+        assert_eq!(resolve(None, lower, false), lower);
+        assert_eq!(resolve(plain, lower, false), plain);
+        assert_eq!(resolve(plain, None, false), plain);
+
+        // Skipping drops the pinboard default only
+        assert_eq!(resolve(None, lower, true), None);
+        assert_eq!(resolve(plain, lower, true), plain);
+    }
+}
@@ -0,0 +1,307 @@
+//! Source app names and icon pixels on Windows
+//!
+//! The Win32 calls live in `gdi` and `clipboard_monitor`; what they return is
+//! turned into names and images here, where it can be tested on any platform.
+//! GetDIBits fills rows at the bitmap's stride, which is only `width * 4` for
+//! 32-bit rows, and bottom-up unless asked otherwise, so the pixels are read
+//! row by row at the stride the header reports. Icons drawn onto a screen
+//! bitmap can come back with no alpha at all (seen on ARM64 drivers); those
+//! are made opaque rather than saved fully transparent. Packaged (MSIX) apps
+//! are named after their package, since their executables often can't be
+//! opened for their module name.
+
+use crate::clipboard::dib;
+use image::RgbaImage;
+use std::path::Path;
+
+/// Suffixes of package names that don't name the app ("Microsoft.MicrosoftEdge.Stable")
+const PACKAGE_CHANNELS: [&str; 5] = ["stable", "beta", "dev", "canary", "preview"];
+
+/// Process that owns a window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerProcess {
+    pub pid: u32,
+    pub exe_path: String,
+    /// AppX package full name of packaged apps
+    /// ("Microsoft.WindowsTerminal_1.18.2822.0_x64__8wekyb3d8bbwe")
+    pub package_full_name: Option<String>,
+}
+
+impl OwnerProcess {
+    /// Friendly name: the package's for packaged apps, else the executable's
+    pub fn app_name(&self) -> Option<String> {
+        self.package_full_name
+            .as_deref()
+            .and_then(package_app_name)
+            .or_else(|| app_name_from_path(&self.exe_path))
+    }
+}
+
+/// Extract a friendly application name from an executable path
+pub fn app_name_from_path(exe_path: &str) -> Option<String> {
+    // Backslashes aren't separators outside Windows; the tests run everywhere
+    let file_name = exe_path.rsplit(['\\', '/']).next()?;
+    let file_name = Path::new(file_name).file_stem()?.to_str()?;
+
+    // Convert to friendly name
+    let friendly_name = match file_name.to_lowercase().as_str() {
+        "chrome" => "Chrome",
+        "firefox" => "Firefox",
+        "msedge" => "Edge",
+        "code" => "Visual Studio Code",
+        "notepad" => "Notepad",
+        "notepad++" => "Notepad++",
+        "explorer" => "Explorer",
+        "outlook" => "Outlook",
+        "excel" => "Excel",
+        "winword" => "Word",
+        "powerpnt" => "PowerPoint",
+        "teams" => "Teams",
+        "slack" => "Slack",
+        "discord" => "Discord",
+        "spotify" => "Spotify",
+        "terminal" => "Terminal",
+        "windowsterminal" => "Windows Terminal",
+        "powershell" => "PowerShell",
+        "cmd" => "Command Prompt",
+        _ => return capitalize(file_name),
+    };
+
+    Some(friendly_name.to_string())
+}
+
+/// Friendly name of a packaged app from its package full name
+/// "Microsoft.WindowsTerminal_1.18.2822.0_x64__8wekyb3d8bbwe" -> "Windows Terminal"
+pub fn package_app_name(package_full_name: &str) -> Option<String> {
+    // Name, version, architecture, resource ID and publisher ID, joined by '_'
+    let name = package_full_name.split('_').next()?;
+    // "Publisher.App", sometimes followed by a release channel
+    let app = name
+        .rsplit('.')
+        .find(|part| !PACKAGE_CHANNELS.contains(&part.to_lowercase().as_str()))?;
+
+    let friendly_name = match app.to_lowercase().as_str() {
+        "" => return None,
+        "microsoftedge" => "Edge",
+        "windowsnotepad" => "Notepad",
+        "windowscalculator" => "Calculator",
+        "whatsappdesktop" => "WhatsApp",
+        "spotifymusic" => "Spotify",
+        _ => return Some(split_camel_case(app)),
+    };
+    Some(friendly_name.to_string())
+}
+
+/// First letter capitalized; None for an empty name
+fn capitalize(name: &str) -> Option<String> {
+    let mut chars = name.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+/// "WindowsTerminal" -> "Windows Terminal"; runs of capitals stay together
+/// ("OneNoteUWP" -> "One Note UWP")
+fn split_camel_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1);
+        let starts_word = c.is_uppercase()
+            && previous.is_some_and(|p| {
+                p.is_lowercase() || (p.is_uppercase() && next.is_some_and(|n| n.is_lowercase()))
+            });
+        if starts_word {
+            words.push(' ');
+        }
+        words.push(c);
+    }
+    words
+}
+
+/// Top-down RGBA image from GetDIBits output: 32-bit BGRA rows `stride`
+/// bytes apart, bottom-up unless `top_down`
+/// None when the buffer is too short for the rows or the stride too narrow
+pub fn bgra_to_rgba(
+    buffer: &[u8],
+    width: u32,
+    height: u32,
+    stride: usize,
+    top_down: bool,
+) -> Option<RgbaImage> {
+    let row_len = width as usize * 4;
+    if width == 0 || height == 0 || stride < row_len {
+        return None;
+    }
+    let needed = stride
+        .checked_mul(height as usize - 1)?
+        .checked_add(row_len)?;
+    if buffer.len() < needed {
+        return None;
+    }
+
+    let mut pixels = Vec::with_capacity(row_len * height as usize);
+    for y in 0..height as usize {
+        let source_row = if top_down { y } else { height as usize - 1 - y };
+        let start = source_row * stride;
+        for bgra in buffer[start..start + row_len].chunks_exact(4) {
+            pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+    RgbaImage::from_raw(width, height, pixels)
+}
+
+/// Bytes per row of a DIB as described by its header
+/// None for anything but the 32-bit rows `bgra_to_rgba` reads
+pub fn bgra_stride(width: u32, bits_per_pixel: u16) -> Option<usize> {
+    (bits_per_pixel == 32).then(|| dib::stride(width, bits_per_pixel))
+}
+
+/// Make an image opaque when none of its pixels has any alpha
+/// Returns whether it had to be
+pub fn restore_missing_alpha(image: &mut RgbaImage) -> bool {
+    if image.pixels().any(|pixel| pixel.0[3] != 0) {
+        return false;
+    }
+    for pixel in image.pixels_mut() {
+        pixel.0[3] = 255;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// BGRA rows of `width` pixels, each padded with `padding` bytes of 0xEE
+    fn buffer(rows: &[&[[u8; 4]]], padding: usize) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        for row in rows {
+            for pixel in *row {
+                buffer.extend_from_slice(pixel);
+            }
+            buffer.extend(std::iter::repeat_n(0xEE, padding));
+        }
+        buffer
+    }
+
+    const BLUE: [u8; 4] = [255, 0, 0, 255];
+    const RED: [u8; 4] = [0, 0, 255, 255];
+    const GREEN_HALF: [u8; 4] = [0, 255, 0, 128];
+
+    #[test]
+    fn test_swaps_channels_of_top_down_rows() {
+        let data = buffer(&[&[BLUE, RED], &[GREEN_HALF, BLUE]], 0);
+        let image = bgra_to_rgba(&data, 2, 2, 8, true).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(1, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(0, 1).0, [0, 255, 0, 128]);
+    }
+
+    #[test]
+    fn test_skips_row_padding() {
+        // 3 pixels per row with 4 bytes of padding: reading as width * 4
+        // would shift every later row by a pixel
+        let data = buffer(&[&[RED, RED, RED], &[BLUE, BLUE, BLUE]], 4);
+        let image = bgra_to_rgba(&data, 3, 2, 16, true).unwrap();
+        for x in 0..3 {
+            assert_eq!(image.get_pixel(x, 0).0, [255, 0, 0, 255]);
+            assert_eq!(image.get_pixel(x, 1).0, [0, 0, 255, 255]);
+        }
+        // The last row needs no padding
+        assert!(bgra_to_rgba(&data[..data.len() - 4], 3, 2, 16, true).is_some());
+    }
+
+    #[test]
+    fn test_flips_bottom_up_rows() {
+        let data = buffer(&[&[RED], &[BLUE]], 0);
+        let image = bgra_to_rgba(&data, 1, 2, 4, false).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(image.get_pixel(0, 1).0, [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_rejects_short_buffers_and_narrow_strides() {
+        let data = buffer(&[&[RED, RED], &[RED, RED]], 0);
+        assert!(bgra_to_rgba(&data[..15], 2, 2, 8, true).is_none());
+        assert!(bgra_to_rgba(&data, 2, 2, 4, true).is_none());
+        assert!(bgra_to_rgba(&data, 0, 2, 8, true).is_none());
+        assert!(bgra_to_rgba(&data, 2, 0, 8, true).is_none());
+        assert!(bgra_to_rgba(&data, 2, 3, usize::MAX, true).is_none());
+    }
+
+    #[test]
+    fn test_bgra_stride() {
+        assert_eq!(bgra_stride(3, 32), Some(12));
+        assert_eq!(bgra_stride(32, 32), Some(128));
+        assert_eq!(bgra_stride(3, 24), None);
+        assert_eq!(bgra_stride(3, 1), None);
+    }
+
+    #[test]
+    fn test_restore_missing_alpha() {
+        let mut no_alpha = RgbaImage::from_pixel(2, 2, image::Rgba([10, 20, 30, 0]));
+        assert!(restore_missing_alpha(&mut no_alpha));
+        assert!(no_alpha.pixels().all(|pixel| pixel.0 == [10, 20, 30, 255]));
+
+        // Real transparency is kept
+        let mut icon = RgbaImage::from_pixel(2, 2, image::Rgba([0, 0, 0, 0]));
+        icon.put_pixel(1, 1, image::Rgba([1, 2, 3, 200]));
+        assert!(!restore_missing_alpha(&mut icon));
+        assert_eq!(icon.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn test_app_name_from_path() {
+        let name = |path: &str| app_name_from_path(path);
+        assert_eq!(
+            name(r"C:\Program Files\Google\Chrome\Application\chrome.exe").as_deref(),
+            Some("Chrome")
+        );
+        assert_eq!(
+            name(r"C:\Windows\System32\WINWORD.EXE").as_deref(),
+            Some("Word")
+        );
+        assert_eq!(name(r"C:\Tools\obsidian.exe").as_deref(), Some("Obsidian"));
+        assert_eq!(name(r"C:\Tools\").as_deref(), None);
+    }
+
+    #[test]
+    fn test_package_app_name() {
+        let name = |package: &str| package_app_name(package);
+        assert_eq!(
+            name("Microsoft.WindowsTerminal_1.18.2822.0_x64__8wekyb3d8bbwe").as_deref(),
+            Some("Windows Terminal")
+        );
+        assert_eq!(
+            name("Microsoft.MicrosoftEdge.Stable_120.0.2210.91_neutral__8wekyb3d8bbwe").as_deref(),
+            Some("Edge")
+        );
+        assert_eq!(
+            name("5319275A.WhatsAppDesktop_2.2401.4.0_x64__cv1g1gvanyjgm").as_deref(),
+            Some("WhatsApp")
+        );
+        assert_eq!(
+            name("Microsoft.OneNoteUWP_16001.14326.21802.0_arm64__8wekyb3d8bbwe").as_deref(),
+            Some("One Note UWP")
+        );
+        assert_eq!(name("Figma_1.0.0.0_x64__abc").as_deref(), Some("Figma"));
+        assert_eq!(name("").as_deref(), None);
+    }
+
+    #[test]
+    fn test_owner_process_prefers_package_name() {
+        let mut process = OwnerProcess {
+            pid: 42,
+            exe_path: r"C:\Program Files\WindowsApps\Pkg\WindowsTerminal.exe".to_string(),
+            package_full_name: Some("Microsoft.WindowsTerminalPreview_1.0_x64__8we".to_string()),
+        };
+        assert_eq!(
+            process.app_name().as_deref(),
+            Some("Windows Terminal Preview")
+        );
+
+        process.package_full_name = None;
+        assert_eq!(process.app_name().as_deref(), Some("Windows Terminal"));
+    }
+}
@@ -1,28 +1,104 @@
 use crate::clipboard::clipboard_reader;
-use crate::models::ClipboardItem;
+use crate::clipboard::copy_back::{self, CopyContent};
+use crate::clipboard::palette::DEFAULT_COLOR_TOLERANCE;
+use crate::clipboard::transforms::Transform;
+use crate::commands::window_commands::hide_panel;
+use crate::error::{ClipsterError, Context};
+use crate::events::{self, ItemUpdatedPayload, ItemsUpdatedPayload};
+use crate::models::{
+    AppGroup, BulkItemResult, BulkOp, ChangeSet, ClipboardItem, ContentType, DuplicateGroup,
+    DuplicateKind, ItemRevision, ReclassifyReport, SearchGroup,
+};
+use crate::paste_target;
+use crate::share_card::{self, CardContent, DEFAULT_CARD_WIDTH};
+use crate::storage::database::{HistorySort, SearchFilter};
+use crate::storage::destructive_guard::{self, DestructiveAction, Guarded};
+use crate::storage::drag_payload::{self, DragPayloads};
+use crate::storage::file_storage::{self, ExportFormat, ImageExportError};
+use crate::storage::history_archive::{self, ArchiveReport};
+use crate::storage::image_info::{self, ImageInfo};
+use crate::storage::zip_export::{self, ZipExportSummary};
+use crate::storage::{profiles, Database};
+use crate::templates::{self, Placeholder};
+use crate::type_text::{self, TypingProgress, TypingStatus};
 use crate::AppState;
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use serde_json;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
 
 /// Get current clipboard text (legacy command)
 #[tauri::command]
-pub fn get_clipboard() -> Result<String, String> {
+pub fn get_clipboard() -> Result<String, ClipsterError> {
     clipboard_reader::get_clipboard_text()
 }
 
 /// Get clipboard history with pagination
+/// Thumbnails are omitted unless `include_thumbnails` is set; use `get_thumbnails`
+/// `sort` orders by `created_at` (default) or `first_copied_at`
 #[tauri::command]
 pub fn get_clipboard_history(
     state: State<'_, AppState>,
     limit: Option<usize>,
     offset: Option<usize>,
-) -> Result<Vec<ClipboardItem>, String> {
+    include_thumbnails: Option<bool>,
+    sort: Option<HistorySort>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
-    state.db.get_items(limit, offset)
+    state.db.get_items(
+        limit,
+        offset,
+        include_thumbnails.unwrap_or(false),
+        sort.unwrap_or_default(),
+    )
+}
+
+/// Get history grouped by source app for the Apps view, `limit_per_app` items per app
+#[tauri::command]
+pub fn get_history_by_app(
+    state: State<'_, AppState>,
+    limit_per_app: Option<usize>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<AppGroup>, ClipsterError> {
+    state.db.get_items_grouped_by_app(
+        limit_per_app.unwrap_or(10),
+        include_thumbnails.unwrap_or(false),
+    )
+}
+
+/// Get the thumbnails of the given items (ID -> base64 PNG)
+/// Called for the items in view after a list was fetched without thumbnails
+#[tauri::command]
+pub fn get_thumbnails(
+    state: State<'_, AppState>,
+    item_ids: Vec<String>,
+) -> Result<HashMap<String, String>, ClipsterError> {
+    let unlocked = state.pinboard_locks.unlocked_ids(chrono::Utc::now());
+    state.db.get_thumbnails(&item_ids, &unlocked)
+}
+
+/// Fail if the item is filed (or auto-filed) in a locked pinboard
+pub(crate) fn ensure_item_unlocked(state: &AppState, item_id: &str) -> Result<(), ClipsterError> {
+    match state.db.get_item(item_id)? {
+        Some(item) => state
+            .pinboard_locks
+            .require_unlocked(&state.db, &item, chrono::Utc::now()),
+        None => Ok(()),
+    }
+}
+
+/// Load an item, failing if it is filed (or auto-filed) in a locked pinboard
+pub(crate) fn require_unlocked_item(
+    state: &AppState,
+    item_id: &str,
+) -> Result<ClipboardItem, ClipsterError> {
+    let item = state.db.require_item(item_id)?;
+    state
+        .pinboard_locks
+        .require_unlocked(&state.db, &item, chrono::Utc::now())?;
+    Ok(item)
 }
 
 /// Get a single clipboard item by ID
@@ -30,8 +106,63 @@ pub fn get_clipboard_history(
 pub fn get_clipboard_item(
     state: State<'_, AppState>,
     id: String,
-) -> Result<Option<ClipboardItem>, String> {
-    state.db.get_item(&id)
+) -> Result<Option<ClipboardItem>, ClipsterError> {
+    let item = state.db.get_item(&id)?;
+    if let Some(item) = &item {
+        state
+            .pinboard_locks
+            .require_unlocked(&state.db, item, chrono::Utc::now())?;
+    }
+    Ok(item)
+}
+
+/// Get item changes since a change-log sequence number
+/// Used to resync after a webview reload or wake from sleep; if `reset` is set
+/// the range is no longer available and the full list must be refetched.
+/// Items of locked pinboards come without their content
+#[tauri::command]
+pub fn get_changes_since(
+    state: State<'_, AppState>,
+    seq: i64,
+) -> Result<ChangeSet, ClipsterError> {
+    state.db.get_changes_since(seq)
+}
+
+/// Replace the text of a text or link item, keeping the previous text as a revision
+/// Returns None if the item doesn't exist
+#[tauri::command]
+pub fn update_item_content(
+    state: State<'_, AppState>,
+    id: String,
+    content_text: String,
+) -> Result<Option<ClipboardItem>, ClipsterError> {
+    ensure_item_unlocked(&state, &id)?;
+    let storage = state.file_storage.lock()?.clone();
+    state.db.update_item_content(&id, &content_text, &storage)
+}
+
+/// Get the previous versions of an edited item, newest first
+#[tauri::command]
+pub fn get_item_revisions(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<Vec<ItemRevision>, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
+    state.db.get_item_revisions(&item_id)
+}
+
+/// Restore a previous version of an item (the current text becomes a new revision)
+#[tauri::command]
+pub fn restore_item_revision(
+    state: State<'_, AppState>,
+    item_id: String,
+    revision_no: i64,
+) -> Result<Option<ClipboardItem>, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
+    let storage = state.file_storage.lock()?.clone();
+    state
+        .db
+        .restore_item_revision(&item_id, revision_no, &storage)
 }
 
 /// Delete a clipboard item by ID
@@ -39,72 +170,423 @@ pub fn get_clipboard_item(
 pub fn delete_clipboard_item(
     state: State<'_, AppState>,
     id: String,
-) -> Result<bool, String> {
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &id)?;
     state.db.delete_item(&id)
 }
 
 /// Search clipboard history by text content
+/// Large text items only match on their preview unless `deep` is set
+/// `language` (ISO 639-3 code) keeps only text detected in that language
 #[tauri::command]
 pub fn search_clipboard(
     state: State<'_, AppState>,
     query: String,
     limit: Option<usize>,
-) -> Result<Vec<ClipboardItem>, String> {
+    deep: Option<bool>,
+    language: Option<String>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
     let limit = limit.unwrap_or(50);
-    state.db.search_items(&query, limit)
+    let include_thumbnails = include_thumbnails.unwrap_or(false);
+    let filter = SearchFilter { language };
+    if deep.unwrap_or(false) {
+        state.db.search_items_deep(&query, &filter, limit, include_thumbnails)
+    } else {
+        state.db.search_items_filtered(&query, &filter, limit, include_thumbnails)
+    }
+}
+
+/// Search grouped by pinboard (history first), `per_group_limit` items per group
+/// Locked pinboards are only searched while unlocked; archived ones only with
+/// `include_archived`
+#[tauri::command]
+pub fn search_grouped(
+    state: State<'_, AppState>,
+    query: String,
+    per_group_limit: Option<usize>,
+    include_archived: Option<bool>,
+    language: Option<String>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<SearchGroup>, ClipsterError> {
+    let unlocked = state.pinboard_locks.unlocked_ids(chrono::Utc::now());
+    state.db.search_items_grouped(
+        &query,
+        &SearchFilter { language },
+        per_group_limit.unwrap_or(5),
+        &unlocked,
+        include_archived.unwrap_or(false),
+        include_thumbnails.unwrap_or(false),
+    )
+}
+
+/// Image items with a dominant color near `hex` (`#rrggbb` or `#rgb`)
+/// `tolerance` is the largest RGB distance that still matches
+#[tauri::command]
+pub fn get_items_by_color(
+    state: State<'_, AppState>,
+    hex: String,
+    tolerance: Option<f64>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
+    state.db.get_items_by_color(
+        &hex,
+        tolerance.unwrap_or(DEFAULT_COLOR_TOLERANCE),
+        include_thumbnails.unwrap_or(false),
+    )
 }
 
 /// Clear all clipboard history (except favorites and pinned items)
+/// Without `confirm_token` (and unless the setting is off) nothing is deleted:
+/// a token and what would be deleted are returned, see `destructive_guard`
 #[tauri::command]
-pub fn clear_clipboard_history(state: State<'_, AppState>) -> Result<usize, String> {
-    state.db.clear_history()
+pub fn clear_clipboard_history(
+    state: State<'_, AppState>,
+    confirm_token: Option<String>,
+) -> Result<Guarded<usize>, ClipsterError> {
+    state.destructive_guard.run(
+        destructive_guard::confirmation_required(&state.db),
+        DestructiveAction::ClearHistory,
+        confirm_token.as_deref(),
+        chrono::Utc::now(),
+        || state.db.clear_history_summary(),
+        || state.db.clear_history(),
+    )
+}
+
+/// Move history items older than `days` (favorites and pinboard items
+/// excepted) and their images into the profile's archive, then vacuum the
+/// database; emits `items-updated` with the archived items as deleted
+#[tauri::command]
+pub async fn archive_items_older_than(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    days: u32,
+) -> Result<ArchiveReport, ClipsterError> {
+    if days == 0 {
+        return Err(ClipsterError::Validation(
+            "Archive items at least one day old".to_string(),
+        ));
+    }
+    let db = state.db.clone();
+    let data_dir = profiles::active_data_dir().map_err(ClipsterError::Io)?;
+
+    let report = tauri::async_runtime::spawn_blocking(move || {
+        let archive = Database::open_archive(&data_dir)?;
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+        history_archive::archive_items_older_than(&db, &archive, cutoff)
+    })
+    .await
+    .map_err(|e| ClipsterError::Database(format!("Archiving failed: {}", e)))??;
+
+    let payload = ItemsUpdatedPayload {
+        deleted: report.archived_ids.clone(),
+        ..Default::default()
+    };
+    events::emit_items_updated(&app, &payload);
+    Ok(report)
+}
+
+/// Search the profile's archive by text content (empty if nothing was archived)
+#[tauri::command]
+pub fn search_archive(
+    query: String,
+    limit: Option<usize>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
+    let data_dir = profiles::active_data_dir().map_err(ClipsterError::Io)?;
+    match history_archive::open_existing(&data_dir)? {
+        Some(archive) => archive.search_items(
+            &query,
+            limit.unwrap_or(50),
+            include_thumbnails.unwrap_or(false),
+        ),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Move an archived item and its image back into history
+#[tauri::command]
+pub fn restore_archived_item(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ClipboardItem, ClipsterError> {
+    let data_dir = profiles::active_data_dir().map_err(ClipsterError::Io)?;
+    let archive = history_archive::open_existing(&data_dir)?
+        .ok_or_else(|| ClipsterError::NotFound("Item not found".to_string()))?;
+    history_archive::restore_archived_item(&state.db, &archive, &id)
+}
+
+/// Report groups of duplicate items: identical content (`exact`) or images
+/// that look alike (`images`)
+/// Runs off the main thread since every stored file is read (and decoded for images)
+#[tauri::command]
+pub async fn find_duplicates(
+    state: State<'_, AppState>,
+    kind: DuplicateKind,
+) -> Result<Vec<DuplicateGroup>, ClipsterError> {
+    let db = state.db.clone();
+
+    tauri::async_runtime::spawn_blocking(move || db.find_duplicates(kind))
+        .await
+        .map_err(|e| ClipsterError::Database(format!("Duplicate search failed: {}", e)))?
+}
+
+/// Merge a group of duplicates into the kept item and delete the others
+/// Returns the number of items removed
+#[tauri::command]
+pub fn merge_duplicate_group(
+    state: State<'_, AppState>,
+    keep_id: String,
+    remove_ids: Vec<String>,
+) -> Result<usize, ClipsterError> {
+    for id in std::iter::once(&keep_id).chain(&remove_ids) {
+        ensure_item_unlocked(&state, id)?;
+    }
+    state.db.merge_duplicate_group(&keep_id, &remove_ids)
+}
+
+/// Re-run content type and language detection on every item, for items
+/// captured before a detector existed (e.g. links saved as text)
+/// Reports the changes grouped by transition; unless `dry_run`, applies them
+/// and emits `item-updated` for each changed item. Running it again finds nothing
+#[tauri::command]
+pub async fn reclassify_items(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dry_run: bool,
+) -> Result<ReclassifyReport, ClipsterError> {
+    let db = state.db.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let changes = db.find_reclassifications()?;
+        if dry_run {
+            let language_changes = db.find_language_changes()?;
+            return Ok(ReclassifyReport::new(&changes, &language_changes, true));
+        }
+
+        let mut applied = Vec::with_capacity(changes.len());
+        db.apply_reclassifications(&changes, |batch| {
+            for change in batch {
+                let payload = ItemUpdatedPayload {
+                    id: change.id.clone(),
+                    content_type: Some(change.to),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&app, &payload);
+            }
+            applied.extend_from_slice(batch);
+        })?;
+
+        // After the type changes, which decide what is text
+        let language_changes = db.find_language_changes()?;
+        let mut languages_applied = Vec::with_capacity(language_changes.len());
+        db.apply_language_changes(&language_changes, |batch| {
+            for change in batch.iter().filter(|change| change.to.is_some()) {
+                let payload = ItemUpdatedPayload {
+                    id: change.id.clone(),
+                    language: change.to.clone(),
+                    ..Default::default()
+                };
+                events::emit_item_updated(&app, &payload);
+            }
+            languages_applied.extend_from_slice(batch);
+        })?;
+        Ok(ReclassifyReport::new(&applied, &languages_applied, false))
+    })
+    .await
+    .map_err(|e| ClipsterError::Database(format!("Reclassification failed: {}", e)))?
 }
 
 /// Copy an item back to the system clipboard
+/// Counts one use of the item, however many formats are written.
+/// Text goes through `transform`, or else the `default_transform` of the
+/// item's pinboard unless `skip_transform` is set
 #[tauri::command]
 pub fn copy_to_clipboard(
     state: State<'_, AppState>,
     id: String,
-) -> Result<(), String> {
+    transform: Option<Transform>,
+    skip_transform: Option<bool>,
+) -> Result<(), ClipsterError> {
     println!("[copy_to_clipboard] Called with id: {}", id);
+    ensure_item_unlocked(&state, &id)?;
 
-    let item = state
-        .db
-        .get_item(&id)?
-        .ok_or_else(|| "Item not found".to_string())?;
-
-    println!("[copy_to_clipboard] Found item, type: {:?}", item.content_type);
-
-    match item.content_type {
-        crate::models::ContentType::Text | crate::models::ContentType::Link => {
-            if let Some(text) = &item.content_text {
-                println!("[copy_to_clipboard] Setting clipboard text (length: {})", text.len());
-                clipboard_reader::set_clipboard_text(text)?;
-                println!("[copy_to_clipboard] Successfully set clipboard text");
-            } else {
-                return Err("No text content in item".to_string());
-            }
-        }
-        crate::models::ContentType::Image => {
-            if let Some(image_path) = &item.image_path {
-                clipboard_reader::set_clipboard_image(image_path)?;
-            } else {
-                return Err("No image path for item".to_string());
+    let skip_transform = skip_transform.unwrap_or(false);
+    let strip_metadata = state.db.get_setting("strip_image_metadata")?.as_deref() == Some("true");
+    copy_back::copy_item(
+        &state.db,
+        &id,
+        transform,
+        skip_transform,
+        |content, exclude| {
+            if let CopyContent::Text(text) = &content {
+                println!(
+                    "[copy_to_clipboard] Setting clipboard text (length: {})",
+                    text.len()
+                );
             }
+            clipboard_reader::write_copy_content(content, strip_metadata, exclude)
+        },
+    )
+}
+
+/// Get the placeholders of a snippet (`{name}` or `{name:default}`), in
+/// order of first use; empty when the item has none
+#[tauri::command]
+pub fn get_item_placeholders(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<Vec<Placeholder>, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
+    templates::item_placeholders(&state.db, &item_id)
+}
+
+/// Copy a snippet to the clipboard with its placeholders filled in
+/// Fields missing from `values` take their default; fails if one has none.
+/// The stored item keeps its placeholders
+#[tauri::command]
+pub fn copy_item_with_values(
+    state: State<'_, AppState>,
+    item_id: String,
+    values: HashMap<String, String>,
+) -> Result<(), ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
+    copy_back::copy_filled_item(&state.db, &item_id, &values, |text, exclude| {
+        clipboard_reader::set_clipboard_text(text, exclude)
+    })
+}
+
+/// Type a text item into the window the panel was summoned from, as keystrokes
+/// For targets that ignore paste (remote desktops, VMs). Returns once typing
+/// has started; `typing-progress` events follow until it is done or cancelled
+#[tauri::command]
+pub fn type_item(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_id: String,
+    chars_per_second: Option<u32>,
+) -> Result<(), ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+    let keys = type_text::keystrokes(type_text::typeable_text(&item)?);
+    if !type_text::has_permission(true) {
+        return Err(ClipsterError::PermissionDenied(
+            "Typing needs the Accessibility permission".to_string(),
+        ));
+    }
+    if !type_text::begin() {
+        return Err(ClipsterError::Validation(
+            "Another item is being typed".to_string(),
+        ));
+    }
+
+    if let Some(window) = app.get_webview_window("main") {
+        hide_panel(&window);
+    }
+    if let Some(target) = state.paste_target.lock()?.clone() {
+        paste_target::activate(&target);
+    }
+
+    let interval = type_text::keystroke_interval(chars_per_second);
+    let step = type_text::progress_step(interval);
+    let total = keys.len();
+    std::thread::spawn(move || {
+        let emit = |typed, status| {
+            let progress = TypingProgress {
+                item_id: item_id.clone(),
+                typed,
+                total,
+                status,
+            };
+            events::emit(&app, &progress);
+        };
+
+        // Let the target window take focus before the first keystroke
+        std::thread::sleep(type_text::FOCUS_DELAY);
+        let mut typed = 0;
+        let status = type_text::run(
+            &keys,
+            interval,
+            type_text::cancel_flag(),
+            type_text::send,
+            std::thread::sleep,
+            |count| {
+                typed = count;
+                if count % step == 0 && count < total {
+                    emit(count, TypingStatus::Typing);
+                }
+            },
+        );
+        type_text::finish();
+        emit(typed, status);
+    });
+    Ok(())
+}
+
+/// Stop `type_item` between two keystrokes; false if nothing is being typed
+#[tauri::command]
+pub fn cancel_typing() -> bool {
+    type_text::cancel()
+}
+
+/// Open a copied file with its default app, or a copied folder in the file manager
+#[tauri::command]
+pub fn open_path(path: String) -> Result<(), ClipsterError> {
+    show_in_file_manager(Path::new(&path), false)
+}
+
+/// Show a copied file or folder selected in its parent folder
+#[tauri::command]
+pub fn reveal_path(path: String) -> Result<(), ClipsterError> {
+    show_in_file_manager(Path::new(&path), true)
+}
+
+/// Run the platform's open or reveal for `path`
+/// Folder paths from the clipboard can end with a separator, which makes
+/// Explorer's /select open the folder instead of selecting it, so it is dropped
+fn show_in_file_manager(path: &Path, reveal: bool) -> Result<(), ClipsterError> {
+    let path: std::path::PathBuf = path.components().collect();
+    if !path.exists() {
+        return Err(ClipsterError::NotFound(format!(
+            "File not found: {}",
+            path.display()
+        )));
+    }
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("open");
+        if reveal {
+            command.arg("-R");
         }
-        crate::models::ContentType::Files
-        | crate::models::ContentType::Audio
-        | crate::models::ContentType::Documents => {
-            if let Some(content_text) = &item.content_text {
-                let file_paths: Vec<String> = serde_json::from_str(content_text)
-                    .map_err(|e| format!("Failed to parse file paths: {}", e))?;
-                clipboard_reader::set_clipboard_files(&file_paths)?;
-            } else {
-                return Err("No file paths for item".to_string());
-            }
+        command.arg(&path);
+        command
+    };
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("explorer");
+        if reveal {
+            command.arg(format!("/select,{}", path.display()));
+        } else {
+            command.arg(&path);
         }
-    }
+        command
+    };
+
+    // No selection support: reveal opens the parent folder
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        let target = match path.parent() {
+            Some(parent) if reveal => parent,
+            _ => path.as_path(),
+        };
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(target);
+        command
+    };
 
+    command.spawn().context("Failed to open file manager")?;
     Ok(())
 }
 
@@ -113,23 +595,82 @@ pub fn copy_to_clipboard(
 pub fn toggle_favorite(
     state: State<'_, AppState>,
     id: String,
-) -> Result<bool, String> {
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &id)?;
     state.db.toggle_item_favorite(&id)
 }
 
+/// Delete an item automatically at `expires_at` ("keep for 10 min"), or keep
+/// it again with None; the maintenance thread emits `item-deleted` once done
+/// Returns false when the item no longer exists
+#[tauri::command]
+pub fn set_item_expiry(
+    state: State<'_, AppState>,
+    item_id: String,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
+    state.db.set_item_expiry(&item_id, expires_at)
+}
+
+/// Apply one operation to several items at once (multi-select), atomically
+/// Returns a result per item; unknown IDs and items of locked pinboards fail
+/// without stopping the others
+/// Emits a single `items-updated` event with the changed and deleted items
+#[tauri::command]
+pub fn bulk_update_items(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_ids: Vec<String>,
+    op: BulkOp,
+) -> Result<Vec<BulkItemResult>, ClipsterError> {
+    let mut locked = HashMap::new();
+    for id in &item_ids {
+        if let Err(e) = ensure_item_unlocked(&state, id) {
+            locked.insert(id.as_str(), e.to_string());
+        }
+    }
+    let unlocked_ids: Vec<String> = item_ids
+        .iter()
+        .filter(|id| !locked.contains_key(id.as_str()))
+        .cloned()
+        .collect();
+    // One result per ID, in order
+    let mut applied = state.db.bulk_update_items(&unlocked_ids, &op)?.into_iter();
+    let results: Vec<BulkItemResult> = item_ids
+        .iter()
+        .filter_map(|id| match locked.get(id.as_str()) {
+            Some(error) => Some(BulkItemResult::failed(id, error)),
+            None => applied.next(),
+        })
+        .collect();
+
+    let mut payload = ItemsUpdatedPayload::default();
+    for result in results.iter().filter(|result| result.ok) {
+        if op == BulkOp::Delete {
+            payload.deleted.push(result.id.clone());
+        } else if let Some(item) = state.db.get_item(&result.id)? {
+            payload.updated.push(item);
+        }
+    }
+    events::emit_items_updated(&app, &payload);
+    Ok(results)
+}
+
 /// Assign an item to a pinboard
 #[tauri::command]
 pub fn assign_to_pinboard(
     state: State<'_, AppState>,
     item_id: String,
     pinboard_id: Option<String>,
-) -> Result<bool, String> {
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
     state.db.update_item_pinboard(&item_id, pinboard_id.as_deref())
 }
 
 /// Get total count of clipboard items
 #[tauri::command]
-pub fn get_clipboard_count(state: State<'_, AppState>) -> Result<usize, String> {
+pub fn get_clipboard_count(state: State<'_, AppState>) -> Result<usize, ClipsterError> {
     state.db.count_items()
 }
 
@@ -138,37 +679,163 @@ pub fn get_clipboard_count(state: State<'_, AppState>) -> Result<usize, String>
 pub fn get_image_data(
     state: State<'_, AppState>,
     id: String,
-) -> Result<String, String> {
-    let item = state
-        .db
-        .get_item(&id)?
-        .ok_or_else(|| "Item not found".to_string())?;
+) -> Result<String, ClipsterError> {
+    let item = require_unlocked_item(&state, &id)?;
 
     // Verify it's an image item
     if item.content_type != crate::models::ContentType::Image {
-        return Err("Item is not an image".to_string());
+        return Err(ClipsterError::Validation("Item is not an image".to_string()));
     }
 
     // Get the image path
     let image_path = item
         .image_path
-        .ok_or_else(|| "Image path not found".to_string())?;
+        .ok_or_else(|| ClipsterError::NotFound("Image path not found".to_string()))?;
 
     // Read the image file
-    let image_bytes = fs::read(&image_path)
-        .map_err(|e| format!("Failed to read image file: {}", e))?;
+    let image_bytes = fs::read(&image_path).context("Failed to read image file")?;
 
     // Encode as base64
     Ok(BASE64.encode(&image_bytes))
 }
 
+/// Get dimensions, color type, file size and EXIF of an image item's stored file
+/// Files that only partly decode return what could be read, see `decode_warning`
+#[tauri::command]
+pub fn get_item_image_info(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<ImageInfo, ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+    if item.content_type != ContentType::Image {
+        return Err(ClipsterError::Validation(
+            "Item is not an image".to_string(),
+        ));
+    }
+
+    let image_path = item
+        .image_path
+        .ok_or_else(|| ClipsterError::NotFound("Image path not found".to_string()))?;
+    image_info::read_image_info(Path::new(&image_path))
+}
+
+/// Let the frontend play an audio item's file
+/// Adds the file to the asset protocol scope and returns its path, to be
+/// loaded through `convertFileSrc`
+#[tauri::command]
+pub fn get_item_audio_stream(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<String, ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+    if item.content_type != ContentType::Audio {
+        return Err(ClipsterError::Validation("Item is not audio".to_string()));
+    }
+
+    let path = item
+        .get_file_paths()
+        .and_then(|paths| paths.into_iter().next())
+        .ok_or_else(|| ClipsterError::NotFound("Audio file not found".to_string()))?;
+    if !Path::new(&path).is_file() {
+        return Err(ClipsterError::NotFound(format!(
+            "Audio file no longer exists: {}",
+            path
+        )));
+    }
+
+    app.asset_protocol_scope()
+        .allow_file(&path)
+        .map_err(|e| ClipsterError::Io(format!("Failed to allow {}: {}", path, e)))?;
+    Ok(path)
+}
+
+/// Render a shareable PNG card of an item (app, time and content), as base64
+/// `width` defaults to 600 pixels; the content is masked with `redact`, and
+/// always for items in a passcode-protected pinboard
+#[tauri::command]
+pub fn render_item_card(
+    state: State<'_, AppState>,
+    item_id: String,
+    width: Option<u32>,
+    redact: Option<bool>,
+) -> Result<String, ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+    let protected = match item.filed_pinboard_id() {
+        Some(pinboard_id) => state
+            .db
+            .get_pinboard(pinboard_id)?
+            .is_some_and(|pinboard| pinboard.is_locked),
+        None => false,
+    };
+
+    let content = CardContent::from_item(&item, redact.unwrap_or(false) || protected);
+    let png = share_card::render_png(&content, width.unwrap_or(DEFAULT_CARD_WIDTH))?;
+    Ok(BASE64.encode(png))
+}
+
+/// Export an image item to `dest_path` as PNG, JPEG or WebP
+/// Optionally downscales to `max_dimension`; returns the written byte size
+#[tauri::command]
+pub fn export_item_image(
+    state: State<'_, AppState>,
+    item_id: String,
+    dest_path: String,
+    format: ExportFormat,
+    max_dimension: Option<u32>,
+) -> Result<u64, ImageExportError> {
+    let item = state
+        .db
+        .get_item(&item_id)
+        .map_err(|e| ImageExportError::Failed(e.to_string()))?
+        .ok_or(ImageExportError::ItemNotFound)?;
+    state
+        .pinboard_locks
+        .require_unlocked(&state.db, &item, chrono::Utc::now())
+        .map_err(|e| ImageExportError::Failed(e.to_string()))?;
+
+    if item.content_type != ContentType::Image {
+        return Err(ImageExportError::NotAnImage);
+    }
+
+    // The stored path is absolute and may be in the fallback directory or
+    // keep the image's original format
+    let image_path = item.image_path.unwrap_or_default();
+    file_storage::export_image(
+        Path::new(&image_path),
+        Path::new(&dest_path),
+        format,
+        max_dimension,
+    )
+}
+
+/// Export the selected items (in the given order) to a ZIP archive at `dest_path`
+/// Items with missing assets or in a locked pinboard are reported in the
+/// summary and the manifest
+#[tauri::command]
+pub fn export_items_zip(
+    state: State<'_, AppState>,
+    item_ids: Vec<String>,
+    dest_path: String,
+) -> Result<ZipExportSummary, ClipsterError> {
+    let storage = state.file_storage.lock()?.clone();
+    zip_export::export_items_zip(
+        &state.db,
+        &storage,
+        &state.pinboard_locks,
+        &item_ids,
+        Path::new(&dest_path),
+    )
+    .map_err(ClipsterError::Io)
+}
+
 /// Prepare an image file for drag by copying it to temp with a readable filename
 /// Returns (image_path, icon_path) - both paths for the drag operation
 #[tauri::command]
 pub fn prepare_image_for_drag(
     source_path: String,
     readable_filename: String,
-) -> Result<(String, String), String> {
+) -> Result<(String, String), ClipsterError> {
     eprintln!("═══════════════════════════════════════════════════════════");
     eprintln!("[DEBUG prepare_image_for_drag] CALLED");
     eprintln!("[DEBUG]   source_path: {}", source_path);
@@ -178,7 +845,10 @@ pub fn prepare_image_for_drag(
     let source = Path::new(&source_path);
     if !source.exists() {
         eprintln!("[DEBUG]   ERROR: Source file not found!");
-        return Err(format!("Source file not found: {}", source_path));
+        return Err(ClipsterError::NotFound(format!(
+            "Source file not found: {}",
+            source_path
+        )));
     }
 
     // Get source file size
@@ -192,8 +862,7 @@ pub fn prepare_image_for_drag(
 
     // Copy file to temp location with readable name
     eprintln!("[DEBUG]   Copying file...");
-    let bytes_copied = fs::copy(source, &temp_path)
-        .map_err(|e| format!("Failed to copy file to temp: {}", e))?;
+    let bytes_copied = fs::copy(source, &temp_path).context("Failed to copy file to temp")?;
     eprintln!("[DEBUG]   Copied {} bytes to temp", bytes_copied);
 
     // Verify the copy
@@ -216,7 +885,7 @@ pub fn prepare_image_for_drag(
     let temp_path_str = temp_path
         .to_str()
         .map(|s| s.to_string())
-        .ok_or_else(|| "Failed to convert path to string".to_string())?;
+        .ok_or_else(|| ClipsterError::Validation("Failed to convert path to string".to_string()))?;
 
     // Create a small thumbnail for drag icon (separate from actual file)
     let icon_filename = format!("icon_{}", readable_filename);
@@ -247,16 +916,43 @@ pub fn prepare_image_for_drag(
     Ok((temp_path_str, icon_path_str))
 }
 
+/// Abort the hold-hotkey history cycle without committing (e.g. Escape in the overlay)
+/// Returns whether a cycle was in progress
+#[tauri::command]
+pub fn cancel_quick_cycle(state: State<'_, AppState>) -> Result<bool, ClipsterError> {
+    let mut cycle = state.quick_cycle.lock()?;
+    Ok(cycle.cancel())
+}
+
+/// Materialize an item as draggable file(s) for drag & drop out of the panel
+/// Returns the file path(s); temp files are cleaned up after an hour
+#[tauri::command]
+pub fn prepare_drag_payload(
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<Vec<String>, ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+
+    let paths = DragPayloads::new()
+        .prepare(&item)
+        .map_err(ClipsterError::Io)?;
+    state.drag_cleanup.lock()?.schedule();
+
+    Ok(paths)
+}
+
 /// Create a temporary text file for drag & drop
 /// Returns the absolute path to the created .txt file
 #[tauri::command]
 pub fn create_temp_text_file(
     content: String,
     filename: String,
-) -> Result<String, String> {
+) -> Result<String, ClipsterError> {
     // Validate filename doesn't contain path traversal
     if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
-        return Err("Invalid filename: path traversal not allowed".to_string());
+        return Err(ClipsterError::Validation(
+            "Invalid filename: path traversal not allowed".to_string(),
+        ));
     }
 
     // Ensure filename has .txt extension
@@ -271,8 +967,7 @@ pub fn create_temp_text_file(
     let temp_path = temp_dir.join(&safe_filename);
 
     // Write content to file
-    fs::write(&temp_path, content)
-        .map_err(|e| format!("Failed to write text file: {}", e))?;
+    fs::write(&temp_path, content).context("Failed to write text file")?;
 
     // On macOS, remove quarantine attribute
     #[cfg(target_os = "macos")]
@@ -285,7 +980,7 @@ pub fn create_temp_text_file(
     temp_path
         .to_str()
         .map(|s| s.to_string())
-        .ok_or_else(|| "Failed to convert path to string".to_string())
+        .ok_or_else(|| ClipsterError::Validation("Failed to convert path to string".to_string()))
 }
 
 /// Create a temporary link file for drag & drop
@@ -294,92 +989,48 @@ pub fn create_temp_text_file(
 pub fn create_temp_link_file(
     url: String,
     filename: String,
-) -> Result<String, String> {
+) -> Result<String, ClipsterError> {
     // Validate filename doesn't contain path traversal
     if filename.contains("..") || filename.contains('/') || filename.contains('\\') {
-        return Err("Invalid filename: path traversal not allowed".to_string());
+        return Err(ClipsterError::Validation(
+            "Invalid filename: path traversal not allowed".to_string(),
+        ));
     }
 
-    // Get system temp directory
-    let temp_dir = std::env::temp_dir();
+    // `.webloc` on macOS, `.url` on Windows, like dragged link items
+    let (extension, content) = drag_payload::link_file_content(&url);
+    let suffix = format!(".{}", extension);
+    let safe_filename = if filename.ends_with(&suffix) {
+        filename
+    } else {
+        format!("{}{}", filename, suffix)
+    };
+    let temp_path = std::env::temp_dir().join(&safe_filename);
+    fs::write(&temp_path, content).context("Failed to write link file")?;
 
+    // On macOS, remove quarantine attribute
     #[cfg(target_os = "macos")]
     {
-        // macOS: Create .webloc file (plist XML format)
-        let safe_filename = if filename.ends_with(".webloc") {
-            filename
-        } else {
-            format!("{}.webloc", filename)
-        };
-        let temp_path = temp_dir.join(&safe_filename);
-
-        // Create plist XML content
-        let plist_content = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-	<key>URL</key>
-	<string>{}</string>
-</dict>
-</plist>"#,
-            url
-        );
-
-        fs::write(&temp_path, plist_content)
-            .map_err(|e| format!("Failed to write webloc file: {}", e))?;
-
-        // Remove quarantine attribute
         let _ = std::process::Command::new("xattr")
             .args(["-d", "com.apple.quarantine", temp_path.to_str().unwrap_or("")])
             .output();
-
-        temp_path
-            .to_str()
-            .map(|s| s.to_string())
-            .ok_or_else(|| "Failed to convert path to string".to_string())
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        // Windows: Create .url file (INI format)
-        let safe_filename = if filename.ends_with(".url") {
-            filename
-        } else {
-            format!("{}.url", filename)
-        };
-        let temp_path = temp_dir.join(&safe_filename);
-
-        // Create INI content
-        let ini_content = format!(
-            "[InternetShortcut]\r\nURL={}\r\n",
-            url
-        );
-
-        fs::write(&temp_path, ini_content)
-            .map_err(|e| format!("Failed to write url file: {}", e))?;
-
-        temp_path
-            .to_str()
-            .map(|s| s.to_string())
-            .ok_or_else(|| "Failed to convert path to string".to_string())
-    }
-
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    {
-        Err("Link file creation not supported on this platform".to_string())
-    }
+    temp_path
+        .to_str()
+        .map(|s| s.to_string())
+        .ok_or_else(|| ClipsterError::Validation("Failed to convert path to string".to_string()))
 }
 
 /// Create a safe fallback icon for drag operations
 /// This avoids Quick Look crashes on problematic files
 #[tauri::command]
-pub fn create_drag_icon(path: String) -> Result<String, String> {
+pub fn create_drag_icon(path: String) -> Result<String, ClipsterError> {
     eprintln!("[create_drag_icon] Creating safe icon for: {}", path);
 
     let source = Path::new(&path);
     if !source.exists() {
-        return Err(format!("File not found: {}", path));
+        return Err(ClipsterError::NotFound(format!("File not found: {}", path)));
     }
 
     let temp_dir = std::env::temp_dir();
@@ -401,7 +1052,7 @@ pub fn create_drag_icon(path: String) -> Result<String, String> {
                 return icon_path
                     .to_str()
                     .map(|s| s.to_string())
-                    .ok_or_else(|| "Path conversion failed".to_string());
+                    .ok_or_else(|| ClipsterError::Validation("Path conversion failed".to_string()));
             }
         }
     }
@@ -436,7 +1087,7 @@ pub fn create_drag_icon(path: String) -> Result<String, String> {
         return icon_path
             .to_str()
             .map(|s| s.to_string())
-            .ok_or_else(|| "Path conversion failed".to_string());
+            .ok_or_else(|| ClipsterError::Validation("Path conversion failed".to_string()));
     }
 
     // Ultimate fallback: just return the original path
@@ -0,0 +1,141 @@
+use crate::auto_clear;
+use crate::clipboard::capture_failures::CaptureFailure;
+use crate::clipboard::clipboard_monitor::{self, MonitoringStatus};
+use crate::error::ClipsterError;
+use crate::events::{self, EventSchema};
+use crate::startup;
+use crate::storage::backup::BackupReport;
+use crate::storage::health::{self, HealthReport};
+use crate::storage::recovery::RecoveryReport;
+use crate::storage::reindex::{self, ReindexComponent, ReindexReport};
+use crate::storage::stats::StorageStats;
+use crate::AppState;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, State};
+
+/// Set by `cancel_reindex` to stop the running reindex
+static REINDEX_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Run the storage health check (safe while capture continues)
+#[tauri::command]
+pub fn run_health_check(state: State<'_, AppState>) -> Result<HealthReport, ClipsterError> {
+    let storage = state.file_storage.lock()?.clone();
+    Ok(health::run_health_check(
+        &state.db,
+        &storage,
+        clipboard_monitor::heartbeat_age_ms(),
+    ))
+}
+
+/// Lifetime usage counters by name: captures (per type too), pastes, items pruned
+#[tauri::command]
+pub fn get_counters(state: State<'_, AppState>) -> Result<BTreeMap<String, u64>, ClipsterError> {
+    state.db.get_counters()
+}
+
+/// Get item counts, lifetime counters and the space saved by text compression
+#[tauri::command]
+pub fn get_stats(state: State<'_, AppState>) -> Result<StorageStats, ClipsterError> {
+    state.db.get_stats()
+}
+
+/// Back up the database to `path` while capture continues
+/// Emits `backup-progress` after each step; succeeds only once the copy passes
+/// an integrity check
+#[tauri::command]
+pub async fn backup_now(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<BackupReport, ClipsterError> {
+    let db = state.db.clone();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        db.backup_to(&PathBuf::from(path), |progress| {
+            events::emit(&app, &progress);
+        })
+    })
+    .await
+    .map_err(|e| ClipsterError::Database(format!("Backup failed: {}", e)))?
+}
+
+/// Rebuild derived item state (`canonical_urls`, `files_summaries`,
+/// `thumbnails`, `app_icons`); all of it when `components` is empty
+/// Runs off the main thread and emits `reindex-progress` after each batch.
+/// Only values that differ are written, so running it again finds nothing
+#[tauri::command]
+pub async fn reindex_all(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    components: Vec<String>,
+) -> Result<ReindexReport, ClipsterError> {
+    let mut components = ReindexComponent::parse_all(&components)?;
+    if components.is_empty() {
+        components = ReindexComponent::ALL.to_vec();
+    }
+
+    let db = state.db.clone();
+    REINDEX_CANCELLED.store(false, Ordering::SeqCst);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        reindex::reindex(&db, &components, &REINDEX_CANCELLED, |progress| {
+            events::emit(&app, &progress);
+        })
+    })
+    .await
+    .map_err(|e| ClipsterError::Database(format!("Reindex failed: {}", e)))?
+}
+
+/// Stop the running reindex after the current batch
+#[tauri::command]
+pub fn cancel_reindex() {
+    REINDEX_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Get the report of the corrupt database replaced at startup, if any
+/// (the `database-recovered` event is sent before the window has loaded)
+#[tauri::command]
+pub fn get_database_recovery(state: State<'_, AppState>) -> Option<RecoveryReport> {
+    state.database_recovery.clone()
+}
+
+/// Get clipboard monitor status, including captures dropped by rate limiting,
+/// failed captures, the next scheduled history clear and startup phase durations
+#[tauri::command]
+pub fn get_monitoring_status(state: State<'_, AppState>) -> MonitoringStatus {
+    MonitoringStatus {
+        next_auto_clear: auto_clear::next_run(&state.db),
+        startup_phases: startup::timings::phases(),
+        ..clipboard_monitor::monitoring_status()
+    }
+}
+
+/// Move images saved to the temporary fallback directory back into the
+/// images directory once it is available again, and save there from now on
+/// Returns the number of images moved
+#[tauri::command]
+pub fn repair_storage(state: State<'_, AppState>) -> Result<usize, ClipsterError> {
+    // The monitor's instance, so its next save goes to the images directory again
+    let storage = state.file_storage.lock()?.clone();
+    let moved = storage.repair()?;
+    // Images saved to the fallback are stored by absolute path
+    state.db.relocate_image_paths()?;
+    clipboard_monitor::storage_repaired();
+    Ok(moved)
+}
+
+/// Get the most recent captures that could not be saved, newest first
+/// (time, stage, error code and content type; never the content)
+#[tauri::command]
+pub fn get_capture_failures() -> Vec<CaptureFailure> {
+    clipboard_monitor::capture_failures()
+}
+
+/// Get the name, payload version and JSON schema of every event sent to the
+/// frontend
+#[tauri::command]
+pub fn get_event_schema() -> Vec<EventSchema> {
+    events::event_schemas()
+}
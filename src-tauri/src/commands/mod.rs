@@ -1,6 +1,8 @@
 //Link between front end calls and back end functions
 
 pub mod clipboard_commands;
+pub mod health_commands;
 pub mod pinboard_commands;
+pub mod profile_commands;
 pub mod settings_commands;
 pub mod window_commands;
@@ -1,11 +1,37 @@
-use crate::models::{ClipboardItem, Pinboard};
+use crate::clipboard::transforms::Transform;
+use crate::commands::clipboard_commands::ensure_item_unlocked;
+use crate::error::ClipsterError;
+use crate::events;
+use crate::models::pinboard::validate_color;
+use crate::models::{ClipboardItem, Pinboard, PinboardRules};
+use crate::pinboard_palette;
+use crate::storage::destructive_guard::{self, DestructiveAction, Guarded};
+use crate::storage::file_attach;
+use crate::storage::image_import::{self, ImportSummary};
+use crate::storage::pinboard_lock::{self, DEFAULT_RELOCK_MINUTES};
 use crate::AppState;
-use tauri::State;
+use chrono::Utc;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, State};
 
-/// Get all pinboards ordered by position
+/// Set by `cancel_image_import` to stop the running folder import
+static IMPORT_CANCELLED: AtomicBool = AtomicBool::new(false);
+
+/// Return an error if the pinboard is passcode-locked and not currently unlocked
+fn ensure_pinboard_unlocked(state: &AppState, pinboard_id: &str) -> Result<(), ClipsterError> {
+    state
+        .pinboard_locks
+        .require_pinboard_unlocked(&state.db, pinboard_id, Utc::now())
+}
+
+/// Get pinboards ordered by position (archived ones only when requested)
 #[tauri::command]
-pub fn get_pinboards(state: State<'_, AppState>) -> Result<Vec<Pinboard>, String> {
-    state.db.get_pinboards()
+pub fn get_pinboards(
+    state: State<'_, AppState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<Pinboard>, ClipsterError> {
+    state.db.get_pinboards(include_archived.unwrap_or(false))
 }
 
 /// Get a single pinboard by ID
@@ -13,62 +39,263 @@ pub fn get_pinboards(state: State<'_, AppState>) -> Result<Vec<Pinboard>, String
 pub fn get_pinboard(
     state: State<'_, AppState>,
     id: String,
-) -> Result<Option<Pinboard>, String> {
+) -> Result<Option<Pinboard>, ClipsterError> {
     state.db.get_pinboard(&id)
 }
 
+/// Reject a malformed color and turn a blank description into none
+fn check_metadata(
+    color: Option<&str>,
+    description: Option<String>,
+) -> Result<Option<String>, ClipsterError> {
+    if let Some(color) = color {
+        validate_color(color).map_err(ClipsterError::Validation)?;
+    }
+    Ok(description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty()))
+}
+
 /// Create a new pinboard
+/// Without a color the pinboard gets a palette color. `default_transform` is
+/// applied when its items are copied back
 #[tauri::command]
 pub fn create_pinboard(
     state: State<'_, AppState>,
     name: String,
     icon: Option<String>,
-) -> Result<Pinboard, String> {
-    // Get current pinboards to determine position
-    let pinboards = state.db.get_pinboards()?;
-    let position = pinboards.len() as i32;
+    color: Option<String>,
+    description: Option<String>,
+    default_transform: Option<Transform>,
+) -> Result<Pinboard, ClipsterError> {
+    let description = check_metadata(color.as_deref(), description)?;
 
-    let pinboard = Pinboard::new(name, icon, position);
+    // New pinboards go at the end of the root level
+    let pinboards = state.db.get_pinboards(true)?;
+    let position = pinboards.iter().filter(|p| p.parent_id.is_none()).count() as i32;
+
+    let mut pinboard = Pinboard::new(name, icon, position);
+    if let Some(color) = color {
+        pinboard.color = color;
+    }
+    pinboard.description = description;
+    pinboard.default_transform = default_transform;
     state.db.insert_pinboard(&pinboard)?;
 
     Ok(pinboard)
 }
 
 /// Update an existing pinboard
+/// A missing color resets the pinboard to its palette color, and a missing
+/// `default_transform` removes it
 #[tauri::command]
 pub fn update_pinboard(
     state: State<'_, AppState>,
     id: String,
     name: String,
     icon: Option<String>,
-) -> Result<bool, String> {
-    state.db.update_pinboard(&id, &name, icon.as_deref())
+    color: Option<String>,
+    description: Option<String>,
+    default_transform: Option<Transform>,
+) -> Result<bool, ClipsterError> {
+    let description = check_metadata(color.as_deref(), description)?;
+    state.db.update_pinboard(
+        &id,
+        &name,
+        icon.as_deref(),
+        color.as_deref(),
+        description.as_deref(),
+        default_transform,
+    )
+}
+
+/// Set (or remove with `None`) the passcode of a pinboard
+/// Changing the passcode of a locked pinboard requires unlocking it first.
+/// The Spotlight index is rebuilt since no item changes are logged for it
+#[tauri::command]
+pub fn set_pinboard_passcode(
+    state: State<'_, AppState>,
+    pinboard_id: String,
+    passcode: Option<String>,
+) -> Result<bool, ClipsterError> {
+    ensure_pinboard_unlocked(&state, &pinboard_id)?;
+
+    let lock_hash = match passcode {
+        Some(passcode) => Some(
+            pinboard_lock::hash_passcode(&passcode).map_err(ClipsterError::Validation)?,
+        ),
+        None => None,
+    };
+
+    state.pinboard_locks.relock(&pinboard_id);
+    let updated = state
+        .db
+        .set_pinboard_lock_hash(&pinboard_id, lock_hash.as_deref())?;
+    state.spotlight.reindex();
+    Ok(updated)
+}
+
+/// Unlock a passcode-protected pinboard for the configured number of minutes
+/// Returns false if the passcode is wrong
+#[tauri::command]
+pub fn unlock_pinboard(
+    state: State<'_, AppState>,
+    id: String,
+    passcode: String,
+) -> Result<bool, ClipsterError> {
+    let pinboard = state
+        .db
+        .get_pinboard(&id)?
+        .ok_or_else(|| ClipsterError::NotFound("Pinboard not found".to_string()))?;
+
+    let Some(lock_hash) = pinboard.lock_hash else {
+        return Ok(true);
+    };
+
+    if !pinboard_lock::verify_passcode(&passcode, &lock_hash) {
+        return Ok(false);
+    }
+
+    let minutes = state
+        .db
+        .get_setting("pinboard_relock_minutes")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RELOCK_MINUTES);
+    state.pinboard_locks.unlock(&id, minutes, Utc::now());
+
+    Ok(true)
+}
+
+/// Relock a pinboard immediately
+#[tauri::command]
+pub fn lock_pinboard(state: State<'_, AppState>, id: String) -> Result<(), ClipsterError> {
+    state.pinboard_locks.relock(&id);
+    Ok(())
+}
+
+/// Archive a pinboard (hidden from the sidebar, items kept)
+#[tauri::command]
+pub fn archive_pinboard(state: State<'_, AppState>, id: String) -> Result<bool, ClipsterError> {
+    state.db.set_pinboard_archived(&id, true)
+}
+
+/// Restore an archived pinboard
+#[tauri::command]
+pub fn unarchive_pinboard(state: State<'_, AppState>, id: String) -> Result<bool, ClipsterError> {
+    state.db.set_pinboard_archived(&id, false)
 }
 
 /// Delete a pinboard
+/// Nested pinboards are deleted too with `delete_children`, otherwise moved to the root
+/// Needs a `confirm_token` like `clear_clipboard_history`
+#[tauri::command]
+pub fn delete_pinboard(
+    state: State<'_, AppState>,
+    id: String,
+    delete_children: Option<bool>,
+    confirm_token: Option<String>,
+) -> Result<Guarded<bool>, ClipsterError> {
+    let delete_children = delete_children.unwrap_or(false);
+    let action = DestructiveAction::DeletePinboard {
+        id: id.clone(),
+        delete_children,
+    };
+    state.destructive_guard.run(
+        destructive_guard::confirmation_required(&state.db),
+        action,
+        confirm_token.as_deref(),
+        Utc::now(),
+        || state.db.delete_pinboard_summary(&id, delete_children),
+        || state.db.delete_pinboard(&id, delete_children),
+    )
+}
+
+/// Nest a pinboard under another (or move it back to the root with `None`)
+/// Fails on cycles and nesting deeper than two levels
+#[tauri::command]
+pub fn set_pinboard_parent(
+    state: State<'_, AppState>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<(), ClipsterError> {
+    state.db.set_pinboard_parent(&id, parent_id.as_deref())
+}
+
+/// Validate smart pinboard rules without saving them
 #[tauri::command]
-pub fn delete_pinboard(state: State<'_, AppState>, id: String) -> Result<bool, String> {
-    state.db.delete_pinboard(&id)
+pub fn validate_pinboard_rules(rules: PinboardRules) -> Result<(), ClipsterError> {
+    rules.validate().map_err(ClipsterError::Validation)
 }
 
-/// Reorder pinboards by providing list of IDs in desired order
+/// Set (or clear with `None`) the smart rules of a pinboard
+#[tauri::command]
+pub fn set_pinboard_rules(
+    state: State<'_, AppState>,
+    pinboard_id: String,
+    rules: Option<PinboardRules>,
+) -> Result<bool, ClipsterError> {
+    if let Some(rules) = &rules {
+        rules.validate().map_err(ClipsterError::Validation)?;
+    }
+    state.db.set_pinboard_rules(&pinboard_id, rules.as_ref())
+}
+
+/// Reorder the pinboards of one level (`parent_id`, or the root when None)
+/// by providing list of IDs in desired order
 #[tauri::command]
 pub fn reorder_pinboards(
     state: State<'_, AppState>,
+    parent_id: Option<String>,
     pinboard_ids: Vec<String>,
-) -> Result<(), String> {
-    state.db.reorder_pinboards(&pinboard_ids)
+) -> Result<(), ClipsterError> {
+    state
+        .db
+        .reorder_pinboards(parent_id.as_deref(), &pinboard_ids)
 }
 
-/// Get items in a specific pinboard
+/// Get items in a specific pinboard with pagination
+/// Thumbnails are omitted unless `include_thumbnails` is set; use `get_thumbnails`
 #[tauri::command]
 pub fn get_pinboard_items(
     state: State<'_, AppState>,
     pinboard_id: String,
     limit: Option<usize>,
-) -> Result<Vec<ClipboardItem>, String> {
+    offset: Option<usize>,
+    include_thumbnails: Option<bool>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
+    ensure_pinboard_unlocked(&state, &pinboard_id)?;
     let limit = limit.unwrap_or(100);
-    state.db.get_pinboard_items(&pinboard_id, limit)
+    let offset = offset.unwrap_or(0);
+    state.db.get_pinboard_items(
+        &pinboard_id,
+        limit,
+        offset,
+        include_thumbnails.unwrap_or(false),
+    )
+}
+
+/// Get the items copied back most often, in one pinboard or across history
+#[tauri::command]
+pub fn get_top_used(
+    state: State<'_, AppState>,
+    pinboard_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ClipboardItem>, ClipsterError> {
+    if let Some(pinboard_id) = &pinboard_id {
+        ensure_pinboard_unlocked(&state, pinboard_id)?;
+    }
+    state
+        .db
+        .get_top_used_items(pinboard_id.as_deref(), limit.unwrap_or(10))
+}
+
+/// Get the number of items in a specific pinboard
+#[tauri::command]
+pub fn count_pinboard_items(
+    state: State<'_, AppState>,
+    pinboard_id: String,
+) -> Result<usize, ClipsterError> {
+    state.db.count_pinboard_items(&pinboard_id)
 }
 
 /// Add an item to a pinboard
@@ -77,7 +304,8 @@ pub fn add_item_to_pinboard(
     state: State<'_, AppState>,
     item_id: String,
     pinboard_id: String,
-) -> Result<bool, String> {
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
     state.db.update_item_pinboard(&item_id, Some(&pinboard_id))
 }
 
@@ -86,6 +314,107 @@ pub fn add_item_to_pinboard(
 pub fn remove_item_from_pinboard(
     state: State<'_, AppState>,
     item_id: String,
-) -> Result<bool, String> {
+) -> Result<bool, ClipsterError> {
+    ensure_item_unlocked(&state, &item_id)?;
     state.db.update_item_pinboard(&item_id, None)
 }
+
+/// Import the images in a folder into a pinboard
+/// Runs off the main thread and emits `image-import-progress` after each file
+#[tauri::command]
+pub async fn import_images_to_pinboard(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    dir_path: String,
+    pinboard_id: String,
+    recursive: bool,
+) -> Result<ImportSummary, ClipsterError> {
+    if state.db.get_pinboard(&pinboard_id)?.is_none() {
+        return Err(ClipsterError::NotFound(format!(
+            "Pinboard not found: {}",
+            pinboard_id
+        )));
+    }
+    ensure_pinboard_unlocked(&state, &pinboard_id)?;
+
+    let db = state.db.clone();
+    let storage = state.file_storage.lock()?.clone();
+    IMPORT_CANCELLED.store(false, Ordering::SeqCst);
+
+    tauri::async_runtime::spawn_blocking(move || -> Result<ImportSummary, ClipsterError> {
+        let files = image_import::collect_files(Path::new(&dir_path), recursive)?;
+
+        Ok(image_import::import_images(
+            &db,
+            &storage,
+            &files,
+            &pinboard_id,
+            &IMPORT_CANCELLED,
+            |progress| {
+                events::emit(&app, &progress);
+            },
+        ))
+    })
+    .await
+    .map_err(|e| ClipsterError::Io(format!("Image import failed: {}", e)))?
+}
+
+/// Add a file from disk to a pinboard without touching the clipboard
+/// Images are imported with a thumbnail; with `keep_mtime` the item is dated
+/// with the file's modification time. Returns the new item
+#[tauri::command]
+pub async fn add_file_to_pinboard(
+    state: State<'_, AppState>,
+    path: String,
+    pinboard_id: String,
+    keep_mtime: Option<bool>,
+) -> Result<ClipboardItem, ClipsterError> {
+    if state.db.get_pinboard(&pinboard_id)?.is_none() {
+        return Err(ClipsterError::NotFound(format!(
+            "Pinboard not found: {}",
+            pinboard_id
+        )));
+    }
+    ensure_pinboard_unlocked(&state, &pinboard_id)?;
+
+    let db = state.db.clone();
+    let storage = state.file_storage.lock()?.clone();
+    tauri::async_runtime::spawn_blocking(move || -> Result<ClipboardItem, ClipsterError> {
+        file_attach::add_file_to_pinboard(
+            &db,
+            &storage,
+            Path::new(&path),
+            &pinboard_id,
+            keep_mtime.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| ClipsterError::Io(format!("Adding the file failed: {}", e)))?
+}
+
+/// Stop the running folder import after the current file
+#[tauri::command]
+pub fn cancel_image_import() {
+    IMPORT_CANCELLED.store(true, Ordering::SeqCst);
+}
+
+/// Open the compact palette of a pinboard (also bound to its palette shortcut)
+#[tauri::command]
+pub fn show_pinboard_palette(app: AppHandle, pinboard_id: String) -> Result<(), ClipsterError> {
+    pinboard_palette::show(&app, &pinboard_id)
+}
+
+/// Copy the palette item under number key `index` (0-based) back to the
+/// clipboard and close the palette
+/// Returns false if the palette has no item at `index`
+#[tauri::command]
+pub fn palette_select(app: AppHandle, index: usize) -> Result<bool, ClipsterError> {
+    pinboard_palette::select(&app, index)
+}
+
+/// Close the pinboard palette
+#[tauri::command]
+pub fn hide_pinboard_palette(app: AppHandle) -> Result<(), ClipsterError> {
+    pinboard_palette::hide(&app);
+    Ok(())
+}
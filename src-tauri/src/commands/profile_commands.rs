@@ -0,0 +1,104 @@
+use crate::clipboard::clipboard_monitor;
+use crate::error::ClipsterError;
+use crate::events;
+use crate::logging;
+use crate::storage::profiles::{self, Profile, ProfileRegistry};
+use crate::storage::FileStorage;
+use crate::AppState;
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, State};
+
+/// Serializes profile switches so two switches never interleave
+static SWITCH_LOCK: Mutex<()> = Mutex::new(());
+
+/// List all profiles, marking the active one
+#[tauri::command]
+pub fn list_profiles() -> Result<Vec<Profile>, ClipsterError> {
+    let root = profiles::clipster_root().map_err(ClipsterError::Io)?;
+    Ok(ProfileRegistry::load(&root)
+        .map_err(ClipsterError::Io)?
+        .list())
+}
+
+/// Create a new, empty profile (does not switch to it)
+#[tauri::command]
+pub fn create_profile(name: String) -> Result<Profile, ClipsterError> {
+    let _guard = SWITCH_LOCK.lock()?;
+
+    let root = profiles::clipster_root().map_err(ClipsterError::Io)?;
+    let mut registry = ProfileRegistry::load(&root).map_err(ClipsterError::Io)?;
+    let profile = registry
+        .create(&root, &name)
+        .map_err(ClipsterError::Validation)?;
+    registry.save(&root).map_err(ClipsterError::Io)?;
+    Ok(profile)
+}
+
+/// Switch to another profile's history
+/// The monitor is stopped first, which waits for an in-flight capture to be
+/// saved to the old profile, then the database is swapped and monitoring
+/// restarts on the new one. Emits `profile-changed`.
+#[tauri::command]
+pub fn switch_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+) -> Result<Profile, ClipsterError> {
+    let _guard = SWITCH_LOCK.lock()?;
+
+    let root = profiles::clipster_root().map_err(ClipsterError::Io)?;
+    let mut registry = ProfileRegistry::load(&root).map_err(ClipsterError::Io)?;
+    let data_dir = registry
+        .data_dir(&name)
+        .cloned()
+        .ok_or_else(|| ClipsterError::NotFound(format!("Profile '{}' not found", name)))?;
+
+    let profile = Profile {
+        name: name.clone(),
+        data_dir: data_dir.to_string_lossy().to_string(),
+        is_active: true,
+    };
+    if registry.active == name {
+        return Ok(profile);
+    }
+
+    let was_monitoring = clipboard_monitor::is_monitoring();
+    clipboard_monitor::stop_monitoring();
+
+    let switched = state
+        .db
+        .switch_to(&data_dir)
+        .and_then(|_| profiles::set_active_data_dir(data_dir.clone()).map_err(ClipsterError::Io))
+        .and_then(|_| {
+            let file_storage = Arc::new(FileStorage::new()?);
+            file_storage.apply_settings(&state.db);
+            *state.file_storage.lock()? = file_storage;
+            Ok(())
+        });
+
+    // Resume capture even if the switch failed, on whichever profile is current
+    if was_monitoring {
+        let restarted = state.file_storage.lock().map(|file_storage| {
+            clipboard_monitor::start_monitoring(app.clone(), state.db.clone(), file_storage.clone())
+        });
+        if let Ok(Err(e)) = restarted {
+            eprintln!("Failed to restart clipboard monitor: {}", e);
+        }
+    }
+    switched?;
+
+    registry.active = name;
+    registry.save(&root).map_err(ClipsterError::Io)?;
+
+    // Session state refers to items and pinboards of the old profile
+    state.pinboard_locks.relock_all();
+    if let Ok(mut cycle) = state.quick_cycle.lock() {
+        cycle.cancel();
+    }
+    state.spotlight.reindex();
+    logging::apply_setting(&state.db);
+
+    events::emit(&app, &profile);
+
+    Ok(profile)
+}
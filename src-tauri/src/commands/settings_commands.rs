@@ -1,5 +1,19 @@
+use crate::auto_clear::AutoClearSchedule;
+use crate::clipboard::capture_merge::DEFAULT_MERGE_WINDOW_MS;
+use crate::clipboard::clipboard_monitor;
+use crate::clipboard::payload_limit::DEFAULT_MAX_IMAGE_CAPTURE_MB;
+use crate::clipboard::storage_guard::DEFAULT_MIN_FREE_SPACE_MB;
+use crate::error::ClipsterError;
+use crate::logging;
+use crate::permissions::{self, PermissionKind, PermissionStatus, RequestOutcome};
+use crate::startup::{self, InitialState};
+use crate::storage::file_storage::{DEFAULT_LARGE_TEXT_THRESHOLD, DEFAULT_QUICKLOOK_TIMEOUT_MS};
+use crate::storage::{destructive_guard, settings_snapshot};
 use crate::AppState;
+use crate::{pinboard_palette, quick_cycle, spotlight};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
 use tauri::{AppHandle, State};
 
 /// Settings structure returned to frontend
@@ -10,6 +24,57 @@ pub struct AppSettings {
     pub start_hidden: bool,
     pub theme: String,
     pub show_menu_bar_icon: bool,
+    pub mirror_favorites_to_pinboard: bool,
+    pub strip_image_metadata: bool,
+    /// Save images with slower, stronger PNG compression (about half the size)
+    pub compact_png_files: bool,
+    pub quick_cycle_shortcut: String,
+    /// Text normalization on capture: "off", "line_endings" or "full"
+    pub normalize_text: String,
+    /// Text larger than this many bytes is stored on disk with a preview in the database
+    pub large_text_threshold_bytes: usize,
+    /// Keep large text in the database zstd-compressed instead of on disk
+    pub compress_large_text: bool,
+    /// Index text and link items into macOS Spotlight
+    pub spotlight_indexing: bool,
+    /// Automatic history clearing: "off", "daily_at(HH:MM)" or "on_quit"
+    pub auto_clear_schedule: String,
+    /// Below this much free disk space (MB), images are not saved
+    pub min_free_space_mb: u64,
+    /// Store the focused window's title with each capture
+    pub capture_window_titles: bool,
+    /// Encoding of image thumbnails: "png", "jpeg" or "webp"
+    pub thumbnail_format: String,
+    /// Time a single Quick Look thumbnail run may take (ms); PDF/PPTX/MOV retry once at double
+    pub quicklook_timeout_ms: u64,
+    /// List the five most recent text items in the Windows jump list / macOS Dock menu
+    pub recent_items_os_menu: bool,
+    /// Also capture text from the macOS find pasteboard (Cmd+F fields)
+    pub capture_find_pasteboard: bool,
+    /// Clipboard capture is paused; set through set_capture_paused
+    pub capture_paused: bool,
+    /// Keep every copied-back item out of the Windows clipboard history and
+    /// cloud sync (items from locked pinboards always are)
+    pub exclude_from_os_history: bool,
+    /// Which earlier copies a new copy replaces: "global", "per_app" or "off"
+    pub dedupe_scope: String,
+    /// Captured images are downscaled to fit within this many pixels per side
+    /// (0 keeps the original size)
+    pub max_image_dimension: u32,
+    /// Images larger than this many MB on the clipboard are not captured
+    /// (0 = no limit)
+    pub max_image_capture_mb: u64,
+    /// Write per-capture tracing to the log files (previews only, never full content)
+    pub debug_capture_logging: bool,
+    /// Clearing history and deleting pinboards need a confirmation token
+    pub require_destructive_confirmation: bool,
+    /// Join text copied from the same app in quick succession into one item
+    pub merge_rapid_text_captures: bool,
+    /// Longest gap between two copies that are joined (ms)
+    pub merge_window_ms: u64,
+    /// Back up the database into the profile's `backups` directory this
+    /// often (0 = never)
+    pub backup_interval_hours: u64,
 }
 
 impl Default for AppSettings {
@@ -20,13 +85,38 @@ impl Default for AppSettings {
             start_hidden: false,
             theme: "dark".to_string(),
             show_menu_bar_icon: true,
+            mirror_favorites_to_pinboard: false,
+            strip_image_metadata: false,
+            compact_png_files: false,
+            quick_cycle_shortcut: "Ctrl+Alt+V".to_string(),
+            normalize_text: "off".to_string(),
+            large_text_threshold_bytes: DEFAULT_LARGE_TEXT_THRESHOLD,
+            compress_large_text: false,
+            spotlight_indexing: false,
+            auto_clear_schedule: "off".to_string(),
+            min_free_space_mb: DEFAULT_MIN_FREE_SPACE_MB,
+            capture_window_titles: false,
+            thumbnail_format: "png".to_string(),
+            quicklook_timeout_ms: DEFAULT_QUICKLOOK_TIMEOUT_MS,
+            recent_items_os_menu: true,
+            capture_find_pasteboard: false,
+            capture_paused: false,
+            exclude_from_os_history: false,
+            dedupe_scope: "global".to_string(),
+            max_image_dimension: 0,
+            max_image_capture_mb: DEFAULT_MAX_IMAGE_CAPTURE_MB,
+            debug_capture_logging: false,
+            require_destructive_confirmation: true,
+            merge_rapid_text_captures: false,
+            merge_window_ms: DEFAULT_MERGE_WINDOW_MS,
+            backup_interval_hours: 0,
         }
     }
 }
 
 /// Get all settings
 #[tauri::command]
-pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
+pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, ClipsterError> {
     let shortcut = state
         .db
         .get_setting("shortcut")?
@@ -55,12 +145,175 @@ pub fn get_settings(state: State<'_, AppState>) -> Result<AppSettings, String> {
         .unwrap_or_else(|| "true".to_string());
     let show_menu_bar_icon = show_menu_bar_icon_str == "true";
 
+    let mirror_favorites_to_pinboard = state
+        .db
+        .get_setting("mirror_favorites_to_pinboard")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let strip_image_metadata = state
+        .db
+        .get_setting("strip_image_metadata")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let compact_png_files = state
+        .db
+        .get_setting("compact_png_files")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let quick_cycle_shortcut = state
+        .db
+        .get_setting("quick_cycle_shortcut")?
+        .unwrap_or_else(|| "Ctrl+Alt+V".to_string());
+
+    let normalize_text = state
+        .db
+        .get_setting("normalize_text")?
+        .unwrap_or_else(|| "off".to_string());
+
+    let large_text_threshold_bytes = state
+        .db
+        .get_setting("large_text_threshold_bytes")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_LARGE_TEXT_THRESHOLD);
+
+    let compress_large_text = state
+        .db
+        .get_setting("compress_large_text")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let spotlight_indexing = state
+        .db
+        .get_setting("spotlight_indexing")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let auto_clear_schedule = state.db.get_auto_clear_schedule()?.to_string();
+
+    let min_free_space_mb = state
+        .db
+        .get_setting("min_free_space_mb")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MIN_FREE_SPACE_MB);
+
+    let capture_window_titles = state
+        .db
+        .get_setting("capture_window_titles")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let thumbnail_format = state
+        .db
+        .get_setting("thumbnail_format")?
+        .unwrap_or_else(|| "png".to_string());
+
+    let quicklook_timeout_ms = state
+        .db
+        .get_setting("quicklook_timeout_ms")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_QUICKLOOK_TIMEOUT_MS);
+
+    let recent_items_os_menu = state
+        .db
+        .get_setting("recent_items_os_menu")?
+        .map(|v| v == "true")
+        .unwrap_or(true);
+
+    let capture_find_pasteboard = state
+        .db
+        .get_setting("capture_find_pasteboard")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let capture_paused = state
+        .db
+        .get_setting("capture_paused")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let exclude_from_os_history = state
+        .db
+        .get_setting("exclude_from_os_history")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let dedupe_scope = state
+        .db
+        .get_setting("dedupe_scope")?
+        .unwrap_or_else(|| "global".to_string());
+
+    let max_image_dimension = state
+        .db
+        .get_setting("max_image_dimension")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    let max_image_capture_mb = state
+        .db
+        .get_setting("max_image_capture_mb")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MAX_IMAGE_CAPTURE_MB);
+
+    let debug_capture_logging = state
+        .db
+        .get_setting("debug_capture_logging")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let require_destructive_confirmation = destructive_guard::confirmation_required(&state.db);
+
+    let merge_rapid_text_captures = state
+        .db
+        .get_setting("merge_rapid_text_captures")?
+        .map(|v| v == "true")
+        .unwrap_or(false);
+
+    let merge_window_ms = state
+        .db
+        .get_setting("merge_window_ms")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_MERGE_WINDOW_MS);
+
+    let backup_interval_hours = state
+        .db
+        .get_setting("backup_interval_hours")?
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
     Ok(AppSettings {
         shortcut,
         history_limit,
         start_hidden,
         theme,
         show_menu_bar_icon,
+        mirror_favorites_to_pinboard,
+        strip_image_metadata,
+        compact_png_files,
+        quick_cycle_shortcut,
+        normalize_text,
+        large_text_threshold_bytes,
+        compress_large_text,
+        spotlight_indexing,
+        auto_clear_schedule,
+        min_free_space_mb,
+        capture_window_titles,
+        thumbnail_format,
+        quicklook_timeout_ms,
+        recent_items_os_menu,
+        capture_find_pasteboard,
+        capture_paused,
+        exclude_from_os_history,
+        dedupe_scope,
+        max_image_dimension,
+        max_image_capture_mb,
+        debug_capture_logging,
+        require_destructive_confirmation,
+        merge_rapid_text_captures,
+        merge_window_ms,
+        backup_interval_hours,
     })
 }
 
@@ -70,40 +323,295 @@ pub fn update_setting(
     state: State<'_, AppState>,
     key: String,
     value: String,
-) -> Result<(), String> {
-    state.db.set_setting(&key, &value)
+) -> Result<(), ClipsterError> {
+    state.db.set_setting(&key, &value)?;
+    match key.as_str() {
+        "debug_capture_logging" => logging::apply_setting(&state.db),
+        "strip_image_metadata" | "compact_png_files" => {
+            state.file_storage.lock()?.apply_settings(&state.db)
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Export every setting to a JSON file at `path`; returns the number of settings
+#[tauri::command]
+pub fn export_settings(state: State<'_, AppState>, path: String) -> Result<usize, ClipsterError> {
+    settings_snapshot::export_settings(&state.db, Path::new(&path))
+}
+
+/// Import a settings file written by `export_settings`
+/// Nothing changes when the file has an unknown version or an invalid value
+#[tauri::command]
+pub fn import_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<AppSettings, ClipsterError> {
+    settings_snapshot::import_settings(&state.db, Path::new(&path))?;
+    apply_restored_settings(&app, &state)
+}
+
+/// Write the default value of every known setting back
+#[tauri::command]
+pub fn reset_settings_to_defaults(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AppSettings, ClipsterError> {
+    state.db.reset_settings_to_defaults()?;
+    apply_restored_settings(&app, &state)
+}
+
+/// Apply settings that act outside the database after an import or reset
+fn apply_restored_settings(
+    app: &AppHandle,
+    state: &State<'_, AppState>,
+) -> Result<AppSettings, ClipsterError> {
+    let settings = get_settings(state.clone())?;
+    state.db.prune_oldest(settings.history_limit as usize)?;
+    apply_menu_bar_icon_visible(app, settings.show_menu_bar_icon)?;
+    logging::apply_setting(&state.db);
+    state.file_storage.lock()?.apply_settings(&state.db);
+    Ok(settings)
 }
 
 /// Get history limit setting
 #[tauri::command]
-pub fn get_history_limit(state: State<'_, AppState>) -> Result<u32, String> {
+pub fn get_history_limit(state: State<'_, AppState>) -> Result<u32, ClipsterError> {
     state.db.get_history_limit().map(|v| v as u32)
 }
 
 /// Set history limit and prune if necessary
 #[tauri::command]
-pub fn set_history_limit(state: State<'_, AppState>, limit: u32) -> Result<(), String> {
+pub fn set_history_limit(state: State<'_, AppState>, limit: u32) -> Result<(), ClipsterError> {
     state.db.set_setting("history_limit", &limit.to_string())?;
     // Prune old items if over limit
     state.db.prune_oldest(limit as usize)?;
     Ok(())
 }
 
+/// Get the per-app default pinboard mapping (bundle identifier or executable -> pinboard ID)
+#[tauri::command]
+pub fn get_app_default_pinboards(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, ClipsterError> {
+    state.db.get_app_default_pinboards()
+}
+
+/// Set the per-app default pinboard mapping
+#[tauri::command]
+pub fn set_app_default_pinboards(
+    state: State<'_, AppState>,
+    mapping: HashMap<String, String>,
+) -> Result<(), ClipsterError> {
+    for pinboard_id in mapping.values() {
+        if state.db.get_pinboard(pinboard_id)?.is_none() {
+            return Err(ClipsterError::NotFound(format!(
+                "Pinboard not found: {}",
+                pinboard_id
+            )));
+        }
+    }
+    state.db.set_app_default_pinboards(&mapping)
+}
+
+/// Get the pinboard palette shortcuts (pinboard ID -> shortcut)
+#[tauri::command]
+pub fn get_pinboard_shortcuts(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, ClipsterError> {
+    state.db.get_pinboard_shortcuts()
+}
+
+/// Set the pinboard palette shortcuts and register them in place of the old ones
+#[tauri::command]
+pub fn set_pinboard_shortcuts(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    shortcuts: HashMap<String, String>,
+) -> Result<(), ClipsterError> {
+    for pinboard_id in shortcuts.keys() {
+        if state.db.get_pinboard(pinboard_id)?.is_none() {
+            return Err(ClipsterError::NotFound(format!(
+                "Pinboard not found: {}",
+                pinboard_id
+            )));
+        }
+    }
+    let reserved = [
+        crate::panel_shortcut(),
+        quick_cycle::configured_shortcut(&state.db),
+    ];
+    let parsed = pinboard_palette::parse_shortcuts(&shortcuts, &reserved)?;
+
+    state.db.set_pinboard_shortcuts(&shortcuts)?;
+    pinboard_palette::register_shortcuts(&app, parsed);
+    Ok(())
+}
+
+/// Get the per-app capture rate limits (bundle identifier or executable -> max captures
+/// per minute)
+#[tauri::command]
+pub fn get_app_rate_limits(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, u32>, ClipsterError> {
+    state.db.get_app_rate_limits()
+}
+
+/// Set the per-app capture rate limits (apps without an entry are unlimited)
+#[tauri::command]
+pub fn set_app_rate_limits(
+    state: State<'_, AppState>,
+    limits: HashMap<String, u32>,
+) -> Result<(), ClipsterError> {
+    state.db.set_app_rate_limits(&limits)
+}
+
+/// Turn Spotlight indexing on (indexes history) or off (removes every indexed item)
+#[tauri::command]
+pub fn set_spotlight_indexing(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), ClipsterError> {
+    state
+        .db
+        .set_setting("spotlight_indexing", if enabled { "true" } else { "false" })?;
+
+    if enabled {
+        state.spotlight.reindex();
+    } else {
+        state.spotlight.delete_all();
+    }
+    Ok(())
+}
+
+/// Turn the recent items jump list / Dock menu on or off (off also empties it)
+#[tauri::command]
+pub fn set_recent_items_os_menu(
+    state: State<'_, AppState>,
+    enabled: bool,
+) -> Result<(), ClipsterError> {
+    state.db.set_setting(
+        "recent_items_os_menu",
+        if enabled { "true" } else { "false" },
+    )?;
+    state.recent_items.refresh();
+    Ok(())
+}
+
+/// Set the automatic history clearing schedule ("off", "daily_at(HH:MM)" or "on_quit")
+#[tauri::command]
+pub fn set_auto_clear_schedule(
+    state: State<'_, AppState>,
+    schedule: String,
+) -> Result<(), ClipsterError> {
+    let schedule = AutoClearSchedule::parse(&schedule).ok_or_else(|| {
+        ClipsterError::Validation(format!("Invalid auto-clear schedule: {}", schedule))
+    })?;
+    state.db.set_auto_clear_schedule(schedule)
+}
+
+/// Rebuild the Spotlight index from the whole history
+#[tauri::command]
+pub fn reindex_spotlight(state: State<'_, AppState>) -> Result<(), ClipsterError> {
+    if !spotlight::is_enabled(&state.db) {
+        return Err(ClipsterError::Validation(
+            "Spotlight indexing is disabled".to_string(),
+        ));
+    }
+    state.spotlight.reindex();
+    Ok(())
+}
+
 /// Set menu bar icon visibility (macOS)
 #[tauri::command]
 pub fn set_menu_bar_icon_visible(
     app: AppHandle,
     state: State<'_, AppState>,
     visible: bool,
-) -> Result<(), String> {
+) -> Result<(), ClipsterError> {
     state
         .db
         .set_setting("show_menu_bar_icon", if visible { "true" } else { "false" })?;
+    apply_menu_bar_icon_visible(&app, visible)
+}
 
+/// Show or hide the tray icon without saving the setting
+pub fn apply_menu_bar_icon_visible(app: &AppHandle, visible: bool) -> Result<(), ClipsterError> {
     if let Some(tray) = app.tray_by_id("main-tray") {
         tray.set_visible(visible)
-            .map_err(|e| format!("Failed to set tray visibility: {}", e))?;
+            .map_err(|e| ClipsterError::Io(format!("Failed to set tray visibility: {}", e)))?;
     }
 
     Ok(())
 }
+
+/// Pause or resume clipboard capture; the choice is restored at the next launch
+#[tauri::command]
+pub fn set_capture_paused(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paused: bool,
+) -> Result<(), ClipsterError> {
+    state
+        .db
+        .set_setting("capture_paused", if paused { "true" } else { "false" })?;
+
+    if paused {
+        clipboard_monitor::stop_monitoring();
+    } else {
+        let file_storage = state.file_storage.lock()?.clone();
+        clipboard_monitor::start_monitoring(app, state.db.clone(), file_storage)
+            .map_err(ClipsterError::ClipboardAccess)?;
+    }
+    Ok(())
+}
+
+/// Capture the next clipboard change even from an app whose rate limit would
+/// drop it (limit 0 included); unused, this lapses after 30 seconds
+/// Returns when it lapses
+#[tauri::command]
+pub fn allow_next_capture() -> chrono::DateTime<chrono::Utc> {
+    clipboard_monitor::allow_next_capture()
+}
+
+/// Get the panel view state saved by `set_view_state`
+/// Each profile has its own, as it lives in the profile's settings
+#[tauri::command]
+pub fn get_view_state(
+    state: State<'_, AppState>,
+) -> Result<Option<serde_json::Value>, ClipsterError> {
+    state.db.get_view_state()
+}
+
+/// Save the panel view state (active pinboard, type filter, scroll anchor item)
+/// Written by the frontend when the panel hides; rejected over 8 KB
+#[tauri::command]
+pub fn set_view_state(
+    state: State<'_, AppState>,
+    view_state: serde_json::Value,
+) -> Result<(), ClipsterError> {
+    state.db.set_view_state(&view_state)
+}
+
+/// Get the state applied at launch (the `app-ready` event can arrive before
+/// the window has loaded)
+#[tauri::command]
+pub fn get_initial_state() -> Option<InitialState> {
+    startup::initial_state()
+}
+
+/// Grant state of the macOS permissions features depend on (Accessibility,
+/// Screen Recording, Full Disk Access); all granted on other platforms
+#[tauri::command]
+pub fn check_permissions() -> PermissionStatus {
+    permissions::check()
+}
+
+/// Ask for a permission: the system prompt the first time, then its System
+/// Settings pane
+#[tauri::command]
+pub fn request_permission(kind: PermissionKind) -> Result<RequestOutcome, ClipsterError> {
+    permissions::request(kind)
+}
@@ -1,4 +1,22 @@
-use tauri::{AppHandle, Manager};
+use crate::clipboard::clipboard_monitor;
+use crate::commands::clipboard_commands::require_unlocked_item;
+use crate::error::ClipsterError;
+use crate::panel_placement::{self, MonitorPlacement, Placement};
+use crate::paste_target::{self, Activation, PasteTarget};
+use crate::pinboard_palette::layout::Area;
+use crate::AppState;
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+/// Label of the floating item preview window
+pub const PREVIEW_WINDOW_LABEL: &str = "preview";
+
+/// Label of the main panel window
+pub const MAIN_WINDOW_LABEL: &str = "main";
+
+/// Maximum share of the monitor the preview window may cover
+const PREVIEW_MAX_SCREEN_FRACTION: f64 = 0.8;
 
 // ── macOS native helpers ──────────────────────────────────────────────────────
 
@@ -110,11 +128,48 @@ fn ns_window_ptr(window: &tauri::WebviewWindow) -> Option<*mut AnyObject> {
 
 // ── Reposition to cursor monitor ──────────────────────────────────────────────
 
-/// Reposition the window to the bottom of the monitor where the cursor is.
+/// Logical bounds of a monitor (top-left origin, same as Tauri)
+#[derive(Debug, Clone)]
+pub struct MonitorBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Stable identifier used to remember the panel placement per monitor
+    pub id: Option<String>,
+}
+
+/// Reposition the window on the monitor where the cursor is, using that
+/// monitor's remembered placement or the global one.
 /// Called every time the window is shown so it follows the user across screens.
-#[cfg(target_os = "macos")]
 pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
+    let Some(bounds) = cursor_monitor_bounds(window) else {
+        return;
+    };
+
+    let placement = match window.app_handle().try_state::<AppState>() {
+        Some(state) => panel_placement::resolve(&state.db, bounds.id.as_deref()),
+        None => Placement::default(),
+    };
+    let frame = placement.frame(Area {
+        x: bounds.x,
+        y: bounds.y,
+        width: bounds.width,
+        height: bounds.height,
+    });
+
+    let _ = window.set_size(tauri::LogicalSize::new(frame.width, frame.height));
+    let _ = window.set_position(tauri::LogicalPosition::new(frame.x, frame.y));
+}
+
+/// Bounds of the monitor under the cursor, in logical points
+#[cfg(target_os = "macos")]
+pub fn cursor_monitor_bounds(_window: &tauri::WebviewWindow) -> Option<MonitorBounds> {
     // Use CoreGraphics C functions directly — avoids objc2 msg_send Encode issues
+    use core_foundation::base::TCFType;
+    use core_foundation::string::{CFString, CFStringRef};
+    use core_foundation::uuid::CFUUIDRef;
+
     #[repr(C)]
     #[derive(Copy, Clone)]
     struct CGPoint {
@@ -146,13 +201,19 @@ pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
             count: *mut u32,
         ) -> i32;
         fn CGDisplayBounds(display: CGDirectDisplayID) -> CGRect;
+        fn CFUUIDCreateString(allocator: *const std::ffi::c_void, uuid: CFUUIDRef) -> CFStringRef;
+    }
+
+    #[link(name = "ColorSync", kind = "framework")]
+    extern "C" {
+        fn CGDisplayCreateUUIDFromDisplayID(display: CGDirectDisplayID) -> CFUUIDRef;
     }
 
     unsafe {
         // Cursor position in global display coords (top-left origin)
         let event = CGEventCreate(std::ptr::null());
         if event.is_null() {
-            return;
+            return None;
         }
         let cursor = CGEventGetLocation(event);
         CFRelease(event);
@@ -162,11 +223,11 @@ pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
         if CGGetActiveDisplayList(0, std::ptr::null_mut(), &mut display_count) != 0
             || display_count == 0
         {
-            return;
+            return None;
         }
         let mut displays = vec![0u32; display_count as usize];
         if CGGetActiveDisplayList(display_count, displays.as_mut_ptr(), &mut display_count) != 0 {
-            return;
+            return None;
         }
 
         // Find display containing cursor
@@ -178,23 +239,55 @@ pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
                 && cursor.y >= bounds.origin.y
                 && cursor.y < bounds.origin.y + bounds.size.height
             {
-                let win_h = bounds.size.height * 0.33;
-                // Global display coords use top-left origin, same as Tauri
-                let win_y = bounds.origin.y + bounds.size.height - win_h;
+                // The display ID can change between boots, its UUID doesn't
+                let uuid = CGDisplayCreateUUIDFromDisplayID(display_id);
+                let id = if uuid.is_null() {
+                    None
+                } else {
+                    let string = CFUUIDCreateString(std::ptr::null(), uuid);
+                    CFRelease(uuid as *const std::ffi::c_void);
+                    (!string.is_null())
+                        .then(|| CFString::wrap_under_create_rule(string).to_string())
+                };
 
-                let _ = window.set_size(tauri::LogicalSize::new(bounds.size.width, win_h));
-                let _ = window.set_position(tauri::LogicalPosition::new(bounds.origin.x, win_y));
-                return;
+                // Global display coords use top-left origin, same as Tauri
+                return Some(MonitorBounds {
+                    x: bounds.origin.x,
+                    y: bounds.origin.y,
+                    width: bounds.size.width,
+                    height: bounds.size.height,
+                    id,
+                });
             }
         }
     }
+
+    None
 }
 
+// ── Windows native helpers ────────────────────────────────────────────────────
+
 #[cfg(target_os = "windows")]
-pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
+use windows::Win32::Foundation::HWND;
+#[cfg(target_os = "windows")]
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetWindowLongPtrW, SetForegroundWindow, SetWindowLongPtrW, SetWindowPos, ShowWindow,
+    GWL_EXSTYLE, HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SWP_SHOWWINDOW,
+    SW_SHOWNOACTIVATE, WS_EX_APPWINDOW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW,
+};
+
+/// Native handle of a Tauri window (Tauri may link a different `windows`
+/// crate version, so the handle is rebuilt from its raw value)
+#[cfg(target_os = "windows")]
+fn window_hwnd(window: &tauri::WebviewWindow) -> Option<HWND> {
+    window.hwnd().ok().map(|hwnd| HWND(hwnd.0 as _))
+}
+
+#[cfg(target_os = "windows")]
+pub fn cursor_monitor_bounds(window: &tauri::WebviewWindow) -> Option<MonitorBounds> {
     use windows::Win32::Foundation::POINT;
     use windows::Win32::Graphics::Gdi::{
-        GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITOR_DEFAULTTONEAREST,
+        GetMonitorInfoW, MonitorFromPoint, MONITORINFO, MONITORINFOEXW, MONITOR_DEFAULTTONEAREST,
     };
     use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
 
@@ -203,33 +296,53 @@ pub fn reposition_to_cursor_monitor(window: &tauri::WebviewWindow) {
         let _ = GetCursorPos(&mut point);
 
         let hmonitor = MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST);
-        let mut info: MONITORINFO = std::mem::zeroed();
-        info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
-        let _ = GetMonitorInfoW(hmonitor, &mut info);
-
-        let rc = info.rcMonitor;
+        // The extended struct also carries the device name
+        let mut info: MONITORINFOEXW = std::mem::zeroed();
+        info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        let found = GetMonitorInfoW(
+            hmonitor,
+            &mut info as *mut MONITORINFOEXW as *mut MONITORINFO,
+        )
+        .as_bool();
+
+        let rc = info.monitorInfo.rcMonitor;
         let phys_w = (rc.right - rc.left) as f64;
         let phys_h = (rc.bottom - rc.top) as f64;
         let phys_x = rc.left as f64;
         let phys_y = rc.top as f64;
 
+        // Device names like \\.\DISPLAY2 are reused by whatever is plugged
+        // into that output, so the resolution is part of the ID
+        let id = found.then(|| {
+            let len = info
+                .szDevice
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(info.szDevice.len());
+            let device = String::from_utf16_lossy(&info.szDevice[..len]);
+            panel_placement::placement::device_monitor_id(
+                &device,
+                rc.right - rc.left,
+                rc.bottom - rc.top,
+            )
+        });
+
         // Convert physical pixels → logical points
         let scale = window.scale_factor().unwrap_or(1.0);
-        let w = phys_w / scale;
-        let h = phys_h / scale;
-        let x = phys_x / scale;
-        let y = phys_y / scale;
-
-        let win_h = h * 0.33;
-        let win_y = y + h - win_h;
-
-        let _ = window.set_size(tauri::LogicalSize::new(w, win_h));
-        let _ = window.set_position(tauri::LogicalPosition::new(x, win_y));
+        Some(MonitorBounds {
+            x: phys_x / scale,
+            y: phys_y / scale,
+            width: phys_w / scale,
+            height: phys_h / scale,
+            id,
+        })
     }
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-pub fn reposition_to_cursor_monitor(_window: &tauri::WebviewWindow) {}
+pub fn cursor_monitor_bounds(_window: &tauri::WebviewWindow) -> Option<MonitorBounds> {
+    None
+}
 
 // ── NSPanel runtime-swizzle pattern ───────────────────────────────────────────
 //
@@ -397,6 +510,23 @@ pub fn setup_window_behavior(window: &tauri::WebviewWindow) {
             }
         }
     }
+
+    // Windows: a tool window (no taskbar button, not in Alt+Tab) that clicks
+    // don't activate, so showing it never takes focus from the app underneath
+    #[cfg(target_os = "windows")]
+    {
+        let Some(hwnd) = window_hwnd(window) else {
+            eprintln!("setup_window_behavior: could not obtain HWND");
+            return;
+        };
+        unsafe {
+            let ex_style = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+            let new_style = (ex_style & !(WS_EX_APPWINDOW.0 as isize))
+                | WS_EX_NOACTIVATE.0 as isize
+                | WS_EX_TOOLWINDOW.0 as isize;
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, new_style);
+        }
+    }
 }
 
 /// Show the panel over any app (including fullscreen) and grab keyboard focus.
@@ -404,7 +534,16 @@ pub fn setup_window_behavior(window: &tauri::WebviewWindow) {
 /// Re-applies collection behavior and level on EVERY show because orderOut
 /// can reset them.  Debug-prints the actual class + level after ordering
 /// front so you can verify the swizzle is intact.
+///
+/// On Windows the panel is shown topmost WITHOUT activation, so a game or
+/// video underneath keeps focus (and stays fullscreen) while the user only
+/// glances at the history; `focus_panel` takes focus once they interact.
 pub fn show_panel(window: &tauri::WebviewWindow) {
+    if window.label() == MAIN_WINDOW_LABEL {
+        clipboard_monitor::set_panel_visible(true);
+        remember_paste_target(window);
+    }
+
     #[cfg(target_os = "macos")]
     {
         let Some(ns_win) = ns_window_ptr(window) else {
@@ -446,9 +585,38 @@ pub fn show_panel(window: &tauri::WebviewWindow) {
 
     #[cfg(target_os = "windows")]
     {
-        let _ = window.set_always_on_top(true);
-        let _ = window.show();
-        let _ = window.set_focus();
+        let Some(hwnd) = window_hwnd(window) else {
+            eprintln!("show_panel: could not obtain HWND");
+            return;
+        };
+        unsafe {
+            // window.show() / set_focus() would activate the panel
+            let _ = ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            if let Err(e) = SetWindowPos(
+                hwnd,
+                HWND_TOPMOST,
+                0,
+                0,
+                0,
+                0,
+                SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE | SWP_SHOWWINDOW,
+            ) {
+                eprintln!("show_panel: SetWindowPos failed: {}", e);
+            }
+        }
+    }
+}
+
+/// Remember the window focus goes back to, before the panel takes it
+/// A panel shown while Clipster is frontmost keeps the previous target
+fn remember_paste_target(window: &tauri::WebviewWindow) {
+    let Some(target) = paste_target::capture().filter(paste_target::is_external) else {
+        return;
+    };
+    if let Some(state) = window.try_state::<AppState>() {
+        if let Ok(mut current) = state.paste_target.lock() {
+            *current = Some(target);
+        }
     }
 }
 
@@ -456,11 +624,30 @@ pub fn show_panel(window: &tauri::WebviewWindow) {
 ///
 /// [NSApp hide:] destroys the window's Space affinity, preventing it from
 /// reappearing over fullscreen apps on the next show_panel call.
+///
+/// Hiding also relocks every passcode-protected pinboard and closes the
+/// item preview, which only makes sense next to the panel.
 pub fn hide_panel(window: &tauri::WebviewWindow) {
+    if let Some(state) = window.try_state::<AppState>() {
+        state.pinboard_locks.relock_all();
+    }
+
+    if window.label() == MAIN_WINDOW_LABEL {
+        clipboard_monitor::set_panel_visible(false);
+        if let Some(preview) = window.get_webview_window(PREVIEW_WINDOW_LABEL) {
+            order_out(&preview);
+        }
+    }
+
+    order_out(window);
+}
+
+/// Order a window out without touching the app's Space affinity
+pub fn order_out(window: &tauri::WebviewWindow) {
     #[cfg(target_os = "macos")]
     {
         let Some(ns_win) = ns_window_ptr(window) else {
-            eprintln!("hide_panel: could not obtain NSWindow");
+            eprintln!("order_out: could not obtain NSWindow");
             return;
         };
         unsafe {
@@ -475,11 +662,26 @@ pub fn hide_panel(window: &tauri::WebviewWindow) {
     }
 }
 
+/// Give a window shown by `show_panel` keyboard focus
+/// Only needed on Windows, where `show_panel` doesn't activate the window
+pub fn focus_window(window: &tauri::WebviewWindow) {
+    #[cfg(target_os = "windows")]
+    if let Some(hwnd) = window_hwnd(window) {
+        // WS_EX_NOACTIVATE only stops clicks from activating the window
+        if !unsafe { SetForegroundWindow(hwnd) }.as_bool() {
+            eprintln!("focus_window: SetForegroundWindow was refused");
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    let _ = window;
+}
+
 // ── Tauri IPC commands ────────────────────────────────────────────────────────
 
 /// Hide the main window
 #[tauri::command]
-pub fn hide_window(app: AppHandle) -> Result<(), String> {
+pub fn hide_window(app: AppHandle) -> Result<(), ClipsterError> {
     if let Some(window) = app.get_webview_window("main") {
         hide_panel(&window);
     }
@@ -488,7 +690,7 @@ pub fn hide_window(app: AppHandle) -> Result<(), String> {
 
 /// Show the main window
 #[tauri::command]
-pub fn show_window(app: AppHandle) -> Result<(), String> {
+pub fn show_window(app: AppHandle) -> Result<(), ClipsterError> {
     if let Some(window) = app.get_webview_window("main") {
         reposition_to_cursor_monitor(&window);
         show_panel(&window);
@@ -496,9 +698,203 @@ pub fn show_window(app: AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// Global panel placement and the one saved for the monitor under the cursor
+#[derive(Debug, Serialize)]
+pub struct PanelPlacementSettings {
+    pub default: Placement,
+    pub monitor_id: Option<String>,
+    /// `None` when this monitor uses the global placement
+    pub monitor: Option<Placement>,
+}
+
+/// Panel placement settings, as seen from the monitor under the cursor
+#[tauri::command]
+pub fn get_panel_placement(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PanelPlacementSettings, ClipsterError> {
+    let monitor_id = app
+        .get_webview_window(MAIN_WINDOW_LABEL)
+        .and_then(|window| cursor_monitor_bounds(&window))
+        .and_then(|bounds| bounds.id);
+    let placements = state.db.get_monitor_placements()?;
+    let monitor = monitor_id
+        .as_deref()
+        .and_then(|id| placements.get(id))
+        .map(|entry| entry.placement);
+
+    Ok(PanelPlacementSettings {
+        default: state.db.get_panel_placement()?,
+        monitor_id,
+        monitor,
+    })
+}
+
+/// Set the panel placement
+///
+/// With `this_monitor` the placement is saved for the monitor under the
+/// cursor only, and `None` makes that monitor use the global placement again.
+/// Otherwise it replaces the global placement (`None` resets it).
+#[tauri::command]
+pub fn set_panel_placement(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    placement: Option<Placement>,
+    this_monitor: bool,
+) -> Result<(), ClipsterError> {
+    if let Some(placement) = &placement {
+        placement.validate().map_err(ClipsterError::Validation)?;
+    }
+    let window = app.get_webview_window(MAIN_WINDOW_LABEL);
+
+    if this_monitor {
+        let monitor_id = window
+            .as_ref()
+            .and_then(cursor_monitor_bounds)
+            .and_then(|bounds| bounds.id)
+            .ok_or_else(|| {
+                ClipsterError::Validation("Could not identify the current monitor".to_string())
+            })?;
+        let mut placements = state.db.get_monitor_placements()?;
+        match placement {
+            Some(placement) => {
+                let entry = MonitorPlacement {
+                    placement,
+                    last_seen: Utc::now(),
+                };
+                placements.insert(monitor_id, entry);
+            }
+            None => {
+                placements.remove(&monitor_id);
+            }
+        }
+        state.db.set_monitor_placements(&placements)?;
+    } else {
+        state
+            .db
+            .set_panel_placement(&placement.unwrap_or_default())?;
+    }
+
+    if let Some(window) = window.filter(|window| window.is_visible().unwrap_or(false)) {
+        reposition_to_cursor_monitor(&window);
+    }
+    Ok(())
+}
+
+/// Give the main window keyboard focus
+///
+/// On Windows the panel is shown without activation; the frontend calls this
+/// on the first key press or click so typing goes to the panel from then on.
+/// Other platforms already focus the panel when it is shown.
+#[tauri::command]
+pub fn focus_panel(app: AppHandle) -> Result<(), ClipsterError> {
+    if let Some(window) = app.get_webview_window("main") {
+        focus_window(&window);
+    }
+    Ok(())
+}
+
+/// App and window focus goes back to when the panel closes, for "Pasting into …"
+#[tauri::command]
+pub fn get_paste_target(state: State<'_, AppState>) -> Result<Option<PasteTarget>, ClipsterError> {
+    Ok(state.paste_target.lock()?.clone())
+}
+
+/// Hide the panel and give focus back to the window it was summoned from,
+/// e.g. right before the user pastes. Falls back to activating the app when
+/// that window was closed; None if there is no remembered target
+#[tauri::command]
+pub fn focus_paste_target(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<Activation>, ClipsterError> {
+    if let Some(window) = app.get_webview_window("main") {
+        hide_panel(&window);
+    }
+    let target = state.paste_target.lock()?.clone();
+    Ok(target.map(|target| paste_target::activate(&target)))
+}
+
+/// Open (or reuse) the floating full-size preview window for an item
+///
+/// Sized to the stored image dimensions, clamped to 80% of the monitor under
+/// the cursor.  The item ID is passed as the `preview` query parameter.
+#[tauri::command]
+pub fn show_item_preview(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    item_id: String,
+) -> Result<(), ClipsterError> {
+    let item = require_unlocked_item(&state, &item_id)?;
+
+    // Image items use their real size; everything else gets a reading-sized window
+    let (image_w, image_h) = item
+        .image_path
+        .as_deref()
+        .and_then(|path| image::image_dimensions(path).ok())
+        .map(|(w, h)| (w as f64, h as f64))
+        .unwrap_or((800.0, 600.0));
+
+    let (window, created) = match app.get_webview_window(PREVIEW_WINDOW_LABEL) {
+        Some(window) => (window, false),
+        None => {
+            let url = WebviewUrl::App(format!("index.html?preview={}", item_id).into());
+            let window = WebviewWindowBuilder::new(&app, PREVIEW_WINDOW_LABEL, url)
+                .title("Clipster Preview")
+                .decorations(false)
+                .resizable(false)
+                .skip_taskbar(true)
+                .visible(false)
+                .build()
+                .map_err(|e| {
+                    ClipsterError::Io(format!("Failed to create preview window: {}", e))
+                })?;
+            setup_window_behavior(&window);
+            (window, true)
+        }
+    };
+
+    if !created {
+        let mut url = window
+            .url()
+            .map_err(|e| ClipsterError::Io(format!("Failed to read preview URL: {}", e)))?;
+        url.query_pairs_mut().clear().append_pair("preview", &item_id);
+        window
+            .navigate(url)
+            .map_err(|e| ClipsterError::Io(format!("Failed to navigate preview window: {}", e)))?;
+    }
+
+    if let Some(bounds) = cursor_monitor_bounds(&window) {
+        let scale = (bounds.width * PREVIEW_MAX_SCREEN_FRACTION / image_w)
+            .min(bounds.height * PREVIEW_MAX_SCREEN_FRACTION / image_h)
+            .min(1.0);
+        let (w, h) = (image_w * scale, image_h * scale);
+        let x = bounds.x + (bounds.width - w) / 2.0;
+        let y = bounds.y + (bounds.height - h) / 2.0;
+
+        let _ = window.set_size(tauri::LogicalSize::new(w, h));
+        let _ = window.set_position(tauri::LogicalPosition::new(x, y));
+    } else {
+        let _ = window.set_size(tauri::LogicalSize::new(image_w, image_h));
+        let _ = window.center();
+    }
+
+    show_panel(&window);
+    Ok(())
+}
+
+/// Hide the item preview window
+#[tauri::command]
+pub fn hide_item_preview(app: AppHandle) -> Result<(), ClipsterError> {
+    if let Some(window) = app.get_webview_window(PREVIEW_WINDOW_LABEL) {
+        order_out(&window);
+    }
+    Ok(())
+}
+
 /// Quit the application
 #[tauri::command]
-pub fn quit_app(app: AppHandle) -> Result<(), String> {
+pub fn quit_app(app: AppHandle) -> Result<(), ClipsterError> {
     app.exit(0);
     Ok(())
 }
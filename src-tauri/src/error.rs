@@ -0,0 +1,189 @@
+//! Error type shared by storage, the clipboard readers and commands
+//!
+//! Errors reach the frontend as `{ code, message }`, so it can tell a missing
+//! item from a locked database or a missing file without parsing messages.
+//! Modules that still return `Result<_, String>` convert with `?` through
+//! `From<ClipsterError> for String`.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::sync::PoisonError;
+
+/// What went wrong, with a human-readable message
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ClipsterError {
+    /// Item, pinboard, revision or file doesn't exist
+    #[error("{0}")]
+    NotFound(String),
+    /// SQLite failure (including a poisoned connection lock)
+    #[error("{0}")]
+    Database(String),
+    /// SQLite database busy or locked by another connection; retrying may succeed
+    #[error("{0}")]
+    Busy(String),
+    /// File system or other OS failure (other than a missing file)
+    #[error("{0}")]
+    Io(String),
+    /// Image bytes that can't be decoded or encoded
+    #[error("{0}")]
+    ImageDecode(String),
+    /// System clipboard can't be read or written
+    #[error("{0}")]
+    ClipboardAccess(String),
+    /// Invalid input or stored value
+    #[error("{0}")]
+    Validation(String),
+    /// An OS permission (e.g. macOS Accessibility) has not been granted
+    #[error("{0}")]
+    PermissionDenied(String),
+}
+
+impl ClipsterError {
+    /// Stable identifier sent to the frontend
+    pub fn code(&self) -> &'static str {
+        match self {
+            ClipsterError::NotFound(_) => "not_found",
+            ClipsterError::Database(_) => "database",
+            ClipsterError::Busy(_) => "busy",
+            ClipsterError::Io(_) => "io",
+            ClipsterError::ImageDecode(_) => "image_decode",
+            ClipsterError::ClipboardAccess(_) => "clipboard_access",
+            ClipsterError::Validation(_) => "validation",
+            ClipsterError::PermissionDenied(_) => "permission_denied",
+        }
+    }
+
+    /// Same error with `what: ` in front of its message
+    pub fn prefixed(self, what: &str) -> Self {
+        let prefix = |message: String| format!("{}: {}", what, message);
+        match self {
+            ClipsterError::NotFound(m) => ClipsterError::NotFound(prefix(m)),
+            ClipsterError::Database(m) => ClipsterError::Database(prefix(m)),
+            ClipsterError::Busy(m) => ClipsterError::Busy(prefix(m)),
+            ClipsterError::Io(m) => ClipsterError::Io(prefix(m)),
+            ClipsterError::ImageDecode(m) => ClipsterError::ImageDecode(prefix(m)),
+            ClipsterError::ClipboardAccess(m) => ClipsterError::ClipboardAccess(prefix(m)),
+            ClipsterError::Validation(m) => ClipsterError::Validation(prefix(m)),
+            ClipsterError::PermissionDenied(m) => ClipsterError::PermissionDenied(prefix(m)),
+        }
+    }
+}
+
+impl Serialize for ClipsterError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ClipsterError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for ClipsterError {
+    fn from(e: rusqlite::Error) -> Self {
+        let busy = matches!(
+            e.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+        );
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => ClipsterError::NotFound(e.to_string()),
+            _ if busy => ClipsterError::Busy(e.to_string()),
+            _ => ClipsterError::Database(e.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for ClipsterError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => ClipsterError::NotFound(e.to_string()),
+            _ => ClipsterError::Io(e.to_string()),
+        }
+    }
+}
+
+impl From<image::ImageError> for ClipsterError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => e.into(),
+            _ => ClipsterError::ImageDecode(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ClipsterError {
+    fn from(e: serde_json::Error) -> Self {
+        ClipsterError::Validation(e.to_string())
+    }
+}
+
+impl<T> From<PoisonError<T>> for ClipsterError {
+    fn from(e: PoisonError<T>) -> Self {
+        ClipsterError::Database(format!("Lock error: {}", e))
+    }
+}
+
+impl From<ClipsterError> for String {
+    fn from(e: ClipsterError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Prefix the error of a `Result` with what was being done, keeping its kind:
+/// `.context("Failed to query items")` reads "Failed to query items: <cause>"
+pub trait Context<T> {
+    fn context(self, what: &str) -> Result<T, ClipsterError>;
+}
+
+impl<T, E: Into<ClipsterError>> Context<T> for Result<T, E> {
+    fn context(self, what: &str) -> Result<T, ClipsterError> {
+        self.map_err(|e| e.into().prefixed(what))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let error = ClipsterError::Validation("Invalid history_limit value".to_string());
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "code": "validation", "message": "Invalid history_limit value" })
+        );
+    }
+
+    #[test]
+    fn test_context_keeps_kind() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let error = Err::<(), _>(missing)
+            .context("Failed to read image file")
+            .unwrap_err();
+        assert_eq!(error.code(), "not_found");
+        assert!(error.to_string().starts_with("Failed to read image file: "));
+
+        let error = Err::<(), _>(rusqlite::Error::InvalidQuery)
+            .context("Failed to query items")
+            .unwrap_err();
+        assert_eq!(error.code(), "database");
+    }
+
+    #[test]
+    fn test_busy_and_locked_are_retryable() {
+        let sqlite_error = |code| {
+            ClipsterError::from(rusqlite::Error::SqliteFailure(
+                rusqlite::ffi::Error::new(code),
+                None,
+            ))
+        };
+        assert_eq!(sqlite_error(rusqlite::ffi::SQLITE_BUSY).code(), "busy");
+        assert_eq!(sqlite_error(rusqlite::ffi::SQLITE_LOCKED).code(), "busy");
+        // Extended codes keep their primary code
+        assert_eq!(sqlite_error(rusqlite::ffi::SQLITE_BUSY_SNAPSHOT).code(), "busy");
+        assert_eq!(sqlite_error(rusqlite::ffi::SQLITE_CONSTRAINT).code(), "database");
+
+        let error = Err::<(), _>(ClipsterError::Busy("database is locked".to_string()))
+            .context("Failed to insert clipboard item")
+            .unwrap_err();
+        assert!(matches!(error, ClipsterError::Busy(_)));
+    }
+}
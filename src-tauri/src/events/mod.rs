@@ -0,0 +1,89 @@
+//! Events sent to the frontend
+//!
+//! Every event is declared in `payloads`, where each payload type implements
+//! `Event` with its event name and payload version. Payloads go out wrapped
+//! in `Versioned`, which adds a `payload_version` field next to their own,
+//! and `get_event_schema` returns the JSON schema of each event so the
+//! TypeScript types can be checked against the wire format.
+//!
+//! Events are pushed onto an `EventQueue` and sent in order by a dedicated
+//! emitter task, so neither a capture nor a command waits on a busy webview
+//! (see `event_queue`).
+
+pub mod payloads;
+
+pub use payloads::*;
+
+use crate::clipboard::event_queue::{self, EventQueue};
+use crate::storage::file_storage::ImageSaveError;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter};
+
+/// Events for the frontend, sent by the emitter task
+static EVENT_QUEUE: OnceLock<EventQueue> = OnceLock::new();
+
+/// Queue `payload` for its event without waiting for it to be sent
+/// Returns false when the event was dropped
+pub fn emit<E: Event>(app: &AppHandle, payload: &E) -> bool {
+    let queue = EVENT_QUEUE.get_or_init(|| {
+        let (queue, events) = EventQueue::new(event_queue::MAX_QUEUED_EVENTS);
+        let app = app.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            event_queue::run_emitter(events, |event| {
+                app.emit(event.name, &event.payload)
+                    .map_err(|e| e.to_string())
+            })
+        });
+        queue
+    });
+    let queued = queue.push(E::NAME, &Versioned::new(payload));
+    if !queued {
+        clipster_log!(Warn, "[events] Dropped {} event, too many waiting", E::NAME);
+    }
+    queued
+}
+
+/// Events waiting for the emitter task
+pub fn queued_events() -> usize {
+    EVENT_QUEUE.get().map_or(0, EventQueue::queued)
+}
+
+/// Events dropped because too many were waiting
+pub fn dropped_events() -> u64 {
+    EVENT_QUEUE.get().map_or(0, EventQueue::dropped)
+}
+
+/// A capture was saved (`clipboard-changed`)
+pub fn emit_item_added(app: &AppHandle, capture: CapturedItem, seq: i64) -> bool {
+    let payload = ClipboardChangedPayload {
+        item: capture.item,
+        replaced_item_id: capture.replaced_item_id,
+        seq,
+    };
+    emit(app, &payload)
+}
+
+/// Captures saved during a burst, oldest first (`clipboard-changed-batch`)
+pub fn emit_items_added(app: &AppHandle, items: Vec<CapturedItem>, seq: i64) -> bool {
+    emit(app, &ClipboardChangedBatchPayload { items, seq })
+}
+
+/// Fields of an already emitted item changed (`item-updated`)
+pub fn emit_item_updated(app: &AppHandle, payload: &ItemUpdatedPayload) -> bool {
+    emit(app, payload)
+}
+
+/// A bulk operation updated or deleted items (`items-updated`)
+pub fn emit_items_updated(app: &AppHandle, payload: &ItemsUpdatedPayload) -> bool {
+    emit(app, payload)
+}
+
+/// The backend deleted an item on its own (`item-deleted`)
+pub fn emit_item_deleted(app: &AppHandle, id: String) -> bool {
+    emit(app, &ItemDeletedPayload { id })
+}
+
+/// A capture was lost because its image could not be written (`storage-error`)
+pub fn emit_storage_error(app: &AppHandle, item_id: String, error: ImageSaveError) -> bool {
+    emit(app, &StorageErrorPayload { item_id, error })
+}
@@ -0,0 +1,401 @@
+//! Names and payloads of every event sent to the frontend
+//!
+//! Each payload type implements `Event`, which gives its event name and
+//! payload version. Payloads of other modules' types (progress reports, the
+//! launch state) are declared here too, so this file lists every event.
+
+use crate::clipboard::capture_failures::CaptureFailure;
+use crate::clipboard::payload_limit::TooLarge;
+use crate::clipboard::storage_guard::StorageLowPayload;
+use crate::models::{ClipboardItem, ContentType, MediaMetadata};
+use crate::permissions::PermissionStatus;
+use crate::startup::initial_state::InitialState;
+use crate::storage::backup::BackupProgress;
+use crate::storage::file_storage::ImageSaveError;
+use crate::storage::image_import::ImportProgress;
+use crate::storage::profiles::Profile;
+use crate::storage::recovery::RecoveryReport;
+use crate::storage::reindex::ReindexProgress;
+use crate::type_text::TypingProgress;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const APP_READY: &str = "app-ready";
+pub const BACKUP_PROGRESS: &str = "backup-progress";
+pub const CAPTURE_FAILURE: &str = "capture-failure";
+pub const CAPTURE_SKIPPED_TOO_LARGE: &str = "capture-skipped-too-large";
+pub const CLIPBOARD_CHANGED: &str = "clipboard-changed";
+pub const CLIPBOARD_CHANGED_BATCH: &str = "clipboard-changed-batch";
+pub const CYCLE_COMMITTED: &str = "cycle-committed";
+pub const CYCLE_SELECTION_CHANGED: &str = "cycle-selection-changed";
+pub const DATABASE_RECOVERED: &str = "database-recovered";
+pub const HISTORY_CLEARED: &str = "history-cleared";
+pub const IMAGE_IMPORT_PROGRESS: &str = "image-import-progress";
+pub const ITEM_DELETED: &str = "item-deleted";
+pub const ITEM_UPDATED: &str = "item-updated";
+pub const ITEMS_UPDATED: &str = "items-updated";
+pub const OPEN_ITEM: &str = "open-item";
+pub const OPEN_SETTINGS: &str = "open-settings";
+pub const OVERRIDE_CONSUMED: &str = "override-consumed";
+pub const PERMISSIONS_CHANGED: &str = "permissions-changed";
+pub const PROFILE_CHANGED: &str = "profile-changed";
+pub const REINDEX_PROGRESS: &str = "reindex-progress";
+pub const STORAGE_ERROR: &str = "storage-error";
+pub const STORAGE_LOW: &str = "storage-low";
+pub const STORAGE_RELOCATED: &str = "storage-relocated";
+pub const THUMBNAIL_UPDATED: &str = "clipboard-item-thumbnail-updated";
+pub const TYPING_PROGRESS: &str = "typing-progress";
+
+/// Payload of a frontend event
+pub trait Event: Serialize + JsonSchema {
+    const NAME: &'static str;
+    /// Raised when the payload changes in a way older listeners would misread
+    const VERSION: u32 = 1;
+}
+
+/// A payload as sent: its fields, plus the version they follow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Versioned<P> {
+    pub payload_version: u32,
+    #[serde(flatten)]
+    pub payload: P,
+}
+
+impl<'a, E: Event> Versioned<&'a E> {
+    pub fn new(payload: &'a E) -> Self {
+        Self {
+            payload_version: E::VERSION,
+            payload,
+        }
+    }
+}
+
+/// Schema of one event's payload, returned by `get_event_schema`
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSchema {
+    pub name: &'static str,
+    pub payload_version: u32,
+    pub schema: serde_json::Value,
+}
+
+impl EventSchema {
+    fn of<E: Event>() -> Self {
+        let schema = schemars::schema_for!(Versioned<E>);
+        Self {
+            name: E::NAME,
+            payload_version: E::VERSION,
+            schema: serde_json::to_value(schema).unwrap_or_default(),
+        }
+    }
+}
+
+/// Schemas of every event, by event name
+pub fn event_schemas() -> Vec<EventSchema> {
+    vec![
+        EventSchema::of::<InitialState>(),
+        EventSchema::of::<BackupProgress>(),
+        EventSchema::of::<CaptureFailure>(),
+        EventSchema::of::<TooLarge>(),
+        EventSchema::of::<ClipboardChangedPayload>(),
+        EventSchema::of::<ClipboardChangedBatchPayload>(),
+        EventSchema::of::<CycleCommittedPayload>(),
+        EventSchema::of::<CycleSelectionPayload>(),
+        EventSchema::of::<RecoveryReport>(),
+        EventSchema::of::<HistoryClearedPayload>(),
+        EventSchema::of::<ImportProgress>(),
+        EventSchema::of::<ItemDeletedPayload>(),
+        EventSchema::of::<ItemUpdatedPayload>(),
+        EventSchema::of::<ItemsUpdatedPayload>(),
+        EventSchema::of::<OpenItemPayload>(),
+        EventSchema::of::<OpenSettingsPayload>(),
+        EventSchema::of::<OverrideConsumedPayload>(),
+        EventSchema::of::<PermissionStatus>(),
+        EventSchema::of::<Profile>(),
+        EventSchema::of::<ReindexProgress>(),
+        EventSchema::of::<StorageErrorPayload>(),
+        EventSchema::of::<StorageLowPayload>(),
+        EventSchema::of::<StorageRelocatedPayload>(),
+        EventSchema::of::<ThumbnailUpdatedPayload>(),
+        EventSchema::of::<TypingProgress>(),
+    ]
+}
+
+/// Event payload for clipboard changes
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClipboardChangedPayload {
+    pub item: ClipboardItem,
+    /// If this item replaced an existing one (move to top), this contains the old item's ID
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_item_id: Option<String>,
+    /// Change-log sequence number after this capture was saved
+    /// The frontend compares it with its last known seq and calls
+    /// `get_changes_since` if anything in between was missed
+    pub seq: i64,
+}
+
+/// A saved capture waiting to be announced to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CapturedItem {
+    pub item: ClipboardItem,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_item_id: Option<String>,
+}
+
+/// Event payload for `clipboard-changed-batch`: captures saved during a burst
+/// Sent instead of one `clipboard-changed` event per item
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClipboardChangedBatchPayload {
+    /// Oldest first, so applying them in order leaves the newest on top
+    pub items: Vec<CapturedItem>,
+    /// Change-log sequence number after the whole burst was saved
+    pub seq: i64,
+}
+
+/// Event payload for `storage-error`: a capture was lost because its image
+/// could not be written
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StorageErrorPayload {
+    pub item_id: String,
+    pub error: ImageSaveError,
+}
+
+/// Event payload for `storage-relocated`: the images directory could not be
+/// written, so images are saved to `fallback_dir` until `repair_storage`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StorageRelocatedPayload {
+    pub images_dir: String,
+    pub fallback_dir: String,
+}
+
+/// Event payload for async thumbnail updates (e.g., OG image for links)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ThumbnailUpdatedPayload {
+    pub id: String,
+    pub thumbnail_base64: String,
+    /// Link preview made from the site icon
+    pub thumbnail_is_favicon: bool,
+}
+
+/// Event payload for `item-updated`: fields of an already emitted item that
+/// changed (its source app icon once extracted, its reclassified type, the
+/// metadata read from its audio file, or its text grown by a merged capture)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemUpdatedPayload {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_app_icon: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<ContentType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_metadata: Option<MediaMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview: Option<String>,
+}
+
+/// Event payload for `item-deleted`: an item deleted by the backend on its
+/// own (its expiry passed)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ItemDeletedPayload {
+    pub id: String,
+}
+
+/// Event payload for `items-updated`, sent once per bulk operation
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ItemsUpdatedPayload {
+    /// Items as they are after the operation
+    pub updated: Vec<ClipboardItem>,
+    /// IDs of deleted items
+    pub deleted: Vec<String>,
+}
+
+/// Event payload for the current cycle candidate
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CycleSelectionPayload {
+    pub item: ClipboardItem,
+    pub index: usize,
+    pub total: usize,
+}
+
+/// Event payload for `cycle-committed`: the cycled-to item was copied back
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CycleCommittedPayload {
+    pub item_id: String,
+}
+
+/// Event payload for `history-cleared`, sent by the automatic history clear
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryClearedPayload {
+    /// Number of items deleted
+    pub deleted: usize,
+}
+
+/// Event payload for `open-item`: a Spotlight result or recent items menu
+/// entry asks the panel to show an item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OpenItemPayload {
+    pub item_id: String,
+}
+
+/// Event payload for `open-settings` (tray menu)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OpenSettingsPayload {}
+
+/// Event payload for `override-consumed`: a capture used the one-shot
+/// override (see `capture_override`)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct OverrideConsumedPayload {}
+
+macro_rules! events {
+    ($($payload:ty => $name:expr),* $(,)?) => {
+        $(impl Event for $payload {
+            const NAME: &'static str = $name;
+        })*
+    };
+}
+
+events! {
+    InitialState => APP_READY,
+    BackupProgress => BACKUP_PROGRESS,
+    CaptureFailure => CAPTURE_FAILURE,
+    TooLarge => CAPTURE_SKIPPED_TOO_LARGE,
+    ClipboardChangedPayload => CLIPBOARD_CHANGED,
+    ClipboardChangedBatchPayload => CLIPBOARD_CHANGED_BATCH,
+    CycleCommittedPayload => CYCLE_COMMITTED,
+    CycleSelectionPayload => CYCLE_SELECTION_CHANGED,
+    RecoveryReport => DATABASE_RECOVERED,
+    HistoryClearedPayload => HISTORY_CLEARED,
+    ImportProgress => IMAGE_IMPORT_PROGRESS,
+    ItemDeletedPayload => ITEM_DELETED,
+    ItemUpdatedPayload => ITEM_UPDATED,
+    ItemsUpdatedPayload => ITEMS_UPDATED,
+    OpenItemPayload => OPEN_ITEM,
+    OpenSettingsPayload => OPEN_SETTINGS,
+    OverrideConsumedPayload => OVERRIDE_CONSUMED,
+    PermissionStatus => PERMISSIONS_CHANGED,
+    Profile => PROFILE_CHANGED,
+    ReindexProgress => REINDEX_PROGRESS,
+    StorageErrorPayload => STORAGE_ERROR,
+    StorageLowPayload => STORAGE_LOW,
+    StorageRelocatedPayload => STORAGE_RELOCATED,
+    ThumbnailUpdatedPayload => THUMBNAIL_UPDATED,
+    TypingProgress => TYPING_PROGRESS,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    fn wire<E: Event>(payload: &E) -> serde_json::Value {
+        serde_json::to_value(Versioned::new(payload)).unwrap()
+    }
+
+    #[test]
+    fn test_payloads_are_versioned_objects() {
+        let updated = ItemUpdatedPayload {
+            id: "a".to_string(),
+            language: Some("eng".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            wire(&updated),
+            json!({ "payload_version": 1, "id": "a", "language": "eng" })
+        );
+        let back: Versioned<ItemUpdatedPayload> = serde_json::from_value(wire(&updated)).unwrap();
+        assert_eq!(back.payload_version, 1);
+        assert_eq!(back.payload, updated);
+
+        assert_eq!(
+            wire(&OpenSettingsPayload {}),
+            json!({ "payload_version": 1 })
+        );
+        assert_eq!(
+            wire(&HistoryClearedPayload { deleted: 3 }),
+            json!({ "payload_version": 1, "deleted": 3 })
+        );
+        let error = StorageErrorPayload {
+            item_id: "a".to_string(),
+            error: ImageSaveError::DiskFull("No space left".to_string()),
+        };
+        assert_eq!(
+            wire(&error),
+            json!({
+                "payload_version": 1,
+                "item_id": "a",
+                "error": { "kind": "disk_full", "message": "No space left" }
+            })
+        );
+        let low = StorageLowPayload {
+            free_bytes: None,
+            min_free_bytes: 1024,
+        };
+        assert_eq!(
+            wire(&low),
+            json!({ "payload_version": 1, "free_bytes": null, "min_free_bytes": 1024 })
+        );
+    }
+
+    #[test]
+    fn test_clipboard_changed_round_trip() {
+        let item = ClipboardItem::new_text("hello".to_string(), Some("Notes".to_string()), None);
+        let payload = ClipboardChangedPayload {
+            item: item.clone(),
+            replaced_item_id: None,
+            seq: 42,
+        };
+        let value = wire(&payload);
+        assert_eq!(value["payload_version"], 1);
+        assert_eq!(value["seq"], 42);
+        assert_eq!(value["item"]["id"], item.id.as_str());
+        assert_eq!(value["item"]["content_type"], "text");
+        assert_eq!(value["item"]["preview"], "hello");
+        assert!(value.get("replaced_item_id").is_none());
+
+        let mut back: Versioned<ClipboardChangedPayload> =
+            serde_json::from_value(value.clone()).unwrap();
+        // The preview is derived, not read back
+        back.payload.item.refresh_preview();
+        assert_eq!(serde_json::to_value(&back).unwrap(), value);
+
+        let batch = ClipboardChangedBatchPayload {
+            items: vec![CapturedItem {
+                item,
+                replaced_item_id: Some("old".to_string()),
+            }],
+            seq: 43,
+        };
+        let value = wire(&batch);
+        assert_eq!(value["items"][0]["replaced_item_id"], "old");
+        let back: Versioned<ClipboardChangedBatchPayload> = serde_json::from_value(value).unwrap();
+        assert_eq!(back.payload.items.len(), 1);
+        assert_eq!(back.payload.seq, 43);
+    }
+
+    #[test]
+    fn test_every_event_has_a_schema() {
+        let schemas = event_schemas();
+        let names: HashSet<&str> = schemas.iter().map(|schema| schema.name).collect();
+        assert_eq!(names.len(), schemas.len());
+        assert_eq!(schemas.len(), 25);
+
+        for schema in &schemas {
+            let required = schema.schema["required"].as_array();
+            assert!(
+                required.is_some_and(|fields| fields.contains(&json!("payload_version"))),
+                "{}",
+                schema.name
+            );
+        }
+        let item_updated = schemas.iter().find(|schema| schema.name == ITEM_UPDATED);
+        let properties = &item_updated.unwrap().schema["properties"];
+        assert!(properties["content_type"].is_object());
+        assert!(properties.get("payload_version").is_some());
+    }
+}
@@ -0,0 +1,314 @@
+//! Owned GDI handles for icon extraction on Windows
+//!
+//! Turning a shell icon into pixels takes an icon, device contexts and
+//! bitmaps, each released by a different call, and every early return used to
+//! repeat the cleanup of whatever had been acquired so far. The wrappers here
+//! release their handle on drop, so a failed step can't leak one.
+
+use crate::clipboard::windows_icons;
+use image::RgbaImage;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Gdi::{
+    CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+    ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP, HDC,
+    HGDIOBJ,
+};
+use windows::Win32::Storage::FileSystem::FILE_ATTRIBUTE_NORMAL;
+use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
+use windows::Win32::UI::WindowsAndMessaging::{
+    DestroyIcon, DrawIconEx, GetIconInfo, DI_NORMAL, HICON, ICONINFO,
+};
+
+/// Shell icon, destroyed on drop
+pub struct Icon(HICON);
+
+impl Icon {
+    /// Large (32x32) shell icon of a file: an executable's own icon, or the
+    /// icon of the file's type
+    pub fn for_path(path: &OsStr) -> Option<Self> {
+        let wide_path: Vec<u16> = path.encode_wide().chain(std::iter::once(0)).collect();
+        let mut file_info = SHFILEINFOW::default();
+        let result = unsafe {
+            SHGetFileInfoW(
+                PCWSTR(wide_path.as_ptr()),
+                FILE_ATTRIBUTE_NORMAL,
+                Some(&mut file_info),
+                std::mem::size_of::<SHFILEINFOW>() as u32,
+                SHGFI_ICON | SHGFI_LARGEICON,
+            )
+        };
+
+        if result == 0 || file_info.hIcon.is_invalid() {
+            return None;
+        }
+        Some(Self(file_info.hIcon))
+    }
+}
+
+impl Drop for Icon {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DestroyIcon(self.0);
+        }
+    }
+}
+
+/// Device context of the screen, released on drop
+struct ScreenDc(HDC);
+
+impl ScreenDc {
+    fn get() -> Option<Self> {
+        let dc = unsafe { GetDC(HWND::default()) };
+        (!dc.is_invalid()).then_some(Self(dc))
+    }
+}
+
+impl Drop for ScreenDc {
+    fn drop(&mut self) {
+        unsafe {
+            ReleaseDC(HWND::default(), self.0);
+        }
+    }
+}
+
+/// Memory device context, deleted on drop
+struct MemoryDc(HDC);
+
+impl MemoryDc {
+    /// Compatible with `screen`, or with the screen itself when None
+    fn new(screen: Option<&ScreenDc>) -> Option<Self> {
+        let dc = unsafe { CreateCompatibleDC(screen.map(|screen| screen.0)) };
+        (!dc.is_invalid()).then_some(Self(dc))
+    }
+}
+
+impl Drop for MemoryDc {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeleteDC(self.0);
+        }
+    }
+}
+
+/// GDI bitmap, deleted on drop
+struct Bitmap(HBITMAP);
+
+impl Bitmap {
+    /// Bitmap owned by the caller, or None for the null handle
+    fn owned(bitmap: HBITMAP) -> Option<Self> {
+        (!bitmap.is_invalid()).then_some(Self(bitmap))
+    }
+}
+
+impl Drop for Bitmap {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = DeleteObject(HGDIOBJ::from(self.0));
+        }
+    }
+}
+
+/// A bitmap selected into a memory DC; the previous selection is restored on
+/// drop, before either of them can be deleted
+struct Selection<'a> {
+    dc: &'a MemoryDc,
+    previous: HGDIOBJ,
+}
+
+impl<'a> Selection<'a> {
+    fn new(dc: &'a MemoryDc, bitmap: &'a Bitmap) -> Self {
+        let previous = unsafe { SelectObject(dc.0, HGDIOBJ::from(bitmap.0)) };
+        Self { dc, previous }
+    }
+}
+
+impl Drop for Selection<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            SelectObject(self.dc.0, self.previous);
+        }
+    }
+}
+
+/// Draw `icon` scaled to `size` x `size`
+pub fn draw_icon(icon: &Icon, size: u32) -> Option<RgbaImage> {
+    let screen = ScreenDc::get()?;
+    let dc = MemoryDc::new(Some(&screen))?;
+    let bitmap =
+        Bitmap::owned(unsafe { CreateCompatibleBitmap(screen.0, size as i32, size as i32) })?;
+
+    {
+        let _selection = Selection::new(&dc, &bitmap);
+        unsafe {
+            DrawIconEx(
+                dc.0,
+                0,
+                0,
+                icon.0,
+                size as i32,
+                size as i32,
+                0,
+                None,
+                DI_NORMAL,
+            )
+        }
+        .ok()?;
+    }
+
+    // GetDIBits needs the bitmap deselected again
+    let mut pixels = read_pixels(&dc, &bitmap, size, size)?;
+    // DrawIconEx leaves alpha at 0 on some drivers (seen on ARM64)
+    windows_icons::restore_missing_alpha(&mut pixels);
+    Some(pixels)
+}
+
+/// Pixels of the icon at its own size: its color bitmap, or the mask of a
+/// monochrome icon
+pub fn icon_pixels(icon: &Icon) -> Option<RgbaImage> {
+    let mut icon_info = ICONINFO::default();
+    unsafe { GetIconInfo(icon.0, &mut icon_info) }.ok()?;
+    // GetIconInfo hands out copies of both bitmaps for the caller to delete
+    let color = Bitmap::owned(icon_info.hbmColor);
+    let mask = Bitmap::owned(icon_info.hbmMask);
+    let bitmap = color.as_ref().or(mask.as_ref())?;
+
+    let dc = MemoryDc::new(None)?;
+    let (width, height) = bitmap_size(&dc, bitmap)?;
+    read_pixels(&dc, bitmap, width, height)
+}
+
+/// Width and height of `bitmap`
+fn bitmap_size(dc: &MemoryDc, bitmap: &Bitmap) -> Option<(u32, u32)> {
+    let mut bmi = BITMAPINFO::default();
+    bmi.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+    // Without a buffer (and a zero bit count) only the header is filled in
+    if unsafe { GetDIBits(dc.0, bitmap.0, 0, 0, None, &mut bmi, DIB_RGB_COLORS) } == 0 {
+        return None;
+    }
+
+    let width = bmi.bmiHeader.biWidth.unsigned_abs();
+    let height = bmi.bmiHeader.biHeight.unsigned_abs();
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// Copy `bitmap` out as top-down RGBA
+fn read_pixels(dc: &MemoryDc, bitmap: &Bitmap, width: u32, height: u32) -> Option<RgbaImage> {
+    let mut bmi = BITMAPINFO::default();
+    bmi.bmiHeader.biSize = std::mem::size_of::<BITMAPINFOHEADER>() as u32;
+    bmi.bmiHeader.biWidth = width as i32;
+    bmi.bmiHeader.biHeight = -(height as i32); // Negative for top-down DIB
+    bmi.bmiHeader.biPlanes = 1;
+    bmi.bmiHeader.biBitCount = 32;
+    bmi.bmiHeader.biCompression = BI_RGB.0;
+
+    let stride = windows_icons::bgra_stride(width, bmi.bmiHeader.biBitCount)?;
+    let mut pixels = vec![0u8; stride * height as usize];
+    let scanlines = unsafe {
+        GetDIBits(
+            dc.0,
+            bitmap.0,
+            0,
+            height,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut bmi,
+            DIB_RGB_COLORS,
+        )
+    };
+    if scanlines == 0 {
+        return None;
+    }
+
+    // GetDIBits writes back the layout it used
+    let stride = windows_icons::bgra_stride(width, bmi.bmiHeader.biBitCount)?;
+    let top_down = bmi.bmiHeader.biHeight < 0;
+    windows_icons::bgra_to_rgba(&pixels, width, height, stride, top_down)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, GetGuiResources, GR_GDIOBJECTS, GR_USEROBJECTS,
+    };
+
+    /// Extractions per test; a leak of one handle each would stand out
+    const ROUNDS: usize = 200;
+
+    /// Headroom for handles the system creates lazily on first use
+    const TOLERANCE: u32 = 10;
+
+    /// GDI and USER (icon) handles held by this process
+    fn handle_counts() -> (u32, u32) {
+        unsafe {
+            let process = GetCurrentProcess();
+            (
+                GetGuiResources(process, GR_GDIOBJECTS),
+                GetGuiResources(process, GR_USEROBJECTS),
+            )
+        }
+    }
+
+    fn assert_no_leak(extract: impl Fn()) {
+        // Warm up once so lazily created handles are counted in the baseline
+        extract();
+        let (gdi_before, user_before) = handle_counts();
+        for _ in 0..ROUNDS {
+            extract();
+        }
+        let (gdi_after, user_after) = handle_counts();
+
+        assert!(
+            gdi_after <= gdi_before + TOLERANCE,
+            "GDI handles grew from {} to {}",
+            gdi_before,
+            gdi_after
+        );
+        assert!(
+            user_after <= user_before + TOLERANCE,
+            "USER handles grew from {} to {}",
+            user_before,
+            user_after
+        );
+    }
+
+    fn test_exe() -> std::path::PathBuf {
+        std::env::current_exe().unwrap()
+    }
+
+    #[test]
+    fn test_draw_icon_does_not_leak() {
+        let path = test_exe();
+        let icon = Icon::for_path(path.as_os_str()).unwrap();
+        let image = draw_icon(&icon, 32).unwrap();
+        assert_eq!(image.dimensions(), (32, 32));
+
+        assert_no_leak(|| {
+            let icon = Icon::for_path(path.as_os_str()).unwrap();
+            draw_icon(&icon, 32).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_icon_pixels_does_not_leak() {
+        let path = test_exe();
+        let icon = Icon::for_path(path.as_os_str()).unwrap();
+        let (width, height) = icon_pixels(&icon).unwrap().dimensions();
+        assert!(width > 0 && height > 0);
+
+        assert_no_leak(|| {
+            let icon = Icon::for_path(path.as_os_str()).unwrap();
+            icon_pixels(&icon).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_missing_file_has_no_icon() {
+        let missing = test_exe().with_file_name("missing-clipster-test.exe");
+        assert_no_leak(|| {
+            assert!(Icon::for_path(missing.as_os_str()).is_none());
+        });
+    }
+}
@@ -0,0 +1,285 @@
+//! Capped log output
+//!
+//! Capture tracing used to go straight to stderr, which grows without limit
+//! once launchd or NSSM redirects it to a file. `clipster_log!` writes to
+//! `~/.clipster/logs/clipster.log` instead, rotated at `MAX_LOG_BYTES` with at
+//! most `MAX_LOG_FILES` files kept (`clipster.log`, then `clipster.1.log` for
+//! the previous one up to `clipster.4.log`), and echoes to stderr only when
+//! it is a terminal. `Debug` lines, the per-capture tracing, are dropped
+//! unless the `debug_capture_logging` setting is on. Callers log previews from
+//! `preview`, never whole clipboard content.
+
+use crate::storage::{profiles, Database};
+use chrono::Local;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+/// Size at which the current log file is rotated
+pub const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Log files kept, the current one included
+pub const MAX_LOG_FILES: usize = 5;
+
+/// Log file name without its extension
+const LOG_NAME: &str = "clipster";
+
+/// Log file being written (None until `init`, or when it can't be opened)
+static LOG: Mutex<Option<RotatingLog>> = Mutex::new(None);
+
+/// `debug_capture_logging` setting
+static DEBUG_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Log a line at a `Level`: `clipster_log!(Debug, "read {} bytes", len)`
+macro_rules! clipster_log {
+    ($level:ident, $($arg:tt)+) => {
+        $crate::logging::log($crate::logging::Level::$level, format_args!($($arg)+))
+    };
+}
+
+/// Severity of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    /// Capture tracing, only written with `debug_capture_logging`
+    Debug,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        }
+    }
+}
+
+/// A log file rotated once it reaches `max_bytes`, keeping `max_files` files
+pub struct RotatingLog {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    /// Bytes in the current file
+    size: u64,
+}
+
+impl RotatingLog {
+    /// Open (or create) the current log file in `dir`, appending to it
+    pub fn open(dir: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut log = Self {
+            dir: dir.to_path_buf(),
+            max_bytes,
+            max_files: max_files.max(1),
+            file: None,
+            size: 0,
+        };
+        log.reopen()?;
+        Ok(log)
+    }
+
+    /// Path of a log file: 0 is the current one, 1 the one before it, and so on
+    pub fn path(&self, index: usize) -> PathBuf {
+        match index {
+            0 => self.dir.join(format!("{}.log", LOG_NAME)),
+            n => self.dir.join(format!("{}.{}.log", LOG_NAME, n)),
+        }
+    }
+
+    /// Append one line, rotating first when it would take the file over
+    /// `max_bytes`; a longer line is cut to fit an empty file
+    pub fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let line = cut_to_bytes(line, self.max_bytes.saturating_sub(1) as usize);
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        if self.file.is_none() {
+            self.reopen()?;
+        }
+        match self.file.as_mut() {
+            Some(file) => writeln!(file, "{}", line)?,
+            None => return Ok(()),
+        }
+        self.size += len;
+        Ok(())
+    }
+
+    /// Shift each file to the next index, dropping the oldest, and start a new one
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        let _ = fs::remove_file(self.path(self.max_files - 1));
+        for index in (0..self.max_files - 1).rev() {
+            let from = self.path(index);
+            if from.exists() {
+                fs::rename(&from, self.path(index + 1))?;
+            }
+        }
+        self.reopen()
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(0))?;
+        self.size = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+/// `text` cut to at most `max_bytes` bytes, on a character boundary
+fn cut_to_bytes(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Directory of the log files
+pub fn log_dir() -> Result<PathBuf, String> {
+    Ok(profiles::clipster_root()?.join("logs"))
+}
+
+/// Start writing to the log files; until then lines only go to a terminal
+pub fn init() {
+    let opened = log_dir().and_then(|dir| {
+        RotatingLog::open(&dir, MAX_LOG_BYTES, MAX_LOG_FILES)
+            .map_err(|e| format!("{}: {}", dir.display(), e))
+    });
+    match (opened, LOG.lock()) {
+        (Ok(log), Ok(mut current)) => *current = Some(log),
+        (Err(e), _) => eprintln!("[logging] Failed to open the log file {}", e),
+        (_, Err(e)) => eprintln!("[logging] {}", e),
+    }
+}
+
+/// Read `debug_capture_logging` (after launch, a change or a profile switch)
+pub fn apply_setting(db: &Database) {
+    let enabled = db
+        .get_setting("debug_capture_logging")
+        .ok()
+        .flatten()
+        .is_some_and(|value| value == "true");
+    DEBUG_CAPTURE.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether lines at `level` are written
+pub fn enabled(level: Level) -> bool {
+    level != Level::Debug || DEBUG_CAPTURE.load(Ordering::Relaxed)
+}
+
+/// Write one line; use `clipster_log!`
+pub fn log(level: Level, args: fmt::Arguments) {
+    if !enabled(level) {
+        return;
+    }
+    if io::stderr().is_terminal() {
+        eprintln!("{}", args);
+    }
+    let Ok(mut current) = LOG.lock() else {
+        return;
+    };
+    if let Some(log) = current.as_mut() {
+        let line = format!(
+            "{} {:<5} {}",
+            Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+            level.as_str(),
+            args
+        );
+        // Nowhere better to report a failing log file; the line is dropped
+        let _ = log.write_line(&line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_rotates_at_size_and_keeps_max_files() {
+        let dir = temp_dir("rotate");
+        // Each line takes 10 bytes with its newline: 3 per 32-byte file
+        let mut log = RotatingLog::open(&dir, 32, 3).unwrap();
+        for i in 0..10 {
+            log.write_line(&format!("line {:04}", i)).unwrap();
+        }
+
+        assert_eq!(read(&log.path(0)), "line 0009\n");
+        assert_eq!(read(&log.path(1)), "line 0006\nline 0007\nline 0008\n");
+        assert_eq!(read(&log.path(2)), "line 0003\nline 0004\nline 0005\n");
+        assert!(!log.path(3).exists());
+        for index in 0..3 {
+            assert!(fs::metadata(log.path(index)).unwrap().len() <= 32);
+        }
+    }
+
+    #[test]
+    fn test_reopen_appends_and_counts_existing_size() {
+        let dir = temp_dir("reopen");
+        let mut log = RotatingLog::open(&dir, 32, 2).unwrap();
+        log.write_line("line 0000").unwrap();
+        log.write_line("line 0001").unwrap();
+        drop(log);
+
+        // Restarted: the third line still fits, the fourth rotates
+        let mut log = RotatingLog::open(&dir, 32, 2).unwrap();
+        log.write_line("line 0002").unwrap();
+        assert!(!log.path(1).exists());
+        log.write_line("line 0003").unwrap();
+        assert_eq!(read(&log.path(0)), "line 0003\n");
+        assert_eq!(read(&log.path(1)), "line 0000\nline 0001\nline 0002\n");
+    }
+
+    #[test]
+    fn test_long_lines_are_cut_to_the_file_size() {
+        let dir = temp_dir("long");
+        let mut log = RotatingLog::open(&dir, 16, 2).unwrap();
+        log.write_line(&"é".repeat(40)).unwrap();
+        let written = read(&log.path(0));
+        assert!(written.len() <= 16);
+        assert_eq!(written, format!("{}\n", "é".repeat(7)));
+
+        // A single file just holds the latest lines
+        let mut single = RotatingLog::open(&dir.join("single"), 16, 1).unwrap();
+        for i in 0..5 {
+            single.write_line(&format!("line {}", i)).unwrap();
+        }
+        assert_eq!(read(&single.path(0)), "line 4\n");
+        assert!(!single.path(1).exists());
+    }
+
+    #[test]
+    fn test_debug_lines_follow_the_setting() {
+        let db = Database::new_in_memory().unwrap();
+        apply_setting(&db);
+        assert!(!enabled(Level::Debug));
+        assert!(enabled(Level::Info));
+
+        db.set_setting("debug_capture_logging", "true").unwrap();
+        apply_setting(&db);
+        assert!(enabled(Level::Debug));
+        clipster_log!(Debug, "[logging] test line {}", 1);
+        db.set_setting("debug_capture_logging", "false").unwrap();
+        apply_setting(&db);
+        assert!(!enabled(Level::Debug));
+    }
+}
@@ -1,40 +1,129 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+// First, so `clipster_log!` is in scope in every module
+#[macro_use]
+mod logging;
+
+mod auto_clear;
+mod cli;
 mod clipboard;
 mod commands;
+mod error;
+mod events;
+#[cfg(target_os = "windows")]
+mod gdi;
 mod models;
+mod panel_placement;
+mod paste_target;
+mod permissions;
+mod pinboard_palette;
+mod preview;
+mod quick_cycle;
+mod recent_items_os_menu;
+mod share_card;
+mod single_instance;
+mod spotlight;
+mod startup;
 mod storage;
+mod templates;
+#[cfg(test)]
+mod test_support;
+mod type_text;
 
+use clipboard::event_coalescer::SystemClock;
 use commands::clipboard_commands::{
-    assign_to_pinboard, clear_clipboard_history, copy_to_clipboard, create_drag_icon,
-    create_temp_link_file, create_temp_text_file, delete_clipboard_item, get_clipboard,
-    get_clipboard_count, get_clipboard_history, get_clipboard_item, get_image_data,
-    prepare_image_for_drag, search_clipboard, toggle_favorite,
+    archive_items_older_than, assign_to_pinboard, bulk_update_items, cancel_quick_cycle,
+    cancel_typing, clear_clipboard_history, copy_item_with_values, copy_to_clipboard,
+    create_drag_icon, create_temp_link_file, create_temp_text_file, delete_clipboard_item,
+    export_item_image, export_items_zip, find_duplicates, get_changes_since, get_clipboard,
+    get_clipboard_count, get_clipboard_history, get_clipboard_item, get_history_by_app,
+    get_image_data, get_item_audio_stream, get_item_image_info, get_item_placeholders,
+    get_item_revisions, get_items_by_color, get_thumbnails, merge_duplicate_group, open_path,
+    prepare_drag_payload, prepare_image_for_drag, reclassify_items, render_item_card,
+    restore_archived_item, restore_item_revision, reveal_path, search_archive, search_clipboard,
+    search_grouped, set_item_expiry, toggle_favorite, type_item, update_item_content,
+};
+use commands::health_commands::{
+    backup_now, cancel_reindex, get_capture_failures, get_counters, get_database_recovery,
+    get_event_schema, get_monitoring_status, get_stats, reindex_all, repair_storage,
+    run_health_check,
 };
 use commands::pinboard_commands::{
-    add_item_to_pinboard, create_pinboard, delete_pinboard, get_pinboard, get_pinboard_items,
-    get_pinboards, remove_item_from_pinboard, reorder_pinboards, update_pinboard,
+    add_file_to_pinboard, add_item_to_pinboard, archive_pinboard, cancel_image_import,
+    count_pinboard_items, create_pinboard, delete_pinboard, get_pinboard, get_pinboard_items,
+    get_pinboards, get_top_used, hide_pinboard_palette, import_images_to_pinboard,
+    lock_pinboard, palette_select, remove_item_from_pinboard, reorder_pinboards,
+    set_pinboard_parent, set_pinboard_passcode, set_pinboard_rules, show_pinboard_palette,
+    unarchive_pinboard, unlock_pinboard, update_pinboard, validate_pinboard_rules,
 };
+use commands::profile_commands::{create_profile, list_profiles, switch_profile};
 use commands::settings_commands::{
-    get_history_limit, get_settings, set_history_limit, set_menu_bar_icon_visible, update_setting,
+    allow_next_capture, check_permissions, export_settings, get_app_default_pinboards,
+    get_app_rate_limits, get_history_limit, get_initial_state, get_pinboard_shortcuts,
+    get_settings, get_view_state, import_settings, reindex_spotlight, request_permission,
+    reset_settings_to_defaults, set_app_default_pinboards, set_app_rate_limits,
+    set_auto_clear_schedule, set_capture_paused, set_history_limit, set_menu_bar_icon_visible,
+    set_pinboard_shortcuts, set_recent_items_os_menu, set_spotlight_indexing, set_view_state,
+    update_setting,
 };
 use commands::window_commands::{
-    hide_panel, hide_window, quit_app, reposition_to_cursor_monitor, setup_window_behavior,
-    show_panel, show_window,
+    focus_panel, focus_paste_target, get_panel_placement, get_paste_target, hide_item_preview,
+    hide_panel, hide_window, quit_app, reposition_to_cursor_monitor, set_panel_placement,
+    setup_window_behavior, show_item_preview, show_panel, show_window,
+};
+use paste_target::PasteTarget;
+use pinboard_palette::PinboardPalette;
+use quick_cycle::CycleState;
+use recent_items_os_menu::RecentItemsMenu;
+use spotlight::SpotlightIndexer;
+use startup::deferred::{self, DeferredTask};
+use startup::timings::PhaseTimer;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use single_instance::instance_lock::{self, Launch};
+use storage::drag_payload::{self, CleanupScheduler, DRAG_PAYLOAD_TTL};
+use storage::recovery::RecoveryReport;
+use storage::{
+    backup, pending_spool, profiles, Database, DestructiveGuard, DragPayloads, FileStorage,
+    PinboardLocks,
 };
-use std::sync::Arc;
-use storage::Database;
 use tauri::menu::{Menu, MenuItem};
 use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 use tauri_plugin_global_shortcut::{Code, GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
-use clipboard::clipboard_monitor;
 use tauri_plugin_autostart::MacosLauncher;
+use tauri_plugin_deep_link::DeepLinkExt;
 
 /// Application state holding the database connection
 pub struct AppState {
     pub db: Arc<Database>,
+    /// Session-only unlock state of passcode-protected pinboards
+    pub pinboard_locks: PinboardLocks,
+    /// Confirmation tokens of clear-history and delete-pinboard calls
+    pub destructive_guard: DestructiveGuard,
+    /// Hold-hotkey history cycle state
+    pub quick_cycle: Mutex<CycleState>,
+    /// Per-pinboard palette popup and its shortcuts
+    pub pinboard_palette: PinboardPalette,
+    /// Background Spotlight indexing worker (no-op outside macOS)
+    pub spotlight: SpotlightIndexer,
+    /// Recent items in the Windows jump list / macOS Dock menu (no-op elsewhere)
+    pub recent_items: RecentItemsMenu,
+    /// Set when a corrupt database was replaced at startup
+    pub database_recovery: Option<RecoveryReport>,
+    /// Window that had focus when the panel was last shown
+    pub paste_target: Mutex<Option<PasteTarget>>,
+    /// Image and spilled text storage of the active profile, shared with the
+    /// clipboard monitor so a repair resets the fallback directory it saves to
+    pub file_storage: Mutex<Arc<FileStorage>>,
+    /// When the drag payloads prepared so far are cleaned up
+    pub drag_cleanup: Arc<Mutex<CleanupScheduler>>,
+}
+
+/// Global shortcut toggling the panel (Ctrl+Shift+V)
+pub fn panel_shortcut() -> Shortcut {
+    Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyV)
 }
 
 /// Toggle window visibility - show if hidden, hide if visible
@@ -51,51 +140,240 @@ fn toggle_window_visibility(app: &tauri::AppHandle) {
     }
 }
 
+/// Show the panel and ask the frontend to open an item (from a deep link)
+fn open_item_from_link(app: &tauri::AppHandle, item_id: String) {
+    if let Some(window) = app.get_webview_window("main") {
+        reposition_to_cursor_monitor(&window);
+        show_panel(&window);
+        events::emit(app, &events::OpenItemPayload { item_id });
+    }
+}
+
+/// Startup work capture doesn't need, run after `app-ready`
+/// Files last written before `launched_at` are left over from the last run
+fn deferred_tasks(app: &tauri::AppHandle, launched_at: SystemTime) -> Vec<DeferredTask> {
+    let mut tasks = Vec::new();
+
+    // First, so the rebuild comes before any capture's sync
+    let reindex_app = app.clone();
+    tasks.push(DeferredTask::new("spotlight_reindex", move || {
+        reindex_app.state::<AppState>().spotlight.reindex();
+        Ok(())
+    }));
+
+    // Image files left half-written by a crash
+    let app = app.clone();
+    tasks.push(DeferredTask::new("temp_files", move || {
+        let file_storage = app.state::<AppState>().file_storage.lock()?.clone();
+        let removed = file_storage.cleanup_temp_files(launched_at)?;
+        if removed > 0 {
+            println!("Removed {} incomplete image files", removed);
+        }
+        Ok(())
+    }));
+
+    // Drag payloads left from the last run
+    tasks.push(DeferredTask::new("drag_payloads", move || {
+        let removed = DragPayloads::new()
+            .cleanup_expired(DRAG_PAYLOAD_TTL, SystemTime::now())
+            .map_err(error::ClipsterError::Io)?;
+        if removed > 0 {
+            println!("Removed {} expired drag payloads", removed);
+        }
+        Ok(())
+    }));
+
+    // The Quick Look working directory of the last run
+    #[cfg(target_os = "macos")]
+    tasks.push(DeferredTask::new("quicklook_dir", move || {
+        use storage::quicklook;
+        let dir = quicklook::work_dir().map_err(error::ClipsterError::Io)?;
+        quicklook::reset_work_dir(&dir, launched_at).map_err(error::ClipsterError::Io)
+    }));
+
+    tasks
+}
+
 fn main() {
-    // Initialize database
-    let db = Database::new().expect("Failed to initialize database");
+    let launched_at = SystemTime::now();
+    let mut timer = PhaseTimer::start();
+
+    // `clipster get <id>` prints an item and exits without starting the app
+    let argv: Vec<String> = std::env::args().collect();
+    if cli::is_cli(&argv) {
+        std::process::exit(cli::main(&argv));
+    }
+
+    // Only one instance may run; later launches hand their argv over and exit
+    let instance_lock = match profiles::clipster_root()
+        .map_err(std::io::Error::other)
+        .and_then(|root| instance_lock::acquire(&root))
+    {
+        Ok(Launch::Primary(lock)) => Some(lock),
+        Ok(Launch::Secondary(running)) => {
+            if let Err(e) = running.hand_off(&argv) {
+                eprintln!("Clipster is already running but did not respond: {}", e);
+            }
+            return;
+        }
+        Err(e) => {
+            eprintln!("Failed to take the instance lock, starting anyway: {}", e);
+            None
+        }
+    };
+
+    // Logs go to ~/.clipster/logs, capped and rotated, rather than stderr
+    logging::init();
+
+    // Select the active profile, then open its database
+    if let Err(e) = profiles::init() {
+        eprintln!("Failed to load profiles, using default: {}", e);
+    }
+    timer.lap("init");
+
+    // A corrupt database is moved aside and replaced with what can be salvaged
+    let data_dir = profiles::active_data_dir().expect("Failed to locate data directory");
+    let (db, database_recovery) =
+        Database::open_or_recover(&data_dir).expect("Failed to initialize database");
     let db = Arc::new(db);
+    logging::apply_setting(&db);
+    let file_storage = Arc::new(FileStorage::new().expect("Failed to initialize file storage"));
+    file_storage.apply_settings(&db);
+    let drag_cleanup = Arc::new(Mutex::new(CleanupScheduler::new(SystemClock)));
+    timer.lap("open_database");
+
+    // Insert captures spooled while the database was locked during the last run
+    let report = pending_spool::replay(&db, &pending_spool::pending_dir(&data_dir));
+    if report != pending_spool::ReplayReport::default() {
+        println!(
+            "Replayed {} pending captures ({} dropped, {} still pending)",
+            report.replayed, report.dropped, report.remaining
+        );
+    }
+
+    // Items that expired while the app was closed are gone before the panel loads
+    match db.delete_expired_items(chrono::Utc::now()) {
+        Ok(expired) if expired.is_empty() => {}
+        Ok(expired) => println!("Deleted {} expired items", expired.len()),
+        Err(e) => eprintln!("Failed to delete expired items: {}", e),
+    }
+    timer.lap("restore_items");
 
     // Define the global shortcut
-    let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyV);
+    let shortcut = panel_shortcut();
+    let cycle_shortcut = quick_cycle::configured_shortcut(&db);
 
     tauri::Builder::default()
         .plugin(tauri_plugin_drag::init())
         .plugin(tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, None))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(move |app, shortcut_pressed, event| {
                     if shortcut_pressed == &shortcut && event.state == ShortcutState::Pressed {
                         toggle_window_visibility(app);
+                    } else if shortcut_pressed == &cycle_shortcut {
+                        quick_cycle::handle_shortcut(app, shortcut_pressed, event.state);
+                    } else if event.state == ShortcutState::Pressed {
+                        if let Some(pinboard_id) =
+                            pinboard_palette::pinboard_for_shortcut(app, shortcut_pressed)
+                        {
+                            if let Err(e) = pinboard_palette::show(app, &pinboard_id) {
+                                eprintln!("Failed to open pinboard palette: {}", e);
+                            }
+                        }
                     }
                 })
                 .build(),
         )
-        .manage(AppState { db: db.clone() })
+        .manage(AppState {
+            db: db.clone(),
+            pinboard_locks: PinboardLocks::new(),
+            destructive_guard: DestructiveGuard::new(),
+            quick_cycle: Mutex::new(CycleState::new()),
+            pinboard_palette: PinboardPalette::new(),
+            spotlight: SpotlightIndexer::start(db.clone()),
+            recent_items: RecentItemsMenu::start(db.clone()),
+            database_recovery: database_recovery.clone(),
+            paste_target: Mutex::new(None),
+            file_storage: Mutex::new(file_storage.clone()),
+            drag_cleanup: drag_cleanup.clone(),
+        })
         .setup(move |app| {
-            // Start clipboard monitoring
-            let app_handle = app.handle().clone();
-            if let Err(e) = clipboard_monitor::start_monitoring(app_handle.clone(), db.clone()) {
-                eprintln!("Failed to start clipboard monitor: {}", e);
-            } else {
-                println!("Clipboard monitor started successfully");
+            timer.lap("plugins");
+
+            // Status the first permissions-changed is compared with
+            permissions::recheck();
+
+            if let Some(report) = &database_recovery {
+                eprintln!(
+                    "Recovered {} items and {} pinboards; corrupt database kept at {}",
+                    report.items_recovered, report.pinboards_recovered, report.corrupt_path
+                );
+                events::emit(app.handle(), report);
+            }
+
+            if let Some(lock) = instance_lock {
+                single_instance::serve(lock, app.handle());
             }
+            single_instance::handle_first_launch(app.handle());
+
+            // Scheduled history clearing (daily_at); on_quit runs from the exit hook
+            auto_clear::start(app.handle().clone());
+
+            // Scheduled database backups (backup_interval_hours)
+            backup::start_scheduler(db.clone());
+
+            // Drag payloads, an hour after they were prepared
+            drag_payload::start_cleanup(drag_cleanup.clone());
 
             // Register global shortcut (Ctrl+Shift+V)
-            let shortcut = Shortcut::new(Some(Modifiers::CONTROL | Modifiers::SHIFT), Code::KeyV);
             app.global_shortcut().register(shortcut)?;
             println!("Global shortcut Ctrl+Shift+V registered");
 
+            // Register the hold-hotkey history cycle shortcut
+            if let Err(e) = app.global_shortcut().register(cycle_shortcut) {
+                eprintln!("Failed to register quick cycle shortcut: {}", e);
+            }
+
+            // Register the per-pinboard palette shortcuts
+            pinboard_palette::register_configured_shortcuts(
+                app.handle(),
+                &[shortcut, cycle_shortcut],
+            );
+
+            // Dock menu entries for recent items (shown while there is a Dock icon)
+            #[cfg(target_os = "macos")]
+            recent_items_os_menu::install_dock_menu();
+
+            // Spotlight results, jump list tasks and Dock menu entries open items
+            // through clipster://item/<id>
+            let app_handle_for_links = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    if let Some(item_id) = spotlight::parse_deep_link(url.as_str()) {
+                        open_item_from_link(&app_handle_for_links, item_id);
+                    }
+                }
+            });
+
             // Create system tray menu
             let show_hide = MenuItem::with_id(app, "show_hide", "Show/Hide", true, None::<&str>)?;
+            let allow_capture = MenuItem::with_id(
+                app,
+                "allow_next_capture",
+                "Allow Next Capture",
+                true,
+                None::<&str>,
+            )?;
             let settings = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
 
-            let menu = Menu::with_items(app, &[&show_hide, &settings, &quit])?;
+            let menu = Menu::with_items(app, &[&show_hide, &allow_capture, &settings, &quit])?;
 
             // Create tray icon
             let app_handle_for_tray = app.handle().clone();
-            let tray = TrayIconBuilder::with_id("main-tray")
+            TrayIconBuilder::with_id("main-tray")
                 .icon(app.default_window_icon().unwrap().clone())
                 .menu(&menu)
                 .tooltip("Clipster - Clipboard Manager")
@@ -114,12 +392,15 @@ fn main() {
                     "show_hide" => {
                         toggle_window_visibility(app);
                     }
+                    "allow_next_capture" => {
+                        clipboard::clipboard_monitor::allow_next_capture();
+                    }
                     "settings" => {
                         println!("Settings clicked");
                         if let Some(window) = app.get_webview_window("main") {
                             reposition_to_cursor_monitor(&window);
                             show_panel(&window);
-                            let _ = window.emit("open-settings", ());
+                            events::emit(app, &events::OpenSettingsPayload {});
                         }
                     }
                     "quit" => {
@@ -130,14 +411,6 @@ fn main() {
                 })
                 .build(app)?;
 
-            // Hide tray icon if setting is false
-            let show_tray = db.get_setting("show_menu_bar_icon")
-                .unwrap_or(None)
-                .unwrap_or_else(|| "true".to_string());
-            if show_tray != "true" {
-                let _ = tray.set_visible(false);
-            }
-
             println!("System tray created");
 
             // Configure window for Accessory-app overlay behaviour
@@ -155,51 +428,169 @@ fn main() {
                     println!("Applied vibrancy effect");
                 }
 
-                // Show the panel via native APIs (no Space switch)
-                show_panel(&window);
+                // Acrylic blur behind the transparent window on Windows
+                #[cfg(target_os = "windows")]
+                {
+                    use window_vibrancy::apply_acrylic;
+                    if let Err(e) = apply_acrylic(&window, Some((28, 28, 32, 160))) {
+                        eprintln!("Failed to apply acrylic effect: {}", e);
+                    }
+                }
             }
 
+            timer.lap("window_and_tray");
+
+            // Start capture, apply tray visibility and show the panel as saved
+            startup::apply(app.handle(), db.clone(), file_storage.clone());
+            timer.lap("app_ready");
+            timer.total("total");
+
+            deferred::spawn(deferred_tasks(app.handle(), launched_at));
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             // Clipboard commands
             get_clipboard,
             get_clipboard_history,
+            get_history_by_app,
             get_clipboard_item,
+            get_changes_since,
+            update_item_content,
+            get_item_revisions,
+            restore_item_revision,
             delete_clipboard_item,
             search_clipboard,
+            search_grouped,
+            get_items_by_color,
+            archive_items_older_than,
+            search_archive,
+            restore_archived_item,
             clear_clipboard_history,
+            find_duplicates,
+            merge_duplicate_group,
+            reclassify_items,
             copy_to_clipboard,
+            get_item_placeholders,
+            copy_item_with_values,
+            type_item,
+            cancel_typing,
+            open_path,
+            reveal_path,
             toggle_favorite,
+            set_item_expiry,
             assign_to_pinboard,
+            bulk_update_items,
             get_clipboard_count,
             get_image_data,
+            get_item_image_info,
+            get_item_audio_stream,
+            get_thumbnails,
+            export_item_image,
+            render_item_card,
+            export_items_zip,
             prepare_image_for_drag,
+            prepare_drag_payload,
             create_temp_text_file,
             create_temp_link_file,
             create_drag_icon,
+            cancel_quick_cycle,
             // Pinboard commands
             get_pinboards,
             get_pinboard,
             create_pinboard,
             update_pinboard,
             delete_pinboard,
+            archive_pinboard,
+            unarchive_pinboard,
+            set_pinboard_passcode,
+            unlock_pinboard,
+            lock_pinboard,
             reorder_pinboards,
             get_pinboard_items,
+            get_top_used,
+            count_pinboard_items,
             add_item_to_pinboard,
+            add_file_to_pinboard,
             remove_item_from_pinboard,
+            set_pinboard_rules,
+            set_pinboard_parent,
+            validate_pinboard_rules,
+            import_images_to_pinboard,
+            cancel_image_import,
+            show_pinboard_palette,
+            palette_select,
+            hide_pinboard_palette,
             // Settings commands
             get_settings,
             update_setting,
             get_history_limit,
             set_history_limit,
             set_menu_bar_icon_visible,
+            set_capture_paused,
+            allow_next_capture,
+            get_initial_state,
+            check_permissions,
+            request_permission,
+            get_view_state,
+            set_view_state,
+            get_app_default_pinboards,
+            set_app_default_pinboards,
+            get_pinboard_shortcuts,
+            set_pinboard_shortcuts,
+            get_app_rate_limits,
+            set_app_rate_limits,
+            set_spotlight_indexing,
+            set_recent_items_os_menu,
+            reindex_spotlight,
+            set_auto_clear_schedule,
+            export_settings,
+            import_settings,
+            reset_settings_to_defaults,
+            // Health commands
+            run_health_check,
+            get_monitoring_status,
+            repair_storage,
+            get_capture_failures,
+            get_event_schema,
+            get_database_recovery,
+            get_stats,
+            get_counters,
+            backup_now,
+            reindex_all,
+            cancel_reindex,
+            // Profile commands
+            list_profiles,
+            create_profile,
+            switch_profile,
             // Window commands
             hide_window,
             show_window,
+            focus_panel,
+            get_paste_target,
+            focus_paste_target,
+            get_panel_placement,
+            set_panel_placement,
+            show_item_preview,
+            hide_item_preview,
             quit_app,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        // Permissions are granted in System Settings while the app is in the
+        // background; tell the UI once it is back
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::Focused(true) = event {
+                if let Some(status) = permissions::recheck() {
+                    events::emit(window.app_handle(), &status);
+                }
+            }
+        })
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Every quit path (tray, quit_app, Cmd+Q) ends here
+            if let tauri::RunEvent::Exit = event {
+                auto_clear::on_quit(app);
+            }
+        });
 }
 
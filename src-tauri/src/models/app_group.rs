@@ -0,0 +1,25 @@
+use super::ClipboardItem;
+use serde::Serialize;
+
+/// Name shown for items captured without a source app
+pub const UNKNOWN_APP: &str = "Unknown";
+
+/// History items captured from one source app
+#[derive(Debug, Clone, Serialize)]
+pub struct AppGroup {
+    /// Source app name, or `UNKNOWN_APP`
+    pub name: String,
+
+    /// False for the group of items without a source app
+    pub is_known: bool,
+
+    /// Icon (base64 PNG) of the newest item that has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+
+    /// History items from this app, including those not in `items`
+    pub total_count: usize,
+
+    /// Most recent items, newest first
+    pub items: Vec<ClipboardItem>,
+}
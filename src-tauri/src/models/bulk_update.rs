@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+/// Operation applied to every item of a multi-selection by `bulk_update_items`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkOp {
+    MoveToPinboard { pinboard_id: String },
+    RemoveFromPinboard,
+    Favorite { favorite: bool },
+    Delete,
+}
+
+/// Outcome of a bulk operation for one item
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BulkItemResult {
+    pub id: String,
+    pub ok: bool,
+    /// Why the item was skipped (e.g. it no longer exists)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BulkItemResult {
+    pub fn ok(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ok: true,
+            error: None,
+        }
+    }
+
+    pub fn failed(id: &str, error: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            ok: false,
+            error: Some(error.to_string()),
+        }
+    }
+}
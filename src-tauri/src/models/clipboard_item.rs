@@ -1,10 +1,19 @@
+use crate::models::MediaMetadata;
+use crate::preview::{self, PREVIEW_MAX_CHARS};
 use chrono::{DateTime, Utc};
 use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
 use rusqlite::Row;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+/// Longest window title stored with an item, in characters
+pub const MAX_WINDOW_TITLE_CHARS: usize = 200;
+
+/// `source_pasteboard` of items captured from the macOS find pasteboard (Cmd+F text)
+pub const FIND_PASTEBOARD: &str = "find";
+
 /// Content type for clipboard items
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ContentType {
     Text,
@@ -39,6 +48,22 @@ impl ContentType {
         }
     }
 
+    /// Type the current detectors give stored content of this type
+    /// Text and links are checked as text, file lists (a JSON array of paths)
+    /// by their files; images and unparseable file lists keep their type
+    pub fn redetect(self, content: &str) -> Self {
+        match self {
+            ContentType::Text | ContentType::Link => Self::detect_from_text(content),
+            ContentType::Files | ContentType::Audio | ContentType::Documents => {
+                match serde_json::from_str::<Vec<String>>(content) {
+                    Ok(paths) if !paths.is_empty() => Self::detect_from_files(&paths),
+                    _ => self,
+                }
+            }
+            ContentType::Image => self,
+        }
+    }
+
     /// Check if text content looks like a URL
     pub fn detect_from_text(text: &str) -> Self {
         let trimmed = text.trim();
@@ -121,8 +146,36 @@ impl FromSql for ContentType {
     }
 }
 
+/// Structured text recognized inside a Text item
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentSubtype {
+    /// vCard (Contacts.app, Outlook)
+    Contact,
+    /// iCalendar event (Calendar, .ics text)
+    Event,
+}
+
+impl ContentSubtype {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentSubtype::Contact => "contact",
+            ContentSubtype::Event => "event",
+        }
+    }
+
+    /// Subtype stored in the database; unknown values are ignored
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "contact" => Some(ContentSubtype::Contact),
+            "event" => Some(ContentSubtype::Event),
+            _ => None,
+        }
+    }
+}
+
 /// Represents a clipboard history item
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ClipboardItem {
     /// Unique identifier (UUID v4)
     pub id: String,
@@ -138,7 +191,13 @@ pub struct ClipboardItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thumbnail_base64: Option<String>,
 
+    /// The Link thumbnail is the site's icon on a plain card (no OG image)
+    #[serde(default)]
+    pub thumbnail_is_favicon: bool,
+
     /// Original image data path (for images, stored externally)
+    /// Always absolute once loaded; the database stores images of the images
+    /// directory by file name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub image_path: Option<String>,
 
@@ -150,9 +209,29 @@ pub struct ClipboardItem {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source_app_icon: Option<String>,
 
-    /// Timestamp when item was captured
+    /// Title of the focused window at capture time (`capture_window_titles` setting)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_window_title: Option<String>,
+
+    /// Generated display title (e.g. "Screenshot 2024-05-12 14:03"), searchable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// A contact or event payload kept as text (see `contact_event`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_subtype: Option<ContentSubtype>,
+
+    /// Pasteboard the item was captured from when not the general one
+    /// (`FIND_PASTEBOARD` with the `capture_find_pasteboard` setting)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_pasteboard: Option<String>,
+
+    /// Timestamp when item was captured (last copy: move-to-top sets a new one)
     pub created_at: DateTime<Utc>,
 
+    /// Timestamp when this content was first copied, kept across move-to-top
+    pub first_copied_at: DateTime<Utc>,
+
     /// Associated pinboard ID (if pinned)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pinboard_id: Option<String>,
@@ -160,40 +239,146 @@ pub struct ClipboardItem {
     /// Whether this item is favorited/starred
     #[serde(default)]
     pub is_favorite: bool,
+
+    /// Pinboard the item was auto-filed into (per-app default) while still in history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_pinboard_id: Option<String>,
+
+    /// Full text file for large text items (content_text then holds a preview)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_path: Option<String>,
+
+    /// Canonical form of a Link's URL, used as its dedupe key
+    #[serde(skip)]
+    pub canonical_url: Option<String>,
+
+    /// Where the files of a multi-file copy are and what they are
+    /// ("40 files from ~/Photos/Trip — 38 jpg, 2 mov"), or what a copied
+    /// folder holds ("Folder Trip — 124 items, 3.2 MB")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_summary: Option<String>,
+
+    /// A file copy of a single folder
+    #[serde(default)]
+    pub is_directory: bool,
+
+    /// Pixel size of an image as copied, set when the stored image is smaller
+    /// (`max_image_dimension` setting) or the limit couldn't be applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_height: Option<u32>,
+
+    /// The image is over `max_image_dimension` but was stored as copied
+    /// because it couldn't be decoded
+    #[serde(default)]
+    pub downscale_skipped: bool,
+
+    /// Times the item was copied back to the clipboard
+    #[serde(default)]
+    pub use_count: u32,
+
+    /// Duration and tags of a single audio file, read in the background
+    /// after capture
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_metadata: Option<MediaMetadata>,
+
+    /// When the item deletes itself (`set_item_expiry`); None keeps it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// ISO 639-3 code of the language of a text item, detected in the
+    /// background after capture (see `clipboard::language`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+
+    /// Dominant colors of an image item (`#rrggbb`, most common first),
+    /// extracted in the background after capture (see `clipboard::palette`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Vec<String>>,
+
+    /// One-line preview for lists (see `preview`), kept current by
+    /// `refresh_preview`
+    #[serde(skip_deserializing)]
+    pub preview: String,
 }
 
 impl ClipboardItem {
     /// Create a new text clipboard item (auto-detects if it's a URL)
     pub fn new_text(text: String, source_app: Option<String>, source_app_icon: Option<String>) -> Self {
         let content_type = ContentType::detect_from_text(&text);
+        let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             content_type,
             content_text: Some(text),
             thumbnail_base64: None,
+            thumbnail_is_favicon: false,
+            files_summary: None,
+            is_directory: false,
+            original_width: None,
+            original_height: None,
+            downscale_skipped: false,
+            use_count: 0,
+            media_metadata: None,
+            expires_at: None,
+            language: None,
+            palette: None,
+            preview: String::new(),
             image_path: None,
             source_app,
             source_app_icon,
-            created_at: Utc::now(),
+            source_window_title: None,
+            title: None,
+            content_subtype: None,
+            source_pasteboard: None,
+            created_at: now,
+            first_copied_at: now,
             pinboard_id: None,
             is_favorite: false,
+            auto_pinboard_id: None,
+            text_path: None,
+            canonical_url: None,
         }
+        .with_preview()
     }
 
     /// Create a new link clipboard item
     pub fn new_link(url: String, source_app: Option<String>, source_app_icon: Option<String>) -> Self {
+        let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             content_type: ContentType::Link,
             content_text: Some(url),
             thumbnail_base64: None,
+            thumbnail_is_favicon: false,
+            files_summary: None,
+            is_directory: false,
+            original_width: None,
+            original_height: None,
+            downscale_skipped: false,
+            use_count: 0,
+            media_metadata: None,
+            expires_at: None,
+            language: None,
+            palette: None,
+            preview: String::new(),
             image_path: None,
             source_app,
             source_app_icon,
-            created_at: Utc::now(),
+            source_window_title: None,
+            title: None,
+            content_subtype: None,
+            source_pasteboard: None,
+            created_at: now,
+            first_copied_at: now,
             pinboard_id: None,
             is_favorite: false,
+            auto_pinboard_id: None,
+            text_path: None,
+            canonical_url: None,
         }
+        .with_preview()
     }
 
     /// Create a new image clipboard item
@@ -203,18 +388,40 @@ impl ClipboardItem {
         source_app: Option<String>,
         source_app_icon: Option<String>,
     ) -> Self {
+        let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             content_type: ContentType::Image,
             content_text: None,
             thumbnail_base64,
+            thumbnail_is_favicon: false,
+            files_summary: None,
+            is_directory: false,
+            original_width: None,
+            original_height: None,
+            downscale_skipped: false,
+            use_count: 0,
+            media_metadata: None,
+            expires_at: None,
+            language: None,
+            palette: None,
+            preview: String::new(),
             image_path: Some(image_path),
             source_app,
             source_app_icon,
-            created_at: Utc::now(),
+            source_window_title: None,
+            title: None,
+            content_subtype: None,
+            source_pasteboard: None,
+            created_at: now,
+            first_copied_at: now,
             pinboard_id: None,
             is_favorite: false,
+            auto_pinboard_id: None,
+            text_path: None,
+            canonical_url: None,
         }
+        .with_preview()
     }
 
     /// Create a new files clipboard item (auto-detects if all files are audio)
@@ -231,35 +438,79 @@ impl ClipboardItem {
     ) -> Self {
         let content_type = ContentType::detect_from_files(&file_paths);
         let paths_json = serde_json::to_string(&file_paths).unwrap_or_default();
+        let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             content_type,
             content_text: Some(paths_json),
             thumbnail_base64,
+            thumbnail_is_favicon: false,
+            files_summary: None,
+            is_directory: false,
+            original_width: None,
+            original_height: None,
+            downscale_skipped: false,
+            use_count: 0,
+            media_metadata: None,
+            expires_at: None,
+            language: None,
+            palette: None,
+            preview: String::new(),
             image_path: None,
             source_app,
             source_app_icon,
-            created_at: Utc::now(),
+            source_window_title: None,
+            title: None,
+            content_subtype: None,
+            source_pasteboard: None,
+            created_at: now,
+            first_copied_at: now,
             pinboard_id: None,
             is_favorite: false,
+            auto_pinboard_id: None,
+            text_path: None,
+            canonical_url: None,
         }
+        .with_preview()
     }
 
     /// Create a new audio files clipboard item
     pub fn new_audio(file_paths: Vec<String>, source_app: Option<String>, source_app_icon: Option<String>) -> Self {
         let paths_json = serde_json::to_string(&file_paths).unwrap_or_default();
+        let now = Utc::now();
         Self {
             id: uuid::Uuid::new_v4().to_string(),
             content_type: ContentType::Audio,
             content_text: Some(paths_json),
             thumbnail_base64: None,
+            thumbnail_is_favicon: false,
+            files_summary: None,
+            is_directory: false,
+            original_width: None,
+            original_height: None,
+            downscale_skipped: false,
+            use_count: 0,
+            media_metadata: None,
+            expires_at: None,
+            language: None,
+            palette: None,
+            preview: String::new(),
             image_path: None,
             source_app,
             source_app_icon,
-            created_at: Utc::now(),
+            source_window_title: None,
+            title: None,
+            content_subtype: None,
+            source_pasteboard: None,
+            created_at: now,
+            first_copied_at: now,
             pinboard_id: None,
             is_favorite: false,
+            auto_pinboard_id: None,
+            text_path: None,
+            canonical_url: None,
         }
+        .with_preview()
     }
 
     /// Get file paths for Files, Audio, or Documents type items
@@ -272,103 +523,96 @@ impl ClipboardItem {
             .and_then(|json| serde_json::from_str(json).ok())
     }
 
+    /// Pinboard the item belongs to: the one it was pinned to, else the one it
+    /// was auto-filed into, the way pinboard listings count it
+    pub fn filed_pinboard_id(&self) -> Option<&str> {
+        self.pinboard_id
+            .as_deref()
+            .or(self.auto_pinboard_id.as_deref())
+    }
+
     /// Create from a rusqlite Row
+    /// Compressed and spilled text stay a preview (see `Database::get_item`)
     pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
         let created_at_str: String = row.get("created_at")?;
         let created_at = DateTime::parse_from_rfc3339(&created_at_str)
             .map(|dt| dt.with_timezone(&Utc))
             .unwrap_or_else(|_| Utc::now());
+        // Rows without one (not migrated yet) count from their capture
+        let first_copied_at = row
+            .get::<_, Option<String>>("first_copied_at")?
+            .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(created_at);
 
-        Ok(Self {
+        let mut item = Self {
             id: row.get("id")?,
             content_type: row.get("content_type")?,
             content_text: row.get("content_text")?,
             thumbnail_base64: row.get("thumbnail_base64")?,
+            thumbnail_is_favicon: row.get::<_, i32>("thumbnail_is_favicon")? != 0,
             image_path: row.get("image_path")?,
             source_app: row.get("source_app")?,
             source_app_icon: row.get("source_app_icon")?,
+            source_window_title: row.get("source_window_title")?,
+            title: row.get("title")?,
+            content_subtype: row
+                .get::<_, Option<String>>("content_subtype")?
+                .as_deref()
+                .and_then(ContentSubtype::parse),
+            source_pasteboard: row.get("source_pasteboard")?,
             created_at,
+            first_copied_at,
             pinboard_id: row.get("pinboard_id")?,
             is_favorite: row.get::<_, i32>("is_favorite")? != 0,
-        })
+            auto_pinboard_id: row.get("auto_pinboard_id")?,
+            text_path: row.get("text_path")?,
+            canonical_url: row.get("canonical_url")?,
+            files_summary: row.get("files_summary")?,
+            is_directory: row.get::<_, i32>("is_directory")? != 0,
+            original_width: row.get("original_width")?,
+            original_height: row.get("original_height")?,
+            downscale_skipped: row.get::<_, i32>("downscale_skipped")? != 0,
+            use_count: row.get("use_count")?,
+            media_metadata: row
+                .get::<_, Option<String>>("media_metadata")?
+                .and_then(|json| MediaMetadata::from_json(&json)),
+            expires_at: row
+                .get::<_, Option<String>>("expires_at")?
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                .map(|dt| dt.with_timezone(&Utc)),
+            language: row.get("language")?,
+            palette: row
+                .get::<_, Option<String>>("palette")?
+                .and_then(|json| serde_json::from_str(&json).ok()),
+            preview: String::new(),
+        };
+
+        item.refresh_preview();
+        Ok(item)
     }
 
-    /// Get a preview string for display (truncated text or description)
-    pub fn preview(&self, max_len: usize) -> String {
-        match self.content_type {
-            ContentType::Text => {
-                let text = self.content_text.as_deref().unwrap_or("");
-                if text.len() > max_len {
-                    format!("{}...", &text[..max_len])
-                } else {
-                    text.to_string()
-                }
-            }
-            ContentType::Link => {
-                let url = self.content_text.as_deref().unwrap_or("");
-                // Extract domain for preview
-                if let Some(start) = url.find("://") {
-                    let after_proto = &url[start + 3..];
-                    if let Some(end) = after_proto.find('/') {
-                        after_proto[..end].to_string()
-                    } else {
-                        after_proto.to_string()
-                    }
-                } else if url.starts_with("www.") {
-                    if let Some(end) = url[4..].find('/') {
-                        url[..4 + end].to_string()
-                    } else {
-                        url.to_string()
-                    }
-                } else {
-                    url.to_string()
-                }
-            }
-            ContentType::Image => "[Image]".to_string(),
-            ContentType::Files => {
-                if let Some(paths) = self.get_file_paths() {
-                    if paths.len() == 1 {
-                        paths[0].clone()
-                    } else {
-                        format!("{} files", paths.len())
-                    }
-                } else {
-                    "[Files]".to_string()
-                }
-            }
-            ContentType::Audio => {
-                if let Some(paths) = self.get_file_paths() {
-                    if paths.len() == 1 {
-                        // Get just the filename
-                        paths[0]
-                            .rsplit(['/', '\\'])
-                            .next()
-                            .unwrap_or(&paths[0])
-                            .to_string()
-                    } else {
-                        format!("{} audio files", paths.len())
-                    }
-                } else {
-                    "[Audio]".to_string()
-                }
-            }
-            ContentType::Documents => {
-                if let Some(paths) = self.get_file_paths() {
-                    if paths.len() == 1 {
-                        // Get just the filename
-                        paths[0]
-                            .rsplit(['/', '\\'])
-                            .next()
-                            .unwrap_or(&paths[0])
-                            .to_string()
-                    } else {
-                        format!("{} documents", paths.len())
-                    }
-                } else {
-                    "[Documents]".to_string()
-                }
-            }
-        }
+    /// Recompute `preview` after the content, title or files summary changed
+    pub fn refresh_preview(&mut self) {
+        self.preview = preview::preview_item(self, PREVIEW_MAX_CHARS);
+    }
+
+    fn with_preview(mut self) -> Self {
+        self.refresh_preview();
+        self
+    }
+}
+
+/// Trim a window title and cap it at `MAX_WINDOW_TITLE_CHARS`, on a char boundary
+/// Returns None for blank titles
+pub fn truncate_window_title(title: &str) -> Option<String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return None;
+    }
+    match title.char_indices().nth(MAX_WINDOW_TITLE_CHARS) {
+        Some((end, _)) => Some(format!("{}…", title[..end].trim_end())),
+        None => Some(title.to_string()),
     }
 }
 
@@ -376,6 +620,35 @@ impl ClipboardItem {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_truncate_window_title() {
+        assert_eq!(
+            truncate_window_title("  Jira - PROJ-123 \n"),
+            Some("Jira - PROJ-123".to_string())
+        );
+        assert_eq!(truncate_window_title("   "), None);
+
+        // Multi-byte characters are never split
+        let long = "é".repeat(MAX_WINDOW_TITLE_CHARS + 10);
+        let truncated = truncate_window_title(&long).unwrap();
+        assert_eq!(truncated.chars().count(), MAX_WINDOW_TITLE_CHARS + 1);
+        assert!(truncated.ends_with('…'));
+
+        let exact = "a".repeat(MAX_WINDOW_TITLE_CHARS);
+        assert_eq!(truncate_window_title(&exact), Some(exact.clone()));
+    }
+
+    #[test]
+    fn test_files_preview_uses_summary() {
+        let paths = vec!["/p/a.jpg".to_string(), "/p/b.jpg".to_string()];
+        let mut item = ClipboardItem::new_files(paths, None, None);
+        assert_eq!(item.preview, "2 files");
+
+        item.files_summary = Some("2 files from /p — 2 jpg".to_string());
+        item.refresh_preview();
+        assert_eq!(item.preview, "2 files from /p — 2 jpg");
+    }
+
     #[test]
     fn test_content_type_serialization() {
         assert_eq!(ContentType::Text.as_str(), "text");
@@ -429,6 +702,26 @@ mod tests {
         assert_eq!(item.content_type, ContentType::Files);
     }
 
+    #[test]
+    fn test_redetect() {
+        use ContentType::*;
+        let cases = [
+            (Text, "https://example.com", Link),
+            (Link, "https://example.com", Link),
+            (Text, "Hello world", Text),
+            (Files, r#"["/music/a.mp3","/music/b.flac"]"#, Audio),
+            (Files, r#"["/docs/a.pdf"]"#, Documents),
+            (Audio, r#"["/a.pdf","/b.mp3"]"#, Files),
+            // Content that isn't a file list keeps its type
+            (Files, "not json", Files),
+            (Documents, "[]", Documents),
+            (Image, "https://example.com", Image),
+        ];
+        for (stored, content, expected) in cases {
+            assert_eq!(stored.redetect(content), expected, "{:?} {}", stored, content);
+        }
+    }
+
     #[test]
     fn test_file_paths() {
         let paths = vec!["C:\\file1.txt".to_string(), "C:\\file2.txt".to_string()];
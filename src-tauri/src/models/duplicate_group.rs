@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// How `find_duplicates` compares items
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateKind {
+    /// Same type and identical content (text, URL, file list or image bytes)
+    Exact,
+    /// Images that look alike, e.g. one picture saved by two apps or recompressed
+    Images,
+}
+
+/// Items found to be duplicates of each other
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    /// Newest first; the first is the suggested item to keep
+    pub item_ids: Vec<String>,
+
+    /// Preview of the newest item
+    pub preview: String,
+
+    /// Thumbnail of the newest item, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_base64: Option<String>,
+}
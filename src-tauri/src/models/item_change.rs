@@ -0,0 +1,42 @@
+use crate::models::ClipboardItem;
+use serde::{Deserialize, Serialize};
+
+/// Kind of change recorded in the `item_events` log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemChangeKind {
+    Insert,
+    Update,
+    Delete,
+    /// The item is in a passcode-locked pinboard: drop it like a deletion
+    Hidden,
+}
+
+/// Latest change of a single item since a given sequence number
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemChange {
+    /// Sequence number of the most recent event for this item
+    pub seq: i64,
+
+    pub item_id: String,
+
+    pub kind: ItemChangeKind,
+
+    /// Current item state (None for deletions and hidden items)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<ClipboardItem>,
+}
+
+/// Ordered delta returned by `get_changes_since`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSet {
+    /// Latest sequence number; pass it to the next `get_changes_since` call
+    pub latest_seq: i64,
+
+    /// True if the requested range was trimmed from the log and the caller
+    /// must reload everything instead of applying `changes`
+    pub reset: bool,
+
+    /// Changes ordered by sequence number, one entry per item
+    pub changes: Vec<ItemChange>,
+}
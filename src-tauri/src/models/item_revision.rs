@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use rusqlite::Row;
+use serde::{Deserialize, Serialize};
+
+/// Most revisions kept per item; older ones are dropped when a new one is written
+pub const MAX_REVISIONS_PER_ITEM: i64 = 10;
+
+/// Previous text of an edited item
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemRevision {
+    pub item_id: String,
+
+    /// Increasing per item; the highest number is the most recent revision
+    pub revision_no: i64,
+
+    /// Full text the item had before the edit
+    pub content_text: String,
+
+    /// When this text was replaced
+    pub edited_at: DateTime<Utc>,
+}
+
+impl ItemRevision {
+    /// Create from database row
+    pub fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let edited_at_str: String = row.get("edited_at")?;
+        let edited_at = DateTime::parse_from_rfc3339(&edited_at_str)
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+
+        Ok(Self {
+            item_id: row.get("item_id")?,
+            revision_no: row.get("revision_no")?,
+            content_text: row.get("content_text")?,
+            edited_at,
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Duration and tags of a copied audio file, stored as JSON in `media_metadata`
+/// Every field is optional: untagged files only have a duration, and files
+/// lofty can't parse the length of only have their tags
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct MediaMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+}
+
+impl MediaMetadata {
+    /// Nothing could be read
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Column value; None for empty metadata
+    pub fn to_json(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        serde_json::to_string(self).ok()
+    }
+
+    /// Parse a column value; unreadable JSON is treated as no metadata
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(json)
+            .ok()
+            .filter(|metadata| !metadata.is_empty())
+    }
+}
@@ -1,7 +1,23 @@
 // Data models for clipboard items and pinboards
 
+pub mod app_group;
+pub mod bulk_update;
 pub mod clipboard_item;
+pub mod duplicate_group;
+pub mod item_change;
+pub mod item_revision;
+pub mod media_metadata;
 pub mod pinboard;
+pub mod reclassification;
+pub mod search_group;
 
-pub use clipboard_item::{ClipboardItem, ContentType};
-pub use pinboard::Pinboard;
+pub use app_group::AppGroup;
+pub use bulk_update::{BulkItemResult, BulkOp};
+pub use clipboard_item::{ClipboardItem, ContentSubtype, ContentType};
+pub use duplicate_group::{DuplicateGroup, DuplicateKind};
+pub use item_change::{ChangeSet, ItemChange, ItemChangeKind};
+pub use item_revision::{ItemRevision, MAX_REVISIONS_PER_ITEM};
+pub use media_metadata::MediaMetadata;
+pub use pinboard::{Pinboard, PinboardRules};
+pub use reclassification::{LanguageChange, Reclassification, ReclassifyReport, TypeTransition};
+pub use search_group::SearchGroup;
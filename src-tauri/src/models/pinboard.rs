@@ -1,7 +1,91 @@
+use crate::clipboard::transforms::Transform;
+use crate::models::{ClipboardItem, ContentType};
 use chrono::{DateTime, Utc};
 use rusqlite::Row;
 use serde::{Deserialize, Serialize};
 
+/// Colors given to pinboards that don't have one set
+pub const PINBOARD_PALETTE: [&str; 8] = [
+    "#e5484d", "#f76b15", "#ffc53d", "#46a758", "#12a594", "#0090ff", "#6e56cf", "#d6409f",
+];
+
+/// Palette color for a pinboard without a stored color
+/// Derived from the ID so a board keeps the same color across launches
+pub fn default_color(id: &str) -> String {
+    // FNV-1a: stable across Rust versions, unlike the std hashers
+    let hash = id.bytes().fold(0x811c_9dc5u32, |hash, byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    PINBOARD_PALETTE[hash as usize % PINBOARD_PALETTE.len()].to_string()
+}
+
+/// Validate a pinboard color: `#` followed by 6 hex digits
+pub fn validate_color(color: &str) -> Result<(), String> {
+    match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(()),
+        _ => Err(format!("Invalid color \"{}\", expected #RRGGBB", color)),
+    }
+}
+
+/// Rules for a smart pinboard that auto-collects matching items
+/// All set predicates must match (AND semantics)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PinboardRules {
+    /// Source application name (case-insensitive exact match)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_app: Option<String>,
+
+    /// Content type the item must have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<ContentType>,
+
+    /// Substring the item text must contain (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_contains: Option<String>,
+}
+
+impl PinboardRules {
+    /// Validate rules before storing them
+    /// At least one predicate is required and string predicates must not be blank
+    pub fn validate(&self) -> Result<(), String> {
+        if self.source_app.is_none() && self.content_type.is_none() && self.text_contains.is_none() {
+            return Err("Rules must define at least one predicate".to_string());
+        }
+        if matches!(&self.source_app, Some(app) if app.trim().is_empty()) {
+            return Err("source_app rule cannot be empty".to_string());
+        }
+        if matches!(&self.text_contains, Some(text) if text.trim().is_empty()) {
+            return Err("text_contains rule cannot be empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Check whether an item satisfies every predicate
+    pub fn matches(&self, item: &ClipboardItem) -> bool {
+        if let Some(app) = &self.source_app {
+            match &item.source_app {
+                Some(item_app) if item_app.eq_ignore_ascii_case(app) => {}
+                _ => return false,
+            }
+        }
+
+        if let Some(content_type) = self.content_type {
+            if item.content_type != content_type {
+                return false;
+            }
+        }
+
+        if let Some(needle) = &self.text_contains {
+            match &item.content_text {
+                Some(text) if text.to_lowercase().contains(&needle.to_lowercase()) => {}
+                _ => return false,
+            }
+        }
+
+        true
+    }
+}
+
 /// Represents a pinboard for organizing clipboard items
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Pinboard {
@@ -15,22 +99,67 @@ pub struct Pinboard {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
 
-    /// Sort order position
+    /// Hex color (`#RRGGBB`), a palette color when none was chosen
+    pub color: String,
+
+    /// Free-form description shown under the name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Pinboard this one is nested under (None at the root)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+
+    /// Sort order position among pinboards with the same parent
     pub position: i32,
 
     /// Timestamp when pinboard was created
     pub created_at: DateTime<Utc>,
+
+    /// Smart pinboard rules (None for a regular pinboard)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rules: Option<PinboardRules>,
+
+    /// Transform applied when its items are copied back (see `transforms`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_transform: Option<Transform>,
+
+    /// Whether the pinboard is archived (hidden from the sidebar by default)
+    #[serde(default)]
+    pub archived: bool,
+
+    /// Argon2 hash of the passcode (never sent to the frontend)
+    #[serde(skip)]
+    pub lock_hash: Option<String>,
+
+    /// Whether the pinboard is protected by a passcode
+    #[serde(default)]
+    pub is_locked: bool,
+
+    /// Number of items in the pinboard (computed by queries)
+    #[serde(default)]
+    pub item_count: usize,
 }
 
 impl Pinboard {
     /// Create a new pinboard
     pub fn new(name: String, icon: Option<String>, position: i32) -> Self {
+        let id = uuid::Uuid::new_v4().to_string();
         Self {
-            id: uuid::Uuid::new_v4().to_string(),
+            color: default_color(&id),
+            id,
             name,
             icon,
+            description: None,
+            parent_id: None,
             position,
             created_at: Utc::now(),
+            rules: None,
+            default_transform: None,
+            archived: false,
+            lock_hash: None,
+            is_locked: false,
+            item_count: 0,
         }
     }
 
@@ -46,12 +175,33 @@ impl Pinboard {
             .map(|dt| dt.with_timezone(&Utc))
             .unwrap_or_else(|_| Utc::now());
 
+        let rules_json: Option<String> = row.get("rules")?;
+        let rules = rules_json.and_then(|json| serde_json::from_str(&json).ok());
+        let default_transform = row
+            .get::<_, Option<String>>("default_transform")?
+            .and_then(|value| Transform::parse(&value));
+        let lock_hash: Option<String> = row.get("lock_hash")?;
+        let id: String = row.get("id")?;
+        // Boards created before colors existed have none stored
+        let color = row
+            .get::<_, Option<String>>("color")?
+            .unwrap_or_else(|| default_color(&id));
+
         Ok(Self {
-            id: row.get("id")?,
+            id,
             name: row.get("name")?,
             icon: row.get("icon")?,
+            color,
+            description: row.get("description")?,
+            parent_id: row.get("parent_id")?,
             position: row.get("position")?,
             created_at,
+            rules,
+            default_transform,
+            archived: row.get::<_, i32>("archived")? != 0,
+            is_locked: lock_hash.is_some(),
+            lock_hash,
+            item_count: row.get::<_, i64>("item_count")? as usize,
         })
     }
 }
@@ -74,4 +224,92 @@ mod tests {
         assert_eq!(favorites.name, "Favorites");
         assert_eq!(favorites.position, 0);
     }
+
+    #[test]
+    fn test_default_color_is_stable_palette_color() {
+        let id = "3f2b9c1e-8a4d-4f6b-9e2a-1c7d5b0a9f38";
+        assert_eq!(default_color(id), default_color(id));
+        assert!(PINBOARD_PALETTE.contains(&default_color(id).as_str()));
+        // Fixed input and output, so a hasher change can't recolor every board
+        assert_eq!(default_color(""), PINBOARD_PALETTE[5]);
+
+        let pinboard = Pinboard::new("Work".to_string(), None, 0);
+        assert_eq!(pinboard.color, default_color(&pinboard.id));
+    }
+
+    #[test]
+    fn test_validate_color() {
+        for valid in ["#000000", "#e5484d", "#A1B2C3"] {
+            assert!(validate_color(valid).is_ok(), "{}", valid);
+        }
+        for invalid in [
+            "", "#", "e5484d", "#fff", "#e5484", "#e5484d0", "#g5484d", "##5484d",
+        ] {
+            assert!(validate_color(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    fn item(text: &str, app: Option<&str>) -> ClipboardItem {
+        ClipboardItem::new_text(text.to_string(), app.map(|a| a.to_string()), None)
+    }
+
+    #[test]
+    fn test_rules_validation() {
+        assert!(PinboardRules::default().validate().is_err());
+        let blank = PinboardRules { source_app: Some("  ".to_string()), ..Default::default() };
+        assert!(blank.validate().is_err());
+        let blank = PinboardRules { text_contains: Some(String::new()), ..Default::default() };
+        assert!(blank.validate().is_err());
+        let ok = PinboardRules { content_type: Some(ContentType::Image), ..Default::default() };
+        assert!(ok.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rules_matching_matrix() {
+        let from_code = PinboardRules { source_app: Some("code".to_string()), ..Default::default() };
+        let github_links = PinboardRules {
+            content_type: Some(ContentType::Link),
+            text_contains: Some("GitHub.com".to_string()),
+            ..Default::default()
+        };
+        let images = PinboardRules { content_type: Some(ContentType::Image), ..Default::default() };
+
+        let code_text = item("fn main() {}", Some("Code"));
+        let github_link = item("https://github.com/rust-lang", Some("Safari"));
+        let other_link = item("https://example.com", Some("Code"));
+        let no_app = item("plain", None);
+        let image = ClipboardItem::new_image(None, "/tmp/x.png".to_string(), Some("Preview".to_string()), None);
+
+        let cases = [
+            (&from_code, &code_text, true),
+            (&from_code, &github_link, false),
+            (&from_code, &other_link, true),
+            (&from_code, &no_app, false),
+            (&from_code, &image, false),
+            (&github_links, &code_text, false),
+            (&github_links, &github_link, true),
+            (&github_links, &other_link, false),
+            (&github_links, &image, false),
+            (&images, &image, true),
+            (&images, &code_text, false),
+            (&images, &github_link, false),
+        ];
+
+        for (rules, item, expected) in cases {
+            assert_eq!(rules.matches(item), expected, "{:?} vs {:?}", rules, item.content_text);
+        }
+    }
+
+    #[test]
+    fn test_rules_json_roundtrip() {
+        let rules = PinboardRules {
+            source_app: Some("Figma".to_string()),
+            content_type: Some(ContentType::Image),
+            text_contains: None,
+        };
+        let json = serde_json::to_string(&rules).unwrap();
+        assert_eq!(json, r#"{"source_app":"Figma","content_type":"image"}"#);
+        let parsed: PinboardRules = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, rules);
+    }
 }
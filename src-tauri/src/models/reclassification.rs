@@ -0,0 +1,125 @@
+use crate::models::ContentType;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// Item whose content type the current detectors would set differently
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reclassification {
+    pub id: String,
+    pub from: ContentType,
+    pub to: ContentType,
+    /// Normalized URL for items becoming links (None otherwise)
+    pub canonical_url: Option<String>,
+}
+
+/// Text item whose detected language differs from the stored one
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageChange {
+    pub id: String,
+    pub from: Option<String>,
+    /// None clears the language (not text anymore, or no longer recognized)
+    pub to: Option<String>,
+}
+
+/// Number of items moving from one content type to another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TypeTransition {
+    pub from: ContentType,
+    pub to: ContentType,
+    pub count: usize,
+}
+
+/// Result of `reclassify_items`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReclassifyReport {
+    /// Nothing was written; the counts are what would change
+    pub dry_run: bool,
+    /// Items whose type changed (or would change)
+    pub changed: usize,
+    /// Changes grouped by transition, most common first
+    pub transitions: Vec<TypeTransition>,
+    /// Items whose detected language changed (or would change)
+    pub languages_changed: usize,
+}
+
+impl ReclassifyReport {
+    pub fn new(
+        changes: &[Reclassification],
+        language_changes: &[LanguageChange],
+        dry_run: bool,
+    ) -> Self {
+        let mut counts: BTreeMap<(&str, &str), TypeTransition> = BTreeMap::new();
+        for change in changes {
+            counts
+                .entry((change.from.as_str(), change.to.as_str()))
+                .or_insert(TypeTransition {
+                    from: change.from,
+                    to: change.to,
+                    count: 0,
+                })
+                .count += 1;
+        }
+
+        let mut transitions: Vec<TypeTransition> = counts.into_values().collect();
+        // Stable sort: equal counts stay in from/to name order
+        transitions.sort_by_key(|transition| Reverse(transition.count));
+        Self {
+            dry_run,
+            changed: changes.len(),
+            transitions,
+            languages_changed: language_changes.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(from: ContentType, to: ContentType) -> Reclassification {
+        Reclassification {
+            id: uuid::Uuid::new_v4().to_string(),
+            from,
+            to,
+            canonical_url: None,
+        }
+    }
+
+    #[test]
+    fn test_report_groups_by_transition() {
+        let changes = vec![
+            change(ContentType::Files, ContentType::Audio),
+            change(ContentType::Text, ContentType::Link),
+            change(ContentType::Text, ContentType::Link),
+            change(ContentType::Files, ContentType::Documents),
+            change(ContentType::Text, ContentType::Link),
+        ];
+        let report = ReclassifyReport::new(&changes, &[], true);
+
+        assert!(report.dry_run);
+        assert_eq!(report.changed, 5);
+        assert_eq!(
+            report.transitions,
+            vec![
+                TypeTransition {
+                    from: ContentType::Text,
+                    to: ContentType::Link,
+                    count: 3
+                },
+                TypeTransition {
+                    from: ContentType::Files,
+                    to: ContentType::Audio,
+                    count: 1
+                },
+                TypeTransition {
+                    from: ContentType::Files,
+                    to: ContentType::Documents,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(report.languages_changed, 0);
+        assert!(ReclassifyReport::new(&[], &[], false).transitions.is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+use super::ClipboardItem;
+use serde::Serialize;
+
+/// Name shown for the group of history items (no pinboard)
+pub const HISTORY_GROUP: &str = "History";
+
+/// Search results from one pinboard, or from history
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchGroup {
+    /// Pinboard the results are in, None for history
+    pub pinboard_id: Option<String>,
+
+    /// Pinboard name, or `HISTORY_GROUP`
+    pub name: String,
+
+    /// Pinboard icon (emoji or icon name)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+
+    /// Matching items in this group, including those not in `items`
+    pub total_count: usize,
+
+    /// Most recent matching items, newest first
+    pub items: Vec<ClipboardItem>,
+}
@@ -0,0 +1,45 @@
+//! Panel placement per monitor
+//!
+//! The panel opens either as a bottom bar or as a centered palette, with an
+//! optional size. The `panel_placement` setting holds the global choice and
+//! `monitor_placements` maps a stable monitor ID (the display UUID on macOS,
+//! device name and resolution on Windows) to a placement chosen while the
+//! panel was on that monitor. Monitors without an entry use the global
+//! setting. Entries for monitors not seen for `STALE_AFTER_DAYS` are pruned
+//! when the panel is shown.
+
+pub mod placement;
+
+pub use placement::{MonitorPlacement, MonitorPlacements, Placement, PlacementMode};
+
+use crate::storage::Database;
+use chrono::Utc;
+
+/// Placement for the monitor `monitor_id`
+/// Also records that the monitor was seen and prunes stale entries
+pub fn resolve(db: &Database, monitor_id: Option<&str>) -> Placement {
+    let default = db.get_panel_placement().unwrap_or_else(|e| {
+        eprintln!("[panel_placement] Failed to read panel placement: {}", e);
+        Placement::default()
+    });
+    let mut placements = match db.get_monitor_placements() {
+        Ok(placements) => placements,
+        Err(e) => {
+            eprintln!("[panel_placement] Failed to read monitor placements: {}", e);
+            return default;
+        }
+    };
+
+    let now = Utc::now();
+    let mut changed = placement::prune_stale(&mut placements, now) > 0;
+    if let Some(id) = monitor_id {
+        changed |= placement::mark_seen(&mut placements, id, now);
+    }
+    if changed {
+        if let Err(e) = db.set_monitor_placements(&placements) {
+            eprintln!("[panel_placement] Failed to save monitor placements: {}", e);
+        }
+    }
+
+    placement::placement_for(&placements, monitor_id, default)
+}
@@ -0,0 +1,260 @@
+use crate::pinboard_palette::layout::Area;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Entries for monitors not seen for this long are dropped
+pub const STALE_AFTER_DAYS: i64 = 90;
+
+/// Smallest panel width or height accepted, in logical points
+pub const MIN_PANEL_SIZE: f64 = 200.0;
+
+/// Share of the monitor height used by the bottom bar by default
+const BOTTOM_BAR_HEIGHT_FRACTION: f64 = 0.33;
+
+/// Default size of the centered palette
+const PALETTE_WIDTH: f64 = 760.0;
+const PALETTE_HEIGHT: f64 = 520.0;
+
+/// How the panel is laid out on a monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementMode {
+    /// Full width along the bottom edge
+    #[default]
+    BottomBar,
+    /// Centered on the monitor
+    CenteredPalette,
+}
+
+/// Panel mode and size in logical points; `None` uses the mode's default size
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Placement {
+    pub mode: PlacementMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+}
+
+/// Placement saved for one monitor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorPlacement {
+    pub placement: Placement,
+    /// Last time the panel was shown on this monitor
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Monitor ID -> saved placement
+pub type MonitorPlacements = BTreeMap<String, MonitorPlacement>;
+
+impl Placement {
+    /// Check that the sizes are usable
+    pub fn validate(&self) -> Result<(), String> {
+        for size in [self.width, self.height].into_iter().flatten() {
+            if !size.is_finite() || size < MIN_PANEL_SIZE {
+                return Err(format!(
+                    "Panel size must be at least {} points",
+                    MIN_PANEL_SIZE
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Window frame on `screen`, clamped to fit it
+    /// The bottom bar always spans the full width, so `width` only applies to the palette
+    pub fn frame(&self, screen: Area) -> Area {
+        match self.mode {
+            PlacementMode::BottomBar => {
+                let height = self
+                    .height
+                    .unwrap_or(screen.height * BOTTOM_BAR_HEIGHT_FRACTION)
+                    .min(screen.height);
+                Area {
+                    x: screen.x,
+                    y: screen.y + screen.height - height,
+                    width: screen.width,
+                    height,
+                }
+            }
+            PlacementMode::CenteredPalette => {
+                let width = self.width.unwrap_or(PALETTE_WIDTH).min(screen.width);
+                let height = self.height.unwrap_or(PALETTE_HEIGHT).min(screen.height);
+                Area {
+                    x: screen.x + (screen.width - width) / 2.0,
+                    y: screen.y + (screen.height - height) / 2.0,
+                    width,
+                    height,
+                }
+            }
+        }
+    }
+}
+
+/// Placement for a monitor: its own entry, or `default` for unknown monitors
+pub fn placement_for(
+    placements: &MonitorPlacements,
+    monitor_id: Option<&str>,
+    default: Placement,
+) -> Placement {
+    monitor_id
+        .and_then(|id| placements.get(id))
+        .map_or(default, |entry| entry.placement)
+}
+
+/// Record that the panel was shown on a monitor with a saved placement
+/// `last_seen` only moves once a day so showing the panel rarely writes settings;
+/// returns true when the map changed
+pub fn mark_seen(placements: &mut MonitorPlacements, monitor_id: &str, now: DateTime<Utc>) -> bool {
+    match placements.get_mut(monitor_id) {
+        Some(entry) if now - entry.last_seen >= Duration::days(1) => {
+            entry.last_seen = now;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Drop entries for monitors not seen for `STALE_AFTER_DAYS`
+/// Returns the number of entries removed
+pub fn prune_stale(placements: &mut MonitorPlacements, now: DateTime<Utc>) -> usize {
+    let before = placements.len();
+    placements.retain(|_, entry| now - entry.last_seen < Duration::days(STALE_AFTER_DAYS));
+    before - placements.len()
+}
+
+/// Monitor ID built from a device name and its resolution in pixels
+/// Used where the OS has no stable display UUID (Windows)
+pub fn device_monitor_id(device: &str, width: i32, height: i32) -> String {
+    format!("{}@{}x{}", device, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const SCREEN: Area = Area {
+        x: 100.0,
+        y: 0.0,
+        width: 1600.0,
+        height: 900.0,
+    };
+
+    fn at(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, day, 12, 0, 0).unwrap()
+    }
+
+    fn palette() -> Placement {
+        Placement {
+            mode: PlacementMode::CenteredPalette,
+            width: Some(1000.0),
+            height: None,
+        }
+    }
+
+    #[test]
+    fn test_bottom_bar_frame() {
+        let frame = Placement::default().frame(SCREEN);
+        assert_eq!(frame.x, 100.0);
+        assert_eq!(frame.width, 1600.0);
+        assert_eq!(frame.height, 900.0 * 0.33);
+        assert_eq!(frame.y + frame.height, 900.0);
+
+        // Width is ignored, height is clamped to the screen
+        let tall = Placement {
+            mode: PlacementMode::BottomBar,
+            width: Some(400.0),
+            height: Some(2000.0),
+        };
+        let frame = tall.frame(SCREEN);
+        assert_eq!((frame.y, frame.width, frame.height), (0.0, 1600.0, 900.0));
+    }
+
+    #[test]
+    fn test_centered_palette_frame() {
+        let frame = palette().frame(SCREEN);
+        assert_eq!((frame.width, frame.height), (1000.0, PALETTE_HEIGHT));
+        assert_eq!(frame.x, 100.0 + 300.0);
+        assert_eq!(frame.y, (900.0 - PALETTE_HEIGHT) / 2.0);
+
+        let small = Area {
+            x: 0.0,
+            y: 0.0,
+            width: 800.0,
+            height: 400.0,
+        };
+        let frame = palette().frame(small);
+        assert_eq!(
+            (frame.x, frame.y, frame.width, frame.height),
+            (0.0, 0.0, 800.0, 400.0)
+        );
+    }
+
+    #[test]
+    fn test_placement_for_falls_back_to_default() {
+        let mut placements = MonitorPlacements::new();
+        placements.insert(
+            "external".to_string(),
+            MonitorPlacement {
+                placement: palette(),
+                last_seen: at(1),
+            },
+        );
+        let default = Placement::default();
+
+        assert_eq!(
+            placement_for(&placements, Some("external"), default),
+            palette()
+        );
+        assert_eq!(placement_for(&placements, Some("new"), default), default);
+        assert_eq!(placement_for(&placements, None, default), default);
+    }
+
+    #[test]
+    fn test_mark_seen_and_prune() {
+        let mut placements = MonitorPlacements::new();
+        for (id, day) in [("office", 1), ("home", 20)] {
+            let entry = MonitorPlacement {
+                placement: palette(),
+                last_seen: at(day),
+            };
+            placements.insert(id.to_string(), entry);
+        }
+
+        // Same day: nothing to save; unknown monitors are not added
+        assert!(!mark_seen(&mut placements, "home", at(20)));
+        assert!(!mark_seen(&mut placements, "new", at(25)));
+        assert!(mark_seen(&mut placements, "home", at(25)));
+        assert_eq!(placements["home"].last_seen, at(25));
+
+        let now = at(1) + Duration::days(STALE_AFTER_DAYS);
+        assert_eq!(prune_stale(&mut placements, now), 1);
+        assert!(placements.contains_key("home"));
+        assert!(!placements.contains_key("office"));
+    }
+
+    #[test]
+    fn test_validate_and_serialize() {
+        assert!(palette().validate().is_ok());
+        let tiny = Placement {
+            height: Some(50.0),
+            ..palette()
+        };
+        assert!(tiny.validate().is_err());
+
+        let json = serde_json::to_string(&palette()).unwrap();
+        assert_eq!(json, r#"{"mode":"centered_palette","width":1000.0}"#);
+        let parsed: Placement = serde_json::from_str(r#"{"mode":"bottom_bar"}"#).unwrap();
+        assert_eq!(parsed, Placement::default());
+    }
+
+    #[test]
+    fn test_device_monitor_id() {
+        assert_eq!(
+            device_monitor_id(r"\\.\DISPLAY2", 2560, 1440),
+            r"\\.\DISPLAY2@2560x1440"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Frontmost window lookup and activation through NSWorkspace, the window
+//! server list and the Accessibility API
+
+use super::{plan, Activation, PasteTarget};
+use crate::permissions::{self, PermissionKind};
+use chrono::Utc;
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::{CFType, CFTypeRef, TCFType};
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use core_graphics::window::{
+    copy_window_info, kCGNullWindowID, kCGWindowLayer, kCGWindowListExcludeDesktopElements,
+    kCGWindowListOptionIncludingWindow, kCGWindowListOptionOnScreenOnly, kCGWindowNumber,
+    kCGWindowOwnerPID,
+};
+use objc2_app_kit::{NSApplicationActivationOptions, NSRunningApplication, NSWorkspace};
+use std::ffi::c_void;
+
+type AXUIElementRef = *const c_void;
+
+/// kAXErrorSuccess
+const AX_SUCCESS: i32 = 0;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXUIElementCreateApplication(pid: i32) -> AXUIElementRef;
+    fn AXUIElementCopyAttributeValue(
+        element: AXUIElementRef,
+        attribute: CFStringRef,
+        value: *mut CFTypeRef,
+    ) -> i32;
+    fn AXUIElementPerformAction(element: AXUIElementRef, action: CFStringRef) -> i32;
+    /// Private but long-stable; the only way to match an AX window to a CGWindowID
+    fn _AXUIElementGetWindow(element: AXUIElementRef, window_id: *mut u32) -> i32;
+}
+
+pub(super) fn capture() -> Option<PasteTarget> {
+    let app = unsafe { NSWorkspace::sharedWorkspace().frontmostApplication()? };
+    let pid = unsafe { app.processIdentifier() };
+    let app_name = unsafe { app.localizedName() }.map(|name| name.to_string());
+
+    Some(PasteTarget {
+        app_name,
+        pid: pid as u32,
+        window_id: frontmost_window(pid),
+        captured_at: Utc::now(),
+    })
+}
+
+pub(super) fn activate(target: &PasteTarget) -> Activation {
+    let pid = target.pid as i32;
+    let app = unsafe { NSRunningApplication::runningApplicationWithProcessIdentifier(pid) };
+    let window_exists = target.window_id.is_some_and(window_exists);
+    let activation = plan(target, window_exists, app.is_some());
+
+    if let Some(app) = app {
+        // Raise first: activating alone brings the app's last used window,
+        // which may be on another Space
+        if activation == Activation::Window {
+            if let Some(window_id) = target.window_id {
+                raise_window(pid, window_id as u32);
+            }
+        }
+        unsafe {
+            app.activateWithOptions(
+                NSApplicationActivationOptions::NSApplicationActivateIgnoringOtherApps,
+            );
+        }
+    }
+    activation
+}
+
+/// Number of a CFDictionary entry of the window list
+fn number(window: &CFDictionary<CFString, CFType>, key: &CFString) -> Option<i64> {
+    window
+        .find(key)
+        .and_then(|value| value.downcast::<CFNumber>())
+        .and_then(|n| n.to_i64())
+}
+
+/// Topmost normal window of `pid` on screen
+fn frontmost_window(pid: i32) -> Option<u64> {
+    let windows = copy_window_info(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+        kCGNullWindowID,
+    )?;
+    let (owner_key, layer_key, number_key) = unsafe {
+        (
+            CFString::wrap_under_get_rule(kCGWindowOwnerPID),
+            CFString::wrap_under_get_rule(kCGWindowLayer),
+            CFString::wrap_under_get_rule(kCGWindowNumber),
+        )
+    };
+
+    // Windows are listed front to back; normal windows are on layer 0
+    windows.iter().find_map(|window| {
+        let window: CFDictionary<CFString, CFType> =
+            unsafe { CFDictionary::wrap_under_get_rule(*window as CFDictionaryRef) };
+        if number(&window, &owner_key) != Some(pid as i64) || number(&window, &layer_key) != Some(0)
+        {
+            return None;
+        }
+        number(&window, &number_key).map(|id| id as u64)
+    })
+}
+
+/// Whether the window server still knows the window (on any Space)
+fn window_exists(window_id: u64) -> bool {
+    copy_window_info(kCGWindowListOptionIncludingWindow, window_id as u32)
+        .is_some_and(|windows| windows.len() > 0)
+}
+
+/// Raise the window `window_id` of `pid` through Accessibility
+/// Does nothing without the Accessibility permission
+fn raise_window(pid: i32, window_id: u32) {
+    if !permissions::is_granted(PermissionKind::Accessibility) {
+        eprintln!("[paste_target] Can't raise the window without Accessibility");
+        return;
+    }
+    unsafe {
+        let app = AXUIElementCreateApplication(pid);
+        if app.is_null() {
+            return;
+        }
+        let app = CFType::wrap_under_create_rule(app as CFTypeRef);
+
+        let attribute = CFString::from_static_string("AXWindows");
+        let mut value: CFTypeRef = std::ptr::null();
+        let result = AXUIElementCopyAttributeValue(
+            app.as_CFTypeRef() as AXUIElementRef,
+            attribute.as_concrete_TypeRef(),
+            &mut value,
+        );
+        if result != AX_SUCCESS || value.is_null() {
+            eprintln!("[paste_target] Can't list windows (AX error {})", result);
+            return;
+        }
+        let windows: CFArray<CFType> = CFArray::wrap_under_create_rule(value as CFArrayRef);
+
+        let raise = CFString::from_static_string("AXRaise");
+        for window in windows.iter() {
+            let element = window.as_CFTypeRef() as AXUIElementRef;
+            let mut id = 0u32;
+            if _AXUIElementGetWindow(element, &mut id) == AX_SUCCESS && id == window_id {
+                AXUIElementPerformAction(element, raise.as_concrete_TypeRef());
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! The window the panel was summoned from
+//!
+//! Showing the panel remembers the frontmost app and its focused window (pid
+//! and CGWindowID on macOS, pid and HWND on Windows) in `AppState`. Handing
+//! focus back activates that exact window, which on macOS may sit in another
+//! Space than the app's other windows. If the window has been closed the app
+//! is activated instead; if the app has quit nothing is done. The UI reads
+//! the target through `get_paste_target` ("Pasting into Safari").
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// App and window that had focus when the panel was shown
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PasteTarget {
+    pub app_name: Option<String>,
+    pub pid: u32,
+    /// CGWindowID on macOS, HWND on Windows; None if no window was found
+    pub window_id: Option<u64>,
+    pub captured_at: DateTime<Utc>,
+}
+
+/// How focus was handed back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    /// The remembered window was raised
+    Window,
+    /// The window is gone; the app was activated
+    App,
+    /// The app is no longer running
+    Gone,
+}
+
+/// How focus can be handed back, given what still exists
+pub fn plan(target: &PasteTarget, window_exists: bool, app_running: bool) -> Activation {
+    if !app_running {
+        Activation::Gone
+    } else if target.window_id.is_some() && window_exists {
+        Activation::Window
+    } else {
+        Activation::App
+    }
+}
+
+/// Whether a target found when showing the panel should replace the one kept
+/// Clipster itself is frontmost when the panel is shown again from itself
+pub fn is_external(target: &PasteTarget) -> bool {
+    target.pid != std::process::id()
+}
+
+/// The frontmost app and its focused window, right now
+pub fn capture() -> Option<PasteTarget> {
+    #[cfg(target_os = "macos")]
+    return macos::capture();
+
+    #[cfg(target_os = "windows")]
+    return windows::capture();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    None
+}
+
+/// Give focus back to `target`: its window if it still exists, else the app
+pub fn activate(target: &PasteTarget) -> Activation {
+    #[cfg(target_os = "macos")]
+    let activation = macos::activate(target);
+
+    #[cfg(target_os = "windows")]
+    let activation = windows::activate(target);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let activation = plan(target, false, false);
+
+    if activation != Activation::Window {
+        eprintln!(
+            "[paste_target] Window of {:?} not raised: {:?}",
+            target.app_name, activation
+        );
+    }
+    activation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(window_id: Option<u64>) -> PasteTarget {
+        PasteTarget {
+            app_name: Some("Safari".to_string()),
+            pid: 4242,
+            window_id,
+            captured_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_plan_prefers_the_window() {
+        assert_eq!(plan(&target(Some(7)), true, true), Activation::Window);
+        // Window closed since the panel was shown
+        assert_eq!(plan(&target(Some(7)), false, true), Activation::App);
+        assert_eq!(plan(&target(None), true, true), Activation::App);
+        assert_eq!(plan(&target(Some(7)), true, false), Activation::Gone);
+    }
+
+    #[test]
+    fn test_own_process_is_not_a_target() {
+        assert!(is_external(&target(Some(7))));
+        let own = PasteTarget {
+            pid: std::process::id(),
+            ..target(Some(7))
+        };
+        assert!(!is_external(&own));
+    }
+}
@@ -0,0 +1,81 @@
+//! Foreground window lookup and activation through the Win32 window APIs
+
+use super::{plan, Activation, PasteTarget};
+use crate::clipboard::clipboard_monitor::window_process;
+use chrono::Utc;
+use windows::Win32::Foundation::{BOOL, HWND, LPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetForegroundWindow, GetWindowThreadProcessId, IsIconic, IsWindow,
+    IsWindowVisible, SetForegroundWindow, ShowWindow, SW_RESTORE,
+};
+
+pub(super) fn capture() -> Option<PasteTarget> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    let owner = window_process(hwnd)?;
+
+    Some(PasteTarget {
+        app_name: owner.app_name(),
+        pid: owner.pid,
+        window_id: Some(hwnd.0 as usize as u64),
+        captured_at: Utc::now(),
+    })
+}
+
+pub(super) fn activate(target: &PasteTarget) -> Activation {
+    let window = target
+        .window_id
+        .map(|id| HWND(id as usize as *mut _))
+        .filter(|&hwnd| owned_by(hwnd, target.pid));
+    let fallback = || app_window(target.pid);
+    let activation = plan(target, window.is_some(), window.or_else(fallback).is_some());
+
+    let hwnd = match activation {
+        Activation::Window => window,
+        Activation::App => fallback(),
+        Activation::Gone => None,
+    };
+    if let Some(hwnd) = hwnd {
+        unsafe {
+            if IsIconic(hwnd).as_bool() {
+                let _ = ShowWindow(hwnd, SW_RESTORE);
+            }
+            if !SetForegroundWindow(hwnd).as_bool() {
+                eprintln!("[paste_target] SetForegroundWindow was refused");
+            }
+        }
+    }
+    activation
+}
+
+/// Whether `hwnd` is still a window of process `pid`
+/// Handles are reused, so a closed window's handle may now belong to another app
+fn owned_by(hwnd: HWND, pid: u32) -> bool {
+    let mut owner = 0u32;
+    unsafe {
+        IsWindow(hwnd).as_bool()
+            && GetWindowThreadProcessId(hwnd, Some(&mut owner)) != 0
+            && owner == pid
+    }
+}
+
+/// First visible top-level window of process `pid` (None once it has quit)
+fn app_window(pid: u32) -> Option<HWND> {
+    struct Search {
+        pid: u32,
+        found: Option<HWND>,
+    }
+
+    unsafe extern "system" fn visit(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let search = &mut *(lparam.0 as *mut Search);
+        if IsWindowVisible(hwnd).as_bool() && owned_by(hwnd, search.pid) {
+            search.found = Some(hwnd);
+            return BOOL(0);
+        }
+        BOOL(1)
+    }
+
+    let mut search = Search { pid, found: None };
+    // EnumWindows reports the stop as an error; the result is in `found`
+    let _ = unsafe { EnumWindows(Some(visit), LPARAM(&mut search as *mut Search as isize)) };
+    search.found
+}
@@ -0,0 +1,86 @@
+//! Permission checks through the Accessibility, CoreGraphics and TCC APIs
+
+use super::{GrantState, PermissionKind, PermissionStatus};
+use crate::error::{ClipsterError, Context};
+use core_foundation::base::TCFType;
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::string::{CFString, CFStringRef};
+use std::fs::File;
+use std::io::ErrorKind;
+
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    static kAXTrustedCheckOptionPrompt: CFStringRef;
+    fn AXIsProcessTrustedWithOptions(options: CFDictionaryRef) -> bool;
+}
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    /// macOS 10.15+
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app is trusted for Accessibility; `prompt` shows the system
+/// prompt when it isn't
+pub(crate) fn accessibility_trusted(prompt: bool) -> bool {
+    unsafe {
+        let key = CFString::wrap_under_get_rule(kAXTrustedCheckOptionPrompt);
+        let options = CFDictionary::from_CFType_pairs(&[(key, CFBoolean::from(prompt))]);
+        AXIsProcessTrustedWithOptions(options.as_concrete_TypeRef())
+    }
+}
+
+/// Full Disk Access has no query API; the TCC database is only readable with it
+fn full_disk_access() -> GrantState {
+    let Some(home) = dirs::home_dir() else {
+        return GrantState::Unknown;
+    };
+    let tcc = home.join("Library/Application Support/com.apple.TCC/TCC.db");
+    match File::open(tcc) {
+        Ok(_) => GrantState::Granted,
+        Err(e) if e.kind() == ErrorKind::PermissionDenied => GrantState::Denied,
+        Err(_) => GrantState::Unknown,
+    }
+}
+
+pub(super) fn state(kind: PermissionKind) -> GrantState {
+    match kind {
+        PermissionKind::Accessibility => GrantState::from_granted(accessibility_trusted(false)),
+        PermissionKind::ScreenRecording => {
+            GrantState::from_granted(unsafe { CGPreflightScreenCaptureAccess() })
+        }
+        PermissionKind::FullDiskAccess => full_disk_access(),
+    }
+}
+
+pub(super) fn check() -> PermissionStatus {
+    PermissionStatus {
+        accessibility: state(PermissionKind::Accessibility),
+        screen_recording: state(PermissionKind::ScreenRecording),
+        full_disk_access: state(PermissionKind::FullDiskAccess),
+    }
+}
+
+/// Show the system prompt for `kind` (Accessibility and Screen Recording)
+pub(super) fn prompt(kind: PermissionKind) {
+    match kind {
+        PermissionKind::Accessibility => {
+            accessibility_trusted(true);
+        }
+        PermissionKind::ScreenRecording => unsafe {
+            CGRequestScreenCaptureAccess();
+        },
+        PermissionKind::FullDiskAccess => {}
+    }
+}
+
+/// Open the System Settings pane of `kind`
+pub(super) fn open_settings(kind: PermissionKind) -> Result<(), ClipsterError> {
+    std::process::Command::new("open")
+        .arg(kind.settings_url())
+        .spawn()
+        .context("Failed to open System Settings")?;
+    Ok(())
+}
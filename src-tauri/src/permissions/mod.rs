@@ -0,0 +1,276 @@
+//! OS permissions the app's features depend on
+//!
+//! On macOS, typing items and raising the paste target's window need
+//! Accessibility, window titles need Screen Recording (names of other apps'
+//! windows are hidden without it) and Quick Look thumbnails of files in
+//! protected folders need Full Disk Access. Without them those features fail
+//! silently, so the first two check here before running; Full Disk Access is
+//! only reported. `check_permissions` reports each grant.
+//! `request_permission` shows the system prompt the first time one exists and
+//! otherwise opens the System Settings pane through its
+//! `x-apple.systempreferences:` URL. Grants are made in System Settings while
+//! the app is in the background, so `permissions-changed` is emitted when the
+//! app regains focus with a different status. Other platforms need none of
+//! them and report everything as granted.
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+#[cfg(target_os = "macos")]
+pub(crate) use macos::accessibility_trusted;
+
+use crate::error::ClipsterError;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// A permission a feature depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    /// Typing items and raising the paste target's window
+    Accessibility,
+    /// Window titles of other apps
+    ScreenRecording,
+    /// Thumbnails of files in protected folders
+    FullDiskAccess,
+}
+
+impl PermissionKind {
+    /// System Settings pane where the permission is granted
+    pub fn settings_url(self) -> &'static str {
+        match self {
+            PermissionKind::Accessibility => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility"
+            }
+            PermissionKind::ScreenRecording => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+            }
+            PermissionKind::FullDiskAccess => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_AllFiles"
+            }
+        }
+    }
+
+    /// Whether macOS has a prompt for it (shown once per app)
+    fn has_prompt(self) -> bool {
+        matches!(
+            self,
+            PermissionKind::Accessibility | PermissionKind::ScreenRecording
+        )
+    }
+}
+
+/// Whether a permission is granted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantState {
+    Granted,
+    Denied,
+    /// Can't be told without using it (Full Disk Access on a system without
+    /// the file it is checked with)
+    Unknown,
+}
+
+impl GrantState {
+    pub fn from_granted(granted: bool) -> Self {
+        if granted {
+            GrantState::Granted
+        } else {
+            GrantState::Denied
+        }
+    }
+}
+
+/// Grant state of every permission, returned by `check_permissions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PermissionStatus {
+    pub accessibility: GrantState,
+    pub screen_recording: GrantState,
+    pub full_disk_access: GrantState,
+}
+
+impl PermissionStatus {
+    /// Everything granted, for platforms that need no permission
+    pub const ALL_GRANTED: Self = Self {
+        accessibility: GrantState::Granted,
+        screen_recording: GrantState::Granted,
+        full_disk_access: GrantState::Granted,
+    };
+
+    pub fn get(&self, kind: PermissionKind) -> GrantState {
+        match kind {
+            PermissionKind::Accessibility => self.accessibility,
+            PermissionKind::ScreenRecording => self.screen_recording,
+            PermissionKind::FullDiskAccess => self.full_disk_access,
+        }
+    }
+}
+
+/// What `request_permission` did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestOutcome {
+    AlreadyGranted,
+    /// The system prompt was shown
+    Prompted,
+    /// The System Settings pane was opened
+    OpenedSettings,
+}
+
+/// Permissions whose prompt was shown this session; macOS shows each once,
+/// so asking again opens System Settings instead
+static PROMPTED: Mutex<Vec<PermissionKind>> = Mutex::new(Vec::new());
+
+/// Status seen when the app last had focus
+static LAST_STATUS: Mutex<Option<PermissionStatus>> = Mutex::new(None);
+
+/// How to ask for `kind` given its state and whether its prompt was shown
+pub fn request_outcome(kind: PermissionKind, state: GrantState, prompted: bool) -> RequestOutcome {
+    if state == GrantState::Granted {
+        RequestOutcome::AlreadyGranted
+    } else if kind.has_prompt() && !prompted {
+        RequestOutcome::Prompted
+    } else {
+        RequestOutcome::OpenedSettings
+    }
+}
+
+/// Grant state of every permission, without prompting
+pub fn check() -> PermissionStatus {
+    #[cfg(target_os = "macos")]
+    return macos::check();
+
+    #[cfg(not(target_os = "macos"))]
+    PermissionStatus::ALL_GRANTED
+}
+
+/// Whether `kind` is granted (or can't be told), without prompting
+pub fn is_granted(kind: PermissionKind) -> bool {
+    #[cfg(target_os = "macos")]
+    return macos::state(kind) != GrantState::Denied;
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = kind;
+        true
+    }
+}
+
+/// Fail with `PermissionDenied` when `kind` is not granted
+pub fn require(kind: PermissionKind, feature: &str) -> Result<(), ClipsterError> {
+    if is_granted(kind) {
+        return Ok(());
+    }
+    let name = match kind {
+        PermissionKind::Accessibility => "Accessibility",
+        PermissionKind::ScreenRecording => "Screen Recording",
+        PermissionKind::FullDiskAccess => "Full Disk Access",
+    };
+    Err(ClipsterError::PermissionDenied(format!(
+        "{} needs the {} permission",
+        feature, name
+    )))
+}
+
+/// Show the system prompt for `kind`, or open its System Settings pane
+pub fn request(kind: PermissionKind) -> Result<RequestOutcome, ClipsterError> {
+    let state = check().get(kind);
+    let outcome = {
+        let mut prompted = PROMPTED.lock()?;
+        let outcome = request_outcome(kind, state, prompted.contains(&kind));
+        if outcome == RequestOutcome::Prompted {
+            prompted.push(kind);
+        }
+        outcome
+    };
+
+    #[cfg(target_os = "macos")]
+    match outcome {
+        RequestOutcome::AlreadyGranted => {}
+        RequestOutcome::Prompted => macos::prompt(kind),
+        RequestOutcome::OpenedSettings => macos::open_settings(kind)?,
+    }
+    Ok(outcome)
+}
+
+/// Re-check after the app regains focus; returns the new status when it
+/// differs from the last one seen, for `permissions-changed`
+pub fn recheck() -> Option<PermissionStatus> {
+    let status = check();
+    let mut last = LAST_STATUS.lock().ok()?;
+    let changed = last.replace(status).is_some_and(|last| last != status);
+    changed.then_some(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_prompts_once_then_opens_settings() {
+        use PermissionKind::*;
+        use RequestOutcome::*;
+
+        assert_eq!(
+            request_outcome(Accessibility, GrantState::Granted, false),
+            AlreadyGranted
+        );
+        assert_eq!(
+            request_outcome(Accessibility, GrantState::Denied, false),
+            Prompted
+        );
+        assert_eq!(
+            request_outcome(Accessibility, GrantState::Denied, true),
+            OpenedSettings
+        );
+        assert_eq!(
+            request_outcome(ScreenRecording, GrantState::Denied, false),
+            Prompted
+        );
+        // No prompt exists for Full Disk Access
+        assert_eq!(
+            request_outcome(FullDiskAccess, GrantState::Unknown, false),
+            OpenedSettings
+        );
+    }
+
+    #[test]
+    fn test_settings_urls() {
+        assert!(PermissionKind::ScreenRecording
+            .settings_url()
+            .ends_with("?Privacy_ScreenCapture"));
+        for kind in [
+            PermissionKind::Accessibility,
+            PermissionKind::ScreenRecording,
+            PermissionKind::FullDiskAccess,
+        ] {
+            assert!(kind
+                .settings_url()
+                .starts_with("x-apple.systempreferences:com.apple.preference.security?"));
+        }
+    }
+
+    #[test]
+    fn test_status_serialization() {
+        let status = PermissionStatus {
+            screen_recording: GrantState::Denied,
+            full_disk_access: GrantState::Unknown,
+            ..PermissionStatus::ALL_GRANTED
+        };
+        assert_eq!(
+            status.get(PermissionKind::ScreenRecording),
+            GrantState::Denied
+        );
+        assert_eq!(
+            serde_json::to_value(status).unwrap(),
+            serde_json::json!({
+                "accessibility": "granted",
+                "screen_recording": "denied",
+                "full_disk_access": "unknown",
+            })
+        );
+        let kind: PermissionKind = serde_json::from_str("\"full_disk_access\"").unwrap();
+        assert_eq!(kind, PermissionKind::FullDiskAccess);
+    }
+}
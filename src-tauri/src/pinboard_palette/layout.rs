@@ -0,0 +1,89 @@
+/// A rectangle in logical points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Space between the caret (or cursor) and the palette
+const ANCHOR_OFFSET: f64 = 8.0;
+
+/// Top-left corner of a `width` x `height` palette opened at `anchor`
+///
+/// The palette goes below and to the right of the anchor, flips above it when
+/// there is no room below, and is kept inside `screen`.
+pub fn palette_origin(anchor: (f64, f64), width: f64, height: f64, screen: Area) -> (f64, f64) {
+    let (anchor_x, anchor_y) = anchor;
+    let screen_right = screen.x + screen.width;
+    let screen_bottom = screen.y + screen.height;
+
+    let x = anchor_x.min(screen_right - width).max(screen.x);
+
+    let below = anchor_y + ANCHOR_OFFSET;
+    let above = anchor_y - ANCHOR_OFFSET - height;
+    let y = if below + height <= screen_bottom || above < screen.y {
+        below.min(screen_bottom - height)
+    } else {
+        above
+    };
+
+    (x, y.max(screen.y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Area = Area {
+        x: 0.0,
+        y: 0.0,
+        width: 1440.0,
+        height: 900.0,
+    };
+
+    #[test]
+    fn test_opens_below_anchor() {
+        assert_eq!(
+            palette_origin((100.0, 200.0), 320.0, 300.0, SCREEN),
+            (100.0, 208.0)
+        );
+    }
+
+    #[test]
+    fn test_flips_above_near_bottom_edge() {
+        assert_eq!(
+            palette_origin((100.0, 800.0), 320.0, 300.0, SCREEN),
+            (100.0, 492.0)
+        );
+    }
+
+    #[test]
+    fn test_stays_on_screen() {
+        // Near the right edge
+        assert_eq!(
+            palette_origin((1400.0, 100.0), 320.0, 300.0, SCREEN),
+            (1120.0, 108.0)
+        );
+        // Taller than the room both above and below
+        assert_eq!(
+            palette_origin((100.0, 450.0), 320.0, 600.0, SCREEN),
+            (100.0, 300.0)
+        );
+    }
+
+    #[test]
+    fn test_secondary_monitor_coordinates() {
+        let left_screen = Area {
+            x: -1920.0,
+            y: -200.0,
+            width: 1920.0,
+            height: 1080.0,
+        };
+        assert_eq!(
+            palette_origin((-2000.0, -300.0), 320.0, 300.0, left_screen),
+            (-1920.0, -200.0)
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Pinboard palette: a compact popup listing one pinboard's items
+//!
+//! Each pinboard can be bound to its own global shortcut (the
+//! `pinboard_shortcuts` setting, pinboard ID -> shortcut). The shortcut opens
+//! a small window at the text caret, or at the cursor when the caret can't be
+//! found, listing the board's first items; the frontend loads them with
+//! `get_pinboard_items` for the pinboard in the `palette` query parameter.
+//! A number key copies that item back through the copy-back path and closes
+//! the palette.
+
+pub mod layout;
+pub mod palette_state;
+
+pub use palette_state::PaletteState;
+
+use crate::clipboard::clipboard_reader;
+use crate::clipboard::copy_back;
+use crate::commands::window_commands::{
+    cursor_monitor_bounds, focus_window, order_out, setup_window_behavior, show_panel,
+};
+use crate::error::ClipsterError;
+use crate::AppState;
+use chrono::Utc;
+use layout::Area;
+use palette_state::MAX_PALETTE_ITEMS;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+/// Label of the pinboard palette window
+pub const PALETTE_WINDOW_LABEL: &str = "palette";
+
+/// Size of the palette window, in logical points
+const PALETTE_WIDTH: f64 = 320.0;
+const PALETTE_HEIGHT: f64 = 360.0;
+
+/// Open palette and registered palette shortcuts
+#[derive(Default)]
+pub struct PinboardPalette {
+    state: Mutex<PaletteState>,
+    /// Registered shortcuts and the pinboard each one opens
+    shortcuts: Mutex<HashMap<Shortcut, String>>,
+}
+
+impl PinboardPalette {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Parse a `pinboard_shortcuts` mapping (pinboard ID -> shortcut)
+/// Shortcuts already used by the app (`reserved`) or by two pinboards are rejected
+pub fn parse_shortcuts(
+    mapping: &HashMap<String, String>,
+    reserved: &[Shortcut],
+) -> Result<HashMap<Shortcut, String>, ClipsterError> {
+    let mut shortcuts = HashMap::new();
+    for (pinboard_id, text) in mapping {
+        let shortcut: Shortcut = text.parse().map_err(|e| {
+            ClipsterError::Validation(format!("Invalid shortcut \"{}\": {}", text, e))
+        })?;
+        if reserved.contains(&shortcut) {
+            return Err(ClipsterError::Validation(format!(
+                "Shortcut \"{}\" is already used by Clipster",
+                text
+            )));
+        }
+        if shortcuts.insert(shortcut, pinboard_id.clone()).is_some() {
+            return Err(ClipsterError::Validation(format!(
+                "Shortcut \"{}\" is assigned to more than one pinboard",
+                text
+            )));
+        }
+    }
+    Ok(shortcuts)
+}
+
+/// Replace the registered palette shortcuts
+pub fn register_shortcuts(app: &AppHandle, shortcuts: HashMap<Shortcut, String>) {
+    let state = app.state::<AppState>();
+    let Ok(mut registered) = state.pinboard_palette.shortcuts.lock() else {
+        return;
+    };
+
+    for shortcut in registered.keys() {
+        if let Err(e) = app.global_shortcut().unregister(*shortcut) {
+            eprintln!("[pinboard_palette] Failed to unregister shortcut: {}", e);
+        }
+    }
+    registered.clear();
+
+    for (shortcut, pinboard_id) in shortcuts {
+        match app.global_shortcut().register(shortcut) {
+            Ok(()) => {
+                registered.insert(shortcut, pinboard_id);
+            }
+            Err(e) => eprintln!(
+                "[pinboard_palette] Failed to register {} for pinboard {}: {}",
+                shortcut.into_string(),
+                pinboard_id,
+                e
+            ),
+        }
+    }
+}
+
+/// Register the shortcuts saved in the `pinboard_shortcuts` setting
+/// Invalid saved shortcuts are skipped
+pub fn register_configured_shortcuts(app: &AppHandle, reserved: &[Shortcut]) {
+    let state = app.state::<AppState>();
+    let mapping = match state.db.get_pinboard_shortcuts() {
+        Ok(mapping) => mapping,
+        Err(e) => {
+            eprintln!("[pinboard_palette] Failed to read shortcuts: {}", e);
+            return;
+        }
+    };
+
+    let mut shortcuts = HashMap::new();
+    for (pinboard_id, text) in mapping {
+        let single = HashMap::from([(pinboard_id, text)]);
+        match parse_shortcuts(&single, reserved) {
+            Ok(parsed) => shortcuts.extend(parsed),
+            Err(e) => eprintln!("[pinboard_palette] Skipping shortcut: {}", e),
+        }
+    }
+    register_shortcuts(app, shortcuts);
+}
+
+/// Pinboard opened by a palette shortcut
+pub fn pinboard_for_shortcut(app: &AppHandle, shortcut: &Shortcut) -> Option<String> {
+    let state = app.state::<AppState>();
+    let registered = state.pinboard_palette.shortcuts.lock().ok()?;
+    registered.get(shortcut).cloned()
+}
+
+/// Open the palette of a pinboard at the text caret (or the cursor)
+pub fn show(app: &AppHandle, pinboard_id: &str) -> Result<(), ClipsterError> {
+    let state = app.state::<AppState>();
+    state
+        .db
+        .get_pinboard(pinboard_id)?
+        .ok_or_else(|| ClipsterError::NotFound(format!("Pinboard not found: {}", pinboard_id)))?;
+    state
+        .pinboard_locks
+        .require_pinboard_unlocked(&state.db, pinboard_id, Utc::now())?;
+
+    let items = state
+        .db
+        .get_pinboard_items(pinboard_id, MAX_PALETTE_ITEMS, 0, false)?;
+    state
+        .pinboard_palette
+        .state
+        .lock()?
+        .open(pinboard_id, items.into_iter().map(|item| item.id).collect());
+
+    // Where the user is typing, measured before the palette takes focus
+    let anchor = caret_position().or_else(|| {
+        app.cursor_position()
+            .ok()
+            .map(|position| (position.x, position.y))
+    });
+
+    let window = match app.get_webview_window(PALETTE_WINDOW_LABEL) {
+        Some(window) => {
+            let mut url = window
+                .url()
+                .map_err(|e| ClipsterError::Io(format!("Failed to read palette URL: {}", e)))?;
+            url.query_pairs_mut()
+                .clear()
+                .append_pair("palette", pinboard_id);
+            window.navigate(url).map_err(|e| {
+                ClipsterError::Io(format!("Failed to navigate palette window: {}", e))
+            })?;
+            window
+        }
+        None => {
+            let url = WebviewUrl::App(format!("index.html?palette={}", pinboard_id).into());
+            let window = WebviewWindowBuilder::new(app, PALETTE_WINDOW_LABEL, url)
+                .title("Clipster Palette")
+                .decorations(false)
+                .resizable(false)
+                .skip_taskbar(true)
+                .inner_size(PALETTE_WIDTH, PALETTE_HEIGHT)
+                .visible(false)
+                .build()
+                .map_err(|e| {
+                    ClipsterError::Io(format!("Failed to create palette window: {}", e))
+                })?;
+            setup_window_behavior(&window);
+            window
+        }
+    };
+
+    if let (Some((x, y)), Some(bounds)) = (anchor, cursor_monitor_bounds(&window)) {
+        // Caret and cursor positions are physical pixels
+        let scale = window.scale_factor().unwrap_or(1.0);
+        let screen = Area {
+            x: bounds.x,
+            y: bounds.y,
+            width: bounds.width,
+            height: bounds.height,
+        };
+        let (x, y) = layout::palette_origin(
+            (x / scale, y / scale),
+            PALETTE_WIDTH,
+            PALETTE_HEIGHT,
+            screen,
+        );
+        let _ = window.set_position(tauri::LogicalPosition::new(x, y));
+    } else {
+        let _ = window.center();
+    }
+
+    show_panel(&window);
+    // Number keys go to the palette right away
+    focus_window(&window);
+    Ok(())
+}
+
+/// Copy the palette item at `index` (0-based) back to the clipboard and close
+/// the palette; false if there is no item at `index`
+pub fn select(app: &AppHandle, index: usize) -> Result<bool, ClipsterError> {
+    let state = app.state::<AppState>();
+    let item_id = state.pinboard_palette.state.lock()?.select(index);
+    let Some(item_id) = item_id else {
+        return Ok(false);
+    };
+
+    // Hide first so focus is back in the app the user was typing in
+    hide(app);
+    // No transform of its own, so the board's `default_transform` applies
+    let strip_metadata = state.db.get_setting("strip_image_metadata")?.as_deref() == Some("true");
+    copy_back::copy_item(&state.db, &item_id, None, false, |content, exclude| {
+        clipboard_reader::write_copy_content(content, strip_metadata, exclude)
+    })?;
+    Ok(true)
+}
+
+/// Close the palette
+pub fn hide(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(mut palette) = state.pinboard_palette.state.lock() {
+        palette.close();
+    }
+    if let Some(window) = app.get_webview_window(PALETTE_WINDOW_LABEL) {
+        order_out(&window);
+    }
+}
+
+/// Screen position (physical pixels) of the text caret in the foreground app
+/// Only apps using the system caret report one; others fall back to the cursor
+#[cfg(target_os = "windows")]
+fn caret_position() -> Option<(f64, f64)> {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::Graphics::Gdi::ClientToScreen;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetGUIThreadInfo, GetWindowThreadProcessId, GUITHREADINFO,
+    };
+
+    unsafe {
+        let foreground = GetForegroundWindow();
+        let thread_id = GetWindowThreadProcessId(foreground, None);
+        let mut info = GUITHREADINFO {
+            cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+            ..Default::default()
+        };
+        GetGUIThreadInfo(thread_id, &mut info).ok()?;
+        if info.hwndCaret.is_invalid() {
+            return None;
+        }
+
+        // Bottom-left of the caret, so the palette opens under the text line
+        let mut point = POINT {
+            x: info.rcCaret.left,
+            y: info.rcCaret.bottom,
+        };
+        ClientToScreen(info.hwndCaret, &mut point).ok().ok()?;
+        Some((point.x as f64, point.y as f64))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn caret_position() -> Option<(f64, f64)> {
+    None
+}
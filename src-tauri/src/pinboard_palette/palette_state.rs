@@ -0,0 +1,88 @@
+/// Items listed in the palette, one per number key (1-9)
+pub const MAX_PALETTE_ITEMS: usize = 9;
+
+/// The pinboard palette currently open, if any, and the items it lists
+#[derive(Debug, Default)]
+pub struct PaletteState {
+    pinboard_id: Option<String>,
+    item_ids: Vec<String>,
+}
+
+impl PaletteState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open the palette of `pinboard_id`, listing the first `MAX_PALETTE_ITEMS`
+    /// of `item_ids` (a palette already open is replaced)
+    pub fn open(&mut self, pinboard_id: &str, mut item_ids: Vec<String>) {
+        item_ids.truncate(MAX_PALETTE_ITEMS);
+        self.pinboard_id = Some(pinboard_id.to_string());
+        self.item_ids = item_ids;
+    }
+
+    /// Pinboard of the open palette
+    pub fn pinboard_id(&self) -> Option<&str> {
+        self.pinboard_id.as_deref()
+    }
+
+    /// Take the item at `index` (0-based) and close the palette
+    /// An index without an item leaves the palette open
+    pub fn select(&mut self, index: usize) -> Option<String> {
+        self.pinboard_id.as_ref()?;
+        let item_id = self.item_ids.get(index)?.clone();
+        self.close();
+        Some(item_id)
+    }
+
+    pub fn close(&mut self) {
+        self.pinboard_id = None;
+        self.item_ids.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(count: usize) -> Vec<String> {
+        (0..count).map(|i| format!("item-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_select_by_number_key_closes_palette() {
+        let mut palette = PaletteState::new();
+        assert_eq!(palette.select(0), None);
+
+        palette.open("snippets", ids(3));
+        assert_eq!(palette.pinboard_id(), Some("snippets"));
+        // No item under that key: nothing happens
+        assert_eq!(palette.select(5), None);
+        assert_eq!(palette.pinboard_id(), Some("snippets"));
+
+        assert_eq!(palette.select(1).as_deref(), Some("item-1"));
+        assert_eq!(palette.pinboard_id(), None);
+        assert_eq!(palette.select(1), None);
+    }
+
+    #[test]
+    fn test_palette_lists_one_item_per_number_key() {
+        let mut palette = PaletteState::new();
+        palette.open("snippets", ids(20));
+        assert_eq!(palette.select(MAX_PALETTE_ITEMS), None);
+        assert_eq!(
+            palette.select(MAX_PALETTE_ITEMS - 1).as_deref(),
+            Some("item-8")
+        );
+    }
+
+    #[test]
+    fn test_reopening_replaces_palette() {
+        let mut palette = PaletteState::new();
+        palette.open("snippets", ids(2));
+        palette.open("addresses", vec!["home".to_string()]);
+        assert_eq!(palette.pinboard_id(), Some("addresses"));
+        assert_eq!(palette.select(1), None);
+        assert_eq!(palette.select(0).as_deref(), Some("home"));
+    }
+}
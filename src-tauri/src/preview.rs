@@ -0,0 +1,275 @@
+//! One-line previews of clipboard content
+//!
+//! Every place that names an item in a single line goes through here: the
+//! `preview` field of serialized items, Spotlight titles, drag-out file names,
+//! duplicate groups and the capture logs. Line breaks become ␤ (blank lines
+//! and the indentation around them are dropped), other control characters
+//! become spaces, and a preview over `max_chars` is cut between grapheme
+//! clusters and ends with "…", so it never has more than `max_chars`
+//! characters and never ends in half an emoji or a bare combining accent.
+
+use crate::models::{ClipboardItem, ContentType};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Length of the `preview` field of items, in characters
+pub const PREVIEW_MAX_CHARS: usize = 100;
+
+/// Shown in place of line breaks
+const NEWLINE_MARK: char = '␤';
+
+/// One-line preview of `text`, at most `max_chars` characters
+pub fn preview_text(text: &str, max_chars: usize) -> String {
+    truncate(&single_line(text, max_chars), max_chars)
+}
+
+/// One-line description of a file list: the file name of a single file,
+/// the count of several
+pub fn preview_paths(paths: &[String]) -> String {
+    match paths {
+        [] => String::new(),
+        [path] => single_line(file_name(path), usize::MAX),
+        _ => format!("{} files", paths.len()),
+    }
+}
+
+/// One-line preview of an item, at most `max_chars` characters
+pub fn preview_item(item: &ClipboardItem, max_chars: usize) -> String {
+    // Generated titles (screenshots) and multi-file summaries describe the item best
+    if let Some(label) = item.title.as_deref().or(item.files_summary.as_deref()) {
+        return preview_text(label, max_chars);
+    }
+
+    let text = item.content_text.as_deref().unwrap_or("");
+    match item.content_type {
+        ContentType::Text => preview_text(text, max_chars),
+        ContentType::Link => preview_text(link_host(text), max_chars),
+        ContentType::Image => preview_text("[Image]", max_chars),
+        ContentType::Files | ContentType::Audio | ContentType::Documents => {
+            preview_text(&item_paths_preview(item), max_chars)
+        }
+    }
+}
+
+/// File list preview of a files, audio or documents item
+fn item_paths_preview(item: &ClipboardItem) -> String {
+    let paths = item.get_file_paths().unwrap_or_default();
+    match (item.content_type, paths.len()) {
+        (ContentType::Audio, 0) => "[Audio]".to_string(),
+        (ContentType::Documents, 0) => "[Documents]".to_string(),
+        (_, 0) => "[Files]".to_string(),
+        (ContentType::Audio, n) if n > 1 => format!("{} audio files", n),
+        (ContentType::Documents, n) if n > 1 => format!("{} documents", n),
+        _ => preview_paths(&paths),
+    }
+}
+
+/// Host of a URL ("https://example.com/a" → "example.com"), or the URL itself
+fn link_host(url: &str) -> &str {
+    if let Some(start) = url.find("://") {
+        let after_proto = &url[start + 3..];
+        return after_proto.split('/').next().unwrap_or(after_proto);
+    }
+    if url.starts_with("www.") {
+        return url.split('/').next().unwrap_or(url);
+    }
+    url
+}
+
+/// Last component of a Windows or Unix path (a folder's own name with a trailing separator)
+fn file_name(path: &str) -> &str {
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    match trimmed.rsplit(['/', '\\']).next() {
+        Some(name) if !name.is_empty() => name,
+        _ => path,
+    }
+}
+
+fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Join the non-blank lines of `text` with ␤ and replace control characters
+/// Stops after `max_chars + 1` characters: enough to know the text is cut, and
+/// huge texts aren't copied whole
+fn single_line(text: &str, max_chars: usize) -> String {
+    let mut line = String::new();
+    let mut chars = 0usize;
+    let lines = text
+        .split(is_line_break)
+        .map(str::trim)
+        .filter(|l| !l.is_empty());
+    for (i, part) in lines.enumerate() {
+        let separator = (i > 0).then_some(NEWLINE_MARK);
+        for c in separator.into_iter().chain(part.chars()) {
+            if chars > max_chars {
+                return line;
+            }
+            line.push(if c.is_control() { ' ' } else { c });
+            chars += 1;
+        }
+    }
+    line
+}
+
+/// Cut `line` to at most `max_chars` characters, ending with "…" when cut
+/// The cut falls between grapheme clusters
+fn truncate(line: &str, max_chars: usize) -> String {
+    if line.chars().count() <= max_chars {
+        return line.to_string();
+    }
+    if max_chars == 0 {
+        return String::new();
+    }
+
+    // One character is kept for the ellipsis
+    let budget = max_chars - 1;
+    let mut end = 0;
+    let mut used = 0;
+    for (start, grapheme) in line.grapheme_indices(true) {
+        let chars = grapheme.chars().count();
+        if used + chars > budget {
+            break;
+        }
+        used += chars;
+        end = start + grapheme.len();
+    }
+    let kept = line[..end].trim_end_matches(|c: char| c.is_whitespace() || c == NEWLINE_MARK);
+    format!("{}…", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Strings that break byte slicing, char counting or naive newline handling
+    const CORPUS: &[&str] = &[
+        "",
+        "plain ascii text that is rather long, longer than most limits used here",
+        "héllo wörld, ça va très bien",
+        "日本語のテキストはマルチバイトです",
+        "👍👍👍👍👍👍👍👍👍👍",
+        "👨‍👩‍👧‍👦 family 👨‍👩‍👧‍👦 family 👨‍👩‍👧‍👦",
+        "🇫🇷🇩🇪🇯🇵🇺🇸🇬🇧🇮🇹",
+        "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}",
+        "Z\u{351}\u{36b}\u{343}\u{36a}a\u{302}\u{36b}\u{33d}l\u{35b}g\u{310}o\u{36f}",
+        "line one\nline two\r\nline three\rline four",
+        "\n\n\n   \n\t\n",
+        "tabs\tand\u{0}nulls\u{7}and bells",
+        "  indented\n    code\n        block  ",
+        "paragraph\u{2029}separator\u{2028}line\u{85}next",
+        "שלום עולם مرحبا بالعالم",
+        "a\u{200d}b\u{200d}c\u{fe0f}d",
+    ];
+
+    #[test]
+    fn test_corpus_respects_limit_and_graphemes() {
+        for text in CORPUS {
+            let flat = single_line(text, usize::MAX);
+            let boundaries: Vec<usize> = flat
+                .grapheme_indices(true)
+                .map(|(i, _)| i)
+                .chain(std::iter::once(flat.len()))
+                .collect();
+
+            for max_chars in 0..=40 {
+                let preview = preview_text(text, max_chars);
+                assert!(
+                    preview.chars().count() <= max_chars,
+                    "{:?} at {}: {:?}",
+                    text,
+                    max_chars,
+                    preview
+                );
+                assert!(!preview.contains(['\n', '\r', '\t', '\u{2028}', '\u{0}']));
+
+                // What is kept before the ellipsis ends on a grapheme boundary
+                let kept = preview.strip_suffix('…').unwrap_or(&preview);
+                assert!(flat.starts_with(kept), "{:?} at {}", text, max_chars);
+                assert!(
+                    boundaries.contains(&kept.len()),
+                    "{:?} at {}: {:?}",
+                    text,
+                    max_chars,
+                    preview
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_short_text_is_kept_whole() {
+        for text in CORPUS {
+            let flat = single_line(text, usize::MAX);
+            assert_eq!(preview_text(text, flat.chars().count()), flat);
+        }
+        assert_eq!(preview_text("hello", 5), "hello");
+    }
+
+    #[test]
+    fn test_single_line() {
+        assert_eq!(preview_text("a\nb", 10), "a␤b");
+        // Blank lines, CRLF and indentation collapse into one mark
+        assert_eq!(
+            preview_text("  fn main() {\r\n\r\n    run();\n}\n", 40),
+            "fn main() {␤run();␤}"
+        );
+        assert_eq!(preview_text("a\tb\u{0}c", 10), "a b c");
+        assert_eq!(preview_text("\n \n", 10), "");
+    }
+
+    #[test]
+    fn test_ellipsis() {
+        assert_eq!(preview_text("abcdefgh", 5), "abcd…");
+        assert_eq!(preview_text("abc defgh", 5), "abc…");
+        // A cut right after a line break doesn't end on the mark
+        assert_eq!(preview_text("abc\ndefgh", 5), "abc…");
+        assert_eq!(preview_text("abcdefgh", 1), "…");
+        assert_eq!(preview_text("abcdefgh", 0), "");
+        // A family emoji is 7 characters: kept whole or not at all
+        let family = "👨‍👩‍👧‍👦";
+        assert_eq!(
+            preview_text(&format!("{}{}", family, family), 8),
+            format!("{}…", family)
+        );
+        assert_eq!(preview_text(&format!("{}{}", family, family), 7), "…");
+        assert_eq!(preview_text("e\u{301}e\u{301}e\u{301}", 4), "e\u{301}…");
+    }
+
+    #[test]
+    fn test_preview_paths() {
+        let paths = |list: &[&str]| list.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(preview_paths(&[]), "");
+        assert_eq!(preview_paths(&paths(&["/Users/me/Trip/a.jpg"])), "a.jpg");
+        assert_eq!(preview_paths(&paths(&["C:\\Users\\me\\b.txt"])), "b.txt");
+        assert_eq!(preview_paths(&paths(&["/Users/me/Trip/"])), "Trip");
+        assert_eq!(preview_paths(&paths(&["/p/bad\nname.txt"])), "bad␤name.txt");
+        assert_eq!(preview_paths(&paths(&["/p/a", "/p/b", "/p/c"])), "3 files");
+    }
+
+    #[test]
+    fn test_preview_item() {
+        let text = ClipboardItem::new_text("first\nsecond".to_string(), None, None);
+        assert_eq!(preview_item(&text, 50), "first␤second");
+        assert_eq!(preview_item(&text, 4), "fir…");
+
+        let link = ClipboardItem::new_link("https://example.com/a/b?c=d".to_string(), None, None);
+        assert_eq!(preview_item(&link, 50), "example.com");
+        let www = ClipboardItem::new_link("www.example.com/page".to_string(), None, None);
+        assert_eq!(preview_item(&www, 50), "www.example.com");
+
+        let audio = ClipboardItem::new_audio(
+            vec!["/m/a.mp3".to_string(), "/m/b.mp3".to_string()],
+            None,
+            None,
+        );
+        assert_eq!(preview_item(&audio, 50), "2 audio files");
+
+        let mut files = ClipboardItem::new_files(vec!["/p/a.jpg".to_string()], None, None);
+        assert_eq!(preview_item(&files, 50), "a.jpg");
+        files.title = Some("Screenshot 2024-05-12 14:03".to_string());
+        assert_eq!(preview_item(&files, 10), "Screensho…");
+    }
+}
@@ -0,0 +1,201 @@
+//! State machine for the hold-hotkey history cycle
+//!
+//! Pure logic only: the global-shortcut glue in `quick_cycle` feeds it
+//! presses and the release of the modifier chord, so every transition is
+//! testable without OS hooks.
+
+/// Current phase of the cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CyclePhase {
+    /// No cycle in progress
+    Idle,
+    /// Stepping through `item_ids`; `index` is the current candidate
+    Cycling { item_ids: Vec<String>, index: usize },
+}
+
+/// Hold-hotkey cycle state
+#[derive(Debug, Clone)]
+pub struct CycleState {
+    phase: CyclePhase,
+    /// Incremented when a cycle starts, so the watcher of a cycle that was
+    /// cancelled can't commit the next one
+    cycle: u64,
+}
+
+impl CycleState {
+    pub fn new() -> Self {
+        Self {
+            phase: CyclePhase::Idle,
+            cycle: 0,
+        }
+    }
+
+    pub fn phase(&self) -> &CyclePhase {
+        &self.phase
+    }
+
+    pub fn is_active(&self) -> bool {
+        matches!(self.phase, CyclePhase::Cycling { .. })
+    }
+
+    /// Number of the current (or last) cycle
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// Current candidate as (item ID, index, total)
+    pub fn candidate(&self) -> Option<(&str, usize, usize)> {
+        match &self.phase {
+            CyclePhase::Idle => None,
+            CyclePhase::Cycling { item_ids, index } => {
+                Some((item_ids[*index].as_str(), *index, item_ids.len()))
+            }
+        }
+    }
+
+    /// Handle a trigger press: start a cycle if idle, otherwise advance
+    /// `load_items` is only called when starting (most recent first)
+    /// Returns the new candidate, or None if there is nothing to cycle through
+    pub fn press<F>(&mut self, load_items: F) -> Option<(&str, usize, usize)>
+    where
+        F: FnOnce() -> Vec<String>,
+    {
+        if self.is_active() {
+            self.advance();
+        } else {
+            self.start(load_items());
+        }
+
+        self.candidate()
+    }
+
+    /// Start cycling over `item_ids` (most recent first)
+    /// The first candidate is the previous item, since the most recent one is
+    /// already on the clipboard (falls back to it if it is the only item)
+    pub fn start(&mut self, item_ids: Vec<String>) {
+        self.cycle += 1;
+        self.phase = if item_ids.is_empty() {
+            CyclePhase::Idle
+        } else {
+            let index = if item_ids.len() > 1 { 1 } else { 0 };
+            CyclePhase::Cycling { item_ids, index }
+        };
+    }
+
+    /// Step to the next candidate, wrapping back to the most recent item
+    pub fn advance(&mut self) {
+        if let CyclePhase::Cycling { item_ids, index } = &mut self.phase {
+            *index = (*index + 1) % item_ids.len();
+        }
+    }
+
+    /// Commit the current candidate of cycle number `cycle` and return to idle
+    /// Returns None if no cycle is active or another one started since
+    pub fn commit(&mut self, cycle: u64) -> Option<String> {
+        if cycle != self.cycle {
+            return None;
+        }
+
+        match std::mem::replace(&mut self.phase, CyclePhase::Idle) {
+            CyclePhase::Idle => None,
+            CyclePhase::Cycling { mut item_ids, index } => Some(item_ids.swap_remove(index)),
+        }
+    }
+
+    /// Abort the cycle without committing; returns whether one was active
+    pub fn cancel(&mut self) -> bool {
+        let was_active = self.is_active();
+        self.phase = CyclePhase::Idle;
+        was_active
+    }
+}
+
+impl Default for CycleState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("item-{}", i)).collect()
+    }
+
+    #[test]
+    fn test_start_selects_previous_item() {
+        let mut state = CycleState::new();
+        assert!(!state.is_active());
+        assert_eq!(state.candidate(), None);
+
+        assert_eq!(state.press(|| ids(3)), Some(("item-1", 1, 3)));
+        assert!(state.is_active());
+
+        // Single item: nothing older, so select it
+        let mut state = CycleState::new();
+        assert_eq!(state.press(|| ids(1)), Some(("item-0", 0, 1)));
+    }
+
+    #[test]
+    fn test_start_with_no_items_stays_idle() {
+        let mut state = CycleState::new();
+        assert_eq!(state.press(Vec::new), None);
+        assert!(!state.is_active());
+    }
+
+    #[test]
+    fn test_advance_and_wrap() {
+        let mut state = CycleState::new();
+        state.press(|| ids(3));
+
+        // Items are only loaded when starting
+        assert_eq!(state.press(|| panic!("should not reload")), Some(("item-2", 2, 3)));
+        assert_eq!(state.press(Vec::new), Some(("item-0", 0, 3)));
+        assert_eq!(state.press(Vec::new), Some(("item-1", 1, 3)));
+    }
+
+    #[test]
+    fn test_commit() {
+        let mut state = CycleState::new();
+        state.press(|| ids(3));
+        state.press(Vec::new);
+
+        let cycle = state.cycle();
+        assert_eq!(state.commit(cycle), Some("item-2".to_string()));
+        assert!(!state.is_active());
+
+        // Nothing left to commit
+        assert_eq!(state.commit(cycle), None);
+    }
+
+    #[test]
+    fn test_stale_commit_is_ignored() {
+        let mut state = CycleState::new();
+        state.press(|| ids(3));
+        let cancelled = state.cycle();
+        state.cancel();
+
+        // The chord of the cancelled cycle is released during the next one
+        state.press(|| ids(3));
+        assert_eq!(state.commit(cancelled), None);
+        assert!(state.is_active());
+
+        assert_eq!(state.commit(state.cycle()), Some("item-1".to_string()));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut state = CycleState::new();
+        assert!(!state.cancel());
+
+        state.press(|| ids(3));
+        assert!(state.cancel());
+        assert!(!state.is_active());
+        assert_eq!(state.commit(state.cycle()), None);
+
+        // A new press starts over from the previous item
+        assert_eq!(state.press(|| ids(3)), Some(("item-1", 1, 3)));
+    }
+}
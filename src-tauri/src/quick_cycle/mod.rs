@@ -0,0 +1,154 @@
+//! Hold-hotkey cycle through recent history (like Windows Win+V)
+//!
+//! While the cycle shortcut's modifiers are held, each press of the trigger key
+//! steps to the next recent item and emits `cycle-selection-changed` so a
+//! minimal overlay can render the candidate. The global-shortcut plugin only
+//! reports the trigger key itself, so while a cycle is active the held
+//! modifiers are polled; once the chord is released the selection is copied
+//! back and pasted into the app that has focus.
+
+pub mod cycle_state;
+mod modifiers;
+
+pub use cycle_state::CycleState;
+
+use crate::clipboard::clipboard_reader;
+use crate::clipboard::copy_back;
+use crate::events::{self, CycleCommittedPayload, CycleSelectionPayload};
+use crate::storage::database::HistorySort;
+use crate::storage::Database;
+use crate::type_text;
+use crate::AppState;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{Code, Modifiers, Shortcut, ShortcutState};
+
+/// Default number of recent items to cycle through
+pub const DEFAULT_CYCLE_ITEMS: usize = 10;
+
+/// How often the held modifiers are checked while a cycle is active
+const MODIFIER_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Default cycle shortcut (Ctrl+Alt+V)
+pub fn default_shortcut() -> Shortcut {
+    Shortcut::new(Some(Modifiers::CONTROL | Modifiers::ALT), Code::KeyV)
+}
+
+/// Read the cycle shortcut from the `quick_cycle_shortcut` setting
+pub fn configured_shortcut(db: &Database) -> Shortcut {
+    db.get_setting("quick_cycle_shortcut")
+        .ok()
+        .flatten()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(default_shortcut)
+}
+
+/// Drive the cycle from a press of the cycle `shortcut`
+/// Releasing the trigger key keeps the cycle going until the chord is released
+pub fn handle_shortcut(app: &AppHandle, shortcut: &Shortcut, state: ShortcutState) {
+    if state == ShortcutState::Pressed {
+        on_press(app, shortcut.mods);
+    }
+}
+
+/// Most recent history item IDs, capped by the `quick_cycle_items` setting
+fn recent_item_ids(db: &Database) -> Vec<String> {
+    let limit = db
+        .get_setting("quick_cycle_items")
+        .ok()
+        .flatten()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_CYCLE_ITEMS);
+
+    db.get_items(limit, 0, false, HistorySort::CreatedAt)
+        .map(|items| items.into_iter().map(|item| item.id).collect())
+        .unwrap_or_default()
+}
+
+fn on_press(app: &AppHandle, chord: Modifiers) {
+    let state = app.state::<AppState>();
+
+    let candidate = match state.quick_cycle.lock() {
+        Ok(mut cycle) => {
+            let starting = !cycle.is_active();
+            let candidate = cycle
+                .press(|| recent_item_ids(&state.db))
+                .map(|(id, index, total)| (id.to_string(), index, total));
+            candidate.map(|candidate| (candidate, starting.then(|| cycle.cycle())))
+        }
+        Err(_) => None,
+    };
+    let Some(((item_id, index, total), started)) = candidate else {
+        return;
+    };
+    if let Some(cycle) = started {
+        commit_on_release(app, cycle, chord);
+    }
+
+    match state.db.get_item(&item_id) {
+        Ok(Some(item)) => {
+            events::emit(app, &CycleSelectionPayload { item, index, total });
+        }
+        Ok(None) => eprintln!("[quick_cycle] Candidate {} no longer exists", item_id),
+        Err(e) => eprintln!("[quick_cycle] Failed to load candidate: {}", e),
+    }
+}
+
+/// Commit cycle number `cycle` once `chord` is no longer held down
+/// Stops watching if the cycle ends first (cancelled from the overlay)
+fn commit_on_release(app: &AppHandle, cycle: u64, chord: Modifiers) {
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(MODIFIER_POLL_INTERVAL);
+        let active = app
+            .state::<AppState>()
+            .quick_cycle
+            .lock()
+            .is_ok_and(|state| state.is_active() && state.cycle() == cycle);
+        if !active {
+            return;
+        }
+        if !modifiers::held().contains(chord) {
+            commit(&app, cycle);
+            return;
+        }
+    });
+}
+
+/// Copy the selection of cycle number `cycle` back and paste it
+fn commit(app: &AppHandle, cycle: u64) {
+    let state = app.state::<AppState>();
+    let item_id = match state.quick_cycle.lock() {
+        Ok(mut state) => state.commit(cycle),
+        Err(_) => None,
+    };
+    let Some(item_id) = item_id else {
+        return;
+    };
+
+    // The pinboard's default transform applies like for any copy-back
+    let strip_metadata = state
+        .db
+        .get_setting("strip_image_metadata")
+        .ok()
+        .flatten()
+        .as_deref()
+        == Some("true");
+    let copied = copy_back::copy_item(&state.db, &item_id, None, false, |content, exclude| {
+        clipboard_reader::write_copy_content(content, strip_metadata, exclude)
+    });
+    if let Err(e) = copied {
+        eprintln!("[quick_cycle] Failed to copy selection: {}", e);
+        return;
+    }
+
+    // The chord was pressed in the app that still has focus
+    if !type_text::has_permission(false) {
+        eprintln!("[quick_cycle] Pasting needs the Accessibility permission");
+    } else if let Err(e) = type_text::send_paste() {
+        eprintln!("[quick_cycle] Failed to paste selection: {}", e);
+    }
+
+    events::emit(app, &CycleCommittedPayload { item_id });
+}
@@ -0,0 +1,70 @@
+//! Modifier keys held right now
+//!
+//! The global-shortcut plugin reports the trigger key only, so an active cycle
+//! polls this to notice its modifier chord being released.
+
+use tauri_plugin_global_shortcut::Modifiers;
+
+/// Modifier keys held down right now; empty where they can't be read
+pub fn held() -> Modifiers {
+    #[cfg(target_os = "macos")]
+    return macos::held();
+
+    #[cfg(target_os = "windows")]
+    return windows::held();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Modifiers::empty()
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use core_graphics::event::CGEventFlags;
+    use tauri_plugin_global_shortcut::Modifiers;
+
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceFlagsState(state_id: i32) -> u64;
+    }
+
+    pub(super) fn held() -> Modifiers {
+        let flags = CGEventFlags::from_bits_truncate(unsafe {
+            CGEventSourceFlagsState(COMBINED_SESSION_STATE)
+        });
+        [
+            (CGEventFlags::CGEventFlagShift, Modifiers::SHIFT),
+            (CGEventFlags::CGEventFlagControl, Modifiers::CONTROL),
+            (CGEventFlags::CGEventFlagAlternate, Modifiers::ALT),
+            (CGEventFlags::CGEventFlagCommand, Modifiers::SUPER),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .fold(Modifiers::empty(), |held, (_, modifier)| held | modifier)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    use tauri_plugin_global_shortcut::Modifiers;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        GetAsyncKeyState, VIRTUAL_KEY, VK_CONTROL, VK_LWIN, VK_MENU, VK_RWIN, VK_SHIFT,
+    };
+
+    pub(super) fn held() -> Modifiers {
+        // The high bit is set while the key is down
+        let down = |key: VIRTUAL_KEY| unsafe { GetAsyncKeyState(key.0 as i32) } < 0;
+        [
+            (VK_SHIFT, Modifiers::SHIFT),
+            (VK_CONTROL, Modifiers::CONTROL),
+            (VK_MENU, Modifiers::ALT),
+            (VK_LWIN, Modifiers::SUPER),
+            (VK_RWIN, Modifiers::SUPER),
+        ]
+        .into_iter()
+        .filter(|(key, _)| down(*key))
+        .fold(Modifiers::empty(), |held, (_, modifier)| held | modifier)
+    }
+}
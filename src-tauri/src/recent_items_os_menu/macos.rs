@@ -0,0 +1,119 @@
+//! Dock menu entries added to the app delegate at runtime
+
+use super::RecentEntry;
+use objc2::runtime::{AnyClass, AnyObject};
+use objc2::{msg_send, sel};
+use objc2_foundation::NSString;
+use std::ffi::{c_char, c_void};
+use std::sync::Mutex;
+
+/// Entries returned by the next `applicationDockMenu:` call
+static ENTRIES: Mutex<Vec<RecentEntry>> = Mutex::new(Vec::new());
+
+// Raw runtime calls, as in window_commands (Tauri links its own objc2 version)
+#[link(name = "objc", kind = "dylib")]
+extern "C" {
+    fn object_getClass(obj: *const c_void) -> *mut c_void;
+    fn class_addMethod(
+        cls: *mut c_void,
+        sel: *const c_void,
+        imp: *const c_void,
+        types: *const c_char,
+    ) -> bool;
+    fn sel_registerName(name: *const c_char) -> *const c_void;
+}
+
+/// Replace the entries shown in the Dock menu (called by the worker)
+pub(super) fn set_entries(entries: &[RecentEntry]) {
+    if let Ok(mut current) = ENTRIES.lock() {
+        *current = entries.to_vec();
+    }
+}
+
+/// IMP for -[delegate applicationDockMenu:]
+/// Built fresh each time the Dock asks, from the latest entries
+extern "C" fn application_dock_menu(
+    this: *mut AnyObject,
+    _cmd: *const c_void,
+    _sender: *mut AnyObject,
+) -> *mut AnyObject {
+    let entries = ENTRIES.lock().map(|e| e.clone()).unwrap_or_default();
+    if entries.is_empty() {
+        return std::ptr::null_mut();
+    }
+
+    unsafe {
+        let menu_cls = AnyClass::get("NSMenu").unwrap();
+        let item_cls = AnyClass::get("NSMenuItem").unwrap();
+        let menu: *mut AnyObject = msg_send![menu_cls, new];
+
+        for entry in &entries {
+            let item: *mut AnyObject = msg_send![item_cls, alloc];
+            let item: *mut AnyObject = msg_send![
+                item,
+                initWithTitle: &*NSString::from_str(&entry.title),
+                action: sel!(clipsterOpenRecentItem:),
+                keyEquivalent: &*NSString::new()
+            ];
+            // The URL travels with the item, so a list rebuilt while the menu
+            // is open can't make a click open a different item
+            let _: () = msg_send![item, setRepresentedObject: &*NSString::from_str(&entry.url)];
+            let _: () = msg_send![item, setTarget: this];
+            let _: () = msg_send![menu, addItem: item];
+            let _: () = msg_send![item, release];
+        }
+
+        msg_send![menu, autorelease]
+    }
+}
+
+/// IMP for -[delegate clipsterOpenRecentItem:]
+/// Opens the item's deep link, which the deep-link plugin routes back to us
+extern "C" fn open_recent_item(_this: *mut AnyObject, _cmd: *const c_void, sender: *mut AnyObject) {
+    unsafe {
+        let url_string: *mut AnyObject = msg_send![sender, representedObject];
+        if url_string.is_null() {
+            return;
+        }
+        let url: *mut AnyObject =
+            msg_send![AnyClass::get("NSURL").unwrap(), URLWithString: url_string];
+        if url.is_null() {
+            return;
+        }
+        let workspace: *mut AnyObject =
+            msg_send![AnyClass::get("NSWorkspace").unwrap(), sharedWorkspace];
+        let _: bool = msg_send![workspace, openURL: url];
+    }
+}
+
+/// Add the Dock menu methods to the application delegate's class
+/// Call once from setup, after Tauri has installed its delegate
+pub fn install_dock_menu() {
+    unsafe {
+        let app: *mut AnyObject =
+            msg_send![AnyClass::get("NSApplication").unwrap(), sharedApplication];
+        let delegate: *mut AnyObject = msg_send![app, delegate];
+        if delegate.is_null() {
+            eprintln!("[recent_items_os_menu] No application delegate, Dock menu not installed");
+            return;
+        }
+        let cls = object_getClass(delegate as *const c_void);
+
+        // Type encodings: id return/argument (@), self (@), _cmd (:)
+        let added = class_addMethod(
+            cls,
+            sel_registerName(c"applicationDockMenu:".as_ptr()),
+            application_dock_menu as *const c_void,
+            c"@@:@".as_ptr(),
+        );
+        if !added {
+            eprintln!("[recent_items_os_menu] Delegate already provides a Dock menu");
+        }
+        class_addMethod(
+            cls,
+            sel_registerName(c"clipsterOpenRecentItem:".as_ptr()),
+            open_recent_item as *const c_void,
+            c"v@:@".as_ptr(),
+        );
+    }
+}
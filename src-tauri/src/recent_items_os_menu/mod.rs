@@ -0,0 +1,257 @@
+//! Recent history items in the Windows jump list and the macOS Dock menu
+//!
+//! The five most recent text and link items are listed as entries that open
+//! `clipster://item/<id>`. The list is rebuilt after each capture flush (where
+//! Spotlight is synced too) and whenever the change log moves, so deleted and
+//! cleared items drop out. Windows persists jump list tasks on disk, so the
+//! `recent_items_os_menu` setting can turn the list off, which also empties it.
+//! The Dock menu only appears while Clipster has a Dock icon; it normally runs
+//! as an accessory app without one.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+#[cfg(target_os = "macos")]
+pub use macos::install_dock_menu;
+
+use crate::models::ContentType;
+use crate::spotlight::deep_link;
+use crate::storage::database::HistorySort;
+use crate::storage::Database;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// Number of items listed
+pub const RECENT_ITEMS_LIMIT: usize = 5;
+
+/// Characters of preview shown per entry
+const TITLE_MAX_CHARS: usize = 48;
+
+/// History items scanned for text and link entries (images are skipped)
+const SCAN_LIMIT: usize = 50;
+
+/// How often the worker checks the change log without being nudged
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+const CHANGE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// One recent item in the OS menu
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentEntry {
+    pub id: String,
+    pub title: String,
+    /// Deep link opening the item in Clipster
+    pub url: String,
+}
+
+/// Whether the `recent_items_os_menu` setting is on (the default)
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("recent_items_os_menu")
+        .ok()
+        .flatten()
+        .as_deref()
+        != Some("false")
+}
+
+/// Single-line menu title: whitespace runs collapse to one space, control
+/// characters are dropped and long text is cut on a char boundary with an ellipsis
+/// None for text with nothing visible
+pub fn menu_title(text: &str, max_chars: usize) -> Option<String> {
+    let mut chars = text
+        .split_whitespace()
+        .flat_map(|word| std::iter::once(' ').chain(word.chars()))
+        .filter(|c| !c.is_control())
+        .skip(1);
+    let title: String = chars.by_ref().take(max_chars).collect();
+
+    if title.trim().is_empty() {
+        None
+    } else if chars.next().is_some() {
+        Some(format!("{}…", title.trim_end()))
+    } else {
+        Some(title)
+    }
+}
+
+/// Most recent text and link history items, newest first
+pub fn recent_entries(db: &Database) -> Result<Vec<RecentEntry>, String> {
+    Ok(db
+        .get_items(SCAN_LIMIT, 0, false, HistorySort::CreatedAt)?
+        .into_iter()
+        .filter(|item| matches!(item.content_type, ContentType::Text | ContentType::Link))
+        .filter_map(|item| {
+            let title = menu_title(item.content_text.as_deref()?, TITLE_MAX_CHARS)?;
+            Some(RecentEntry {
+                url: deep_link(&item.id),
+                id: item.id,
+                title,
+            })
+        })
+        .take(RECENT_ITEMS_LIMIT)
+        .collect())
+}
+
+/// Entries the OS menu should show: none while the setting is off
+pub fn entries_to_show(db: &Database) -> Result<Vec<RecentEntry>, String> {
+    if !is_enabled(db) {
+        return Ok(Vec::new());
+    }
+    recent_entries(db)
+}
+
+/// Worker loop: recomputes the entries on each refresh or change-log move and
+/// hands them to `apply` when they differ from what the OS already shows
+#[cfg(any(target_os = "macos", target_os = "windows", test))]
+fn follow_history(
+    db: &Database,
+    refreshes: std::sync::mpsc::Receiver<()>,
+    mut apply: impl FnMut(&[RecentEntry]),
+) {
+    use std::sync::mpsc::RecvTimeoutError;
+
+    let mut shown: Option<Vec<RecentEntry>> = None;
+    let mut seen_seq = None;
+    loop {
+        match refreshes.recv_timeout(CHANGE_POLL_INTERVAL) {
+            Ok(()) => {}
+            Err(RecvTimeoutError::Timeout) => {
+                if db.latest_change_seq().ok() == seen_seq {
+                    continue;
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        seen_seq = db.latest_change_seq().ok();
+
+        match entries_to_show(db) {
+            Ok(entries) if shown.as_ref() != Some(&entries) => {
+                apply(&entries);
+                shown = Some(entries);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[recent_items_os_menu] Failed to load recent items: {}", e),
+        }
+    }
+}
+
+/// Handle to the background worker keeping the OS menu up to date
+/// A no-op on platforms without a jump list or Dock menu
+pub struct RecentItemsMenu {
+    refreshes: Option<Sender<()>>,
+}
+
+impl RecentItemsMenu {
+    /// Start the worker; it fills the menu once right away
+    #[cfg(any(target_os = "macos", target_os = "windows"))]
+    pub fn start(db: Arc<Database>) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        #[cfg(target_os = "macos")]
+        std::thread::spawn(move || follow_history(&db, receiver, macos::set_entries));
+        #[cfg(target_os = "windows")]
+        std::thread::spawn(move || windows::run_worker(&db, receiver));
+
+        let menu = Self {
+            refreshes: Some(sender),
+        };
+        menu.refresh();
+        menu
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    pub fn start(_db: Arc<Database>) -> Self {
+        Self { refreshes: None }
+    }
+
+    /// Rebuild the menu now (after a capture or a settings change)
+    pub fn refresh(&self) {
+        if let Some(refreshes) = &self.refreshes {
+            let _ = refreshes.send(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardItem;
+
+    #[test]
+    fn test_menu_title() {
+        assert_eq!(menu_title("hello", 10), Some("hello".to_string()));
+        assert_eq!(
+            menu_title("  line one\n\tline   two \r\n", 40),
+            Some("line one line two".to_string())
+        );
+        assert_eq!(menu_title("abcdef ghi", 6), Some("abcdef…".to_string()));
+        // Trailing space before the cut is not kept
+        assert_eq!(menu_title("abcde fghi", 6), Some("abcde…".to_string()));
+        assert_eq!(menu_title("a\u{0}b\u{7}c", 10), Some("abc".to_string()));
+        assert_eq!(menu_title(" \n\t ", 10), None);
+        assert_eq!(menu_title("\u{0}", 10), None);
+    }
+
+    #[test]
+    fn test_menu_title_cuts_on_char_boundaries() {
+        assert_eq!(menu_title("héllo wörld", 4), Some("héll…".to_string()));
+        assert_eq!(
+            menu_title("日本語のテキスト", 3),
+            Some("日本語…".to_string())
+        );
+        assert_eq!(menu_title("👍👍👍", 2), Some("👍👍…".to_string()));
+        assert_eq!(menu_title("👍👍", 2), Some("👍👍".to_string()));
+    }
+
+    #[test]
+    fn test_recent_entries() {
+        let db = Database::new_in_memory().unwrap();
+        let mut ids = Vec::new();
+        for i in 0..7 {
+            let item = ClipboardItem::new_text(format!("text {}", i), None, None);
+            db.insert_item(&item).unwrap();
+            ids.push(item.id);
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let image = ClipboardItem::new_image(None, "/tmp/x.png".to_string(), None, None);
+        db.insert_item(&image).unwrap();
+        let blank = ClipboardItem::new_text("   ".to_string(), None, None);
+        db.insert_item(&blank).unwrap();
+
+        let entries = recent_entries(&db).unwrap();
+        assert_eq!(entries.len(), RECENT_ITEMS_LIMIT);
+        assert_eq!(entries[0].id, ids[6]);
+        assert_eq!(entries[0].title, "text 6");
+        assert_eq!(entries[0].url, format!("clipster://item/{}", ids[6]));
+        assert_eq!(entries[4].id, ids[2]);
+    }
+
+    #[test]
+    fn test_disabled_setting_empties_the_menu() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("hello".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        assert!(is_enabled(&db));
+        assert_eq!(entries_to_show(&db).unwrap().len(), 1);
+
+        db.set_setting("recent_items_os_menu", "false").unwrap();
+        assert!(entries_to_show(&db).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_follow_history_applies_only_changes() {
+        let db = Database::new_in_memory().unwrap();
+        db.insert_item(&ClipboardItem::new_text("hello".to_string(), None, None))
+            .unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        for _ in 0..3 {
+            sender.send(()).unwrap();
+        }
+        drop(sender);
+
+        let mut applied = Vec::new();
+        follow_history(&db, receiver, |entries| applied.push(entries.to_vec()));
+        assert_eq!(applied.len(), 1);
+        assert_eq!(applied[0][0].title, "hello");
+    }
+}
@@ -0,0 +1,77 @@
+//! Jump list tasks written through ICustomDestinationList
+
+use super::{follow_history, RecentEntry};
+use crate::storage::Database;
+use std::sync::mpsc::Receiver;
+use windows::core::{Interface, HSTRING, PCWSTR, PROPVARIANT};
+use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+use windows::Win32::System::Com::{
+    CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+};
+use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+use windows::Win32::UI::Shell::{
+    DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+};
+
+/// Worker loop: COM is initialized once on this thread
+pub(super) fn run_worker(db: &Database, refreshes: Receiver<()>) {
+    if let Err(e) = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.ok() {
+        eprintln!("[recent_items_os_menu] CoInitializeEx failed: {}", e);
+        return;
+    }
+
+    follow_history(db, refreshes, |entries| {
+        if let Err(e) = write_jump_list(entries) {
+            eprintln!("[recent_items_os_menu] Failed to update jump list: {}", e);
+        }
+    });
+}
+
+/// Replace the jump list tasks; an empty list deletes it from disk
+fn write_jump_list(entries: &[RecentEntry]) -> windows::core::Result<()> {
+    unsafe {
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+        if entries.is_empty() {
+            return list.DeleteList(PCWSTR::null());
+        }
+
+        let exe = std::env::current_exe().map_err(|e| {
+            windows::core::Error::new(
+                windows::Win32::Foundation::E_FAIL,
+                &format!("Failed to locate executable: {}", e),
+            )
+        })?;
+        let mut max_slots = 0u32;
+        let _removed: IObjectArray = list.BeginList(&mut max_slots)?;
+
+        let tasks: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for entry in entries {
+            tasks.AddObject(&task_link(&exe, entry)?)?;
+        }
+
+        list.AddUserTasks(&tasks.cast::<IObjectArray>()?)?;
+        list.CommitList()
+    }
+}
+
+/// Shell link relaunching Clipster with the item's deep link; the new
+/// process hands it to the running instance and exits
+unsafe fn task_link(
+    exe: &std::path::Path,
+    entry: &RecentEntry,
+) -> windows::core::Result<IShellLinkW> {
+    let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+    link.SetPath(&HSTRING::from(exe.as_os_str()))?;
+    link.SetArguments(&HSTRING::from(entry.url.as_str()))?;
+    link.SetDescription(&HSTRING::from(entry.title.as_str()))?;
+    link.SetIconLocation(&HSTRING::from(exe.as_os_str()), 0)?;
+
+    // Tasks show PKEY_Title, not the description
+    let properties: IPropertyStore = link.cast()?;
+    properties.SetValue(&PKEY_Title, &PROPVARIANT::from(entry.title.as_str()))?;
+    properties.Commit()?;
+    Ok(link)
+}
@@ -0,0 +1,123 @@
+//! Line breaking of the card text
+//!
+//! Kept apart from the font: widths come from a `measure` function, so the
+//! rules can be tested with a monospace stand-in.
+
+/// Appended to the last line when the text doesn't fit
+pub const ELLIPSIS: &str = "…";
+
+/// Break `text` into at most `max_lines` lines no wider than `max_width`
+///
+/// Lines break at spaces, and a word wider than a line is split between
+/// characters. Line breaks in the text are kept, runs of whitespace collapse
+/// to one space and blank lines at either end are dropped. Text that doesn't
+/// fit ends with `ELLIPSIS` on the last line.
+pub fn wrap(
+    text: &str,
+    max_width: f32,
+    max_lines: usize,
+    measure: impl Fn(&str) -> f32,
+) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for paragraph in text.trim().lines() {
+        if lines.len() > max_lines {
+            break;
+        }
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if lines.len() > max_lines {
+                break;
+            }
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{} {}", line, word)
+            };
+            if measure(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            for c in word.chars() {
+                line.push(c);
+                if measure(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(std::mem::replace(&mut line, c.to_string()));
+                }
+            }
+        }
+        lines.push(line);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        if let Some(last) = lines.last_mut() {
+            *last = ellipsize(last, max_width, &measure);
+        }
+    }
+    lines
+}
+
+/// `line` shortened until it fits with `ELLIPSIS` after it
+fn ellipsize(line: &str, max_width: f32, measure: &impl Fn(&str) -> f32) -> String {
+    let mut line = line.trim_end().to_string();
+    while !line.is_empty() && measure(&format!("{}{}", line, ELLIPSIS)) > max_width {
+        line.pop();
+        line.truncate(line.trim_end().len());
+    }
+    line + ELLIPSIS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    #[test]
+    fn test_wraps_at_spaces_and_keeps_line_breaks() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10.0, 5, chars),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(
+            wrap("\n  first   line\n\nthird line\n\n", 20.0, 5, chars),
+            vec!["first line", "", "third line"]
+        );
+        assert!(wrap("   ", 10.0, 5, chars).is_empty());
+    }
+
+    #[test]
+    fn test_splits_words_wider_than_a_line() {
+        assert_eq!(
+            wrap("see https://example.com/abc", 8.0, 5, chars),
+            vec!["see", "https://", "example.", "com/abc"]
+        );
+        // A line always holds at least one character
+        assert_eq!(wrap("ab", 0.5, 5, chars), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_overflow_ends_with_an_ellipsis() {
+        assert_eq!(
+            wrap("one two three four five six", 9.0, 2, chars),
+            vec!["one two", "three…"]
+        );
+        // The last line is shortened to make room
+        assert_eq!(
+            wrap("aaaa bbbb cccc dddd", 9.0, 1, chars),
+            vec!["aaaa bbb…"]
+        );
+        assert_eq!(wrap("abcdefghij klm", 5.0, 1, chars), vec!["abcd…"]);
+        let lines = wrap(&"word ".repeat(200), 12.0, 3, chars);
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|line| chars(line) <= 12.0));
+        assert!(lines[2].ends_with(ELLIPSIS));
+        // Text that fits exactly has none
+        assert_eq!(wrap("one two", 7.0, 1, chars), vec!["one two"]);
+    }
+}
@@ -0,0 +1,421 @@
+//! Shareable PNG cards of items
+//!
+//! `render_item_card` draws an item the way it would be shown in a bug
+//! report: the source app's icon and name, when it was copied, and the text
+//! (wrapped to `MAX_TEXT_LINES`) or image. Text is set in the bundled DejaVu
+//! Sans, so a card looks the same on every machine. Redacted cards, and
+//! every card of an item in a passcode-protected pinboard, show bars in place
+//! of the content. Sizes are given for `DEFAULT_CARD_WIDTH` and scale with
+//! the width asked for.
+
+pub mod layout;
+
+use crate::error::ClipsterError;
+use crate::models::ClipboardItem;
+use crate::preview;
+use crate::storage::file_storage;
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::sync::OnceLock;
+
+/// Card width when none is given, in pixels
+pub const DEFAULT_CARD_WIDTH: u32 = 600;
+
+/// Narrowest card
+pub const MIN_CARD_WIDTH: u32 = 320;
+
+/// Widest card
+pub const MAX_CARD_WIDTH: u32 = 1600;
+
+/// Lines of text shown before the ellipsis
+pub const MAX_TEXT_LINES: usize = 8;
+
+const FONT_DATA: &[u8] = include_bytes!("../../fonts/DejaVuSans.ttf");
+
+const PADDING: f32 = 32.0;
+const ICON_SIZE: f32 = 40.0;
+const HEADER_GAP: f32 = 12.0;
+const BODY_GAP: f32 = 24.0;
+const APP_NAME_SIZE: f32 = 18.0;
+const TIMESTAMP_SIZE: f32 = 14.0;
+const BODY_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = 1.4;
+
+/// Widths of the redaction bars, as parts of the content width
+const REDACTED_BARS: [f32; 3] = [1.0, 0.85, 0.6];
+
+const BACKGROUND: Rgba<u8> = Rgba([246, 246, 248, 255]);
+const TEXT_COLOR: Rgba<u8> = Rgba([28, 28, 30, 255]);
+const MUTED_COLOR: Rgba<u8> = Rgba([110, 110, 115, 255]);
+const ICON_PLACEHOLDER: Rgba<u8> = Rgba([210, 210, 215, 255]);
+const REDACTED_COLOR: Rgba<u8> = Rgba([72, 72, 78, 255]);
+
+/// What the card shows below the header
+pub enum CardBody {
+    Text(String),
+    Image(DynamicImage),
+    Redacted,
+}
+
+/// Everything drawn on a card
+pub struct CardContent {
+    pub app_name: String,
+    pub app_icon: Option<DynamicImage>,
+    pub timestamp: String,
+    pub body: CardBody,
+}
+
+impl CardContent {
+    /// Content of `item`'s card; with `redact`, the content is masked but the
+    /// app and time are kept
+    pub fn from_item(item: &ClipboardItem, redact: bool) -> Self {
+        let app_icon = item
+            .source_app_icon
+            .as_deref()
+            .and_then(decode_base64_image);
+        let body = if redact {
+            CardBody::Redacted
+        } else if let Some(image) = item
+            .thumbnail_base64
+            .as_deref()
+            .and_then(decode_base64_image)
+        {
+            CardBody::Image(image)
+        } else {
+            CardBody::Text(card_text(item))
+        };
+
+        Self {
+            app_name: item
+                .source_app
+                .clone()
+                .unwrap_or_else(|| "Unknown app".to_string()),
+            app_icon,
+            timestamp: item
+                .created_at
+                .with_timezone(&chrono::Local)
+                .format("%b %-d, %Y at %H:%M")
+                .to_string(),
+            body,
+        }
+    }
+}
+
+/// Text of an item for its card: generated titles and file summaries as in
+/// the preview, the full text otherwise
+fn card_text(item: &ClipboardItem) -> String {
+    if item.title.is_some() || item.files_summary.is_some() {
+        return preview::preview_item(item, usize::MAX);
+    }
+    item.content_text
+        .clone()
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or_else(|| item.preview.clone())
+}
+
+fn decode_base64_image(base64: &str) -> Option<DynamicImage> {
+    let bytes = file_storage::base64_to_thumbnail(base64).ok()?;
+    image::load_from_memory(&bytes).ok()
+}
+
+fn font() -> &'static FontRef<'static> {
+    static FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    FONT.get_or_init(|| FontRef::try_from_slice(FONT_DATA).expect("bundled font is valid"))
+}
+
+/// Draw the card of `content`, `width` pixels wide (clamped to the allowed
+/// range); the height follows from the content
+pub fn render(content: &CardContent, width: u32) -> RgbaImage {
+    let width = width.clamp(MIN_CARD_WIDTH, MAX_CARD_WIDTH);
+    let scale = width as f32 / DEFAULT_CARD_WIDTH as f32;
+    let px = |size: f32| (size * scale).round();
+    let padding = px(PADDING);
+    let content_width = width as f32 - 2.0 * padding;
+    let body_size = px(BODY_SIZE);
+    let line_height = (body_size * LINE_HEIGHT).round();
+
+    let body_top = padding + px(ICON_SIZE) + px(BODY_GAP);
+    let (lines, image) = match &content.body {
+        CardBody::Text(text) => {
+            let measure = |line: &str| text_width(line, body_size);
+            (
+                layout::wrap(text, content_width, MAX_TEXT_LINES, measure),
+                None,
+            )
+        }
+        CardBody::Image(image) => (Vec::new(), Some(fit_image(image, content_width as u32))),
+        CardBody::Redacted => (Vec::new(), None),
+    };
+    let body_height = match (&content.body, &image) {
+        (_, Some(image)) => image.height() as f32,
+        (CardBody::Redacted, _) => REDACTED_BARS.len() as f32 * line_height,
+        _ => lines.len().max(1) as f32 * line_height,
+    };
+    let height = (body_top + body_height + padding).round() as u32;
+
+    let mut card = RgbaImage::from_pixel(width, height, BACKGROUND);
+
+    // Header: icon, app name and timestamp
+    let icon_size = px(ICON_SIZE) as u32;
+    match &content.app_icon {
+        Some(icon) => {
+            let icon = icon.resize_exact(icon_size, icon_size, FilterType::Triangle);
+            imageops::overlay(&mut card, &icon.to_rgba8(), padding as i64, padding as i64);
+        }
+        None => fill_rect(
+            &mut card,
+            padding,
+            padding,
+            icon_size as f32,
+            icon_size as f32,
+            ICON_PLACEHOLDER,
+        ),
+    }
+    let header_x = padding + px(ICON_SIZE) + px(HEADER_GAP);
+    let name_size = px(APP_NAME_SIZE);
+    let time_size = px(TIMESTAMP_SIZE);
+    let header_width = width as f32 - padding - header_x;
+    let app_name = layout::wrap(&content.app_name, header_width, 1, |line| {
+        text_width(line, name_size)
+    });
+    if let Some(name) = app_name.first() {
+        draw_text(
+            &mut card,
+            name,
+            header_x,
+            padding + name_size,
+            name_size,
+            TEXT_COLOR,
+        );
+    }
+    draw_text(
+        &mut card,
+        &content.timestamp,
+        header_x,
+        padding + px(ICON_SIZE),
+        time_size,
+        MUTED_COLOR,
+    );
+
+    // Body
+    if let Some(image) = &image {
+        let x = padding + (content_width - image.width() as f32) / 2.0;
+        imageops::overlay(&mut card, image, x as i64, body_top as i64);
+    } else if let CardBody::Redacted = content.body {
+        for (i, part) in REDACTED_BARS.iter().enumerate() {
+            let top = body_top + i as f32 * line_height;
+            fill_rect(
+                &mut card,
+                padding,
+                top,
+                content_width * part,
+                body_size,
+                REDACTED_COLOR,
+            );
+        }
+    } else {
+        for (i, line) in lines.iter().enumerate() {
+            let baseline = body_top + i as f32 * line_height + body_size;
+            draw_text(&mut card, line, padding, baseline, body_size, TEXT_COLOR);
+        }
+    }
+    card
+}
+
+/// PNG of the card of `content`
+pub fn render_png(content: &CardContent, width: u32) -> Result<Vec<u8>, ClipsterError> {
+    file_storage::encode_png(&DynamicImage::ImageRgba8(render(content, width)))
+}
+
+/// `image` scaled to `max_width` wide, and at most as tall as it is wide
+fn fit_image(image: &DynamicImage, max_width: u32) -> RgbaImage {
+    let (width, height) = (image.width().max(1) as f32, image.height().max(1) as f32);
+    let factor = (max_width as f32 / width).min(max_width as f32 / height);
+    let fitted_width = ((width * factor).round() as u32).max(1);
+    let fitted_height = ((height * factor).round() as u32).max(1);
+    image
+        .resize_exact(fitted_width, fitted_height, FilterType::Triangle)
+        .to_rgba8()
+}
+
+/// Width of `text` set at `size` pixels
+fn text_width(text: &str, size: f32) -> f32 {
+    let font = font().as_scaled(PxScale::from(size));
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let glyph = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, glyph);
+        }
+        width += font.h_advance(glyph);
+        previous = Some(glyph);
+    }
+    width
+}
+
+/// Draw `text` at `size` pixels from `x`, on the `baseline` row
+fn draw_text(card: &mut RgbaImage, text: &str, x: f32, baseline: f32, size: f32, color: Rgba<u8>) {
+    let scale = PxScale::from(size);
+    let scaled = font().as_scaled(scale);
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outline) = font().outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + i64::from(gx);
+            let py = bounds.min.y as i64 + i64::from(gy);
+            if px < 0 || py < 0 || px >= card.width() as i64 || py >= card.height() as i64 {
+                return;
+            }
+            let pixel = card.get_pixel_mut(px as u32, py as u32);
+            *pixel = blend(*pixel, color, coverage);
+        });
+    }
+}
+
+fn fill_rect(card: &mut RgbaImage, x: f32, y: f32, width: f32, height: f32, color: Rgba<u8>) {
+    let (x0, y0) = (x.max(0.0) as u32, y.max(0.0) as u32);
+    let x1 = ((x + width) as u32).min(card.width());
+    let y1 = ((y + height) as u32).min(card.height());
+    for py in y0..y1 {
+        for px in x0..x1 {
+            card.put_pixel(px, py, color);
+        }
+    }
+}
+
+/// `color` laid over `under` with `coverage` (0 to 1) opacity
+fn blend(under: Rgba<u8>, color: Rgba<u8>, coverage: f32) -> Rgba<u8> {
+    let coverage = coverage.clamp(0.0, 1.0);
+    let mix =
+        |a: u8, b: u8| (f32::from(a) * (1.0 - coverage) + f32::from(b) * coverage).round() as u8;
+    Rgba([
+        mix(under[0], color[0]),
+        mix(under[1], color[1]),
+        mix(under[2], color[2]),
+        255,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_card(text: &str) -> CardContent {
+        CardContent {
+            app_name: "Notes".to_string(),
+            app_icon: None,
+            timestamp: "Mar 4, 2026 at 09:30".to_string(),
+            body: CardBody::Text(text.to_string()),
+        }
+    }
+
+    fn decode(png: &[u8]) -> RgbaImage {
+        image::load_from_memory(png).unwrap().to_rgba8()
+    }
+
+    /// Pixels in the given rows that are much darker than the background
+    fn dark_pixels(card: &RgbaImage, rows: std::ops::Range<u32>) -> usize {
+        rows.flat_map(|y| (0..card.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| card.get_pixel(x, y)[0] < 150)
+            .count()
+    }
+
+    #[test]
+    fn test_text_card_snapshot() {
+        let card = decode(&render_png(&text_card("Hello from the clipboard"), 600).unwrap());
+        // Padding, icon row, gap, one line of text, padding
+        let line_height = (BODY_SIZE * LINE_HEIGHT).round();
+        let expected = PADDING + ICON_SIZE + BODY_GAP + line_height + PADDING;
+        assert_eq!(card.dimensions(), (600, expected as u32));
+
+        assert_eq!(*card.get_pixel(0, 0), BACKGROUND);
+        assert_eq!(*card.get_pixel(599, card.height() - 1), BACKGROUND);
+        let icon_center = (PADDING + ICON_SIZE / 2.0) as u32;
+        assert_eq!(*card.get_pixel(icon_center, icon_center), ICON_PLACEHOLDER);
+
+        let body_top = (PADDING + ICON_SIZE + BODY_GAP) as u32;
+        assert!(dark_pixels(&card, body_top..body_top + BODY_SIZE as u32) > 50);
+        // Nothing is drawn in the margin on the right
+        assert!((body_top..card.height()).all(|y| *card.get_pixel(590, y) == BACKGROUND));
+
+        // Long text stops at MAX_TEXT_LINES; the width scales everything
+        let long = render(&text_card(&"lorem ipsum ".repeat(200)), 1200);
+        let line_height = (BODY_SIZE * 2.0 * LINE_HEIGHT).round();
+        let expected =
+            2.0 * (PADDING + ICON_SIZE + BODY_GAP + PADDING) + MAX_TEXT_LINES as f32 * line_height;
+        assert_eq!(long.dimensions(), (1200, expected as u32));
+        assert_eq!(render(&text_card("tiny"), 10).width(), MIN_CARD_WIDTH);
+    }
+
+    #[test]
+    fn test_redacted_and_image_cards() {
+        let mut content = text_card("secret token 1234");
+        content.body = CardBody::Redacted;
+        let card = render(&content, 600);
+        let body_top = PADDING + ICON_SIZE + BODY_GAP;
+        let line_height = (BODY_SIZE * LINE_HEIGHT).round();
+        assert_eq!(
+            card.height(),
+            (body_top + REDACTED_BARS.len() as f32 * line_height + PADDING) as u32
+        );
+        let (x, y) = ((PADDING + 10.0) as u32, (body_top + 5.0) as u32);
+        assert_eq!(*card.get_pixel(x, y), REDACTED_COLOR);
+        // The last bar is shorter than the content width
+        let last_bar = (body_top + 2.0 * line_height + 5.0) as u32;
+        assert_eq!(*card.get_pixel(500, last_bar), BACKGROUND);
+
+        let red = RgbaImage::from_pixel(200, 100, Rgba([220, 30, 30, 255]));
+        content.body = CardBody::Image(DynamicImage::ImageRgba8(red));
+        content.app_icon = Some(DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+            16,
+            16,
+            Rgba([0, 120, 255, 255]),
+        )));
+        let card = render(&content, 600);
+        // The image fills the content width, keeping its aspect ratio
+        let image_height = (600.0 - 2.0 * PADDING) / 2.0;
+        assert_eq!(card.height(), (body_top + image_height + PADDING) as u32);
+        assert_eq!(
+            *card.get_pixel(300, (body_top + image_height / 2.0) as u32),
+            Rgba([220, 30, 30, 255])
+        );
+        let icon_center = (PADDING + ICON_SIZE / 2.0) as u32;
+        assert_eq!(
+            *card.get_pixel(icon_center, icon_center),
+            Rgba([0, 120, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_item_content() {
+        let mut item = ClipboardItem::new_text(
+            "password: hunter2".to_string(),
+            Some("Terminal".to_string()),
+            None,
+        );
+        let content = CardContent::from_item(&item, false);
+        assert_eq!(content.app_name, "Terminal");
+        assert!(matches!(&content.body, CardBody::Text(text) if text == "password: hunter2"));
+        assert!(matches!(
+            CardContent::from_item(&item, true).body,
+            CardBody::Redacted
+        ));
+
+        item.source_app = None;
+        assert_eq!(CardContent::from_item(&item, false).app_name, "Unknown app");
+    }
+}
@@ -0,0 +1,294 @@
+//! Lock file and loopback socket that keep Clipster to one instance
+//!
+//! The first launch takes an exclusive OS lock on `instance.lock` in the
+//! Clipster root and listens on a loopback port, written with a random token
+//! to `instance.port`. A later launch that finds the lock taken sends the token
+//! and its argv to that port, waits for `ok` and exits. The OS releases the
+//! lock when its process dies, so files left behind by a crash are simply
+//! taken over by the next launch.
+
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Held with an exclusive lock by the running instance
+const LOCK_FILE: &str = "instance.lock";
+
+/// `<port> <token>` of the running instance
+const PORT_FILE: &str = "instance.port";
+
+/// How long a second launch keeps trying, including while the running
+/// instance is still starting up (opening or recovering its database)
+const HAND_OFF_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Pause between attempts to reach the running instance
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Time a connected launch gets to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Upper bound on a hand-off request (token and argv)
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// Reply sent once a request was accepted
+const ACK: &str = "ok";
+
+/// Outcome of trying to become the running instance
+#[derive(Debug)]
+pub enum Launch {
+    /// No other instance runs; this one holds the lock
+    Primary(InstanceLock),
+    /// Another instance holds the lock
+    Secondary(RunningInstance),
+}
+
+/// The lock of the running instance, with the socket later launches talk to
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+    listener: TcpListener,
+    token: String,
+}
+
+/// Another instance holding the lock in a directory
+#[derive(Debug)]
+pub struct RunningInstance {
+    dir: PathBuf,
+}
+
+/// Become the running instance for `dir`, or find the one that already is
+pub fn acquire(dir: &Path) -> io::Result<Launch> {
+    fs::create_dir_all(dir)?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+
+    if let Err(e) = file.try_lock_exclusive() {
+        if e.raw_os_error() == fs2::lock_contended_error().raw_os_error() {
+            return Ok(Launch::Secondary(RunningInstance {
+                dir: dir.to_path_buf(),
+            }));
+        }
+        return Err(e);
+    }
+
+    // Bound right away: launches arriving before `serve` wait in the backlog
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    write_port_file(dir, listener.local_addr()?.port(), &token)?;
+
+    Ok(Launch::Primary(InstanceLock {
+        _file: file,
+        listener,
+        token,
+    }))
+}
+
+/// Replace the port file in one step, so a launch never reads half of it
+fn write_port_file(dir: &Path, port: u16, token: &str) -> io::Result<()> {
+    let tmp_path = dir.join(format!("{}.tmp", PORT_FILE));
+    fs::write(&tmp_path, format!("{} {}", port, token))?;
+    fs::rename(&tmp_path, dir.join(PORT_FILE))
+}
+
+impl InstanceLock {
+    /// Answer later launches on a background thread, which keeps the lock
+    /// for the rest of the process
+    pub fn serve<F>(self, handler: F)
+    where
+        F: Fn(Vec<String>) + Send + 'static,
+    {
+        thread::spawn(move || {
+            // Moves the whole lock in; capturing only the fields used below
+            // would drop (and unlock) the file right away
+            let lock = self;
+            for stream in lock.listener.incoming() {
+                let request = stream.and_then(|stream| read_request(stream, &lock.token));
+                match request {
+                    Ok(argv) => handler(argv),
+                    Err(e) => eprintln!("Rejected hand-off from another launch: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// Read and acknowledge one request: the token line, then argv as JSON
+fn read_request(mut stream: TcpStream, token: &str) -> io::Result<Vec<String>> {
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    let mut reader = BufReader::new((&stream).take(MAX_REQUEST_BYTES));
+
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    if line.trim_end() != token {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "wrong token",
+        ));
+    }
+
+    line.clear();
+    reader.read_line(&mut line)?;
+    let argv: Vec<String> = serde_json::from_str(&line)?;
+
+    stream.write_all(format!("{}\n", ACK).as_bytes())?;
+    Ok(argv)
+}
+
+impl RunningInstance {
+    /// Send `argv` to the running instance, retrying while it starts up
+    pub fn hand_off(&self, argv: &[String]) -> io::Result<()> {
+        let deadline = Instant::now() + HAND_OFF_TIMEOUT;
+        loop {
+            match self.try_hand_off(argv, deadline) {
+                Ok(()) => return Ok(()),
+                Err(_) if Instant::now() + RETRY_INTERVAL < deadline => {
+                    thread::sleep(RETRY_INTERVAL)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn try_hand_off(&self, argv: &[String], deadline: Instant) -> io::Result<()> {
+        let (port, token) = self.read_port_file()?;
+        let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let mut stream = TcpStream::connect_timeout(&address, remaining.max(RETRY_INTERVAL))?;
+
+        stream.write_all(format!("{}\n{}\n", token, serde_json::to_string(argv)?).as_bytes())?;
+
+        // The running instance only accepts once its window exists
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        stream.set_read_timeout(Some(remaining.max(RETRY_INTERVAL)))?;
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+        if reply.trim_end() != ACK {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "running instance did not acknowledge",
+            ));
+        }
+        Ok(())
+    }
+
+    fn read_port_file(&self) -> io::Result<(u16, String)> {
+        let content = fs::read_to_string(self.dir.join(PORT_FILE))?;
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "malformed port file");
+        let (port, token) = content.trim().split_once(' ').ok_or_else(invalid)?;
+        Ok((port.parse().map_err(|_| invalid())?, token.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::sync::mpsc;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn primary(dir: &Path) -> InstanceLock {
+        match acquire(dir).unwrap() {
+            Launch::Primary(lock) => lock,
+            Launch::Secondary(_) => panic!("expected to acquire the lock"),
+        }
+    }
+
+    fn secondary(dir: &Path) -> RunningInstance {
+        match acquire(dir).unwrap() {
+            Launch::Secondary(running) => running,
+            Launch::Primary(_) => panic!("expected the lock to be taken"),
+        }
+    }
+
+    #[test]
+    fn test_second_launch_hands_off_argv() {
+        let dir = temp_dir("instance");
+        let lock = primary(&dir);
+        let running = secondary(&dir);
+
+        let (sender, received) = mpsc::channel();
+        lock.serve(move |argv| sender.send(argv).unwrap());
+
+        let sent = argv(&["clipster", "--show", "clipster://item/a b"]);
+        running.hand_off(&sent).unwrap();
+        assert_eq!(received.recv_timeout(Duration::from_secs(5)).unwrap(), sent);
+
+        // The lock is still held after serving
+        secondary(&dir);
+    }
+
+    #[test]
+    fn test_hand_off_waits_for_startup() {
+        let dir = temp_dir("instance");
+        let lock = primary(&dir);
+        let running = secondary(&dir);
+
+        // The running instance is still opening its database
+        let (sender, received) = mpsc::channel();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(300));
+            lock.serve(move |argv| sender.send(argv).unwrap());
+        });
+
+        running.hand_off(&argv(&["clipster"])).unwrap();
+        assert_eq!(
+            received.recv_timeout(Duration::from_secs(5)).unwrap(),
+            argv(&["clipster"])
+        );
+    }
+
+    #[test]
+    fn test_lock_released_with_its_process() {
+        let dir = temp_dir("instance");
+        let lock = primary(&dir);
+        let first_port = fs::read_to_string(dir.join(PORT_FILE)).unwrap();
+
+        // Dropping the lock file handle is what the OS does for a crashed process
+        drop(lock);
+        assert!(dir.join(LOCK_FILE).exists());
+        let _lock = primary(&dir);
+        assert_ne!(fs::read_to_string(dir.join(PORT_FILE)).unwrap(), first_port);
+    }
+
+    #[test]
+    fn test_stale_files_do_not_block_startup() {
+        let dir = temp_dir("instance");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(LOCK_FILE), "12345").unwrap();
+        fs::write(dir.join(PORT_FILE), "1 stale-token").unwrap();
+
+        let lock = primary(&dir);
+        let port = lock.listener.local_addr().unwrap().port();
+        assert_eq!(
+            fs::read_to_string(dir.join(PORT_FILE)).unwrap(),
+            format!("{} {}", port, lock.token)
+        );
+    }
+
+    #[test]
+    fn test_request_with_wrong_token_is_rejected() {
+        let dir = temp_dir("instance");
+        let lock = primary(&dir);
+        let port = lock.listener.local_addr().unwrap().port();
+
+        let (sender, received) = mpsc::channel();
+        lock.serve(move |argv| sender.send(argv).unwrap());
+
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        stream.write_all(b"guessed\n[\"clipster\"]\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply).unwrap();
+        assert_eq!(reply, "");
+        assert!(received.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+}
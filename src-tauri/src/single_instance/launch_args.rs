@@ -0,0 +1,105 @@
+//! What a launch asks for, from its command line
+//!
+//! A second launch forwards its argv to the running instance and exits; this
+//! decides what the running instance does with it. Pure logic only, so the
+//! hand-off is testable without starting two processes.
+
+use crate::spotlight::parse_deep_link;
+
+/// Show the panel right away (launchers and scripts)
+pub const SHOW_FLAG: &str = "--show";
+
+/// What a launch asks the running instance to do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchRequest {
+    /// Show the panel
+    ShowPanel,
+    /// Open the items of `clipster://item/<id>` links (jump list tasks)
+    OpenItems(Vec<String>),
+}
+
+/// What a launch with `argv` (executable path first) asks for; None for a
+/// plain start
+pub fn launch_request(argv: &[String]) -> Option<LaunchRequest> {
+    let args = argv.get(1..).unwrap_or_default();
+
+    let item_ids: Vec<String> = args.iter().filter_map(|arg| parse_deep_link(arg)).collect();
+    if !item_ids.is_empty() {
+        Some(LaunchRequest::OpenItems(item_ids))
+    } else if args.iter().any(|arg| arg == SHOW_FLAG) {
+        Some(LaunchRequest::ShowPanel)
+    } else {
+        None
+    }
+}
+
+/// What a second launch asks for: a plain relaunch from the app launcher
+/// means the user is looking for the panel
+pub fn second_launch_request(argv: &[String]) -> LaunchRequest {
+    launch_request(argv).unwrap_or(LaunchRequest::ShowPanel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        std::iter::once("/Applications/Clipster.app/Contents/MacOS/clipster")
+            .chain(args.iter().copied())
+            .map(String::from)
+            .collect()
+    }
+
+    #[test]
+    fn test_plain_start_stays_in_background() {
+        assert_eq!(launch_request(&argv(&[])), None);
+        // Unknown flags (e.g. from an autostart entry) are ignored
+        assert_eq!(launch_request(&argv(&["--minimized"])), None);
+        assert_eq!(launch_request(&argv(&["--shows"])), None);
+        assert_eq!(launch_request(&[]), None);
+    }
+
+    #[test]
+    fn test_show_flag() {
+        assert_eq!(
+            launch_request(&argv(&["--show"])),
+            Some(LaunchRequest::ShowPanel)
+        );
+    }
+
+    #[test]
+    fn test_deep_links_open_items() {
+        assert_eq!(
+            launch_request(&argv(&["clipster://item/abc-123"])),
+            Some(LaunchRequest::OpenItems(vec!["abc-123".to_string()]))
+        );
+        assert_eq!(
+            launch_request(&argv(&[
+                "--show",
+                "clipster://item/a",
+                "clipster://item/b/"
+            ])),
+            Some(LaunchRequest::OpenItems(vec![
+                "a".to_string(),
+                "b".to_string()
+            ]))
+        );
+        // Malformed links are ignored
+        assert_eq!(launch_request(&argv(&["clipster://pinboard/x"])), None);
+    }
+
+    #[test]
+    fn test_executable_path_is_not_an_argument() {
+        let argv = vec!["clipster://item/exe".to_string()];
+        assert_eq!(launch_request(&argv), None);
+    }
+
+    #[test]
+    fn test_relaunch_shows_panel() {
+        assert_eq!(second_launch_request(&argv(&[])), LaunchRequest::ShowPanel);
+        assert_eq!(
+            second_launch_request(&argv(&["clipster://item/a"])),
+            LaunchRequest::OpenItems(vec!["a".to_string()])
+        );
+    }
+}
@@ -0,0 +1,45 @@
+//! One running Clipster per user session
+//!
+//! A second launch would start another clipboard monitor and database handle
+//! on the same files. `main` takes the instance lock before opening the
+//! database; a launch that can't get it hands its argv (`--show`, deep links
+//! of jump list tasks) to the running instance and exits.
+
+pub mod instance_lock;
+pub mod launch_args;
+
+use crate::commands::window_commands::show_window;
+use instance_lock::InstanceLock;
+use launch_args::LaunchRequest;
+use tauri::AppHandle;
+
+/// Answer later launches from now on
+pub fn serve(lock: InstanceLock, app: &AppHandle) {
+    let app = app.clone();
+    lock.serve(move |argv| {
+        handle_request(&app, launch_args::second_launch_request(&argv));
+    });
+}
+
+/// Act on what this launch's own command line asks for (e.g. `--show`)
+pub fn handle_first_launch(app: &AppHandle) {
+    let argv: Vec<String> = std::env::args().collect();
+    if let Some(request) = launch_args::launch_request(&argv) {
+        handle_request(app, request);
+    }
+}
+
+fn handle_request(app: &AppHandle, request: LaunchRequest) {
+    match request {
+        LaunchRequest::OpenItems(item_ids) => {
+            for item_id in item_ids {
+                crate::open_item_from_link(app, item_id);
+            }
+        }
+        LaunchRequest::ShowPanel => {
+            if let Err(e) = show_window(app.clone()) {
+                eprintln!("Failed to show window for launch: {}", e);
+            }
+        }
+    }
+}
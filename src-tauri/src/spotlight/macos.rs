@@ -0,0 +1,213 @@
+//! CoreSpotlight bindings and the indexing worker loop
+//!
+//! CoreSpotlight is called through the raw Objective-C runtime, like the
+//! panel code in `window_commands`, so no binding crate has to match the
+//! objc2 version Tauri pulls in.
+
+use super::{deep_link, is_enabled, is_indexable, plan_sync, Job};
+use crate::models::{ClipboardItem, ContentType};
+use crate::preview;
+use crate::storage::Database;
+use objc2::msg_send;
+use objc2::rc::{autoreleasepool, Retained};
+use objc2::runtime::{AnyClass, AnyObject, Sel};
+use objc2::{sel, Message};
+use objc2_foundation::{NSArray, NSString};
+use std::ffi::c_void;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How often the worker follows the change log without being nudged
+/// Catches deletions made outside the capture path
+const SYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Domain grouping all items indexed by Clipster
+const DOMAIN_IDENTIFIER: &str = "clipster.history";
+
+/// Title length shown in Spotlight results
+const TITLE_MAX_LEN: usize = 80;
+
+/// Upper bound of items fetched per content type when rebuilding the index
+const REINDEX_LIMIT: usize = 10_000;
+
+#[link(name = "CoreSpotlight", kind = "framework")]
+extern "C" {}
+
+#[link(name = "objc", kind = "dylib")]
+extern "C" {
+    fn objc_msgSend();
+}
+
+/// `-[CSSearchableIndex.defaultSearchableIndex <sel>:completionHandler:]`
+/// with a nil handler. Sent through `objc_msgSend` directly: `msg_send!`
+/// has no argument type matching a block's encoding
+unsafe fn send_to_index<T: Message>(sel: Sel, array: &NSArray<T>) {
+    let Some(cls) = AnyClass::get("CSSearchableIndex") else {
+        eprintln!("[spotlight] CoreSpotlight is not available");
+        return;
+    };
+    let index: *mut AnyObject = msg_send![cls, defaultSearchableIndex];
+    if index.is_null() {
+        return;
+    }
+    let send: unsafe extern "C" fn(*mut AnyObject, Sel, *const NSArray<T>, *mut c_void) =
+        std::mem::transmute(objc_msgSend as unsafe extern "C" fn());
+    send(index, sel, array, std::ptr::null_mut());
+}
+
+/// `[[<class> alloc] init...]`, with `init` sending the init message
+unsafe fn new_object(
+    class: &str,
+    init: impl FnOnce(*mut AnyObject) -> *mut AnyObject,
+) -> Option<Retained<AnyObject>> {
+    let cls = AnyClass::get(class)?;
+    let allocated: *mut AnyObject = msg_send![cls, alloc];
+    Retained::from_raw(init(allocated))
+}
+
+/// Worker loop: applies jobs and periodically syncs from the change log
+pub(super) fn run_worker(db: Arc<Database>, jobs: Receiver<Job>) {
+    let mut cursor = 0;
+    loop {
+        let job = match jobs.recv_timeout(SYNC_INTERVAL) {
+            Ok(job) => job,
+            Err(RecvTimeoutError::Timeout) => Job::Sync,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+
+        let result = match job {
+            Job::Sync => sync(&db, &mut cursor),
+            Job::Reindex => reindex(&db, &mut cursor),
+            Job::DeleteAll => {
+                delete_all();
+                Ok(())
+            }
+        };
+        if let Err(e) = result {
+            eprintln!("[spotlight] {:?} failed: {}", job, e);
+        }
+    }
+}
+
+fn locked_pinboard_ids(db: &Database) -> Result<Vec<String>, String> {
+    Ok(db
+        .get_pinboards(true)?
+        .into_iter()
+        .filter(|p| p.is_locked)
+        .map(|p| p.id)
+        .collect())
+}
+
+/// Apply changes recorded since `cursor`
+fn sync(db: &Database, cursor: &mut i64) -> Result<(), String> {
+    if !is_enabled(db) {
+        return Ok(());
+    }
+
+    let changes = db.get_changes_since(*cursor)?;
+    if changes.reset {
+        return reindex(db, cursor);
+    }
+    if changes.changes.is_empty() {
+        return Ok(());
+    }
+
+    let plan = plan_sync(&changes, &locked_pinboard_ids(db)?);
+    delete_items(&plan.delete);
+    index_items(&plan.index);
+    *cursor = changes.latest_seq;
+    Ok(())
+}
+
+/// Drop the index and rebuild it from every indexable item
+fn reindex(db: &Database, cursor: &mut i64) -> Result<(), String> {
+    if !is_enabled(db) {
+        return Ok(());
+    }
+
+    let latest = db.latest_change_seq()?;
+    let locked = locked_pinboard_ids(db)?;
+    let mut items = db.get_items_by_type(ContentType::Text, REINDEX_LIMIT)?;
+    items.extend(db.get_items_by_type(ContentType::Link, REINDEX_LIMIT)?);
+    items.retain(|item| is_indexable(item, &locked));
+
+    delete_all();
+    index_items(&items);
+    *cursor = latest;
+    Ok(())
+}
+
+/// A `CSSearchableItem` for `item`; None when CoreSpotlight is not available
+unsafe fn searchable_item(item: &ClipboardItem) -> Option<Retained<AnyObject>> {
+    let text = NSString::from_str(item.content_text.as_deref().unwrap_or(""));
+    let title = NSString::from_str(&preview::preview_item(item, TITLE_MAX_LEN));
+
+    // initWithItemContentType takes a UTI string and avoids a
+    // UniformTypeIdentifiers dependency
+    let content_type = NSString::from_str("public.plain-text");
+    let attributes = new_object("CSSearchableItemAttributeSet", |allocated| {
+        msg_send![allocated, initWithItemContentType: &*content_type]
+    })?;
+    let _: () = msg_send![&*attributes, setTitle: &*title];
+    let _: () = msg_send![&*attributes, setContentDescription: &*text];
+    let _: () = msg_send![&*attributes, setTextContent: &*text];
+    if let Some(url_cls) = AnyClass::get("NSURL") {
+        let link = NSString::from_str(&deep_link(&item.id));
+        let url: *mut AnyObject = msg_send![url_cls, URLWithString: &*link];
+        if !url.is_null() {
+            let _: () = msg_send![&*attributes, setContentURL: url];
+        }
+    }
+
+    let id = NSString::from_str(&item.id);
+    let domain = NSString::from_str(DOMAIN_IDENTIFIER);
+    new_object("CSSearchableItem", |allocated| {
+        msg_send![
+            allocated,
+            initWithUniqueIdentifier: &*id
+            domainIdentifier: &*domain
+            attributeSet: &*attributes
+        ]
+    })
+}
+
+fn index_items(items: &[ClipboardItem]) {
+    if items.is_empty() {
+        return;
+    }
+    autoreleasepool(|_| unsafe {
+        let searchable: Vec<Retained<AnyObject>> =
+            items.iter().filter_map(|item| searchable_item(item)).collect();
+        if !searchable.is_empty() {
+            send_to_index(
+                sel!(indexSearchableItems:completionHandler:),
+                &NSArray::from_vec(searchable),
+            );
+        }
+    });
+}
+
+fn delete_items(ids: &[String]) {
+    if ids.is_empty() {
+        return;
+    }
+    let ids: Vec<Retained<NSString>> = ids.iter().map(|id| NSString::from_str(id)).collect();
+    autoreleasepool(|_| unsafe {
+        send_to_index(
+            sel!(deleteSearchableItemsWithIdentifiers:completionHandler:),
+            &NSArray::from_vec(ids),
+        );
+    });
+}
+
+/// Remove everything Clipster has indexed
+fn delete_all() {
+    let domains = NSArray::from_vec(vec![NSString::from_str(DOMAIN_IDENTIFIER)]);
+    autoreleasepool(|_| unsafe {
+        send_to_index(
+            sel!(deleteSearchableItemsWithDomainIdentifiers:completionHandler:),
+            &domains,
+        );
+    });
+}
@@ -0,0 +1,194 @@
+//! Optional macOS Spotlight indexing of clipboard history
+//!
+//! When the `spotlight_indexing` setting is on, text and link items are
+//! indexed as `CSSearchableItem`s whose unique identifier is the item ID.
+//! Indexing runs on a background worker that follows the `item_events` change
+//! log, so captures never wait on CoreSpotlight and every deletion path
+//! (delete, clear, prune, move-to-top) removes its index entry. Spotlight
+//! results link back into the app through `clipster://item/<id>`.
+
+#[cfg(target_os = "macos")]
+mod macos;
+
+use crate::models::{ChangeSet, ClipboardItem, ContentType, ItemChangeKind};
+use crate::storage::Database;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// URL scheme registered for deep links
+pub const DEEP_LINK_SCHEME: &str = "clipster";
+
+/// Deep link that opens an item in Clipster
+pub fn deep_link(item_id: &str) -> String {
+    format!("{}://item/{}", DEEP_LINK_SCHEME, item_id)
+}
+
+/// Item ID from a `clipster://item/<id>` deep link
+pub fn parse_deep_link(url: &str) -> Option<String> {
+    let rest = url.strip_prefix(DEEP_LINK_SCHEME)?.strip_prefix("://item/")?;
+    let id = rest.trim_end_matches('/');
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+/// Whether the `spotlight_indexing` setting is on
+pub fn is_enabled(db: &Database) -> bool {
+    db.get_setting("spotlight_indexing").ok().flatten().as_deref() == Some("true")
+}
+
+fn is_text_or_link(item: &ClipboardItem) -> bool {
+    matches!(item.content_type, ContentType::Text | ContentType::Link)
+}
+
+/// Text and link items outside passcode-locked pinboards (filed or
+/// auto-filed there) are indexed
+pub fn is_indexable(item: &ClipboardItem, locked_pinboards: &[String]) -> bool {
+    is_text_or_link(item)
+        && item.content_text.is_some()
+        && !item
+            .filed_pinboard_id()
+            .is_some_and(|id| locked_pinboards.iter().any(|locked| locked == id))
+}
+
+/// Index updates needed to apply a change set
+#[derive(Debug, Default)]
+pub struct SyncPlan {
+    pub index: Vec<ClipboardItem>,
+    pub delete: Vec<String>,
+}
+
+/// Split a change set into items to (re)index and index entries to remove
+/// Items that stopped being indexable (e.g. moved into a locked pinboard) are removed
+pub fn plan_sync(changes: &ChangeSet, locked_pinboards: &[String]) -> SyncPlan {
+    let mut plan = SyncPlan::default();
+    for change in &changes.changes {
+        match (&change.kind, &change.item) {
+            (ItemChangeKind::Delete, _) | (_, None) => plan.delete.push(change.item_id.clone()),
+            (_, Some(item)) if is_indexable(item, locked_pinboards) => {
+                plan.index.push(item.clone())
+            }
+            // Only text and link items can have an entry to remove
+            (_, Some(item)) if is_text_or_link(item) => plan.delete.push(change.item_id.clone()),
+            (_, Some(_)) => {}
+        }
+    }
+    plan
+}
+
+/// Work sent to the indexing worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Job {
+    /// Apply changes recorded since the last sync
+    Sync,
+    /// Drop the whole index and rebuild it from the database
+    Reindex,
+    /// Remove every indexed item (feature turned off)
+    DeleteAll,
+}
+
+/// Handle to the background Spotlight indexing worker
+/// A no-op on platforms without Spotlight
+pub struct SpotlightIndexer {
+    jobs: Option<Sender<Job>>,
+}
+
+impl SpotlightIndexer {
+    /// Start the worker; the index is rebuilt when `reindex` is called, at
+    /// launch once the app is ready
+    #[cfg(target_os = "macos")]
+    pub fn start(db: Arc<Database>) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || macos::run_worker(db, receiver));
+
+        Self { jobs: Some(sender) }
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    pub fn start(_db: Arc<Database>) -> Self {
+        Self { jobs: None }
+    }
+
+    fn send(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(job);
+        }
+    }
+
+    /// Pick up recent changes now instead of at the next periodic sync
+    pub fn sync(&self) {
+        self.send(Job::Sync);
+    }
+
+    /// Rebuild the whole index
+    pub fn reindex(&self) {
+        self.send(Job::Reindex);
+    }
+
+    /// Remove every indexed item
+    pub fn delete_all(&self) {
+        self.send(Job::DeleteAll);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ItemChange;
+
+    #[test]
+    fn test_deep_link_round_trip() {
+        let link = deep_link("abc-123");
+        assert_eq!(link, "clipster://item/abc-123");
+        assert_eq!(parse_deep_link(&link), Some("abc-123".to_string()));
+        assert_eq!(parse_deep_link("clipster://item/abc-123/"), Some("abc-123".to_string()));
+
+        assert_eq!(parse_deep_link("clipster://item/"), None);
+        assert_eq!(parse_deep_link("clipster://pinboard/abc"), None);
+        assert_eq!(parse_deep_link("https://item/abc"), None);
+        assert_eq!(parse_deep_link("clipster://item/a/b"), None);
+    }
+
+    #[test]
+    fn test_plan_sync() {
+        let text = ClipboardItem::new_text("hello".to_string(), None, None);
+        let image = ClipboardItem::new_image(None, "/tmp/x.png".to_string(), None, None);
+        let mut locked = ClipboardItem::new_text("secret".to_string(), None, None);
+        locked.pinboard_id = Some("vault".to_string());
+
+        let change = |item: &ClipboardItem, kind| ItemChange {
+            seq: 1,
+            item_id: item.id.clone(),
+            kind,
+            item: (kind != ItemChangeKind::Delete).then(|| item.clone()),
+        };
+        let changes = ChangeSet {
+            latest_seq: 4,
+            reset: false,
+            changes: vec![
+                change(&text, ItemChangeKind::Insert),
+                change(&image, ItemChangeKind::Insert),
+                change(&locked, ItemChangeKind::Update),
+                change(&text, ItemChangeKind::Delete),
+            ],
+        };
+
+        let plan = plan_sync(&changes, &["vault".to_string()]);
+        assert_eq!(plan.index.len(), 1);
+        assert_eq!(plan.index[0].id, text.id);
+        assert_eq!(plan.delete, vec![locked.id.clone(), text.id.clone()]);
+    }
+
+    #[test]
+    fn test_auto_filed_items_of_locked_pinboards_are_not_indexed() {
+        let mut auto_filed = ClipboardItem::new_text("secret".to_string(), None, None);
+        auto_filed.auto_pinboard_id = Some("vault".to_string());
+        assert!(!is_indexable(&auto_filed, &["vault".to_string()]));
+        assert!(is_indexable(&auto_filed, &["other".to_string()]));
+
+        // Pinning elsewhere takes the item out of the auto-filed board
+        auto_filed.pinboard_id = Some("work".to_string());
+        assert!(is_indexable(&auto_filed, &["vault".to_string()]));
+    }
+}
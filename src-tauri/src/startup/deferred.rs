@@ -0,0 +1,131 @@
+//! Startup work that can wait until the app is ready
+//!
+//! Only opening the database is needed before the window and the hotkey
+//! work; housekeeping such as removing files left by the last run or
+//! rebuilding the Spotlight index runs on one background thread started after
+//! `app-ready`. Tasks run in order, each timed as `deferred.<name>`; one that
+//! fails or panics is logged and the next still runs.
+
+use crate::error::ClipsterError;
+use crate::startup::timings;
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+use std::time::Instant;
+
+type Task = Box<dyn FnOnce() -> Result<(), ClipsterError> + Send>;
+
+/// A named piece of deferred work
+pub struct DeferredTask {
+    pub name: &'static str,
+    run: Task,
+}
+
+impl DeferredTask {
+    pub fn new<F>(name: &'static str, run: F) -> Self
+    where
+        F: FnOnce() -> Result<(), ClipsterError> + Send + 'static,
+    {
+        Self {
+            name,
+            run: Box::new(run),
+        }
+    }
+}
+
+/// How a deferred task ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskOutcome {
+    Done,
+    Failed(String),
+    Panicked,
+}
+
+/// Run `tasks` in order on a background thread
+pub fn spawn(tasks: Vec<DeferredTask>) {
+    let spawned = thread::Builder::new()
+        .name("deferred-startup".to_string())
+        .spawn(move || run_all(tasks));
+    if let Err(e) = spawned {
+        eprintln!("[startup] Failed to start deferred tasks: {}", e);
+    }
+}
+
+/// Run `tasks` in order, timing each; failures and panics don't stop the rest
+pub fn run_all(tasks: Vec<DeferredTask>) -> Vec<(&'static str, TaskOutcome)> {
+    tasks
+        .into_iter()
+        .map(|task| {
+            let started = Instant::now();
+            let outcome = match panic::catch_unwind(AssertUnwindSafe(task.run)) {
+                Ok(Ok(())) => TaskOutcome::Done,
+                Ok(Err(e)) => {
+                    eprintln!("[startup] Deferred task {} failed: {}", task.name, e);
+                    TaskOutcome::Failed(e.to_string())
+                }
+                Err(_) => {
+                    eprintln!("[startup] Deferred task {} panicked", task.name);
+                    TaskOutcome::Panicked
+                }
+            };
+            timings::record(&format!("deferred.{}", task.name), started.elapsed());
+            (task.name, outcome)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_failures_are_not_fatal() {
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let log = |name: &'static str| {
+            let ran = Arc::clone(&ran);
+            move || ran.lock().unwrap().push(name)
+        };
+
+        let (first, last) = (log("first"), log("last"));
+        let outcomes = run_all(vec![
+            DeferredTask::new("test_ok", move || {
+                first();
+                Ok(())
+            }),
+            DeferredTask::new("test_error", || {
+                Err(ClipsterError::Io("disk gone".to_string()))
+            }),
+            DeferredTask::new("test_panic", || panic!("bug in a task")),
+            DeferredTask::new("test_after", move || {
+                last();
+                Ok(())
+            }),
+        ]);
+
+        assert_eq!(
+            outcomes,
+            vec![
+                ("test_ok", TaskOutcome::Done),
+                ("test_error", TaskOutcome::Failed("disk gone".to_string())),
+                ("test_panic", TaskOutcome::Panicked),
+                ("test_after", TaskOutcome::Done),
+            ]
+        );
+        assert_eq!(*ran.lock().unwrap(), vec!["first", "last"]);
+
+        let timed: Vec<String> = timings::phases()
+            .into_iter()
+            .map(|timing| timing.phase)
+            .filter(|phase| phase.starts_with("deferred.test_"))
+            .collect();
+        assert_eq!(
+            timed,
+            vec![
+                "deferred.test_ok",
+                "deferred.test_error",
+                "deferred.test_panic",
+                "deferred.test_after"
+            ]
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Launch state resolved from saved settings
+
+use crate::storage::Database;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// State the app starts in, sent to the frontend with `app-ready`
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct InitialState {
+    /// The panel is shown at launch (`start_hidden` is off)
+    pub panel_visible: bool,
+    /// Clipboard capture was left paused (`capture_paused`)
+    pub capture_paused: bool,
+    /// The tray / menu bar icon is shown (`show_menu_bar_icon`)
+    pub menu_bar_icon_visible: bool,
+    /// Panel view saved when it was last hidden (`view_state`), so the first
+    /// render opens on it
+    pub view_state: Option<serde_json::Value>,
+}
+
+impl InitialState {
+    /// Read the launch settings; missing or unreadable ones use their defaults
+    pub fn load(db: &Database) -> Self {
+        Self {
+            panel_visible: !bool_setting(db, "start_hidden", false),
+            capture_paused: bool_setting(db, "capture_paused", false),
+            menu_bar_icon_visible: bool_setting(db, "show_menu_bar_icon", true),
+            view_state: db.get_view_state().unwrap_or_else(|e| {
+                eprintln!("[startup] Failed to read view_state: {}", e);
+                None
+            }),
+        }
+    }
+}
+
+fn bool_setting(db: &Database, key: &str, default: bool) -> bool {
+    match db.get_setting(key) {
+        Ok(Some(value)) => value == "true",
+        Ok(None) => default,
+        Err(e) => {
+            eprintln!("[startup] Failed to read {}: {}", key, e);
+            default
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults_show_panel_and_capture() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(
+            InitialState::load(&db),
+            InitialState {
+                panel_visible: true,
+                capture_paused: false,
+                menu_bar_icon_visible: true,
+                view_state: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_saved_settings_are_restored() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("start_hidden", "true").unwrap();
+        db.set_setting("capture_paused", "true").unwrap();
+        db.set_setting("show_menu_bar_icon", "false").unwrap();
+        let view_state = serde_json::json!({ "active_pinboard_id": "snippets" });
+        db.set_view_state(&view_state).unwrap();
+        assert_eq!(
+            InitialState::load(&db),
+            InitialState {
+                panel_visible: false,
+                capture_paused: true,
+                menu_bar_icon_visible: false,
+                view_state: Some(view_state),
+            }
+        );
+    }
+}
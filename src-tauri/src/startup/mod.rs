@@ -0,0 +1,60 @@
+//! Startup coordinator
+//!
+//! Runs at the end of setup, once the main window and the tray exist, and
+//! applies the saved launch state: the panel is only shown when `start_hidden`
+//! is off (the window is configured either way, so the first show behaves like
+//! any other), capture stays stopped when it was left paused, and the tray icon
+//! follows `show_menu_bar_icon`. The resolved state, with the saved panel view,
+//! is sent in one `app-ready` event; `get_initial_state` returns it to a
+//! frontend that loaded after it. Housekeeping that capture doesn't need runs
+//! after that (see `deferred`), and every phase is timed (see `timings`).
+
+pub mod deferred;
+pub mod initial_state;
+pub mod timings;
+
+pub use initial_state::InitialState;
+
+use crate::clipboard::clipboard_monitor;
+use crate::commands::settings_commands::apply_menu_bar_icon_visible;
+use crate::commands::window_commands::{show_panel, MAIN_WINDOW_LABEL};
+use crate::events;
+use crate::storage::{Database, FileStorage};
+use std::sync::{Arc, OnceLock};
+use tauri::{AppHandle, Manager};
+
+/// State resolved at launch
+static INITIAL_STATE: OnceLock<InitialState> = OnceLock::new();
+
+/// Apply the saved launch state and announce it with `app-ready`
+pub fn apply(app: &AppHandle, db: Arc<Database>, file_storage: Arc<FileStorage>) -> InitialState {
+    let state = InitialState::load(&db);
+
+    if state.capture_paused {
+        println!("Clipboard capture paused (restored from last session)");
+    } else if let Err(e) = clipboard_monitor::start_monitoring(app.clone(), db, file_storage) {
+        eprintln!("Failed to start clipboard monitor: {}", e);
+    } else {
+        println!("Clipboard monitor started successfully");
+    }
+
+    if let Err(e) = apply_menu_bar_icon_visible(app, state.menu_bar_icon_visible) {
+        eprintln!("[startup] {}", e);
+    }
+
+    if state.panel_visible {
+        if let Some(window) = app.get_webview_window(MAIN_WINDOW_LABEL) {
+            // Show the panel via native APIs (no Space switch)
+            show_panel(&window);
+        }
+    }
+
+    let _ = INITIAL_STATE.set(state.clone());
+    events::emit(app, &state);
+    state
+}
+
+/// The state applied at launch (None while setup is still running)
+pub fn initial_state() -> Option<InitialState> {
+    INITIAL_STATE.get().cloned()
+}
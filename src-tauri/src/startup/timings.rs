@@ -0,0 +1,99 @@
+//! Durations of the startup phases
+//!
+//! Each phase is logged as it ends and kept for `get_monitoring_status`, so a
+//! slower launch shows which phase grew.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long one phase took
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub duration_ms: u64,
+}
+
+/// Phases recorded since launch, in the order they ended
+static PHASES: Mutex<Vec<PhaseTiming>> = Mutex::new(Vec::new());
+
+/// Log `duration` for `phase` and keep it
+pub fn record(phase: &str, duration: Duration) {
+    let timing = PhaseTiming {
+        phase: phase.to_string(),
+        duration_ms: duration.as_millis() as u64,
+    };
+    println!("[startup] {}: {}ms", timing.phase, timing.duration_ms);
+    if let Ok(mut phases) = PHASES.lock() {
+        phases.push(timing);
+    }
+}
+
+/// Every phase recorded so far
+pub fn phases() -> Vec<PhaseTiming> {
+    PHASES
+        .lock()
+        .map(|phases| phases.clone())
+        .unwrap_or_default()
+}
+
+/// Times consecutive phases from a common start
+pub struct PhaseTimer {
+    started: Instant,
+    lap: Instant,
+}
+
+impl PhaseTimer {
+    pub fn start() -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            lap: now,
+        }
+    }
+
+    /// End `phase`, which began when the previous one ended
+    /// Returns its duration
+    pub fn lap(&mut self, phase: &str) -> Duration {
+        let now = Instant::now();
+        let duration = now - self.lap;
+        self.lap = now;
+        record(phase, duration);
+        duration
+    }
+
+    /// Record the time since the timer started as `phase`
+    pub fn total(&self, phase: &str) -> Duration {
+        let duration = self.started.elapsed();
+        record(phase, duration);
+        duration
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_laps_are_consecutive_and_recorded() {
+        let mut timer = PhaseTimer::start();
+        thread::sleep(Duration::from_millis(20));
+        let first = timer.lap("test_first");
+        thread::sleep(Duration::from_millis(10));
+        let second = timer.lap("test_second");
+        let total = timer.total("test_total");
+
+        assert!(first >= Duration::from_millis(20));
+        assert!(second >= Duration::from_millis(10) && second < first + second);
+        assert!(total >= first + second);
+
+        // Other tests may record phases too
+        let names: Vec<String> = phases()
+            .into_iter()
+            .map(|timing| timing.phase)
+            .filter(|phase| phase.starts_with("test_"))
+            .collect();
+        assert_eq!(names, vec!["test_first", "test_second", "test_total"]);
+    }
+}
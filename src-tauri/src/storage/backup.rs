@@ -0,0 +1,198 @@
+//! Verified database backups and their schedule
+//!
+//! `Database::backup_to` copies the live database; `backup_now` runs it on
+//! demand. With the `backup_interval_hours` setting above 0, a background
+//! thread also backs up the active profile into its `backups` directory once
+//! the newest backup there is that old, keeping the last `KEEP_BACKUPS`.
+//! The schedule is read from the file names, so a backup missed while the app
+//! was closed runs at the first check after launch.
+
+use crate::error::ClipsterError;
+use crate::storage::{profiles, Database};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Directory of scheduled backups inside a profile data directory
+pub const BACKUP_DIR: &str = "backups";
+
+/// Scheduled backups kept; older ones are deleted after each new one
+pub const KEEP_BACKUPS: usize = 7;
+
+/// How often the scheduler checks whether a backup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Name of a scheduled backup: `clipster-<UTC time>.db`
+const FILE_PREFIX: &str = "clipster-";
+const FILE_SUFFIX: &str = ".db";
+const FILE_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// Progress of a running backup, reported after each step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct BackupProgress {
+    pub copied_pages: i32,
+    pub total_pages: i32,
+}
+
+/// Result of a verified backup
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BackupReport {
+    pub path: String,
+    pub size_bytes: u64,
+    pub total_pages: i32,
+}
+
+/// Time between scheduled backups (None when the `backup_interval_hours`
+/// setting is 0 or unset)
+pub fn backup_interval(db: &Database) -> Result<Option<chrono::Duration>, ClipsterError> {
+    let hours = db
+        .get_setting("backup_interval_hours")?
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    Ok((hours > 0).then(|| chrono::Duration::hours(hours)))
+}
+
+/// Start the scheduler thread
+pub fn start_scheduler(db: Arc<Database>) {
+    thread::spawn(move || loop {
+        if let Err(e) = run_scheduled(&db) {
+            eprintln!("[backup] Scheduled backup failed: {}", e);
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+fn run_scheduled(db: &Database) -> Result<(), ClipsterError> {
+    let Some(interval) = backup_interval(db)? else {
+        return Ok(());
+    };
+    let data_dir = profiles::active_data_dir().map_err(ClipsterError::Io)?;
+    if let Some(report) = run_if_due(db, &data_dir.join(BACKUP_DIR), interval, Utc::now())? {
+        println!(
+            "[backup] Backed up {} pages to {}",
+            report.total_pages, report.path
+        );
+    }
+    Ok(())
+}
+
+/// Back up `db` into `dir` unless its newest backup is younger than
+/// `interval`, then delete all but the newest `KEEP_BACKUPS`
+pub fn run_if_due(
+    db: &Database,
+    dir: &Path,
+    interval: chrono::Duration,
+    now: DateTime<Utc>,
+) -> Result<Option<BackupReport>, ClipsterError> {
+    let backups = scheduled_backups(dir);
+    if let Some((newest, _)) = backups.last() {
+        if now - *newest < interval {
+            return Ok(None);
+        }
+    }
+
+    let name = format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        now.format(FILE_TIME_FORMAT),
+        FILE_SUFFIX
+    );
+    let report = db.backup_to(&dir.join(name), |_| {})?;
+
+    let backups = scheduled_backups(dir);
+    let expired = backups.len().saturating_sub(KEEP_BACKUPS);
+    for (_, path) in &backups[..expired] {
+        if let Err(e) = fs::remove_file(path) {
+            eprintln!(
+                "[backup] Failed to delete old backup {}: {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    Ok(Some(report))
+}
+
+/// Scheduled backups in `dir` by time taken, oldest first
+/// Files not named by the scheduler are ignored
+fn scheduled_backups(dir: &Path) -> Vec<(DateTime<Utc>, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<_> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let time = name
+                .to_str()?
+                .strip_prefix(FILE_PREFIX)?
+                .strip_suffix(FILE_SUFFIX)?;
+            let taken = NaiveDateTime::parse_from_str(time, FILE_TIME_FORMAT).ok()?;
+            Some((taken.and_utc(), entry.path()))
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardItem;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_backup_interval_setting() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(backup_interval(&db).unwrap(), None);
+
+        db.set_setting("backup_interval_hours", "0").unwrap();
+        assert_eq!(backup_interval(&db).unwrap(), None);
+
+        db.set_setting("backup_interval_hours", "24").unwrap();
+        assert_eq!(
+            backup_interval(&db).unwrap(),
+            Some(chrono::Duration::hours(24))
+        );
+    }
+
+    #[test]
+    fn test_scheduled_backups_run_when_due_and_rotate() {
+        let root = temp_dir("scheduled-backup");
+        let db = Database::open(&root.join("data")).unwrap();
+        db.insert_item(&ClipboardItem::new_text("kept".to_string(), None, None))
+            .unwrap();
+        let dir = root.join(BACKUP_DIR);
+        let interval = chrono::Duration::hours(24);
+        let start = Utc::now();
+
+        // First check backs up, the next one within the interval does not
+        assert!(run_if_due(&db, &dir, interval, start).unwrap().is_some());
+        let later = start + chrono::Duration::hours(23);
+        assert!(run_if_due(&db, &dir, interval, later).unwrap().is_none());
+
+        // A day each; only the newest KEEP_BACKUPS remain
+        let mut now = start;
+        for _ in 0..KEEP_BACKUPS + 2 {
+            now += interval;
+            assert!(run_if_due(&db, &dir, interval, now).unwrap().is_some());
+        }
+        let backups = scheduled_backups(&dir);
+        assert_eq!(backups.len(), KEEP_BACKUPS);
+        assert_eq!(backups.last().unwrap().0.timestamp(), now.timestamp());
+
+        // Other files are left alone
+        fs::write(dir.join("notes.txt"), "mine").unwrap();
+        now += interval;
+        run_if_due(&db, &dir, interval, now).unwrap();
+        assert!(dir.join("notes.txt").exists());
+        assert_eq!(scheduled_backups(&dir).len(), KEEP_BACKUPS);
+    }
+}
@@ -0,0 +1,77 @@
+//! zstd compression of large text content
+//!
+//! When the `compress_large_text` setting is on, text above the threshold is
+//! kept in the database as a zstd blob in `content_compressed` instead of
+//! being spilled to disk. `content_text` then only holds a preview, which
+//! search uses to pre-filter rows before decompressing.
+
+/// Size above which text is compressed (64KB)
+pub const COMPRESSION_THRESHOLD: usize = 64 * 1024;
+
+/// zstd level: fast, still shrinks logs and dumps several times
+const ZSTD_LEVEL: i32 = 3;
+
+/// Whether text of this size should be stored compressed
+pub fn should_compress(text: &str, threshold: usize) -> bool {
+    text.len() > threshold
+}
+
+/// Compress text with zstd
+pub fn compress_text(text: &str) -> Result<Vec<u8>, String> {
+    zstd::bulk::compress(text.as_bytes(), ZSTD_LEVEL)
+        .map_err(|e| format!("Failed to compress text: {}", e))
+}
+
+/// Decompress text produced by `compress_text`
+pub fn decompress_text(data: &[u8]) -> Result<String, String> {
+    let bytes = zstd::stream::decode_all(data)
+        .map_err(|e| format!("Failed to decompress text: {}", e))?;
+    String::from_utf8(bytes).map_err(|e| format!("Decompressed text is not UTF-8: {}", e))
+}
+
+/// Original size recorded in the zstd frame header (None if unknown)
+pub fn original_size(data: &[u8]) -> Option<u64> {
+    zstd::zstd_safe::get_frame_content_size(data).ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        for text in [
+            String::new(),
+            "hello".to_string(),
+            "line\r\nwith\ttabs and ünïcödé 🦀\u{200D}".repeat(5000),
+        ] {
+            let compressed = compress_text(&text).unwrap();
+            assert_eq!(decompress_text(&compressed).unwrap(), text);
+            assert_eq!(original_size(&compressed), Some(text.len() as u64));
+        }
+    }
+
+    #[test]
+    fn test_compresses_repetitive_text() {
+        let text = "SELECT * FROM logs;\n".repeat(10_000);
+        let compressed = compress_text(&text).unwrap();
+        assert!(compressed.len() * 10 < text.len());
+    }
+
+    #[test]
+    fn test_threshold_boundary() {
+        let at = "x".repeat(COMPRESSION_THRESHOLD);
+        let above = "x".repeat(COMPRESSION_THRESHOLD + 1);
+        assert!(!should_compress(&at, COMPRESSION_THRESHOLD));
+        assert!(should_compress(&above, COMPRESSION_THRESHOLD));
+
+        // Multi-byte characters count in bytes, not chars
+        let wide = "é".repeat(COMPRESSION_THRESHOLD / 2 + 1);
+        assert!(should_compress(&wide, COMPRESSION_THRESHOLD));
+    }
+
+    #[test]
+    fn test_invalid_data_is_an_error() {
+        assert!(decompress_text(b"not zstd").is_err());
+    }
+}
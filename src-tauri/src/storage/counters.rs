@@ -0,0 +1,130 @@
+//! Lifetime usage counters
+//!
+//! The `counters` table keeps totals that outlive the items they count:
+//! captures (overall and per content type), pastes and pruned items. Each
+//! counter is bumped in the same transaction as the write it counts, so a
+//! crash or a failed insert can't leave the totals out of step. `get_stats`
+//! reports them alongside the item counts.
+
+use crate::error::{ClipsterError, Context};
+use crate::models::ContentType;
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+
+/// Items saved from the clipboard
+pub const CAPTURES: &str = "captures";
+
+/// Items copied back to the clipboard (copy-back, paste, palette)
+pub const PASTES: &str = "pastes";
+
+/// History items deleted by pruning
+pub const ITEMS_PRUNED: &str = "items_pruned";
+
+/// Counter of the captures of one content type (`captures.text`, ...)
+pub fn captures_of(content_type: ContentType) -> String {
+    format!("{}.{}", CAPTURES, content_type.as_str())
+}
+
+/// Add `by` to the counter `name`, creating it at zero first
+pub fn increment(conn: &Connection, name: &str, by: u64) -> Result<(), ClipsterError> {
+    conn.execute(
+        "INSERT INTO counters (name, value) VALUES (?1, ?2)
+         ON CONFLICT(name) DO UPDATE SET value = value + excluded.value",
+        params![name, by as i64],
+    )
+    .context("Failed to update counter")?;
+    Ok(())
+}
+
+/// Every counter by name
+pub fn read_all(conn: &Connection) -> Result<BTreeMap<String, u64>, ClipsterError> {
+    let mut stmt = conn
+        .prepare_cached("SELECT name, value FROM counters")
+        .context("Failed to prepare counters query")?;
+    let counters = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?.max(0) as u64,
+            ))
+        })
+        .context("Failed to query counters")?
+        .collect::<rusqlite::Result<_>>()
+        .context("Failed to read counters")?;
+    Ok(counters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardItem;
+    use crate::storage::Database;
+
+    fn counter(db: &Database, name: &str) -> u64 {
+        db.get_counters().unwrap().get(name).copied().unwrap_or(0)
+    }
+
+    #[test]
+    fn test_captures_are_counted_by_type() {
+        let db = Database::new_in_memory().unwrap();
+        db.insert_capture(&ClipboardItem::new_text("one".to_string(), None, None))
+            .unwrap();
+        db.insert_capture(&ClipboardItem::new_text("two".to_string(), None, None))
+            .unwrap();
+        db.insert_capture(&ClipboardItem::new_link(
+            "https://example.com".to_string(),
+            None,
+            None,
+        ))
+        .unwrap();
+        // Items added any other way are not captures
+        db.insert_item(&ClipboardItem::new_text("imported".to_string(), None, None))
+            .unwrap();
+
+        assert_eq!(counter(&db, CAPTURES), 3);
+        assert_eq!(counter(&db, &captures_of(ContentType::Text)), 2);
+        assert_eq!(counter(&db, &captures_of(ContentType::Link)), 1);
+        assert_eq!(counter(&db, &captures_of(ContentType::Image)), 0);
+
+        db.increment_counter(PASTES).unwrap();
+        assert_eq!(counter(&db, PASTES), 1);
+    }
+
+    #[test]
+    fn test_failed_insert_counts_nothing() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("once".to_string(), None, None);
+        db.insert_capture(&item).unwrap();
+
+        // Same ID again: the insert fails after the counters were bumped
+        assert!(db.insert_capture(&item).is_err());
+        assert_eq!(counter(&db, CAPTURES), 1);
+        assert_eq!(counter(&db, &captures_of(ContentType::Text)), 1);
+    }
+
+    #[test]
+    fn test_uses_and_pruning_are_counted() {
+        let db = Database::new_in_memory().unwrap();
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let item = ClipboardItem::new_text(format!("item {}", i), None, None);
+            db.insert_capture(&item).unwrap();
+            ids.push(item.id);
+        }
+
+        db.record_item_use(&ids[0]).unwrap();
+        db.record_item_use(&ids[0]).unwrap();
+        // Unknown items count nothing
+        db.record_item_use("missing").unwrap();
+        assert_eq!(counter(&db, PASTES), 2);
+
+        assert_eq!(db.prune_oldest(2).unwrap(), 3);
+        assert_eq!(db.prune_oldest(2).unwrap(), 0);
+        assert_eq!(counter(&db, ITEMS_PRUNED), 3);
+
+        let stats = db.get_stats().unwrap();
+        assert_eq!(stats.total_items, 2);
+        assert_eq!(stats.counters.get(CAPTURES), Some(&5));
+        assert_eq!(stats.counters.get(ITEMS_PRUNED), Some(&3));
+    }
+}
@@ -1,30 +1,249 @@
-use crate::models::{ClipboardItem, ContentType, Pinboard};
-use rusqlite::{params, Connection, Result as SqliteResult};
-use std::path::PathBuf;
+use crate::auto_clear::schedule::AutoClearSchedule;
+use crate::clipboard::app_id;
+use crate::clipboard::canonical_url::{canonicalize_url, DEFAULT_TRACKING_PARAMS};
+use crate::clipboard::capture_merge;
+use crate::clipboard::contact_event::{self, StructuredPayload};
+use crate::clipboard::file_group;
+use crate::clipboard::transforms::Transform;
+use crate::clipboard::{language, palette};
+use crate::error::{ClipsterError, Context};
+use crate::models::app_group::UNKNOWN_APP;
+use crate::models::search_group::HISTORY_GROUP;
+use crate::models::{
+    AppGroup, BulkItemResult, BulkOp, ChangeSet, ClipboardItem, ContentType, DuplicateGroup,
+    DuplicateKind, ItemChange, ItemChangeKind, ItemRevision, MediaMetadata, Pinboard,
+    LanguageChange, PinboardRules, Reclassification, SearchGroup, MAX_REVISIONS_PER_ITEM,
+};
+use crate::panel_placement::{MonitorPlacements, Placement};
+use crate::preview;
+use crate::storage::backup::{BackupProgress, BackupReport};
+use crate::storage::counters;
+use crate::storage::destructive_guard::DeletionSummary;
+use crate::storage::duplicates::{self, DHASH_MAX_DISTANCE};
+use crate::storage::history_archive;
+use crate::storage::pinboard_tree::{self, ParentMap};
+use crate::storage::recovery::{self, RecoveryReport};
+use crate::storage::reindex::{DerivedColumn, DerivedUpdate};
+use crate::storage::stats::StorageStats;
+use crate::storage::{compression, file_storage, FileStorage};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::backup::{Backup, StepResult};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension, Result as SqliteResult};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::Duration;
+
+/// ID, source_app, source_app_icon, source_window_title and first_copied_at of an
+/// item removed by "move to top"
+type ReplacedItem = (
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<DateTime<Utc>>,
+);
+
+/// Timestamp the history list is ordered by (newest first)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistorySort {
+    /// Last time the content was copied (move-to-top brings items up)
+    #[default]
+    CreatedAt,
+    /// First time the content was copied, kept across move-to-top
+    FirstCopiedAt,
+}
+
+impl HistorySort {
+    fn order_column(self) -> &'static str {
+        match self {
+            HistorySort::CreatedAt => "created_at",
+            HistorySort::FirstCopiedAt => "first_copied_at",
+        }
+    }
+}
+
+/// Narrows search results beyond the query text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SearchFilter {
+    /// Language of text items, as an ISO 639-3 code (see `clipboard::language`)
+    pub language: Option<String>,
+}
+
+/// Which earlier copies "move to top" replaces (`dedupe_scope` setting)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DedupeScope {
+    /// Any unpinned item with the same content
+    #[default]
+    Global,
+    /// Only items captured from the same source app
+    PerApp,
+    /// Never; every copy is a new item
+    Off,
+}
+
+impl DedupeScope {
+    /// Parse `global`, `per_app` or `off`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "global" => Some(Self::Global),
+            "per_app" => Some(Self::PerApp),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// Number of events kept in the `item_events` change log
+const ITEM_EVENTS_RETAINED: i64 = 5000;
+
+/// Longest preview shown for a group of duplicates
+const DUPLICATE_PREVIEW_LEN: usize = 120;
+
+/// Items updated per transaction by `apply_reclassifications`, so captures
+/// and UI queries get the connection between batches
+const RECLASSIFY_BATCH_SIZE: usize = 300;
+
+/// Pages copied per backup step
+const BACKUP_PAGES_PER_STEP: i32 = 64;
+
+/// Wait before retrying a backup step when the destination is busy
+const BACKUP_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Statements kept compiled by `prepare_cached` (rusqlite's default of 16 is
+/// smaller than the set of hot queries, thumbnail variants included)
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Largest panel view state accepted by `set_view_state`, in bytes of JSON
+pub const MAX_VIEW_STATE_BYTES: usize = 8 * 1024;
+
+/// Settings seeded on first launch and written back by `reset_settings_to_defaults`
+pub const DEFAULT_SETTINGS: &[(&str, &str)] = &[
+    ("history_limit", "500"),
+    ("shortcut", "Ctrl+Shift+V"),
+    ("start_hidden", "false"),
+    ("theme", "dark"),
+    ("show_menu_bar_icon", "true"),
+    ("mirror_favorites_to_pinboard", "false"),
+    ("strip_image_metadata", "false"),
+    ("compact_png_files", "false"),
+    ("quick_cycle_shortcut", "Ctrl+Alt+V"),
+    ("quick_cycle_items", "10"),
+    ("normalize_text", "off"),
+    ("large_text_threshold_bytes", "262144"),
+    ("compress_large_text", "false"),
+    ("spotlight_indexing", "false"),
+    ("auto_clear_schedule", "off"),
+    ("min_free_space_mb", "200"),
+    ("capture_window_titles", "false"),
+    ("thumbnail_format", "png"),
+    ("quicklook_timeout_ms", "3000"),
+    ("recent_items_os_menu", "true"),
+    ("capture_find_pasteboard", "false"),
+    ("exclude_from_os_history", "false"),
+    ("dedupe_scope", "global"),
+    ("max_image_dimension", "0"),
+    ("max_image_capture_mb", "100"),
+    ("debug_capture_logging", "false"),
+    ("require_destructive_confirmation", "true"),
+    ("merge_rapid_text_captures", "false"),
+    ("merge_window_ms", "500"),
+    ("backup_interval_hours", "0"),
+];
 
 /// Database wrapper for thread-safe SQLite operations
 pub struct Database {
     conn: Mutex<Connection>,
+    /// Path of the database file (None for in-memory databases)
+    db_path: Mutex<Option<PathBuf>>,
+    /// Images directory of the data directory, which `image_path` is stored
+    /// relative to (None for in-memory databases, which keep absolute paths)
+    images_dir: Option<PathBuf>,
 }
 
 impl Database {
-    /// Create a new database connection
-    /// Uses app data directory: ~/.clipster/clipster.db
-    pub fn new() -> Result<Self, String> {
-        let db_path = Self::get_db_path()?;
+    /// Open the database stored in a profile data directory
+    pub fn open(data_dir: &Path) -> Result<Self, ClipsterError> {
+        let db = Self::open_unseeded(data_dir)?;
+        db.relocate_image_paths_on_open();
+        db.ensure_default_pinboards()?;
+        Ok(db)
+    }
 
+    /// Open the database of a profile data directory, replacing it with a
+    /// fresh one holding the salvageable rows if it is corrupt
+    pub fn open_or_recover(
+        data_dir: &Path,
+    ) -> Result<(Self, Option<RecoveryReport>), ClipsterError> {
+        let db_path = data_dir.join("clipster.db");
+        let Some(reason) = recovery::detect_corruption(&db_path) else {
+            return Ok((Self::open(data_dir)?, None));
+        };
+        eprintln!("Database is corrupt, recovering: {}", reason);
+
+        let corrupt_path =
+            recovery::move_aside(&db_path).context("Failed to move corrupt database aside")?;
+        let db = Self::open_unseeded(data_dir)?;
+        let counts = recovery::salvage(&corrupt_path, &*db.conn.lock()?);
+        db.relocate_image_paths_on_open();
+        // After salvaging, so a recovered first-run flag and Favorites board are kept
+        db.ensure_default_pinboards()?;
+
+        let recovered = |table: &str| {
+            counts
+                .iter()
+                .find(|(name, _)| *name == table)
+                .map_or(0, |(_, count)| *count)
+        };
+        Ok((
+            db,
+            Some(RecoveryReport {
+                corrupt_path: corrupt_path.to_string_lossy().to_string(),
+                reason,
+                items_recovered: recovered("clipboard_items"),
+                pinboards_recovered: recovered("pinboards"),
+            }),
+        ))
+    }
+
+    /// Open the history archive of a profile data directory (`archive.db`,
+    /// images in `archive_images`), see `history_archive`
+    /// It has no default pinboards: it only ever holds history items
+    pub fn open_archive(data_dir: &Path) -> Result<Self, ClipsterError> {
+        Self::open_files(
+            data_dir.join(history_archive::ARCHIVE_DB),
+            data_dir.join(history_archive::ARCHIVE_IMAGES_DIR),
+        )
+    }
+
+    /// Open and migrate the database of a profile data directory, without
+    /// the first-run setup
+    fn open_unseeded(data_dir: &Path) -> Result<Self, ClipsterError> {
+        Self::open_files(data_dir.join("clipster.db"), data_dir.join("images"))
+    }
+
+    /// Open and migrate the database file at `db_path`, whose images are in
+    /// `images_dir`
+    fn open_files(db_path: PathBuf, images_dir: PathBuf) -> Result<Self, ClipsterError> {
         // Ensure parent directory exists
-        if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create database directory: {}", e))?;
+        if let Some(data_dir) = db_path.parent() {
+            std::fs::create_dir_all(data_dir).context("Failed to create database directory")?;
         }
 
-        let conn = Connection::open(&db_path)
-            .map_err(|e| format!("Failed to open database: {}", e))?;
+        let conn = Connection::open(&db_path).context("Failed to open database")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+        // Readers (backups, the CLI) then never block captures
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .context("Failed to enable WAL")?;
 
         let db = Self {
             conn: Mutex::new(conn),
+            db_path: Mutex::new(Some(db_path)),
+            images_dir: Some(images_dir),
         };
 
         db.run_migrations()?;
@@ -32,14 +251,30 @@ impl Database {
         Ok(db)
     }
 
+    /// Open the database of a profile data directory for reading only
+    /// Nothing is created or migrated, so the running app is never disturbed
+    pub fn open_read_only(data_dir: &Path) -> Result<Self, ClipsterError> {
+        let db_path = data_dir.join("clipster.db");
+        let conn = Connection::open_with_flags(&db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .context("Failed to open database")?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            db_path: Mutex::new(Some(db_path)),
+            images_dir: Some(data_dir.join("images")),
+        })
+    }
+
     /// Create an in-memory database (for testing)
     #[cfg(test)]
-    pub fn new_in_memory() -> Result<Self, String> {
-        let conn = Connection::open_in_memory()
-            .map_err(|e| format!("Failed to open in-memory database: {}", e))?;
+    pub fn new_in_memory() -> Result<Self, ClipsterError> {
+        let conn = Connection::open_in_memory().context("Failed to open in-memory database")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
 
         let db = Self {
             conn: Mutex::new(conn),
+            db_path: Mutex::new(None),
+            images_dir: None,
         };
 
         db.run_migrations()?;
@@ -47,18 +282,40 @@ impl Database {
         Ok(db)
     }
 
-    /// Get the database file path
-    fn get_db_path() -> Result<PathBuf, String> {
-        let home = dirs::data_local_dir()
-            .or_else(dirs::home_dir)
-            .ok_or_else(|| "Could not determine home directory".to_string())?;
+    /// Set how long statements wait for another connection's lock (for testing)
+    #[cfg(test)]
+    pub fn set_busy_timeout(&self, timeout: Duration) -> Result<(), ClipsterError> {
+        Ok(self.conn.lock()?.busy_timeout(timeout)?)
+    }
+
+    /// Swap the connection over to the database of another profile
+    /// Existing `Arc<Database>` handles follow the switch; callers mid-query
+    /// finish on the old connection before the swap takes the lock
+    pub fn switch_to(&self, data_dir: &Path) -> Result<(), ClipsterError> {
+        let fresh = Self::open(data_dir)?;
 
-        Ok(home.join(".clipster").join("clipster.db"))
+        let mut conn = self.conn.lock()?;
+        let mut fresh_conn = fresh.conn.lock()?;
+        std::mem::swap(&mut *conn, &mut *fresh_conn);
+
+        let mut db_path = self.db_path.lock()?;
+        let mut fresh_path = fresh.db_path.lock()?;
+        std::mem::swap(&mut *db_path, &mut *fresh_path);
+
+        Ok(())
+    }
+
+    /// Path of the database file
+    fn file_path(&self) -> Result<PathBuf, ClipsterError> {
+        self.db_path
+            .lock()?
+            .clone()
+            .ok_or_else(|| ClipsterError::NotFound("In-memory database has no file".to_string()))
     }
 
     /// Run database migrations
-    fn run_migrations(&self) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    fn run_migrations(&self) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
 
         // Create clipboard_items table
         conn.execute(
@@ -77,7 +334,7 @@ impl Database {
             )",
             [],
         )
-        .map_err(|e| format!("Failed to create clipboard_items table: {}", e))?;
+        .context("Failed to create clipboard_items table")?;
 
         // Migration: Add source_app_icon column if it doesn't exist
         let _ = conn.execute(
@@ -85,6 +342,123 @@ impl Database {
             [],
         );
 
+        // Migration: Add auto_pinboard_id column (per-app default pinboard) if it doesn't exist
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN auto_pinboard_id TEXT",
+            [],
+        );
+
+        // Migration: Add text_path column (large text spilled to disk) if it doesn't exist
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN text_path TEXT", []);
+
+        // Migration: Add content_compressed column (zstd text, preview in content_text)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN content_compressed BLOB",
+            [],
+        );
+
+        // Migration: Add canonical_url column (Link dedupe key) if it doesn't exist
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN canonical_url TEXT", []);
+
+        // Migration: Add source_window_title column (focused window at capture time)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN source_window_title TEXT",
+            [],
+        );
+
+        // Migration: Add source_pasteboard column (NULL = general pasteboard)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN source_pasteboard TEXT",
+            [],
+        );
+
+        // Migration: Add first_copied_at column (kept across move-to-top);
+        // existing rows start from their created_at
+        if conn
+            .execute("ALTER TABLE clipboard_items ADD COLUMN first_copied_at TEXT", [])
+            .is_ok()
+        {
+            let _ = conn.execute("UPDATE clipboard_items SET first_copied_at = created_at", []);
+        }
+
+        // Migration: Add thumbnail_is_favicon flag (Link previews made from the site icon)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN thumbnail_is_favicon INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Migration: Add files_summary column (multi-file copies)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN files_summary TEXT",
+            [],
+        );
+
+        // Migration: Add copy-back counter and time of the last copy-back
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN use_count INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN last_used_at TEXT",
+            [],
+        );
+
+        // Migration: Add is_directory column (file copies of a single folder)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN is_directory INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Migration: Add the copied size of downscaled images (max_image_dimension)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN original_width INTEGER",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN original_height INTEGER",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN downscale_skipped INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Migration: Add the generated title (e.g. of screenshots)
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN title TEXT", []);
+
+        // Migration: Add duration and tags of audio items (JSON)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN media_metadata TEXT",
+            [],
+        );
+
+        // Migration: Add the contact/event subtype of text items
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN content_subtype TEXT",
+            [],
+        );
+
+        // Migration: Add the self-destruct time of items (see `expiry_timestamp`)
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN expires_at TEXT", []);
+
+        // Migration: Add the detected language of text items (see `clipboard::language`)
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN language TEXT", []);
+
+        // Migration: Add the dominant colors of image items (see `clipboard::palette`)
+        let _ = conn.execute("ALTER TABLE clipboard_items ADD COLUMN palette TEXT", []);
+
+        // Migration: Add content_hash column (dedupe key of the full text)
+        let _ = conn.execute(
+            "ALTER TABLE clipboard_items ADD COLUMN content_hash TEXT",
+            [],
+        );
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_items_content_hash
+             ON clipboard_items(content_hash)",
+            [],
+        )
+        .context("Failed to create content_hash index")?;
+
         // Create pinboards table
         conn.execute(
             "CREATE TABLE IF NOT EXISTS pinboards (
@@ -96,7 +470,33 @@ impl Database {
             )",
             [],
         )
-        .map_err(|e| format!("Failed to create pinboards table: {}", e))?;
+        .context("Failed to create pinboards table")?;
+
+        // Migration: Add rules column for smart pinboards if it doesn't exist
+        let _ = conn.execute("ALTER TABLE pinboards ADD COLUMN rules TEXT", []);
+
+        // Migration: Add passcode hash for locked pinboards if it doesn't exist
+        let _ = conn.execute("ALTER TABLE pinboards ADD COLUMN lock_hash TEXT", []);
+
+        // Migration: Add archived flag if it doesn't exist
+        let _ = conn.execute(
+            "ALTER TABLE pinboards ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // Migration: Add color and description if they don't exist
+        // A NULL color reads as a palette color derived from the pinboard ID
+        let _ = conn.execute("ALTER TABLE pinboards ADD COLUMN color TEXT", []);
+        let _ = conn.execute("ALTER TABLE pinboards ADD COLUMN description TEXT", []);
+
+        // Migration: Add parent pinboard for nested pinboards if it doesn't exist
+        let _ = conn.execute("ALTER TABLE pinboards ADD COLUMN parent_id TEXT", []);
+
+        // Migration: Add the paste transform of pinboard items (see `clipboard::transforms`)
+        let _ = conn.execute(
+            "ALTER TABLE pinboards ADD COLUMN default_transform TEXT",
+            [],
+        );
 
         // Create settings table
         conn.execute(
@@ -106,7 +506,7 @@ impl Database {
             )",
             [],
         )
-        .map_err(|e| format!("Failed to create settings table: {}", e))?;
+        .context("Failed to create settings table")?;
 
         // Create indexes for better query performance
         conn.execute(
@@ -114,251 +514,864 @@ impl Database {
              ON clipboard_items(created_at DESC)",
             [],
         )
-        .map_err(|e| format!("Failed to create created_at index: {}", e))?;
+        .context("Failed to create created_at index")?;
 
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_clipboard_items_content_type
              ON clipboard_items(content_type)",
             [],
         )
-        .map_err(|e| format!("Failed to create content_type index: {}", e))?;
+        .context("Failed to create content_type index")?;
 
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_clipboard_items_pinboard
              ON clipboard_items(pinboard_id)",
             [],
         )
-        .map_err(|e| format!("Failed to create pinboard_id index: {}", e))?;
+        .context("Failed to create pinboard_id index")?;
 
-        // Insert default settings if not present
         conn.execute(
-            "INSERT OR IGNORE INTO settings (key, value) VALUES ('history_limit', '500')",
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_items_auto_pinboard
+             ON clipboard_items(auto_pinboard_id)",
+            [],
+        )
+        .context("Failed to create auto_pinboard_id index")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_items_first_copied_at
+             ON clipboard_items(first_copied_at DESC)",
+            [],
+        )
+        .context("Failed to create first_copied_at index")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_items_expires_at
+             ON clipboard_items(expires_at) WHERE expires_at IS NOT NULL",
+            [],
+        )
+        .context("Failed to create expires_at index")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_clipboard_items_language
+             ON clipboard_items(language) WHERE language IS NOT NULL",
+            [],
+        )
+        .context("Failed to create language index")?;
+
+        // Change log for frontend resync, maintained by triggers so every
+        // mutation of clipboard_items is recorded with a sequence number
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS item_events (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                item_id TEXT NOT NULL,
+                kind TEXT NOT NULL
+            )",
             [],
         )
-        .map_err(|e| format!("Failed to insert default settings: {}", e))?;
+        .context("Failed to create item_events table")?;
+
+        conn.execute_batch(
+            "CREATE TRIGGER IF NOT EXISTS trg_clipboard_items_insert
+             AFTER INSERT ON clipboard_items
+             BEGIN
+                 INSERT INTO item_events (item_id, kind) VALUES (NEW.id, 'insert');
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS trg_clipboard_items_update
+             AFTER UPDATE ON clipboard_items
+             BEGIN
+                 INSERT INTO item_events (item_id, kind) VALUES (NEW.id, 'update');
+             END;
+
+             CREATE TRIGGER IF NOT EXISTS trg_clipboard_items_delete
+             AFTER DELETE ON clipboard_items
+             BEGIN
+                 INSERT INTO item_events (item_id, kind) VALUES (OLD.id, 'delete');
+             END;",
+        )
+        .context("Failed to create item_events triggers")?;
 
+        // Previous text of edited items, dropped together with the item
         conn.execute(
-            "INSERT OR IGNORE INTO settings (key, value) VALUES ('shortcut', 'Ctrl+Shift+V')",
+            "CREATE TABLE IF NOT EXISTS item_revisions (
+                item_id TEXT NOT NULL,
+                revision_no INTEGER NOT NULL,
+                content_text TEXT NOT NULL,
+                edited_at TEXT NOT NULL,
+                PRIMARY KEY (item_id, revision_no)
+            )",
             [],
         )
-        .map_err(|e| format!("Failed to insert default shortcut: {}", e))?;
+        .context("Failed to create item_revisions table")?;
 
         conn.execute(
-            "INSERT OR IGNORE INTO settings (key, value) VALUES ('start_hidden', 'false')",
+            "CREATE TRIGGER IF NOT EXISTS trg_clipboard_items_delete_revisions
+             AFTER DELETE ON clipboard_items
+             BEGIN
+                 DELETE FROM item_revisions WHERE item_id = OLD.id;
+             END",
             [],
         )
-        .map_err(|e| format!("Failed to insert default start_hidden: {}", e))?;
+        .context("Failed to create item_revisions trigger")?;
 
+        // Source app icons by executable path, filled in by the icon worker
         conn.execute(
-            "INSERT OR IGNORE INTO settings (key, value) VALUES ('theme', 'dark')",
+            "CREATE TABLE IF NOT EXISTS app_icons (
+                app_path TEXT PRIMARY KEY,
+                icon TEXT NOT NULL,
+                extracted_at TEXT NOT NULL
+            )",
             [],
         )
-        .map_err(|e| format!("Failed to insert default theme: {}", e))?;
+        .context("Failed to create app_icons table")?;
 
+        // Lifetime usage counters (see `storage::counters`)
         conn.execute(
-            "INSERT OR IGNORE INTO settings (key, value) VALUES ('show_menu_bar_icon', 'true')",
+            "CREATE TABLE IF NOT EXISTS counters (
+                name TEXT PRIMARY KEY,
+                value INTEGER NOT NULL DEFAULT 0
+            )",
             [],
         )
-        .map_err(|e| format!("Failed to insert default show_menu_bar_icon: {}", e))?;
+        .context("Failed to create counters table")?;
+
+        // Insert default settings if not present
+        for (key, value) in DEFAULT_SETTINGS {
+            conn.execute(
+                "INSERT OR IGNORE INTO settings (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .context("Failed to insert default settings")?;
+        }
 
         Ok(())
     }
 
+    /// First-run setup: create the default Favorites pinboard exactly once
+    /// Guarded by the `first_run_completed` setting so a deleted board stays deleted
+    pub fn ensure_default_pinboards(&self) -> Result<(), ClipsterError> {
+        if self.get_setting("first_run_completed")?.as_deref() == Some("true") {
+            return Ok(());
+        }
+
+        if self.get_pinboards(true)?.is_empty() {
+            let favorites = Pinboard::default_favorites();
+            self.insert_pinboard(&favorites)?;
+            self.set_setting("favorites_pinboard_id", &favorites.id)?;
+        }
+
+        self.set_setting("first_run_completed", "true")
+    }
+
     // ==================== CLIPBOARD ITEMS ====================
 
     /// Insert a new clipboard item
-    pub fn insert_item(&self, item: &ClipboardItem) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn insert_item(&self, item: &ClipboardItem) -> Result<(), ClipsterError> {
+        self.insert_items_counted(&[item], &[])
+    }
 
-        conn.execute(
-            "INSERT INTO clipboard_items
-             (id, content_type, content_text, thumbnail_base64, image_path, source_app, source_app_icon, created_at, pinboard_id, is_favorite)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
+    /// Insert several items, all or nothing
+    pub fn insert_items(&self, items: &[ClipboardItem]) -> Result<(), ClipsterError> {
+        let items: Vec<&ClipboardItem> = items.iter().collect();
+        self.insert_items_counted(&items, &[])
+    }
+
+    /// Insert an item captured from the clipboard, counting the capture and
+    /// its content type in the same transaction
+    pub fn insert_capture(&self, item: &ClipboardItem) -> Result<(), ClipsterError> {
+        let counted = [
+            counters::CAPTURES.to_string(),
+            counters::captures_of(item.content_type),
+        ];
+        self.insert_items_counted(&[item], &counted)
+    }
+
+    /// Insert items and bump `counted` by one, all or nothing
+    fn insert_items_counted(
+        &self,
+        items: &[&ClipboardItem],
+        counted: &[String],
+    ) -> Result<(), ClipsterError> {
+        let compress = self.get_setting("compress_large_text")?.as_deref() == Some("true");
+
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        for name in counted {
+            counters::increment(&tx, name, 1)?;
+        }
+
+        let mut stmt = tx
+            .prepare_cached(
+                "INSERT INTO clipboard_items
+                 (id, content_type, content_text, thumbnail_base64, image_path, source_app,
+                  source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id, text_path,
+                  content_compressed, canonical_url, source_window_title, source_pasteboard,
+                  first_copied_at, thumbnail_is_favicon, files_summary, is_directory,
+                  original_width, original_height, downscale_skipped, title, media_metadata,
+                  content_subtype, expires_at, language, palette, content_hash)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+                         ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30)",
+            )
+            .context("Failed to prepare insert")?;
+        for item in items {
+            let (content_text, content_compressed) = stored_content(item, compress)?;
+            stmt.execute(params![
                 item.id,
                 item.content_type,
-                item.content_text,
+                content_text,
                 item.thumbnail_base64,
-                item.image_path,
+                item.image_path
+                    .as_deref()
+                    .map(|path| self.stored_image_path(path)),
                 item.source_app,
                 item.source_app_icon,
                 item.created_at.to_rfc3339(),
                 item.pinboard_id,
                 item.is_favorite as i32,
-            ],
-        )
-        .map_err(|e| format!("Failed to insert clipboard item: {}", e))?;
+                item.auto_pinboard_id,
+                item.text_path,
+                content_compressed,
+                item.canonical_url,
+                item.source_window_title,
+                item.source_pasteboard,
+                item.first_copied_at.to_rfc3339(),
+                item.thumbnail_is_favicon as i32,
+                item.files_summary,
+                item.is_directory as i32,
+                item.original_width,
+                item.original_height,
+                item.downscale_skipped as i32,
+                item.title,
+                item.media_metadata
+                    .as_ref()
+                    .and_then(MediaMetadata::to_json),
+                item.content_subtype.map(|subtype| subtype.as_str()),
+                item.expires_at.map(expiry_timestamp),
+                item.language,
+                item.palette
+                    .as_ref()
+                    .and_then(|palette| serde_json::to_string(palette).ok()),
+                item_content_hash(item),
+            ])
+            .context("Failed to insert clipboard item")?;
+        }
+        drop(stmt);
+        tx.commit().context("Failed to commit item")?;
 
         Ok(())
     }
 
     /// Get clipboard history items with pagination
-    /// Returns only items NOT in a pinboard, ordered by created_at DESC (newest first)
-    pub fn get_items(&self, limit: usize, offset: usize) -> Result<Vec<ClipboardItem>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Returns only items NOT in a pinboard, newest first by `sort`
+    /// Items auto-filed into a locked pinboard are left out (see `visible_item`)
+    /// Thumbnails are left out unless `include_thumbnails` is set (see `get_thumbnails`)
+    pub fn get_items(
+        &self,
+        limit: usize,
+        offset: usize,
+        include_thumbnails: bool,
+        sort: HistorySort,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, content_type, content_text, thumbnail_base64, image_path,
-                        source_app, source_app_icon, created_at, pinboard_id, is_favorite
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
                  FROM clipboard_items
-                 WHERE pinboard_id IS NULL
-                 ORDER BY created_at DESC
+                 WHERE pinboard_id IS NULL AND {}
+                 ORDER BY {} DESC
                  LIMIT ?1 OFFSET ?2",
-            )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+                thumbnail_column(include_thumbnails),
+                visible_item(None),
+                sort.order_column()
+            ))
+            .context("Failed to prepare query")?;
 
         let items = stmt
             .query_map(params![limit as i64, offset as i64], |row| {
-                ClipboardItem::from_row(row)
+                self.item_from_row(row)
             })
-            .map_err(|e| format!("Failed to query items: {}", e))?
+            .context("Failed to query items")?
             .collect::<SqliteResult<Vec<_>>>()
-            .map_err(|e| format!("Failed to collect items: {}", e))?;
+            .context("Failed to collect items")?;
 
         Ok(items)
     }
 
     /// Get a single clipboard item by ID
-    pub fn get_item(&self, id: &str) -> Result<Option<ClipboardItem>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Large text spilled to disk or compressed is loaded back into `content_text`
+    pub fn get_item(&self, id: &str) -> Result<Option<ClipboardItem>, ClipsterError> {
+        Ok(self.get_item_row(id)?.map(load_spilled_text))
+    }
+
+    /// Get a single clipboard item by ID, failing with `NotFound` if it doesn't exist
+    pub fn require_item(&self, id: &str) -> Result<ClipboardItem, ClipsterError> {
+        self.get_item(id)?
+            .ok_or_else(|| ClipsterError::NotFound("Item not found".to_string()))
+    }
+
+    /// Get a single clipboard item row, compressed text decompressed
+    /// (spilled text stays a preview)
+    fn get_item_row(&self, id: &str) -> Result<Option<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
-            .prepare(
+            .prepare_cached(
                 "SELECT id, content_type, content_text, thumbnail_base64, image_path,
-                        source_app, source_app_icon, created_at, pinboard_id, is_favorite
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
                  FROM clipboard_items
                  WHERE id = ?1",
             )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+            .context("Failed to prepare query")?;
 
         let mut rows = stmt
-            .query_map(params![id], |row| ClipboardItem::from_row(row))
-            .map_err(|e| format!("Failed to query item: {}", e))?;
+            .query_map(params![id], |row| {
+                let mut item = self.item_from_row(row)?;
+                if let Some(data) = row.get::<_, Option<Vec<u8>>>("content_compressed")? {
+                    match compression::decompress_text(&data) {
+                        Ok(text) => item.content_text = Some(text),
+                        Err(e) => eprintln!("[database] {} for item {}", e, item.id),
+                    }
+                }
+                Ok(item)
+            })
+            .context("Failed to query item")?;
 
         match rows.next() {
             Some(Ok(item)) => Ok(Some(item)),
-            Some(Err(e)) => Err(format!("Failed to read item: {}", e)),
+            Some(Err(e)) => Err(e).context("Failed to read item"),
             None => Ok(None),
         }
     }
 
     /// Delete a clipboard item by ID
-    pub fn delete_item(&self, id: &str) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn delete_item(&self, id: &str) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
 
+        let text_paths = spilled_text_paths(&conn, "id = ?1", params![id])?;
         let rows_affected = conn
             .execute("DELETE FROM clipboard_items WHERE id = ?1", params![id])
-            .map_err(|e| format!("Failed to delete item: {}", e))?;
+            .context("Failed to delete item")?;
+        remove_files(&text_paths);
 
         Ok(rows_affected > 0)
     }
 
+    /// Get the thumbnails of several items (ID -> base64 PNG)
+    /// Items without a thumbnail, and items of locked pinboards not listed in
+    /// `unlocked`, are left out of the map
+    pub fn get_thumbnails(
+        &self,
+        item_ids: &[String],
+        unlocked: &[String],
+    ) -> Result<HashMap<String, String>, ClipsterError> {
+        let unlocked =
+            serde_json::to_string(unlocked).context("Failed to serialize unlocked pinboards")?;
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT thumbnail_base64 FROM clipboard_items
+                 WHERE id = ?1 AND thumbnail_base64 IS NOT NULL AND {}",
+                visible_item(Some(2))
+            ))
+            .context("Failed to prepare query")?;
+
+        let mut thumbnails = HashMap::new();
+        for id in item_ids {
+            let mut rows = stmt
+                .query_map(params![id, unlocked], |row| row.get::<_, String>(0))
+                .context("Failed to query thumbnail")?;
+            if let Some(thumbnail) = rows.next() {
+                let thumbnail = thumbnail.context("Failed to read thumbnail")?;
+                thumbnails.insert(id.clone(), thumbnail);
+            }
+        }
+
+        Ok(thumbnails)
+    }
+
     /// Update the thumbnail_base64 of an existing item (used for async OG image fetching)
-    pub fn update_thumbnail(&self, id: &str, thumbnail_base64: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// `is_favicon` marks a preview made from the site icon
+    pub fn update_thumbnail(
+        &self,
+        id: &str,
+        thumbnail_base64: &str,
+        is_favicon: bool,
+    ) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
 
         conn.execute(
-            "UPDATE clipboard_items SET thumbnail_base64 = ?1 WHERE id = ?2",
-            params![thumbnail_base64, id],
+            "UPDATE clipboard_items SET thumbnail_base64 = ?1, thumbnail_is_favicon = ?2
+             WHERE id = ?3",
+            params![thumbnail_base64, is_favicon as i32, id],
         )
-        .map_err(|e| format!("Failed to update thumbnail: {}", e))?;
+        .context("Failed to update thumbnail")?;
 
         Ok(())
     }
 
     /// Search clipboard items by text content
-    pub fn search_items(&self, query: &str, limit: usize) -> Result<Vec<ClipboardItem>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Items in passcode-locked pinboards are never returned
+    /// Spilled and compressed items match on their preview; their full text is
+    /// only searched by `search_items_deep`
+    pub fn search_items(
+        &self,
+        query: &str,
+        limit: usize,
+        include_thumbnails: bool,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        self.search_items_filtered(query, &SearchFilter::default(), limit, include_thumbnails)
+    }
+
+    /// `search_items` narrowed by `filter`
+    pub fn search_items_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+        include_thumbnails: bool,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let search_pattern = format!("%{}%", query);
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, content_type, content_text, thumbnail_base64, image_path,
-                        source_app, source_app_icon, created_at, pinboard_id, is_favorite
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
                  FROM clipboard_items
-                 WHERE content_text LIKE ?1
+                 WHERE (content_text LIKE ?1
+                        OR source_window_title LIKE ?1
+                        OR title LIKE ?1)
+                   AND {}
+                   AND (?2 IS NULL OR language = ?2)
                  ORDER BY created_at DESC
-                 LIMIT ?2",
+                 LIMIT ?3",
+                thumbnail_column(include_thumbnails),
+                visible_item(None)
+            ))
+            .context("Failed to prepare search query")?;
+
+        let items = stmt
+            .query_map(
+                params![search_pattern, filter.language, limit as i64],
+                |row| self.item_from_row(row),
             )
-            .map_err(|e| format!("Failed to prepare search query: {}", e))?;
+            .context("Failed to search items")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to collect search results")?;
+
+        Ok(items)
+    }
+
+    /// Search including the full text of items spilled to disk or compressed
+    /// Slower than `search_items`: every spilled file and compressed blob whose
+    /// preview doesn't match is read
+    pub fn search_items_deep(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: usize,
+        include_thumbnails: bool,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let mut results = self.search_items_filtered(query, filter, limit, include_thumbnails)?;
+
+        let candidates = {
+            let conn = self.conn.lock()?;
+
+            let mut stmt = conn
+                .prepare(&format!(
+                    "SELECT id, content_type, content_text, {}, image_path,
+                            source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                            auto_pinboard_id, text_path, content_compressed, canonical_url,
+                            source_window_title, source_pasteboard, first_copied_at,
+                            thumbnail_is_favicon, files_summary, use_count, is_directory,
+                            original_width, original_height, downscale_skipped, title,
+                            media_metadata, content_subtype, expires_at, language, palette
+                     FROM clipboard_items
+                     WHERE (text_path IS NOT NULL OR content_compressed IS NOT NULL)
+                       AND NOT (COALESCE(content_text LIKE ?1, 0)
+                                OR COALESCE(source_window_title LIKE ?1, 0)
+                                OR COALESCE(title LIKE ?1, 0))
+                       AND {}
+                       AND (?2 IS NULL OR language = ?2)
+                     ORDER BY created_at DESC",
+                    thumbnail_column(include_thumbnails),
+                    visible_item(None)
+                ))
+                .context("Failed to prepare deep search query")?;
+
+            let items = stmt
+                .query_map(params![format!("%{}%", query), filter.language], |row| {
+                    Ok((
+                        self.item_from_row(row)?,
+                        row.get::<_, Option<Vec<u8>>>("content_compressed")?,
+                    ))
+                })
+                .context("Failed to deep search items")?
+                .collect::<SqliteResult<Vec<_>>>()
+                .context("Failed to collect deep search results")?;
+            items
+        };
+
+        // Same case-insensitivity as LIKE, read without holding the lock
+        let needle = query.to_lowercase();
+        results.extend(
+            candidates
+                .into_iter()
+                .filter(|(item, compressed)| {
+                    let text = match compressed {
+                        Some(data) => compression::decompress_text(data).ok(),
+                        None => item
+                            .text_path
+                            .as_ref()
+                            .and_then(|path| fs::read_to_string(path).ok()),
+                    };
+                    text.is_some_and(|text| text.to_lowercase().contains(&needle))
+                })
+                .map(|(item, _)| item),
+        );
+
+        results.sort_by_key(|item| std::cmp::Reverse(item.created_at));
+        results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Image items with a palette color within `tolerance` (RGB distance) of
+    /// `hex`, newest first; items in locked pinboards are left out
+    pub fn get_items_by_color(
+        &self,
+        hex: &str,
+        tolerance: f64,
+        include_thumbnails: bool,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let color = palette::parse_hex(hex)
+            .ok_or_else(|| ClipsterError::Validation(format!("Invalid color: {}", hex)))?;
+
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                        auto_pinboard_id, text_path, content_compressed, canonical_url,
+                        source_window_title, source_pasteboard, first_copied_at,
+                        thumbnail_is_favicon, files_summary, use_count, is_directory,
+                        original_width, original_height, downscale_skipped, title,
+                        media_metadata, content_subtype, expires_at, language, palette
+                 FROM clipboard_items
+                 WHERE palette IS NOT NULL AND {}
+                 ORDER BY created_at DESC",
+                thumbnail_column(include_thumbnails),
+                visible_item(None)
+            ))
+            .context("Failed to prepare color query")?;
 
         let items = stmt
-            .query_map(params![search_pattern, limit as i64], |row| {
-                ClipboardItem::from_row(row)
+            .query_map([], |row| self.item_from_row(row))
+            .context("Failed to query items by color")?
+            .filter_map(|row| match row {
+                Ok(item) => item
+                    .palette
+                    .as_deref()
+                    .is_some_and(|colors| palette::matches(colors, color, tolerance))
+                    .then_some(Ok(item)),
+                Err(e) => Some(Err(e)),
             })
-            .map_err(|e| format!("Failed to search items: {}", e))?
             .collect::<SqliteResult<Vec<_>>>()
-            .map_err(|e| format!("Failed to collect search results: {}", e))?;
+            .context("Failed to collect items by color")?;
 
         Ok(items)
     }
 
+    /// Search results grouped by pinboard: history first, then boards by position
+    /// Each group holds its `per_group_limit` newest matches and the number of
+    /// all of them. Locked pinboards are left out unless listed in `unlocked`,
+    /// archived ones unless `include_archived`. Matching is the same as
+    /// `search_items`: spilled and compressed items match on their preview
+    pub fn search_items_grouped(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        per_group_limit: usize,
+        unlocked: &[String],
+        include_archived: bool,
+        include_thumbnails: bool,
+    ) -> Result<Vec<SearchGroup>, ClipsterError> {
+        let conn = self.conn.lock()?;
+        let search_pattern = format!("%{}%", query);
+
+        let unlocked =
+            serde_json::to_string(unlocked).context("Failed to serialize unlocked pinboards")?;
+
+        // One query for all groups: rank matches within their pinboard and keep the top ones
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                        auto_pinboard_id, text_path, content_compressed, canonical_url,
+                        source_window_title, source_pasteboard, first_copied_at,
+                        thumbnail_is_favicon, files_summary, use_count, is_directory,
+                        original_width, original_height, downscale_skipped, title, media_metadata,
+                        content_subtype, expires_at, language, palette,
+                        board_name, board_icon, group_total
+                 FROM (
+                     SELECT c.*,
+                            p.name AS board_name,
+                            p.icon AS board_icon,
+                            p.position AS board_position,
+                            ROW_NUMBER() OVER (PARTITION BY c.pinboard_id
+                                               ORDER BY c.created_at DESC) AS group_rank,
+                            COUNT(*) OVER (PARTITION BY c.pinboard_id) AS group_total
+                     FROM clipboard_items c
+                     LEFT JOIN pinboards p ON p.id = c.pinboard_id
+                     WHERE (c.content_text LIKE ?1
+                            OR c.source_window_title LIKE ?1
+                            OR c.title LIKE ?1)
+                       AND {}
+                       AND (c.pinboard_id IS NULL OR ?3 OR p.archived = 0)
+                       AND (?5 IS NULL OR c.language = ?5)
+                 )
+                 WHERE group_rank <= ?4
+                 ORDER BY pinboard_id IS NOT NULL, board_position, pinboard_id, group_rank",
+                thumbnail_column(include_thumbnails),
+                visible_item(Some(2))
+            ))
+            .context("Failed to prepare grouped search query")?;
+
+        let rows = stmt
+            .query_map(
+                params![
+                    search_pattern,
+                    unlocked,
+                    include_archived,
+                    per_group_limit as i64,
+                    filter.language
+                ],
+                |row| {
+                    Ok((
+                        self.item_from_row(row)?,
+                        row.get::<_, Option<String>>("board_name")?,
+                        row.get::<_, Option<String>>("board_icon")?,
+                        row.get::<_, i64>("group_total")?,
+                    ))
+                },
+            )
+            .context("Failed to search items by pinboard")?;
+
+        let mut groups: Vec<SearchGroup> = Vec::new();
+        for row in rows {
+            let (item, board_name, board_icon, total) = row.context("Failed to read item")?;
+            match groups.last_mut() {
+                Some(group) if group.pinboard_id == item.pinboard_id => group.items.push(item),
+                _ => groups.push(SearchGroup {
+                    pinboard_id: item.pinboard_id.clone(),
+                    name: board_name.unwrap_or_else(|| HISTORY_GROUP.to_string()),
+                    icon: board_icon,
+                    total_count: total as usize,
+                    items: vec![item],
+                }),
+            }
+        }
+
+        Ok(groups)
+    }
+
+    /// History items grouped by source app, the most recently active app first
+    /// Each group holds its `limit_per_app` newest items; items without a
+    /// source app form the `UNKNOWN_APP` group
+    pub fn get_items_grouped_by_app(
+        &self,
+        limit_per_app: usize,
+        include_thumbnails: bool,
+    ) -> Result<Vec<AppGroup>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        // One query for all apps: rank items within their app and keep the top ones
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                        auto_pinboard_id, text_path, content_compressed, canonical_url,
+                        source_window_title, source_pasteboard, first_copied_at,
+                        thumbnail_is_favicon, files_summary, use_count, is_directory,
+                        original_width, original_height, downscale_skipped, title, media_metadata,
+                        content_subtype, expires_at, language, palette,
+                        app_total
+                 FROM (
+                     SELECT *,
+                            ROW_NUMBER() OVER (PARTITION BY source_app
+                                               ORDER BY created_at DESC) AS app_rank,
+                            COUNT(*) OVER (PARTITION BY source_app) AS app_total,
+                            MAX(created_at) OVER (PARTITION BY source_app) AS app_latest
+                     FROM clipboard_items
+                     WHERE pinboard_id IS NULL AND {}
+                 )
+                 WHERE app_rank <= ?1
+                 ORDER BY app_latest DESC, source_app, app_rank",
+                thumbnail_column(include_thumbnails),
+                visible_item(None)
+            ))
+            .context("Failed to prepare query")?;
+
+        let rows = stmt
+            .query_map(params![limit_per_app as i64], |row| {
+                Ok((self.item_from_row(row)?, row.get::<_, i64>("app_total")?))
+            })
+            .context("Failed to query items by app")?;
+
+        let mut groups: Vec<AppGroup> = Vec::new();
+        for row in rows {
+            let (item, total) = row.context("Failed to read item")?;
+            let is_known = item.source_app.is_some();
+            let name = item
+                .source_app
+                .clone()
+                .unwrap_or_else(|| UNKNOWN_APP.to_string());
+            match groups.last_mut() {
+                Some(group) if group.is_known == is_known && group.name == name => {
+                    if group.icon.is_none() {
+                        group.icon = item.source_app_icon.clone();
+                    }
+                    group.items.push(item);
+                }
+                _ => groups.push(AppGroup {
+                    name,
+                    is_known,
+                    icon: item.source_app_icon.clone(),
+                    total_count: total as usize,
+                    items: vec![item],
+                }),
+            }
+        }
+
+        Ok(groups)
+    }
+
     /// Get items by content type
     pub fn get_items_by_type(
         &self,
         content_type: ContentType,
         limit: usize,
-    ) -> Result<Vec<ClipboardItem>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
             .prepare(
                 "SELECT id, content_type, content_text, thumbnail_base64, image_path,
-                        source_app, source_app_icon, created_at, pinboard_id, is_favorite
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
                  FROM clipboard_items
                  WHERE content_type = ?1
                  ORDER BY created_at DESC
                  LIMIT ?2",
             )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+            .context("Failed to prepare query")?;
 
         let items = stmt
             .query_map(params![content_type, limit as i64], |row| {
-                ClipboardItem::from_row(row)
+                self.item_from_row(row)
             })
-            .map_err(|e| format!("Failed to query items by type: {}", e))?
+            .context("Failed to query items by type")?
             .collect::<SqliteResult<Vec<_>>>()
-            .map_err(|e| format!("Failed to collect items: {}", e))?;
+            .context("Failed to collect items")?;
 
         Ok(items)
     }
 
     /// Count history items (unpinned only)
     /// Pinboard items are saved permanently and not counted in history limit
-    pub fn count_items(&self) -> Result<usize, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn count_items(&self) -> Result<usize, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM clipboard_items WHERE pinboard_id IS NULL",
-                [],
-                |row| row.get(0),
+            .prepare_cached("SELECT COUNT(*) FROM clipboard_items WHERE pinboard_id IS NULL")
+            .and_then(|mut stmt| stmt.query_row([], |row| row.get(0)))
+            .context("Failed to count items")?;
+
+        Ok(count as usize)
+    }
+
+    /// Count history items `prune_oldest` could delete (unpinned, not favorited)
+    pub fn count_prunable_items(&self) -> Result<usize, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let count: i64 = conn
+            .prepare_cached(
+                "SELECT COUNT(*) FROM clipboard_items WHERE is_favorite = 0 AND pinboard_id IS NULL",
             )
-            .map_err(|e| format!("Failed to count items: {}", e))?;
+            .and_then(|mut stmt| stmt.query_row([], |row| row.get(0)))
+            .context("Failed to count prunable items")?;
 
         Ok(count as usize)
     }
 
     /// Prune oldest items to maintain history limit
     /// Keeps favorited items and items in pinboards
-    pub fn prune_oldest(&self, keep_count: usize) -> Result<usize, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Items auto-filed into a per-app default pinboard leave history by being
+    /// moved into that pinboard instead of being deleted
+    /// Image and spilled text files of the deleted items are removed too
+    pub fn prune_oldest(&self, keep_count: usize) -> Result<usize, ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        tx.execute(
+            "UPDATE clipboard_items SET pinboard_id = auto_pinboard_id
+             WHERE auto_pinboard_id IS NOT NULL AND id IN (
+                 SELECT id FROM clipboard_items
+                 WHERE is_favorite = 0 AND pinboard_id IS NULL
+                 ORDER BY created_at DESC
+                 LIMIT -1 OFFSET ?1
+             )",
+            params![keep_count as i64],
+        )
+        .context("Failed to move auto-filed items")?;
 
         // Delete oldest non-favorited, non-pinned items beyond the limit
-        let deleted = conn
+        let prunable = "id IN (
+                SELECT id FROM clipboard_items
+                WHERE is_favorite = 0 AND pinboard_id IS NULL
+                ORDER BY created_at DESC
+                LIMIT -1 OFFSET ?1
+            )";
+        let text_paths = spilled_text_paths(&tx, prunable, params![keep_count as i64])?;
+        let image_paths = self.image_file_paths(&tx, prunable, params![keep_count as i64])?;
+        let deleted = tx
             .execute(
-                "DELETE FROM clipboard_items
-                 WHERE id IN (
-                     SELECT id FROM clipboard_items
-                     WHERE is_favorite = 0 AND pinboard_id IS NULL
-                     ORDER BY created_at DESC
-                     LIMIT -1 OFFSET ?1
-                 )",
+                &format!("DELETE FROM clipboard_items WHERE {}", prunable),
                 params![keep_count as i64],
             )
-            .map_err(|e| format!("Failed to prune items: {}", e))?;
+            .context("Failed to prune items")?;
+        if deleted > 0 {
+            counters::increment(&tx, counters::ITEMS_PRUNED, deleted as u64)?;
+        }
+        tx.commit().context("Failed to commit prune")?;
+        remove_files(&text_paths);
+        remove_files(&image_paths);
+
+        drop(conn);
+        self.trim_item_events(ITEM_EVENTS_RETAINED)?;
 
         Ok(deleted)
     }
@@ -368,431 +1381,5426 @@ impl Database {
         &self,
         item_id: &str,
         pinboard_id: Option<&str>,
-    ) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let rows_affected = conn
             .execute(
                 "UPDATE clipboard_items SET pinboard_id = ?1 WHERE id = ?2",
                 params![pinboard_id, item_id],
             )
-            .map_err(|e| format!("Failed to update item pinboard: {}", e))?;
+            .context("Failed to update item pinboard")?;
 
         Ok(rows_affected > 0)
     }
 
     /// Toggle item's favorite status
-    pub fn toggle_item_favorite(&self, item_id: &str) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// When `mirror_favorites_to_pinboard` is enabled, favorited items are also
+    /// placed into the default Favorites pinboard (and removed when unfavorited)
+    pub fn toggle_item_favorite(&self, item_id: &str) -> Result<bool, ClipsterError> {
+        let rows_affected = {
+            let conn = self.conn.lock()?;
 
-        let rows_affected = conn
-            .execute(
+            conn.execute(
                 "UPDATE clipboard_items SET is_favorite = NOT is_favorite WHERE id = ?1",
                 params![item_id],
             )
-            .map_err(|e| format!("Failed to toggle favorite: {}", e))?;
+            .context("Failed to toggle favorite")?
+        };
+
+        if rows_affected > 0 {
+            self.mirror_favorite(item_id)?;
+        }
 
         Ok(rows_affected > 0)
     }
 
-    /// Clear all non-favorited, non-pinned clipboard items
-    pub fn clear_history(&self) -> Result<usize, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
-
-        let deleted = conn
+    /// Mirror an item's favorite status into the default Favorites pinboard
+    fn mirror_favorite(&self, item_id: &str) -> Result<(), ClipsterError> {
+        if self.get_setting("mirror_favorites_to_pinboard")?.as_deref() != Some("true") {
+            return Ok(());
+        }
+        let Some(favorites_id) = self.get_setting("favorites_pinboard_id")? else {
+            return Ok(());
+        };
+        if self.get_pinboard(&favorites_id)?.is_none() {
+            return Ok(());
+        }
+        let Some(item) = self.get_item(item_id)? else {
+            return Ok(());
+        };
+
+        if item.is_favorite && item.pinboard_id.is_none() {
+            self.update_item_pinboard(item_id, Some(&favorites_id))?;
+        } else if !item.is_favorite && item.pinboard_id.as_deref() == Some(favorites_id.as_str()) {
+            self.update_item_pinboard(item_id, None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Apply `op` to each of `item_ids` in a single transaction
+    /// Unknown IDs are reported as failed in the results without stopping the
+    /// others; a database error rolls back every item and is returned instead
+    /// Deleted items' image and spilled text files are removed after the commit
+    pub fn bulk_update_items(
+        &self,
+        item_ids: &[String],
+        op: &BulkOp,
+    ) -> Result<Vec<BulkItemResult>, ClipsterError> {
+        if let BulkOp::MoveToPinboard { pinboard_id } = op {
+            if self.get_pinboard(pinboard_id)?.is_none() {
+                return Err(ClipsterError::NotFound(format!(
+                    "Pinboard not found: {}",
+                    pinboard_id
+                )));
+            }
+        }
+
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        let mut results = Vec::with_capacity(item_ids.len());
+        let mut removed_files = Vec::new();
+        for id in item_ids {
+            let exists: bool = tx
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM clipboard_items WHERE id = ?1)",
+                    params![id],
+                    |row| row.get(0),
+                )
+                .context("Failed to check item")?;
+            if !exists {
+                results.push(BulkItemResult::failed(id, "Item not found"));
+                continue;
+            }
+
+            match op {
+                BulkOp::MoveToPinboard { pinboard_id } => tx.execute(
+                    "UPDATE clipboard_items SET pinboard_id = ?1 WHERE id = ?2",
+                    params![pinboard_id, id],
+                ),
+                BulkOp::RemoveFromPinboard => tx.execute(
+                    "UPDATE clipboard_items SET pinboard_id = NULL WHERE id = ?1",
+                    params![id],
+                ),
+                BulkOp::Favorite { favorite } => tx.execute(
+                    "UPDATE clipboard_items SET is_favorite = ?1 WHERE id = ?2",
+                    params![*favorite as i32, id],
+                ),
+                BulkOp::Delete => {
+                    removed_files.extend(spilled_text_paths(&tx, "id = ?1", params![id])?);
+                    removed_files.extend(self.image_file_paths(&tx, "id = ?1", params![id])?);
+                    tx.execute("DELETE FROM clipboard_items WHERE id = ?1", params![id])
+                }
+            }
+            .context("Failed to update item")?;
+            results.push(BulkItemResult::ok(id));
+        }
+        tx.commit().context("Failed to commit bulk update")?;
+        drop(conn);
+        remove_files(&removed_files);
+
+        if matches!(op, BulkOp::Favorite { .. }) {
+            for result in results.iter().filter(|result| result.ok) {
+                self.mirror_favorite(&result.id)?;
+            }
+        }
+        Ok(results)
+    }
+
+    /// Clear all non-favorited, non-pinned clipboard items
+    pub fn clear_history(&self) -> Result<usize, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let unpinned = "is_favorite = 0 AND pinboard_id IS NULL";
+        let text_paths = spilled_text_paths(&conn, unpinned, [])?;
+        let image_paths = self.image_file_paths(&conn, unpinned, [])?;
+        let deleted = conn
             .execute(
                 "DELETE FROM clipboard_items WHERE is_favorite = 0 AND pinboard_id IS NULL",
                 [],
             )
-            .map_err(|e| format!("Failed to clear history: {}", e))?;
+            .context("Failed to clear history")?;
+        remove_files(&text_paths);
+        remove_files(&image_paths);
 
         Ok(deleted)
     }
 
+    /// What `clear_history` would delete, to confirm it with
+    pub fn clear_history_summary(&self) -> Result<DeletionSummary, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let unpinned = "is_favorite = 0 AND pinboard_id IS NULL";
+        let (items, text_bytes): (i64, i64) = conn
+            .query_row(
+                &format!(
+                    "SELECT COUNT(*), COALESCE(SUM(LENGTH(CAST(content_text AS BLOB))), 0)
+                     FROM clipboard_items WHERE {}",
+                    unpinned
+                ),
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .context("Failed to summarize history")?;
+        let mut paths = spilled_text_paths(&conn, unpinned, [])?;
+        paths.extend(self.image_file_paths(&conn, unpinned, [])?);
+        let file_bytes: u64 = paths
+            .iter()
+            .filter_map(|path| fs::metadata(path).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+
+        Ok(DeletionSummary {
+            items: items as usize,
+            bytes: text_bytes as u64 + file_bytes,
+            ..Default::default()
+        })
+    }
+
     /// Check if content already exists in UNPINNED history (not in pinboards)
     /// This allows the same content to exist both in history and in pinboards
-    pub fn content_exists(&self, content_text: &str) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn content_exists(&self, content_text: &str) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let exists: bool = conn
-            .query_row(
-                "SELECT EXISTS(SELECT 1 FROM clipboard_items WHERE content_text = ?1 AND pinboard_id IS NULL LIMIT 1)",
-                params![content_text],
-                |row| row.get(0),
-            )
-            .map_err(|e| format!("Failed to check content existence: {}", e))?;
+            .prepare_cached(&format!(
+                "SELECT EXISTS(SELECT 1 FROM clipboard_items WHERE {} AND pinboard_id IS NULL LIMIT 1)",
+                same_content(1, 2)
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_row(params![content_hash(content_text), content_text], |row| {
+                    row.get(0)
+                })
+            })
+            .context("Failed to check content existence")?;
 
         Ok(exists)
     }
 
     /// Delete unpinned items with matching content (for "move to top" behavior)
-    /// Returns the ID, source_app and source_app_icon of the deleted item (if any)
-    /// Does NOT delete pinned items - they are preserved separately
+    /// Returns the source fields of the deleted item (if any), see `ReplacedItem`
+    /// Does NOT delete pinned or favorited items - see `move_favorite_to_top`
+    /// Only items from the same `source_pasteboard` match (None = general pasteboard);
+    /// with `DedupeScope::PerApp` only items from `source_app`, with `Off` none
     pub fn delete_unpinned_by_content(
         &self,
         content_text: &str,
-    ) -> Result<Option<(String, Option<String>, Option<String>)>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        source_pasteboard: Option<&str>,
+        scope: DedupeScope,
+        source_app: Option<&str>,
+    ) -> Result<Option<ReplacedItem>, ClipsterError> {
+        if scope == DedupeScope::Off {
+            return Ok(None);
+        }
+        let per_app = scope == DedupeScope::PerApp;
+        let conn = self.conn.lock()?;
 
         // First, get the ID and source app info of the item we're about to delete
-        let existing: Option<(String, Option<String>, Option<String>)> = conn
+        let matching = format!(
+            "{} AND pinboard_id IS NULL AND is_favorite = 0
+             AND source_pasteboard IS ?2 AND (?3 = 0 OR source_app IS ?4)",
+            same_content(1, 5)
+        );
+        let hash = content_hash(content_text);
+        let existing: Option<ReplacedItem> = conn
+            .prepare_cached(&format!(
+                "SELECT id, source_app, source_app_icon, source_window_title,
+                        COALESCE(first_copied_at, created_at)
+                 FROM clipboard_items
+                 WHERE {}
+                 LIMIT 1",
+                matching
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_row(
+                    params![hash, source_pasteboard, per_app, source_app, content_text],
+                    replaced_item_from_row,
+                )
+            })
+            .ok();
+
+        if existing.is_some() {
+            let params = params![hash, source_pasteboard, per_app, source_app, content_text];
+            let text_paths = spilled_text_paths(&conn, &matching, params)?;
+            conn.execute(
+                &format!("DELETE FROM clipboard_items WHERE {}", matching),
+                params,
+            )
+            .context("Failed to delete by content")?;
+            remove_files(&text_paths);
+        }
+
+        Ok(existing)
+    }
+
+    /// Delete unpinned Link items with the same canonical URL (for "move to top" behavior)
+    /// Rows captured before canonicalization still match on their exact text
+    /// Favorited items are kept, as in `delete_unpinned_by_content`
+    /// Returns the source fields of the deleted item (if any), see `ReplacedItem`
+    /// Only items from the same `source_pasteboard` match (None = general pasteboard);
+    /// `scope` and `source_app` apply as in `delete_unpinned_by_content`
+    pub fn delete_unpinned_by_canonical_url(
+        &self,
+        canonical_url: &str,
+        content_text: &str,
+        source_pasteboard: Option<&str>,
+        scope: DedupeScope,
+        source_app: Option<&str>,
+    ) -> Result<Option<ReplacedItem>, ClipsterError> {
+        if scope == DedupeScope::Off {
+            return Ok(None);
+        }
+        let per_app = scope == DedupeScope::PerApp;
+        let params = params![
+            canonical_url,
+            content_text,
+            source_pasteboard,
+            per_app,
+            source_app
+        ];
+        let conn = self.conn.lock()?;
+
+        let existing: Option<ReplacedItem> = conn
             .query_row(
-                "SELECT id, source_app, source_app_icon FROM clipboard_items WHERE content_text = ?1 AND pinboard_id IS NULL LIMIT 1",
-                params![content_text],
-                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+                "SELECT id, source_app, source_app_icon, source_window_title,
+                        COALESCE(first_copied_at, created_at)
+                 FROM clipboard_items
+                 WHERE (canonical_url = ?1 OR content_text = ?2) AND pinboard_id IS NULL
+                   AND is_favorite = 0 AND source_pasteboard IS ?3
+                   AND (?4 = 0 OR source_app IS ?5)
+                 ORDER BY created_at DESC LIMIT 1",
+                params,
+                replaced_item_from_row,
             )
             .ok();
 
         if existing.is_some() {
             conn.execute(
-                "DELETE FROM clipboard_items WHERE content_text = ?1 AND pinboard_id IS NULL",
-                params![content_text],
+                "DELETE FROM clipboard_items
+                 WHERE (canonical_url = ?1 OR content_text = ?2) AND pinboard_id IS NULL
+                   AND is_favorite = 0 AND source_pasteboard IS ?3
+                   AND (?4 = 0 OR source_app IS ?5)",
+                params,
             )
-            .map_err(|e| format!("Failed to delete by content: {}", e))?;
+            .context("Failed to delete by canonical URL")?;
         }
 
         Ok(existing)
     }
 
+    /// Move a favorited history item with matching content to the top by setting
+    /// its `created_at` to now, instead of replacing it with a new row
+    /// Keeps the favorite star that deleting and re-inserting would lose
+    /// Links also match on `canonical_url`; pasteboard and `scope` apply as in
+    /// `delete_unpinned_by_content`. Returns the updated item, None if no favorite matched
+    pub fn move_favorite_to_top(
+        &self,
+        content_text: &str,
+        canonical_url: Option<&str>,
+        source_pasteboard: Option<&str>,
+        scope: DedupeScope,
+        source_app: Option<&str>,
+    ) -> Result<Option<ClipboardItem>, ClipsterError> {
+        if scope == DedupeScope::Off {
+            return Ok(None);
+        }
+        let per_app = scope == DedupeScope::PerApp;
+        let updated: Option<String> = {
+            let conn = self.conn.lock()?;
+            conn.query_row(
+                &format!(
+                    "UPDATE clipboard_items SET created_at = ?1
+                     WHERE id = (
+                         SELECT id FROM clipboard_items
+                         WHERE ({} OR canonical_url = ?3) AND pinboard_id IS NULL
+                           AND is_favorite = 1 AND source_pasteboard IS ?4
+                           AND (?5 = 0 OR source_app IS ?6)
+                         ORDER BY created_at DESC LIMIT 1
+                     )
+                     RETURNING id",
+                    same_content(2, 7)
+                ),
+                params![
+                    Utc::now().to_rfc3339(),
+                    content_hash(content_text),
+                    canonical_url,
+                    source_pasteboard,
+                    per_app,
+                    source_app,
+                    content_text
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("Failed to move favorite to top")?
+        };
+
+        match updated {
+            Some(id) => self.get_item(&id),
+            None => Ok(None),
+        }
+    }
+
+    /// Query parameters stripped when canonicalizing URLs (`url_tracking_params` setting)
+    pub fn get_url_tracking_params(&self) -> Result<Vec<String>, ClipsterError> {
+        match self.get_setting("url_tracking_params")? {
+            Some(json) => serde_json::from_str(&json).context("Invalid url_tracking_params value"),
+            None => Ok(DEFAULT_TRACKING_PARAMS.iter().map(|p| p.to_string()).collect()),
+        }
+    }
+
+    // ==================== ITEM REVISIONS ====================
+
+    /// Replace the text of a text or link item
+    /// The previous text is kept as a revision; returns None if the item doesn't exist
+    pub fn update_item_content(
+        &self,
+        id: &str,
+        content_text: &str,
+        storage: &FileStorage,
+    ) -> Result<Option<ClipboardItem>, ClipsterError> {
+        let Some(item) = self.get_item(id)? else {
+            return Ok(None);
+        };
+        if !matches!(item.content_type, ContentType::Text | ContentType::Link) {
+            return Err(ClipsterError::Validation(
+                "Only text and link items can be edited".to_string(),
+            ));
+        }
+        if item.content_text.as_deref() == Some(content_text) {
+            return Ok(Some(item));
+        }
+
+        self.write_content(item, content_text, storage).map(Some)
+    }
+
+    /// Append a captured piece of text to a plain text item on a new line
+    /// (see `capture_merge`); the result is stored inline (or compressed)
+    /// No revision is saved; returns None if the item doesn't exist or isn't
+    /// plain text
+    pub fn append_item_text(
+        &self,
+        id: &str,
+        fragment: &str,
+    ) -> Result<Option<ClipboardItem>, ClipsterError> {
+        let Some(item) = self.get_item(id)? else {
+            return Ok(None);
+        };
+        if item.content_type != ContentType::Text || item.content_subtype.is_some() {
+            return Ok(None);
+        }
+        let compress = self.get_setting("compress_large_text")?.as_deref() == Some("true");
+
+        let old_text_path = item.text_path.clone();
+        let mut updated = item;
+        let existing = updated.content_text.take().unwrap_or_default();
+        updated.content_text = Some(capture_merge::join(&existing, fragment));
+        updated.text_path = None;
+        updated.refresh_preview();
+        let (stored_text, content_compressed) = stored_content(&updated, compress)?;
+
+        let conn = self.conn.lock()?;
+        conn.execute(
+            "UPDATE clipboard_items
+             SET content_text = ?1, content_compressed = ?2, text_path = NULL, content_hash = ?3
+             WHERE id = ?4",
+            params![
+                stored_text,
+                content_compressed,
+                item_content_hash(&updated),
+                updated.id
+            ],
+        )
+        .context("Failed to append item text")?;
+        drop(conn);
+
+        if let Some(path) = old_text_path {
+            remove_files(&[path]);
+        }
+
+        Ok(Some(updated))
+    }
+
+    /// Get the revisions of an item, newest first
+    pub fn get_item_revisions(&self, item_id: &str) -> Result<Vec<ItemRevision>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT item_id, revision_no, content_text, edited_at
+                 FROM item_revisions
+                 WHERE item_id = ?1
+                 ORDER BY revision_no DESC",
+            )
+            .context("Failed to prepare query")?;
+
+        let revisions = stmt
+            .query_map(params![item_id], ItemRevision::from_row)
+            .context("Failed to query revisions")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to collect revisions")?;
+
+        Ok(revisions)
+    }
+
+    /// Restore the text of a revision
+    /// The current text is saved as a new revision first, so it can be restored in turn
+    pub fn restore_item_revision(
+        &self,
+        item_id: &str,
+        revision_no: i64,
+        storage: &FileStorage,
+    ) -> Result<Option<ClipboardItem>, ClipsterError> {
+        let Some(item) = self.get_item(item_id)? else {
+            return Ok(None);
+        };
+
+        let content_text = {
+            let conn = self.conn.lock()?;
+            let mut stmt = conn
+                .prepare(
+                    "SELECT content_text FROM item_revisions
+                     WHERE item_id = ?1 AND revision_no = ?2",
+                )
+                .context("Failed to prepare query")?;
+            let mut rows = stmt
+                .query_map(params![item_id, revision_no], |row| row.get::<_, String>(0))
+                .context("Failed to query revision")?;
+
+            match rows.next() {
+                Some(Ok(text)) => text,
+                Some(Err(e)) => return Err(e).context("Failed to read revision"),
+                None => {
+                    return Err(ClipsterError::NotFound(format!(
+                        "Revision {} not found",
+                        revision_no
+                    )))
+                }
+            }
+        };
+
+        self.write_content(item, &content_text, storage).map(Some)
+    }
+
+    /// Overwrite an item's text, saving its current text as the next revision
+    /// Edited text is stored like captured text: spilled to `storage` above the
+    /// `large_text_threshold_bytes` setting, or compressed when that is on
+    fn write_content(
+        &self,
+        item: ClipboardItem,
+        content_text: &str,
+        storage: &FileStorage,
+    ) -> Result<ClipboardItem, ClipsterError> {
+        let compress = self.get_setting("compress_large_text")?.as_deref() == Some("true");
+        let threshold = self
+            .get_setting("large_text_threshold_bytes")?
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(file_storage::DEFAULT_LARGE_TEXT_THRESHOLD);
+        let canonical_url = match item.content_type {
+            ContentType::Link => {
+                Some(canonicalize_url(content_text, &self.get_url_tracking_params()?))
+            }
+            _ => None,
+        };
+
+        let previous_text = item.content_text.clone().unwrap_or_default();
+        let old_text_path = item.text_path.clone();
+        let mut updated = item;
+        updated.content_text = Some(content_text.to_string());
+        updated.text_path = None;
+        updated.canonical_url = canonical_url;
+        // An edited contact or event stays one while its text still parses
+        if updated.content_type == ContentType::Text {
+            let payload = contact_event::detect(content_text);
+            updated.content_subtype = payload.as_ref().map(StructuredPayload::subtype);
+            updated.title = payload.as_ref().map(StructuredPayload::title);
+        }
+        updated.refresh_preview();
+        if !compress {
+            storage.spill_large_text(&mut updated, threshold)?;
+        }
+        let (stored_text, content_compressed) = stored_content(&updated, compress)?;
+        let content_hash = item_content_hash(&updated);
+
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        tx.execute(
+            "INSERT INTO item_revisions (item_id, revision_no, content_text, edited_at)
+             SELECT ?1, COALESCE(MAX(revision_no), 0) + 1, ?2, ?3
+             FROM item_revisions WHERE item_id = ?1",
+            params![updated.id, previous_text, Utc::now().to_rfc3339()],
+        )
+        .context("Failed to save revision")?;
+
+        tx.execute(
+            "DELETE FROM item_revisions
+             WHERE item_id = ?1 AND revision_no NOT IN (
+                 SELECT revision_no FROM item_revisions
+                 WHERE item_id = ?1
+                 ORDER BY revision_no DESC
+                 LIMIT ?2
+             )",
+            params![updated.id, MAX_REVISIONS_PER_ITEM],
+        )
+        .context("Failed to trim revisions")?;
+
+        tx.execute(
+            "UPDATE clipboard_items
+             SET content_text = ?1, content_compressed = ?2, text_path = ?3, canonical_url = ?4,
+                 content_subtype = ?5, title = ?6, content_hash = ?7
+             WHERE id = ?8",
+            params![
+                stored_text,
+                content_compressed,
+                updated.text_path,
+                updated.canonical_url,
+                updated.content_subtype.map(|subtype| subtype.as_str()),
+                updated.title,
+                content_hash,
+                updated.id
+            ],
+        )
+        .context("Failed to update item content")?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        drop(conn);
+
+        // A spill file is named after the item, so a re-spilled edit reuses it
+        if let Some(path) = old_text_path.filter(|path| updated.text_path.as_ref() != Some(path)) {
+            remove_files(&[path]);
+        }
+
+        // Callers get the full text back, like `get_item` returns it
+        updated.content_text = Some(content_text.to_string());
+        Ok(updated)
+    }
+
     // ==================== PINBOARDS ====================
 
     /// Insert a new pinboard
-    pub fn insert_pinboard(&self, pinboard: &Pinboard) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn insert_pinboard(&self, pinboard: &Pinboard) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
 
         conn.execute(
-            "INSERT INTO pinboards (id, name, icon, position, created_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT INTO pinboards
+                 (id, name, icon, color, description, parent_id, position, created_at,
+                  default_transform)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 pinboard.id,
                 pinboard.name,
                 pinboard.icon,
+                pinboard.color,
+                pinboard.description,
+                pinboard.parent_id,
                 pinboard.position,
                 pinboard.created_at.to_rfc3339(),
+                pinboard
+                    .default_transform
+                    .map(|transform| transform.as_str()),
             ],
         )
-        .map_err(|e| format!("Failed to insert pinboard: {}", e))?;
+        .context("Failed to insert pinboard")?;
 
         Ok(())
     }
 
-    /// Get all pinboards ordered by position
-    pub fn get_pinboards(&self) -> Result<Vec<Pinboard>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Get pinboards ordered by position, as a flat list
+    /// Nested pinboards carry their `parent_id`; the frontend builds the tree
+    /// Archived pinboards are only included when `include_archived` is true
+    pub fn get_pinboards(&self, include_archived: bool) -> Result<Vec<Pinboard>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, icon, position, created_at
-                 FROM pinboards
-                 ORDER BY position ASC",
+                "SELECT p.id, p.name, p.icon, p.color, p.description, p.parent_id, p.position,
+                        p.created_at, p.rules, p.default_transform, p.archived, p.lock_hash,
+                        COUNT(c.id) AS item_count
+                 FROM pinboards p
+                 LEFT JOIN clipboard_items c
+                   ON c.pinboard_id = p.id OR (c.pinboard_id IS NULL AND c.auto_pinboard_id = p.id)
+                 WHERE ?1 OR p.archived = 0
+                 GROUP BY p.id
+                 ORDER BY p.position ASC",
             )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+            .context("Failed to prepare query")?;
 
         let pinboards = stmt
-            .query_map([], |row| Pinboard::from_row(row))
-            .map_err(|e| format!("Failed to query pinboards: {}", e))?
+            .query_map(params![include_archived], |row| Pinboard::from_row(row))
+            .context("Failed to query pinboards")?
             .collect::<SqliteResult<Vec<_>>>()
-            .map_err(|e| format!("Failed to collect pinboards: {}", e))?;
+            .context("Failed to collect pinboards")?;
 
         Ok(pinboards)
     }
 
-    /// Get items in a specific pinboard
+    /// Get items in a specific pinboard with pagination
+    /// Thumbnails are left out unless `include_thumbnails` is set (see `get_thumbnails`)
     pub fn get_pinboard_items(
         &self,
         pinboard_id: &str,
         limit: usize,
-    ) -> Result<Vec<ClipboardItem>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        offset: usize,
+        include_thumbnails: bool,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
-            .prepare(
-                "SELECT id, content_type, content_text, thumbnail_base64, image_path,
-                        source_app, source_app_icon, created_at, pinboard_id, is_favorite
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, {}, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
                  FROM clipboard_items
-                 WHERE pinboard_id = ?1
+                 WHERE pinboard_id = ?1 OR (pinboard_id IS NULL AND auto_pinboard_id = ?1)
                  ORDER BY created_at DESC
+                 LIMIT ?2 OFFSET ?3",
+                thumbnail_column(include_thumbnails)
+            ))
+            .context("Failed to prepare query")?;
+
+        let items = stmt
+            .query_map(params![pinboard_id, limit as i64, offset as i64], |row| {
+                self.item_from_row(row)
+            })
+            .context("Failed to query pinboard items")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to collect items")?;
+
+        Ok(items)
+    }
+
+    /// Items copied back most often, ties broken by the most recent copy-back
+    /// With `pinboard_id`, only that pinboard's items (including auto-filed ones);
+    /// otherwise all items outside locked pinboards. Items never used are left out
+    pub fn get_top_used_items(
+        &self,
+        pinboard_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare_cached(&format!(
+                "SELECT id, content_type, content_text, thumbnail_base64, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                        auto_pinboard_id, text_path, content_compressed, canonical_url,
+                        source_window_title, source_pasteboard, first_copied_at,
+                        thumbnail_is_favicon, files_summary, use_count, is_directory,
+                        original_width, original_height, downscale_skipped, title,
+                        media_metadata, content_subtype, expires_at, language, palette
+                 FROM clipboard_items
+                 WHERE use_count > 0
+                   AND CASE WHEN ?1 IS NULL
+                       THEN {}
+                       ELSE pinboard_id = ?1 OR (pinboard_id IS NULL AND auto_pinboard_id = ?1)
+                       END
+                 ORDER BY use_count DESC, last_used_at DESC
                  LIMIT ?2",
-            )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+                visible_item(None)
+            ))
+            .context("Failed to prepare query")?;
 
         let items = stmt
             .query_map(params![pinboard_id, limit as i64], |row| {
-                ClipboardItem::from_row(row)
+                self.item_from_row(row)
             })
-            .map_err(|e| format!("Failed to query pinboard items: {}", e))?
+            .context("Failed to query top used items")?
             .collect::<SqliteResult<Vec<_>>>()
-            .map_err(|e| format!("Failed to collect items: {}", e))?;
+            .context("Failed to collect items")?;
 
         Ok(items)
     }
 
+    /// Count one copy-back of an item, and one paste overall
+    pub fn record_item_use(&self, id: &str) -> Result<(), ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        let updated = tx
+            .execute(
+                "UPDATE clipboard_items SET use_count = use_count + 1, last_used_at = ?1
+                 WHERE id = ?2",
+                params![Utc::now().to_rfc3339(), id],
+            )
+            .context("Failed to record item use")?;
+        if updated > 0 {
+            counters::increment(&tx, counters::PASTES, 1)?;
+        }
+        tx.commit().context("Failed to commit item use")?;
+        Ok(())
+    }
+
+    /// Count items in a specific pinboard (including auto-filed items)
+    pub fn count_pinboard_items(&self, pinboard_id: &str) -> Result<usize, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM clipboard_items
+                 WHERE pinboard_id = ?1 OR (pinboard_id IS NULL AND auto_pinboard_id = ?1)",
+                params![pinboard_id],
+                |row| row.get(0),
+            )
+            .context("Failed to count pinboard items")?;
+
+        Ok(count as usize)
+    }
+
     /// Update pinboard details
+    /// A `None` color goes back to the pinboard's palette color
     pub fn update_pinboard(
         &self,
         id: &str,
         name: &str,
         icon: Option<&str>,
-    ) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+        color: Option<&str>,
+        description: Option<&str>,
+        default_transform: Option<Transform>,
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let rows_affected = conn
             .execute(
-                "UPDATE pinboards SET name = ?1, icon = ?2 WHERE id = ?3",
-                params![name, icon, id],
+                "UPDATE pinboards SET name = ?1, icon = ?2, color = ?3, description = ?4,
+                                      default_transform = ?5
+                 WHERE id = ?6",
+                params![
+                    name,
+                    icon,
+                    color,
+                    description,
+                    default_transform.map(|transform| transform.as_str()),
+                    id
+                ],
             )
-            .map_err(|e| format!("Failed to update pinboard: {}", e))?;
+            .context("Failed to update pinboard")?;
 
         Ok(rows_affected > 0)
     }
 
     /// Delete a pinboard (items will have pinboard_id set to NULL)
-    pub fn delete_pinboard(&self, id: &str) -> Result<bool, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Nested pinboards are deleted with it when `delete_children` is set,
+    /// otherwise they move to the end of the root level
+    pub fn delete_pinboard(&self, id: &str, delete_children: bool) -> Result<bool, ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        let parents = pinboard_parents(&tx)?;
+        let mut deleted = vec![id];
+        if delete_children {
+            deleted.extend(pinboard_tree::descendants(&parents, id));
+        } else {
+            // Children keep their relative order
+            let end: i32 = tx
+                .query_row(
+                    "SELECT COALESCE(MAX(position) + 1, 0) FROM pinboards WHERE parent_id IS NULL",
+                    [],
+                    |row| row.get(0),
+                )
+                .context("Failed to read pinboard positions")?;
+            let children = tx
+                .prepare("SELECT id FROM pinboards WHERE parent_id = ?1 ORDER BY position")
+                .and_then(|mut stmt| {
+                    stmt.query_map(params![id], |row| row.get::<_, String>(0))?
+                        .collect::<SqliteResult<Vec<_>>>()
+                })
+                .context("Failed to query nested pinboards")?;
+            for (offset, child) in children.iter().enumerate() {
+                tx.execute(
+                    "UPDATE pinboards SET parent_id = NULL, position = ?1 WHERE id = ?2",
+                    params![end + offset as i32, child],
+                )
+                .context("Failed to move nested pinboard to the root")?;
+            }
+        }
 
-        let rows_affected = conn
-            .execute("DELETE FROM pinboards WHERE id = ?1", params![id])
-            .map_err(|e| format!("Failed to delete pinboard: {}", e))?;
+        let mut rows_affected = 0;
+        for pinboard_id in deleted {
+            tx.execute(
+                "UPDATE clipboard_items SET auto_pinboard_id = NULL WHERE auto_pinboard_id = ?1",
+                params![pinboard_id],
+            )
+            .context("Failed to clear auto-filed items")?;
+
+            rows_affected += tx
+                .execute("DELETE FROM pinboards WHERE id = ?1", params![pinboard_id])
+                .context("Failed to delete pinboard")?;
+        }
 
+        tx.commit().context("Failed to commit pinboard deletion")?;
         Ok(rows_affected > 0)
     }
 
+    /// What `delete_pinboard` would delete, to confirm it with
+    /// No item is deleted: the items of the deleted pinboards go back to history
+    pub fn delete_pinboard_summary(
+        &self,
+        id: &str,
+        delete_children: bool,
+    ) -> Result<DeletionSummary, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let parents = pinboard_parents(&conn)?;
+        if !parents.contains_key(id) {
+            return Err(ClipsterError::NotFound(format!("Pinboard {}", id)));
+        }
+        let mut deleted = vec![id];
+        if delete_children {
+            deleted.extend(pinboard_tree::descendants(&parents, id));
+        }
+
+        let mut items_unpinned = 0;
+        for pinboard_id in &deleted {
+            let count: i64 = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM clipboard_items WHERE pinboard_id = ?1",
+                    params![pinboard_id],
+                    |row| row.get(0),
+                )
+                .context("Failed to count pinboard items")?;
+            items_unpinned += count as usize;
+        }
+
+        Ok(DeletionSummary {
+            pinboards: deleted.len(),
+            items_unpinned,
+            ..Default::default()
+        })
+    }
+
+    /// Nest a pinboard under `parent_id`, or move it to the root with `None`
+    /// The pinboard goes to the end of its new level
+    pub fn set_pinboard_parent(
+        &self,
+        id: &str,
+        parent_id: Option<&str>,
+    ) -> Result<(), ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        let parents = pinboard_parents(&tx)?;
+        pinboard_tree::check_parent(&parents, id, parent_id).map_err(ClipsterError::Validation)?;
+
+        let position: i32 = tx
+            .query_row(
+                "SELECT COALESCE(MAX(position) + 1, 0) FROM pinboards
+                 WHERE parent_id IS ?1 AND id != ?2",
+                params![parent_id, id],
+                |row| row.get(0),
+            )
+            .context("Failed to read pinboard positions")?;
+        tx.execute(
+            "UPDATE pinboards SET parent_id = ?1, position = ?2 WHERE id = ?3",
+            params![parent_id, position, id],
+        )
+        .context("Failed to update pinboard parent")?;
+
+        tx.commit().context("Failed to commit pinboard parent")
+    }
+
     /// Get a single pinboard by ID
-    pub fn get_pinboard(&self, id: &str) -> Result<Option<Pinboard>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    pub fn get_pinboard(&self, id: &str) -> Result<Option<Pinboard>, ClipsterError> {
+        let conn = self.conn.lock()?;
 
         let mut stmt = conn
             .prepare(
-                "SELECT id, name, icon, position, created_at
-                 FROM pinboards
-                 WHERE id = ?1",
+                "SELECT p.id, p.name, p.icon, p.color, p.description, p.parent_id, p.position,
+                        p.created_at, p.rules, p.default_transform, p.archived, p.lock_hash,
+                        COUNT(c.id) AS item_count
+                 FROM pinboards p
+                 LEFT JOIN clipboard_items c
+                   ON c.pinboard_id = p.id OR (c.pinboard_id IS NULL AND c.auto_pinboard_id = p.id)
+                 WHERE p.id = ?1
+                 GROUP BY p.id",
             )
-            .map_err(|e| format!("Failed to prepare query: {}", e))?;
+            .context("Failed to prepare query")?;
 
         let mut rows = stmt
             .query_map(params![id], |row| Pinboard::from_row(row))
-            .map_err(|e| format!("Failed to query pinboard: {}", e))?;
+            .context("Failed to query pinboard")?;
 
         match rows.next() {
             Some(Ok(pinboard)) => Ok(Some(pinboard)),
-            Some(Err(e)) => Err(format!("Failed to read pinboard: {}", e)),
+            Some(Err(e)) => Err(e).context("Failed to read pinboard"),
             None => Ok(None),
         }
     }
 
-    /// Reorder pinboards by updating their positions
-    /// Takes a list of pinboard IDs in the desired order
-    pub fn reorder_pinboards(&self, pinboard_ids: &[String]) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Set or clear the smart rules of a pinboard
+    pub fn set_pinboard_rules(
+        &self,
+        id: &str,
+        rules: Option<&PinboardRules>,
+    ) -> Result<bool, ClipsterError> {
+        let rules_json = match rules {
+            Some(rules) => {
+                Some(serde_json::to_string(rules).context("Failed to serialize pinboard rules")?)
+            }
+            None => None,
+        };
 
-        for (position, id) in pinboard_ids.iter().enumerate() {
-            conn.execute(
-                "UPDATE pinboards SET position = ?1 WHERE id = ?2",
-                params![position as i32, id],
+        let conn = self.conn.lock()?;
+
+        let rows_affected = conn
+            .execute(
+                "UPDATE pinboards SET rules = ?1 WHERE id = ?2",
+                params![rules_json, id],
             )
-            .map_err(|e| format!("Failed to update pinboard position: {}", e))?;
-        }
+            .context("Failed to update pinboard rules")?;
 
-        Ok(())
+        Ok(rows_affected > 0)
     }
 
-    // ==================== SETTINGS ====================
+    /// Find the first smart pinboard (by position) whose rules match the item
+    pub fn find_matching_pinboard(
+        &self,
+        item: &ClipboardItem,
+    ) -> Result<Option<String>, ClipsterError> {
+        let pinboards = self.get_pinboards(false)?;
+
+        Ok(pinboards
+            .into_iter()
+            .find(|p| p.rules.as_ref().map(|r| r.matches(item)).unwrap_or(false))
+            .map(|p| p.id))
+    }
 
-    /// Get a setting value
-    pub fn get_setting(&self, key: &str) -> Result<Option<String>, String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
+    /// Auto-assign an unpinned item to the first matching smart pinboard
+    /// Returns the assigned pinboard ID (if any)
+    pub fn apply_pinboard_rules(
+        &self,
+        item: &ClipboardItem,
+    ) -> Result<Option<String>, ClipsterError> {
+        if item.pinboard_id.is_some() {
+            return Ok(None);
+        }
 
-        let result: SqliteResult<String> =
-            conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| {
-                row.get(0)
-            });
+        let Some(pinboard_id) = self.find_matching_pinboard(item)? else {
+            return Ok(None);
+        };
 
-        match result {
-            Ok(value) => Ok(Some(value)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(format!("Failed to get setting: {}", e)),
-        }
+        self.update_item_pinboard(&item.id, Some(&pinboard_id))?;
+        Ok(Some(pinboard_id))
     }
 
-    /// Set a setting value
-    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| format!("Lock error: {}", e))?;
-
-        conn.execute(
-            "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
-            params![key, value],
-        )
-        .map_err(|e| format!("Failed to set setting: {}", e))?;
+    /// Get the per-app default pinboard mapping (app identifier -> pinboard ID)
+    /// Apps are identified as in `app_id::find_entry`
+    pub fn get_app_default_pinboards(&self) -> Result<HashMap<String, String>, ClipsterError> {
+        match self.get_setting("app_default_pinboards")? {
+            Some(json) => {
+                serde_json::from_str(&json).context("Invalid app_default_pinboards value")
+            }
+            None => Ok(HashMap::new()),
+        }
+    }
 
-        Ok(())
+    /// Replace the per-app default pinboard mapping
+    pub fn set_app_default_pinboards(
+        &self,
+        mapping: &HashMap<String, String>,
+    ) -> Result<(), ClipsterError> {
+        let json =
+            serde_json::to_string(mapping).context("Failed to serialize app_default_pinboards")?;
+        self.set_setting("app_default_pinboards", &json)
     }
 
-    /// Get history limit setting
-    pub fn get_history_limit(&self) -> Result<usize, String> {
-        let limit_str = self.get_setting("history_limit")?.unwrap_or_else(|| "500".to_string());
-        limit_str
-            .parse()
-            .map_err(|_| "Invalid history_limit value".to_string())
+    /// Get the pinboard palette shortcuts (pinboard ID -> shortcut, e.g. "Alt+Shift+1")
+    pub fn get_pinboard_shortcuts(&self) -> Result<HashMap<String, String>, ClipsterError> {
+        match self.get_setting("pinboard_shortcuts")? {
+            Some(json) => serde_json::from_str(&json).context("Invalid pinboard_shortcuts value"),
+            None => Ok(HashMap::new()),
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Replace the pinboard palette shortcuts
+    pub fn set_pinboard_shortcuts(
+        &self,
+        shortcuts: &HashMap<String, String>,
+    ) -> Result<(), ClipsterError> {
+        let json =
+            serde_json::to_string(shortcuts).context("Failed to serialize pinboard_shortcuts")?;
+        self.set_setting("pinboard_shortcuts", &json)
+    }
 
-    #[test]
-    fn test_database_creation() {
-        let db = Database::new_in_memory().expect("Failed to create in-memory database");
-        assert!(db.count_items().unwrap() == 0);
+    /// Global panel placement, used on monitors without their own
+    pub fn get_panel_placement(&self) -> Result<Placement, ClipsterError> {
+        match self.get_setting("panel_placement")? {
+            Some(json) => serde_json::from_str(&json).context("Invalid panel_placement value"),
+            None => Ok(Placement::default()),
+        }
     }
 
-    #[test]
-    fn test_insert_and_get_item() {
-        let db = Database::new_in_memory().unwrap();
+    /// Replace the global panel placement
+    pub fn set_panel_placement(&self, placement: &Placement) -> Result<(), ClipsterError> {
+        let json =
+            serde_json::to_string(placement).context("Failed to serialize panel_placement")?;
+        self.set_setting("panel_placement", &json)
+    }
 
-        let item = ClipboardItem::new_text("Hello, World!".to_string(), Some("Test".to_string()), None);
-        db.insert_item(&item).unwrap();
+    /// Panel placements saved per monitor
+    pub fn get_monitor_placements(&self) -> Result<MonitorPlacements, ClipsterError> {
+        match self.get_setting("monitor_placements")? {
+            Some(json) => serde_json::from_str(&json).context("Invalid monitor_placements value"),
+            None => Ok(MonitorPlacements::new()),
+        }
+    }
 
-        let items = db.get_items(10, 0).unwrap();
-        assert_eq!(items.len(), 1);
-        assert_eq!(items[0].content_text, Some("Hello, World!".to_string()));
+    /// Replace the panel placements saved per monitor
+    pub fn set_monitor_placements(
+        &self,
+        placements: &MonitorPlacements,
+    ) -> Result<(), ClipsterError> {
+        let json =
+            serde_json::to_string(placements).context("Failed to serialize monitor_placements")?;
+        self.set_setting("monitor_placements", &json)
     }
 
-    #[test]
-    fn test_delete_item() {
-        let db = Database::new_in_memory().unwrap();
+    /// Auto-file an unpinned item into the default pinboard of its source app,
+    /// identified by `source_app_id` (bundle identifier or executable path)
+    /// The item stays in history; returns the pinboard ID it was filed into (if any)
+    pub fn apply_app_default_pinboard(
+        &self,
+        item: &ClipboardItem,
+        source_app_id: &str,
+    ) -> Result<Option<String>, ClipsterError> {
+        if item.pinboard_id.is_some() {
+            return Ok(None);
+        }
 
-        let item = ClipboardItem::new_text("To delete".to_string(), None, None);
-        let id = item.id.clone();
-        db.insert_item(&item).unwrap();
+        let mapping = self.get_app_default_pinboards()?;
+        let Some(pinboard_id) = app_id::find_entry(&mapping, source_app_id).cloned() else {
+            return Ok(None);
+        };
 
-        assert_eq!(db.count_items().unwrap(), 1);
+        // Ignore stale mappings pointing at deleted pinboards
+        if self.get_pinboard(&pinboard_id)?.is_none() {
+            return Ok(None);
+        }
 
-        db.delete_item(&id).unwrap();
-        assert_eq!(db.count_items().unwrap(), 0);
+        let conn = self.conn.lock()?;
+        conn.execute(
+            "UPDATE clipboard_items SET auto_pinboard_id = ?1 WHERE id = ?2",
+            params![pinboard_id, item.id],
+        )
+        .context("Failed to set auto pinboard")?;
+
+        Ok(Some(pinboard_id))
+    }
+
+    /// Get the per-app capture rate limits (app identifier -> max captures per minute)
+    /// Apps are identified as in `rate_limiter::limit_for`; apps without an entry
+    /// are unlimited
+    pub fn get_app_rate_limits(&self) -> Result<HashMap<String, u32>, ClipsterError> {
+        match self.get_setting("app_rate_limits")? {
+            Some(json) => serde_json::from_str(&json).context("Invalid app_rate_limits value"),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Replace the per-app capture rate limits
+    pub fn set_app_rate_limits(&self, limits: &HashMap<String, u32>) -> Result<(), ClipsterError> {
+        let json = serde_json::to_string(limits).context("Failed to serialize app_rate_limits")?;
+        self.set_setting("app_rate_limits", &json)
+    }
+
+    /// Get the panel view state saved when it was last hidden
+    /// None until the frontend saves one
+    pub fn get_view_state(&self) -> Result<Option<serde_json::Value>, ClipsterError> {
+        match self.get_setting("view_state")? {
+            Some(json) => Self::parse_view_state(&json).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Save the panel view state (active pinboard, type filter, scroll anchor)
+    /// Only JSON objects of at most `MAX_VIEW_STATE_BYTES` are accepted
+    pub fn set_view_state(&self, view_state: &serde_json::Value) -> Result<(), ClipsterError> {
+        let json = serde_json::to_string(view_state).context("Failed to serialize view_state")?;
+        Self::parse_view_state(&json)?;
+        self.set_setting("view_state", &json)
+    }
+
+    /// Parse a view state value, checking its size and that it is an object
+    fn parse_view_state(json: &str) -> Result<serde_json::Value, ClipsterError> {
+        if json.len() > MAX_VIEW_STATE_BYTES {
+            return Err(ClipsterError::Validation(format!(
+                "View state is too large ({} bytes, at most {})",
+                json.len(),
+                MAX_VIEW_STATE_BYTES
+            )));
+        }
+        match serde_json::from_str(json) {
+            Ok(value @ serde_json::Value::Object(_)) => Ok(value),
+            Ok(_) => Err(ClipsterError::Validation(
+                "View state must be a JSON object".to_string(),
+            )),
+            Err(e) => Err(ClipsterError::Validation(format!(
+                "Invalid view state: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Get the automatic clearing schedule (invalid values count as off)
+    pub fn get_auto_clear_schedule(&self) -> Result<AutoClearSchedule, ClipsterError> {
+        Ok(self
+            .get_setting("auto_clear_schedule")?
+            .and_then(|value| AutoClearSchedule::parse(&value))
+            .unwrap_or_default())
+    }
+
+    /// Set the automatic clearing schedule
+    /// Restarts the schedule from now, so a new time never triggers a catch-up run
+    pub fn set_auto_clear_schedule(
+        &self,
+        schedule: AutoClearSchedule,
+    ) -> Result<(), ClipsterError> {
+        self.set_setting("auto_clear_schedule", &schedule.to_string())?;
+        self.set_auto_clear_last_run(Utc::now())
+    }
+
+    /// Time of the last scheduled clear (or of the last schedule change)
+    pub fn get_auto_clear_last_run(&self) -> Result<Option<DateTime<Utc>>, ClipsterError> {
+        Ok(self
+            .get_setting("auto_clear_last_run")?
+            .and_then(|value| DateTime::parse_from_rfc3339(&value).ok())
+            .map(|at| at.with_timezone(&Utc)))
+    }
+
+    /// Record a scheduled clear
+    pub fn set_auto_clear_last_run(&self, at: DateTime<Utc>) -> Result<(), ClipsterError> {
+        self.set_setting("auto_clear_last_run", &at.to_rfc3339())
+    }
+
+    /// Set or clear the passcode hash of a pinboard
+    pub fn set_pinboard_lock_hash(
+        &self,
+        id: &str,
+        lock_hash: Option<&str>,
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let rows_affected = conn
+            .execute(
+                "UPDATE pinboards SET lock_hash = ?1 WHERE id = ?2",
+                params![lock_hash, id],
+            )
+            .context("Failed to update pinboard lock")?;
+
+        Ok(rows_affected > 0)
+    }
+
+    /// Archive or unarchive a pinboard
+    /// Archived pinboards keep their items (still protected from pruning)
+    /// An unarchived pinboard is appended after the active boards of its level:
+    /// its old position may have been reused by `reorder_pinboards` meanwhile
+    pub fn set_pinboard_archived(&self, id: &str, archived: bool) -> Result<bool, ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        if !archived {
+            tx.execute(
+                "UPDATE pinboards SET position = (
+                     SELECT COALESCE(MAX(p.position), -1) + 1 FROM pinboards p
+                     WHERE p.archived = 0 AND p.parent_id IS pinboards.parent_id
+                 )
+                 WHERE id = ?1 AND archived = 1",
+                params![id],
+            )
+            .context("Failed to update pinboard position")?;
+        }
+
+        let rows_affected = tx
+            .execute(
+                "UPDATE pinboards SET archived = ?1 WHERE id = ?2",
+                params![archived as i32, id],
+            )
+            .context("Failed to update pinboard archived state")?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(rows_affected > 0)
+    }
+
+    /// Reorder pinboards by updating their positions
+    /// Takes a list of pinboard IDs in the desired order, all nested under
+    /// `parent_id` (`None` for the root level); positions are per level
+    /// Archived pinboards and pinboards of other levels are skipped and keep their position
+    /// All positions are written in one transaction: either every pinboard moves or none do
+    pub fn reorder_pinboards(
+        &self,
+        parent_id: Option<&str>,
+        pinboard_ids: &[String],
+    ) -> Result<(), ClipsterError> {
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "UPDATE pinboards SET position = ?1
+                     WHERE id = ?2 AND archived = 0 AND parent_id IS ?3",
+                )
+                .context("Failed to prepare query")?;
+            let mut position = 0;
+            for id in pinboard_ids {
+                let updated = stmt
+                    .execute(params![position, id, parent_id])
+                    .context("Failed to update pinboard position")?;
+                if updated > 0 {
+                    position += 1;
+                }
+            }
+        }
+
+        tx.commit().context("Failed to commit pinboard order")
+    }
+
+    // ==================== CHANGE LOG ====================
+
+    /// Sequence number of the most recent item event (0 if none)
+    pub fn latest_change_seq(&self) -> Result<i64, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        conn.prepare_cached("SELECT COALESCE(MAX(seq), 0) FROM item_events")
+            .and_then(|mut stmt| stmt.query_row([], |row| row.get(0)))
+            .context("Failed to get latest change seq")
+    }
+
+    /// Get item changes after `since`, one entry per item, ordered by sequence
+    /// Insert-then-update collapses to Insert; items that no longer exist are Delete,
+    /// items of locked pinboards are Hidden, without their content
+    pub fn get_changes_since(&self, since: i64) -> Result<ChangeSet, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let (min_seq, latest_seq): (Option<i64>, i64) = conn
+            .query_row(
+                "SELECT MIN(seq), COALESCE(MAX(seq), 0) FROM item_events",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .context("Failed to read change log bounds")?;
+
+        // Events after `since` were trimmed, or `since` is from another database
+        let trimmed = matches!(min_seq, Some(min) if since < min - 1);
+        if trimmed || since > latest_seq {
+            return Ok(ChangeSet {
+                latest_seq,
+                reset: true,
+                changes: Vec::new(),
+            });
+        }
+
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT e.item_id, e.last_seq, e.inserted, {} AS visible,
+                        c.id, c.content_type, c.content_text, c.thumbnail_base64, c.image_path,
+                        c.source_app, c.source_app_icon, c.created_at, c.pinboard_id,
+                        c.is_favorite, c.auto_pinboard_id, c.text_path, c.content_compressed,
+                        c.canonical_url, c.source_window_title, c.source_pasteboard,
+                        c.first_copied_at, c.thumbnail_is_favicon, c.files_summary, c.use_count,
+                        c.is_directory, c.original_width, c.original_height, c.downscale_skipped,
+                        c.title, c.media_metadata, c.content_subtype, c.expires_at, c.language,
+                        c.palette
+                 FROM (
+                     SELECT item_id, MAX(seq) AS last_seq, MAX(kind = 'insert') AS inserted
+                     FROM item_events
+                     WHERE seq > ?1
+                     GROUP BY item_id
+                 ) e
+                 LEFT JOIN clipboard_items c ON c.id = e.item_id
+                 ORDER BY e.last_seq",
+                visible_item(None)
+            ))
+            .context("Failed to prepare query")?;
+
+        let changes = stmt
+            .query_map(params![since], |row| {
+                let item_id: String = row.get("item_id")?;
+                let seq: i64 = row.get("last_seq")?;
+                let inserted: bool = row.get::<_, i64>("inserted")? != 0;
+                let exists = row.get::<_, Option<String>>("id")?.is_some();
+                let visible: bool = row.get("visible")?;
+
+                let (kind, item) = if !exists {
+                    (ItemChangeKind::Delete, None)
+                } else if !visible {
+                    (ItemChangeKind::Hidden, None)
+                } else if inserted {
+                    (ItemChangeKind::Insert, Some(self.item_from_row(row)?))
+                } else {
+                    (ItemChangeKind::Update, Some(self.item_from_row(row)?))
+                };
+
+                Ok(ItemChange {
+                    seq,
+                    item_id,
+                    kind,
+                    item,
+                })
+            })
+            .context("Failed to query changes")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to read changes")?;
+
+        Ok(ChangeSet {
+            latest_seq,
+            reset: false,
+            changes,
+        })
+    }
+
+    /// Drop all but the most recent `keep` item events
+    pub fn trim_item_events(&self, keep: i64) -> Result<usize, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        conn.execute(
+            "DELETE FROM item_events WHERE seq <= (SELECT MAX(seq) FROM item_events) - ?1",
+            params![keep],
+        )
+        .context("Failed to trim item events")
+    }
+
+    // ==================== APP ICONS ====================
+
+    /// Cached icon (base64 PNG) of the app at `app_path`
+    pub fn get_app_icon(&self, app_path: &str) -> Result<Option<String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        conn.prepare_cached("SELECT icon FROM app_icons WHERE app_path = ?1")
+            .and_then(|mut stmt| {
+                stmt.query_row(params![app_path], |row| row.get(0))
+                    .optional()
+            })
+            .context("Failed to get app icon")
+    }
+
+    /// Cache the icon of the app at `app_path`
+    pub fn set_app_icon(&self, app_path: &str, icon: &str) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        conn.prepare_cached(
+            "INSERT OR REPLACE INTO app_icons (app_path, icon, extracted_at) VALUES (?1, ?2, ?3)",
+        )
+        .and_then(|mut stmt| stmt.execute(params![app_path, icon, Utc::now().to_rfc3339()]))
+        .context("Failed to cache app icon")?;
+
+        Ok(())
+    }
+
+    /// Set the source app icon of an item saved before it was available
+    /// Returns false if the item is gone or already has an icon
+    pub fn fill_source_app_icon(&self, item_id: &str, icon: &str) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE clipboard_items SET source_app_icon = ?1
+                 WHERE id = ?2 AND source_app_icon IS NULL",
+                params![icon, item_id],
+            )
+            .context("Failed to set source app icon")?;
+
+        Ok(updated > 0)
+    }
+
+    /// Store the metadata read from an audio item's file
+    /// Returns false when the item no longer exists
+    pub fn set_media_metadata(
+        &self,
+        item_id: &str,
+        metadata: &MediaMetadata,
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE clipboard_items SET media_metadata = ?1 WHERE id = ?2",
+                params![metadata.to_json(), item_id],
+            )
+            .context("Failed to set media metadata")?;
+
+        Ok(updated > 0)
+    }
+
+    /// Store the language detected for a text item
+    /// Returns false when the item no longer exists
+    pub fn set_item_language(&self, item_id: &str, language: &str) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE clipboard_items SET language = ?1 WHERE id = ?2",
+                params![language, item_id],
+            )
+            .context("Failed to set item language")?;
+
+        Ok(updated > 0)
+    }
+
+    /// Store the dominant colors extracted from an image item
+    /// Returns false when the item no longer exists
+    pub fn set_item_palette(
+        &self,
+        item_id: &str,
+        colors: &[String],
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE clipboard_items SET palette = ?1 WHERE id = ?2",
+                params![serde_json::to_string(colors)?, item_id],
+            )
+            .context("Failed to set item palette")?;
+
+        Ok(updated > 0)
+    }
+
+    /// Make an item delete itself at `expires_at`, or keep it with None
+    /// Returns false when the item no longer exists
+    pub fn set_item_expiry(
+        &self,
+        item_id: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<bool, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let updated = conn
+            .execute(
+                "UPDATE clipboard_items SET expires_at = ?1 WHERE id = ?2",
+                params![expires_at.map(expiry_timestamp), item_id],
+            )
+            .context("Failed to set item expiry")?;
+
+        Ok(updated > 0)
+    }
+
+    /// Delete the items whose expiry is at or before `now`, pinned or not,
+    /// with their image and text files; returns their IDs
+    pub fn delete_expired_items(&self, now: DateTime<Utc>) -> Result<Vec<String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let expired = "expires_at IS NOT NULL AND expires_at <= ?1";
+        let now = expiry_timestamp(now);
+        let ids = conn
+            .prepare_cached(&format!("SELECT id FROM clipboard_items WHERE {}", expired))
+            .and_then(|mut stmt| {
+                stmt.query_map(params![now], |row| row.get::<_, String>(0))?
+                    .collect::<SqliteResult<Vec<_>>>()
+            })
+            .context("Failed to query expired items")?;
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+
+        let text_paths = spilled_text_paths(&conn, expired, params![now])?;
+        let image_paths = self.image_file_paths(&conn, expired, params![now])?;
+        conn.execute(
+            &format!("DELETE FROM clipboard_items WHERE {}", expired),
+            params![now],
+        )
+        .context("Failed to delete expired items")?;
+        remove_files(&text_paths);
+        remove_files(&image_paths);
+
+        Ok(ids)
+    }
+
+    // ==================== DUPLICATES ====================
+
+    /// Groups of items with the same content (`Exact`) or a similar image (`Images`)
+    /// Items in passcode-locked pinboards are left out; groups list the newest item first
+    pub fn find_duplicates(
+        &self,
+        kind: DuplicateKind,
+    ) -> Result<Vec<DuplicateGroup>, ClipsterError> {
+        let items = self.duplicate_candidates(kind)?;
+
+        let groups = match kind {
+            DuplicateKind::Exact => {
+                let keys: Vec<(String, u64)> = items
+                    .iter()
+                    .filter_map(|item| Some((item.id.clone(), duplicates::exact_key(item)?)))
+                    .collect();
+                duplicates::group_exact(&keys)
+            }
+            DuplicateKind::Images => {
+                let hashes: Vec<(String, u64)> = items
+                    .iter()
+                    .filter_map(|item| {
+                        let path = item.image_path.as_deref()?;
+                        match image::open(path) {
+                            Ok(image) => Some((item.id.clone(), duplicates::dhash(&image))),
+                            Err(e) => {
+                                eprintln!("[database] Failed to decode {}: {}", path, e);
+                                None
+                            }
+                        }
+                    })
+                    .collect();
+                duplicates::group_similar(&hashes, DHASH_MAX_DISTANCE)
+            }
+        };
+
+        let newest_ids: Vec<String> = groups.iter().map(|group| group[0].clone()).collect();
+        let mut thumbnails = self.get_thumbnails(&newest_ids, &[])?;
+        let by_id: HashMap<&str, &ClipboardItem> =
+            items.iter().map(|item| (item.id.as_str(), item)).collect();
+
+        Ok(groups
+            .into_iter()
+            .map(|item_ids| DuplicateGroup {
+                preview: preview::preview_item(by_id[item_ids[0].as_str()], DUPLICATE_PREVIEW_LEN),
+                thumbnail_base64: thumbnails.remove(&item_ids[0]),
+                item_ids,
+            })
+            .collect())
+    }
+
+    /// Items compared by `find_duplicates`, newest first, with their full text
+    fn duplicate_candidates(
+        &self,
+        kind: DuplicateKind,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let type_filter = match kind {
+            DuplicateKind::Exact => "1",
+            DuplicateKind::Images => "content_type = 'image' AND image_path IS NOT NULL",
+        };
+
+        let conn = self.conn.lock()?;
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT id, content_type, content_text, NULL AS thumbnail_base64, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
+                 FROM clipboard_items
+                 WHERE {} AND {}
+                 ORDER BY created_at DESC",
+                type_filter,
+                visible_item(None)
+            ))
+            .context("Failed to prepare duplicates query")?;
+
+        let items = stmt
+            .query_map([], |row| self.item_from_row(row))
+            .context("Failed to query items")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to collect items")?;
+        drop(stmt);
+        drop(conn);
+
+        Ok(items.into_iter().map(load_spilled_text).collect())
+    }
+
+    /// Merge a group of duplicates into `keep_id`, in one transaction
+    /// The kept item takes over a pinboard (if it has none), favorite status and
+    /// the earliest first copy and latest copy times and the use counts of the
+    /// removed items, which are deleted together with their stored files
+    /// Returns the number of items removed (IDs already gone are skipped)
+    pub fn merge_duplicate_group(
+        &self,
+        keep_id: &str,
+        remove_ids: &[String],
+    ) -> Result<usize, ClipsterError> {
+        if remove_ids.iter().any(|id| id == keep_id) {
+            return Err(ClipsterError::Validation(
+                "The kept item can't also be removed".to_string(),
+            ));
+        }
+
+        let select = "SELECT id, content_type, NULL AS content_text, NULL AS thumbnail_base64,
+                             image_path, source_app, source_app_icon, created_at, pinboard_id,
+                             is_favorite, auto_pinboard_id, text_path, NULL AS content_compressed,
+                             canonical_url, source_window_title, source_pasteboard,
+                             first_copied_at, thumbnail_is_favicon, files_summary, use_count,
+                             is_directory, original_width, original_height, downscale_skipped,
+                             title, media_metadata, content_subtype, expires_at, language, palette
+                      FROM clipboard_items
+                      WHERE id = ?1";
+
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        let mut kept = tx
+            .query_row(select, params![keep_id], |row| self.item_from_row(row))
+            .optional()
+            .context("Failed to read kept item")?
+            .ok_or_else(|| ClipsterError::NotFound("Item not found".to_string()))?;
+
+        let mut removed_files = Vec::new();
+        let mut removed = 0;
+        for id in remove_ids {
+            let Some(duplicate) = tx
+                .query_row(select, params![id], |row| self.item_from_row(row))
+                .optional()
+                .context("Failed to read duplicate item")?
+            else {
+                continue;
+            };
+
+            if kept.pinboard_id.is_none() {
+                kept.pinboard_id = duplicate.pinboard_id;
+                kept.auto_pinboard_id = duplicate.auto_pinboard_id;
+            }
+            kept.is_favorite |= duplicate.is_favorite;
+            kept.created_at = kept.created_at.max(duplicate.created_at);
+            kept.first_copied_at = kept.first_copied_at.min(duplicate.first_copied_at);
+            kept.use_count += duplicate.use_count;
+
+            // Duplicates may share a stored file with the kept item
+            removed_files.extend(
+                [duplicate.image_path, duplicate.text_path]
+                    .into_iter()
+                    .flatten()
+                    .filter(|path| {
+                        Some(path) != kept.image_path.as_ref()
+                            && Some(path) != kept.text_path.as_ref()
+                    }),
+            );
+
+            tx.execute("DELETE FROM clipboard_items WHERE id = ?1", params![id])
+                .context("Failed to delete duplicate item")?;
+            removed += 1;
+        }
+
+        tx.execute(
+            "UPDATE clipboard_items
+             SET pinboard_id = ?1, auto_pinboard_id = ?2, is_favorite = ?3, created_at = ?4,
+                 first_copied_at = ?5, use_count = ?6
+             WHERE id = ?7",
+            params![
+                kept.pinboard_id,
+                kept.auto_pinboard_id,
+                kept.is_favorite as i32,
+                kept.created_at.to_rfc3339(),
+                kept.first_copied_at.to_rfc3339(),
+                kept.use_count,
+                kept.id,
+            ],
+        )
+        .context("Failed to update kept item")?;
+
+        tx.commit().context("Failed to commit transaction")?;
+        drop(conn);
+
+        remove_files(&removed_files);
+        Ok(removed)
+    }
+
+    // ==================== RECLASSIFY ====================
+
+    /// Items whose content type the current detectors would set differently
+    /// Spilled text keeps its type: only a preview is in the database, and
+    /// text that large was never a link
+    pub fn find_reclassifications(&self) -> Result<Vec<Reclassification>, ClipsterError> {
+        let tracking_params = self.get_url_tracking_params()?;
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, content_type, content_text, content_compressed
+                 FROM clipboard_items
+                 WHERE content_type != 'image' AND text_path IS NULL
+                 ORDER BY created_at DESC",
+            )
+            .context("Failed to prepare reclassify query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, ContentType>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<Vec<u8>>>(3)?,
+                ))
+            })
+            .context("Failed to query items")?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            let (id, from, content_text, compressed) = row.context("Failed to read item")?;
+            let text = match compressed {
+                Some(data) => match compression::decompress_text(&data) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        eprintln!("[reclassify] {} for item {}", e, id);
+                        continue;
+                    }
+                },
+                None => match content_text {
+                    Some(text) => text,
+                    None => continue,
+                },
+            };
+
+            let to = from.redetect(&text);
+            if to != from {
+                let canonical_url =
+                    (to == ContentType::Link).then(|| canonicalize_url(&text, &tracking_params));
+                changes.push(Reclassification {
+                    id,
+                    from,
+                    to,
+                    canonical_url,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Write content type changes in transactions of `RECLASSIFY_BATCH_SIZE`
+    /// `on_batch` gets the changes of each committed batch; items whose type
+    /// changed since they were found are skipped. Returns the number updated
+    pub fn apply_reclassifications(
+        &self,
+        changes: &[Reclassification],
+        mut on_batch: impl FnMut(&[Reclassification]),
+    ) -> Result<usize, ClipsterError> {
+        let mut updated = 0;
+        for batch in changes.chunks(RECLASSIFY_BATCH_SIZE) {
+            let mut applied = Vec::with_capacity(batch.len());
+            {
+                let mut conn = self.conn.lock()?;
+                let tx = conn.transaction().context("Failed to start transaction")?;
+                for change in batch {
+                    let rows = tx
+                        .execute(
+                            "UPDATE clipboard_items SET content_type = ?1, canonical_url = ?2
+                             WHERE id = ?3 AND content_type = ?4",
+                            params![change.to, change.canonical_url, change.id, change.from],
+                        )
+                        .context("Failed to update content type")?;
+                    if rows > 0 {
+                        applied.push(change.clone());
+                    }
+                }
+                tx.commit().context("Failed to commit transaction")?;
+            }
+
+            updated += applied.len();
+            on_batch(&applied);
+        }
+        Ok(updated)
+    }
+
+    /// Items whose stored language differs from what detection gives now:
+    /// text items are detected again, other items lose their language
+    pub fn find_language_changes(&self) -> Result<Vec<LanguageChange>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, content_type, content_text, content_compressed, language
+                 FROM clipboard_items
+                 WHERE content_type = 'text' OR language IS NOT NULL
+                 ORDER BY created_at DESC",
+            )
+            .context("Failed to prepare language query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, ContentType>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<Vec<u8>>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .context("Failed to query items")?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            let (id, content_type, content_text, compressed, from) =
+                row.context("Failed to read item")?;
+            let to = if content_type != ContentType::Text {
+                None
+            } else {
+                // Spilled text is detected on its preview, which is long enough
+                let text = match compressed {
+                    Some(data) => match compression::decompress_text(&data) {
+                        Ok(text) => Some(text),
+                        Err(e) => {
+                            eprintln!("[reclassify] {} for item {}", e, id);
+                            continue;
+                        }
+                    },
+                    None => content_text,
+                };
+                text.as_deref().and_then(language::detect)
+            };
+            if to != from {
+                changes.push(LanguageChange { id, from, to });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Write language changes in transactions of `RECLASSIFY_BATCH_SIZE`
+    /// Like `apply_reclassifications`, items whose language changed since
+    /// they were found are skipped. Returns the number updated
+    pub fn apply_language_changes(
+        &self,
+        changes: &[LanguageChange],
+        mut on_batch: impl FnMut(&[LanguageChange]),
+    ) -> Result<usize, ClipsterError> {
+        let mut updated = 0;
+        for batch in changes.chunks(RECLASSIFY_BATCH_SIZE) {
+            let mut applied = Vec::with_capacity(batch.len());
+            {
+                let mut conn = self.conn.lock()?;
+                let tx = conn.transaction().context("Failed to start transaction")?;
+                for change in batch {
+                    let rows = tx
+                        .execute(
+                            "UPDATE clipboard_items SET language = ?1
+                             WHERE id = ?2 AND language IS ?3",
+                            params![change.to, change.id, change.from],
+                        )
+                        .context("Failed to update language")?;
+                    if rows > 0 {
+                        applied.push(change.clone());
+                    }
+                }
+                tx.commit().context("Failed to commit transaction")?;
+            }
+
+            updated += applied.len();
+            on_batch(&applied);
+        }
+        Ok(updated)
+    }
+
+    // ==================== REINDEX ====================
+
+    /// Links whose `canonical_url` differs from the one the current tracking
+    /// parameters give, and other items that still have one
+    pub fn find_canonical_url_updates(&self) -> Result<Vec<DerivedUpdate>, ClipsterError> {
+        let tracking_params = self.get_url_tracking_params()?;
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, content_type, content_text, content_compressed, canonical_url
+                 FROM clipboard_items
+                 WHERE content_type = 'link' OR canonical_url IS NOT NULL",
+            )
+            .context("Failed to prepare canonical URL query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, ContentType>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<Vec<u8>>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })
+            .context("Failed to query items")?;
+
+        let mut updates = Vec::new();
+        for row in rows {
+            let (id, content_type, content_text, compressed, current) =
+                row.context("Failed to read item")?;
+            let canonical = if content_type == ContentType::Link {
+                let text = match compressed {
+                    Some(data) => match compression::decompress_text(&data) {
+                        Ok(text) => Some(text),
+                        Err(e) => {
+                            eprintln!("[reindex] {} for item {}", e, id);
+                            continue;
+                        }
+                    },
+                    None => content_text,
+                };
+                text.map(|text| canonicalize_url(&text, &tracking_params))
+            } else {
+                None
+            };
+            if canonical != current {
+                updates.push(DerivedUpdate {
+                    id,
+                    value: canonical,
+                });
+            }
+        }
+        Ok(updates)
+    }
+
+    /// Multi-file items whose `files_summary` differs from their file list's
+    pub fn find_files_summary_updates(
+        &self,
+        home: Option<&Path>,
+    ) -> Result<Vec<DerivedUpdate>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, content_text, files_summary FROM clipboard_items
+                 WHERE content_type IN ('files', 'audio', 'documents') AND is_directory = 0",
+            )
+            .context("Failed to prepare files summary query")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })
+            .context("Failed to query items")?;
+
+        let mut updates = Vec::new();
+        for row in rows {
+            let (id, content_text, current) = row.context("Failed to read item")?;
+            let Some(paths) =
+                content_text.and_then(|json| serde_json::from_str::<Vec<String>>(&json).ok())
+            else {
+                continue;
+            };
+            let summary = file_group::summarize(&paths, home);
+            if summary != current {
+                updates.push(DerivedUpdate { id, value: summary });
+            }
+        }
+        Ok(updates)
+    }
+
+    /// ID and image path of image items without a thumbnail
+    pub fn get_items_missing_thumbnail(&self) -> Result<Vec<(String, String)>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, image_path FROM clipboard_items
+                 WHERE content_type = 'image' AND thumbnail_base64 IS NULL
+                   AND image_path IS NOT NULL",
+            )
+            .context("Failed to prepare query")?;
+        let items = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to query items")?
+            .collect::<SqliteResult<Vec<(String, String)>>>()
+            .context("Failed to read items")?;
+
+        Ok(items
+            .into_iter()
+            .map(|(id, path)| (id, resolve_image_path(self.images_dir.as_deref(), path)))
+            .collect())
+    }
+
+    /// Write one batch of derived values in a transaction
+    /// Rows that already hold the value are left alone; returns the number changed
+    pub fn update_derived_column(
+        &self,
+        column: DerivedColumn,
+        updates: &[DerivedUpdate],
+    ) -> Result<usize, ClipsterError> {
+        let sql = format!(
+            "UPDATE clipboard_items SET {0} = ?1 WHERE id = ?2 AND {0} IS NOT ?1",
+            column.name()
+        );
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        let mut updated = 0;
+        for update in updates {
+            updated += tx
+                .execute(&sql, params![update.value, update.id])
+                .context("Failed to update derived column")?;
+        }
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(updated)
+    }
+
+    /// Drop cached icons of apps for which `exists` is false
+    /// Returns the number of icons removed
+    pub fn prune_app_icons(&self, exists: impl Fn(&str) -> bool) -> Result<usize, ClipsterError> {
+        let mut conn = self.conn.lock()?;
+
+        let paths = conn
+            .prepare("SELECT app_path FROM app_icons")
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get::<_, String>(0))?
+                    .collect::<SqliteResult<Vec<String>>>()
+            })
+            .context("Failed to read app icons")?;
+
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        let mut removed = 0;
+        for path in paths.iter().filter(|path| !exists(path)) {
+            removed += tx
+                .execute("DELETE FROM app_icons WHERE app_path = ?1", params![path])
+                .context("Failed to delete app icon")?;
+        }
+        tx.commit().context("Failed to commit transaction")?;
+        Ok(removed)
+    }
+
+    // ==================== SETTINGS ====================
+
+    /// Get a setting value
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let result: SqliteResult<String> = conn
+            .prepare_cached("SELECT value FROM settings WHERE key = ?1")
+            .and_then(|mut stmt| stmt.query_row(params![key], |row| row.get(0)));
+
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e).context("Failed to get setting"),
+        }
+    }
+
+    /// Set a setting value
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        conn.prepare_cached("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+            .and_then(|mut stmt| stmt.execute(params![key, value]))
+            .context("Failed to set setting")?;
+
+        Ok(())
+    }
+
+    /// Get history limit setting
+    pub fn get_history_limit(&self) -> Result<usize, ClipsterError> {
+        let limit_str = self.get_setting("history_limit")?.unwrap_or_else(|| "500".to_string());
+        limit_str
+            .parse()
+            .map_err(|_| ClipsterError::Validation("Invalid history_limit value".to_string()))
+    }
+
+    /// Scope of "move to top" deduplication (`dedupe_scope` setting, global by default)
+    pub fn get_dedupe_scope(&self) -> Result<DedupeScope, ClipsterError> {
+        Ok(self
+            .get_setting("dedupe_scope")?
+            .and_then(|value| DedupeScope::parse(&value))
+            .unwrap_or_default())
+    }
+
+    /// Every stored setting, by key
+    pub fn get_all_settings(&self) -> Result<BTreeMap<String, String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings")
+            .context("Failed to prepare query")?;
+        let settings = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("Failed to query settings")?
+            .collect::<SqliteResult<BTreeMap<_, _>>>()
+            .context("Failed to read settings")?;
+
+        Ok(settings)
+    }
+
+    /// Write several settings in one transaction
+    /// Nothing is written when any known setting has an invalid value
+    pub fn set_settings(&self, settings: &BTreeMap<String, String>) -> Result<(), ClipsterError> {
+        if let Some((key, value)) = settings
+            .iter()
+            .find(|(key, value)| !Self::is_valid_setting(key, value))
+        {
+            return Err(ClipsterError::Validation(format!(
+                "Invalid value for {}: {}",
+                key, value
+            )));
+        }
+
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+        {
+            let mut stmt = tx
+                .prepare_cached("INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)")
+                .context("Failed to prepare query")?;
+            for (key, value) in settings {
+                stmt.execute(params![key, value])
+                    .context("Failed to set setting")?;
+            }
+        }
+        tx.commit().context("Failed to commit settings")?;
+
+        Ok(())
+    }
+
+    /// Write `DEFAULT_SETTINGS` back; other settings are left as they are
+    pub fn reset_settings_to_defaults(&self) -> Result<(), ClipsterError> {
+        let defaults = DEFAULT_SETTINGS
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self.set_settings(&defaults)
+    }
+
+    /// Keys of known settings whose stored value cannot be parsed
+    pub fn invalid_settings(&self) -> Result<Vec<String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare("SELECT key, value FROM settings ORDER BY key")
+            .context("Failed to prepare query")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .context("Failed to query settings")?;
+
+        let mut invalid = Vec::new();
+        for row in rows {
+            let (key, value) = row.context("Failed to read setting")?;
+            if !Self::is_valid_setting(&key, &value) {
+                invalid.push(key);
+            }
+        }
+
+        Ok(invalid)
+    }
+
+    /// Check a setting value against the format its readers expect
+    /// Unknown keys are always considered valid
+    fn is_valid_setting(key: &str, value: &str) -> bool {
+        match key {
+            "start_hidden"
+            | "show_menu_bar_icon"
+            | "mirror_favorites_to_pinboard"
+            | "strip_image_metadata"
+            | "compact_png_files"
+            | "compress_large_text"
+            | "spotlight_indexing"
+            | "capture_window_titles"
+            | "recent_items_os_menu"
+            | "capture_find_pasteboard"
+            | "capture_paused"
+            | "exclude_from_os_history"
+            | "debug_capture_logging"
+            | "require_destructive_confirmation"
+            | "merge_rapid_text_captures"
+            | "first_run_completed" => value == "true" || value == "false",
+            "history_limit" | "quick_cycle_items" | "large_text_threshold_bytes" => {
+                value.parse::<usize>().is_ok()
+            }
+            "pinboard_relock_minutes" => value.parse::<i64>().is_ok(),
+            "min_free_space_mb" | "backup_interval_hours" => value.parse::<u64>().is_ok(),
+            "quicklook_timeout_ms" | "merge_window_ms" => {
+                value.parse::<u64>().is_ok_and(|ms| ms > 0)
+            }
+            "theme" => matches!(value, "light" | "dark" | "system"),
+            "normalize_text" => matches!(value, "off" | "line_endings" | "full"),
+            "thumbnail_format" => file_storage::ExportFormat::parse(value).is_some(),
+            "app_default_pinboards" | "pinboard_shortcuts" => {
+                serde_json::from_str::<HashMap<String, String>>(value).is_ok()
+            }
+            "app_rate_limits" => serde_json::from_str::<HashMap<String, u32>>(value).is_ok(),
+            "url_tracking_params" => serde_json::from_str::<Vec<String>>(value).is_ok(),
+            "panel_placement" => serde_json::from_str::<Placement>(value)
+                .is_ok_and(|placement| placement.validate().is_ok()),
+            "monitor_placements" => serde_json::from_str::<MonitorPlacements>(value).is_ok(),
+            "auto_clear_schedule" => AutoClearSchedule::parse(value).is_some(),
+            "dedupe_scope" => DedupeScope::parse(value).is_some(),
+            "max_image_dimension" => value.parse::<u32>().is_ok(),
+            "max_image_capture_mb" => value.parse::<u64>().is_ok(),
+            "auto_clear_last_run" => DateTime::parse_from_rfc3339(value).is_ok(),
+            "view_state" => Self::parse_view_state(value).is_ok(),
+            _ => true,
+        }
+    }
+
+    // ==================== COUNTERS ====================
+
+    /// Add one to the counter `name`
+    pub fn increment_counter(&self, name: &str) -> Result<(), ClipsterError> {
+        let conn = self.conn.lock()?;
+        counters::increment(&conn, name, 1)
+    }
+
+    /// Lifetime usage counters by name
+    pub fn get_counters(&self) -> Result<BTreeMap<String, u64>, ClipsterError> {
+        let conn = self.conn.lock()?;
+        counters::read_all(&conn)
+    }
+
+    // ==================== IMAGE PATHS ====================
+
+    /// Item from a row, with its `image_path` resolved to an absolute path
+    fn item_from_row(&self, row: &rusqlite::Row) -> SqliteResult<ClipboardItem> {
+        let mut item = ClipboardItem::from_row(row)?;
+        item.image_path = item
+            .image_path
+            .map(|path| resolve_image_path(self.images_dir.as_deref(), path));
+        Ok(item)
+    }
+
+    /// Stored form of an image path: images in the images directory are
+    /// stored by file name, so the data directory can move
+    fn stored_image_path(&self, path: &str) -> String {
+        let path = Path::new(path);
+        match (&self.images_dir, path.file_name()) {
+            (Some(dir), Some(name)) if path.parent() == Some(dir.as_path()) => {
+                name.to_string_lossy().to_string()
+            }
+            _ => path.to_string_lossy().to_string(),
+        }
+    }
+
+    /// Image files of the items matching `filter`, collected before they are deleted
+    fn image_file_paths<P: rusqlite::Params>(
+        &self,
+        conn: &Connection,
+        filter: &str,
+        params: P,
+    ) -> Result<Vec<String>, ClipsterError> {
+        let paths = stored_file_paths(conn, "image_path", filter, params)?;
+        Ok(paths
+            .into_iter()
+            .map(|path| resolve_image_path(self.images_dir.as_deref(), path))
+            .collect())
+    }
+
+    /// Rewrite absolute image paths to file names where the file is in the
+    /// current images directory, so items saved before paths were stored
+    /// relative (or restored under another home directory) load again
+    /// Returns the number of items rewritten
+    pub fn relocate_image_paths(&self) -> Result<usize, ClipsterError> {
+        let Some(images_dir) = &self.images_dir else {
+            return Ok(0);
+        };
+        let mut conn = self.conn.lock()?;
+        let tx = conn.transaction().context("Failed to start transaction")?;
+
+        let rows = {
+            let mut stmt = tx
+                .prepare("SELECT id, image_path FROM clipboard_items WHERE image_path IS NOT NULL")
+                .context("Failed to prepare image path query")?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+                })
+                .context("Failed to query image paths")?
+                .collect::<SqliteResult<Vec<_>>>()
+                .context("Failed to read image paths")?;
+            rows
+        };
+
+        let mut relocated = 0;
+        for (id, stored) in rows {
+            let path = Path::new(&stored);
+            let Some(name) = path.file_name().filter(|_| path.is_absolute()) else {
+                continue;
+            };
+            if images_dir.join(name).is_file() {
+                relocated += tx
+                    .execute(
+                        "UPDATE clipboard_items SET image_path = ?1 WHERE id = ?2",
+                        params![name.to_string_lossy(), id],
+                    )
+                    .context("Failed to relocate image path")?;
+            }
+        }
+        tx.commit().context("Failed to commit image paths")?;
+
+        Ok(relocated)
+    }
+
+    fn relocate_image_paths_on_open(&self) {
+        match self.relocate_image_paths() {
+            Ok(0) => {}
+            Ok(count) => eprintln!("[database] Relocated {} image paths", count),
+            Err(e) => eprintln!("[database] Failed to relocate image paths: {}", e),
+        }
+    }
+
+    // ==================== HISTORY ARCHIVE ====================
+
+    /// Directory image files are stored in (None for in-memory databases)
+    pub fn images_dir(&self) -> Option<&Path> {
+        self.images_dir.as_deref()
+    }
+
+    /// Up to `limit` history items created before `cutoff` that are neither
+    /// favorited nor in a pinboard, oldest first, with thumbnails and any
+    /// spilled text loaded
+    pub fn get_archivable_items(
+        &self,
+        cutoff: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<Vec<ClipboardItem>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT id, content_type, content_text, thumbnail_base64, image_path,
+                        source_app, source_app_icon, created_at, pinboard_id, is_favorite, auto_pinboard_id,
+                        text_path, content_compressed, canonical_url, source_window_title,
+                        source_pasteboard, first_copied_at, thumbnail_is_favicon, files_summary,
+                        use_count, is_directory, original_width, original_height,
+                        downscale_skipped, title, media_metadata, content_subtype, expires_at,
+                        language, palette
+                 FROM clipboard_items
+                 WHERE is_favorite = 0 AND pinboard_id IS NULL AND created_at < ?1
+                 ORDER BY created_at ASC
+                 LIMIT ?2",
+            )
+            .context("Failed to prepare archivable items query")?;
+
+        let items = stmt
+            .query_map(params![cutoff.to_rfc3339(), limit as i64], |row| {
+                self.item_from_row(row)
+            })
+            .context("Failed to query archivable items")?
+            .collect::<SqliteResult<Vec<_>>>()
+            .context("Failed to collect archivable items")?;
+
+        Ok(items.into_iter().map(load_spilled_text).collect())
+    }
+
+    /// Rebuild the database file, giving the space of deleted rows back to
+    /// the file system
+    /// In WAL mode the rebuilt pages land in the log first, so it is
+    /// checkpointed and truncated right away
+    pub fn vacuum(&self) -> Result<(), ClipsterError> {
+        self.conn
+            .lock()?
+            .execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")
+            .context("Failed to vacuum database")
+    }
+
+    // ==================== HEALTH ====================
+
+    /// Item count and space saved by text compression
+    pub fn get_stats(&self) -> Result<StorageStats, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let total_items: i64 = conn
+            .query_row("SELECT COUNT(*) FROM clipboard_items", [], |row| row.get(0))
+            .context("Failed to count items")?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT content_compressed FROM clipboard_items
+                 WHERE content_compressed IS NOT NULL",
+            )
+            .context("Failed to prepare stats query")?;
+        let blobs = stmt
+            .query_map([], |row| row.get::<_, Vec<u8>>(0))
+            .context("Failed to query compressed items")?;
+
+        let mut stats = StorageStats {
+            total_items: total_items as usize,
+            compressed_items: 0,
+            uncompressed_bytes: 0,
+            compressed_bytes: 0,
+            saved_bytes: 0,
+            counters: counters::read_all(&conn)?,
+            languages: BTreeMap::new(),
+        };
+        for blob in blobs {
+            let blob = blob.context("Failed to read compressed item")?;
+            let compressed = blob.len() as u64;
+            stats.compressed_items += 1;
+            stats.compressed_bytes += compressed;
+            stats.uncompressed_bytes += compression::original_size(&blob).unwrap_or(compressed);
+        }
+        stats.saved_bytes = stats.uncompressed_bytes.saturating_sub(stats.compressed_bytes);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT language, COUNT(*) FROM clipboard_items
+                 WHERE language IS NOT NULL
+                 GROUP BY language",
+            )
+            .context("Failed to prepare language stats query")?;
+        stats.languages = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as usize))
+            })
+            .context("Failed to count items by language")?
+            .collect::<SqliteResult<_>>()
+            .context("Failed to read language counts")?;
+
+        Ok(stats)
+    }
+
+    /// Run `PRAGMA integrity_check` ("ok" when the database is healthy)
+    pub fn integrity_check(&self) -> Result<String, ClipsterError> {
+        let conn = self.conn.lock()?;
+        run_integrity_check(&conn)
+    }
+
+    /// Copy the live database to `path` with the SQLite online backup API
+    ///
+    /// Pages are copied in steps, calling `on_progress` after each one. The
+    /// copy reads through a read-only connection of its own whose read
+    /// transaction spans every step: the snapshot never changes under it, and
+    /// in WAL mode captures keep committing meanwhile. The copy is written next to
+    /// `path`, checked with `PRAGMA integrity_check` and only then renamed into
+    /// place, so a failed backup never replaces a good one.
+    pub fn backup_to<F>(
+        &self,
+        path: &Path,
+        mut on_progress: F,
+    ) -> Result<BackupReport, ClipsterError>
+    where
+        F: FnMut(BackupProgress),
+    {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create backup directory")?;
+        }
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+        let _ = fs::remove_file(&tmp_path);
+
+        let total_pages = {
+            let source =
+                Connection::open_with_flags(self.file_path()?, OpenFlags::SQLITE_OPEN_READ_ONLY)
+                    .context("Failed to open database for backup")?;
+            // Take the snapshot up front; steps reuse an open read transaction
+            source
+                .execute_batch("BEGIN")
+                .context("Failed to start backup")?;
+            source
+                .query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+                .context("Failed to start backup")?;
+
+            let mut dest = Connection::open(&tmp_path).context("Failed to create backup file")?;
+            let backup = Backup::new(&source, &mut dest).context("Failed to start backup")?;
+
+            loop {
+                let step = backup
+                    .step(BACKUP_PAGES_PER_STEP)
+                    .context("Backup step failed")?;
+                let progress = backup.progress();
+                on_progress(BackupProgress {
+                    copied_pages: progress.pagecount - progress.remaining,
+                    total_pages: progress.pagecount,
+                });
+                match step {
+                    StepResult::Done => break progress.pagecount,
+                    StepResult::More => {}
+                    _ => std::thread::sleep(BACKUP_RETRY_DELAY),
+                }
+            }
+        };
+
+        let verified = Connection::open(&tmp_path)
+            .context("Failed to open backup")
+            .and_then(|conn| run_integrity_check(&conn));
+        match verified {
+            Ok(result) if result == "ok" => {}
+            Ok(result) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(ClipsterError::Database(format!(
+                    "Backup failed integrity check: {}",
+                    result
+                )));
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        }
+
+        fs::rename(&tmp_path, path).context("Failed to save backup")?;
+        let size_bytes = fs::metadata(path)
+            .map(|m| m.len())
+            .context("Failed to read backup file")?;
+
+        Ok(BackupReport {
+            path: path.to_string_lossy().to_string(),
+            size_bytes,
+            total_pages,
+        })
+    }
+
+    /// Size of the database file in bytes
+    pub fn file_size(&self) -> Result<u64, ClipsterError> {
+        let path = self.file_path()?;
+
+        std::fs::metadata(path)
+            .map(|m| m.len())
+            .context("Failed to read database file")
+    }
+
+    /// Size of the write-ahead log in bytes (0 if there is none)
+    pub fn wal_size(&self) -> Result<u64, ClipsterError> {
+        let mut wal_path = self.file_path()?.into_os_string();
+        wal_path.push("-wal");
+
+        match std::fs::metadata(&wal_path) {
+            Ok(meta) => Ok(meta.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e).context("Failed to read WAL file"),
+        }
+    }
+
+    /// Stored image paths of all items
+    pub fn get_image_paths(&self) -> Result<Vec<String>, ClipsterError> {
+        let conn = self.conn.lock()?;
+
+        let mut stmt = conn
+            .prepare("SELECT image_path FROM clipboard_items WHERE image_path IS NOT NULL")
+            .context("Failed to prepare query")?;
+        let paths = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .context("Failed to query image paths")?
+            .collect::<SqliteResult<Vec<String>>>()
+            .context("Failed to read image paths")?;
+
+        Ok(paths
+            .into_iter()
+            .map(|path| resolve_image_path(self.images_dir.as_deref(), path))
+            .collect())
+    }
+}
+
+/// Columns written for an item's text: `(content_text, content_compressed)`
+/// Large text items are stored as a zstd blob plus a preview when compression is on
+fn stored_content(
+    item: &ClipboardItem,
+    compress: bool,
+) -> Result<(Option<&str>, Option<Vec<u8>>), ClipsterError> {
+    let text = item.content_text.as_deref();
+    match text {
+        Some(text)
+            if compress
+                && item.content_type == ContentType::Text
+                && item.text_path.is_none()
+                && compression::should_compress(text, compression::COMPRESSION_THRESHOLD) =>
+        {
+            let preview = file_storage::text_preview(text, file_storage::TEXT_PREVIEW_BYTES);
+            Ok((
+                Some(preview),
+                Some(compression::compress_text(text).map_err(ClipsterError::Io)?),
+            ))
+        }
+        _ => Ok((text, None)),
+    }
+}
+
+/// Dedupe key of a text: hex SHA-256
+/// Rows only keep a preview of spilled and compressed text, so the full text
+/// can't be compared in SQL
+fn content_hash(text: &str) -> String {
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// `content_hash` of an item's full text; spilled text is read back from its file
+fn item_content_hash(item: &ClipboardItem) -> Option<String> {
+    match &item.text_path {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(text) => Some(content_hash(&text)),
+            Err(e) => {
+                eprintln!("[database] Failed to hash spilled text {}: {}", path, e);
+                None
+            }
+        },
+        None => item.content_text.as_deref().map(content_hash),
+    }
+}
+
+/// Condition leaving out items of passcode-locked pinboards, filed there or
+/// auto-filed from history (`auto_pinboard_id`)
+/// With `unlocked_param`, boards in the JSON array bound to it count as unlocked
+fn visible_item(unlocked_param: Option<usize>) -> String {
+    let locked = match unlocked_param {
+        Some(param) => format!(
+            "SELECT id FROM pinboards WHERE lock_hash IS NOT NULL
+             AND id NOT IN (SELECT value FROM json_each(?{}))",
+            param
+        ),
+        None => "SELECT id FROM pinboards WHERE lock_hash IS NOT NULL".to_string(),
+    };
+    format!(
+        "(COALESCE(pinboard_id, auto_pinboard_id) IS NULL
+          OR COALESCE(pinboard_id, auto_pinboard_id) NOT IN ({}))",
+        locked
+    )
+}
+
+/// Condition matching rows that hold the text bound to `?text_param`, whose
+/// `content_hash` is bound to `?hash_param`
+/// Rows stored before content hashes match on their text while it is inline
+fn same_content(hash_param: usize, text_param: usize) -> String {
+    format!(
+        "(content_hash = ?{} OR (content_hash IS NULL AND text_path IS NULL
+          AND content_compressed IS NULL AND content_text = ?{}))",
+        hash_param, text_param
+    )
+}
+
+/// `ReplacedItem` from the columns selected by the move-to-top lookups
+fn replaced_item_from_row(row: &rusqlite::Row) -> SqliteResult<ReplacedItem> {
+    let first_copied_at: String = row.get(4)?;
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        DateTime::parse_from_rfc3339(&first_copied_at)
+            .map(|dt| dt.with_timezone(&Utc))
+            .ok(),
+    ))
+}
+
+/// Thumbnail column of item list queries
+/// Thumbnails make up most of a list's IPC payload, so lists select NULL instead
+/// unless asked and the frontend loads visible ones with `get_thumbnails`
+fn thumbnail_column(include_thumbnails: bool) -> &'static str {
+    if include_thumbnails {
+        "thumbnail_base64"
+    } else {
+        "NULL AS thumbnail_base64"
+    }
+}
+
+/// Replace the preview of a spilled text item with the full text from disk
+/// Falls back to the stored preview if the file can't be read
+fn load_spilled_text(mut item: ClipboardItem) -> ClipboardItem {
+    if let Some(path) = &item.text_path {
+        match fs::read_to_string(path) {
+            Ok(text) => item.content_text = Some(text),
+            Err(e) => eprintln!("[database] Failed to read spilled text {}: {}", path, e),
+        }
+    }
+    item
+}
+
+/// Run `PRAGMA integrity_check` on a connection
+fn run_integrity_check(conn: &Connection) -> Result<String, ClipsterError> {
+    let mut stmt = conn
+        .prepare("PRAGMA integrity_check")
+        .context("Failed to prepare integrity check")?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .context("Failed to run integrity check")?
+        .collect::<SqliteResult<Vec<String>>>()
+        .context("Failed to read integrity check")?;
+
+    Ok(messages.join("; "))
+}
+
+/// Parent of every pinboard, for the hierarchy checks in `pinboard_tree`
+fn pinboard_parents(conn: &Connection) -> Result<ParentMap, ClipsterError> {
+    let mut stmt = conn
+        .prepare("SELECT id, parent_id FROM pinboards")
+        .context("Failed to prepare query")?;
+
+    let parents = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .context("Failed to query pinboard parents")?
+        .collect::<SqliteResult<ParentMap>>()
+        .context("Failed to read pinboard parents")?;
+
+    Ok(parents)
+}
+
+/// Spill files of the items matching `filter`, collected before they are deleted
+fn spilled_text_paths<P: rusqlite::Params>(
+    conn: &Connection,
+    filter: &str,
+    params: P,
+) -> Result<Vec<String>, ClipsterError> {
+    stored_file_paths(conn, "text_path", filter, params)
+}
+
+fn stored_file_paths<P: rusqlite::Params>(
+    conn: &Connection,
+    column: &str,
+    filter: &str,
+    params: P,
+) -> Result<Vec<String>, ClipsterError> {
+    let sql = format!(
+        "SELECT {column} FROM clipboard_items WHERE {column} IS NOT NULL AND ({filter})"
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .context(&format!("Failed to prepare {} query", column))?;
+
+    let paths = stmt
+        .query_map(params, |row| row.get::<_, String>(0))
+        .context(&format!("Failed to query {}", column))?
+        .collect::<SqliteResult<Vec<_>>>()
+        .context(&format!("Failed to read {}", column))?;
+
+    Ok(paths)
+}
+
+/// Absolute path of a stored `image_path`: file names are in `images_dir`,
+/// absolute paths (legacy rows, in-memory databases) are kept as they are
+fn resolve_image_path(images_dir: Option<&Path>, stored: String) -> String {
+    match images_dir {
+        Some(dir) if Path::new(&stored).is_relative() => {
+            dir.join(stored).to_string_lossy().to_string()
+        }
+        _ => stored,
+    }
+}
+
+/// Stored form of `expires_at`: fixed-width UTC, so that SQL compares the
+/// strings in time order
+fn expiry_timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// Remove files of deleted items (missing files are ignored)
+fn remove_files(paths: &[String]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{pinboard, ContentSubtype};
+    use crate::test_support::{temp_dir, TempDir};
+
+    #[test]
+    fn test_database_creation() {
+        let db = Database::new_in_memory().expect("Failed to create in-memory database");
+        assert!(db.count_items().unwrap() == 0);
+    }
+
+    #[test]
+    fn test_insert_and_get_item() {
+        let db = Database::new_in_memory().unwrap();
+
+        let item = ClipboardItem::new_text("Hello, World!".to_string(), Some("Test".to_string()), None);
+        db.insert_item(&item).unwrap();
+
+        let items = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].content_text, Some("Hello, World!".to_string()));
+    }
+
+    #[test]
+    fn test_image_original_size_round_trip() {
+        let db = Database::new_in_memory().unwrap();
+
+        let mut downscaled = ClipboardItem::new_image(None, "/tmp/a.png".to_string(), None, None);
+        downscaled.original_width = Some(12000);
+        downscaled.original_height = Some(8000);
+        db.insert_item(&downscaled).unwrap();
+        let mut raw = ClipboardItem::new_image(None, "/tmp/b.png".to_string(), None, None);
+        raw.downscale_skipped = true;
+        db.insert_item(&raw).unwrap();
+
+        let stored = db.require_item(&downscaled.id).unwrap();
+        assert_eq!((stored.original_width, stored.original_height), (Some(12000), Some(8000)));
+        assert!(!stored.downscale_skipped);
+        let stored = db.require_item(&raw.id).unwrap();
+        assert_eq!((stored.original_width, stored.original_height), (None, None));
+        assert!(stored.downscale_skipped);
+
+        assert!(Database::is_valid_setting("max_image_dimension", "4096"));
+        assert!(!Database::is_valid_setting("max_image_dimension", "-1"));
+    }
+
+    #[test]
+    fn test_delete_item() {
+        let db = Database::new_in_memory().unwrap();
+
+        let item = ClipboardItem::new_text("To delete".to_string(), None, None);
+        let id = item.id.clone();
+        db.insert_item(&item).unwrap();
+
+        assert_eq!(db.count_items().unwrap(), 1);
+
+        db.delete_item(&id).unwrap();
+        assert_eq!(db.count_items().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_update_thumbnail_favicon_flag() {
+        let db = Database::new_in_memory().unwrap();
+        let link = ClipboardItem::new_link("https://docs.example.com".to_string(), None, None);
+        db.insert_item(&link).unwrap();
+        assert!(!db.require_item(&link.id).unwrap().thumbnail_is_favicon);
+
+        db.update_thumbnail(&link.id, "icon", true).unwrap();
+        let item = db.require_item(&link.id).unwrap();
+        assert_eq!(item.thumbnail_base64.as_deref(), Some("icon"));
+        assert!(item.thumbnail_is_favicon);
+
+        // A later OG image replaces the favicon preview
+        db.update_thumbnail(&link.id, "og", false).unwrap();
+        assert!(!db.require_item(&link.id).unwrap().thumbnail_is_favicon);
+        let history = db.get_items(10, 0, false, HistorySort::default()).unwrap();
+        assert!(!history[0].thumbnail_is_favicon);
+    }
+
+    #[test]
+    fn test_app_icon_cache_fills_missing_icons() {
+        let db = Database::new_in_memory().unwrap();
+        let app_path = r"C:\Program Files\Editor\editor.exe";
+        assert_eq!(db.get_app_icon(app_path).unwrap(), None);
+
+        let waiting = ClipboardItem::new_text("a".to_string(), Some("Editor".to_string()), None);
+        let with_icon = ClipboardItem::new_text(
+            "b".to_string(),
+            Some("Editor".to_string()),
+            Some("own".to_string()),
+        );
+        db.insert_item(&waiting).unwrap();
+        db.insert_item(&with_icon).unwrap();
+
+        db.set_app_icon(app_path, "icon").unwrap();
+        assert_eq!(db.get_app_icon(app_path).unwrap().as_deref(), Some("icon"));
+
+        assert!(db.fill_source_app_icon(&waiting.id, "icon").unwrap());
+        assert!(!db.fill_source_app_icon(&with_icon.id, "icon").unwrap());
+        assert!(!db.fill_source_app_icon("missing", "icon").unwrap());
+        let icon_of = |id: &str| db.require_item(id).unwrap().source_app_icon;
+        assert_eq!(icon_of(&waiting.id).as_deref(), Some("icon"));
+        assert_eq!(icon_of(&with_icon.id).as_deref(), Some("own"));
+
+        // A newer extraction replaces the cached icon
+        db.set_app_icon(app_path, "updated").unwrap();
+        assert_eq!(
+            db.get_app_icon(app_path).unwrap().as_deref(),
+            Some("updated")
+        );
+    }
+
+    #[test]
+    fn test_switch_to_other_profile() {
+        let root = temp_dir("switch");
+        let (personal, work) = (root.join("personal"), root.join("work"));
+
+        let db = Database::open(&personal).unwrap();
+        db.insert_item(&ClipboardItem::new_text("personal".to_string(), None, None))
+            .unwrap();
+        assert_eq!(db.count_items().unwrap(), 1);
+
+        // Each profile has its own history, and the new one is fully migrated
+        db.switch_to(&work).unwrap();
+        assert_eq!(db.count_items().unwrap(), 0);
+        assert!(!db.get_pinboards(false).unwrap().is_empty());
+        assert!(work.join("clipster.db").exists());
+        assert!(db.file_size().unwrap() > 0);
+
+        db.switch_to(&personal).unwrap();
+        assert_eq!(db.count_items().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_image_paths_follow_a_moved_data_dir() {
+        let root = temp_dir("move");
+        let (old_dir, new_dir) = (root.join("alice"), root.join("bob"));
+        let images = old_dir.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+
+        let db = Database::open(&old_dir).unwrap();
+        let image_path = images.join("shot.png");
+        std::fs::write(&image_path, b"png").unwrap();
+        let item =
+            ClipboardItem::new_image(None, image_path.to_string_lossy().to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        let stored: String = db
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT image_path FROM clipboard_items WHERE id = ?1",
+                params![item.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored, "shot.png");
+        assert_eq!(
+            db.get_item(&item.id).unwrap().unwrap().image_path,
+            item.image_path
+        );
+        drop(db);
+
+        std::fs::rename(&old_dir, &new_dir).unwrap();
+        let db = Database::open(&new_dir).unwrap();
+        let moved = db.get_item(&item.id).unwrap().unwrap();
+        let moved_path = PathBuf::from(moved.image_path.unwrap());
+        assert_eq!(moved_path, new_dir.join("images").join("shot.png"));
+        assert!(moved_path.is_file());
+    }
+
+    #[test]
+    fn test_legacy_absolute_image_paths_are_relocated() {
+        let root = temp_dir("legacy");
+        let images = root.join("images");
+        std::fs::create_dir_all(&images).unwrap();
+        std::fs::write(images.join("restored.png"), b"png").unwrap();
+
+        let db = Database::open(&root).unwrap();
+        // Rows written before paths were relative, on a machine with another user
+        let restored = ClipboardItem::new_image(
+            None,
+            "/Users/alice/Library/Application Support/clipster/images/restored.png".to_string(),
+            None,
+            None,
+        );
+        let elsewhere =
+            ClipboardItem::new_image(None, "/tmp/elsewhere.png".to_string(), None, None);
+        db.insert_item(&restored).unwrap();
+        db.insert_item(&elsewhere).unwrap();
+        assert_eq!(
+            db.get_item(&restored.id).unwrap().unwrap().image_path,
+            restored.image_path
+        );
+        drop(db);
+
+        // Repaired on open: only the file that is in the images dir
+        let db = Database::open(&root).unwrap();
+        let path = images.join("restored.png").to_string_lossy().to_string();
+        assert_eq!(
+            db.get_item(&restored.id).unwrap().unwrap().image_path,
+            Some(path.clone())
+        );
+        assert_eq!(
+            db.get_item(&elsewhere.id).unwrap().unwrap().image_path,
+            elsewhere.image_path
+        );
+        assert_eq!(db.relocate_image_paths().unwrap(), 0);
+        assert!(db.get_image_paths().unwrap().contains(&path));
+
+        // Deleting the item removes the file at its resolved path
+        db.clear_history().unwrap();
+        assert!(!images.join("restored.png").exists());
+    }
+
+    #[test]
+    fn test_backup_while_inserting() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let root = temp_dir("backup");
+        let db = Arc::new(Database::open(&root.join("data")).unwrap());
+        for i in 0..500 {
+            let text = format!("seed item {} {}", i, "x".repeat(200));
+            db.insert_item(&ClipboardItem::new_text(text, None, None)).unwrap();
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let writer = {
+            let (db, stop) = (db.clone(), stop.clone());
+            std::thread::spawn(move || {
+                let mut inserted = 0;
+                while !stop.load(Ordering::SeqCst) {
+                    let item = ClipboardItem::new_text(format!("live {}", inserted), None, None);
+                    db.insert_item(&item).unwrap();
+                    inserted += 1;
+                }
+                inserted
+            })
+        };
+
+        let dest = root.join("backup.db");
+        let mut steps = Vec::new();
+        let report = db.backup_to(&dest, |progress| steps.push(progress)).unwrap();
+
+        stop.store(true, Ordering::SeqCst);
+        let inserted = writer.join().unwrap();
+        assert!(inserted > 0);
+
+        // Stepped, ending with every page copied
+        assert!(steps.len() > 1);
+        let last = steps.last().unwrap();
+        assert_eq!(last.copied_pages, last.total_pages);
+        assert_eq!(report.total_pages, last.total_pages);
+        assert_eq!(report.size_bytes, std::fs::metadata(&dest).unwrap().len());
+        assert!(!root.join("backup.db.tmp").exists());
+
+        // The copy is a consistent snapshot taken between inserts
+        let conn = Connection::open(&dest).unwrap();
+        assert_eq!(run_integrity_check(&conn).unwrap(), "ok");
+        let copied: usize = conn
+            .query_row("SELECT COUNT(*) FROM clipboard_items", [], |row| row.get(0))
+            .unwrap();
+        assert!(copied >= 500);
+        assert!(copied <= 500 + inserted);
+    }
+
+    fn spill_storage() -> (crate::storage::FileStorage, TempDir) {
+        let root = temp_dir("spill");
+        let storage = crate::storage::FileStorage::with_images_dir(root.join("images")).unwrap();
+        (storage, root)
+    }
+
+    /// Storage for edits that stay below the spill threshold; nothing is created
+    fn unused_storage() -> crate::storage::FileStorage {
+        crate::storage::FileStorage::read_only(std::env::temp_dir().join("clipster-unused/images"))
+    }
+
+    #[test]
+    fn test_large_text_spill_round_trip() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+
+        let text = format!("{}needle", "x".repeat(4096));
+        let mut item = ClipboardItem::new_text(text.clone(), None, None);
+        assert!(storage.spill_large_text(&mut item, 1024).unwrap());
+        db.insert_item(&item).unwrap();
+
+        // Lists only carry the preview
+        let listed = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap().remove(0);
+        assert_eq!(listed.content_text.as_ref().unwrap().len(), 2048);
+        assert!(listed.text_path.is_some());
+
+        // get_item resolves the full text
+        let full = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(full.content_text, Some(text));
+
+        // The tail is only found by deep search
+        assert!(db.search_items("needle", 10, false).unwrap().is_empty());
+        let results = db
+            .search_items_deep("NEEDLE", &SearchFilter::default(), 10, false)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, item.id);
+
+        // Small text stays inline
+        let mut small = ClipboardItem::new_text("short".to_string(), None, None);
+        assert!(!storage.spill_large_text(&mut small, 1024).unwrap());
+        assert!(small.text_path.is_none());
+    }
+
+    #[test]
+    fn test_large_edit_is_spilled() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+        db.set_setting("large_text_threshold_bytes", "1024")
+            .unwrap();
+        let item = ClipboardItem::new_text("short".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        let text = format!("{}needle", "x".repeat(4096));
+        let edited = db
+            .update_item_content(&item.id, &text, &storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.content_text.as_deref(), Some(text.as_str()));
+        let path = std::path::PathBuf::from(edited.text_path.unwrap());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), text);
+
+        let listed = db
+            .get_items(10, 0, false, HistorySort::CreatedAt)
+            .unwrap()
+            .remove(0);
+        assert_eq!(listed.content_text.as_ref().unwrap().len(), 2048);
+        let full = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(full.content_text, Some(text.clone()));
+        let revisions = db.get_item_revisions(&item.id).unwrap();
+        assert_eq!(revisions[0].content_text, "short");
+
+        // Editing a spilled item again rewrites its file in place
+        let longer = format!("{text}!");
+        db.update_item_content(&item.id, &longer, &storage).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), longer);
+
+        // Shrinking it back below the threshold moves it inline
+        let shrunk = db
+            .update_item_content(&item.id, "short again", &storage)
+            .unwrap()
+            .unwrap();
+        assert!(shrunk.text_path.is_none());
+        assert!(!path.exists());
+        let stored = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(stored.content_text.as_deref(), Some("short again"));
+    }
+
+    #[test]
+    fn test_delete_removes_spill_file() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+
+        let spill = |text: &str| {
+            let mut item = ClipboardItem::new_text(text.repeat(2048), None, None);
+            storage.spill_large_text(&mut item, 1024).unwrap();
+            db.insert_item(&item).unwrap();
+            (item.id.clone(), std::path::PathBuf::from(item.text_path.unwrap()))
+        };
+
+        let (deleted_id, deleted_path) = spill("a");
+        let (_, cleared_path) = spill("b");
+        assert!(deleted_path.exists() && cleared_path.exists());
+
+        assert!(db.delete_item(&deleted_id).unwrap());
+        assert!(!deleted_path.exists());
+
+        db.clear_history().unwrap();
+        assert!(!cleared_path.exists());
+    }
+
+    #[test]
+    fn test_recopied_spilled_text_replaces_itself() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+
+        let text = format!("{}tail", "x".repeat(4096));
+        let mut item = ClipboardItem::new_text(text.clone(), None, None);
+        assert!(storage.spill_large_text(&mut item, 1024).unwrap());
+        db.insert_item(&item).unwrap();
+
+        let replaced = db
+            .delete_unpinned_by_content(&text, None, DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(item.id.clone()));
+        assert!(db.get_item(&item.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_text_equal_to_a_preview_keeps_the_large_item() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+
+        let mut large = ClipboardItem::new_text("y".repeat(8192), None, None);
+        assert!(storage.spill_large_text(&mut large, 1024).unwrap());
+        db.insert_item(&large).unwrap();
+        let preview = large.content_text.clone().unwrap();
+
+        let replaced = db
+            .delete_unpinned_by_content(&preview, None, DedupeScope::Global, None)
+            .unwrap();
+        assert!(replaced.is_none());
+        assert!(!db.content_exists(&preview).unwrap());
+        assert!(db.get_item(&large.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_rows_without_content_hash_still_dedupe() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("legacy".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE clipboard_items SET content_hash = NULL", [])
+            .unwrap();
+
+        assert!(db.content_exists("legacy").unwrap());
+        let replaced = db
+            .delete_unpinned_by_content("legacy", None, DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(item.id));
+    }
+
+    #[test]
+    fn test_compressed_text_round_trip() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("compress_large_text", "true").unwrap();
+
+        let text = format!("{}needle", "log line\n".repeat(20_000));
+        let item = ClipboardItem::new_text(text.clone(), None, None);
+        db.insert_item(&item).unwrap();
+        let small = ClipboardItem::new_text("small needle".to_string(), None, None);
+        db.insert_item(&small).unwrap();
+
+        // Only the preview is stored as text
+        let conn = db.conn.lock().unwrap();
+        let stored: String = conn
+            .query_row(
+                "SELECT content_text FROM clipboard_items WHERE id = ?1",
+                params![item.id],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(stored.len(), file_storage::TEXT_PREVIEW_BYTES);
+        drop(conn);
+
+        assert_eq!(db.get_item(&item.id).unwrap().unwrap().content_text, Some(text.clone()));
+
+        // Lists only carry the preview
+        let listed = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        let listed = listed.iter().find(|listed| listed.id == item.id).unwrap();
+        assert_eq!(
+            listed.content_text.as_ref().unwrap().len(),
+            file_storage::TEXT_PREVIEW_BYTES
+        );
+
+        // The match is past the preview: only found by deep search
+        let no_filter = SearchFilter::default();
+        let results = db.search_items("NEEDLE", 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, small.id);
+        let deep = |query: &str, limit: usize| {
+            db.search_items_deep(query, &no_filter, limit, false)
+                .unwrap()
+        };
+        assert_eq!(deep("NEEDLE", 10).len(), 2);
+        assert_eq!(deep("needle", 1).len(), 1);
+        assert!(deep("absent", 10).is_empty());
+
+        let stats = db.get_stats().unwrap();
+        assert_eq!(stats.total_items, 2);
+        assert_eq!(stats.compressed_items, 1);
+        assert_eq!(stats.uncompressed_bytes, text.len() as u64);
+        assert!(stats.saved_bytes > 0);
+    }
+
+    #[test]
+    fn test_recopied_compressed_text_replaces_itself() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("compress_large_text", "true").unwrap();
+
+        let text = "log line\n".repeat(20_000);
+        let item = ClipboardItem::new_text(text.clone(), None, None);
+        db.insert_item(&item).unwrap();
+        let preview = file_storage::text_preview(&text, file_storage::TEXT_PREVIEW_BYTES);
+
+        // A copy of the preview alone is new content
+        assert!(db
+            .delete_unpinned_by_content(preview, None, DedupeScope::Global, None)
+            .unwrap()
+            .is_none());
+        let replaced = db
+            .delete_unpinned_by_content(&text, None, DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(item.id));
+    }
+
+    #[test]
+    fn test_compression_off_by_default() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("x".repeat(100_000), None, None);
+        db.insert_item(&item).unwrap();
+        assert_eq!(db.get_stats().unwrap().compressed_items, 0);
+    }
+
+    #[test]
+    fn test_links_dedupe_by_canonical_url() {
+        use crate::clipboard::canonical_url::canonicalize_url;
+
+        let db = Database::new_in_memory().unwrap();
+        let params = db.get_url_tracking_params().unwrap();
+
+        let from_site = "https://example.com/post";
+        let mut item = ClipboardItem::new_text(from_site.to_string(), None, None);
+        item.canonical_url = Some(canonicalize_url(from_site, &params));
+        db.insert_item(&item).unwrap();
+
+        // Same article with tracking params replaces the earlier copy
+        let global = DedupeScope::Global;
+        let from_twitter = "https://example.com/post/?utm_source=twitter";
+        let canonical = canonicalize_url(from_twitter, &params);
+        let replaced = db
+            .delete_unpinned_by_canonical_url(&canonical, from_twitter, None, global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(item.id.clone()));
+        assert_eq!(db.count_items().unwrap(), 0);
+
+        // Rows without a canonical URL still match on exact text
+        let legacy = ClipboardItem::new_text(from_twitter.to_string(), None, None);
+        db.insert_item(&legacy).unwrap();
+        let replaced = db
+            .delete_unpinned_by_canonical_url(&canonical, from_twitter, None, global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(legacy.id));
+
+        // Custom tracking params override the defaults
+        db.set_setting("url_tracking_params", r#"["ref"]"#).unwrap();
+        assert_eq!(db.get_url_tracking_params().unwrap(), vec!["ref".to_string()]);
+    }
+
+    #[test]
+    fn test_lazy_thumbnails_payload() {
+        use crate::storage::file_storage::{generate_thumbnail_default, thumbnail_to_base64};
+
+        let source = image::RgbImage::from_fn(64, 40, |x, y| image::Rgb([x as u8, y as u8, 128]));
+        let thumbnail = thumbnail_to_base64(
+            &generate_thumbnail_default(&image::DynamicImage::ImageRgb8(source)).unwrap(),
+        );
+
+        let db = Database::new_in_memory().unwrap();
+        let mut ids = Vec::new();
+        for i in 0..30 {
+            let item = ClipboardItem::new_image(
+                Some(thumbnail.clone()),
+                format!("/tmp/{}.png", i),
+                None,
+                None,
+            );
+            ids.push(item.id.clone());
+            db.insert_item(&item).unwrap();
+        }
+
+        let payload = |with_thumbnails| {
+            let items = db
+                .get_items(30, 0, with_thumbnails, HistorySort::CreatedAt)
+                .unwrap();
+            match serde_json::to_value(items).unwrap() {
+                serde_json::Value::Array(items) => items,
+                other => panic!("expected an array, got {other}"),
+            }
+        };
+
+        let eager = payload(true);
+        assert_eq!(eager.len(), 30);
+        for item in &eager {
+            assert_eq!(item["thumbnail_base64"], thumbnail.as_str());
+        }
+
+        // Lazy lists leave the key out entirely but keep the image path to load it by
+        let lazy = payload(false);
+        assert_eq!(lazy.len(), 30);
+        for item in &lazy {
+            assert!(item.get("thumbnail_base64").is_none());
+            assert!(item["image_path"].as_str().unwrap().ends_with(".png"));
+            assert_eq!(item["content_type"], "image");
+        }
+
+        // A viewport's worth is fetched afterwards
+        let visible = &ids[..20];
+        let thumbnails = db.get_thumbnails(visible, &[]).unwrap();
+        assert_eq!(thumbnails.len(), 20);
+        assert_eq!(thumbnails[&ids[0]], thumbnail);
+
+        let text = ClipboardItem::new_text("no thumbnail".to_string(), None, None);
+        db.insert_item(&text).unwrap();
+        assert!(db.get_thumbnails(&[text.id, "missing".to_string()], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_search_items() {
+        let db = Database::new_in_memory().unwrap();
+
+        db.insert_item(&ClipboardItem::new_text("Hello World".to_string(), None, None))
+            .unwrap();
+        db.insert_item(&ClipboardItem::new_text("Goodbye World".to_string(), None, None))
+            .unwrap();
+        db.insert_item(&ClipboardItem::new_text("Hello Rust".to_string(), None, None))
+            .unwrap();
+
+        let results = db.search_items("Hello", 10, false).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let results = db.search_items("World", 10, false).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let results = db.search_items("Rust", 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_window_title_is_stored_searched_and_carried() {
+        let db = Database::new_in_memory().unwrap();
+
+        let mut item =
+            ClipboardItem::new_text("42".to_string(), Some("Chrome".to_string()), None);
+        item.source_window_title = Some("Jira ticket PROJ-123".to_string());
+        db.insert_item(&item).unwrap();
+        db.insert_item(&ClipboardItem::new_text("43".to_string(), None, None))
+            .unwrap();
+
+        let stored = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(stored.source_window_title.as_deref(), Some("Jira ticket PROJ-123"));
+
+        // The title matches even though the content does not
+        let results = db.search_items("proj-123", 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, item.id);
+
+        // Move to top hands back the title with the other source fields
+        let replaced = db
+            .delete_unpinned_by_content("42", None, DedupeScope::Global, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            replaced,
+            (
+                item.id,
+                Some("Chrome".to_string()),
+                None,
+                Some("Jira ticket PROJ-123".to_string()),
+                Some(item.first_copied_at)
+            )
+        );
+    }
+
+    #[test]
+    fn test_title_is_stored_and_searched() {
+        let db = Database::new_in_memory().unwrap();
+        let mut shot = ClipboardItem::new_image(None, "/img/shot.png".to_string(), None, None);
+        shot.source_app = Some("Screenshot".to_string());
+        shot.title = Some("Screenshot 2024-05-12 14:03".to_string());
+        db.insert_item(&shot).unwrap();
+        let other = ClipboardItem::new_image(None, "/img/other.png".to_string(), None, None);
+        db.insert_item(&other).unwrap();
+
+        let stored = db.get_item(&shot.id).unwrap().unwrap();
+        assert_eq!(stored.title, shot.title);
+
+        let results = db.search_items("2024-05-12", 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, shot.id);
+    }
+
+    #[test]
+    fn test_expired_items_are_deleted_at_their_time() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let millisecond = chrono::Duration::milliseconds(1);
+
+        let text = |name: &str, expires_at: Option<DateTime<Utc>>| {
+            let item = ClipboardItem::new_text(name.to_string(), None, None);
+            db.insert_item(&item).unwrap();
+            db.set_item_expiry(&item.id, expires_at).unwrap();
+            item.id
+        };
+        let past = text("past", Some(now - chrono::Duration::minutes(10)));
+        let due = text("due", Some(now));
+        let next = text("next", Some(now + millisecond));
+        let kept = text("kept", None);
+
+        // Pinned items expire too, with their files
+        let image_id = uuid::Uuid::new_v4().to_string();
+        let path = storage.save_png_bytes(&image_id, b"png").unwrap();
+        let mut image =
+            ClipboardItem::new_image(None, path.to_string_lossy().to_string(), None, None);
+        image.id = image_id.clone();
+        image.is_favorite = true;
+        image.expires_at = Some(now - millisecond);
+        db.insert_item(&image).unwrap();
+
+        let stored = db.get_item(&next).unwrap().unwrap();
+        assert_eq!(stored.expires_at, Some(now + millisecond));
+        assert!(!db.set_item_expiry("missing", Some(now)).unwrap());
+
+        let mut deleted = db.delete_expired_items(now).unwrap();
+        deleted.sort();
+        let mut expected = vec![past, due, image_id];
+        expected.sort();
+        assert_eq!(deleted, expected);
+        assert!(!path.exists());
+        assert!(db.get_item(&next).unwrap().is_some());
+
+        // Nothing left to expire yet; a later pass (or launch) catches the rest
+        assert!(db.delete_expired_items(now).unwrap().is_empty());
+        assert_eq!(
+            db.delete_expired_items(now + millisecond).unwrap(),
+            vec![next]
+        );
+
+        // Keeping an item again clears its expiry
+        db.set_item_expiry(&kept, Some(now)).unwrap();
+        db.set_item_expiry(&kept, None).unwrap();
+        let later = now + chrono::Duration::days(365);
+        assert!(db.delete_expired_items(later).unwrap().is_empty());
+        assert_eq!(db.get_item(&kept).unwrap().unwrap().expires_at, None);
+    }
+
+    #[test]
+    fn test_media_metadata_is_stored() {
+        let db = Database::new_in_memory().unwrap();
+        let song = ClipboardItem::new_audio(vec!["/m/song.mp3".to_string()], None, None);
+        db.insert_item(&song).unwrap();
+        assert_eq!(db.get_item(&song.id).unwrap().unwrap().media_metadata, None);
+
+        let metadata = MediaMetadata {
+            duration_ms: Some(215_000),
+            title: Some("Song".to_string()),
+            artist: Some("Band".to_string()),
+            album: None,
+        };
+        assert!(db.set_media_metadata(&song.id, &metadata).unwrap());
+        let stored = db.get_item(&song.id).unwrap().unwrap();
+        assert_eq!(stored.media_metadata, Some(metadata.clone()));
+        let listed = db
+            .get_items(10, 0, false, HistorySort::default())
+            .unwrap()
+            .remove(0);
+        assert_eq!(listed.media_metadata, Some(metadata.clone()));
+        assert!(!db.set_media_metadata("missing", &metadata).unwrap());
+
+        // A damaged column reads as no metadata rather than failing the item
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE clipboard_items SET media_metadata = '{oops' WHERE id = ?1",
+                params![song.id],
+            )
+            .unwrap();
+        assert_eq!(db.get_item(&song.id).unwrap().unwrap().media_metadata, None);
+    }
+
+    #[test]
+    fn test_move_to_top_keeps_first_copied_at() {
+        let db = Database::new_in_memory().unwrap();
+        let original = ClipboardItem::new_text("again".to_string(), None, None);
+        db.insert_item(&original).unwrap();
+        let other = ClipboardItem::new_text("other".to_string(), None, None);
+        db.insert_item(&other).unwrap();
+
+        // Re-copy three times the way the monitor does
+        let mut latest = original.clone();
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(2));
+            let (id, .., first_copied_at) = db
+                .delete_unpinned_by_content("again", None, DedupeScope::Global, None)
+                .unwrap()
+                .unwrap();
+            assert_eq!(id, latest.id);
+
+            latest = ClipboardItem::new_text("again".to_string(), None, None);
+            latest.first_copied_at = first_copied_at.unwrap();
+            db.insert_item(&latest).unwrap();
+        }
+
+        let stored = db.get_item(&latest.id).unwrap().unwrap();
+        assert_eq!(stored.first_copied_at, original.first_copied_at);
+        assert!(stored.created_at > original.created_at);
+        assert_eq!(db.count_items().unwrap(), 2);
+
+        let by_last_copy = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        assert_eq!(by_last_copy[0].id, latest.id);
+        let by_first_copy = db.get_items(10, 0, false, HistorySort::FirstCopiedAt).unwrap();
+        assert_eq!(by_first_copy[0].id, other.id);
+        assert_eq!(by_first_copy[1].id, latest.id);
+    }
+
+    #[test]
+    fn test_recopied_favorite_keeps_its_star() {
+        let db = Database::new_in_memory().unwrap();
+        let favorite = ClipboardItem::new_text("starred".to_string(), None, None);
+        db.insert_item(&favorite).unwrap();
+        db.toggle_item_favorite(&favorite.id).unwrap();
+        let other = ClipboardItem::new_text("other".to_string(), None, None);
+        db.insert_item(&other).unwrap();
+
+        // Re-copy the way the monitor does: favorites first, then replace
+        std::thread::sleep(Duration::from_millis(2));
+        let moved = db
+            .move_favorite_to_top("starred", None, None, DedupeScope::Global, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(moved.id, favorite.id);
+        assert!(moved.is_favorite);
+        assert!(moved.created_at > other.created_at);
+        assert_eq!(moved.first_copied_at, favorite.first_copied_at);
+
+        // The favorite is never deleted by the replace step
+        let replaced = db
+            .delete_unpinned_by_content("starred", None, DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced, None);
+
+        let items = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec![favorite.id.as_str(), other.id.as_str()]);
+
+        // Non-favorites and the off scope are left to the replace step
+        assert!(db
+            .move_favorite_to_top("other", None, None, DedupeScope::Global, None)
+            .unwrap()
+            .is_none());
+        assert!(db
+            .move_favorite_to_top("starred", None, None, DedupeScope::Off, None)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_recopied_favorite_link_matches_canonical_url() {
+        let db = Database::new_in_memory().unwrap();
+        let mut link =
+            ClipboardItem::new_link("https://example.com/a?utm_source=x".to_string(), None, None);
+        link.canonical_url = Some("https://example.com/a".to_string());
+        db.insert_item(&link).unwrap();
+        db.toggle_item_favorite(&link.id).unwrap();
+
+        let replaced = db
+            .delete_unpinned_by_canonical_url(
+                "https://example.com/a",
+                "https://example.com/a?utm_source=y",
+                None,
+                DedupeScope::Global,
+                None,
+            )
+            .unwrap();
+        assert_eq!(replaced, None);
+
+        let moved = db
+            .move_favorite_to_top(
+                "https://example.com/a?utm_source=y",
+                Some("https://example.com/a"),
+                None,
+                DedupeScope::Global,
+                None,
+            )
+            .unwrap();
+        assert_eq!(moved.map(|item| item.id), Some(link.id));
+        assert_eq!(db.count_items().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_find_pasteboard_items_dedupe_separately() {
+        use crate::models::clipboard_item::FIND_PASTEBOARD;
+
+        let db = Database::new_in_memory().unwrap();
+        let general = ClipboardItem::new_text("needle".to_string(), None, None);
+        db.insert_item(&general).unwrap();
+        let mut found = ClipboardItem::new_text("needle".to_string(), None, None);
+        found.source_pasteboard = Some(FIND_PASTEBOARD.to_string());
+        db.insert_item(&found).unwrap();
+
+        let stored = db.get_item(&found.id).unwrap().unwrap();
+        assert_eq!(stored.source_pasteboard.as_deref(), Some(FIND_PASTEBOARD));
+
+        // Each pasteboard only moves its own copy to the top
+        let replaced = db
+            .delete_unpinned_by_content("needle", Some(FIND_PASTEBOARD), DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(found.id));
+        assert!(db.get_item(&general.id).unwrap().is_some());
+
+        let replaced = db
+            .delete_unpinned_by_content("needle", None, DedupeScope::Global, None)
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(general.id));
+        assert_eq!(db.count_items().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_dedupe_scopes() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(db.get_dedupe_scope().unwrap(), DedupeScope::Global);
+
+        // The same string copied from Slack, then from the editor
+        let copy_from = |app: &str| {
+            let item = ClipboardItem::new_text("same".to_string(), Some(app.to_string()), None);
+            db.insert_item(&item).unwrap();
+            item.id
+        };
+        let recopy = |scope, app: &str| {
+            db.delete_unpinned_by_content("same", None, scope, Some(app))
+                .unwrap()
+                .map(|(id, ..)| id)
+        };
+
+        let slack = copy_from("Slack");
+        assert_eq!(recopy(DedupeScope::Global, "Code"), Some(slack));
+        assert_eq!(db.count_items().unwrap(), 0);
+
+        let slack = copy_from("Slack");
+        assert_eq!(recopy(DedupeScope::PerApp, "Code"), None);
+        let code = copy_from("Code");
+        assert_eq!(recopy(DedupeScope::PerApp, "Slack"), Some(slack));
+        assert_eq!(db.count_items().unwrap(), 1);
+
+        assert_eq!(recopy(DedupeScope::Off, "Code"), None);
+        assert!(db.get_item(&code).unwrap().is_some());
+
+        // Links follow the same scope
+        let url = "https://example.com/post";
+        let mut link = ClipboardItem::new_link(url.to_string(), Some("Slack".to_string()), None);
+        link.canonical_url = Some(url.to_string());
+        db.insert_item(&link).unwrap();
+        let replaced = db
+            .delete_unpinned_by_canonical_url(url, url, None, DedupeScope::PerApp, Some("Code"))
+            .unwrap();
+        assert!(replaced.is_none());
+        let replaced = db
+            .delete_unpinned_by_canonical_url(url, url, None, DedupeScope::PerApp, Some("Slack"))
+            .unwrap();
+        assert_eq!(replaced.map(|(id, ..)| id), Some(link.id));
+
+        db.set_setting("dedupe_scope", "per_app").unwrap();
+        assert_eq!(db.get_dedupe_scope().unwrap(), DedupeScope::PerApp);
+        assert!(!Database::is_valid_setting("dedupe_scope", "per_window"));
+    }
+
+    #[test]
+    fn test_items_grouped_by_app() {
+        let db = Database::new_in_memory().unwrap();
+        let pinboard = Pinboard::new("Work".to_string(), None, 0);
+        db.insert_pinboard(&pinboard).unwrap();
+
+        // Minutes ago for each capture; Notes has the newest item
+        let base = Utc::now();
+        let seed = [
+            (Some("Safari"), vec![50, 40, 30, 20, 10]),
+            (Some("Notes"), vec![5, 15]),
+            (Some("Terminal"), vec![25, 35, 45]),
+            (None, vec![60, 70]),
+        ];
+        for (app, minutes) in &seed {
+            for minutes_ago in minutes {
+                let text = format!("{} {}", app.unwrap_or("none"), minutes_ago);
+                let icon = app.map(|app| format!("{}-icon", app));
+                let mut item = ClipboardItem::new_text(text, app.map(str::to_string), icon);
+                item.created_at = base - chrono::Duration::minutes(*minutes_ago);
+                db.insert_item(&item).unwrap();
+            }
+        }
+        // Pinned items are not history
+        let terminal = Some("Terminal".to_string());
+        let mut pinned = ClipboardItem::new_text("pinned".to_string(), terminal, None);
+        pinned.pinboard_id = Some(pinboard.id.clone());
+        db.insert_item(&pinned).unwrap();
+
+        let groups = db.get_items_grouped_by_app(3, false).unwrap();
+        let names: Vec<_> = groups.iter().map(|g| g.name.as_str()).collect();
+        assert_eq!(names, vec!["Notes", "Safari", "Terminal", UNKNOWN_APP]);
+        let counts: Vec<_> = groups
+            .iter()
+            .map(|g| (g.total_count, g.items.len()))
+            .collect();
+        assert_eq!(counts, vec![(2, 2), (5, 3), (3, 3), (2, 2)]);
+
+        let safari = &groups[1];
+        assert_eq!(safari.icon.as_deref(), Some("Safari-icon"));
+        let texts: Vec<_> = safari
+            .items
+            .iter()
+            .map(|i| i.content_text.as_deref().unwrap())
+            .collect();
+        assert_eq!(texts, vec!["Safari 10", "Safari 20", "Safari 30"]);
+
+        let unknown = &groups[3];
+        assert!(!unknown.is_known);
+        assert!(unknown.icon.is_none());
+
+        let groups = db.get_items_grouped_by_app(1, false).unwrap();
+        assert!(groups.iter().all(|g| g.items.len() == 1));
+    }
+
+    #[test]
+    fn test_find_and_merge_exact_duplicates() {
+        let db = Database::new_in_memory().unwrap();
+        let pinboard = Pinboard::new("Work".to_string(), None, 0);
+        db.insert_pinboard(&pinboard).unwrap();
+
+        // Three captures of the same text, oldest first, from before dedupe
+        let base = Utc::now();
+        let mut copies = Vec::new();
+        for days_ago in [30, 20, 10] {
+            let mut item = ClipboardItem::new_text("same".to_string(), None, None);
+            item.created_at = base - chrono::Duration::days(days_ago);
+            item.first_copied_at = item.created_at;
+            db.insert_item(&item).unwrap();
+            copies.push(item);
+        }
+        db.update_item_pinboard(&copies[0].id, Some(&pinboard.id))
+            .unwrap();
+        db.toggle_item_favorite(&copies[1].id).unwrap();
+        // Same text as a link, and unrelated text, are not duplicates
+        db.insert_item(&ClipboardItem::new_link("same".to_string(), None, None))
+            .unwrap();
+        db.insert_item(&ClipboardItem::new_text("other".to_string(), None, None))
+            .unwrap();
+
+        let groups = db.find_duplicates(DuplicateKind::Exact).unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].item_ids,
+            vec![
+                copies[2].id.clone(),
+                copies[1].id.clone(),
+                copies[0].id.clone()
+            ]
+        );
+        assert_eq!(groups[0].preview, "same");
+
+        let removed = db
+            .merge_duplicate_group(&groups[0].item_ids[0], &groups[0].item_ids[1..])
+            .unwrap();
+        assert_eq!(removed, 2);
+        assert!(db.find_duplicates(DuplicateKind::Exact).unwrap().is_empty());
+
+        // The kept item inherits the pinboard, favorite and first copy time
+        let kept = db.require_item(&copies[2].id).unwrap();
+        assert_eq!(kept.pinboard_id.as_deref(), Some(pinboard.id.as_str()));
+        assert!(kept.is_favorite);
+        assert_eq!(kept.first_copied_at, copies[0].first_copied_at);
+        assert_eq!(kept.created_at, copies[2].created_at);
+        assert!(db.get_item(&copies[0].id).unwrap().is_none());
+        assert!(db.get_item(&copies[1].id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_merge_similar_images_removes_files() {
+        use image::{Rgb, RgbImage};
+
+        let db = Database::new_in_memory().unwrap();
+        let root = temp_dir("dupes");
+        std::fs::create_dir_all(&root).unwrap();
+
+        let picture = RgbImage::from_fn(120, 80, |x, y| Rgb([(x * 2) as u8, (y * 3) as u8, 60]));
+        let other = RgbImage::from_fn(120, 80, |x, y| {
+            Rgb([255 - (x * 2) as u8, (y * 3) as u8, 60])
+        });
+        let mut items = Vec::new();
+        for (name, image) in [("a.png", &picture), ("b.jpg", &picture), ("c.png", &other)] {
+            let path = root.join(name);
+            image.save(&path).unwrap();
+            let item =
+                ClipboardItem::new_image(None, path.to_string_lossy().to_string(), None, None);
+            db.insert_item(&item).unwrap();
+            items.push(item);
+        }
+
+        // The PNG and JPEG of one picture only match as images
+        assert!(db.find_duplicates(DuplicateKind::Exact).unwrap().is_empty());
+        let groups = db.find_duplicates(DuplicateKind::Images).unwrap();
+        assert_eq!(groups.len(), 1);
+        let mut group = groups[0].item_ids.clone();
+        group.sort();
+        let mut expected = vec![items[0].id.clone(), items[1].id.clone()];
+        expected.sort();
+        assert_eq!(group, expected);
+
+        assert_eq!(
+            db.merge_duplicate_group(&items[0].id, &[items[1].id.clone()])
+                .unwrap(),
+            1
+        );
+        assert!(root.join("a.png").exists());
+        assert!(!root.join("b.jpg").exists());
+        assert!(root.join("c.png").exists());
+    }
+
+    #[test]
+    fn test_merge_duplicate_group_validation() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("a".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        assert!(matches!(
+            db.merge_duplicate_group(&item.id, std::slice::from_ref(&item.id)),
+            Err(ClipsterError::Validation(_))
+        ));
+        assert!(matches!(
+            db.merge_duplicate_group("missing", std::slice::from_ref(&item.id)),
+            Err(ClipsterError::NotFound(_))
+        ));
+        // Nothing was deleted, and already removed duplicates are skipped
+        assert_eq!(
+            db.merge_duplicate_group(&item.id, &["gone".to_string()])
+                .unwrap(),
+            0
+        );
+        assert!(db.get_item(&item.id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_reclassify_items_is_idempotent() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Captured before the link and audio detectors existed
+        let mut link =
+            ClipboardItem::new_text("https://example.com/?utm_source=x".to_string(), None, None);
+        link.content_type = ContentType::Text;
+        let mut audio = ClipboardItem::new_files(vec!["/music/a.mp3".to_string()], None, None);
+        audio.content_type = ContentType::Files;
+        let text = ClipboardItem::new_text("plain".to_string(), None, None);
+        for item in [&link, &audio, &text] {
+            db.insert_item(item).unwrap();
+        }
+
+        let changes = db.find_reclassifications().unwrap();
+        assert_eq!(changes.len(), 2);
+
+        let mut batches = Vec::new();
+        let updated = db
+            .apply_reclassifications(&changes, |batch| batches.push(batch.to_vec()))
+            .unwrap();
+        assert_eq!(updated, 2);
+        assert_eq!(batches.concat(), changes);
+
+        let saved = db.get_item(&link.id).unwrap().unwrap();
+        assert_eq!(saved.content_type, ContentType::Link);
+        assert_eq!(saved.canonical_url.as_deref(), Some("https://example.com/"));
+        assert_eq!(
+            db.get_item(&audio.id).unwrap().unwrap().content_type,
+            ContentType::Audio
+        );
+
+        // A second run finds nothing, and stale changes are not reapplied
+        assert!(db.find_reclassifications().unwrap().is_empty());
+        assert_eq!(db.apply_reclassifications(&changes, |_| {}).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_language_is_filled_in_filtered_and_counted() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Saved before detection existed
+        let french = ClipboardItem::new_text(
+            "Le rapport de la réunion est prêt, je te l'envoie demain matin.".to_string(),
+            None,
+            None,
+        );
+        let english = ClipboardItem::new_text(
+            "The meeting report is ready, I will send it to you tomorrow morning.".to_string(),
+            None,
+            None,
+        );
+        let short = ClipboardItem::new_text("rapport".to_string(), None, None);
+        for item in [&french, &english, &short] {
+            db.insert_item(item).unwrap();
+        }
+        // A link given a language by mistake loses it
+        let mut link =
+            ClipboardItem::new_link("https://example.com/rapport".to_string(), None, None);
+        link.language = Some("eng".to_string());
+        db.insert_item(&link).unwrap();
+
+        let changes = db.find_language_changes().unwrap();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(db.apply_language_changes(&changes, |_| {}).unwrap(), 3);
+        assert!(db.find_language_changes().unwrap().is_empty());
+        assert_eq!(db.apply_language_changes(&changes, |_| {}).unwrap(), 0);
+        assert_eq!(db.get_item(&link.id).unwrap().unwrap().language, None);
+
+        let no_filter = SearchFilter::default();
+        let french_only = SearchFilter {
+            language: Some("fra".to_string()),
+        };
+        assert_eq!(db.search_items("rapport", 10, false).unwrap().len(), 3);
+        let found = db
+            .search_items_filtered("rapport", &french_only, 10, false)
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, french.id);
+        assert_eq!(found[0].language.as_deref(), Some("fra"));
+        assert_eq!(
+            db.search_items_deep("report", &no_filter, 10, false)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(db
+            .search_items_deep("report", &french_only, 10, false)
+            .unwrap()
+            .is_empty());
+
+        let stats = db.get_stats().unwrap();
+        assert_eq!(stats.languages.get("fra"), Some(&1));
+        assert_eq!(stats.languages.get("eng"), Some(&1));
+        assert_eq!(stats.languages.len(), 2);
+    }
+
+    #[test]
+    fn test_items_by_palette_color() {
+        let db = Database::new_in_memory().unwrap();
+        let image = |name: &str| {
+            let item = ClipboardItem::new_image(None, format!("/images/{}.png", name), None, None);
+            db.insert_item(&item).unwrap();
+            item
+        };
+        let sunset = image("sunset");
+        let sea = image("sea");
+        let secret = image("secret");
+        image("no-palette");
+        let red = vec!["#e61414".to_string(), "#202020".to_string()];
+        assert!(db.set_item_palette(&sunset.id, &red).unwrap());
+        assert!(db
+            .set_item_palette(&sea.id, &["#1428c8".to_string()])
+            .unwrap());
+        assert!(db.set_item_palette(&secret.id, &red).unwrap());
+        assert!(!db.set_item_palette("missing", &red).unwrap());
+
+        let vault = Pinboard::new("Vault".to_string(), None, 0);
+        db.insert_pinboard(&vault).unwrap();
+        db.update_item_pinboard(&secret.id, Some(&vault.id))
+            .unwrap();
+        db.set_pinboard_lock_hash(&vault.id, Some("$argon2id$hash"))
+            .unwrap();
+
+        let found = db.get_items_by_color("#ff0000", 60.0, false).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, sunset.id);
+        assert_eq!(found[0].palette.as_ref(), Some(&red));
+        assert!(db.get_items_by_color("#ff0000", 10.0, false).unwrap().is_empty());
+        assert_eq!(db.get_items_by_color("#00f", 80.0, false).unwrap()[0].id, sea.id);
+
+        let error = db.get_items_by_color("red", 60.0, false).unwrap_err();
+        assert_eq!(error.code(), "validation");
+    }
+
+    #[test]
+    fn test_prune_oldest() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Insert 10 items
+        for i in 0..10 {
+            let item = ClipboardItem::new_text(format!("Item {}", i), None, None);
+            db.insert_item(&item).unwrap();
+        }
+
+        assert_eq!(db.count_items().unwrap(), 10);
+
+        // Prune to keep only 5
+        db.prune_oldest(5).unwrap();
+        assert_eq!(db.count_items().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_prune_removes_image_files() {
+        let db = Database::new_in_memory().unwrap();
+        let dir = temp_dir("prune");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut paths = Vec::new();
+        for i in 0..3 {
+            let path = dir.join(format!("{}.png", i));
+            fs::write(&path, b"png").unwrap();
+            let image_path = path.to_string_lossy().to_string();
+            let mut item = ClipboardItem::new_image(None, image_path, None, None);
+            item.created_at = Utc::now() - chrono::Duration::minutes(10 - i);
+            db.insert_item(&item).unwrap();
+            paths.push(path);
+        }
+        let mut favorite = ClipboardItem::new_text("kept".to_string(), None, None);
+        favorite.created_at = Utc::now() - chrono::Duration::hours(1);
+        favorite.is_favorite = true;
+        db.insert_item(&favorite).unwrap();
+
+        assert_eq!(db.count_prunable_items().unwrap(), 3);
+        assert_eq!(db.prune_oldest(1).unwrap(), 2);
+        assert_eq!(db.count_prunable_items().unwrap(), 1);
+        assert!(!paths[0].exists() && !paths[1].exists());
+        assert!(paths[2].exists());
+    }
+
+    #[test]
+    fn test_prune_preserves_pinned_items() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Create a pinboard
+        let pinboard = Pinboard::new("Test".to_string(), None, 0);
+        let pinboard_id = pinboard.id.clone();
+        db.insert_pinboard(&pinboard).unwrap();
+
+        // Insert 10 history items
+        for i in 0..10 {
+            let item = ClipboardItem::new_text(format!("History {}", i), None, None);
+            db.insert_item(&item).unwrap();
+        }
+
+        // Insert 5 pinned items
+        for i in 0..5 {
+            let item = ClipboardItem::new_text(format!("Pinned {}", i), None, None);
+            let item_id = item.id.clone();
+            db.insert_item(&item).unwrap();
+            db.update_item_pinboard(&item_id, Some(&pinboard_id)).unwrap();
+        }
+
+        // History count should be 10 (pinned items not counted)
+        assert_eq!(db.count_items().unwrap(), 10);
+
+        // Prune to keep only 3 history items
+        db.prune_oldest(3).unwrap();
+
+        // History count should now be 3
+        assert_eq!(db.count_items().unwrap(), 3);
+
+        // But pinned items should still exist
+        let pinboard_items = db.get_pinboard_items(&pinboard_id, 100, 0, false).unwrap();
+        assert_eq!(pinboard_items.len(), 5);
+    }
+
+    #[test]
+    fn test_settings() {
+        let db = Database::new_in_memory().unwrap();
+
+        // Default value
+        assert_eq!(db.get_setting("history_limit").unwrap(), Some("500".to_string()));
+
+        // Update value
+        db.set_setting("history_limit", "1000").unwrap();
+        assert_eq!(db.get_setting("history_limit").unwrap(), Some("1000".to_string()));
+
+        // New key
+        db.set_setting("custom_key", "custom_value").unwrap();
+        assert_eq!(db.get_setting("custom_key").unwrap(), Some("custom_value".to_string()));
+    }
+
+    #[test]
+    fn test_default_settings() {
+        let db = Database::new_in_memory().unwrap();
+        for (key, value) in DEFAULT_SETTINGS {
+            assert!(Database::is_valid_setting(key, value), "{}", key);
+            let stored = db.get_setting(key).unwrap();
+            assert_eq!(stored.as_deref(), Some(*value), "{}", key);
+        }
+        assert!(db.invalid_settings().unwrap().is_empty());
+
+        let defaults: HashMap<_, _> = DEFAULT_SETTINGS.iter().copied().collect();
+        let threshold = file_storage::DEFAULT_LARGE_TEXT_THRESHOLD.to_string();
+        assert_eq!(defaults["large_text_threshold_bytes"], threshold);
+        let timeout = file_storage::DEFAULT_QUICKLOOK_TIMEOUT_MS.to_string();
+        assert_eq!(defaults["quicklook_timeout_ms"], timeout);
+        let min_free_space = crate::clipboard::storage_guard::DEFAULT_MIN_FREE_SPACE_MB.to_string();
+        assert_eq!(defaults["min_free_space_mb"], min_free_space);
+        let max_capture = crate::clipboard::payload_limit::DEFAULT_MAX_IMAGE_CAPTURE_MB.to_string();
+        assert_eq!(defaults["max_image_capture_mb"], max_capture);
+        assert!(Database::is_valid_setting("max_image_capture_mb", "0"));
+        assert!(!Database::is_valid_setting("max_image_capture_mb", "100MB"));
+        let merge_window = capture_merge::DEFAULT_MERGE_WINDOW_MS.to_string();
+        assert_eq!(defaults["merge_window_ms"], merge_window);
+        assert!(!Database::is_valid_setting("merge_window_ms", "0"));
+    }
+
+    #[test]
+    fn test_auto_clear_schedule_setting() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(db.get_auto_clear_schedule().unwrap(), AutoClearSchedule::Off);
+        assert_eq!(db.get_auto_clear_last_run().unwrap(), None);
+
+        let daily = AutoClearSchedule::parse("daily_at(18:00)").unwrap();
+        db.set_auto_clear_schedule(daily).unwrap();
+        assert_eq!(db.get_auto_clear_schedule().unwrap(), daily);
+        assert!(db.get_auto_clear_last_run().unwrap().is_some());
+
+        // Unparseable values read as off and are reported by the health check
+        db.set_setting("auto_clear_schedule", "hourly").unwrap();
+        db.set_setting("auto_clear_last_run", "yesterday").unwrap();
+        assert_eq!(db.get_auto_clear_schedule().unwrap(), AutoClearSchedule::Off);
+        assert_eq!(db.get_auto_clear_last_run().unwrap(), None);
+        assert_eq!(
+            db.invalid_settings().unwrap(),
+            vec!["auto_clear_last_run".to_string(), "auto_clear_schedule".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_clear_history_removes_image_files() {
+        let db = Database::new_in_memory().unwrap();
+        let (storage, _root) = spill_storage();
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let path = storage.save_png_bytes(&id, b"png").unwrap();
+        let mut image =
+            ClipboardItem::new_image(None, path.to_string_lossy().to_string(), None, None);
+        image.id = id;
+        db.insert_item(&image).unwrap();
+
+        let pinned_id = uuid::Uuid::new_v4().to_string();
+        let pinned_path = storage.save_png_bytes(&pinned_id, b"png").unwrap();
+        let mut pinned =
+            ClipboardItem::new_image(None, pinned_path.to_string_lossy().to_string(), None, None);
+        pinned.id = pinned_id;
+        pinned.is_favorite = true;
+        db.insert_item(&pinned).unwrap();
+
+        let summary = db.clear_history_summary().unwrap();
+        assert_eq!(summary.items, 1);
+        assert_eq!(summary.bytes, 3);
+        assert_eq!(db.clear_history().unwrap(), 1);
+        assert!(!path.exists());
+        assert!(pinned_path.exists());
+    }
+
+    #[test]
+    fn test_pinboards() {
+        let db = Database::new_in_memory().unwrap();
+
+        let pinboard = Pinboard::new("Work".to_string(), Some("briefcase".to_string()), 0);
+        let pinboard_id = pinboard.id.clone();
+        db.insert_pinboard(&pinboard).unwrap();
+
+        let pinboards = db.get_pinboards(false).unwrap();
+        assert_eq!(pinboards.len(), 1);
+        assert_eq!(pinboards[0].name, "Work");
+
+        // Add item to pinboard
+        let item = ClipboardItem::new_text("Work item".to_string(), None, None);
+        let item_id = item.id.clone();
+        db.insert_item(&item).unwrap();
+        db.update_item_pinboard(&item_id, Some(&pinboard_id)).unwrap();
+
+        let pinboard_items = db.get_pinboard_items(&pinboard_id, 10, 0, false).unwrap();
+        assert_eq!(pinboard_items.len(), 1);
+    }
+
+    #[test]
+    fn test_pinboard_color_and_description() {
+        let db = Database::new_in_memory().unwrap();
+
+        let mut pinboard = Pinboard::new("Work".to_string(), None, 0);
+        pinboard.color = "#12a594".to_string();
+        pinboard.description = Some("Snippets for the day job".to_string());
+        db.insert_pinboard(&pinboard).unwrap();
+        let saved = db.get_pinboard(&pinboard.id).unwrap().unwrap();
+        assert_eq!(saved.color, "#12a594");
+        assert_eq!(saved.description, pinboard.description);
+
+        db.update_pinboard(&pinboard.id, "Job", None, Some("#6e56cf"), None, None)
+            .unwrap();
+        let saved = db.get_pinboard(&pinboard.id).unwrap().unwrap();
+        assert_eq!(saved.name, "Job");
+        assert_eq!(saved.color, "#6e56cf");
+        assert_eq!(saved.description, None);
+
+        // Boards from before the migration have no stored color
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE pinboards SET color = NULL", [])
+            .unwrap();
+        let saved = db.get_pinboards(false).unwrap().remove(0);
+        assert_eq!(saved.color, pinboard::default_color(&pinboard.id));
+    }
+
+    #[test]
+    fn test_pinboard_default_transform() {
+        let db = Database::new_in_memory().unwrap();
+
+        let mut pinboard = Pinboard::new("SQL snippets".to_string(), None, 0);
+        pinboard.default_transform = Some(Transform::LowercaseTrimmed);
+        db.insert_pinboard(&pinboard).unwrap();
+        let saved = db.get_pinboard(&pinboard.id).unwrap().unwrap();
+        assert_eq!(saved.default_transform, Some(Transform::LowercaseTrimmed));
+
+        let update = |transform| {
+            db.update_pinboard(&pinboard.id, "SQL", None, None, None, transform)
+                .unwrap();
+            db.get_pinboards(false).unwrap().remove(0).default_transform
+        };
+        assert_eq!(
+            update(Some(Transform::PlainText)),
+            Some(Transform::PlainText)
+        );
+        assert_eq!(update(None), None);
+
+        // A transform this version doesn't know reads as none
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE pinboards SET default_transform = 'shout'", [])
+            .unwrap();
+        let saved = db.get_pinboard(&pinboard.id).unwrap().unwrap();
+        assert_eq!(saved.default_transform, None);
+    }
+
+    #[test]
+    fn test_top_used_items() {
+        let db = Database::new_in_memory().unwrap();
+        let pinboard = Pinboard::new("Snippets".to_string(), None, 0);
+        db.insert_pinboard(&pinboard).unwrap();
+        let secrets = Pinboard::new("Secrets".to_string(), None, 1);
+        db.insert_pinboard(&secrets).unwrap();
+
+        let add = |text: &str, pinboard_id: Option<&str>, auto: Option<&str>, uses| {
+            let mut item = ClipboardItem::new_text(text.to_string(), None, None);
+            item.pinboard_id = pinboard_id.map(str::to_string);
+            item.auto_pinboard_id = auto.map(str::to_string);
+            db.insert_item(&item).unwrap();
+            for _ in 0..uses {
+                db.record_item_use(&item.id).unwrap();
+            }
+            item.id
+        };
+        let greeting = add("hello", Some(&pinboard.id), None, 3);
+        let signature = add("-- Jane", Some(&pinboard.id), None, 1);
+        let address = add("1 Main St", None, Some(&pinboard.id), 1);
+        add("never used", Some(&pinboard.id), None, 0);
+        let loose = add("loose", None, None, 2);
+        let password = add("hunter2", Some(&secrets.id), None, 5);
+
+        // Equal counts go to the most recently used
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE clipboard_items SET last_used_at = ?1 WHERE id = ?2",
+                params!["2026-01-01T00:00:00+00:00", signature],
+            )
+            .unwrap();
+
+        let ids = |items: Vec<ClipboardItem>| items.into_iter().map(|i| i.id).collect::<Vec<_>>();
+        let top = db.get_top_used_items(Some(&pinboard.id), 10).unwrap();
+        assert_eq!(ids(top), vec![greeting.clone(), address, signature]);
+        let top = db.get_top_used_items(Some(&pinboard.id), 1).unwrap();
+        assert_eq!(ids(top), vec![greeting.clone()]);
+
+        let top = db.get_top_used_items(None, 2).unwrap();
+        assert_eq!(ids(top), vec![password, greeting.clone()]);
+
+        // Items in locked pinboards stay out of the overall list
+        db.set_pinboard_lock_hash(&secrets.id, Some("$argon2id$hash"))
+            .unwrap();
+        let top = db.get_top_used_items(None, 2).unwrap();
+        assert_eq!(ids(top), vec![greeting, loose]);
+    }
+
+    #[test]
+    fn test_nested_pinboards() {
+        let db = Database::new_in_memory().unwrap();
+        let board = |name: &str, position| {
+            let pinboard = Pinboard::new(name.to_string(), None, position);
+            db.insert_pinboard(&pinboard).unwrap();
+            pinboard.id
+        };
+        let work = board("Work", 0);
+        let clients = board("Clients", 1);
+        let snippets = board("Snippets", 2);
+        let personal = board("Personal", 3);
+
+        db.set_pinboard_parent(&clients, Some(&work)).unwrap();
+        db.set_pinboard_parent(&snippets, Some(&work)).unwrap();
+        let saved = db.get_pinboard(&snippets).unwrap().unwrap();
+        assert_eq!(saved.parent_id.as_deref(), Some(work.as_str()));
+        assert_eq!(saved.position, 1);
+
+        // Cycles and a third level are refused and change nothing
+        assert!(matches!(
+            db.set_pinboard_parent(&work, Some(&clients)),
+            Err(ClipsterError::Validation(_))
+        ));
+        assert!(db.set_pinboard_parent(&personal, Some(&clients)).is_err());
+        assert!(db.set_pinboard_parent(&work, Some(&personal)).is_err());
+        assert_eq!(db.get_pinboard(&work).unwrap().unwrap().parent_id, None);
+
+        // Reordering one level leaves the other alone
+        db.reorder_pinboards(
+            Some(&work),
+            &[snippets.clone(), clients.clone(), personal.clone()],
+        )
+        .unwrap();
+        assert_eq!(db.get_pinboard(&snippets).unwrap().unwrap().position, 0);
+        assert_eq!(db.get_pinboard(&clients).unwrap().unwrap().position, 1);
+        assert_eq!(db.get_pinboard(&personal).unwrap().unwrap().position, 3);
+
+        // Deleting without children moves them to the end of the root level
+        db.delete_pinboard(&work, false).unwrap();
+        let root: Vec<(String, i32)> = db
+            .get_pinboards(false)
+            .unwrap()
+            .into_iter()
+            .map(|p| {
+                assert_eq!(p.parent_id, None);
+                (p.id, p.position)
+            })
+            .collect();
+        let expected = vec![
+            (personal.clone(), 3),
+            (snippets.clone(), 4),
+            (clients.clone(), 5),
+        ];
+        assert_eq!(root, expected);
+
+        // Deleting with children removes the whole branch
+        db.set_pinboard_parent(&clients, Some(&personal)).unwrap();
+        let item = ClipboardItem::new_text("Client note".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        db.update_item_pinboard(&item.id, Some(&clients)).unwrap();
+        let summary = db.delete_pinboard_summary(&personal, true).unwrap();
+        assert_eq!((summary.pinboards, summary.items_unpinned), (2, 1));
+        assert_eq!((summary.items, summary.bytes), (0, 0));
+        let summary = db.delete_pinboard_summary(&personal, false).unwrap();
+        assert_eq!((summary.pinboards, summary.items_unpinned), (1, 0));
+        assert!(db.delete_pinboard_summary("missing", true).is_err());
+        db.delete_pinboard(&personal, true).unwrap();
+        let remaining = db.get_pinboards(false).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, snippets);
+    }
+
+    #[test]
+    fn test_content_deduplication() {
+        let db = Database::new_in_memory().unwrap();
+
+        let item = ClipboardItem::new_text("Duplicate content".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        assert!(db.content_exists("Duplicate content").unwrap());
+        assert!(!db.content_exists("Non-existent content").unwrap());
+    }
+
+    #[test]
+    fn test_smart_pinboard_rules() {
+        let db = Database::new_in_memory().unwrap();
+
+        let plain = Pinboard::new("Plain".to_string(), None, 0);
+        db.insert_pinboard(&plain).unwrap();
+
+        let smart = Pinboard::new("GitHub".to_string(), None, 1);
+        let smart_id = smart.id.clone();
+        db.insert_pinboard(&smart).unwrap();
+
+        let rules = PinboardRules {
+            text_contains: Some("github.com".to_string()),
+            ..Default::default()
+        };
+        assert!(db.set_pinboard_rules(&smart_id, Some(&rules)).unwrap());
+        assert_eq!(db.get_pinboard(&smart_id).unwrap().unwrap().rules, Some(rules));
+
+        let link = ClipboardItem::new_text("https://github.com/rust-lang".to_string(), None, None);
+        db.insert_item(&link).unwrap();
+        assert_eq!(db.apply_pinboard_rules(&link).unwrap(), Some(smart_id.clone()));
+
+        let other = ClipboardItem::new_text("https://example.com".to_string(), None, None);
+        db.insert_item(&other).unwrap();
+        assert_eq!(db.apply_pinboard_rules(&other).unwrap(), None);
+
+        // Auto-assigned items are protected from pruning
+        db.prune_oldest(0).unwrap();
+        assert_eq!(db.count_items().unwrap(), 0);
+        assert_eq!(db.get_pinboard_items(&smart_id, 10, 0, false).unwrap().len(), 1);
+
+        // Clearing rules turns it back into a regular pinboard
+        db.set_pinboard_rules(&smart_id, None).unwrap();
+        assert_eq!(db.get_pinboard(&smart_id).unwrap().unwrap().rules, None);
+    }
+
+    #[test]
+    fn test_pinboard_shortcuts_setting() {
+        let db = Database::new_in_memory().unwrap();
+        assert!(db.get_pinboard_shortcuts().unwrap().is_empty());
+
+        let shortcuts = HashMap::from([("snippets".to_string(), "Alt+Shift+1".to_string())]);
+        db.set_pinboard_shortcuts(&shortcuts).unwrap();
+        assert_eq!(db.get_pinboard_shortcuts().unwrap(), shortcuts);
+        assert!(db.invalid_settings().unwrap().is_empty());
+
+        db.set_setting("pinboard_shortcuts", "Alt+1").unwrap();
+        assert_eq!(db.invalid_settings().unwrap(), vec!["pinboard_shortcuts"]);
+    }
+
+    #[test]
+    fn test_view_state_setting() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(db.get_view_state().unwrap(), None);
+
+        let view_state = serde_json::json!({
+            "active_pinboard_id": "snippets",
+            "anchor_item_id": "item-1",
+        });
+        db.set_view_state(&view_state).unwrap();
+        assert_eq!(db.get_view_state().unwrap(), Some(view_state.clone()));
+        assert!(db.invalid_settings().unwrap().is_empty());
+
+        // Oversized and non-object blobs are rejected and the saved one is kept
+        let huge = serde_json::json!({ "anchor_item_id": "x".repeat(MAX_VIEW_STATE_BYTES) });
+        assert_eq!(db.set_view_state(&huge).unwrap_err().code(), "validation");
+        let list = serde_json::json!(["snippets"]);
+        assert_eq!(db.set_view_state(&list).unwrap_err().code(), "validation");
+        assert_eq!(db.get_view_state().unwrap(), Some(view_state));
+
+        db.set_setting("view_state", "{not json").unwrap();
+        assert_eq!(db.invalid_settings().unwrap(), vec!["view_state"]);
+    }
+
+    #[test]
+    fn test_app_default_pinboard() {
+        let db = Database::new_in_memory().unwrap();
+
+        let design = Pinboard::new("Design".to_string(), None, 0);
+        let design_id = design.id.clone();
+        db.insert_pinboard(&design).unwrap();
+
+        let mut mapping = HashMap::new();
+        mapping.insert("com.figma.Desktop".to_string(), design_id.clone());
+        mapping.insert("Ghost.exe".to_string(), "deleted-board".to_string());
+        db.set_app_default_pinboards(&mapping).unwrap();
+        assert_eq!(db.get_app_default_pinboards().unwrap(), mapping);
+
+        let from_figma =
+            ClipboardItem::new_text("Frame 1".to_string(), Some("Figma".to_string()), None);
+        db.insert_item(&from_figma).unwrap();
+        assert_eq!(
+            db.apply_app_default_pinboard(&from_figma, "com.figma.desktop")
+                .unwrap(),
+            Some(design_id.clone())
+        );
+
+        let from_ghost =
+            ClipboardItem::new_text("Boo".to_string(), Some("Ghost".to_string()), None);
+        db.insert_item(&from_ghost).unwrap();
+        assert_eq!(
+            db.apply_app_default_pinboard(&from_ghost, r"C:\Ghost\ghost.exe")
+                .unwrap(),
+            None
+        );
+
+        // The display name is not an identifier
+        let by_name =
+            ClipboardItem::new_text("Frame 2".to_string(), Some("Figma".to_string()), None);
+        db.insert_item(&by_name).unwrap();
+        assert_eq!(
+            db.apply_app_default_pinboard(&by_name, "Figma").unwrap(),
+            None
+        );
+
+        // Auto-filed item is visible in both history and the pinboard
+        assert_eq!(db.count_items().unwrap(), 3);
+        assert_eq!(db.get_pinboard_items(&design_id, 10, 0, false).unwrap().len(), 1);
+
+        // Pruning moves it out of history into the pinboard instead of deleting it
+        db.prune_oldest(0).unwrap();
+        assert_eq!(db.count_items().unwrap(), 0);
+        let pinned = db.get_pinboard_items(&design_id, 10, 0, false).unwrap();
+        assert_eq!(pinned.len(), 1);
+        assert_eq!(pinned[0].pinboard_id, Some(design_id));
     }
 
     #[test]
-    fn test_search_items() {
+    fn test_bulk_update_reports_missing_items() {
         let db = Database::new_in_memory().unwrap();
+        let board = Pinboard::new("Work".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+        let ids: Vec<String> = (0..3)
+            .map(|i| {
+                let item = ClipboardItem::new_text(format!("Item {}", i), None, None);
+                db.insert_item(&item).unwrap();
+                item.id
+            })
+            .collect();
+        let selection = vec![ids[0].clone(), "missing".to_string(), ids[1].clone()];
 
-        db.insert_item(&ClipboardItem::new_text("Hello World".to_string(), None, None))
-            .unwrap();
-        db.insert_item(&ClipboardItem::new_text("Goodbye World".to_string(), None, None))
-            .unwrap();
-        db.insert_item(&ClipboardItem::new_text("Hello Rust".to_string(), None, None))
+        let move_to_board = BulkOp::MoveToPinboard {
+            pinboard_id: board.id.clone(),
+        };
+        let results = db.bulk_update_items(&selection, &move_to_board).unwrap();
+        assert_eq!(
+            results,
+            vec![
+                BulkItemResult::ok(&ids[0]),
+                BulkItemResult::failed("missing", "Item not found"),
+                BulkItemResult::ok(&ids[1]),
+            ]
+        );
+        assert_eq!(db.count_pinboard_items(&board.id).unwrap(), 2);
+
+        let favorite = BulkOp::Favorite { favorite: true };
+        db.bulk_update_items(&ids[1..], &favorite).unwrap();
+        assert!(db.get_item(&ids[2]).unwrap().unwrap().is_favorite);
+        db.bulk_update_items(&ids[..2], &BulkOp::RemoveFromPinboard)
             .unwrap();
+        assert_eq!(db.count_pinboard_items(&board.id).unwrap(), 0);
 
-        let results = db.search_items("Hello", 10).unwrap();
-        assert_eq!(results.len(), 2);
+        // An op that can't apply to any item fails as a whole
+        let unknown_board = BulkOp::MoveToPinboard {
+            pinboard_id: "missing".to_string(),
+        };
+        assert!(db.bulk_update_items(&ids, &unknown_board).is_err());
+    }
 
-        let results = db.search_items("World", 10).unwrap();
-        assert_eq!(results.len(), 2);
+    #[test]
+    fn test_bulk_delete_is_atomic() {
+        let db = Database::new_in_memory().unwrap();
+        let dir = temp_dir("bulk");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut ids = Vec::new();
+        let mut paths = Vec::new();
+        for i in 0..3 {
+            let path = dir.join(format!("{}.png", i));
+            fs::write(&path, b"png").unwrap();
+            let image_path = path.to_string_lossy().to_string();
+            let item = ClipboardItem::new_image(None, image_path, None, None);
+            db.insert_item(&item).unwrap();
+            ids.push(item.id);
+            paths.push(path);
+        }
 
-        let results = db.search_items("Rust", 10).unwrap();
-        assert_eq!(results.len(), 1);
+        // Fail the delete of the last item, after the first two were deleted
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "CREATE TRIGGER fail_delete BEFORE DELETE ON clipboard_items
+                     WHEN OLD.id = '{}' BEGIN SELECT RAISE(ABORT, 'delete failed'); END",
+                    ids[2]
+                ),
+                [],
+            )
+            .unwrap();
+        assert!(db.bulk_update_items(&ids, &BulkOp::Delete).is_err());
+        assert_eq!(db.count_items().unwrap(), 3);
+        assert!(paths.iter().all(|path| path.exists()));
+
+        let results = db.bulk_update_items(&ids[..2], &BulkOp::Delete).unwrap();
+        assert!(results.iter().all(|result| result.ok));
+        assert_eq!(db.count_items().unwrap(), 1);
+        assert!(!paths[0].exists() && !paths[1].exists());
+        assert!(paths[2].exists());
     }
 
     #[test]
-    fn test_prune_oldest() {
+    fn test_pinboard_pagination_and_counts() {
         let db = Database::new_in_memory().unwrap();
 
-        // Insert 10 items
-        for i in 0..10 {
-            let item = ClipboardItem::new_text(format!("Item {}", i), None, None);
+        let work = Pinboard::new("Work".to_string(), None, 0);
+        let work_id = work.id.clone();
+        db.insert_pinboard(&work).unwrap();
+        let home = Pinboard::new("Home".to_string(), None, 1);
+        let home_id = home.id.clone();
+        db.insert_pinboard(&home).unwrap();
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let item = ClipboardItem::new_text(format!("Snippet {}", i), None, None);
+            ids.push(item.id.clone());
             db.insert_item(&item).unwrap();
+            db.update_item_pinboard(&item.id, Some(&work_id)).unwrap();
         }
 
-        assert_eq!(db.count_items().unwrap(), 10);
+        assert_eq!(db.count_pinboard_items(&work_id).unwrap(), 5);
+        assert_eq!(db.get_pinboard_items(&work_id, 2, 0, false).unwrap().len(), 2);
+        assert_eq!(db.get_pinboard_items(&work_id, 2, 4, false).unwrap().len(), 1);
 
-        // Prune to keep only 5
-        db.prune_oldest(5).unwrap();
-        assert_eq!(db.count_items().unwrap(), 5);
+        // Move one item to another board, and one back to history
+        db.update_item_pinboard(&ids[0], Some(&home_id)).unwrap();
+        db.update_item_pinboard(&ids[1], None).unwrap();
+
+        assert_eq!(db.count_pinboard_items(&work_id).unwrap(), 3);
+        assert_eq!(db.count_pinboard_items(&home_id).unwrap(), 1);
+
+        let pinboards = db.get_pinboards(false).unwrap();
+        assert_eq!(pinboards[0].item_count, 3);
+        assert_eq!(pinboards[1].item_count, 1);
+        assert_eq!(db.get_pinboard(&home_id).unwrap().unwrap().item_count, 1);
+
+        let empty = Pinboard::new("Empty".to_string(), None, 2);
+        let empty_id = empty.id.clone();
+        db.insert_pinboard(&empty).unwrap();
+        assert_eq!(db.get_pinboard(&empty_id).unwrap().unwrap().item_count, 0);
     }
 
     #[test]
-    fn test_prune_preserves_pinned_items() {
+    fn test_default_favorites_created_once() {
         let db = Database::new_in_memory().unwrap();
 
-        // Create a pinboard
-        let pinboard = Pinboard::new("Test".to_string(), None, 0);
-        let pinboard_id = pinboard.id.clone();
-        db.insert_pinboard(&pinboard).unwrap();
+        // Fresh database gets the Favorites board
+        db.ensure_default_pinboards().unwrap();
+        let pinboards = db.get_pinboards(false).unwrap();
+        assert_eq!(pinboards.len(), 1);
+        assert_eq!(pinboards[0].name, "Favorites");
 
-        // Insert 10 history items
-        for i in 0..10 {
-            let item = ClipboardItem::new_text(format!("History {}", i), None, None);
-            db.insert_item(&item).unwrap();
+        // Second launch doesn't duplicate it
+        db.ensure_default_pinboards().unwrap();
+        assert_eq!(db.get_pinboards(false).unwrap().len(), 1);
+
+        // A user-deleted board stays deleted
+        db.delete_pinboard(&pinboards[0].id, false).unwrap();
+        db.ensure_default_pinboards().unwrap();
+        assert!(db.get_pinboards(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_default_favorites_skipped_for_existing_pinboards() {
+        let db = Database::new_in_memory().unwrap();
+        db.insert_pinboard(&Pinboard::new("Work".to_string(), None, 0)).unwrap();
+
+        db.ensure_default_pinboards().unwrap();
+        let pinboards = db.get_pinboards(false).unwrap();
+        assert_eq!(pinboards.len(), 1);
+        assert_eq!(pinboards[0].name, "Work");
+    }
+
+    #[test]
+    fn test_mirror_favorites_to_pinboard() {
+        let db = Database::new_in_memory().unwrap();
+        db.ensure_default_pinboards().unwrap();
+        let favorites_id = db.get_setting("favorites_pinboard_id").unwrap().unwrap();
+
+        let item = ClipboardItem::new_text("Keep me".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        // Mirroring is off by default
+        db.toggle_item_favorite(&item.id).unwrap();
+        assert_eq!(db.get_item(&item.id).unwrap().unwrap().pinboard_id, None);
+        db.toggle_item_favorite(&item.id).unwrap();
+
+        db.set_setting("mirror_favorites_to_pinboard", "true").unwrap();
+        db.toggle_item_favorite(&item.id).unwrap();
+        assert_eq!(db.get_item(&item.id).unwrap().unwrap().pinboard_id, Some(favorites_id));
+
+        db.toggle_item_favorite(&item.id).unwrap();
+        assert_eq!(db.get_item(&item.id).unwrap().unwrap().pinboard_id, None);
+    }
+
+    #[test]
+    fn test_archive_pinboards() {
+        let db = Database::new_in_memory().unwrap();
+
+        let old = Pinboard::new("Old project".to_string(), None, 0);
+        let old_id = old.id.clone();
+        db.insert_pinboard(&old).unwrap();
+        let current = Pinboard::new("Current".to_string(), None, 1);
+        let current_id = current.id.clone();
+        db.insert_pinboard(&current).unwrap();
+
+        let item = ClipboardItem::new_text("Archived snippet".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        db.update_item_pinboard(&item.id, Some(&old_id)).unwrap();
+
+        assert!(db.set_pinboard_archived(&old_id, true).unwrap());
+        let visible = db.get_pinboards(false).unwrap();
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, current_id);
+        let all = db.get_pinboards(true).unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().find(|p| p.id == old_id).unwrap().archived);
+
+        // Items of archived boards stay protected from pruning
+        db.prune_oldest(0).unwrap();
+        assert_eq!(db.count_pinboard_items(&old_id).unwrap(), 1);
+
+        // Reordering only touches non-archived boards
+        db.reorder_pinboards(None, &[old_id.clone(), current_id.clone()])
+            .unwrap();
+        assert_eq!(db.get_pinboard(&current_id).unwrap().unwrap().position, 0);
+        assert_eq!(db.get_pinboard(&old_id).unwrap().unwrap().position, 0);
+
+        assert!(db.set_pinboard_archived(&old_id, false).unwrap());
+        assert_eq!(db.get_pinboards(false).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_unarchived_pinboard_goes_last() {
+        let db = Database::new_in_memory().unwrap();
+        let ids: Vec<String> = (0..3)
+            .map(|i| {
+                let pinboard = Pinboard::new(format!("Board {}", i), None, i);
+                db.insert_pinboard(&pinboard).unwrap();
+                pinboard.id
+            })
+            .collect();
+        let mut child = Pinboard::new("Child".to_string(), None, 0);
+        child.parent_id = Some(ids[1].clone());
+        db.insert_pinboard(&child).unwrap();
+
+        // Board 0's position is handed out while it is archived
+        db.set_pinboard_archived(&ids[0], true).unwrap();
+        db.reorder_pinboards(None, &[ids[2].clone(), ids[1].clone()])
+            .unwrap();
+        assert!(db.set_pinboard_archived(&ids[0], false).unwrap());
+
+        let positions: Vec<(String, i32)> = db
+            .get_pinboards(false)
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.parent_id.is_none())
+            .map(|p| (p.id, p.position))
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (ids[2].clone(), 0),
+                (ids[1].clone(), 1),
+                (ids[0].clone(), 2)
+            ]
+        );
+
+        // Other levels don't count
+        db.set_pinboard_archived(&child.id, true).unwrap();
+        db.set_pinboard_archived(&child.id, false).unwrap();
+        assert_eq!(db.get_pinboard(&child.id).unwrap().unwrap().position, 0);
+
+        // Unarchiving an active board leaves it in place
+        assert!(db.set_pinboard_archived(&ids[2], false).unwrap());
+        assert_eq!(db.get_pinboard(&ids[2]).unwrap().unwrap().position, 0);
+    }
+
+    #[test]
+    fn test_reorder_pinboards_rolls_back_on_failure() {
+        let db = Database::new_in_memory().unwrap();
+        let ids: Vec<String> = (0..3)
+            .map(|i| {
+                let pinboard = Pinboard::new(format!("Board {}", i), None, i);
+                db.insert_pinboard(&pinboard).unwrap();
+                pinboard.id
+            })
+            .collect();
+
+        // Fail the update of the last board, after the first two were written
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                &format!(
+                    "CREATE TRIGGER fail_reorder BEFORE UPDATE OF position ON pinboards
+                     WHEN NEW.id = '{}' BEGIN SELECT RAISE(ABORT, 'reorder failed'); END",
+                    ids[0]
+                ),
+                [],
+            )
+            .unwrap();
+
+        let reversed: Vec<String> = ids.iter().rev().cloned().collect();
+        assert!(db.reorder_pinboards(None, &reversed).is_err());
+        for (position, id) in ids.iter().enumerate() {
+            assert_eq!(db.get_pinboard(id).unwrap().unwrap().position, position as i32);
         }
+    }
 
-        // Insert 5 pinned items
+    #[test]
+    #[ignore = "benchmark"]
+    fn bench_insert_and_query_10k_items() {
+        use std::time::Instant;
+
+        let db = Database::new_in_memory().unwrap();
+        let items: Vec<ClipboardItem> = (0..10_000)
+            .map(|i| ClipboardItem::new_text(format!("benchmark item {}", i), None, None))
+            .collect();
+
+        let start = Instant::now();
+        for item in &items {
+            db.insert_item(item).unwrap();
+        }
+        let insert = start.elapsed();
+
+        let start = Instant::now();
+        for item in &items {
+            assert!(db.get_item(&item.id).unwrap().is_some());
+        }
+        let cached = start.elapsed();
+
+        // The same lookups re-preparing their statement every time, as before
+        let start = Instant::now();
+        {
+            let conn = db.conn.lock().unwrap();
+            for item in &items {
+                let mut stmt = conn
+                    .prepare(
+                        "SELECT id, content_type, content_text, thumbnail_base64, image_path,
+                                source_app, source_app_icon, created_at, pinboard_id, is_favorite,
+                                auto_pinboard_id, text_path, content_compressed, canonical_url,
+                                source_window_title, source_pasteboard, first_copied_at,
+                            thumbnail_is_favicon, files_summary, use_count, is_directory,
+                            original_width, original_height, downscale_skipped, title,
+                            media_metadata, content_subtype, expires_at, language, palette
+                         FROM clipboard_items
+                         WHERE id = ?1",
+                    )
+                    .unwrap();
+                stmt.query_row(params![item.id], ClipboardItem::from_row).unwrap();
+            }
+        }
+        let uncached = start.elapsed();
+
+        println!(
+            "insert 10k: {:?}, get_item 10k: {:?} cached / {:?} uncached",
+            insert, cached, uncached
+        );
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let db = Database::new_in_memory().unwrap();
+        assert_eq!(db.latest_change_seq().unwrap(), 0);
+
+        let kept = ClipboardItem::new_text("kept".to_string(), None, None);
+        let edited = ClipboardItem::new_text("edited".to_string(), None, None);
+        let removed = ClipboardItem::new_text("removed".to_string(), None, None);
+        db.insert_item(&kept).unwrap();
+        db.insert_item(&edited).unwrap();
+        db.insert_item(&removed).unwrap();
+        let checkpoint = db.latest_change_seq().unwrap();
+        assert_eq!(checkpoint, 3);
+
+        // Nothing new since the checkpoint
+        let delta = db.get_changes_since(checkpoint).unwrap();
+        assert!(!delta.reset);
+        assert!(delta.changes.is_empty());
+        assert_eq!(delta.latest_seq, checkpoint);
+
+        let added = ClipboardItem::new_text("added".to_string(), None, None);
+        db.insert_item(&added).unwrap();
+        db.toggle_item_favorite(&edited.id).unwrap();
+        db.delete_item(&removed.id).unwrap();
+        db.update_thumbnail(&added.id, "thumb", false).unwrap();
+
+        let delta = db.get_changes_since(checkpoint).unwrap();
+        assert_eq!(delta.latest_seq, db.latest_change_seq().unwrap());
+        let summary: Vec<(&str, ItemChangeKind)> = delta
+            .changes
+            .iter()
+            .map(|c| (c.item_id.as_str(), c.kind))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (edited.id.as_str(), ItemChangeKind::Update),
+                (removed.id.as_str(), ItemChangeKind::Delete),
+                // Insert then update collapses to a single insert with the latest state
+                (added.id.as_str(), ItemChangeKind::Insert),
+            ]
+        );
+        assert!(delta.changes[0].item.as_ref().unwrap().is_favorite);
+        assert!(delta.changes[1].item.is_none());
+        assert_eq!(
+            delta.changes[2].item.as_ref().unwrap().thumbnail_base64.as_deref(),
+            Some("thumb")
+        );
+        assert!(delta.changes.windows(2).all(|w| w[0].seq < w[1].seq));
+
+        // Bulk operations are logged too
+        let before_clear = db.latest_change_seq().unwrap();
+        db.clear_history().unwrap();
+        let delta = db.get_changes_since(before_clear).unwrap();
+        assert!(delta.changes.iter().all(|c| c.kind == ItemChangeKind::Delete));
+        assert!(!delta.changes.is_empty());
+    }
+
+    #[test]
+    fn test_changes_since_after_trim_requests_reset() {
+        let db = Database::new_in_memory().unwrap();
         for i in 0..5 {
-            let item = ClipboardItem::new_text(format!("Pinned {}", i), None, None);
-            let item_id = item.id.clone();
+            let item = ClipboardItem::new_text(format!("item {}", i), None, None);
             db.insert_item(&item).unwrap();
-            db.update_item_pinboard(&item_id, Some(&pinboard_id)).unwrap();
         }
 
-        // History count should be 10 (pinned items not counted)
-        assert_eq!(db.count_items().unwrap(), 10);
+        assert_eq!(db.trim_item_events(2).unwrap(), 3);
+        assert_eq!(db.latest_change_seq().unwrap(), 5);
 
-        // Prune to keep only 3 history items
-        db.prune_oldest(3).unwrap();
+        // Events 4 and 5 are still available
+        let delta = db.get_changes_since(3).unwrap();
+        assert!(!delta.reset);
+        assert_eq!(delta.changes.len(), 2);
 
-        // History count should now be 3
-        assert_eq!(db.count_items().unwrap(), 3);
+        // Event 2 and 3 were trimmed
+        let delta = db.get_changes_since(1).unwrap();
+        assert!(delta.reset);
+        assert!(delta.changes.is_empty());
+        assert_eq!(delta.latest_seq, 5);
 
-        // But pinned items should still exist
-        let pinboard_items = db.get_pinboard_items(&pinboard_id, 100).unwrap();
-        assert_eq!(pinboard_items.len(), 5);
+        // Unknown future sequence (e.g. a different database)
+        assert!(db.get_changes_since(99).unwrap().reset);
     }
 
     #[test]
-    fn test_settings() {
+    fn test_changes_since_hides_locked_items() {
         let db = Database::new_in_memory().unwrap();
+        let personal = Pinboard::new("Personal".to_string(), None, 0);
+        db.insert_pinboard(&personal).unwrap();
+        let mut mapping = HashMap::new();
+        mapping.insert("com.apple.Notes".to_string(), personal.id.clone());
+        db.set_app_default_pinboards(&mapping).unwrap();
+
+        let pinned = ClipboardItem::new_text("pinned secret".to_string(), None, None);
+        db.insert_item(&pinned).unwrap();
+        db.update_item_pinboard(&pinned.id, Some(&personal.id))
+            .unwrap();
+        let auto_filed =
+            ClipboardItem::new_text("filed secret".to_string(), Some("Notes".to_string()), None);
+        db.insert_item(&auto_filed).unwrap();
+        db.apply_app_default_pinboard(&auto_filed, "com.apple.Notes")
+            .unwrap();
+        let public = ClipboardItem::new_text("public note".to_string(), None, None);
+        db.insert_item(&public).unwrap();
+        db.set_pinboard_lock_hash(&personal.id, Some("$argon2id$hash"))
+            .unwrap();
 
-        // Default value
-        assert_eq!(db.get_setting("history_limit").unwrap(), Some("500".to_string()));
+        let delta = db.get_changes_since(0).unwrap();
+        let summary: Vec<(&str, ItemChangeKind, bool)> = delta
+            .changes
+            .iter()
+            .map(|c| (c.item_id.as_str(), c.kind, c.item.is_some()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (pinned.id.as_str(), ItemChangeKind::Hidden, false),
+                (auto_filed.id.as_str(), ItemChangeKind::Hidden, false),
+                (public.id.as_str(), ItemChangeKind::Insert, true),
+            ]
+        );
 
-        // Update value
-        db.set_setting("history_limit", "1000").unwrap();
-        assert_eq!(db.get_setting("history_limit").unwrap(), Some("1000".to_string()));
+        db.set_pinboard_lock_hash(&personal.id, None).unwrap();
+        let delta = db.get_changes_since(0).unwrap();
+        assert!(delta.changes.iter().all(|c| c.item.is_some()));
+    }
 
-        // New key
-        db.set_setting("custom_key", "custom_value").unwrap();
-        assert_eq!(db.get_setting("custom_key").unwrap(), Some("custom_value".to_string()));
+    #[test]
+    fn test_locked_pinboard_hidden_from_search() {
+        let db = Database::new_in_memory().unwrap();
+
+        let personal = Pinboard::new("Personal".to_string(), None, 0);
+        let personal_id = personal.id.clone();
+        db.insert_pinboard(&personal).unwrap();
+
+        let secret = ClipboardItem::new_text("secret note".to_string(), None, None);
+        db.insert_item(&secret).unwrap();
+        db.update_item_pinboard(&secret.id, Some(&personal_id)).unwrap();
+        db.insert_item(&ClipboardItem::new_text("public note".to_string(), None, None))
+            .unwrap();
+
+        assert_eq!(db.search_items("note", 10, false).unwrap().len(), 2);
+
+        db.set_pinboard_lock_hash(&personal_id, Some("$argon2id$hash")).unwrap();
+        let pinboard = db.get_pinboard(&personal_id).unwrap().unwrap();
+        assert!(pinboard.is_locked);
+        assert_eq!(pinboard.lock_hash.as_deref(), Some("$argon2id$hash"));
+
+        let results = db.search_items("note", 10, false).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content_text.as_deref(), Some("public note"));
+
+        db.set_pinboard_lock_hash(&personal_id, None).unwrap();
+        assert!(!db.get_pinboard(&personal_id).unwrap().unwrap().is_locked);
+        assert_eq!(db.search_items("note", 10, false).unwrap().len(), 2);
     }
 
     #[test]
-    fn test_pinboards() {
+    fn test_auto_filed_items_of_locked_pinboard_hidden() {
         let db = Database::new_in_memory().unwrap();
+        let no_filter = SearchFilter::default();
 
-        let pinboard = Pinboard::new("Work".to_string(), Some("briefcase".to_string()), 0);
-        let pinboard_id = pinboard.id.clone();
-        db.insert_pinboard(&pinboard).unwrap();
+        let personal = Pinboard::new("Personal".to_string(), None, 0);
+        db.insert_pinboard(&personal).unwrap();
+        db.set_pinboard_lock_hash(&personal.id, Some("$argon2id$hash"))
+            .unwrap();
+        let mut mapping = HashMap::new();
+        mapping.insert("com.apple.Notes".to_string(), personal.id.clone());
+        db.set_app_default_pinboards(&mapping).unwrap();
+
+        let secret =
+            ClipboardItem::new_text("secret note".to_string(), Some("Notes".to_string()), None);
+        db.insert_item(&secret).unwrap();
+        db.apply_app_default_pinboard(&secret, "com.apple.Notes")
+            .unwrap();
+        let public = ClipboardItem::new_text("public note".to_string(), None, None);
+        db.insert_item(&public).unwrap();
 
-        let pinboards = db.get_pinboards().unwrap();
-        assert_eq!(pinboards.len(), 1);
-        assert_eq!(pinboards[0].name, "Work");
+        let ids = |items: Vec<ClipboardItem>| -> Vec<String> {
+            items.into_iter().map(|item| item.id).collect()
+        };
+        let history = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        assert_eq!(ids(history), vec![public.id.clone()]);
+        let by_app = db.get_items_grouped_by_app(10, false).unwrap();
+        assert_eq!(by_app.len(), 1);
+        let results = db.search_items("note", 10, false).unwrap();
+        assert_eq!(ids(results), vec![public.id.clone()]);
+        let deep = db.search_items_deep("note", &no_filter, 10, false).unwrap();
+        assert_eq!(ids(deep), vec![public.id.clone()]);
+        let groups = db
+            .search_items_grouped("note", &no_filter, 10, &[], false, false)
+            .unwrap();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(ids(groups[0].items.clone()), vec![public.id.clone()]);
+
+        // Listed under the board, and searchable while it is unlocked
+        let board_items = db.get_pinboard_items(&personal.id, 10, 0, false).unwrap();
+        assert_eq!(ids(board_items), vec![secret.id.clone()]);
+        let unlocked = vec![personal.id.clone()];
+        let groups = db
+            .search_items_grouped("note", &no_filter, 10, &unlocked, false, false)
+            .unwrap();
+        let found: usize = groups.iter().map(|group| group.items.len()).sum();
+        assert_eq!(found, 2);
 
-        // Add item to pinboard
-        let item = ClipboardItem::new_text("Work item".to_string(), None, None);
-        let item_id = item.id.clone();
+        db.set_pinboard_lock_hash(&personal.id, None).unwrap();
+        let history = db.get_items(10, 0, false, HistorySort::CreatedAt).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(db.search_items("note", 10, false).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_thumbnails_of_locked_pinboard_items_need_unlocking() {
+        let db = Database::new_in_memory().unwrap();
+        let personal = Pinboard::new("Personal".to_string(), None, 0);
+        db.insert_pinboard(&personal).unwrap();
+        db.set_pinboard_lock_hash(&personal.id, Some("$argon2id$hash"))
+            .unwrap();
+
+        let image = ClipboardItem::new_image(
+            Some("thumb".to_string()),
+            "/tmp/a.png".to_string(),
+            None,
+            None,
+        );
+        db.insert_item(&image).unwrap();
+        db.update_item_pinboard(&image.id, Some(&personal.id)).unwrap();
+
+        let ids = [image.id.clone()];
+        assert!(db.get_thumbnails(&ids, &[]).unwrap().is_empty());
+        let unlocked = [personal.id.clone()];
+        assert_eq!(db.get_thumbnails(&ids, &unlocked).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_grouped_search_counts_match_flat_search() {
+        let db = Database::new_in_memory().unwrap();
+        let no_filter = SearchFilter::default();
+        db.set_setting("compress_large_text", "true").unwrap();
+
+        let snippets = Pinboard::new("Snippets".to_string(), None, 0);
+        let refs = Pinboard::new("Design refs".to_string(), None, 1);
+        db.insert_pinboard(&refs).unwrap();
+        db.insert_pinboard(&snippets).unwrap();
+
+        for i in 0..5 {
+            let item = ClipboardItem::new_text(format!("history note {}", i), None, None);
+            db.insert_item(&item).unwrap();
+        }
+        for i in 0..3 {
+            let item = ClipboardItem::new_text(format!("snippet note {}", i), None, None);
+            db.insert_item(&item).unwrap();
+            db.update_item_pinboard(&item.id, Some(&snippets.id)).unwrap();
+        }
+        // Compressed items match on their preview, as in the flat search
+        let long =
+            ClipboardItem::new_text(format!("note {}", "log line\n".repeat(20_000)), None, None);
+        db.insert_item(&long).unwrap();
+        db.update_item_pinboard(&long.id, Some(&refs.id)).unwrap();
+        let tail_only =
+            ClipboardItem::new_text(format!("{}note", "log line\n".repeat(20_000)), None, None);
+        db.insert_item(&tail_only).unwrap();
+        db.update_item_pinboard(&tail_only.id, Some(&refs.id)).unwrap();
+        db.insert_item(&ClipboardItem::new_text("unrelated".to_string(), None, None))
+            .unwrap();
+
+        let groups = db
+            .search_items_grouped("NOTE", &no_filter, 2, &[], false, false)
+            .unwrap();
+        let summary: Vec<(&str, usize, usize)> = groups
+            .iter()
+            .map(|group| (group.name.as_str(), group.total_count, group.items.len()))
+            .collect();
+        // History first, then boards by position
+        assert_eq!(
+            summary,
+            vec![("History", 5, 2), ("Snippets", 3, 2), ("Design refs", 1, 1)]
+        );
+        assert_eq!(groups[0].pinboard_id, None);
+        assert_eq!(groups[1].pinboard_id.as_deref(), Some(snippets.id.as_str()));
+        assert_eq!(groups[0].items[0].content_text.as_deref(), Some("history note 4"));
+
+        let flat = db.search_items("NOTE", 100, false).unwrap();
+        let total: usize = groups.iter().map(|group| group.total_count).sum();
+        assert_eq!(total, flat.len());
+        for group in &groups {
+            let flat_count = flat
+                .iter()
+                .filter(|item| item.pinboard_id == group.pinboard_id)
+                .count();
+            assert_eq!(group.total_count, flat_count);
+        }
+        assert!(db
+            .search_items_grouped("absent", &no_filter, 2, &[], false, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_grouped_search_skips_locked_and_archived_boards() {
+        let db = Database::new_in_memory().unwrap();
+        let no_filter = SearchFilter::default();
+        let secret = Pinboard::new("Secret".to_string(), None, 0);
+        let old = Pinboard::new("Old".to_string(), None, 1);
+        db.insert_pinboard(&secret).unwrap();
+        db.insert_pinboard(&old).unwrap();
+        for board in [&secret, &old] {
+            let item = ClipboardItem::new_text(format!("{} note", board.name), None, None);
+            db.insert_item(&item).unwrap();
+            db.update_item_pinboard(&item.id, Some(&board.id)).unwrap();
+        }
+        db.set_pinboard_lock_hash(&secret.id, Some("$argon2id$hash")).unwrap();
+        db.set_pinboard_archived(&old.id, true).unwrap();
+
+        let names = |groups: Vec<SearchGroup>| -> Vec<String> {
+            groups.into_iter().map(|group| group.name).collect()
+        };
+        assert!(db
+            .search_items_grouped("note", &no_filter, 10, &[], false, false)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            names(
+                db.search_items_grouped("note", &no_filter, 10, &[], true, false)
+                    .unwrap()
+            ),
+            vec!["Old"]
+        );
+        // An unlocked board is searched until it relocks
+        let unlocked = vec![secret.id.clone()];
+        assert_eq!(
+            names(
+                db.search_items_grouped("note", &no_filter, 10, &unlocked, true, false)
+                    .unwrap()
+            ),
+            vec!["Secret", "Old"]
+        );
+        // Archived boards stay out even when unlocked
+        db.set_pinboard_archived(&secret.id, true).unwrap();
+        assert!(db
+            .search_items_grouped("note", &no_filter, 10, &unlocked, false, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_item_revisions_capped() {
+        let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let item = ClipboardItem::new_text("v0".to_string(), None, None);
         db.insert_item(&item).unwrap();
-        db.update_item_pinboard(&item_id, Some(&pinboard_id)).unwrap();
 
-        let pinboard_items = db.get_pinboard_items(&pinboard_id, 10).unwrap();
-        assert_eq!(pinboard_items.len(), 1);
+        for i in 1..=12 {
+            let updated = db
+                .update_item_content(&item.id, &format!("v{}", i), &storage)
+                .unwrap();
+            assert_eq!(updated.unwrap().content_text, Some(format!("v{}", i)));
+        }
+        // Unchanged text doesn't add a revision
+        db.update_item_content(&item.id, "v12", &storage).unwrap();
+
+        let revisions = db.get_item_revisions(&item.id).unwrap();
+        assert_eq!(revisions.len(), MAX_REVISIONS_PER_ITEM as usize);
+        assert_eq!(revisions[0].revision_no, 12);
+        assert_eq!(revisions[0].content_text, "v11");
+        assert_eq!(revisions[9].content_text, "v2");
+
+        assert!(db
+            .update_item_content("missing", "x", &storage)
+            .unwrap()
+            .is_none());
     }
 
     #[test]
-    fn test_content_deduplication() {
+    fn test_restore_revision_saves_current_text() {
         let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let item = ClipboardItem::new_text("original".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        db.update_item_content(&item.id, "edited", &storage)
+            .unwrap();
 
-        let item = ClipboardItem::new_text("Duplicate content".to_string(), None, None);
+        let restored = db
+            .restore_item_revision(&item.id, 1, &storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(restored.content_text.as_deref(), Some("original"));
+        assert_eq!(
+            db.get_item(&item.id).unwrap().unwrap().content_text.as_deref(),
+            Some("original")
+        );
+
+        let revisions = db.get_item_revisions(&item.id).unwrap();
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].content_text, "edited");
+        assert!(db.restore_item_revision(&item.id, 99, &storage).is_err());
+    }
+
+    #[test]
+    fn test_edit_link_and_reject_images() {
+        let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let mut link = ClipboardItem::new_text("https://example.com/a".to_string(), None, None);
+        link.content_type = ContentType::Link;
+        db.insert_item(&link).unwrap();
+
+        let edited = db
+            .update_item_content(&link.id, "https://example.com/b?utm_source=x", &storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.canonical_url.as_deref(), Some("https://example.com/b"));
+
+        let mut image = ClipboardItem::new_text(String::new(), None, None);
+        image.content_type = ContentType::Image;
+        db.insert_item(&image).unwrap();
+        assert!(db.update_item_content(&image.id, "text", &storage).is_err());
+    }
+
+    #[test]
+    fn test_contact_subtype_is_stored_and_follows_edits() {
+        let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let vcard = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Jane Doe\r\nEND:VCARD\r\n";
+        let mut contact = ClipboardItem::new_text(vcard.to_string(), None, None);
+        contact.content_subtype = Some(ContentSubtype::Contact);
+        contact.title = Some("Jane Doe".to_string());
+        db.insert_item(&contact).unwrap();
+
+        let stored = db.get_item(&contact.id).unwrap().unwrap();
+        assert_eq!(stored.content_subtype, Some(ContentSubtype::Contact));
+        // The raw payload is kept byte for byte for copy-back
+        assert_eq!(stored.content_text.as_deref(), Some(vcard));
+        assert_eq!(stored.preview, "Jane Doe");
+
+        let renamed = db
+            .update_item_content(&contact.id, &vcard.replace("Jane", "Janet"), &storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(renamed.title.as_deref(), Some("Janet Doe"));
+        assert_eq!(db.search_items("Janet", 10, false).unwrap().len(), 1);
+
+        let plain = db
+            .update_item_content(&contact.id, "just a note", &storage)
+            .unwrap()
+            .unwrap();
+        assert_eq!(plain.content_subtype, None);
+        assert_eq!(plain.title, None);
+        let stored = db.get_item(&contact.id).unwrap().unwrap();
+        assert_eq!(stored.content_subtype, None);
+        assert_eq!(stored.preview, "just a note");
+    }
+
+    #[test]
+    fn test_append_item_text() {
+        let db = Database::new_in_memory().unwrap();
+        let item = ClipboardItem::new_text("$ cargo test".to_string(), None, None);
         db.insert_item(&item).unwrap();
 
-        assert!(db.content_exists("Duplicate content").unwrap());
-        assert!(!db.content_exists("Non-existent content").unwrap());
+        let merged = db
+            .append_item_text(&item.id, "test result: ok")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            merged.content_text.as_deref(),
+            Some("$ cargo test\ntest result: ok")
+        );
+        let stored = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(stored.content_text, merged.content_text);
+        assert_eq!(stored.preview, merged.preview);
+        assert_eq!(db.search_items("result", 10, false).unwrap().len(), 1);
+        // Merging is not an edit
+        assert!(db.get_item_revisions(&item.id).unwrap().is_empty());
+
+        let mut link = ClipboardItem::new_text("https://example.com".to_string(), None, None);
+        link.content_type = ContentType::Link;
+        db.insert_item(&link).unwrap();
+        assert!(db.append_item_text(&link.id, "more").unwrap().is_none());
+        assert!(db.append_item_text("missing", "more").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_deleting_item_drops_revisions() {
+        let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let deleted = ClipboardItem::new_text("a".to_string(), None, None);
+        let pruned = ClipboardItem::new_text("b".to_string(), None, None);
+        db.insert_item(&pruned).unwrap();
+        db.insert_item(&deleted).unwrap();
+        db.update_item_content(&deleted.id, "a2", &storage).unwrap();
+        db.update_item_content(&pruned.id, "b2", &storage).unwrap();
+
+        assert!(db.delete_item(&deleted.id).unwrap());
+        assert!(db.get_item_revisions(&deleted.id).unwrap().is_empty());
+
+        db.prune_oldest(0).unwrap();
+        assert!(db.get_item_revisions(&pruned.id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_item_errors_are_not_found() {
+        let db = Database::new_in_memory().unwrap();
+        let storage = unused_storage();
+        let error = db.require_item("missing").unwrap_err();
+        assert_eq!(error.code(), "not_found");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({ "code": "not_found", "message": "Item not found" })
+        );
+
+        let item = ClipboardItem::new_text("original".to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        assert_eq!(db.require_item(&item.id).unwrap().id, item.id);
+        let error = db
+            .restore_item_revision(&item.id, 99, &storage)
+            .unwrap_err();
+        assert_eq!(error.code(), "not_found");
     }
 }
@@ -0,0 +1,314 @@
+//! Two-phase confirmation of destructive commands
+//!
+//! `clear_clipboard_history` and `delete_pinboard` delete data in a single
+//! call, so a frontend bug could wipe the history. With the
+//! `require_destructive_confirmation` setting on (the default), a call without
+//! a `confirm_token` deletes nothing: it returns a token and a summary of what
+//! would be deleted. Calling again with the token within `TOKEN_TTL_SECONDS`
+//! runs the command. Tokens are single-use and only valid for the action and
+//! arguments they were issued for. Scripts can turn the setting off.
+
+use crate::error::ClipsterError;
+use crate::storage::Database;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// How long a confirmation token stays valid
+pub const TOKEN_TTL_SECONDS: i64 = 30;
+
+/// A destructive command with the arguments its token is bound to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DestructiveAction {
+    ClearHistory,
+    DeletePinboard { id: String, delete_children: bool },
+}
+
+/// What a destructive command would delete
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct DeletionSummary {
+    /// Items deleted
+    pub items: usize,
+    /// Their text in the database plus their stored image and text files
+    pub bytes: u64,
+    /// Pinboards deleted
+    pub pinboards: usize,
+    /// Items moved back to history because their pinboard is deleted
+    pub items_unpinned: usize,
+}
+
+/// Token to confirm a destructive command with
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Confirmation {
+    pub confirm_token: String,
+    pub summary: DeletionSummary,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outcome of a guarded command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Guarded<T> {
+    /// Nothing was deleted; call again with the token to go ahead
+    ConfirmationRequired(Confirmation),
+    Done {
+        result: T,
+    },
+}
+
+/// Confirmation tokens handed out and not used yet
+#[derive(Default)]
+pub struct DestructiveGuard {
+    /// Token -> action it confirms and its expiry
+    tokens: Mutex<HashMap<String, (DestructiveAction, DateTime<Utc>)>>,
+}
+
+impl DestructiveGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hand out a token for `action`, valid for `TOKEN_TTL_SECONDS` from `now`
+    /// Expired tokens are dropped on the way
+    pub fn issue(
+        &self,
+        action: DestructiveAction,
+        summary: DeletionSummary,
+        now: DateTime<Utc>,
+    ) -> Confirmation {
+        let confirm_token = uuid::Uuid::new_v4().to_string();
+        let expires_at = now + Duration::seconds(TOKEN_TTL_SECONDS);
+        if let Ok(mut tokens) = self.tokens.lock() {
+            tokens.retain(|_, (_, expiry)| *expiry > now);
+            tokens.insert(confirm_token.clone(), (action, expires_at));
+        }
+        Confirmation {
+            confirm_token,
+            summary,
+            expires_at,
+        }
+    }
+
+    /// Use up `token` to confirm `action`
+    /// Fails when the token is unknown, expired or was issued for another action
+    pub fn redeem(
+        &self,
+        token: &str,
+        action: &DestructiveAction,
+        now: DateTime<Utc>,
+    ) -> Result<(), ClipsterError> {
+        let issued = self.tokens.lock()?.remove(token);
+        match issued {
+            Some((issued_for, expiry)) if issued_for == *action && expiry > now => Ok(()),
+            Some((issued_for, _)) if issued_for != *action => Err(ClipsterError::Validation(
+                "Confirmation token was issued for another action".to_string(),
+            )),
+            _ => Err(ClipsterError::Validation(
+                "Confirmation token is invalid or expired".to_string(),
+            )),
+        }
+    }
+
+    /// Run a destructive command, or hand out a token when it needs confirming
+    /// `summarize` is only called to issue a token, `run` only once confirmed
+    /// (or right away when `required` is off)
+    pub fn run<T>(
+        &self,
+        required: bool,
+        action: DestructiveAction,
+        confirm_token: Option<&str>,
+        now: DateTime<Utc>,
+        summarize: impl FnOnce() -> Result<DeletionSummary, ClipsterError>,
+        run: impl FnOnce() -> Result<T, ClipsterError>,
+    ) -> Result<Guarded<T>, ClipsterError> {
+        match (required, confirm_token) {
+            (false, _) => {}
+            (true, Some(token)) => self.redeem(token, &action, now)?,
+            (true, None) => {
+                let summary = summarize()?;
+                return Ok(Guarded::ConfirmationRequired(
+                    self.issue(action, summary, now),
+                ));
+            }
+        }
+        Ok(Guarded::Done { result: run()? })
+    }
+}
+
+/// `require_destructive_confirmation` setting (on unless set to "false")
+pub fn confirmation_required(db: &Database) -> bool {
+    db.get_setting("require_destructive_confirmation")
+        .ok()
+        .flatten()
+        .is_none_or(|value| value != "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(items: usize) -> DeletionSummary {
+        DeletionSummary {
+            items,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_token_confirms_its_action_once() {
+        let guard = DestructiveGuard::new();
+        let now = Utc::now();
+        let confirmation = guard.issue(DestructiveAction::ClearHistory, summary(3), now);
+        assert_eq!(confirmation.summary.items, 3);
+        assert_eq!(confirmation.expires_at, now + Duration::seconds(30));
+
+        let token = confirmation.confirm_token;
+        assert!(guard
+            .redeem(&token, &DestructiveAction::ClearHistory, now)
+            .is_ok());
+        // Single use
+        let reused = guard.redeem(&token, &DestructiveAction::ClearHistory, now);
+        assert_eq!(reused.unwrap_err().code(), "validation");
+        let unknown = guard.redeem("nope", &DestructiveAction::ClearHistory, now);
+        assert_eq!(unknown.unwrap_err().code(), "validation");
+    }
+
+    #[test]
+    fn test_token_expires_and_is_bound_to_arguments() {
+        let guard = DestructiveGuard::new();
+        let now = Utc::now();
+        let clear = DestructiveAction::ClearHistory;
+
+        let token = guard.issue(clear.clone(), summary(1), now).confirm_token;
+        let late = now + Duration::seconds(TOKEN_TTL_SECONDS);
+        assert!(guard.redeem(&token, &clear, late).is_err());
+
+        let delete = |id: &str, delete_children| DestructiveAction::DeletePinboard {
+            id: id.to_string(),
+            delete_children,
+        };
+        let token = guard
+            .issue(delete("a", false), summary(0), now)
+            .confirm_token;
+        assert!(guard.redeem(&token, &delete("b", false), now).is_err());
+        let token = guard
+            .issue(delete("a", false), summary(0), now)
+            .confirm_token;
+        assert!(guard.redeem(&token, &delete("a", true), now).is_err());
+        let token = guard
+            .issue(delete("a", false), summary(0), now)
+            .confirm_token;
+        let just_in_time = now + Duration::seconds(TOKEN_TTL_SECONDS - 1);
+        assert!(guard
+            .redeem(&token, &delete("a", false), just_in_time)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_issue_drops_expired_tokens() {
+        let guard = DestructiveGuard::new();
+        let now = Utc::now();
+        guard.issue(DestructiveAction::ClearHistory, summary(1), now);
+        guard.issue(DestructiveAction::ClearHistory, summary(1), now);
+        let later = now + Duration::seconds(TOKEN_TTL_SECONDS + 1);
+        guard.issue(DestructiveAction::ClearHistory, summary(1), later);
+        assert_eq!(guard.tokens.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_run_needs_a_token_when_required() {
+        let guard = DestructiveGuard::new();
+        let now = Utc::now();
+        let mut runs = 0;
+
+        let outcome = guard
+            .run(
+                true,
+                DestructiveAction::ClearHistory,
+                None,
+                now,
+                || Ok(summary(7)),
+                || {
+                    runs += 1;
+                    Ok(7)
+                },
+            )
+            .unwrap();
+        let Guarded::ConfirmationRequired(confirmation) = outcome else {
+            panic!("expected a confirmation, got {:?}", outcome);
+        };
+        assert_eq!(confirmation.summary, summary(7));
+        assert_eq!(runs, 0);
+
+        let outcome = guard
+            .run(
+                true,
+                DestructiveAction::ClearHistory,
+                Some(&confirmation.confirm_token),
+                now,
+                || panic!("no summary once confirmed"),
+                || {
+                    runs += 1;
+                    Ok(7)
+                },
+            )
+            .unwrap();
+        assert_eq!(outcome, Guarded::Done { result: 7 });
+        assert_eq!(runs, 1);
+
+        // A bad token runs nothing
+        let outcome = guard.run(
+            true,
+            DestructiveAction::ClearHistory,
+            Some("stale"),
+            now,
+            || Ok(summary(0)),
+            || {
+                runs += 1;
+                Ok(0)
+            },
+        );
+        assert!(outcome.is_err());
+        assert_eq!(runs, 1);
+    }
+
+    #[test]
+    fn test_run_without_confirmation() {
+        let guard = DestructiveGuard::new();
+        let outcome = guard
+            .run(
+                false,
+                DestructiveAction::ClearHistory,
+                None,
+                Utc::now(),
+                || panic!("no summary when confirmation is off"),
+                || Ok(2),
+            )
+            .unwrap();
+        assert_eq!(outcome, Guarded::Done { result: 2 });
+    }
+
+    #[test]
+    fn test_serialized_outcomes() {
+        let done = serde_json::to_value(Guarded::Done { result: 4 }).unwrap();
+        assert_eq!(done, serde_json::json!({ "status": "done", "result": 4 }));
+
+        let guard = DestructiveGuard::new();
+        let confirmation = guard.issue(DestructiveAction::ClearHistory, summary(2), Utc::now());
+        let json =
+            serde_json::to_value(Guarded::<usize>::ConfirmationRequired(confirmation)).unwrap();
+        assert_eq!(json["status"], "confirmation_required");
+        assert_eq!(json["summary"]["items"], 2);
+        assert!(json["confirm_token"].is_string());
+    }
+
+    #[test]
+    fn test_confirmation_setting_defaults_on() {
+        let db = Database::new_in_memory().unwrap();
+        assert!(confirmation_required(&db));
+        db.set_setting("require_destructive_confirmation", "false")
+            .unwrap();
+        assert!(!confirmation_required(&db));
+    }
+}
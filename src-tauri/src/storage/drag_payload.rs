@@ -0,0 +1,393 @@
+//! Temporary files for dragging items out of the panel
+//!
+//! Each drag gets its own subdirectory under `<temp>/clipster-drag/<item id>/`
+//! so readable filenames never collide. Subdirectories older than
+//! `DRAG_PAYLOAD_TTL` are removed at launch and, while the app runs, by one
+//! cleanup thread that `CleanupScheduler` wakes an hour after a drag.
+
+use crate::clipboard::event_coalescer::{Clock, SystemClock};
+use crate::models::{ClipboardItem, ContentType};
+use crate::preview;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long drag payloads are kept before being cleaned up
+pub const DRAG_PAYLOAD_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// How often the cleanup thread checks whether a cleanup is due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum length of the readable part of a payload filename
+const MAX_FILENAME_LEN: usize = 40;
+
+/// Temp directory holding materialized drag payloads
+#[derive(Debug, Clone)]
+pub struct DragPayloads {
+    root: PathBuf,
+}
+
+impl DragPayloads {
+    /// Use the default `<system temp>/clipster-drag` directory
+    pub fn new() -> Self {
+        Self::with_root(std::env::temp_dir().join("clipster-drag"))
+    }
+
+    /// Use a custom root directory (used by tests)
+    pub fn with_root(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Materialize an item as draggable file(s) and return their paths
+    /// File items return their original paths (missing files are filtered out)
+    pub fn prepare(&self, item: &ClipboardItem) -> Result<Vec<String>, String> {
+        if let Some(paths) = item.get_file_paths() {
+            return Ok(paths
+                .into_iter()
+                .filter(|p| Path::new(p).exists())
+                .collect());
+        }
+
+        let dir = self.root.join(&item.id);
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create drag directory: {}", e))?;
+
+        let name = payload_filename(item);
+        let path = match item.content_type {
+            ContentType::Image => {
+                let source = item
+                    .image_path
+                    .as_deref()
+                    .ok_or_else(|| "Image item has no stored file".to_string())?;
+                // Stored images are not all PNG (TIFF is kept as copied)
+                let extension = Path::new(source)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or("png");
+                let dest = dir.join(format!("{}.{}", name, extension));
+                fs::copy(source, &dest)
+                    .map_err(|e| format!("Failed to copy image for drag: {}", e))?;
+                dest
+            }
+            ContentType::Link => {
+                let url = item.content_text.as_deref().unwrap_or("");
+                let (extension, content) = link_file_content(url);
+                let dest = dir.join(format!("{}.{}", name, extension));
+                fs::write(&dest, content)
+                    .map_err(|e| format!("Failed to write link file: {}", e))?;
+                dest
+            }
+            _ => {
+                let dest = dir.join(format!("{}.txt", name));
+                fs::write(&dest, item.content_text.as_deref().unwrap_or(""))
+                    .map_err(|e| format!("Failed to write text file: {}", e))?;
+                dest
+            }
+        };
+
+        // Remove quarantine attribute so Finder and Quick Look accept the file
+        #[cfg(target_os = "macos")]
+        {
+            let _ = std::process::Command::new("xattr")
+                .args(["-d", "com.apple.quarantine", path.to_str().unwrap_or("")])
+                .output();
+        }
+
+        path.to_str()
+            .map(|s| vec![s.to_string()])
+            .ok_or_else(|| "Failed to convert path to string".to_string())
+    }
+
+    /// Remove payload directories last modified more than `max_age` before `now`
+    /// Returns the number of directories removed
+    pub fn cleanup_expired(&self, max_age: Duration, now: SystemTime) -> Result<usize, String> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(format!("Failed to read drag directory: {}", e)),
+        };
+
+        let mut removed = 0;
+        for entry in entries.flatten() {
+            let modified = match entry.metadata().and_then(|m| m.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+            let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+            if age < max_age {
+                continue;
+            }
+
+            let path = entry.path();
+            let result = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            if result.is_ok() {
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Whether any payload is left in the directory
+    pub fn has_payloads(&self) -> bool {
+        fs::read_dir(&self.root).is_ok_and(|mut entries| entries.next().is_some())
+    }
+}
+
+impl Default for DragPayloads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decides when the cleanup thread removes expired payloads
+/// The first drag after a cleanup makes one due `DRAG_PAYLOAD_TTL` later;
+/// later drags don't push it back, so a payload is gone within two TTLs
+#[derive(Debug)]
+pub struct CleanupScheduler<C: Clock = SystemClock> {
+    clock: C,
+    /// `clock.now()` and the wall time at creation: payload ages are measured
+    /// on `clock` too
+    started: (Instant, SystemTime),
+    /// None while no payload waits for a cleanup
+    due: Option<Instant>,
+}
+
+impl<C: Clock> CleanupScheduler<C> {
+    pub fn new(clock: C) -> Self {
+        let started = (clock.now(), SystemTime::now());
+        Self {
+            clock,
+            started,
+            due: None,
+        }
+    }
+
+    /// Note that a payload was prepared
+    pub fn schedule(&mut self) {
+        if self.due.is_none() {
+            self.due = Some(self.clock.now() + DRAG_PAYLOAD_TTL);
+        }
+    }
+
+    /// Remove expired payloads if a cleanup is due; returns how many, None if
+    /// none ran. Payloads that are still too young make another one due
+    pub fn tick(&mut self, payloads: &DragPayloads) -> Result<Option<usize>, String> {
+        let now = self.clock.now();
+        if self.due.is_none_or(|due| due > now) {
+            return Ok(None);
+        }
+
+        let wall_now = self.started.1 + now.duration_since(self.started.0);
+        let removed = payloads.cleanup_expired(DRAG_PAYLOAD_TTL, wall_now)?;
+        self.due = payloads.has_payloads().then(|| now + DRAG_PAYLOAD_TTL);
+        Ok(Some(removed))
+    }
+}
+
+/// Start the thread running the cleanups of `scheduler` on the default directory
+pub fn start_cleanup(scheduler: Arc<Mutex<CleanupScheduler>>) {
+    let payloads = DragPayloads::new();
+    thread::spawn(move || loop {
+        thread::sleep(CHECK_INTERVAL);
+        let result = match scheduler.lock() {
+            Ok(mut scheduler) => scheduler.tick(&payloads),
+            Err(_) => return,
+        };
+        match result {
+            Ok(Some(removed)) if removed > 0 => {
+                println!("[drag_payload] Removed {} expired drag payloads", removed)
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("[drag_payload] Cleanup failed: {}", e),
+        }
+    });
+}
+
+/// Build a filesystem-safe, readable filename (without extension) for an item
+fn payload_filename(item: &ClipboardItem) -> String {
+    let preview = preview::preview_item(item, MAX_FILENAME_LEN * 2);
+    let cleaned: String = preview
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let name = cleaned
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_FILENAME_LEN)
+        .collect::<String>()
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .to_string();
+
+    if name.is_empty() {
+        format!("clipster-{}", &item.id[..item.id.len().min(8)])
+    } else {
+        name
+    }
+}
+
+/// Link file extension and content for the current platform
+/// `.webloc` plist on macOS, `.url` internet shortcut elsewhere
+pub fn link_file_content(url: &str) -> (&'static str, String) {
+    if cfg!(target_os = "macos") {
+        (
+            "webloc",
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>URL</key>
+	<string>{}</string>
+</dict>
+</plist>"#,
+                url
+            ),
+        )
+    } else {
+        ("url", format!("[InternetShortcut]\r\nURL={}\r\n", url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{temp_dir, FakeClock, TempDir};
+
+    /// Payloads under a temp root, removed with the returned guard
+    fn temp_payloads() -> (TempDir, DragPayloads) {
+        let root = temp_dir("drag-test");
+        let payloads = DragPayloads::with_root(root.to_path_buf());
+        (root, payloads)
+    }
+
+    #[test]
+    fn test_prepare_text_and_link() {
+        let (_root, payloads) = temp_payloads();
+
+        let text = ClipboardItem::new_text("Hello / world: notes".to_string(), None, None);
+        let paths = payloads.prepare(&text).unwrap();
+        assert_eq!(paths.len(), 1);
+        assert!(paths[0].ends_with("Hello world notes.txt"));
+        assert_eq!(fs::read_to_string(&paths[0]).unwrap(), "Hello / world: notes");
+
+        let link = ClipboardItem::new_link("https://example.com/page".to_string(), None, None);
+        let paths = payloads.prepare(&link).unwrap();
+        let content = fs::read_to_string(&paths[0]).unwrap();
+        assert!(content.contains("https://example.com/page"));
+        assert!(paths[0].ends_with(".webloc") || paths[0].ends_with(".url"));
+    }
+
+    #[test]
+    fn test_prepare_image_copies_stored_file() {
+        let (_root, payloads) = temp_payloads();
+        fs::create_dir_all(payloads.root()).unwrap();
+        let source = payloads.root().join("source.png");
+        fs::write(&source, b"png-bytes").unwrap();
+
+        let mut item =
+            ClipboardItem::new_image(None, source.to_string_lossy().to_string(), None, None);
+        let paths = payloads.prepare(&item).unwrap();
+        assert!(paths[0].ends_with(".png"));
+        assert_eq!(fs::read(&paths[0]).unwrap(), b"png-bytes");
+
+        // Keeps the stored file's format
+        let tiff = payloads.root().join("source.tiff");
+        fs::write(&tiff, b"tiff-bytes").unwrap();
+        item.image_path = Some(tiff.to_string_lossy().to_string());
+        let paths = payloads.prepare(&item).unwrap();
+        assert!(paths[0].ends_with(".tiff"));
+        assert_eq!(fs::read(&paths[0]).unwrap(), b"tiff-bytes");
+
+        item.image_path = None;
+        assert!(payloads.prepare(&item).is_err());
+    }
+
+    #[test]
+    fn test_prepare_files_filters_missing() {
+        let (_root, payloads) = temp_payloads();
+        fs::create_dir_all(payloads.root()).unwrap();
+        let existing = payloads.root().join("exists.txt");
+        fs::write(&existing, "x").unwrap();
+        let existing = existing.to_string_lossy().to_string();
+
+        let item = ClipboardItem::new_files(
+            vec![existing.clone(), "/nonexistent/clipster/file.txt".to_string()],
+            None,
+            None,
+        );
+        assert_eq!(payloads.prepare(&item).unwrap(), vec![existing]);
+    }
+
+    #[test]
+    fn test_cleanup_expired() {
+        let (_root, payloads) = temp_payloads();
+        let item = ClipboardItem::new_text("cleanup me".to_string(), None, None);
+        payloads.prepare(&item).unwrap();
+
+        let now = SystemTime::now();
+        // Fresh payloads are kept
+        assert_eq!(payloads.cleanup_expired(DRAG_PAYLOAD_TTL, now).unwrap(), 0);
+        assert!(payloads.root().join(&item.id).exists());
+
+        // An hour later they are removed
+        let later = now + DRAG_PAYLOAD_TTL + Duration::from_secs(1);
+        assert_eq!(payloads.cleanup_expired(DRAG_PAYLOAD_TTL, later).unwrap(), 1);
+        assert!(!payloads.root().join(&item.id).exists());
+
+        // Missing root is not an error
+        let _ = fs::remove_dir_all(payloads.root());
+        assert_eq!(payloads.cleanup_expired(DRAG_PAYLOAD_TTL, later).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_cleanup_scheduler() {
+        let (_root, payloads) = temp_payloads();
+        let clock = FakeClock::new();
+        let mut scheduler = CleanupScheduler::new(clock.clone());
+        let second = Duration::from_secs(1);
+
+        // Nothing is due before the first drag
+        clock.advance(DRAG_PAYLOAD_TTL * 2);
+        assert_eq!(scheduler.tick(&payloads).unwrap(), None);
+
+        let item = ClipboardItem::new_text("dragged".to_string(), None, None);
+        payloads.prepare(&item).unwrap();
+        scheduler.schedule();
+        clock.advance(DRAG_PAYLOAD_TTL - second);
+        // Another drag doesn't push the cleanup back
+        scheduler.schedule();
+        assert_eq!(scheduler.tick(&payloads).unwrap(), None);
+        assert!(payloads.root().join(&item.id).exists());
+
+        clock.advance(second * 2);
+        assert_eq!(scheduler.tick(&payloads).unwrap(), Some(1));
+        assert!(!payloads.has_payloads());
+
+        // Nothing left, so no cleanup is due until the next drag
+        clock.advance(DRAG_PAYLOAD_TTL * 2);
+        assert_eq!(scheduler.tick(&payloads).unwrap(), None);
+        payloads.prepare(&item).unwrap();
+        scheduler.schedule();
+        clock.advance(DRAG_PAYLOAD_TTL + second);
+        assert_eq!(scheduler.tick(&payloads).unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,224 @@
+//! Grouping of duplicate items for the duplicates report
+//!
+//! Years of history collect the same content several times: text captured
+//! before move-to-top dedupe existed, or one picture copied from different
+//! apps. Exact duplicates share a content hash. Images are also compared by a
+//! difference hash (dHash) of their stored file, which survives resizing and
+//! recompression, so near-identical pictures group together.
+
+use crate::models::{ClipboardItem, ContentType};
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+
+/// Most differing dHash bits for two images to count as the same picture
+pub const DHASH_MAX_DISTANCE: u32 = 10;
+
+/// dHash grid: each of the 8 rows compares 9 neighbouring samples
+const DHASH_WIDTH: u32 = 9;
+const DHASH_HEIGHT: u32 = 8;
+
+/// Hash of an item's type and full content, for exact matching
+/// Image items hash their stored file; items without content have no key
+pub fn exact_key(item: &ClipboardItem) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    item.content_type.as_str().hash(&mut hasher);
+    match item.content_type {
+        ContentType::Image => {
+            let path = item.image_path.as_deref()?;
+            match fs::read(path) {
+                Ok(bytes) => bytes.hash(&mut hasher),
+                Err(e) => {
+                    eprintln!("[duplicates] Failed to read {}: {}", path, e);
+                    return None;
+                }
+            }
+        }
+        _ => item.content_text.as_deref()?.hash(&mut hasher),
+    }
+    Some(hasher.finish())
+}
+
+/// Difference hash of an image: one bit per pair of horizontal neighbours in a
+/// 9x8 grayscale thumbnail, set where brightness increases to the right
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(DHASH_WIDTH, DHASH_HEIGHT, FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..DHASH_HEIGHT {
+        for x in 0..DHASH_WIDTH - 1 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(right > left);
+        }
+    }
+    hash
+}
+
+/// Number of bits that differ between two hashes
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// IDs sharing a key, in input order; only groups of two or more are returned
+pub fn group_exact(keys: &[(String, u64)]) -> Vec<Vec<String>> {
+    let mut index_of: HashMap<u64, usize> = HashMap::new();
+    let mut groups: Vec<Vec<String>> = Vec::new();
+    for (id, key) in keys {
+        match index_of.get(key) {
+            Some(&index) => groups[index].push(id.clone()),
+            None => {
+                index_of.insert(*key, groups.len());
+                groups.push(vec![id.clone()]);
+            }
+        }
+    }
+
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// IDs whose hashes are within `max_distance` of each other, directly or
+/// through other members of the group, in input order
+pub fn group_similar(hashes: &[(String, u64)], max_distance: u32) -> Vec<Vec<String>> {
+    // Union-find over the input indices
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if hamming_distance(hashes[i].1, hashes[j].1) <= max_distance {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                // The earlier index stays the root, keeping groups in input order
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let roots: Vec<u64> = (0..hashes.len())
+        .map(|i| root(&mut parent, i) as u64)
+        .collect();
+    let keyed: Vec<(String, u64)> = hashes
+        .iter()
+        .zip(roots)
+        .map(|((id, _), root)| (id.clone(), root))
+        .collect();
+    group_exact(&keyed)
+}
+
+/// Representative of `i`'s set, halving the path on the way up
+fn root(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{Rgb, RgbImage};
+    use std::io::Cursor;
+
+    /// A photo-like test picture: soft gradients with a bright disc off-center
+    fn picture() -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(320, 240, |x, y| {
+            let (dx, dy) = (x as i32 - 220, y as i32 - 90);
+            if dx * dx + dy * dy < 50 * 50 {
+                Rgb([250, 230, 120])
+            } else {
+                Rgb([(x * 255 / 320) as u8, (y * 255 / 240) as u8, 90])
+            }
+        }))
+    }
+
+    /// `image` saved as a JPEG at `quality` and decoded again
+    fn recompress(image: &DynamicImage, quality: u8) -> DynamicImage {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut Cursor::new(&mut jpeg), quality)
+            .encode_image(image)
+            .unwrap();
+        image::load_from_memory(&jpeg).unwrap()
+    }
+
+    #[test]
+    fn test_dhash_survives_recompression() {
+        let original = picture();
+        let hash = dhash(&original);
+
+        for quality in [90, 70, 40] {
+            let variant = dhash(&recompress(&original, quality));
+            assert!(
+                hamming_distance(hash, variant) <= DHASH_MAX_DISTANCE,
+                "quality {} differs by {} bits",
+                quality,
+                hamming_distance(hash, variant)
+            );
+        }
+
+        // Downscaled and recompressed, as another app might have saved it
+        let smaller = recompress(&original.resize(160, 120, FilterType::Triangle), 80);
+        assert!(hamming_distance(hash, dhash(&smaller)) <= DHASH_MAX_DISTANCE);
+    }
+
+    #[test]
+    fn test_dhash_tells_different_pictures_apart() {
+        let original = picture();
+        let mirrored = original.fliph();
+        assert!(hamming_distance(dhash(&original), dhash(&mirrored)) > DHASH_MAX_DISTANCE);
+    }
+
+    #[test]
+    fn test_hamming_distance() {
+        assert_eq!(hamming_distance(0, 0), 0);
+        assert_eq!(hamming_distance(0b1011, 0b0001), 2);
+        assert_eq!(hamming_distance(u64::MAX, 0), 64);
+    }
+
+    fn keyed(entries: &[(&str, u64)]) -> Vec<(String, u64)> {
+        entries
+            .iter()
+            .map(|(id, key)| (id.to_string(), *key))
+            .collect()
+    }
+
+    #[test]
+    fn test_group_exact_keeps_input_order() {
+        let groups = group_exact(&keyed(&[("a", 1), ("b", 2), ("c", 1), ("d", 3), ("e", 2)]));
+        assert_eq!(groups, vec![vec!["a", "c"], vec!["b", "e"]]);
+        assert!(group_exact(&keyed(&[("a", 1), ("b", 2)])).is_empty());
+    }
+
+    #[test]
+    fn test_group_similar_is_transitive() {
+        // b is close to both a and c, which are too far apart on their own
+        let hashes = keyed(&[
+            ("a", 0),
+            ("far", u64::MAX),
+            ("b", 0xFF),
+            ("c", 0xFFFF),
+            ("alone", 0xFFFF_FFFF_0000_0000),
+        ]);
+        assert_eq!(group_similar(&hashes, 8), vec![vec!["a", "b", "c"]]);
+        assert!(group_similar(&hashes, 4).is_empty());
+    }
+
+    #[test]
+    fn test_exact_key_compares_type_and_content() {
+        let text = ClipboardItem::new_text("example.com".to_string(), None, None);
+        let same = ClipboardItem::new_text("example.com".to_string(), None, None);
+        let link = ClipboardItem::new_link("example.com".to_string(), None, None);
+        assert_eq!(exact_key(&text), exact_key(&same));
+        assert_ne!(exact_key(&text), exact_key(&link));
+
+        let mut missing_image = ClipboardItem::new_text(String::new(), None, None);
+        missing_image.content_type = ContentType::Image;
+        missing_image.image_path = Some("/nonexistent/clipster.png".to_string());
+        assert_eq!(exact_key(&missing_image), None);
+    }
+}
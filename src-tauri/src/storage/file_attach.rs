@@ -0,0 +1,178 @@
+//! Attaching a file from disk to a pinboard
+//!
+//! `add_file_to_pinboard` puts a file on a board without going through the
+//! system clipboard. Supported images are imported into `FileStorage` with a
+//! thumbnail, like the folder import. Any other file, an image that doesn't
+//! decode, or a folder becomes a file item pointing at the path in place
+//! (Files, or Audio and Documents by extension, like a copied file).
+//! The item can keep the file's modification time as its `created_at`.
+
+use crate::clipboard::folder_summary::{self, DIRECTORY_SCAN_CAP};
+use crate::error::ClipsterError;
+use crate::models::ClipboardItem;
+use crate::storage::image_import::{import_image, is_supported_image};
+use crate::storage::{Database, FileStorage};
+use chrono::{DateTime, Utc};
+use std::fs;
+use std::path::Path;
+
+/// Build the item for the file at `path` on a pinboard
+/// Fails when `path` is relative or doesn't exist
+pub fn attach_file(
+    storage: &FileStorage,
+    path: &Path,
+    pinboard_id: &str,
+    keep_mtime: bool,
+) -> Result<ClipboardItem, ClipsterError> {
+    if !path.is_absolute() {
+        return Err(ClipsterError::Validation(format!(
+            "Path must be absolute: {}",
+            path.display()
+        )));
+    }
+    let metadata = fs::metadata(path)
+        .map_err(|_| ClipsterError::Validation(format!("File not found: {}", path.display())))?;
+
+    let image = if metadata.is_file() && is_supported_image(path) {
+        import_image(storage, path, pinboard_id)
+            .inspect_err(|e| eprintln!("[file_attach] Adding {:?} as a file: {}", path, e))
+            .ok()
+    } else {
+        None
+    };
+    let mut item = match image {
+        Some(item) => item,
+        None => file_item(path, metadata.is_dir(), pinboard_id),
+    };
+
+    // import_image dates images with their mtime
+    item.created_at = match metadata.modified() {
+        Ok(modified) if keep_mtime => DateTime::<Utc>::from(modified),
+        _ => item.first_copied_at,
+    };
+    Ok(item)
+}
+
+/// File item referencing `path`, with a folder summary for directories
+fn file_item(path: &Path, is_directory: bool, pinboard_id: &str) -> ClipboardItem {
+    let mut item = ClipboardItem::new_files(vec![path.to_string_lossy().to_string()], None, None);
+    if is_directory {
+        item.is_directory = true;
+        item.files_summary = folder_summary::scan_directory(path, DIRECTORY_SCAN_CAP)
+            .map(|summary| folder_summary::describe(path, &summary))
+            .ok();
+        item.refresh_preview();
+    }
+    item.pinboard_id = Some(pinboard_id.to_string());
+    item
+}
+
+/// Attach the file at `path` to a pinboard and save it; returns the new item
+/// An imported image is removed again if the item can't be saved
+pub fn add_file_to_pinboard(
+    db: &Database,
+    storage: &FileStorage,
+    path: &Path,
+    pinboard_id: &str,
+    keep_mtime: bool,
+) -> Result<ClipboardItem, ClipsterError> {
+    let item = attach_file(storage, path, pinboard_id, keep_mtime)?;
+    db.insert_item(&item).inspect_err(|_| {
+        if let Some(image_path) = &item.image_path {
+            let _ = fs::remove_file(image_path);
+        }
+    })?;
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ContentType, Pinboard};
+    use crate::test_support::temp_dir;
+    use image::{ImageFormat, Rgb, RgbImage};
+    use std::path::PathBuf;
+
+    fn setup(root: &Path) -> (Database, FileStorage, Pinboard) {
+        fs::create_dir_all(root).unwrap();
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+        let board = Pinboard::new("Inbox".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+        (db, storage, board)
+    }
+
+    fn mtime(path: &Path) -> DateTime<Utc> {
+        fs::metadata(path).unwrap().modified().unwrap().into()
+    }
+
+    #[test]
+    fn test_image_is_imported_with_thumbnail() {
+        let root = temp_dir("attach");
+        let (db, storage, board) = setup(&root);
+        let photo = root.join("photo.jpg");
+        RgbImage::from_pixel(8, 6, Rgb([10, 200, 10]))
+            .save_with_format(&photo, ImageFormat::Jpeg)
+            .unwrap();
+
+        let item = add_file_to_pinboard(&db, &storage, &photo, &board.id, true).unwrap();
+        assert_eq!(item.content_type, ContentType::Image);
+        assert!(item.thumbnail_base64.is_some());
+        assert_eq!(
+            PathBuf::from(item.image_path.as_ref().unwrap()),
+            storage.get_image_path(&item.id)
+        );
+        assert_eq!(item.created_at.timestamp(), mtime(&photo).timestamp());
+
+        let saved = db.get_pinboard_items(&board.id, 10, 0, true).unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].id, item.id);
+    }
+
+    #[test]
+    fn test_other_files_are_referenced_in_place() {
+        let root = temp_dir("attach");
+        let (db, storage, board) = setup(&root);
+        let notes = root.join("notes.zip");
+        fs::write(&notes, "meeting notes").unwrap();
+        // Not an image after all
+        let broken = root.join("broken.png");
+        fs::write(&broken, "not really a png").unwrap();
+        let folder = root.join("refs");
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("a.txt"), "a").unwrap();
+
+        let item = add_file_to_pinboard(&db, &storage, &notes, &board.id, false).unwrap();
+        assert_eq!(item.content_type, ContentType::Files);
+        assert_eq!(
+            item.get_file_paths(),
+            Some(vec![notes.to_string_lossy().to_string()])
+        );
+        assert_eq!(item.pinboard_id.as_deref(), Some(board.id.as_str()));
+        assert!(item.image_path.is_none());
+        assert_eq!(item.created_at, item.first_copied_at);
+
+        let item = add_file_to_pinboard(&db, &storage, &broken, &board.id, false).unwrap();
+        assert_eq!(item.content_type, ContentType::Files);
+
+        let item = add_file_to_pinboard(&db, &storage, &folder, &board.id, true).unwrap();
+        assert!(item.is_directory);
+        assert!(item.files_summary.is_some());
+        assert_eq!(item.created_at.timestamp(), mtime(&folder).timestamp());
+
+        assert_eq!(db.count_pinboard_items(&board.id).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_missing_and_relative_paths_are_rejected() {
+        let root = temp_dir("attach");
+        let (db, storage, board) = setup(&root);
+
+        let missing = add_file_to_pinboard(&db, &storage, &root.join("gone.txt"), &board.id, false);
+        assert_eq!(missing.unwrap_err().code(), "validation");
+        let relative =
+            add_file_to_pinboard(&db, &storage, Path::new("notes.txt"), &board.id, false);
+        assert_eq!(relative.unwrap_err().code(), "validation");
+        assert_eq!(db.count_pinboard_items(&board.id).unwrap(), 0);
+    }
+}
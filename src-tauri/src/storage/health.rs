@@ -0,0 +1,194 @@
+//! Storage health check for support triage
+//!
+//! Every check runs independently and records its own success or failure, so
+//! one broken check never hides the results of the others. Checks only hold
+//! the database lock for the duration of a single query, which keeps them safe
+//! to run while clipboard capture continues.
+
+use crate::error::ClipsterError;
+use crate::storage::{Database, FileStorage};
+use serde::Serialize;
+use std::path::Path;
+
+/// Outcome of a single health check
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Check<T> {
+    Ok { value: T },
+    Failed { error: String },
+}
+
+impl<T> Check<T> {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Check::Ok { .. })
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Check::Ok { value } => Some(value),
+            Check::Failed { .. } => None,
+        }
+    }
+}
+
+impl<T> From<Result<T, ClipsterError>> for Check<T> {
+    fn from(result: Result<T, ClipsterError>) -> Self {
+        match result {
+            Ok(value) => Check::Ok { value },
+            Err(error) => Check::Failed {
+                error: error.to_string(),
+            },
+        }
+    }
+}
+
+/// State of the images directory
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImagesDirStatus {
+    pub path: String,
+    pub exists: bool,
+    pub writable: bool,
+}
+
+/// Structured result of `run_health_check`
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// `PRAGMA integrity_check` output ("ok" when healthy)
+    pub integrity: Check<String>,
+    pub db_size_bytes: Check<u64>,
+    pub wal_size_bytes: Check<u64>,
+    pub images_dir: Check<ImagesDirStatus>,
+    /// Items whose `image_path` no longer exists on disk
+    pub missing_image_files: Check<usize>,
+    /// Files in the images directory not referenced by any item
+    pub orphaned_image_files: Check<usize>,
+    /// Keys of settings whose stored value cannot be parsed
+    pub invalid_settings: Check<Vec<String>>,
+    /// Milliseconds since the clipboard monitor last reported in (None if never)
+    pub monitor_heartbeat_age_ms: Option<u64>,
+}
+
+impl HealthReport {
+    /// Whether every check passed and found no problems
+    pub fn is_healthy(&self) -> bool {
+        self.integrity.value().map(|v| v == "ok").unwrap_or(false)
+            && self.db_size_bytes.is_ok()
+            && self.wal_size_bytes.is_ok()
+            && self
+                .images_dir
+                .value()
+                .map(|d| d.exists && d.writable)
+                .unwrap_or(false)
+            && self.missing_image_files.value() == Some(&0)
+            && self.orphaned_image_files.value() == Some(&0)
+            && self.invalid_settings.value().map(|v| v.is_empty()).unwrap_or(false)
+    }
+}
+
+/// Run every storage check and collect the results
+pub fn run_health_check(
+    db: &Database,
+    storage: &FileStorage,
+    monitor_heartbeat_age_ms: Option<u64>,
+) -> HealthReport {
+    let image_paths = db.get_image_paths();
+
+    let missing_image_files = image_paths
+        .as_ref()
+        .map(|paths| paths.iter().filter(|p| !Path::new(p).exists()).count())
+        .map_err(|e| e.clone());
+
+    let orphaned_image_files = image_paths.clone().and_then(|paths| {
+        let valid_ids: Vec<String> = paths
+            .iter()
+            .filter_map(|p| Path::new(p).file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .collect();
+        storage.count_orphans(&valid_ids)
+    });
+
+    HealthReport {
+        integrity: db.integrity_check().into(),
+        db_size_bytes: db.file_size().into(),
+        wal_size_bytes: db.wal_size().into(),
+        images_dir: storage.images_dir_status().into(),
+        missing_image_files: missing_image_files.into(),
+        orphaned_image_files: orphaned_image_files.into(),
+        invalid_settings: db.invalid_settings().into(),
+        monitor_heartbeat_age_ms,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ClipboardItem;
+    use crate::test_support::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_health_check_report() {
+        let dir = temp_dir("health");
+        let storage = FileStorage::with_images_dir(dir.to_path_buf()).unwrap();
+        let db = Database::new_in_memory().unwrap();
+
+        // One valid image, one missing, one orphan file on disk
+        let valid = dir.join("valid.png");
+        fs::write(&valid, b"png").unwrap();
+        fs::write(dir.join("orphan.png"), b"png").unwrap();
+        let item = ClipboardItem::new_image(None, valid.to_string_lossy().to_string(), None, None);
+        db.insert_item(&item).unwrap();
+        let missing = dir.join("missing.png").to_string_lossy().to_string();
+        let item = ClipboardItem::new_image(None, missing, None, None);
+        db.insert_item(&item).unwrap();
+
+        db.set_setting("history_limit", "lots").unwrap();
+
+        let report = run_health_check(&db, &storage, Some(250));
+        assert_eq!(report.integrity, Check::Ok { value: "ok".to_string() });
+        assert_eq!(report.missing_image_files, Check::Ok { value: 1 });
+        assert_eq!(report.orphaned_image_files, Check::Ok { value: 1 });
+        assert_eq!(
+            report.invalid_settings,
+            Check::Ok { value: vec!["history_limit".to_string()] }
+        );
+        let images_dir = report.images_dir.value().unwrap();
+        assert!(images_dir.exists && images_dir.writable);
+        assert_eq!(report.monitor_heartbeat_age_ms, Some(250));
+        assert!(!report.is_healthy());
+
+        // In-memory database has no file: those checks fail on their own
+        assert!(!report.db_size_bytes.is_ok());
+        assert!(!report.wal_size_bytes.is_ok());
+    }
+
+    #[test]
+    fn test_missing_images_dir_does_not_abort() {
+        let dir = temp_dir("health");
+        let storage = FileStorage::with_images_dir(dir.to_path_buf()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        let db = Database::new_in_memory().unwrap();
+
+        let report = run_health_check(&db, &storage, None);
+        let images_dir = report.images_dir.value().unwrap();
+        assert!(!images_dir.exists && !images_dir.writable);
+        assert!(!report.orphaned_image_files.is_ok());
+
+        // Database checks still ran
+        assert!(report.integrity.is_ok());
+        assert_eq!(report.missing_image_files, Check::Ok { value: 0 });
+        assert_eq!(report.invalid_settings, Check::Ok { value: vec![] });
+    }
+
+    #[test]
+    fn test_check_serialization() {
+        let ok: Check<u64> = Ok(5).into();
+        assert_eq!(serde_json::to_string(&ok).unwrap(), r#"{"status":"ok","value":5}"#);
+
+        let failed: Check<u64> = Err(ClipsterError::Io("boom".to_string())).into();
+        assert_eq!(
+            serde_json::to_string(&failed).unwrap(),
+            r#"{"status":"failed","error":"boom"}"#
+        );
+    }
+}
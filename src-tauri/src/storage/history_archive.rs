@@ -0,0 +1,302 @@
+//! Archive of old history
+//!
+//! `archive_items_older_than` moves history items older than a cutoff that
+//! are neither favorited nor in a pinboard out of `clipster.db` into
+//! `archive.db`, and their image files from `images` into `archive_images`,
+//! both in the profile data directory. The archive is a second `Database`,
+//! only opened when it is searched or restored from, so years of old items
+//! weigh neither on startup nor on everyday queries.
+//!
+//! Items are moved copy first: rows and image files are written to the other
+//! database, then deleted from the one they came from. A failure on the way
+//! undoes the copy, and a crash in between leaves an item in both databases,
+//! never in neither. Spilled text is stored inline; revisions are not kept.
+
+use crate::error::{ClipsterError, Context};
+use crate::models::{BulkOp, ClipboardItem};
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Archive database file name in the profile data directory
+pub const ARCHIVE_DB: &str = "archive.db";
+
+/// Directory of archived image files in the profile data directory
+pub const ARCHIVE_IMAGES_DIR: &str = "archive_images";
+
+/// Items moved per transaction
+pub const ARCHIVE_BATCH_SIZE: usize = 200;
+
+/// What `archive_items_older_than` moved
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ArchiveReport {
+    /// IDs of the items now in the archive, oldest first
+    pub archived_ids: Vec<String>,
+    pub images_moved: usize,
+    /// Space the live database file shrank by once vacuumed
+    pub bytes_reclaimed: u64,
+}
+
+/// The archive of `data_dir`, or None if nothing was ever archived there
+pub fn open_existing(data_dir: &Path) -> Result<Option<Database>, ClipsterError> {
+    if !data_dir.join(ARCHIVE_DB).is_file() {
+        return Ok(None);
+    }
+    Database::open_archive(data_dir).map(Some)
+}
+
+/// Move history items created before `cutoff` (favorites and pinboard items
+/// excepted) into `archive`, then vacuum `live`
+/// Batches moved before a failure stay archived
+pub fn archive_items_older_than(
+    live: &Database,
+    archive: &Database,
+    cutoff: DateTime<Utc>,
+) -> Result<ArchiveReport, ClipsterError> {
+    let size_before = disk_size(live).ok();
+    let mut report = ArchiveReport::default();
+    loop {
+        let items = live.get_archivable_items(cutoff, ARCHIVE_BATCH_SIZE)?;
+        if items.is_empty() {
+            break;
+        }
+        let ids: Vec<String> = items.iter().map(|item| item.id.clone()).collect();
+        report.images_moved += move_items(live, archive, items)?;
+        report.archived_ids.extend(ids);
+    }
+
+    if !report.archived_ids.is_empty() {
+        live.vacuum()?;
+        if let (Some(before), Ok(after)) = (size_before, disk_size(live)) {
+            report.bytes_reclaimed = before.saturating_sub(after);
+        }
+    }
+    Ok(report)
+}
+
+/// Size of a database on disk, write-ahead log included
+fn disk_size(db: &Database) -> Result<u64, ClipsterError> {
+    Ok(db.file_size()? + db.wal_size()?)
+}
+
+/// Move an archived item back into `live` history
+/// Its automatic pinboard is dropped if that pinboard was deleted meanwhile
+pub fn restore_archived_item(
+    live: &Database,
+    archive: &Database,
+    id: &str,
+) -> Result<ClipboardItem, ClipsterError> {
+    let mut item = archive.require_item(id)?;
+    if live.get_item(id)?.is_some() {
+        // Left in both by an interrupted move: finish it
+        archive.bulk_update_items(&[id.to_string()], &BulkOp::Delete)?;
+        return live.require_item(id);
+    }
+    if let Some(pinboard_id) = &item.auto_pinboard_id {
+        if live.get_pinboard(pinboard_id)?.is_none() {
+            item.auto_pinboard_id = None;
+        }
+    }
+
+    move_items(archive, live, vec![item])?;
+    live.require_item(id)
+}
+
+/// Copy `items` and their image files into `to`, then delete them (and the
+/// original files) from `from`; on failure `to` is left as it was
+/// Returns the number of image files moved
+fn move_items(
+    from: &Database,
+    to: &Database,
+    mut items: Vec<ClipboardItem>,
+) -> Result<usize, ClipsterError> {
+    let mut copied = Vec::new();
+    for item in &mut items {
+        // The full text was loaded; the spilled file goes with `from`'s row
+        item.text_path = None;
+        let Some(path) = &item.image_path else {
+            continue;
+        };
+        match copy_image(path, to.images_dir()) {
+            Ok(Some(copy)) => {
+                copied.push(copy.clone());
+                item.image_path = Some(copy);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                remove_files(&copied);
+                return Err(e);
+            }
+        }
+    }
+
+    if let Err(e) = to.insert_items(&items) {
+        remove_files(&copied);
+        return Err(e);
+    }
+    let ids: Vec<String> = items.into_iter().map(|item| item.id).collect();
+    if let Err(e) = from.bulk_update_items(&ids, &BulkOp::Delete) {
+        // Deleting the copies removes their image files too
+        if let Err(undo) = to.bulk_update_items(&ids, &BulkOp::Delete) {
+            eprintln!("[history_archive] Failed to undo copied items: {}", undo);
+        }
+        return Err(e);
+    }
+    Ok(copied.len())
+}
+
+/// Copy an image file into `images_dir` under the same name
+/// None when the file is gone, in which case the item keeps its path
+fn copy_image(path: &str, images_dir: Option<&Path>) -> Result<Option<String>, ClipsterError> {
+    let source = Path::new(path);
+    if !source.is_file() {
+        return Ok(None);
+    }
+    let (Some(images_dir), Some(name)) = (images_dir, source.file_name()) else {
+        return Err(ClipsterError::Validation(
+            "Database has no images directory to move images into".to_string(),
+        ));
+    };
+
+    fs::create_dir_all(images_dir).context("Failed to create images directory")?;
+    let copy = images_dir.join(name);
+    fs::copy(source, &copy).context("Failed to copy image")?;
+    Ok(Some(copy.to_string_lossy().to_string()))
+}
+
+fn remove_files(paths: &[String]) {
+    for path in paths {
+        let _ = fs::remove_file(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use chrono::Duration;
+
+    fn created_days_ago(mut item: ClipboardItem, days: i64) -> ClipboardItem {
+        item.created_at = Utc::now() - Duration::days(days);
+        item
+    }
+
+    #[test]
+    fn test_archive_and_restore_round_trip() {
+        let root = temp_dir("archive");
+        let live = Database::open(&root).unwrap();
+        assert!(open_existing(&root).unwrap().is_none());
+
+        let image_path = root.join("images").join("old.png");
+        fs::create_dir_all(image_path.parent().unwrap()).unwrap();
+        fs::write(&image_path, b"png bytes").unwrap();
+        let old_image = created_days_ago(
+            ClipboardItem::new_image(
+                Some("thumb".to_string()),
+                image_path.to_string_lossy().to_string(),
+                None,
+                None,
+            ),
+            400,
+        );
+        let old_text = created_days_ago(
+            ClipboardItem::new_text("old meeting notes".to_string(), None, None),
+            500,
+        );
+        let recent = created_days_ago(
+            ClipboardItem::new_text("recent notes".to_string(), None, None),
+            10,
+        );
+        let mut favorite = created_days_ago(
+            ClipboardItem::new_text("favorite notes".to_string(), None, None),
+            600,
+        );
+        favorite.is_favorite = true;
+        let mut pinned = created_days_ago(
+            ClipboardItem::new_text("pinned notes".to_string(), None, None),
+            600,
+        );
+        pinned.pinboard_id = Some(live.get_pinboards(false).unwrap()[0].id.clone());
+        for item in [&old_image, &old_text, &recent, &favorite, &pinned] {
+            live.insert_item(item).unwrap();
+        }
+
+        let archive = Database::open_archive(&root).unwrap();
+        let report =
+            archive_items_older_than(&live, &archive, Utc::now() - Duration::days(365)).unwrap();
+        assert_eq!(
+            report.archived_ids,
+            vec![old_text.id.clone(), old_image.id.clone()]
+        );
+        assert_eq!(report.images_moved, 1);
+
+        // Gone from live history, with the image file
+        assert!(live.get_item(&old_text.id).unwrap().is_none());
+        assert!(live.get_item(&old_image.id).unwrap().is_none());
+        for kept in [&recent, &favorite, &pinned] {
+            assert!(live.get_item(&kept.id).unwrap().is_some());
+        }
+        assert!(!image_path.exists());
+        let archived_image = root.join(ARCHIVE_IMAGES_DIR).join("old.png");
+        assert_eq!(
+            archive.require_item(&old_image.id).unwrap().image_path,
+            Some(archived_image.to_string_lossy().to_string())
+        );
+        assert_eq!(fs::read(&archived_image).unwrap(), b"png bytes");
+
+        let archive = open_existing(&root).unwrap().unwrap();
+        let found = archive.search_items("meeting", 10, false).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, old_text.id);
+        assert!(live.search_items("meeting", 10, false).unwrap().is_empty());
+
+        // Restoring moves the row and the file back
+        let restored = restore_archived_item(&live, &archive, &old_image.id).unwrap();
+        assert_eq!(
+            restored.image_path,
+            Some(image_path.to_string_lossy().to_string())
+        );
+        assert_eq!(restored.created_at, old_image.created_at);
+        assert_eq!(fs::read(&image_path).unwrap(), b"png bytes");
+        assert!(!archived_image.exists());
+        assert!(archive.get_item(&old_image.id).unwrap().is_none());
+
+        let missing = restore_archived_item(&live, &archive, &old_image.id);
+        assert!(matches!(missing, Err(ClipsterError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_live_database_shrinks_after_archiving() {
+        let root = temp_dir("archive-vacuum");
+        let live = Database::open(&root).unwrap();
+        let items: Vec<ClipboardItem> = (0..400)
+            .map(|_| {
+                let text = (0..100)
+                    .map(|_| uuid::Uuid::new_v4().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                created_days_ago(ClipboardItem::new_text(text, None, None), 100)
+            })
+            .collect();
+        live.insert_items(&items).unwrap();
+        let size_before = disk_size(&live).unwrap();
+
+        let archive = Database::open_archive(&root).unwrap();
+        let report =
+            archive_items_older_than(&live, &archive, Utc::now() - Duration::days(30)).unwrap();
+        assert_eq!(report.archived_ids.len(), items.len());
+        assert_eq!(live.count_items().unwrap(), 0);
+        assert_eq!(archive.count_items().unwrap(), items.len());
+
+        let size_after = disk_size(&live).unwrap();
+        assert!(
+            size_after < size_before / 4,
+            "{} -> {}",
+            size_before,
+            size_after
+        );
+        assert_eq!(report.bytes_reclaimed, size_before - size_after);
+    }
+}
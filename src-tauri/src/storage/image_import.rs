@@ -0,0 +1,306 @@
+//! Bulk import of image files from a folder into a pinboard
+//!
+//! Each supported image is copied into `FileStorage` under a new item ID and
+//! becomes an Image item on the board, dated with the file's modification
+//! time. Unsupported and unreadable files are skipped and counted. The import
+//! checks a cancel flag between files and reports progress through a callback.
+
+use crate::error::{ClipsterError, Context};
+use crate::models::ClipboardItem;
+use crate::storage::file_storage::{generate_thumbnail_default, thumbnail_to_base64};
+use crate::storage::{Database, FileStorage};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Extensions of the image formats the importer decodes
+pub const SUPPORTED_EXTENSIONS: &[&str] =
+    &["png", "jpg", "jpeg", "gif", "webp", "bmp", "tif", "tiff"];
+
+/// Result of an image folder import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportSummary {
+    /// Files found (hidden files excluded)
+    pub total: usize,
+    pub imported: usize,
+    /// Files that are not a supported image format
+    pub skipped_unsupported: usize,
+    /// Images that could not be read, decoded or saved
+    pub skipped_unreadable: usize,
+    /// The import was cancelled before all files were processed
+    pub cancelled: bool,
+}
+
+/// Payload of the `image-import-progress` event, sent after each file
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ImportProgress {
+    pub processed: usize,
+    pub total: usize,
+    pub imported: usize,
+}
+
+/// Whether the file extension is a supported image format
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .map(|ext| SUPPORTED_EXTENSIONS.contains(&ext.as_str()))
+        .unwrap_or(false)
+}
+
+/// Files in `dir` (and its subdirectories when `recursive`), sorted by path
+/// Hidden files are ignored and symlinked directories are not followed
+pub fn collect_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>, ClipsterError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        let entries = match fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) if current == dir => {
+                return Err(e).context("Failed to read directory");
+            }
+            // Unreadable subdirectories are skipped
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            if is_dir {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if path.is_file() {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Copy one image into storage and build its pinboard item
+/// PNG files are stored as-is; other formats are converted to PNG
+pub fn import_image(
+    storage: &FileStorage,
+    path: &Path,
+    pinboard_id: &str,
+) -> Result<ClipboardItem, ClipsterError> {
+    let data = fs::read(path).context("Failed to read image")?;
+    let image = image::load_from_memory(&data).context("Failed to decode image")?;
+    let thumbnail = generate_thumbnail_default(&image)?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let is_png = path
+        .extension()
+        .map(|ext| ext.eq_ignore_ascii_case("png"))
+        .unwrap_or(false);
+    let image_path = if is_png {
+        storage.save_png_bytes(&id, &data)?
+    } else {
+        storage.save_image(&id, &image)?
+    };
+
+    let mut item = ClipboardItem::new_image(
+        Some(thumbnail_to_base64(&thumbnail)),
+        image_path.to_string_lossy().to_string(),
+        None,
+        None,
+    );
+    item.id = id;
+    item.pinboard_id = Some(pinboard_id.to_string());
+    if let Ok(modified) = fs::metadata(path).and_then(|m| m.modified()) {
+        item.created_at = DateTime::<Utc>::from(modified);
+    }
+
+    Ok(item)
+}
+
+/// Import `files` into a pinboard, stopping early once `cancel` is set
+pub fn import_images<F>(
+    db: &Database,
+    storage: &FileStorage,
+    files: &[PathBuf],
+    pinboard_id: &str,
+    cancel: &AtomicBool,
+    mut on_progress: F,
+) -> ImportSummary
+where
+    F: FnMut(&ImportProgress),
+{
+    let mut summary = ImportSummary {
+        total: files.len(),
+        ..Default::default()
+    };
+
+    for (index, path) in files.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            summary.cancelled = true;
+            break;
+        }
+
+        if !is_supported_image(path) {
+            summary.skipped_unsupported += 1;
+        } else {
+            let imported = import_image(storage, path, pinboard_id).and_then(|item| {
+                db.insert_item(&item).inspect_err(|_| {
+                    if let Some(image_path) = &item.image_path {
+                        let _ = fs::remove_file(image_path);
+                    }
+                })
+            });
+            match imported {
+                Ok(()) => summary.imported += 1,
+                Err(e) => {
+                    eprintln!("[image_import] Skipping {:?}: {}", path, e);
+                    summary.skipped_unreadable += 1;
+                }
+            }
+        }
+
+        on_progress(&ImportProgress {
+            processed: index + 1,
+            total: summary.total,
+            imported: summary.imported,
+        });
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Pinboard;
+    use crate::test_support::temp_dir;
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    fn write_image(path: &Path, format: ImageFormat) {
+        RgbImage::from_pixel(8, 6, Rgb([200, 10, 10]))
+            .save_with_format(path, format)
+            .unwrap();
+    }
+
+    /// Folder with two images, a text file, a corrupt image, a hidden file and a
+    /// nested image
+    fn fixture(root: &Path) -> PathBuf {
+        let dir = root.join("refs");
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        write_image(&dir.join("a.png"), ImageFormat::Png);
+        write_image(&dir.join("b.JPG"), ImageFormat::Jpeg);
+        fs::write(dir.join("notes.txt"), "not an image").unwrap();
+        fs::write(dir.join("broken.png"), "not really a png").unwrap();
+        fs::write(dir.join(".DS_Store"), "").unwrap();
+        write_image(&dir.join("nested").join("c.png"), ImageFormat::Png);
+        dir
+    }
+
+    #[test]
+    fn test_collect_files() {
+        let root = temp_dir("import");
+        let dir = fixture(&root);
+
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+        assert_eq!(
+            names(collect_files(&dir, false).unwrap()),
+            vec!["a.png", "b.JPG", "broken.png", "notes.txt"]
+        );
+        assert_eq!(
+            names(collect_files(&dir, true).unwrap()),
+            vec!["a.png", "b.JPG", "broken.png", "nested/c.png", "notes.txt"]
+        );
+        assert!(collect_files(&root.join("missing"), true).is_err());
+    }
+
+    #[test]
+    fn test_import_into_pinboard() {
+        let root = temp_dir("import");
+        let dir = fixture(&root);
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+        let board = Pinboard::new("Design refs".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+
+        let files = collect_files(&dir, true).unwrap();
+        let mut progress = Vec::new();
+        let summary = import_images(
+            &db,
+            &storage,
+            &files,
+            &board.id,
+            &AtomicBool::new(false),
+            |p| progress.push(p.processed),
+        );
+
+        assert_eq!(
+            summary,
+            ImportSummary {
+                total: 5,
+                imported: 3,
+                skipped_unsupported: 1,
+                skipped_unreadable: 1,
+                cancelled: false,
+            }
+        );
+        assert_eq!(progress, vec![1, 2, 3, 4, 5]);
+
+        let items = db.get_pinboard_items(&board.id, 10, 0, true).unwrap();
+        assert_eq!(items.len(), 3);
+        for item in &items {
+            assert!(item.thumbnail_base64.is_some());
+            // Every image is stored as PNG under its new item ID
+            let stored = PathBuf::from(item.image_path.as_ref().unwrap());
+            assert_eq!(stored, storage.get_image_path(&item.id));
+            assert_eq!(image::open(&stored).unwrap().width(), 8);
+        }
+
+        // created_at comes from the file's mtime
+        let mtime: DateTime<Utc> = fs::metadata(dir.join("a.png"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .into();
+        assert!(items
+            .iter()
+            .any(|item| item.created_at.timestamp() == mtime.timestamp()));
+    }
+
+    #[test]
+    fn test_cancel_stops_import() {
+        let root = temp_dir("import");
+        let dir = fixture(&root);
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+        let board = Pinboard::new("Design refs".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+
+        let files = collect_files(&dir, false).unwrap();
+        let cancel = AtomicBool::new(false);
+        let summary = import_images(&db, &storage, &files, &board.id, &cancel, |p| {
+            if p.processed == 1 {
+                cancel.store(true, Ordering::SeqCst);
+            }
+        });
+
+        assert!(summary.cancelled);
+        assert_eq!(summary.imported, 1);
+        assert_eq!(db.get_pinboard_items(&board.id, 10, 0, false).unwrap().len(), 1);
+    }
+}
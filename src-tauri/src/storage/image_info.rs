@@ -0,0 +1,274 @@
+//! Metadata of stored image files for the details pane
+//!
+//! Dimensions come from the image header, color type and bit depth from a
+//! full decode, and DPI and capture date from EXIF (read with `kamadak-exif`).
+//! Images stored from raw clipboard bytes may be truncated or in a format the
+//! image crate can't decode; whatever could be read is still returned and
+//! `decode_warning` says what couldn't.
+
+use crate::error::{ClipsterError, Context};
+use chrono::NaiveDateTime;
+use exif::{In, Tag, Value};
+use image::io::Reader as ImageReader;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+/// EXIF ResolutionUnit value for centimeters (2 is inches)
+const RESOLUTION_UNIT_CM: u32 = 3;
+
+/// Metadata of an image file; fields are None when they can't be read
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImageInfo {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Detected file format (e.g. "Png", "Jpeg")
+    pub format: Option<String>,
+    /// Decoded color type (e.g. "Rgba8")
+    pub color_type: Option<String>,
+    /// Bits per channel
+    pub bit_depth: Option<u16>,
+    pub file_size: u64,
+    pub dpi_x: Option<f64>,
+    pub dpi_y: Option<f64>,
+    /// EXIF DateTimeOriginal (or DateTime), in the camera's local time
+    pub captured_at: Option<NaiveDateTime>,
+    /// All EXIF fields of the primary image, in file order
+    pub exif: Vec<ExifField>,
+    /// What could not be read, if anything
+    pub decode_warning: Option<String>,
+}
+
+/// One EXIF field as shown in the details pane
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExifField {
+    pub tag: String,
+    pub value: String,
+}
+
+/// Read the metadata of the image file at `path`
+/// Only a missing or unreadable file is an error
+pub fn read_image_info(path: &Path) -> Result<ImageInfo, ClipsterError> {
+    let file_size = fs::metadata(path)
+        .context("Failed to read image file")?
+        .len();
+    let mut info = ImageInfo {
+        file_size,
+        ..ImageInfo::default()
+    };
+    let mut warnings = Vec::new();
+
+    read_pixels_info(path, &mut info, &mut warnings)?;
+    read_exif_info(path, &mut info)?;
+
+    if !warnings.is_empty() {
+        info.decode_warning = Some(warnings.join("; "));
+    }
+    Ok(info)
+}
+
+/// Fill format and dimensions from the header, color type and bit depth from
+/// a full decode
+fn read_pixels_info(
+    path: &Path,
+    info: &mut ImageInfo,
+    warnings: &mut Vec<String>,
+) -> Result<(), ClipsterError> {
+    // The format comes from the content only: raw bytes may be saved as `.png`
+    let open = || -> Result<ImageReader<BufReader<File>>, ClipsterError> {
+        File::open(path)
+            .and_then(|file| ImageReader::new(BufReader::new(file)).with_guessed_format())
+            .context("Failed to read image file")
+    };
+
+    let reader = open()?;
+    let Some(format) = reader.format() else {
+        warnings.push("Unrecognized image format".to_string());
+        return Ok(());
+    };
+    info.format = Some(format!("{:?}", format));
+
+    match reader.into_dimensions() {
+        Ok((width, height)) => {
+            info.width = Some(width);
+            info.height = Some(height);
+        }
+        Err(e) => {
+            warnings.push(format!("Can't read dimensions: {}", e));
+            return Ok(());
+        }
+    }
+
+    // Truncated files still have a readable header
+    match open()?.decode() {
+        Ok(image) => {
+            let color = image.color();
+            info.color_type = Some(format!("{:?}", color));
+            info.bit_depth = Some(color.bits_per_pixel() / color.channel_count() as u16);
+        }
+        Err(e) => warnings.push(format!("Can't decode pixels: {}", e)),
+    }
+    Ok(())
+}
+
+/// Fill DPI, capture date and the field list from EXIF, if the file has any
+fn read_exif_info(path: &Path, info: &mut ImageInfo) -> Result<(), ClipsterError> {
+    let file = File::open(path).context("Failed to read image file")?;
+    // Most images have no EXIF at all, which is not worth a warning
+    let Ok(exif) = exif::Reader::new().read_from_container(&mut BufReader::new(file)) else {
+        return Ok(());
+    };
+
+    let rational = |tag| match exif.get_field(tag, In::PRIMARY).map(|f| &f.value) {
+        Some(Value::Rational(values)) => values.first().map(|r| r.to_f64()),
+        _ => None,
+    };
+    let per_cm = exif
+        .get_field(Tag::ResolutionUnit, In::PRIMARY)
+        .and_then(|f| f.value.get_uint(0))
+        == Some(RESOLUTION_UNIT_CM);
+    let to_dpi = |resolution: f64| {
+        if per_cm {
+            resolution * 2.54
+        } else {
+            resolution
+        }
+    };
+    info.dpi_x = rational(Tag::XResolution).map(to_dpi);
+    info.dpi_y = rational(Tag::YResolution).map(to_dpi);
+
+    let date = |tag| match exif.get_field(tag, In::PRIMARY).map(|f| &f.value) {
+        Some(Value::Ascii(values)) => values.first().and_then(|bytes| parse_exif_date(bytes)),
+        _ => None,
+    };
+    info.captured_at = date(Tag::DateTimeOriginal).or_else(|| date(Tag::DateTime));
+
+    info.exif = exif
+        .fields()
+        .filter(|field| field.ifd_num == In::PRIMARY)
+        .map(|field| ExifField {
+            tag: field.tag.to_string(),
+            value: field.display_value().with_unit(&exif).to_string(),
+        })
+        .collect();
+    Ok(())
+}
+
+/// Parse an EXIF date ("2024:03:15 14:30:00")
+fn parse_exif_date(bytes: &[u8]) -> Option<NaiveDateTime> {
+    let text = std::str::from_utf8(bytes).ok()?;
+    NaiveDateTime::parse_from_str(text.trim(), "%Y:%m:%d %H:%M:%S").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{Rgb, RgbImage};
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn temp_file(dir: &Path, name: &str, bytes: &[u8]) -> PathBuf {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// 8x6 JPEG with an APP1 EXIF segment: 300 DPI, taken 2024-03-15 14:30:00
+    fn jpeg_fixture() -> Vec<u8> {
+        let mut jpeg = Vec::new();
+        JpegEncoder::new(&mut jpeg)
+            .encode_image(&RgbImage::from_pixel(8, 6, Rgb([200, 100, 50])))
+            .unwrap();
+
+        let field = |tag, value| Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value,
+        };
+        let resolution = || Value::Rational(vec![Rational { num: 300, denom: 1 }]);
+        let fields = [
+            field(Tag::Make, Value::Ascii(vec![b"Clipster".to_vec()])),
+            field(Tag::XResolution, resolution()),
+            field(Tag::YResolution, resolution()),
+            field(Tag::ResolutionUnit, Value::Short(vec![2])),
+            field(
+                Tag::DateTimeOriginal,
+                Value::Ascii(vec![b"2024:03:15 14:30:00".to_vec()]),
+            ),
+        ];
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        let tiff = tiff.into_inner();
+
+        // SOI, then APP1 "Exif\0\0" + TIFF, then the rest of the encoded JPEG
+        let length = (2 + 6 + tiff.len()) as u16;
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xE1]);
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(b"Exif\0\0");
+        bytes.extend_from_slice(&tiff);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    #[test]
+    fn test_jpeg_exif_fields() {
+        let bytes = jpeg_fixture();
+        let dir = temp_dir("image-info");
+        let path = temp_file(&dir, "photo.jpg", &bytes);
+
+        let info = read_image_info(&path).unwrap();
+        assert_eq!((info.width, info.height), (Some(8), Some(6)));
+        assert_eq!(info.format.as_deref(), Some("Jpeg"));
+        assert_eq!(info.color_type.as_deref(), Some("Rgb8"));
+        assert_eq!(info.bit_depth, Some(8));
+        assert_eq!(info.file_size, bytes.len() as u64);
+        assert_eq!((info.dpi_x, info.dpi_y), (Some(300.0), Some(300.0)));
+        let taken = NaiveDateTime::parse_from_str("2024-03-15 14:30:00", "%Y-%m-%d %H:%M:%S");
+        assert_eq!(info.captured_at, taken.ok());
+        assert!(info
+            .exif
+            .iter()
+            .any(|field| field.tag == "Make" && field.value.contains("Clipster")));
+        assert_eq!(info.decode_warning, None);
+    }
+
+    #[test]
+    fn test_truncated_file_keeps_header_fields() {
+        let bytes = jpeg_fixture();
+        let dir = temp_dir("image-info");
+        let path = temp_file(&dir, "cut.jpg", &bytes[..bytes.len() / 2]);
+
+        let info = read_image_info(&path).unwrap();
+        assert_eq!((info.width, info.height), (Some(8), Some(6)));
+        assert_eq!(info.dpi_x, Some(300.0));
+        assert_eq!(info.color_type, None);
+        assert!(info.decode_warning.is_some());
+    }
+
+    #[test]
+    fn test_unrecognized_bytes() {
+        let dir = temp_dir("image-info");
+        let path = temp_file(&dir, "raw.png", b"not an image at all");
+
+        let info = read_image_info(&path).unwrap();
+        assert_eq!(info.width, None);
+        assert_eq!(info.file_size, 19);
+        assert!(info.exif.is_empty());
+        assert_eq!(
+            info.decode_warning.as_deref(),
+            Some("Unrecognized image format")
+        );
+        assert!(read_image_info(Path::new("/nonexistent/clipster.png")).is_err());
+    }
+}
@@ -1,7 +1,32 @@
 // Storage module for SQLite database and file operations
 
+pub mod backup;
+pub mod compression;
+pub mod counters;
 pub mod database;
+pub mod destructive_guard;
+pub mod drag_payload;
+pub mod duplicates;
+pub mod file_attach;
 pub mod file_storage;
+pub mod health;
+pub mod history_archive;
+pub mod image_import;
+pub mod image_info;
+pub mod pending_spool;
+pub mod pinboard_lock;
+pub mod pinboard_tree;
+pub mod profiles;
+#[cfg(any(target_os = "macos", test))]
+pub mod quicklook;
+pub mod recovery;
+pub mod reindex;
+pub mod settings_snapshot;
+pub mod stats;
+pub mod zip_export;
 
 pub use database::Database;
+pub use destructive_guard::DestructiveGuard;
+pub use drag_payload::DragPayloads;
 pub use file_storage::FileStorage;
+pub use pinboard_lock::PinboardLocks;
@@ -0,0 +1,316 @@
+//! Retry and spool for captures the database could not take
+//!
+//! A capture whose insert fails with SQLITE_BUSY/LOCKED is retried a few times
+//! with growing delays. If the database is still unavailable, the item is
+//! written to a JSON file in `pending/` inside the profile data directory
+//! (`~/.clipster/pending/` for the default profile), next to its already-saved
+//! image or text file. At the next startup `replay` inserts spooled items and
+//! removes their files.
+
+use crate::error::{ClipsterError, Context};
+use crate::models::ClipboardItem;
+use crate::storage::Database;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Waits before each retry of a busy insert
+pub const INSERT_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_millis(100),
+    Duration::from_millis(300),
+    Duration::from_millis(900),
+];
+
+/// Spool directory inside a profile data directory
+const PENDING_DIR: &str = "pending";
+
+/// A spooled capture
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingItem {
+    /// The item, with the paths of its saved image or text file
+    item: ClipboardItem,
+    /// Not part of the item's serialized form
+    canonical_url: Option<String>,
+}
+
+/// Result of a spool replay
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Items inserted into the database
+    pub replayed: usize,
+    /// Spooled items whose image or text file is gone
+    pub dropped: usize,
+    /// Items left in the spool for the next startup
+    pub remaining: usize,
+}
+
+/// Whether an error is SQLite reporting the database busy or locked
+pub fn is_busy(error: &ClipsterError) -> bool {
+    matches!(error, ClipsterError::Busy(_))
+}
+
+/// Run `op`, retrying after each of `delays` while it fails with a busy error
+/// Other errors are returned right away
+pub fn retry_busy<T>(
+    delays: &[Duration],
+    mut sleep: impl FnMut(Duration),
+    mut op: impl FnMut() -> Result<T, ClipsterError>,
+) -> Result<T, ClipsterError> {
+    let mut delays = delays.iter();
+    loop {
+        match op() {
+            Err(e) if is_busy(&e) => match delays.next() {
+                Some(delay) => {
+                    eprintln!("[pending_spool] {}, retrying in {:?}", e, delay);
+                    sleep(*delay);
+                }
+                None => return Err(e),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Spool directory of a profile data directory
+pub fn pending_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(PENDING_DIR)
+}
+
+/// Write an item the database could not take to the spool
+pub fn spool(dir: &Path, item: &ClipboardItem) -> Result<PathBuf, ClipsterError> {
+    fs::create_dir_all(dir).context("Failed to create pending directory")?;
+
+    let pending = PendingItem {
+        item: item.clone(),
+        canonical_url: item.canonical_url.clone(),
+    };
+    let path = dir.join(format!("{}.json", item.id));
+    let json = serde_json::to_vec(&pending).context("Failed to serialize pending item")?;
+    // Written aside and renamed, so a replay never reads half a file
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, json).context("Failed to write pending item")?;
+    fs::rename(&partial, &path).context("Failed to write pending item")?;
+    Ok(path)
+}
+
+/// Insert spooled items into the database, oldest capture first
+/// Files that can't be parsed are renamed to `.invalid` and kept for inspection
+pub fn replay(db: &Database, dir: &Path) -> ReplayReport {
+    let mut report = ReplayReport::default();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return report;
+    };
+
+    let mut pending: Vec<(PathBuf, PendingItem)> = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let parsed = fs::read(&path)
+            .map_err(ClipsterError::from)
+            .and_then(|json| Ok(serde_json::from_slice::<PendingItem>(&json)?));
+        match parsed {
+            Ok(item) => pending.push((path, item)),
+            Err(e) => {
+                eprintln!(
+                    "[pending_spool] Invalid spool file {}: {}",
+                    path.display(),
+                    e
+                );
+                let _ = fs::rename(&path, path.with_extension("invalid"));
+            }
+        }
+    }
+    pending.sort_by_key(|(_, pending)| pending.item.created_at);
+
+    for (
+        path,
+        PendingItem {
+            mut item,
+            canonical_url,
+        },
+    ) in pending
+    {
+        item.canonical_url = canonical_url;
+        let outcome = if !files_exist(&item) {
+            eprintln!("[pending_spool] Files of {} are gone, dropping it", item.id);
+            report.dropped += 1;
+            Ok(())
+        } else {
+            match db.get_item(&item.id) {
+                // Already inserted by an earlier replay that couldn't remove the file
+                Ok(Some(_)) => Ok(()),
+                Ok(None) => db.insert_capture(&item).map(|()| report.replayed += 1),
+                Err(e) => Err(e),
+            }
+        };
+
+        match outcome {
+            Ok(()) => {
+                if let Err(e) = fs::remove_file(&path) {
+                    eprintln!("[pending_spool] Failed to remove {}: {}", path.display(), e);
+                }
+            }
+            Err(e) => {
+                eprintln!("[pending_spool] Failed to replay {}: {}", item.id, e);
+                report.remaining += 1;
+            }
+        }
+    }
+    report
+}
+
+/// The item's saved image and spilled text (if any) are still on disk
+fn files_exist(item: &ClipboardItem) -> bool {
+    [&item.image_path, &item.text_path]
+        .into_iter()
+        .flatten()
+        .all(|path| Path::new(path).exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::cell::Cell;
+
+    fn busy() -> ClipsterError {
+        ClipsterError::Busy("database is locked".to_string())
+    }
+
+    #[test]
+    fn test_retry_busy_backs_off_then_succeeds() {
+        let attempts = Cell::new(0);
+        let mut slept = Vec::new();
+        let result = retry_busy(
+            &INSERT_RETRY_DELAYS,
+            |delay| slept.push(delay),
+            || {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() < 3 {
+                    Err(busy())
+                } else {
+                    Ok(attempts.get())
+                }
+            },
+        );
+        assert_eq!(result, Ok(3));
+        assert_eq!(slept, INSERT_RETRY_DELAYS[..2]);
+    }
+
+    #[test]
+    fn test_retry_busy_gives_up_after_last_delay() {
+        let attempts = Cell::new(0);
+        let mut slept = Vec::new();
+        let result: Result<(), _> = retry_busy(
+            &INSERT_RETRY_DELAYS,
+            |delay| slept.push(delay),
+            || {
+                attempts.set(attempts.get() + 1);
+                Err(busy())
+            },
+        );
+        assert!(matches!(result, Err(ClipsterError::Busy(_))));
+        assert_eq!(attempts.get(), 1 + INSERT_RETRY_DELAYS.len());
+        assert_eq!(slept, INSERT_RETRY_DELAYS);
+    }
+
+    #[test]
+    fn test_retry_busy_returns_other_errors_at_once() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_busy(
+            &INSERT_RETRY_DELAYS,
+            |_| panic!("slept on a non-busy error"),
+            || {
+                attempts.set(attempts.get() + 1);
+                Err(ClipsterError::Database("constraint failed".to_string()))
+            },
+        );
+        assert!(matches!(result, Err(ClipsterError::Database(_))));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn test_locked_database_insert_is_busy() {
+        let dir = temp_dir("busy");
+        let db = Database::open(&dir).unwrap();
+
+        db.set_busy_timeout(Duration::ZERO).unwrap();
+
+        // Another connection holds the write lock
+        let other = rusqlite::Connection::open(dir.join("clipster.db")).unwrap();
+        other.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+        let item = ClipboardItem::new_text("locked".to_string(), None, None);
+        let error = db.insert_item(&item).unwrap_err();
+        assert!(is_busy(&error), "{:?}", error);
+
+        // Released during the first backoff
+        let result = retry_busy(
+            &INSERT_RETRY_DELAYS,
+            |_| other.execute_batch("COMMIT").unwrap(),
+            || db.insert_item(&item),
+        );
+        assert_eq!(result, Ok(()));
+    }
+
+    #[test]
+    fn test_spool_replay_inserts_items_once() {
+        let dir = temp_dir("pending");
+        let db = Database::new_in_memory().unwrap();
+
+        let mut item =
+            ClipboardItem::new_text("https://example.com/?utm_source=x".to_string(), None, None);
+        item.canonical_url = Some("https://example.com/".to_string());
+        spool(&dir, &item).unwrap();
+        let second = ClipboardItem::new_text("second capture".to_string(), None, None);
+        spool(&dir, &second).unwrap();
+
+        let report = replay(&db, &dir);
+        assert_eq!(
+            report,
+            ReplayReport {
+                replayed: 2,
+                dropped: 0,
+                remaining: 0
+            }
+        );
+        let saved = db.get_item(&item.id).unwrap().unwrap();
+        assert_eq!(saved.content_text, item.content_text);
+        assert_eq!(saved.created_at, item.created_at);
+        assert_eq!(saved.canonical_url, item.canonical_url);
+        assert!(db.get_item(&second.id).unwrap().is_some());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        // A file left behind by an interrupted replay is not inserted twice
+        spool(&dir, &item).unwrap();
+        assert_eq!(replay(&db, &dir), ReplayReport::default());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_spool_replay_drops_items_without_files() {
+        let dir = temp_dir("pending");
+        let db = Database::new_in_memory().unwrap();
+
+        let image = dir.join("image.png");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&image, b"png").unwrap();
+        let kept = ClipboardItem::new_image(None, image.to_string_lossy().to_string(), None, None);
+        let lost =
+            ClipboardItem::new_image(None, "/nonexistent/clipster.png".to_string(), None, None);
+        spool(&dir, &kept).unwrap();
+        spool(&dir, &lost).unwrap();
+        fs::write(dir.join("garbage.json"), b"{").unwrap();
+
+        let report = replay(&db, &dir);
+        assert_eq!(report.replayed, 1);
+        assert_eq!(report.dropped, 1);
+        assert!(db.get_item(&kept.id).unwrap().is_some());
+        assert!(db.get_item(&lost.id).unwrap().is_none());
+        // Unparseable files are kept aside, not retried
+        assert!(dir.join("garbage.invalid").exists());
+        assert!(!dir.join("garbage.json").exists());
+    }
+}
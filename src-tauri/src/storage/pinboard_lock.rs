@@ -0,0 +1,211 @@
+//! Passcode protection for pinboards
+//!
+//! Passcodes are stored as argon2 hashes in the `pinboards.lock_hash` column.
+//! Unlocking a pinboard is session-only: the unlocked-until timestamp lives in
+//! memory and every board relocks after a timeout or when the panel is hidden.
+
+use crate::error::ClipsterError;
+use crate::models::ClipboardItem;
+use crate::storage::Database;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Default number of minutes a pinboard stays unlocked
+pub const DEFAULT_RELOCK_MINUTES: i64 = 5;
+
+/// Hash a passcode with argon2 (random salt, PHC string format)
+pub fn hash_passcode(passcode: &str) -> Result<String, String> {
+    if passcode.is_empty() {
+        return Err("Passcode cannot be empty".to_string());
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passcode.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash passcode: {}", e))
+}
+
+/// Verify a passcode against a stored argon2 hash
+pub fn verify_passcode(passcode: &str, hash: &str) -> bool {
+    match PasswordHash::new(hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(passcode.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// In-memory registry of temporarily unlocked pinboards
+#[derive(Default)]
+pub struct PinboardLocks {
+    /// Pinboard ID -> time at which it relocks
+    unlocked_until: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl PinboardLocks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a pinboard as unlocked for `minutes` starting at `now`
+    pub fn unlock(&self, pinboard_id: &str, minutes: i64, now: DateTime<Utc>) {
+        if let Ok(mut unlocked) = self.unlocked_until.lock() {
+            unlocked.insert(pinboard_id.to_string(), now + Duration::minutes(minutes));
+        }
+    }
+
+    /// Check whether a pinboard is currently unlocked (expired entries are dropped)
+    pub fn is_unlocked(&self, pinboard_id: &str, now: DateTime<Utc>) -> bool {
+        let Ok(mut unlocked) = self.unlocked_until.lock() else {
+            return false;
+        };
+
+        match unlocked.get(pinboard_id) {
+            Some(until) if *until > now => true,
+            Some(_) => {
+                unlocked.remove(pinboard_id);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Pinboards unlocked at `now`
+    pub fn unlocked_ids(&self, now: DateTime<Utc>) -> Vec<String> {
+        let Ok(unlocked) = self.unlocked_until.lock() else {
+            return Vec::new();
+        };
+        unlocked
+            .iter()
+            .filter(|(_, until)| **until > now)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Fail if the pinboard has a passcode and is not unlocked at `now`
+    pub fn require_pinboard_unlocked(
+        &self,
+        db: &Database,
+        pinboard_id: &str,
+        now: DateTime<Utc>,
+    ) -> Result<(), ClipsterError> {
+        match db.get_pinboard(pinboard_id)? {
+            Some(pinboard) if pinboard.is_locked && !self.is_unlocked(pinboard_id, now) => {
+                Err(ClipsterError::Validation("Pinboard is locked".to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if `item` is filed in a locked pinboard, or auto-filed into one
+    /// from history (`auto_pinboard_id`), that is not unlocked at `now`
+    /// By-ID counterpart of the visibility condition of the database queries
+    pub fn require_unlocked(
+        &self,
+        db: &Database,
+        item: &ClipboardItem,
+        now: DateTime<Utc>,
+    ) -> Result<(), ClipsterError> {
+        match item.filed_pinboard_id() {
+            Some(pinboard_id) => self.require_pinboard_unlocked(db, pinboard_id, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Relock a single pinboard
+    pub fn relock(&self, pinboard_id: &str) {
+        if let Ok(mut unlocked) = self.unlocked_until.lock() {
+            unlocked.remove(pinboard_id);
+        }
+    }
+
+    /// Relock every pinboard (e.g. when the panel is hidden)
+    pub fn relock_all(&self) {
+        if let Ok(mut unlocked) = self.unlocked_until.lock() {
+            unlocked.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_passcode("1234").unwrap();
+        assert!(hash.starts_with("$argon2"));
+        assert!(verify_passcode("1234", &hash));
+        assert!(!verify_passcode("4321", &hash));
+        assert!(!verify_passcode("1234", "not-a-hash"));
+
+        // Salted: same passcode gives a different hash
+        assert_ne!(hash, hash_passcode("1234").unwrap());
+        assert!(hash_passcode("").is_err());
+    }
+
+    #[test]
+    fn test_relock_timer() {
+        let locks = PinboardLocks::new();
+        let now = Utc::now();
+
+        assert!(!locks.is_unlocked("board", now));
+
+        locks.unlock("board", 5, now);
+        assert!(locks.is_unlocked("board", now));
+        assert!(locks.is_unlocked("board", now + Duration::minutes(4)));
+        assert!(!locks.is_unlocked("board", now + Duration::minutes(5)));
+
+        // Expired entry was dropped
+        assert!(!locks.is_unlocked("board", now));
+    }
+
+    #[test]
+    fn test_relock_all() {
+        let locks = PinboardLocks::new();
+        let now = Utc::now();
+
+        locks.unlock("a", 5, now);
+        locks.unlock("b", 5, now);
+        locks.relock("a");
+        assert!(!locks.is_unlocked("a", now));
+        assert!(locks.is_unlocked("b", now));
+        assert_eq!(locks.unlocked_ids(now), vec!["b".to_string()]);
+        assert!(locks.unlocked_ids(now + Duration::minutes(5)).is_empty());
+
+        locks.relock_all();
+        assert!(!locks.is_unlocked("b", now));
+    }
+
+    #[test]
+    fn test_require_unlocked() {
+        use crate::models::Pinboard;
+
+        let db = Database::new_in_memory().unwrap();
+        let locks = PinboardLocks::new();
+        let now = Utc::now();
+        let board = Pinboard::new("Personal".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+        db.set_pinboard_lock_hash(&board.id, Some("$argon2id$hash"))
+            .unwrap();
+
+        let mut filed = ClipboardItem::new_text("filed".to_string(), None, None);
+        filed.pinboard_id = Some(board.id.clone());
+        let mut auto_filed = ClipboardItem::new_text("auto".to_string(), None, None);
+        auto_filed.auto_pinboard_id = Some(board.id.clone());
+        let history = ClipboardItem::new_text("history".to_string(), None, None);
+
+        assert!(locks.require_unlocked(&db, &filed, now).is_err());
+        assert!(locks.require_unlocked(&db, &auto_filed, now).is_err());
+        assert!(locks.require_unlocked(&db, &history, now).is_ok());
+
+        locks.unlock(&board.id, 5, now);
+        assert!(locks.require_unlocked(&db, &filed, now).is_ok());
+        assert!(locks.require_unlocked(&db, &auto_filed, now).is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+//! Pinboard hierarchy rules
+//!
+//! A pinboard can be nested under another through `pinboards.parent_id`, so
+//! boards can be grouped under folders like "Work" and "Personal". Nesting is
+//! limited to `MAX_PINBOARD_DEPTH` levels. The checks work on a parent map
+//! (pinboard ID -> parent ID) loaded from the table, so they can be tested
+//! without a database.
+
+use std::collections::HashMap;
+
+/// Levels of nesting: root pinboards and one level of children
+pub const MAX_PINBOARD_DEPTH: usize = 2;
+
+/// Pinboard ID -> parent ID, for every pinboard
+pub type ParentMap = HashMap<String, Option<String>>;
+
+/// Check that `id` can be moved under `parent_id` (or to the root with `None`)
+/// Rejects unknown pinboards, cycles and trees deeper than `MAX_PINBOARD_DEPTH`
+pub fn check_parent(parents: &ParentMap, id: &str, parent_id: Option<&str>) -> Result<(), String> {
+    if !parents.contains_key(id) {
+        return Err(format!("Pinboard not found: {}", id));
+    }
+    let Some(parent_id) = parent_id else {
+        return Ok(());
+    };
+    if !parents.contains_key(parent_id) {
+        return Err(format!("Parent pinboard not found: {}", parent_id));
+    }
+
+    // The new parent and its ancestors must not include the moved pinboard
+    let mut ancestors = vec![parent_id];
+    let mut current = parent_id;
+    while let Some(Some(next)) = parents.get(current) {
+        if ancestors.contains(&next.as_str()) {
+            // Already corrupt; refuse rather than loop
+            return Err("Pinboard hierarchy contains a cycle".to_string());
+        }
+        ancestors.push(next);
+        current = next;
+    }
+    if ancestors.contains(&id) {
+        return Err("A pinboard cannot be moved inside itself".to_string());
+    }
+
+    if ancestors.len() + subtree_height(parents, id) > MAX_PINBOARD_DEPTH {
+        return Err(format!(
+            "Pinboards can only be nested {} levels deep",
+            MAX_PINBOARD_DEPTH
+        ));
+    }
+    Ok(())
+}
+
+/// Direct children of `id`
+pub fn children<'a>(parents: &'a ParentMap, id: &str) -> Vec<&'a str> {
+    let mut children: Vec<&str> = parents
+        .iter()
+        .filter(|(_, parent)| parent.as_deref() == Some(id))
+        .map(|(child, _)| child.as_str())
+        .collect();
+    children.sort_unstable();
+    children
+}
+
+/// All pinboards below `id`, children before their own children
+pub fn descendants<'a>(parents: &'a ParentMap, id: &str) -> Vec<&'a str> {
+    let mut found = children(parents, id);
+    let mut next = 0;
+    while next < found.len() {
+        for child in children(parents, found[next]) {
+            if !found.contains(&child) {
+                found.push(child);
+            }
+        }
+        next += 1;
+    }
+    found
+}
+
+/// Levels in the tree rooted at `id`, counting `id` itself
+fn subtree_height(parents: &ParentMap, id: &str) -> usize {
+    let mut height = 1;
+    let mut level = vec![id];
+    let mut seen = vec![id];
+    loop {
+        let next: Vec<&str> = level
+            .iter()
+            .flat_map(|parent| children(parents, parent))
+            .filter(|child| !seen.contains(child))
+            .collect();
+        if next.is_empty() {
+            return height;
+        }
+        seen.extend(&next);
+        level = next;
+        height += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree(entries: &[(&str, Option<&str>)]) -> ParentMap {
+        entries
+            .iter()
+            .map(|(id, parent)| (id.to_string(), parent.map(str::to_string)))
+            .collect()
+    }
+
+    /// work > (clients, snippets), personal, loose
+    fn sample() -> ParentMap {
+        tree(&[
+            ("work", None),
+            ("clients", Some("work")),
+            ("snippets", Some("work")),
+            ("personal", None),
+            ("loose", None),
+        ])
+    }
+
+    #[test]
+    fn test_check_parent_allows_one_level() {
+        let parents = sample();
+        assert!(check_parent(&parents, "loose", Some("personal")).is_ok());
+        assert!(check_parent(&parents, "clients", Some("personal")).is_ok());
+        assert!(check_parent(&parents, "clients", None).is_ok());
+        // A parent that already has children can still move to the root
+        assert!(check_parent(&parents, "work", None).is_ok());
+    }
+
+    #[test]
+    fn test_check_parent_rejects_cycles() {
+        let parents = sample();
+        assert!(check_parent(&parents, "work", Some("work")).is_err());
+        assert!(check_parent(&parents, "work", Some("clients")).is_err());
+
+        // Would not be depth-limited if the limit were higher
+        let deep = tree(&[("a", None), ("b", Some("a")), ("c", Some("b"))]);
+        let error = check_parent(&deep, "a", Some("c")).unwrap_err();
+        assert!(error.contains("inside itself"), "{}", error);
+
+        // A cycle already in the table is reported instead of looping
+        let corrupt = tree(&[("a", Some("b")), ("b", Some("a")), ("c", None)]);
+        assert!(check_parent(&corrupt, "c", Some("a")).is_err());
+    }
+
+    #[test]
+    fn test_check_parent_enforces_max_depth() {
+        let parents = sample();
+        // Under a child: three levels
+        assert!(check_parent(&parents, "loose", Some("clients")).is_err());
+        // A pinboard with children can't become a child
+        assert!(check_parent(&parents, "work", Some("personal")).is_err());
+    }
+
+    #[test]
+    fn test_check_parent_rejects_unknown_pinboards() {
+        let parents = sample();
+        assert!(check_parent(&parents, "missing", None).is_err());
+        assert!(check_parent(&parents, "loose", Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_descendants() {
+        let parents = sample();
+        assert_eq!(descendants(&parents, "work"), vec!["clients", "snippets"]);
+        assert!(descendants(&parents, "personal").is_empty());
+
+        let deep = tree(&[("a", None), ("b", Some("a")), ("c", Some("b"))]);
+        assert_eq!(descendants(&deep, "a"), vec!["b", "c"]);
+    }
+}
@@ -0,0 +1,275 @@
+//! Profiles: separate clipboard histories (e.g. work and personal)
+//!
+//! Each profile owns a data directory holding its own `clipster.db`, `images/`
+//! and `texts/`. The registry (profile name -> data directory, plus the active
+//! profile) lives in `~/.clipster/profiles.json`. The `default` profile maps to
+//! `~/.clipster` itself, so existing installs keep their history untouched.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Name of the profile backed by the original `~/.clipster` directory
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Registry file name inside the Clipster root directory
+const REGISTRY_FILE: &str = "profiles.json";
+
+/// Directory holding the data directories of non-default profiles
+const PROFILES_DIR: &str = "profiles";
+
+/// Maximum length of a profile name
+const MAX_NAME_LEN: usize = 32;
+
+/// Data directory of the active profile (None until `init` or a switch)
+static ACTIVE_DATA_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// A profile as shown to the frontend
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct Profile {
+    pub name: String,
+    pub data_dir: String,
+    pub is_active: bool,
+}
+
+/// Persisted profile registry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileRegistry {
+    /// Name of the active profile
+    pub active: String,
+    /// Profile name -> data directory
+    pub profiles: BTreeMap<String, PathBuf>,
+}
+
+/// Root directory of Clipster data: ~/.clipster
+pub fn clipster_root() -> Result<PathBuf, String> {
+    let data_dir = dirs::data_local_dir()
+        .or_else(dirs::home_dir)
+        .ok_or_else(|| "Could not determine home directory".to_string())?;
+
+    Ok(data_dir.join(".clipster"))
+}
+
+/// Data directory of the active profile
+/// Falls back to the default profile directory before `init` has run
+pub fn active_data_dir() -> Result<PathBuf, String> {
+    let active = ACTIVE_DATA_DIR
+        .read()
+        .map_err(|e| format!("Lock error: {}", e))?
+        .clone();
+
+    match active {
+        Some(dir) => Ok(dir),
+        None => clipster_root(),
+    }
+}
+
+/// Point `Database::new` and `FileStorage::new` at another data directory
+pub fn set_active_data_dir(dir: PathBuf) -> Result<(), String> {
+    let mut active = ACTIVE_DATA_DIR
+        .write()
+        .map_err(|e| format!("Lock error: {}", e))?;
+    *active = Some(dir);
+    Ok(())
+}
+
+/// Load the registry and make its active profile current
+/// Called once at startup, before the database is opened
+pub fn init() -> Result<ProfileRegistry, String> {
+    let root = clipster_root()?;
+    let registry = ProfileRegistry::load(&root)?;
+    set_active_data_dir(registry.active_dir())?;
+    Ok(registry)
+}
+
+/// Trim and validate a profile name
+/// Names become directory names, so only letters, digits, spaces, `-` and `_`
+/// are allowed
+pub fn validate_profile_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!(
+            "Profile name cannot be longer than {} characters",
+            MAX_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || c == ' ' || c == '-' || c == '_')
+    {
+        return Err(
+            "Profile name can only contain letters, digits, spaces, '-' and '_'".to_string(),
+        );
+    }
+    Ok(name.to_string())
+}
+
+impl ProfileRegistry {
+    /// Registry with only the default profile, rooted at `root`
+    pub fn with_default(root: &Path) -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), root.to_path_buf())]),
+        }
+    }
+
+    /// Read the registry from `root` (a missing file means only the default profile)
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let path = root.join(REGISTRY_FILE);
+        if !path.exists() {
+            return Ok(Self::with_default(root));
+        }
+
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read profiles file: {}", e))?;
+        let mut registry: Self = serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse profiles file: {}", e))?;
+
+        // The default profile always exists and always points at the root
+        registry
+            .profiles
+            .insert(DEFAULT_PROFILE.to_string(), root.to_path_buf());
+        if !registry.profiles.contains_key(&registry.active) {
+            registry.active = DEFAULT_PROFILE.to_string();
+        }
+
+        Ok(registry)
+    }
+
+    /// Write the registry to `root`
+    pub fn save(&self, root: &Path) -> Result<(), String> {
+        fs::create_dir_all(root).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+        fs::write(root.join(REGISTRY_FILE), json)
+            .map_err(|e| format!("Failed to write profiles file: {}", e))
+    }
+
+    /// Data directory of a profile
+    pub fn data_dir(&self, name: &str) -> Option<&PathBuf> {
+        self.profiles.get(name)
+    }
+
+    /// Data directory of the active profile
+    pub fn active_dir(&self) -> PathBuf {
+        self.profiles
+            .get(&self.active)
+            .or_else(|| self.profiles.get(DEFAULT_PROFILE))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Register a new profile with its data directory under `root/profiles`
+    /// Names are unique case-insensitively
+    pub fn create(&mut self, root: &Path, name: &str) -> Result<Profile, String> {
+        let name = validate_profile_name(name)?;
+        if self
+            .profiles
+            .keys()
+            .any(|existing| existing.eq_ignore_ascii_case(&name))
+        {
+            return Err(format!("Profile '{}' already exists", name));
+        }
+
+        let data_dir = root.join(PROFILES_DIR).join(&name);
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| format!("Failed to create profile directory: {}", e))?;
+
+        self.profiles.insert(name.clone(), data_dir.clone());
+        Ok(Profile {
+            name,
+            data_dir: data_dir.to_string_lossy().to_string(),
+            is_active: false,
+        })
+    }
+
+    /// All profiles, default first, then by name
+    pub fn list(&self) -> Vec<Profile> {
+        let mut profiles: Vec<Profile> = self
+            .profiles
+            .iter()
+            .map(|(name, dir)| Profile {
+                name: name.clone(),
+                data_dir: dir.to_string_lossy().to_string(),
+                is_active: *name == self.active,
+            })
+            .collect();
+        profiles.sort_by_key(|p| p.name != DEFAULT_PROFILE);
+        profiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_missing_file_gives_default_profile() {
+        let root = temp_dir("profiles");
+        let registry = ProfileRegistry::load(&root).unwrap();
+
+        assert_eq!(registry.active, DEFAULT_PROFILE);
+        assert_eq!(registry.active_dir(), *root);
+        assert_eq!(registry.list().len(), 1);
+        assert!(registry.list()[0].is_active);
+    }
+
+    #[test]
+    fn test_create_and_reload() {
+        let root = temp_dir("profiles");
+        let mut registry = ProfileRegistry::load(&root).unwrap();
+
+        let work = registry.create(&root, "  Work ").unwrap();
+        assert_eq!(work.name, "Work");
+        assert!(root.join("profiles").join("Work").is_dir());
+
+        registry.active = "Work".to_string();
+        registry.save(&root).unwrap();
+
+        let reloaded = ProfileRegistry::load(&root).unwrap();
+        assert_eq!(reloaded.active, "Work");
+        assert_eq!(reloaded.active_dir(), root.join("profiles").join("Work"));
+
+        let names: Vec<String> = reloaded.list().into_iter().map(|p| p.name).collect();
+        assert_eq!(names, vec!["default".to_string(), "Work".to_string()]);
+    }
+
+    #[test]
+    fn test_create_rejects_duplicates_and_bad_names() {
+        let root = temp_dir("profiles");
+        let mut registry = ProfileRegistry::load(&root).unwrap();
+
+        registry.create(&root, "work").unwrap();
+        assert!(registry.create(&root, "WORK").is_err());
+        assert!(registry.create(&root, "Default").is_err());
+        assert!(registry.create(&root, "").is_err());
+        assert!(registry.create(&root, "../escape").is_err());
+        assert!(registry
+            .create(&root, &"x".repeat(MAX_NAME_LEN + 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_load_repairs_registry() {
+        let root = temp_dir("profiles");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join(REGISTRY_FILE),
+            r#"{"active":"gone","profiles":{"default":"/elsewhere"}}"#,
+        )
+        .unwrap();
+
+        // Unknown active profile falls back to default, which always points at the root
+        let registry = ProfileRegistry::load(&root).unwrap();
+        assert_eq!(registry.active, DEFAULT_PROFILE);
+        assert_eq!(registry.data_dir(DEFAULT_PROFILE), Some(&*root));
+    }
+}
@@ -0,0 +1,539 @@
+//! Quick Look thumbnails
+//!
+//! Thumbnails are requested in-process from `QLThumbnailGenerator` first (see
+//! `generate_thumbnail_native`). When that API can't be used, `qlmanage -t`
+//! renders the thumbnail into an output directory instead. Large PDFs, slide
+//! decks and videos regularly need longer than the configured timeout, so
+//! those types get one more attempt with twice the time. Each qlmanage call
+//! writes into its own subdirectory of `~/.clipster/tmp/ql/`, so two files
+//! with the same name never share an output path; the subdirectory is removed
+//! afterwards, and whatever a crash left behind is cleared on startup.
+
+use crate::storage::profiles;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Output smaller than this is a failure qlmanage didn't report
+const MIN_THUMBNAIL_BYTES: usize = 100;
+
+/// How often a running qlmanage is checked for exit
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to wait for qlmanage's stderr after it exited or was killed
+const STDERR_GRACE: Duration = Duration::from_millis(200);
+
+/// How a qlmanage run ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunOutcome {
+    /// Exited within the timeout (successfully or not)
+    Exited { stderr: String },
+    /// Killed once the timeout passed
+    TimedOut { stderr: String },
+    /// Could not be started or waited on
+    Failed(String),
+}
+
+/// Runs qlmanage, replaced by a stub in tests
+pub trait CommandRunner {
+    fn run(&self, args: &[String], timeout: Duration) -> RunOutcome;
+}
+
+/// Spawns the real `qlmanage`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QlManage;
+
+impl CommandRunner for QlManage {
+    fn run(&self, args: &[String], timeout: Duration) -> RunOutcome {
+        let mut child = match Command::new("qlmanage")
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => return RunOutcome::Failed(format!("Failed to start qlmanage: {}", e)),
+        };
+
+        // Read on another thread so a chatty qlmanage can't block on a full pipe
+        let stderr = child.stderr.take().map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut output = String::new();
+                let _ = pipe.read_to_string(&mut output);
+                output
+            })
+        });
+
+        let start = Instant::now();
+        loop {
+            match child.try_wait() {
+                Ok(Some(_)) => {
+                    return RunOutcome::Exited {
+                        stderr: collect_stderr(stderr),
+                    }
+                }
+                Ok(None) if start.elapsed() > timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return RunOutcome::TimedOut {
+                        stderr: collect_stderr(stderr),
+                    };
+                }
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => {
+                    let _ = child.kill();
+                    return RunOutcome::Failed(format!("Failed to wait for qlmanage: {}", e));
+                }
+            }
+        }
+    }
+}
+
+/// Stderr read so far; helpers spawned by qlmanage may keep the pipe open
+fn collect_stderr(reader: Option<JoinHandle<String>>) -> String {
+    let Some(reader) = reader else {
+        return String::new();
+    };
+    let start = Instant::now();
+    while !reader.is_finished() && start.elapsed() < STDERR_GRACE {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    if reader.is_finished() {
+        reader.join().unwrap_or_default()
+    } else {
+        String::new()
+    }
+}
+
+/// Working directory shared by all qlmanage runs: ~/.clipster/tmp/ql
+pub fn work_dir() -> Result<PathBuf, String> {
+    Ok(profiles::clipster_root()?.join("tmp").join("ql"))
+}
+
+/// Remove what the last run left in the working directory: files last
+/// written before `written_before` (the launch), so thumbnails being
+/// generated since are left alone
+pub fn reset_work_dir(dir: &Path, written_before: SystemTime) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create Quick Look directory: {}", e))?;
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to clean Quick Look directory: {}", e))?;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata
+            .modified()
+            .is_ok_and(|modified| modified > written_before)
+        {
+            continue;
+        }
+        let removed = if metadata.is_dir() {
+            fs::remove_dir_all(entry.path())
+        } else {
+            fs::remove_file(entry.path())
+        };
+        if let Err(e) = removed {
+            return Err(format!("Failed to clean Quick Look directory: {}", e));
+        }
+    }
+    Ok(())
+}
+
+/// File types that routinely need longer than the configured timeout
+fn is_slow_type(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    matches!(
+        extension.as_deref(),
+        Some("pdf") | Some("pptx") | Some("mov")
+    )
+}
+
+/// Timeout of each attempt: a retry at double the timeout for slow types
+pub fn attempt_timeouts(path: &Path, timeout: Duration) -> Vec<Duration> {
+    if is_slow_type(path) {
+        vec![timeout, timeout * 2]
+    } else {
+        vec![timeout]
+    }
+}
+
+/// Render a PNG thumbnail of `path` with qlmanage
+/// Only a timeout is retried; a run that exited without usable output is final
+pub fn generate_thumbnail<R: CommandRunner>(
+    runner: &R,
+    work_dir: &Path,
+    path: &Path,
+    max_size: u32,
+    timeout: Duration,
+) -> Option<Vec<u8>> {
+    let run_dir = work_dir.join(uuid::Uuid::new_v4().to_string());
+    fs::create_dir_all(&run_dir).ok()?;
+
+    let png_data = run_qlmanage(runner, &run_dir, path, max_size, timeout);
+    let _ = fs::remove_dir_all(&run_dir);
+    png_data
+}
+
+/// Attempts of `generate_thumbnail`, writing into `run_dir`
+fn run_qlmanage<R: CommandRunner>(
+    runner: &R,
+    run_dir: &Path,
+    path: &Path,
+    max_size: u32,
+    timeout: Duration,
+) -> Option<Vec<u8>> {
+    // qlmanage names its output after the input file: report.pdf -> report.pdf.png
+    let output = run_dir.join(format!("{}.png", path.file_name()?.to_str()?));
+    let args = vec![
+        "-t".to_string(),
+        "-s".to_string(),
+        max_size.to_string(),
+        "-o".to_string(),
+        run_dir.to_str()?.to_string(),
+        path.to_str()?.to_string(),
+    ];
+
+    for (attempt, timeout) in attempt_timeouts(path, timeout).into_iter().enumerate() {
+        let _ = fs::remove_file(&output);
+        match runner.run(&args, timeout) {
+            RunOutcome::Exited { stderr } => {
+                log_stderr(&stderr);
+                let png_data = fs::read(&output).ok();
+                return png_data.filter(|data| data.len() >= MIN_THUMBNAIL_BYTES);
+            }
+            RunOutcome::TimedOut { stderr } => {
+                log_stderr(&stderr);
+                eprintln!(
+                    "[quicklook] Attempt {} timed out after {:?}: {:?}",
+                    attempt + 1,
+                    timeout,
+                    path.file_name()
+                );
+            }
+            RunOutcome::Failed(e) => {
+                eprintln!("[quicklook] {}", e);
+                return None;
+            }
+        }
+    }
+
+    None
+}
+
+/// Why `generate_thumbnail_native` produced no thumbnail
+#[cfg(target_os = "macos")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NativeThumbnailError {
+    /// QuickLookThumbnailing could not be loaded
+    Unavailable,
+    /// Quick Look is still working; qlmanage would be just as slow
+    TimedOut,
+    /// The request failed or its image couldn't be converted
+    Failed(String),
+}
+
+/// Path of the QuickLookThumbnailing framework binary
+#[cfg(target_os = "macos")]
+const QUICKLOOK_THUMBNAILING_FRAMEWORK: &str =
+    "/System/Library/Frameworks/QuickLookThumbnailing.framework/QuickLookThumbnailing";
+
+/// QLThumbnailGenerationRequestRepresentationTypeThumbnail (no generic file icons)
+#[cfg(target_os = "macos")]
+const REPRESENTATION_TYPE_THUMBNAIL: usize = 1 << 2;
+
+/// Load QuickLookThumbnailing once; loaded at runtime rather than linked so a
+/// missing framework falls back to qlmanage instead of failing at launch
+#[cfg(target_os = "macos")]
+fn load_thumbnailing_framework() -> bool {
+    use std::ffi::{c_char, c_int, c_void, CString};
+    use std::sync::OnceLock;
+
+    extern "C" {
+        fn dlopen(path: *const c_char, mode: c_int) -> *mut c_void;
+    }
+    /// RTLD_LAZY
+    const RTLD_LAZY: c_int = 1;
+
+    static LOADED: OnceLock<bool> = OnceLock::new();
+    *LOADED.get_or_init(|| {
+        let Ok(path) = CString::new(QUICKLOOK_THUMBNAILING_FRAMEWORK) else {
+            return false;
+        };
+        !unsafe { dlopen(path.as_ptr(), RTLD_LAZY) }.is_null()
+    })
+}
+
+/// Render a PNG thumbnail of `path` with QLThumbnailGenerator
+/// Runs in-process: no qlmanage spawn and no output file to read back
+#[cfg(target_os = "macos")]
+pub fn generate_thumbnail_native(
+    path: &Path,
+    max_size: u32,
+    timeout: Duration,
+) -> Result<Vec<u8>, NativeThumbnailError> {
+    use block2::RcBlock;
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::{AnyClass, AnyObject};
+    use objc2::{msg_send, msg_send_id};
+    use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep, NSImage};
+    use objc2_foundation::{CGFloat, NSDictionary, NSError, NSSize, NSString, NSURL};
+    use std::sync::mpsc;
+
+    if !load_thumbnailing_framework() {
+        return Err(NativeThumbnailError::Unavailable);
+    }
+    let (Some(generator_class), Some(request_class)) = (
+        AnyClass::get("QLThumbnailGenerator"),
+        AnyClass::get("QLThumbnailGenerationRequest"),
+    ) else {
+        return Err(NativeThumbnailError::Unavailable);
+    };
+    let path_str = path
+        .to_str()
+        .ok_or_else(|| NativeThumbnailError::Failed("Path is not valid UTF-8".to_string()))?;
+
+    let (sender, receiver) = mpsc::channel::<Result<Vec<u8>, String>>();
+
+    unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(path_str));
+        let size = NSSize::new(max_size as CGFloat, max_size as CGFloat);
+        let request: Allocated<AnyObject> = msg_send_id![request_class, alloc];
+        let request: Retained<AnyObject> = msg_send_id![
+            request,
+            initWithFileAtURL: &*url,
+            size: size,
+            scale: 1.0 as CGFloat,
+            representationTypes: REPRESENTATION_TYPE_THUMBNAIL
+        ];
+        let generator: Retained<AnyObject> = msg_send_id![generator_class, sharedGenerator];
+
+        // Called once on a Quick Look queue, with either a representation or an error
+        let completion = RcBlock::new(move |thumbnail: *mut AnyObject, error: *mut NSError| {
+            let result = match thumbnail.as_ref() {
+                Some(thumbnail) => {
+                    let image: Option<Retained<NSImage>> = msg_send_id![thumbnail, NSImage];
+                    image
+                        .and_then(|image| image.TIFFRepresentation())
+                        .and_then(|tiff| NSBitmapImageRep::imageRepWithData(&tiff))
+                        .and_then(|bitmap| {
+                            bitmap.representationUsingType_properties(
+                                NSBitmapImageFileType::PNG,
+                                &NSDictionary::new(),
+                            )
+                        })
+                        .map(|png| png.bytes().to_vec())
+                        .ok_or_else(|| "Failed to convert thumbnail to PNG".to_string())
+                }
+                None => Err(error
+                    .as_ref()
+                    .map(|error| error.localizedDescription().to_string())
+                    .unwrap_or_else(|| "No thumbnail".to_string())),
+            };
+            let _ = sender.send(result);
+        });
+
+        let _: () = msg_send![
+            &generator,
+            generateBestRepresentationForRequest: &*request,
+            completionHandler: &*completion
+        ];
+
+        match receiver.recv_timeout(timeout) {
+            Ok(Ok(png_data)) if png_data.len() >= MIN_THUMBNAIL_BYTES => Ok(png_data),
+            Ok(Ok(_)) => Err(NativeThumbnailError::Failed(
+                "Thumbnail is empty".to_string(),
+            )),
+            Ok(Err(e)) => Err(NativeThumbnailError::Failed(e)),
+            Err(_) => {
+                let _: () = msg_send![&generator, cancelRequest: &*request];
+                Err(NativeThumbnailError::TimedOut)
+            }
+        }
+    }
+}
+
+/// Pass qlmanage's diagnostics on to the log
+fn log_stderr(stderr: &str) {
+    let stderr = stderr.trim();
+    if !stderr.is_empty() {
+        eprintln!("[quicklook] qlmanage stderr: {}", stderr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::cell::RefCell;
+    use std::collections::VecDeque;
+
+    const TIMEOUT: Duration = Duration::from_millis(3000);
+
+    /// Replays canned outcomes, writing `output` into the -o directory first
+    struct StubRunner {
+        outcomes: RefCell<VecDeque<(RunOutcome, Option<Vec<u8>>)>>,
+        timeouts: RefCell<Vec<Duration>>,
+        output_dirs: RefCell<Vec<PathBuf>>,
+    }
+
+    impl StubRunner {
+        fn new(outcomes: Vec<(RunOutcome, Option<Vec<u8>>)>) -> Self {
+            Self {
+                outcomes: RefCell::new(outcomes.into()),
+                timeouts: RefCell::new(Vec::new()),
+                output_dirs: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl CommandRunner for StubRunner {
+        fn run(&self, args: &[String], timeout: Duration) -> RunOutcome {
+            self.timeouts.borrow_mut().push(timeout);
+            self.output_dirs.borrow_mut().push(PathBuf::from(&args[4]));
+            let (outcome, output) = self
+                .outcomes
+                .borrow_mut()
+                .pop_front()
+                .expect("unexpected run");
+            if let Some(bytes) = output {
+                let name = Path::new(&args[5]).file_name().unwrap().to_str().unwrap();
+                fs::write(Path::new(&args[4]).join(format!("{}.png", name)), bytes).unwrap();
+            }
+            outcome
+        }
+    }
+
+    fn exited() -> RunOutcome {
+        RunOutcome::Exited {
+            stderr: String::new(),
+        }
+    }
+
+    fn timed_out() -> RunOutcome {
+        RunOutcome::TimedOut {
+            stderr: "still rendering".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_attempt_timeouts() {
+        assert_eq!(
+            attempt_timeouts(Path::new("/a/deck.pptx"), TIMEOUT),
+            vec![TIMEOUT, TIMEOUT * 2]
+        );
+        assert_eq!(attempt_timeouts(Path::new("/a/Clip.MOV"), TIMEOUT).len(), 2);
+        assert_eq!(
+            attempt_timeouts(Path::new("/a/notes.docx"), TIMEOUT),
+            vec![TIMEOUT]
+        );
+        assert_eq!(
+            attempt_timeouts(Path::new("/a/no_extension"), TIMEOUT),
+            vec![TIMEOUT]
+        );
+    }
+
+    #[test]
+    fn test_slow_type_retried_at_double_timeout() {
+        let dir = temp_dir("ql");
+        let runner = StubRunner::new(vec![(timed_out(), None), (exited(), Some(vec![7; 500]))]);
+
+        let png = generate_thumbnail(&runner, &dir, Path::new("/docs/report.pdf"), 400, TIMEOUT);
+        assert_eq!(png, Some(vec![7; 500]));
+        assert_eq!(*runner.timeouts.borrow(), vec![TIMEOUT, TIMEOUT * 2]);
+        // Both attempts write into one run directory, removed once read
+        let output_dirs = runner.output_dirs.borrow();
+        assert_eq!(output_dirs[0], output_dirs[1]);
+        assert_eq!(output_dirs[0].parent(), Some(dir.as_path()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_other_types_not_retried() {
+        let dir = temp_dir("ql");
+        let runner = StubRunner::new(vec![(timed_out(), None)]);
+        assert_eq!(
+            generate_thumbnail(&runner, &dir, Path::new("/docs/notes.docx"), 400, TIMEOUT),
+            None
+        );
+        assert_eq!(runner.timeouts.borrow().len(), 1);
+
+        // Slow types give up after the retry
+        let runner = StubRunner::new(vec![(timed_out(), None), (timed_out(), None)]);
+        assert_eq!(
+            generate_thumbnail(&runner, &dir, Path::new("/videos/clip.mov"), 400, TIMEOUT),
+            None
+        );
+        assert_eq!(runner.timeouts.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_exit_without_usable_output_is_final() {
+        let dir = temp_dir("ql");
+        let path = Path::new("/docs/report.pdf");
+
+        let runner = StubRunner::new(vec![(exited(), None)]);
+        assert_eq!(generate_thumbnail(&runner, &dir, path, 400, TIMEOUT), None);
+        assert_eq!(runner.timeouts.borrow().len(), 1);
+
+        let runner = StubRunner::new(vec![(exited(), Some(vec![0; 10]))]);
+        assert_eq!(generate_thumbnail(&runner, &dir, path, 400, TIMEOUT), None);
+
+        let runner = StubRunner::new(vec![(RunOutcome::Failed("no qlmanage".to_string()), None)]);
+        assert_eq!(generate_thumbnail(&runner, &dir, path, 400, TIMEOUT), None);
+        assert_eq!(runner.timeouts.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_stale_output_is_not_returned() {
+        let dir = temp_dir("ql");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("report.pdf.png"), vec![1; 500]).unwrap();
+
+        let runner = StubRunner::new(vec![(exited(), None)]);
+        let png = generate_thumbnail(&runner, &dir, Path::new("/docs/report.pdf"), 400, TIMEOUT);
+        assert_eq!(png, None);
+    }
+
+    #[test]
+    fn test_each_call_has_its_own_output_dir() {
+        let dir = temp_dir("ql");
+        // Same file name in two folders, as concurrent thumbnail requests may be
+        let runner = StubRunner::new(vec![
+            (exited(), Some(vec![1; 500])),
+            (exited(), Some(vec![2; 500])),
+        ]);
+
+        let first = generate_thumbnail(&runner, &dir, Path::new("/a/report.pdf"), 400, TIMEOUT);
+        let second = generate_thumbnail(&runner, &dir, Path::new("/b/report.pdf"), 400, TIMEOUT);
+        assert_eq!(first, Some(vec![1; 500]));
+        assert_eq!(second, Some(vec![2; 500]));
+
+        let output_dirs = runner.output_dirs.borrow();
+        assert_ne!(output_dirs[0], output_dirs[1]);
+        assert!(output_dirs.iter().all(|dir| !dir.exists()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_reset_work_dir() {
+        let root = temp_dir("ql");
+        let dir = root.join("tmp").join("ql");
+        reset_work_dir(&dir, SystemTime::now()).unwrap();
+        fs::write(dir.join("left-over.pdf.png"), b"x").unwrap();
+
+        // Written after the launch: a thumbnail being generated
+        reset_work_dir(&dir, SystemTime::UNIX_EPOCH).unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        reset_work_dir(&dir, SystemTime::now()).unwrap();
+        assert!(dir.is_dir());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+    }
+}
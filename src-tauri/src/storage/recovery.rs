@@ -0,0 +1,307 @@
+//! Startup recovery of a corrupted database
+//!
+//! A database that can't be opened or fails `PRAGMA integrity_check` is
+//! renamed aside (`clipster.db.corrupt-<timestamp>`, kept for manual
+//! inspection) and a fresh one is created in its place. Rows that can still be
+//! read from the old file are copied over table by table, so the app starts
+//! with whatever history survived instead of refusing to start.
+
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{Connection, ErrorCode, OpenFlags, OptionalExtension, Statement};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Tables copied from a corrupt database, parents first for the foreign keys.
+/// Settings come along so `favorites_pinboard_id` still matches a pinboard.
+const SALVAGED_TABLES: &[&str] = &["settings", "pinboards", "clipboard_items"];
+
+/// Files next to the database that belong to it
+const SIDE_FILE_SUFFIXES: &[&str] = &["-wal", "-shm"];
+
+/// Payload of the `database-recovered` event
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, JsonSchema)]
+pub struct RecoveryReport {
+    /// Where the corrupt database was moved
+    pub corrupt_path: String,
+    /// Why the database was considered corrupt
+    pub reason: String,
+    pub items_recovered: usize,
+    pub pinboards_recovered: usize,
+}
+
+/// Why the database at `db_path` needs recovering (None if it is healthy,
+/// missing, or fails for a reason renaming it wouldn't fix, like permissions)
+pub fn detect_corruption(db_path: &Path) -> Option<String> {
+    if !db_path.exists() {
+        return None;
+    }
+
+    match integrity_check(db_path) {
+        Ok(result) if result == "ok" => None,
+        Ok(result) => Some(result),
+        Err(e) if is_corruption(&e) => Some(e.to_string()),
+        Err(_) => None,
+    }
+}
+
+/// Move the database and its WAL/shared-memory files aside under a
+/// timestamped name; returns the new path of the database file
+pub fn move_aside(db_path: &Path) -> std::io::Result<PathBuf> {
+    let suffix = format!(".corrupt-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+    let aside = with_suffix(db_path, &suffix);
+    fs::rename(db_path, &aside)?;
+
+    for side in SIDE_FILE_SUFFIXES {
+        let from = with_suffix(db_path, side);
+        if from.exists() {
+            fs::rename(&from, with_suffix(&aside, side))?;
+        }
+    }
+
+    Ok(aside)
+}
+
+/// Copy the readable rows of the corrupt database at `corrupt_path` into
+/// `conn` (already migrated); returns the number of rows copied per table
+pub fn salvage(corrupt_path: &Path, conn: &Connection) -> Vec<(&'static str, usize)> {
+    // Read-only, so the file kept for inspection is left exactly as it was
+    let source = match Connection::open_with_flags(corrupt_path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Failed to open corrupt database for recovery: {}", e);
+            return SALVAGED_TABLES.iter().map(|table| (*table, 0)).collect();
+        }
+    };
+    // Skips the sanity checks that otherwise reject the whole schema of a
+    // truncated file (root pages past its end)
+    if let Err(e) = source.pragma_update(None, "writable_schema", true) {
+        eprintln!("Failed to relax schema checks for recovery: {}", e);
+    }
+
+    SALVAGED_TABLES
+        .iter()
+        .map(|table| (*table, copy_rows(&source, conn, table)))
+        .collect()
+}
+
+/// Copy the rows of `table` that can still be read
+/// Rows are read by seeking from one rowid to the next rather than by a single
+/// scan, so a damaged page only loses its own rows. Only columns present in
+/// both databases are copied; the rest keep their defaults.
+fn copy_rows(source: &Connection, dest: &Connection, table: &str) -> usize {
+    let dest_columns = column_names(dest, table);
+    let columns: Vec<String> = column_names(source, table)
+        .into_iter()
+        .filter(|column| dest_columns.contains(column))
+        .collect();
+    if columns.is_empty() {
+        return 0;
+    }
+
+    let column_list = columns.join(", ");
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let select = format!(
+        "SELECT rowid, {} FROM {} WHERE rowid >= ?1 ORDER BY rowid LIMIT 1",
+        column_list, table
+    );
+    // Settings the fresh database seeded with defaults take the recovered value
+    let insert = format!(
+        "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+        table, column_list, placeholders
+    );
+
+    let (mut read, mut write) = match (source.prepare(&select), dest.prepare(&insert)) {
+        (Ok(read), Ok(write)) => (read, write),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Failed to recover {}: {}", table, e);
+            return 0;
+        }
+    };
+
+    let mut copied = 0;
+    // Rowids are positive unless set explicitly
+    let mut next = Some(0);
+    while let Some(from) = next {
+        match seek_row(&mut read, from) {
+            Ok(Some((rowid, values))) => {
+                match write.execute(rusqlite::params_from_iter(values)) {
+                    Ok(_) => copied += 1,
+                    Err(e) => eprintln!("Skipped {} row {}: {}", table, rowid, e),
+                }
+                next = rowid.checked_add(1);
+            }
+            Ok(None) => break,
+            Err(e) => {
+                next = skip_damaged(&mut read, from);
+                eprintln!(
+                    "Skipped unreadable {} rows from rowid {}: {}",
+                    table, from, e
+                );
+            }
+        }
+    }
+
+    copied
+}
+
+/// First row at or after `from`: its rowid and the selected columns
+fn seek_row(read: &mut Statement, from: i64) -> rusqlite::Result<Option<(i64, Vec<Value>)>> {
+    read.query_row([from], |row| {
+        let values = (1..row.as_ref().column_count())
+            .map(|i| row.get::<_, Value>(i))
+            .collect::<rusqlite::Result<Vec<Value>>>()?;
+        Ok((row.get(0)?, values))
+    })
+    .optional()
+}
+
+/// Lowest rowid after the unreadable `from` whose seek succeeds again (None
+/// if every seek up to the end fails). Probes ahead with doubling steps, then
+/// narrows down so the rows at the start of the next readable page are kept.
+fn skip_damaged(read: &mut Statement, from: i64) -> Option<i64> {
+    let (mut failed, mut step) = (from, 1_i64);
+    let mut readable = loop {
+        if failed == i64::MAX {
+            return None;
+        }
+        let probe = failed.saturating_add(step);
+        if seek_row(read, probe).is_ok() {
+            break probe;
+        }
+        failed = probe;
+        step = step.saturating_mul(2);
+    };
+
+    while readable - failed > 1 {
+        let middle = failed + (readable - failed) / 2;
+        if seek_row(read, middle).is_ok() {
+            readable = middle;
+        } else {
+            failed = middle;
+        }
+    }
+    Some(readable)
+}
+
+/// Column names of `table` (empty if the table can't be read)
+fn column_names(conn: &Connection, table: &str) -> Vec<String> {
+    let sql = format!("PRAGMA table_info({})", table);
+    conn.prepare(&sql)
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get::<_, String>(1))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .unwrap_or_default()
+}
+
+/// `PRAGMA integrity_check` on a fresh read-only connection
+fn integrity_check(db_path: &Path) -> rusqlite::Result<String> {
+    let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(messages.join("; "))
+}
+
+/// Errors SQLite reports for a damaged or non-database file
+fn is_corruption(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase)
+    )
+}
+
+/// `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClipboardItem, Pinboard};
+    use crate::storage::Database;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_healthy_database_is_not_recovered() {
+        let dir = temp_dir("healthy");
+        assert_eq!(detect_corruption(&dir.join("clipster.db")), None);
+
+        drop(Database::open(&dir).unwrap());
+        let (db, report) = Database::open_or_recover(&dir).unwrap();
+        assert_eq!(report, None);
+        assert!(!db.get_pinboards(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_truncated_database_is_recovered() {
+        let dir = temp_dir("recovery");
+        let db_path = dir.join("clipster.db");
+        let pinboard_id = {
+            let db = Database::open(&dir).unwrap();
+            let pinboard = Pinboard::new("Work".to_string(), None, 1);
+            db.insert_pinboard(&pinboard).unwrap();
+            // Enough rows to span many pages, so truncation loses some of them
+            let padding = "x".repeat(500);
+            for i in 0..200 {
+                let item = ClipboardItem::new_text(format!("{} {}", i, padding), None, None);
+                db.insert_item(&item).unwrap();
+            }
+            pinboard.id
+        };
+
+        let size = fs::metadata(&db_path).unwrap().len();
+        let file = fs::OpenOptions::new().write(true).open(&db_path).unwrap();
+        file.set_len(size / 2).unwrap();
+        drop(file);
+        assert!(detect_corruption(&db_path).is_some());
+
+        let (db, report) = Database::open_or_recover(&dir).unwrap();
+        let report = report.expect("truncated database should be recovered");
+
+        // The corrupt file is kept, a fresh database took its place
+        let corrupt_path = PathBuf::from(&report.corrupt_path);
+        assert!(corrupt_path.exists());
+        assert_eq!(corrupt_path.parent(), Some(dir.as_path()));
+        assert_eq!(detect_corruption(&db_path), None);
+
+        // Recovered rows are in the new database; rows past the cut are gone
+        assert_eq!(report.pinboards_recovered, 2);
+        assert!(db.get_pinboard(&pinboard_id).unwrap().is_some());
+        let count = db.count_items().unwrap();
+        assert_eq!(count, report.items_recovered);
+        assert!(count > 0 && count < 200, "recovered {} of 200 items", count);
+
+        // The recovered Favorites pinboard is reused rather than duplicated
+        assert_eq!(db.get_pinboards(true).unwrap().len(), 2);
+
+        // And it is usable
+        db.insert_item(&ClipboardItem::new_text("after".to_string(), None, None))
+            .unwrap();
+        assert_eq!(db.count_items().unwrap(), count + 1);
+
+        drop(db);
+    }
+
+    #[test]
+    fn test_garbage_file_is_recovered_empty() {
+        let dir = temp_dir("garbage");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("clipster.db"), vec![0x42; 8192]).unwrap();
+
+        let (db, report) = Database::open_or_recover(&dir).unwrap();
+        let report = report.unwrap();
+        assert_eq!((report.items_recovered, report.pinboards_recovered), (0, 0));
+        // First-run setup still runs on the fresh database
+        assert_eq!(db.get_pinboards(true).unwrap().len(), 1);
+
+        drop(db);
+    }
+}
@@ -0,0 +1,333 @@
+//! Rebuilding derived item state from the source data
+//!
+//! Some columns are computed from an item's content when it is captured: the
+//! canonical URL of links, the summary line of multi-file copies and image
+//! thumbnails. The app icon cache holds icons of executables that may since
+//! have been removed. After a crash or a manual edit of the database these can
+//! drift; `reindex` recomputes the selected components and writes only the
+//! values that differ, in transactions of `REINDEX_BATCH_SIZE`, so running it
+//! again right after finds nothing to do.
+
+use crate::error::ClipsterError;
+use crate::storage::{file_storage, Database};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Rows written per transaction
+pub const REINDEX_BATCH_SIZE: usize = 200;
+
+/// Derived state `reindex` can rebuild
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReindexComponent {
+    /// `canonical_url` of links (dedupe key)
+    CanonicalUrls,
+    /// `files_summary` of multi-file copies
+    FilesSummaries,
+    /// Missing image thumbnails, regenerated from the stored image
+    Thumbnails,
+    /// Cached icons of executables that no longer exist
+    AppIcons,
+}
+
+impl ReindexComponent {
+    pub const ALL: [ReindexComponent; 4] = [
+        ReindexComponent::CanonicalUrls,
+        ReindexComponent::FilesSummaries,
+        ReindexComponent::Thumbnails,
+        ReindexComponent::AppIcons,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReindexComponent::CanonicalUrls => "canonical_urls",
+            ReindexComponent::FilesSummaries => "files_summaries",
+            ReindexComponent::Thumbnails => "thumbnails",
+            ReindexComponent::AppIcons => "app_icons",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|component| component.name() == value)
+    }
+
+    /// Parse component names, rejecting the whole list if one is unknown
+    pub fn parse_all(values: &[String]) -> Result<Vec<Self>, ClipsterError> {
+        let mut components = Vec::with_capacity(values.len());
+        for value in values {
+            let component = Self::parse(value).ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|c| c.name()).collect();
+                ClipsterError::Validation(format!(
+                    "Unknown reindex component: {} (expected one of {})",
+                    value,
+                    known.join(", ")
+                ))
+            })?;
+            if !components.contains(&component) {
+                components.push(component);
+            }
+        }
+        Ok(components)
+    }
+}
+
+/// Item column rewritten by a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivedColumn {
+    CanonicalUrl,
+    FilesSummary,
+    Thumbnail,
+}
+
+impl DerivedColumn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DerivedColumn::CanonicalUrl => "canonical_url",
+            DerivedColumn::FilesSummary => "files_summary",
+            DerivedColumn::Thumbnail => "thumbnail_base64",
+        }
+    }
+}
+
+/// New value of a derived column for one item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedUpdate {
+    pub id: String,
+    pub value: Option<String>,
+}
+
+/// Sent after each committed batch
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ReindexProgress {
+    pub component: ReindexComponent,
+    pub processed: usize,
+    pub total: usize,
+}
+
+/// Rows changed per component
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReindexReport {
+    pub updated: BTreeMap<ReindexComponent, usize>,
+    pub cancelled: bool,
+}
+
+/// Rebuild `components` in order, stopping between batches once `cancel` is set
+pub fn reindex<F>(
+    db: &Database,
+    components: &[ReindexComponent],
+    cancel: &AtomicBool,
+    mut on_progress: F,
+) -> Result<ReindexReport, ClipsterError>
+where
+    F: FnMut(&ReindexProgress),
+{
+    let mut report = ReindexReport::default();
+    for &component in components {
+        if cancel.load(Ordering::SeqCst) {
+            report.cancelled = true;
+            break;
+        }
+
+        let mut progress = |processed, total| {
+            on_progress(&ReindexProgress {
+                component,
+                processed,
+                total,
+            })
+        };
+        let (updated, finished) = match component {
+            ReindexComponent::CanonicalUrls => {
+                let updates = db.find_canonical_url_updates()?;
+                apply(db, DerivedColumn::CanonicalUrl, &updates, cancel, progress)?
+            }
+            ReindexComponent::FilesSummaries => {
+                let updates = db.find_files_summary_updates(dirs::home_dir().as_deref())?;
+                apply(db, DerivedColumn::FilesSummary, &updates, cancel, progress)?
+            }
+            ReindexComponent::Thumbnails => {
+                let updates = missing_thumbnails(db)?;
+                apply(db, DerivedColumn::Thumbnail, &updates, cancel, progress)?
+            }
+            ReindexComponent::AppIcons => {
+                let removed = db.prune_app_icons(|path| Path::new(path).exists())?;
+                progress(removed, removed);
+                (removed, true)
+            }
+        };
+
+        report.updated.insert(component, updated);
+        if !finished {
+            report.cancelled = true;
+            break;
+        }
+    }
+    Ok(report)
+}
+
+/// Thumbnails for image items that have none, from their stored image
+/// Images that can't be read are skipped and stay without a thumbnail
+pub fn missing_thumbnails(db: &Database) -> Result<Vec<DerivedUpdate>, ClipsterError> {
+    let format = db
+        .get_setting("thumbnail_format")?
+        .and_then(|value| file_storage::ExportFormat::parse(&value))
+        .unwrap_or(file_storage::ExportFormat::Png);
+
+    let mut updates = Vec::new();
+    for (id, image_path) in db.get_items_missing_thumbnail()? {
+        match file_storage::thumbnail_from_file(Path::new(&image_path), format) {
+            Ok(thumbnail) => updates.push(DerivedUpdate {
+                id,
+                value: Some(thumbnail),
+            }),
+            Err(e) => eprintln!("[reindex] No thumbnail for item {}: {}", id, e),
+        }
+    }
+    Ok(updates)
+}
+
+/// Write `updates` batch by batch; returns the rows changed and whether every
+/// batch was written before `cancel` was set
+fn apply(
+    db: &Database,
+    column: DerivedColumn,
+    updates: &[DerivedUpdate],
+    cancel: &AtomicBool,
+    mut progress: impl FnMut(usize, usize),
+) -> Result<(usize, bool), ClipsterError> {
+    let mut updated = 0;
+    let mut processed = 0;
+    for batch in updates.chunks(REINDEX_BATCH_SIZE) {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok((updated, false));
+        }
+        updated += db.update_derived_column(column, batch)?;
+        processed += batch.len();
+        progress(processed, updates.len());
+    }
+    Ok((updated, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClipboardItem, ContentType};
+    use image::{Rgb, RgbImage};
+
+    fn run(db: &Database, components: &[ReindexComponent]) -> ReindexReport {
+        reindex(db, components, &AtomicBool::new(false), |_| {}).unwrap()
+    }
+
+    fn updated(report: &ReindexReport, component: ReindexComponent) -> usize {
+        report.updated.get(&component).copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_parse_components() {
+        let names = vec!["thumbnails".to_string(), "app_icons".to_string()];
+        assert_eq!(
+            ReindexComponent::parse_all(&names).unwrap(),
+            vec![ReindexComponent::Thumbnails, ReindexComponent::AppIcons]
+        );
+
+        let error = ReindexComponent::parse_all(&["fts".to_string()]).unwrap_err();
+        assert!(matches!(error, ClipsterError::Validation(_)));
+        assert!(error.to_string().contains("canonical_urls"), "{}", error);
+    }
+
+    #[test]
+    fn test_canonical_urls_rebuild_once() {
+        let db = Database::new_in_memory().unwrap();
+        let mut link = ClipboardItem::new_text(
+            "https://example.com/post?utm_source=feed".to_string(),
+            None,
+            None,
+        );
+        assert_eq!(link.content_type, ContentType::Link);
+        link.canonical_url = Some("stale".to_string());
+        db.insert_item(&link).unwrap();
+        let mut text = ClipboardItem::new_text("hello".to_string(), None, None);
+        text.canonical_url = Some("https://example.com/post".to_string());
+        db.insert_item(&text).unwrap();
+
+        let report = run(&db, &[ReindexComponent::CanonicalUrls]);
+        assert_eq!(updated(&report, ReindexComponent::CanonicalUrls), 2);
+        assert_eq!(
+            db.require_item(&link.id).unwrap().canonical_url.as_deref(),
+            Some("https://example.com/post")
+        );
+        assert_eq!(db.require_item(&text.id).unwrap().canonical_url, None);
+
+        let report = run(&db, &[ReindexComponent::CanonicalUrls]);
+        assert_eq!(updated(&report, ReindexComponent::CanonicalUrls), 0);
+    }
+
+    #[test]
+    fn test_files_summaries_rebuild_once() {
+        let db = Database::new_in_memory().unwrap();
+        let paths = vec!["/p/a.jpg".to_string(), "/p/b.jpg".to_string()];
+        let files = ClipboardItem::new_files(paths, None, None);
+        db.insert_item(&files).unwrap();
+
+        let report = run(&db, &[ReindexComponent::FilesSummaries]);
+        assert_eq!(updated(&report, ReindexComponent::FilesSummaries), 1);
+        assert_eq!(
+            db.require_item(&files.id).unwrap().files_summary.as_deref(),
+            Some("2 files from /p — 2 jpg")
+        );
+
+        let report = run(&db, &[ReindexComponent::FilesSummaries]);
+        assert_eq!(updated(&report, ReindexComponent::FilesSummaries), 0);
+    }
+
+    #[test]
+    fn test_missing_thumbnails_rebuild_once() {
+        let db = Database::new_in_memory().unwrap();
+        let name = format!("clipster-reindex-{}.png", uuid::Uuid::new_v4());
+        let path = std::env::temp_dir().join(name);
+        RgbImage::from_pixel(8, 6, Rgb([10, 200, 10]))
+            .save(&path)
+            .unwrap();
+        let image_path = path.to_string_lossy().to_string();
+        let item = ClipboardItem::new_image(None, image_path, None, None);
+        db.insert_item(&item).unwrap();
+        let gone = ClipboardItem::new_image(None, "/nonexistent/x.png".to_string(), None, None);
+        db.insert_item(&gone).unwrap();
+
+        let report = run(&db, &[ReindexComponent::Thumbnails]);
+        assert_eq!(updated(&report, ReindexComponent::Thumbnails), 1);
+        let thumbnail = |id: &str| db.require_item(id).unwrap().thumbnail_base64;
+        assert!(thumbnail(&item.id).is_some());
+        assert!(thumbnail(&gone.id).is_none());
+
+        let report = run(&db, &[ReindexComponent::Thumbnails]);
+        assert_eq!(updated(&report, ReindexComponent::Thumbnails), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_app_icons_prune_once() {
+        let db = Database::new_in_memory().unwrap();
+        let (kept, removed) = ("/Applications/Kept.app", "/Applications/Removed.app");
+        db.set_app_icon(kept, "icon").unwrap();
+        db.set_app_icon(removed, "icon").unwrap();
+
+        let exists = |path: &str| path == kept;
+        assert_eq!(db.prune_app_icons(exists).unwrap(), 1);
+        assert!(db.get_app_icon(kept).unwrap().is_some());
+        assert!(db.get_app_icon(removed).unwrap().is_none());
+        assert_eq!(db.prune_app_icons(exists).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_cancel_stops_before_next_component() {
+        let db = Database::new_in_memory().unwrap();
+        let report = reindex(&db, &ReindexComponent::ALL, &AtomicBool::new(true), |_| {}).unwrap();
+        assert!(report.cancelled);
+        assert!(report.updated.is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Settings export and import
+//!
+//! A snapshot is a JSON file holding every row of the settings table with a
+//! format version, kept apart from history backups. Importing checks the
+//! version and every value (with the checks `invalid_settings` uses) before
+//! writing anything, then writes all settings in one transaction. Settings
+//! missing from the file keep their current value.
+
+use crate::error::{ClipsterError, Context};
+use crate::storage::Database;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Snapshot format version
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Contents of a settings file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsSnapshot {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Write every setting to `path`; returns the number of settings written
+pub fn export_settings(db: &Database, path: &Path) -> Result<usize, ClipsterError> {
+    let snapshot = SettingsSnapshot {
+        version: SNAPSHOT_VERSION,
+        exported_at: Utc::now(),
+        settings: db.get_all_settings()?,
+    };
+    let json = serde_json::to_string_pretty(&snapshot).context("Failed to encode settings")?;
+    fs::write(path, json).context("Failed to write settings file")?;
+    Ok(snapshot.settings.len())
+}
+
+/// Read a settings file and apply it; returns the number of settings written
+pub fn import_settings(db: &Database, path: &Path) -> Result<usize, ClipsterError> {
+    let json = fs::read_to_string(path).context("Failed to read settings file")?;
+    let snapshot = parse_snapshot(&json)?;
+    db.set_settings(&snapshot.settings)?;
+    Ok(snapshot.settings.len())
+}
+
+/// Parse a settings file, rejecting versions this build doesn't know
+pub fn parse_snapshot(json: &str) -> Result<SettingsSnapshot, ClipsterError> {
+    let snapshot: SettingsSnapshot = serde_json::from_str(json)
+        .map_err(|e| ClipsterError::Validation(format!("Invalid settings file: {}", e)))?;
+    if snapshot.version != SNAPSHOT_VERSION {
+        return Err(ClipsterError::Validation(format!(
+            "Unsupported settings file version: {}",
+            snapshot.version
+        )));
+    }
+    Ok(snapshot)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::database::DEFAULT_SETTINGS;
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("clipster-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("theme", "light").unwrap();
+        db.set_setting("history_limit", "2000").unwrap();
+        let path = temp_path("settings-round-trip");
+
+        let exported = export_settings(&db, &path).unwrap();
+        assert_eq!(exported, db.get_all_settings().unwrap().len());
+
+        let other = Database::new_in_memory().unwrap();
+        assert_eq!(import_settings(&other, &path).unwrap(), exported);
+        assert_eq!(
+            other.get_all_settings().unwrap(),
+            db.get_all_settings().unwrap()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_value_rejects_whole_import() {
+        let db = Database::new_in_memory().unwrap();
+        let json = r#"{
+            "version": 1,
+            "exported_at": "2026-10-01T09:00:00Z",
+            "settings": { "theme": "light", "history_limit": "lots" }
+        }"#;
+        let snapshot = parse_snapshot(json).unwrap();
+
+        let error = db.set_settings(&snapshot.settings).unwrap_err();
+        assert!(matches!(error, ClipsterError::Validation(_)));
+        assert!(error.to_string().contains("history_limit"), "{}", error);
+        assert_eq!(db.get_setting("theme").unwrap().as_deref(), Some("dark"));
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let json = r#"{"version": 2, "exported_at": "2026-10-01T09:00:00Z", "settings": {}}"#;
+        let error = parse_snapshot(json).unwrap_err();
+        assert!(error.to_string().contains("version: 2"), "{}", error);
+
+        assert!(parse_snapshot(r#"{"settings": {}}"#).is_err());
+    }
+
+    #[test]
+    fn test_reset_to_defaults() {
+        let db = Database::new_in_memory().unwrap();
+        db.set_setting("theme", "light").unwrap();
+        db.set_setting("exclude_from_os_history", "true").unwrap();
+        db.set_setting("first_run_completed", "true").unwrap();
+
+        db.reset_settings_to_defaults().unwrap();
+        for (key, value) in DEFAULT_SETTINGS {
+            assert_eq!(
+                db.get_setting(key).unwrap().as_deref(),
+                Some(*value),
+                "{}",
+                key
+            );
+        }
+        // Internal state is not a default and stays
+        assert_eq!(
+            db.get_setting("first_run_completed").unwrap().as_deref(),
+            Some("true")
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Item counts, compression savings and usage counters returned by `get_stats`
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StorageStats {
+    pub total_items: usize,
+    /// Items whose text is stored zstd-compressed
+    pub compressed_items: usize,
+    /// Size of those texts before compression
+    pub uncompressed_bytes: u64,
+    /// Size of the compressed blobs
+    pub compressed_bytes: u64,
+    pub saved_bytes: u64,
+    /// Lifetime usage counters by name (see `storage::counters`)
+    pub counters: BTreeMap<String, u64>,
+    /// Text items by detected language (ISO 639-3 code)
+    pub languages: BTreeMap<String, usize>,
+}
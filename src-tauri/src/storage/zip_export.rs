@@ -0,0 +1,431 @@
+//! Export of selected clipboard items as a ZIP archive
+//!
+//! Each item becomes one numbered file (`001-text.txt`, `002-image.png`, ...)
+//! and `manifest.json` describes them. Entries are written one item at a time
+//! and images are streamed from `FileStorage`, so large selections never sit
+//! in memory together. Items whose assets are missing, or that are in a
+//! locked pinboard, are listed in the manifest's `errors` array instead of
+//! failing the export.
+
+use crate::models::{ClipboardItem, ContentType};
+use crate::storage::{Database, FileStorage, PinboardLocks};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Name of the manifest entry
+pub const MANIFEST_NAME: &str = "manifest.json";
+
+/// Manifest format version
+const MANIFEST_VERSION: u32 = 1;
+
+/// An exported item as described in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Entry name inside the archive
+    pub file: String,
+    pub id: String,
+    pub content_type: ContentType,
+    pub source_app: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub pinboard_id: Option<String>,
+    pub is_favorite: bool,
+}
+
+/// An item that could not be exported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestError {
+    pub id: String,
+    pub error: String,
+}
+
+/// Contents of `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub items: Vec<ManifestEntry>,
+    pub errors: Vec<ManifestError>,
+}
+
+/// Result of `export_items_zip`
+#[derive(Debug, Clone, Serialize)]
+pub struct ZipExportSummary {
+    pub path: String,
+    pub exported: usize,
+    pub errors: Vec<ManifestError>,
+}
+
+/// Entry extension for an item's content type
+fn extension(content_type: ContentType) -> &'static str {
+    match content_type {
+        ContentType::Image => "png",
+        ContentType::Link => "url",
+        ContentType::Text | ContentType::Files | ContentType::Audio | ContentType::Documents => {
+            "txt"
+        }
+    }
+}
+
+/// Text written for a non-image item
+/// Links become internet shortcuts; file items list their paths, one per line
+fn text_body(item: &ClipboardItem) -> Option<String> {
+    match item.content_type {
+        ContentType::Text => item.content_text.clone(),
+        ContentType::Link => item
+            .content_text
+            .as_ref()
+            .map(|url| format!("[InternetShortcut]\r\nURL={}\r\n", url.trim())),
+        ContentType::Files | ContentType::Audio | ContentType::Documents => {
+            item.get_file_paths().map(|paths| paths.join("\n"))
+        }
+        ContentType::Image => None,
+    }
+}
+
+/// Write the items with `item_ids` (in that order) to a ZIP archive at `dest`
+pub fn export_items_zip(
+    db: &Database,
+    storage: &FileStorage,
+    locks: &PinboardLocks,
+    item_ids: &[String],
+    dest: &Path,
+) -> Result<ZipExportSummary, String> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create export directory: {}", e))?;
+    }
+    let file = File::create(dest).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let width = item_ids.len().to_string().len().max(3);
+    let mut manifest = ExportManifest {
+        version: MANIFEST_VERSION,
+        exported_at: Utc::now(),
+        items: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    for (index, id) in item_ids.iter().enumerate() {
+        let item = match db.get_item(id)? {
+            Some(item) => item,
+            None => {
+                manifest.errors.push(ManifestError {
+                    id: id.clone(),
+                    error: "Item not found".to_string(),
+                });
+                continue;
+            }
+        };
+        if let Err(e) = locks.require_unlocked(db, &item, Utc::now()) {
+            manifest.errors.push(ManifestError {
+                id: id.clone(),
+                error: e.to_string(),
+            });
+            continue;
+        }
+
+        let name = format!(
+            "{:0width$}-{}.{}",
+            index + 1,
+            item.content_type.as_str(),
+            extension(item.content_type),
+            width = width
+        );
+        match write_item(&mut zip, storage, &item, &name) {
+            Ok(()) => {}
+            Err(EntryError::Missing(error)) => {
+                manifest.errors.push(ManifestError {
+                    id: id.clone(),
+                    error,
+                });
+                continue;
+            }
+            Err(EntryError::Write(e)) => return Err(e),
+        }
+
+        manifest.items.push(ManifestEntry {
+            file: name,
+            id: item.id,
+            content_type: item.content_type,
+            source_app: item.source_app,
+            created_at: item.created_at,
+            pinboard_id: item.pinboard_id,
+            is_favorite: item.is_favorite,
+        });
+    }
+
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+    zip.start_file(MANIFEST_NAME, deflated())
+        .and_then(|_| zip.write_all(&json).map_err(Into::into))
+        .map_err(|e| format!("Failed to write manifest: {}", e))?;
+    zip.finish()
+        .map_err(|e| format!("Failed to finish archive: {}", e))?;
+
+    Ok(ZipExportSummary {
+        path: dest.to_string_lossy().to_string(),
+        exported: manifest.items.len(),
+        errors: manifest.errors,
+    })
+}
+
+fn deflated() -> SimpleFileOptions {
+    SimpleFileOptions::default().compression_method(CompressionMethod::Deflated)
+}
+
+/// Why an item's entry was not written
+enum EntryError {
+    /// The item's content or image file is missing; reported in the manifest
+    Missing(String),
+    /// Writing the archive failed; aborts the export
+    Write(String),
+}
+
+/// Write one item's entry
+fn write_item(
+    zip: &mut ZipWriter<File>,
+    storage: &FileStorage,
+    item: &ClipboardItem,
+    name: &str,
+) -> Result<(), EntryError> {
+    let write_err =
+        |e: &dyn std::fmt::Display| EntryError::Write(format!("Failed to write {}: {}", name, e));
+
+    if item.content_type == ContentType::Image {
+        // The stored path is absolute, possibly in the fallback directory
+        let Some(path) = item.image_path.as_deref().map(Path::new) else {
+            return Err(EntryError::Missing("Item has no image file".to_string()));
+        };
+        let Ok(mut source) = File::open(path) else {
+            return Err(EntryError::Missing(format!(
+                "Image file not found: {}",
+                path.display()
+            )));
+        };
+
+        // PNG data is already compressed
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        if path.extension().is_some_and(|ext| ext == "png") {
+            zip.start_file(name, stored).map_err(|e| write_err(&e))?;
+            io::copy(&mut source, zip).map_err(|e| write_err(&e))?;
+            return Ok(());
+        }
+
+        // Images kept in their original format are converted to match the name
+        let png = storage
+            .read_png(path)
+            .map_err(|e| EntryError::Missing(format!("Unreadable image file: {}", e)))?;
+        zip.start_file(name, stored).map_err(|e| write_err(&e))?;
+        zip.write_all(&png).map_err(|e| write_err(&e))?;
+        return Ok(());
+    }
+
+    let Some(body) = text_body(item) else {
+        return Err(EntryError::Missing("Item has no content".to_string()));
+    };
+    zip.start_file(name, deflated())
+        .map_err(|e| write_err(&e))?;
+    zip.write_all(body.as_bytes()).map_err(|e| write_err(&e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_export_mixed_selection() {
+        let root = temp_dir("zip");
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+
+        let text =
+            ClipboardItem::new_text("hello zip".to_string(), Some("Notes".to_string()), None);
+        let link = ClipboardItem::new_text("https://example.com/a".to_string(), None, None);
+
+        let image_id = uuid::Uuid::new_v4().to_string();
+        let image_path = storage
+            .save_png_bytes(&image_id, b"fake png bytes")
+            .unwrap();
+        let image =
+            ClipboardItem::new_image(None, image_path.to_string_lossy().to_string(), None, None);
+
+        let missing = ClipboardItem::new_image(
+            None,
+            root.join("images")
+                .join("gone.png")
+                .to_string_lossy()
+                .to_string(),
+            None,
+            None,
+        );
+        let files = ClipboardItem::new_files(
+            vec!["/tmp/a.bin".to_string(), "/tmp/b.bin".to_string()],
+            None,
+            None,
+        );
+
+        for item in [&text, &link, &image, &missing, &files] {
+            db.insert_item(item).unwrap();
+        }
+
+        let ids = vec![
+            text.id.clone(),
+            link.id.clone(),
+            image.id.clone(),
+            missing.id.clone(),
+            "unknown-id".to_string(),
+            files.id.clone(),
+        ];
+        let dest = root.join("out").join("export.zip");
+        let locks = PinboardLocks::new();
+        let summary = export_items_zip(&db, &storage, &locks, &ids, &dest).unwrap();
+        assert_eq!(summary.exported, 4);
+        assert_eq!(summary.errors.len(), 2);
+
+        let mut archive = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(|n| n.to_string()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "001-text.txt",
+                "002-link.url",
+                "003-image.png",
+                "006-files.txt",
+                "manifest.json"
+            ]
+        );
+
+        assert_eq!(read_entry(&mut archive, "001-text.txt"), b"hello zip");
+        assert_eq!(
+            String::from_utf8(read_entry(&mut archive, "002-link.url")).unwrap(),
+            "[InternetShortcut]\r\nURL=https://example.com/a\r\n"
+        );
+        assert_eq!(read_entry(&mut archive, "003-image.png"), b"fake png bytes");
+        assert_eq!(
+            read_entry(&mut archive, "006-files.txt"),
+            b"/tmp/a.bin\n/tmp/b.bin"
+        );
+
+        let manifest: ExportManifest =
+            serde_json::from_slice(&read_entry(&mut archive, MANIFEST_NAME)).unwrap();
+        assert_eq!(manifest.version, MANIFEST_VERSION);
+        let files_in_manifest: Vec<&str> = manifest.items.iter().map(|i| i.file.as_str()).collect();
+        assert_eq!(
+            files_in_manifest,
+            vec![
+                "001-text.txt",
+                "002-link.url",
+                "003-image.png",
+                "006-files.txt"
+            ]
+        );
+        assert_eq!(manifest.items[0].id, text.id);
+        assert_eq!(manifest.items[0].source_app.as_deref(), Some("Notes"));
+        assert_eq!(manifest.items[2].content_type, ContentType::Image);
+
+        assert_eq!(manifest.errors[0].id, missing.id);
+        assert!(manifest.errors[0].error.starts_with("Image file not found"));
+        assert_eq!(
+            manifest.errors[1],
+            ManifestError {
+                id: "unknown-id".to_string(),
+                error: "Item not found".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_empty_selection_has_only_manifest() {
+        let root = temp_dir("zip");
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+
+        let dest = root.join("empty.zip");
+        let locks = PinboardLocks::new();
+        let summary = export_items_zip(&db, &storage, &locks, &[], &dest).unwrap();
+        assert_eq!(summary.exported, 0);
+
+        let archive = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        assert_eq!(
+            archive.file_names().collect::<Vec<_>>(),
+            vec![MANIFEST_NAME]
+        );
+    }
+
+    #[test]
+    fn test_original_format_image_is_exported_as_png() {
+        let root = temp_dir("zip");
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+
+        let mut tiff = Vec::new();
+        image::DynamicImage::new_rgba8(8, 4)
+            .write_to(&mut io::Cursor::new(&mut tiff), image::ImageFormat::Tiff)
+            .unwrap();
+        let path = storage.save_original_bytes("raw", &tiff, "tiff").unwrap();
+        let item = ClipboardItem::new_image(None, path.to_string_lossy().to_string(), None, None);
+        db.insert_item(&item).unwrap();
+
+        let dest = root.join("tiff.zip");
+        let locks = PinboardLocks::new();
+        let summary = export_items_zip(&db, &storage, &locks, &[item.id], &dest).unwrap();
+        assert_eq!(summary.exported, 1);
+
+        let mut archive = ZipArchive::new(File::open(&dest).unwrap()).unwrap();
+        let png = read_entry(&mut archive, "001-image.png");
+        assert_eq!(image::guess_format(&png).unwrap(), image::ImageFormat::Png);
+    }
+
+    #[test]
+    fn test_locked_pinboard_items_are_not_exported() {
+        use crate::models::Pinboard;
+
+        let root = temp_dir("zip");
+        let db = Database::new_in_memory().unwrap();
+        let storage = FileStorage::with_images_dir(root.join("images")).unwrap();
+        let locks = PinboardLocks::new();
+
+        let board = Pinboard::new("Personal".to_string(), None, 0);
+        db.insert_pinboard(&board).unwrap();
+        db.set_pinboard_lock_hash(&board.id, Some("$argon2id$hash"))
+            .unwrap();
+        let secret = ClipboardItem::new_text("secret".to_string(), None, None);
+        db.insert_item(&secret).unwrap();
+        db.update_item_pinboard(&secret.id, Some(&board.id)).unwrap();
+
+        let dest = root.join("locked.zip");
+        let ids = vec![secret.id.clone()];
+        let summary = export_items_zip(&db, &storage, &locks, &ids, &dest).unwrap();
+        assert_eq!(summary.exported, 0);
+        assert_eq!(
+            summary.errors,
+            vec![ManifestError {
+                id: secret.id.clone(),
+                error: "Pinboard is locked".to_string()
+            }]
+        );
+
+        locks.unlock(&board.id, 5, Utc::now());
+        let summary = export_items_zip(&db, &storage, &locks, &ids, &dest).unwrap();
+        assert_eq!(summary.exported, 1);
+    }
+}
@@ -0,0 +1,27 @@
+//! Text snippets with placeholders
+//!
+//! A text item holding `{name}` fields is a template: before pasting it the
+//! panel asks for the values, and the filled-in text goes on the clipboard.
+//! The stored item keeps its placeholders. See `placeholder` for the syntax.
+
+pub mod placeholder;
+
+pub use placeholder::{fill, placeholders, Placeholder};
+
+use crate::error::ClipsterError;
+use crate::models::{ClipboardItem, ContentType};
+use crate::storage::Database;
+
+/// Placeholders of the item `id` (empty for items that aren't templates)
+pub fn item_placeholders(db: &Database, id: &str) -> Result<Vec<Placeholder>, ClipsterError> {
+    let item = db.require_item(id)?;
+    Ok(template_text(&item).map(placeholders).unwrap_or_default())
+}
+
+/// Text of a text or link item, the only kinds that can be templates
+pub fn template_text(item: &ClipboardItem) -> Option<&str> {
+    match item.content_type {
+        ContentType::Text | ContentType::Link => item.content_text.as_deref(),
+        _ => None,
+    }
+}
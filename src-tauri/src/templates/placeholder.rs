@@ -0,0 +1,236 @@
+//! Placeholder syntax of text templates
+//!
+//! - `{name}` is a placeholder; names start with a letter or `_` and hold
+//!   letters, digits and `_`
+//! - `{name:default}` gives the value used when none is supplied; the default
+//!   runs to the next `}` and can't contain braces
+//! - `{{` and `}}` are a literal `{` and `}`
+//! - Any other brace is kept as typed, so JSON or code in a snippet is not
+//!   mistaken for a placeholder
+
+use crate::error::ClipsterError;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A field to fill in before pasting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Placeholder {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+/// Part of a parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Placeholder(Placeholder),
+}
+
+/// Split `template` into text and placeholders
+pub fn parse(template: &str) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push(c);
+            rest = &rest[2..];
+            continue;
+        }
+        if c == '{' {
+            if let Some((placeholder, len)) = parse_placeholder(rest) {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Placeholder(placeholder));
+                rest = &rest[len..];
+                continue;
+            }
+        }
+        text.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+/// Placeholders of `template` in order of first use, each name once
+/// A name used with several defaults keeps the first one given
+pub fn placeholders(template: &str) -> Vec<Placeholder> {
+    let mut found: Vec<Placeholder> = Vec::new();
+    for segment in parse(template) {
+        let Segment::Placeholder(placeholder) = segment else {
+            continue;
+        };
+        match found.iter_mut().find(|p| p.name == placeholder.name) {
+            Some(existing) => {
+                if existing.default.is_none() {
+                    existing.default = placeholder.default;
+                }
+            }
+            None => found.push(placeholder),
+        }
+    }
+    found
+}
+
+/// Substitute `values` into `template`
+///
+/// A placeholder without a value takes its default; if it has none either,
+/// fails with a `Validation` error naming every missing field. An empty value
+/// is a value. Values are inserted as given, braces included.
+pub fn fill(template: &str, values: &HashMap<String, String>) -> Result<String, ClipsterError> {
+    let defaults: HashMap<String, Option<String>> = placeholders(template)
+        .into_iter()
+        .map(|p| (p.name, p.default))
+        .collect();
+
+    let mut output = String::with_capacity(template.len());
+    let mut missing: Vec<String> = Vec::new();
+    for segment in parse(template) {
+        match segment {
+            Segment::Text(text) => output.push_str(&text),
+            Segment::Placeholder(placeholder) => {
+                let default = defaults.get(&placeholder.name).and_then(Option::as_ref);
+                match values.get(&placeholder.name).or(default) {
+                    Some(value) => output.push_str(value),
+                    None if !missing.contains(&placeholder.name) => missing.push(placeholder.name),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    if !missing.is_empty() {
+        return Err(ClipsterError::Validation(format!(
+            "Missing values for: {}",
+            missing.join(", ")
+        )));
+    }
+    Ok(output)
+}
+
+/// Parse the placeholder at the start of `input` (which starts with `{`)
+/// Returns it with the number of bytes it spans
+fn parse_placeholder(input: &str) -> Option<(Placeholder, usize)> {
+    let end = input.find('}')?;
+    let inner = &input[1..end];
+    if inner.contains('{') {
+        return None;
+    }
+
+    let (name, default) = match inner.split_once(':') {
+        Some((name, default)) => (name, Some(default.to_string())),
+        None => (inner, None),
+    };
+    if !is_identifier(name) {
+        return None;
+    }
+
+    let placeholder = Placeholder {
+        name: name.to_string(),
+        default,
+    };
+    Some((placeholder, end + 1))
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn field(name: &str, default: Option<&str>) -> Placeholder {
+        Placeholder {
+            name: name.to_string(),
+            default: default.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_placeholders_in_order_of_first_use() {
+        let template = "Hi {name}, your order {order_id} shipped via {carrier:UPS}. Thanks {name}!";
+        assert_eq!(
+            placeholders(template),
+            vec![
+                field("name", None),
+                field("order_id", None),
+                field("carrier", Some("UPS")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_uses_values_then_defaults() {
+        let template = "Hi {name}, your order {order_id} shipped via {carrier:UPS}";
+        let filled = fill(template, &values(&[("name", "Ada"), ("order_id", "42")])).unwrap();
+        assert_eq!(filled, "Hi Ada, your order 42 shipped via UPS");
+
+        let filled = fill(
+            template,
+            &values(&[("name", "Ada"), ("order_id", "42"), ("carrier", "")]),
+        )
+        .unwrap();
+        assert_eq!(filled, "Hi Ada, your order 42 shipped via ");
+    }
+
+    #[test]
+    fn test_missing_values_are_an_error() {
+        let template = "{greeting:Hi} {name}, order {order_id} for {name}";
+        let error = fill(template, &HashMap::new()).unwrap_err();
+        assert!(matches!(error, ClipsterError::Validation(_)));
+        assert!(error.to_string().contains("name, order_id"), "{}", error);
+    }
+
+    #[test]
+    fn test_double_braces_are_literal() {
+        let template = "{{name}} is {name}; set = {{ {a}, {b:2} }}";
+        assert_eq!(
+            placeholders(template),
+            vec![field("name", None), field("a", None), field("b", Some("2"))]
+        );
+        let filled = fill(template, &values(&[("name", "x"), ("a", "1")])).unwrap();
+        assert_eq!(filled, "{name} is x; set = { 1, 2 }");
+    }
+
+    #[test]
+    fn test_other_braces_are_kept() {
+        let template = r#"{"id": {id}, "tags": []} { spaced } {1st} {open"#;
+        assert_eq!(placeholders(template), vec![field("id", None)]);
+        let filled = fill(template, &values(&[("id", "7")])).unwrap();
+        assert_eq!(filled, r#"{"id": 7, "tags": []} { spaced } {1st} {open"#);
+    }
+
+    #[test]
+    fn test_first_default_wins() {
+        let template = "{city} {city:Paris} {city:Lyon}";
+        assert_eq!(placeholders(template), vec![field("city", Some("Paris"))]);
+        assert_eq!(
+            fill(template, &HashMap::new()).unwrap(),
+            "Paris Paris Paris"
+        );
+    }
+
+    #[test]
+    fn test_values_are_not_reparsed() {
+        let filled = fill("Dear {name}", &values(&[("name", "{other}")])).unwrap();
+        assert_eq!(filled, "Dear {other}");
+    }
+}
@@ -0,0 +1,72 @@
+//! Helpers shared by unit tests
+
+use crate::clipboard::event_coalescer::Clock;
+use std::cell::Cell;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Unique path under the system temp directory, removed with everything in it
+/// when dropped (also when the test panics)
+/// The directory itself is not created: several tests start from a missing one
+#[derive(Debug)]
+pub struct TempDir(PathBuf);
+
+/// Reserve `clipster-<name>-<uuid>` in the system temp directory
+pub fn temp_dir(name: &str) -> TempDir {
+    TempDir(std::env::temp_dir().join(format!("clipster-{}-{}", name, uuid::Uuid::new_v4())))
+}
+
+impl Deref for TempDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Manually advanced clock
+/// Clones share one time, so a test keeps a handle to the clock it hands to
+/// the code under test
+#[derive(Debug, Clone)]
+pub struct FakeClock(Rc<Cell<Instant>>);
+
+impl FakeClock {
+    pub fn new() -> Self {
+        Self(Rc::new(Cell::new(Instant::now())))
+    }
+
+    pub fn now(&self) -> Instant {
+        self.0.get()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.0.set(self.0.get() + by);
+    }
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        FakeClock::now(self)
+    }
+}
@@ -0,0 +1,51 @@
+//! Keystrokes through CGEvent, and the Accessibility permission they need
+
+use super::Keystroke;
+use crate::error::ClipsterError;
+use crate::permissions;
+use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
+use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+/// kVK_Return
+const KEY_RETURN: CGKeyCode = 0x24;
+/// kVK_Tab
+const KEY_TAB: CGKeyCode = 0x30;
+/// kVK_ANSI_V
+const KEY_V: CGKeyCode = 0x09;
+
+pub(super) fn has_permission(prompt: bool) -> bool {
+    permissions::accessibility_trusted(prompt)
+}
+
+pub(super) fn send(key: &Keystroke) -> Result<(), ClipsterError> {
+    let (keycode, text) = match key {
+        // The key code is ignored when the event carries a string
+        Keystroke::Text(text) => (0, Some(text.as_str())),
+        Keystroke::Return => (KEY_RETURN, None),
+        Keystroke::Tab => (KEY_TAB, None),
+    };
+    // Modifiers still held from the shortcut would turn letters into commands
+    post(keycode, CGEventFlags::empty(), text)
+}
+
+pub(super) fn send_paste() -> Result<(), ClipsterError> {
+    post(KEY_V, CGEventFlags::CGEventFlagCommand, None)
+}
+
+/// Post a key down and key up with exactly `flags` held
+fn post(keycode: CGKeyCode, flags: CGEventFlags, text: Option<&str>) -> Result<(), ClipsterError> {
+    let failed = |what: &str| ClipsterError::Io(format!("Failed to create {}", what));
+
+    for keydown in [true, false] {
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .map_err(|_| failed("event source"))?;
+        let event = CGEvent::new_keyboard_event(source, keycode, keydown)
+            .map_err(|_| failed("keyboard event"))?;
+        event.set_flags(flags);
+        if let Some(text) = text {
+            event.set_string(text);
+        }
+        event.post(CGEventTapLocation::HID);
+    }
+    Ok(())
+}
@@ -0,0 +1,338 @@
+//! Typing an item into the frontmost app as keystrokes
+//!
+//! Some remote-desktop and virtual machine windows ignore clipboard paste.
+//! `type_item` gives focus back to the paste target and sends the item's text
+//! as unicode keyboard events (CGEvent on macOS, SendInput on Windows) at a
+//! set rate: one grapheme cluster per keystroke, line breaks and tabs as the
+//! Return and Tab keys. Only text and links up to `MAX_TYPED_CHARS` are typed.
+//! On macOS posting events needs the Accessibility permission, which is
+//! requested (System Settings prompt) when it is missing. `typing-progress`
+//! events report progress and `cancel_typing` stops between keystrokes.
+//! `send_paste` sends the paste shortcut the same way.
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(target_os = "windows")]
+mod windows;
+
+use crate::error::ClipsterError;
+use crate::models::{ClipboardItem, ContentType};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Longest text that is typed, in characters
+pub const MAX_TYPED_CHARS: usize = 10_000;
+
+/// Typing rate when none is given
+pub const DEFAULT_CHARS_PER_SECOND: u32 = 30;
+
+/// Fastest typing rate; remote sessions drop faster input
+pub const MAX_CHARS_PER_SECOND: u32 = 200;
+
+/// Wait for the paste target to take focus before the first keystroke
+pub const FOCUS_DELAY: Duration = Duration::from_millis(150);
+
+/// Progress events per second while typing
+const PROGRESS_EVENTS_PER_SECOND: u32 = 10;
+
+/// An item is being typed
+static TYPING: AtomicBool = AtomicBool::new(false);
+/// `cancel_typing` was called for the item being typed
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+/// One key press
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Keystroke {
+    /// A grapheme cluster, sent as a unicode string
+    Text(String),
+    Return,
+    Tab,
+}
+
+/// State of a typing run, sent with each `typing-progress` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TypingStatus {
+    Typing,
+    Done,
+    Cancelled,
+    /// The OS refused a keystroke
+    Failed,
+}
+
+/// Payload of the `typing-progress` event
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TypingProgress {
+    pub item_id: String,
+    /// Keystrokes sent so far
+    pub typed: usize,
+    pub total: usize,
+    pub status: TypingStatus,
+}
+
+/// Text of an item that can be typed
+/// Only text and links, and at most `MAX_TYPED_CHARS` characters
+pub fn typeable_text(item: &ClipboardItem) -> Result<&str, ClipsterError> {
+    if !matches!(item.content_type, ContentType::Text | ContentType::Link) {
+        return Err(ClipsterError::Validation(format!(
+            "Only text and links can be typed, not {}",
+            item.content_type.as_str()
+        )));
+    }
+    let text = item.content_text.as_deref().unwrap_or("");
+    let chars = text.chars().count();
+    if chars > MAX_TYPED_CHARS {
+        return Err(ClipsterError::Validation(format!(
+            "Text is too long to type ({} characters, at most {})",
+            chars, MAX_TYPED_CHARS
+        )));
+    }
+    Ok(text)
+}
+
+/// Keystrokes typing `text`; a CRLF is a single Return
+pub fn keystrokes(text: &str) -> Vec<Keystroke> {
+    text.graphemes(true)
+        .map(|grapheme| match grapheme {
+            "\r\n" | "\n" | "\r" => Keystroke::Return,
+            "\t" => Keystroke::Tab,
+            _ => Keystroke::Text(grapheme.to_string()),
+        })
+        .collect()
+}
+
+/// Pause between keystrokes for a typing rate (clamped to 1..=`MAX_CHARS_PER_SECOND`)
+pub fn keystroke_interval(chars_per_second: Option<u32>) -> Duration {
+    let rate = chars_per_second
+        .unwrap_or(DEFAULT_CHARS_PER_SECOND)
+        .clamp(1, MAX_CHARS_PER_SECOND);
+    Duration::from_secs(1) / rate
+}
+
+/// Keystrokes between two progress events at `interval`
+pub fn progress_step(interval: Duration) -> usize {
+    let per_event = Duration::from_secs(1) / PROGRESS_EVENTS_PER_SECOND;
+    (per_event.as_micros() / interval.as_micros().max(1)).max(1) as usize
+}
+
+/// Send `keys` one by one, `interval` apart, until done or `cancel` is set
+/// `progress` gets the number of keystrokes sent after each one
+pub fn run(
+    keys: &[Keystroke],
+    interval: Duration,
+    cancel: &AtomicBool,
+    mut send: impl FnMut(&Keystroke) -> Result<(), ClipsterError>,
+    mut sleep: impl FnMut(Duration),
+    mut progress: impl FnMut(usize),
+) -> TypingStatus {
+    for (i, key) in keys.iter().enumerate() {
+        if cancel.load(Ordering::SeqCst) {
+            return TypingStatus::Cancelled;
+        }
+        if i > 0 {
+            sleep(interval);
+        }
+        if let Err(e) = send(key) {
+            eprintln!("[type_text] {}", e);
+            return TypingStatus::Failed;
+        }
+        progress(i + 1);
+    }
+    TypingStatus::Done
+}
+
+/// Claim typing for one item; false while another one is being typed
+pub fn begin() -> bool {
+    let claimed = TYPING
+        .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+        .is_ok();
+    if claimed {
+        CANCEL.store(false, Ordering::SeqCst);
+    }
+    claimed
+}
+
+/// Release the claim taken by `begin`
+pub fn finish() {
+    TYPING.store(false, Ordering::SeqCst);
+}
+
+/// Flag `run` checks between keystrokes
+pub fn cancel_flag() -> &'static AtomicBool {
+    &CANCEL
+}
+
+/// Stop the item being typed; false if nothing is being typed
+pub fn cancel() -> bool {
+    let typing = TYPING.load(Ordering::SeqCst);
+    if typing {
+        CANCEL.store(true, Ordering::SeqCst);
+    }
+    typing
+}
+
+/// Whether keystrokes can be sent to other apps
+/// On macOS, `prompt` opens the Accessibility permission prompt when missing
+pub fn has_permission(prompt: bool) -> bool {
+    #[cfg(target_os = "macos")]
+    return macos::has_permission(prompt);
+
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = prompt;
+        true
+    }
+}
+
+/// Send one keystroke to the app with keyboard focus
+pub fn send(key: &Keystroke) -> Result<(), ClipsterError> {
+    #[cfg(target_os = "macos")]
+    return macos::send(key);
+
+    #[cfg(target_os = "windows")]
+    return windows::send(key);
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        let _ = key;
+        Err(ClipsterError::Validation(
+            "Typing is not supported on this platform".to_string(),
+        ))
+    }
+}
+
+/// Send the paste shortcut (Cmd+V on macOS, Ctrl+V on Windows) to the app
+/// with keyboard focus
+pub fn send_paste() -> Result<(), ClipsterError> {
+    #[cfg(target_os = "macos")]
+    return macos::send_paste();
+
+    #[cfg(target_os = "windows")]
+    return windows::send_paste();
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    Err(ClipsterError::Validation(
+        "Pasting is not supported on this platform".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Keystroke {
+        Keystroke::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_keystrokes() {
+        assert_eq!(
+            keystrokes("a\tb\r\nc\nd"),
+            vec![
+                text("a"),
+                Keystroke::Tab,
+                text("b"),
+                Keystroke::Return,
+                text("c"),
+                Keystroke::Return,
+                text("d"),
+            ]
+        );
+        // Combining accents and emoji sequences are one keystroke each
+        assert_eq!(keystrokes("e\u{301}👨‍👩‍👧"), vec![text("e\u{301}"), text("👨‍👩‍👧")]);
+        assert!(keystrokes("").is_empty());
+    }
+
+    #[test]
+    fn test_typeable_text() {
+        let item = ClipboardItem::new_text("hello".to_string(), None, None);
+        assert_eq!(typeable_text(&item).unwrap(), "hello");
+        let link = ClipboardItem::new_link("https://example.com".to_string(), None, None);
+        assert!(typeable_text(&link).is_ok());
+
+        let files = ClipboardItem::new_files(vec!["/tmp/a.txt".to_string()], None, None);
+        assert_eq!(typeable_text(&files).unwrap_err().code(), "validation");
+
+        // The cap is in characters, not bytes
+        let at_cap = ClipboardItem::new_text("é".repeat(MAX_TYPED_CHARS), None, None);
+        assert!(typeable_text(&at_cap).is_ok());
+        let over = ClipboardItem::new_text("a".repeat(MAX_TYPED_CHARS + 1), None, None);
+        assert_eq!(typeable_text(&over).unwrap_err().code(), "validation");
+    }
+
+    #[test]
+    fn test_keystroke_interval() {
+        assert_eq!(keystroke_interval(None), Duration::from_secs(1) / 30);
+        assert_eq!(keystroke_interval(Some(10)), Duration::from_millis(100));
+        assert_eq!(keystroke_interval(Some(0)), Duration::from_secs(1));
+        assert_eq!(keystroke_interval(Some(100_000)), Duration::from_millis(5));
+
+        assert_eq!(progress_step(Duration::from_millis(100)), 1);
+        assert_eq!(progress_step(Duration::from_secs(1)), 1);
+        assert_eq!(progress_step(Duration::from_millis(5)), 20);
+    }
+
+    #[test]
+    fn test_run_sends_every_key_with_pauses() {
+        let keys = keystrokes("ab\nc");
+        let cancel = AtomicBool::new(false);
+        let (mut sent, mut sleeps, mut typed) = (Vec::new(), Vec::new(), Vec::new());
+
+        let status = run(
+            &keys,
+            Duration::from_millis(50),
+            &cancel,
+            |key| {
+                sent.push(key.clone());
+                Ok(())
+            },
+            |pause| sleeps.push(pause),
+            |count| typed.push(count),
+        );
+        assert_eq!(status, TypingStatus::Done);
+        assert_eq!(sent, keys);
+        assert_eq!(sleeps, vec![Duration::from_millis(50); 3]);
+        assert_eq!(typed, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_run_stops_on_cancel_and_failure() {
+        let keys = keystrokes("abcdef");
+        let cancel = AtomicBool::new(false);
+        let mut sent = 0;
+        let status = run(
+            &keys,
+            Duration::ZERO,
+            &cancel,
+            |_| {
+                sent += 1;
+                Ok(())
+            },
+            |_| {},
+            |count| {
+                if count == 2 {
+                    cancel.store(true, Ordering::SeqCst);
+                }
+            },
+        );
+        assert_eq!(status, TypingStatus::Cancelled);
+        assert_eq!(sent, 2);
+
+        let cancel = AtomicBool::new(false);
+        let status = run(
+            &keys,
+            Duration::ZERO,
+            &cancel,
+            |key| match key {
+                Keystroke::Text(s) if s == "c" => Err(ClipsterError::Io("refused".to_string())),
+                _ => Ok(()),
+            },
+            |_| {},
+            |_| {},
+        );
+        assert_eq!(status, TypingStatus::Failed);
+    }
+}
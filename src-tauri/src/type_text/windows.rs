@@ -0,0 +1,58 @@
+//! Keystrokes through SendInput unicode events
+
+use super::Keystroke;
+use crate::error::ClipsterError;
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+    KEYEVENTF_UNICODE, VIRTUAL_KEY, VK_CONTROL, VK_RETURN, VK_TAB, VK_V,
+};
+
+pub(super) fn send(key: &Keystroke) -> Result<(), ClipsterError> {
+    let inputs: Vec<INPUT> = match key {
+        // One press per UTF-16 unit; characters outside the BMP take two
+        Keystroke::Text(text) => text
+            .encode_utf16()
+            .flat_map(|unit| press(VIRTUAL_KEY(0), unit, KEYEVENTF_UNICODE))
+            .collect(),
+        Keystroke::Return => press(VK_RETURN, 0, KEYBD_EVENT_FLAGS(0)).to_vec(),
+        Keystroke::Tab => press(VK_TAB, 0, KEYBD_EVENT_FLAGS(0)).to_vec(),
+    };
+    send_inputs(&inputs)
+}
+
+pub(super) fn send_paste() -> Result<(), ClipsterError> {
+    let [ctrl_down, ctrl_up] = press(VK_CONTROL, 0, KEYBD_EVENT_FLAGS(0));
+    let [v_down, v_up] = press(VK_V, 0, KEYBD_EVENT_FLAGS(0));
+    send_inputs(&[ctrl_down, v_down, v_up, ctrl_up])
+}
+
+fn send_inputs(inputs: &[INPUT]) -> Result<(), ClipsterError> {
+    // Blocked when the focused window runs elevated (UIPI)
+    let sent = unsafe { SendInput(inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        return Err(ClipsterError::Io(format!(
+            "SendInput sent {} of {} events: {}",
+            sent,
+            inputs.len(),
+            windows::core::Error::from_win32()
+        )));
+    }
+    Ok(())
+}
+
+/// Key down and key up events
+fn press(key: VIRTUAL_KEY, scan: u16, flags: KEYBD_EVENT_FLAGS) -> [INPUT; 2] {
+    let input = |flags| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: key,
+                wScan: scan,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    [input(flags), input(flags | KEYEVENTF_KEYUP)]
+}
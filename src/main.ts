@@ -1,9 +1,12 @@
 import { createApp } from "vue";
 import App from "./App.vue";
+import PinboardPalette from "./components/PinboardPalette.vue";
 import './assets/tailwind.css';
 import { createPinia } from 'pinia';
 
+// The pinboard palette window loads the same page with ?palette=<pinboard id>
+const isPalette = new URLSearchParams(window.location.search).has('palette');
 
-const app = createApp(App);
+const app = createApp(isPalette ? PinboardPalette : App);
 app.use(createPinia());
 app.mount('#app');
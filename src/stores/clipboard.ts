@@ -1,12 +1,40 @@
 import { defineStore } from 'pinia';
-import { invoke } from '@tauri-apps/api/core';
+import { convertFileSrc, invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
-import type {
-  ClipboardItem,
-  ClipboardChangedPayload,
-  ThumbnailUpdatedPayload,
+import {
+  errorMessage,
+  type AppGroup,
+  type ArchiveReport,
+  type BulkItemResult,
+  type BulkOp,
+  type ClipboardItem,
+  type ClipboardChangedPayload,
+  type ClipboardChangedBatchPayload,
+  type ThumbnailUpdatedPayload,
+  type ItemUpdatedPayload,
+  type ItemDeletedPayload,
+  type ItemsUpdatedPayload,
+  type SearchGroup,
+  type ImageInfo,
+  type PasteTransform,
+  type Placeholder,
+  type DuplicateGroup,
+  type DuplicateKind,
+  type Guarded,
+  type ReclassifyReport,
+  type ReindexComponent,
+  type ReindexReport,
 } from '@/types';
 
+// Thumbnails requested by cards scrolled into view, loaded in one get_thumbnails call
+const pendingThumbnailIds = new Set<string>();
+let thumbnailFlush: ReturnType<typeof setTimeout> | null = null;
+
+// App icons extracted before their item's (coalesced) clipboard-changed event arrived
+const earlyAppIcons = new Map<string, string>();
+// Text merged into an item before its clipboard-changed event arrived
+const earlyTexts = new Map<string, { content_text: string; preview: string }>();
+
 interface ClipboardState {
   items: ClipboardItem[];
   loading: boolean;
@@ -101,7 +129,7 @@ export const useClipboardStore = defineStore('clipboard', {
         this.items = items;
         this.totalCount = await invoke<number>('get_clipboard_count');
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to fetch clipboard history:', e);
       } finally {
         this.loading = false;
@@ -110,8 +138,9 @@ export const useClipboardStore = defineStore('clipboard', {
 
     /**
      * Search clipboard items (server-side search)
+     * `language` (ISO 639-3 code) keeps only text detected in that language
      */
-    async search(query: string, limit = 50): Promise<void> {
+    async search(query: string, limit = 50, language?: string): Promise<void> {
       this.searchQuery = query;
 
       if (!query.trim()) {
@@ -126,10 +155,11 @@ export const useClipboardStore = defineStore('clipboard', {
         const items = await invoke<ClipboardItem[]>('search_clipboard', {
           query,
           limit,
+          language,
         });
         this.items = items;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to search clipboard:', e);
       } finally {
         this.loading = false;
@@ -148,7 +178,7 @@ export const useClipboardStore = defineStore('clipboard', {
         }
         return success;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to delete clipboard item:', e);
         return false;
       }
@@ -156,18 +186,138 @@ export const useClipboardStore = defineStore('clipboard', {
 
     /**
      * Copy an item back to system clipboard
+     * Text goes through `transform`, or else the default transform of the
+     * item's pinboard unless `skipTransform` is set
      */
-    async copyToClipboard(id: string): Promise<boolean> {
+    async copyToClipboard(
+      id: string,
+      transform?: PasteTransform,
+      skipTransform = false,
+    ): Promise<boolean> {
       try {
-        await invoke('copy_to_clipboard', { id });
+        await invoke('copy_to_clipboard', {
+          id,
+          transform: transform ?? null,
+          skipTransform,
+        });
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to copy to clipboard:', e);
         return false;
       }
     },
 
+    /**
+     * Open a copied file with its default app, or a copied folder in the file manager
+     */
+    async openPath(path: string): Promise<boolean> {
+      try {
+        await invoke('open_path', { path });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to open path:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Show a copied file or folder selected in its parent folder
+     */
+    async revealPath(path: string): Promise<boolean> {
+      try {
+        await invoke('reveal_path', { path });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to reveal path:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Get dimensions, color type, file size and EXIF of an image item
+     */
+    async getItemImageInfo(itemId: string): Promise<ImageInfo | null> {
+      try {
+        return await invoke<ImageInfo>('get_item_image_info', { itemId });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get image info:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Get a playable URL for an audio item's file (null if it is gone)
+     */
+    async getItemAudioUrl(itemId: string): Promise<string | null> {
+      try {
+        const path = await invoke<string>('get_item_audio_stream', { itemId });
+        return convertFileSrc(path);
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get audio stream:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Get the placeholders of a snippet (empty when it has none)
+     */
+    async getItemPlaceholders(itemId: string): Promise<Placeholder[]> {
+      try {
+        return await invoke<Placeholder[]>('get_item_placeholders', { itemId });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get placeholders:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Copy a snippet with its placeholders filled in; fields left out use
+     * their default. The stored item is not changed
+     */
+    async copyItemWithValues(itemId: string, values: Record<string, string>): Promise<boolean> {
+      try {
+        await invoke('copy_item_with_values', { itemId, values });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to copy filled snippet:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Type a text item into the window the panel was summoned from, as keystrokes.
+     * Resolves once typing has started; typing-progress events follow
+     */
+    async typeItem(itemId: string, charsPerSecond?: number): Promise<boolean> {
+      try {
+        await invoke('type_item', { itemId, charsPerSecond: charsPerSecond ?? null });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to type item:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Stop the item being typed (false if nothing is being typed)
+     */
+    async cancelTyping(): Promise<boolean> {
+      try {
+        return await invoke<boolean>('cancel_typing');
+      } catch (e) {
+        console.error('Failed to cancel typing:', e);
+        return false;
+      }
+    },
+
     /**
      * Toggle favorite status
      */
@@ -181,27 +331,223 @@ export const useClipboardStore = defineStore('clipboard', {
         }
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to toggle favorite:', e);
         return false;
       }
     },
 
+    /**
+     * Delete an item automatically after keepForMs, or keep it with null
+     * The item-deleted event removes it from the list once it expires
+     */
+    async setItemExpiry(id: string, keepForMs: number | null): Promise<boolean> {
+      const expiresAt = keepForMs === null ? null : new Date(Date.now() + keepForMs).toISOString();
+      try {
+        await invoke<boolean>('set_item_expiry', { itemId: id, expiresAt });
+        const item = this.items.find((item) => item.id === id);
+        if (item) {
+          item.expires_at = expiresAt ?? undefined;
+        }
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to set item expiry:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Apply one operation to all selected items in a single call
+     * Local state is updated by the items-updated event
+     */
+    async bulkUpdateItems(itemIds: string[], op: BulkOp): Promise<BulkItemResult[]> {
+      try {
+        return await invoke<BulkItemResult[]>('bulk_update_items', { itemIds, op });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to update items:', e);
+        return [];
+      }
+    },
+
     /**
      * Clear all clipboard history (except favorites and pinned)
+     * Without confirmToken nothing is deleted unless confirmation is turned off:
+     * the result holds the token to call again with and what would be deleted
      */
-    async clearHistory(): Promise<number> {
+    async clearHistory(confirmToken?: string): Promise<Guarded<number> | null> {
       try {
-        const deletedCount = await invoke<number>('clear_clipboard_history');
-        await this.fetchHistory();
-        return deletedCount;
+        const outcome = await invoke<Guarded<number>>('clear_clipboard_history', {
+          confirmToken,
+        });
+        if (outcome.status === 'done') {
+          await this.fetchHistory();
+        }
+        return outcome;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to clear clipboard history:', e);
+        return null;
+      }
+    },
+
+    /**
+     * History grouped by source app for the Apps view
+     */
+    async fetchHistoryByApp(limitPerApp = 10): Promise<AppGroup[]> {
+      try {
+        return await invoke<AppGroup[]>('get_history_by_app', { limitPerApp });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to fetch history by app:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Image items with a dominant color near `hex` (`#rrggbb`)
+     * `tolerance` is the largest RGB distance that still matches (60 by default)
+     */
+    async getItemsByColor(hex: string, tolerance?: number): Promise<ClipboardItem[]> {
+      try {
+        return await invoke<ClipboardItem[]>('get_items_by_color', {
+          hex,
+          tolerance,
+        });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to find items by color:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Move history older than `days` (favorites and pinboard items excepted)
+     * into the archive; the archived items leave the list via items-updated
+     */
+    async archiveItemsOlderThan(days: number): Promise<ArchiveReport | null> {
+      try {
+        return await invoke<ArchiveReport>('archive_items_older_than', { days });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to archive items:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Search the archive, which is only opened for this
+     */
+    async searchArchive(query: string, limit = 50): Promise<ClipboardItem[]> {
+      try {
+        return await invoke<ClipboardItem[]>('search_archive', { query, limit });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to search archive:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Move an archived item back into history
+     */
+    async restoreArchivedItem(id: string): Promise<ClipboardItem | null> {
+      try {
+        return await invoke<ClipboardItem>('restore_archived_item', { id });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to restore archived item:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Search grouped by pinboard ("History (12) / Snippets (3)")
+     */
+    async searchGrouped(
+      query: string,
+      perGroupLimit = 5,
+      includeArchived = false,
+      language?: string
+    ): Promise<SearchGroup[]> {
+      try {
+        return await invoke<SearchGroup[]>('search_grouped', {
+          query,
+          perGroupLimit,
+          includeArchived,
+          language,
+        });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to search by pinboard:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Find groups of duplicate items (identical content or similar images)
+     */
+    async findDuplicates(kind: DuplicateKind): Promise<DuplicateGroup[]> {
+      try {
+        return await invoke<DuplicateGroup[]>('find_duplicates', { kind });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to find duplicates:', e);
+        return [];
+      }
+    },
+
+    /**
+     * Merge a group of duplicates into the kept item, deleting the others
+     */
+    async mergeDuplicateGroup(keepId: string, removeIds: string[]): Promise<number> {
+      try {
+        const removed = await invoke<number>('merge_duplicate_group', { keepId, removeIds });
+        await this.refreshItems();
+        return removed;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to merge duplicates:', e);
         return 0;
       }
     },
 
+    /**
+     * Re-run content type detection on all items (item-updated events
+     * update the loaded ones); with dryRun, only report what would change
+     */
+    async reclassifyItems(dryRun: boolean): Promise<ReclassifyReport | null> {
+      try {
+        return await invoke<ReclassifyReport>('reclassify_items', { dryRun });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to reclassify items:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Rebuild derived item state (all components when none are given);
+     * progress arrives as reindex-progress events
+     */
+    async reindexAll(components: ReindexComponent[] = []): Promise<ReindexReport | null> {
+      try {
+        const report = await invoke<ReindexReport>('reindex_all', { components });
+        await this.refreshItems();
+        return report;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to reindex items:', e);
+        return null;
+      }
+    },
+
+    /** Stop the running reindex after the current batch */
+    async cancelReindex(): Promise<void> {
+      await invoke('cancel_reindex');
+    },
+
     /**
      * Add a new item to the beginning of the list
      * Called when clipboard-changed event is received
@@ -210,6 +556,17 @@ export const useClipboardStore = defineStore('clipboard', {
      */
     addItem(item: ClipboardItem, replacedItemId?: string): void {
       console.log('[addItem] Called with:', { itemId: item.id, replacedItemId });
+      const earlyIcon = earlyAppIcons.get(item.id);
+      if (earlyIcon) {
+        item.source_app_icon = earlyIcon;
+        earlyAppIcons.delete(item.id);
+      }
+      const earlyText = earlyTexts.get(item.id);
+      if (earlyText) {
+        item.content_text = earlyText.content_text;
+        item.preview = earlyText.preview;
+        earlyTexts.delete(item.id);
+      }
       console.log('[addItem] Current items:', this.items.map((i) => i.id));
 
       // If this item replaced an existing one (move to top), remove the old one
@@ -264,19 +621,83 @@ export const useClipboardStore = defineStore('clipboard', {
         },
       );
 
+      // Bursts of copies (e.g. scripted) arrive as one event
+      const unlistenBatch = await listen<ClipboardChangedBatchPayload>(
+        'clipboard-changed-batch',
+        (event) => {
+          console.log('[ClipboardStore] Received batch of', event.payload.items.length, 'items');
+          for (const { item, replaced_item_id } of event.payload.items) {
+            this.addItem(item, replaced_item_id);
+          }
+        },
+      );
+
       const unlistenThumbnail = await listen<ThumbnailUpdatedPayload>(
         'clipboard-item-thumbnail-updated',
         (event) => {
           const item = this.items.find((i) => i.id === event.payload.id);
           if (item) {
             item.thumbnail_base64 = event.payload.thumbnail_base64;
+            item.thumbnail_is_favicon = event.payload.thumbnail_is_favicon;
           }
         },
       );
 
+      const unlistenItemUpdated = await listen<ItemUpdatedPayload>('item-updated', (event) => {
+        const {
+          id,
+          source_app_icon,
+          content_type,
+          media_metadata,
+          language,
+          palette,
+          content_text,
+          preview,
+        } = event.payload;
+        const item = this.items.find((i) => i.id === id);
+        if (item) {
+          if (source_app_icon) item.source_app_icon = source_app_icon;
+          if (content_type) item.content_type = content_type;
+          if (media_metadata) item.media_metadata = media_metadata;
+          if (language) item.language = language;
+          if (palette) item.palette = palette;
+          if (content_text !== undefined) item.content_text = content_text;
+          if (preview !== undefined) item.preview = preview;
+        } else {
+          if (source_app_icon) earlyAppIcons.set(id, source_app_icon);
+          if (content_text !== undefined && preview !== undefined) {
+            earlyTexts.set(id, { content_text, preview });
+          }
+        }
+      });
+
+      const unlistenItemDeleted = await listen<ItemDeletedPayload>('item-deleted', (event) => {
+        const before = this.items.length;
+        this.items = this.items.filter((item) => item.id !== event.payload.id);
+        this.totalCount = Math.max(0, this.totalCount - (before - this.items.length));
+      });
+
+      const unlistenItemsUpdated = await listen<ItemsUpdatedPayload>('items-updated', (event) => {
+        const { updated, deleted } = event.payload;
+        for (const item of updated) {
+          const index = this.items.findIndex((i) => i.id === item.id);
+          if (index !== -1) this.items[index] = item;
+        }
+        if (deleted.length > 0) {
+          const removed = new Set(deleted);
+          const before = this.items.length;
+          this.items = this.items.filter((item) => !removed.has(item.id));
+          this.totalCount = Math.max(0, this.totalCount - (before - this.items.length));
+        }
+      });
+
       return () => {
         unlistenChanged();
+        unlistenBatch();
         unlistenThumbnail();
+        unlistenItemUpdated();
+        unlistenItemDeleted();
+        unlistenItemsUpdated();
       };
     },
 
@@ -316,7 +737,7 @@ export const useClipboardStore = defineStore('clipboard', {
         this.items = items;
         this.totalCount = items.length;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to fetch pinboard items:', e);
       } finally {
         this.loading = false;
@@ -334,6 +755,33 @@ export const useClipboardStore = defineStore('clipboard', {
       }
     },
 
+    /**
+     * Queue a thumbnail load for an item that scrolled into view
+     * List fetches omit thumbnails; requests made in the same tick are batched
+     */
+    requestThumbnail(id: string): void {
+      pendingThumbnailIds.add(id);
+      if (thumbnailFlush) return;
+
+      thumbnailFlush = setTimeout(async () => {
+        const itemIds = [...pendingThumbnailIds];
+        pendingThumbnailIds.clear();
+        thumbnailFlush = null;
+
+        try {
+          const thumbnails = await invoke<Record<string, string>>('get_thumbnails', { itemIds });
+          for (const item of this.items) {
+            const thumbnail = thumbnails[item.id];
+            if (thumbnail) {
+              item.thumbnail_base64 = thumbnail;
+            }
+          }
+        } catch (e) {
+          console.error('Failed to load thumbnails:', e);
+        }
+      }, 0);
+    },
+
     /**
      * Get full image data as base64
      */
@@ -342,12 +790,26 @@ export const useClipboardStore = defineStore('clipboard', {
         const base64 = await invoke<string>('get_image_data', { id });
         return base64;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to get image data:', e);
         return null;
       }
     },
 
+    /**
+     * Render a shareable PNG card of an item, as base64
+     * With `redact` the content is masked (always for passcode-protected pinboards)
+     */
+    async renderItemCard(itemId: string, width?: number, redact = false): Promise<string | null> {
+      try {
+        return await invoke<string>('render_item_card', { itemId, width, redact });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to render item card:', e);
+        return null;
+      }
+    },
+
     /**
      * Update item's pinboard assignment locally
      * Called after drag-and-drop operations
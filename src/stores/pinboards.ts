@@ -1,6 +1,12 @@
 import { defineStore } from 'pinia';
 import { invoke } from '@tauri-apps/api/core';
-import type { Pinboard } from '@/types';
+import {
+  errorMessage,
+  type ClipboardItem,
+  type Guarded,
+  type PasteTransform,
+  type Pinboard,
+} from '@/types';
 import { useClipboardStore } from './clipboard';
 
 interface DropZone {
@@ -36,10 +42,17 @@ export const usePinboardStore = defineStore('pinboards', {
 
   getters: {
     /**
-     * Get sorted pinboards by position
+     * Get sorted pinboards by position, each root pinboard followed by its children
+     * Children of a pinboard that isn't loaded (archived) are listed as roots
      */
     sortedPinboards(state): Pinboard[] {
-      return [...state.pinboards].sort((a, b) => a.position - b.position);
+      const byPosition = [...state.pinboards].sort((a, b) => a.position - b.position);
+      const ids = new Set(byPosition.map((p) => p.id));
+      const roots = byPosition.filter((p) => !p.parent_id || !ids.has(p.parent_id));
+      return roots.flatMap((root) => [
+        root,
+        ...byPosition.filter((p) => p.parent_id === root.id),
+      ]);
     },
 
     /**
@@ -70,7 +83,7 @@ export const usePinboardStore = defineStore('pinboards', {
         const pinboards = await invoke<Pinboard[]>('get_pinboards');
         this.pinboards = pinboards;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to fetch pinboards:', e);
       } finally {
         this.loading = false;
@@ -80,40 +93,68 @@ export const usePinboardStore = defineStore('pinboards', {
     /**
      * Create a new pinboard
      */
-    async createPinboard(name: string, icon?: string): Promise<Pinboard | null> {
+    async createPinboard(
+      name: string,
+      icon?: string,
+      color?: string,
+      description?: string,
+      defaultTransform?: PasteTransform,
+    ): Promise<Pinboard | null> {
       try {
         const pinboard = await invoke<Pinboard>('create_pinboard', {
           name,
           icon: icon || null,
+          color: color || null,
+          description: description || null,
+          defaultTransform: defaultTransform ?? null,
         });
         this.pinboards.push(pinboard);
         return pinboard;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to create pinboard:', e);
         return null;
       }
     },
 
     /**
-     * Update a pinboard's name and/or icon
+     * Update a pinboard's name, icon, color, description and default transform
+     * Color, description and transform keep their current values when not
+     * given; pass null as the transform to remove it
      */
-    async updatePinboard(id: string, name: string, icon?: string): Promise<boolean> {
+    async updatePinboard(
+      id: string,
+      name: string,
+      icon?: string,
+      color?: string,
+      description?: string,
+      defaultTransform?: PasteTransform | null,
+    ): Promise<boolean> {
+      const pinboard = this.pinboards.find((p) => p.id === id);
+      const newColor = color ?? pinboard?.color;
+      const newDescription = description ?? pinboard?.description;
+      const newTransform =
+        defaultTransform === undefined ? pinboard?.default_transform : defaultTransform;
       try {
         await invoke<boolean>('update_pinboard', {
           id,
           name,
           icon: icon || null,
+          color: newColor || null,
+          description: newDescription || null,
+          defaultTransform: newTransform ?? null,
         });
         // Update local state
-        const pinboard = this.pinboards.find((p) => p.id === id);
         if (pinboard) {
           pinboard.name = name;
           pinboard.icon = icon || null;
+          if (newColor) pinboard.color = newColor;
+          pinboard.description = newDescription || undefined;
+          pinboard.default_transform = newTransform ?? undefined;
         }
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to update pinboard:', e);
         return false;
       }
@@ -121,29 +162,63 @@ export const usePinboardStore = defineStore('pinboards', {
 
     /**
      * Delete a pinboard
+     * Nested pinboards are deleted too with deleteChildren, otherwise moved to the root
+     * Needs a confirmToken like clipboardStore.clearHistory
      */
-    async deletePinboard(id: string): Promise<boolean> {
+    async deletePinboard(
+      id: string,
+      deleteChildren = false,
+      confirmToken?: string
+    ): Promise<Guarded<boolean> | null> {
+      const hasChildren = this.pinboards.some((p) => p.parent_id === id);
       try {
-        await invoke<boolean>('delete_pinboard', { id });
-        this.pinboards = this.pinboards.filter((p) => p.id !== id);
+        const outcome = await invoke<Guarded<boolean>>('delete_pinboard', {
+          id,
+          deleteChildren,
+          confirmToken,
+        });
+        if (outcome.status === 'confirmation_required') {
+          return outcome;
+        }
+        if (hasChildren) {
+          // Children were deleted or moved, reload the new positions
+          await this.fetchPinboards();
+        } else {
+          this.pinboards = this.pinboards.filter((p) => p.id !== id);
+        }
         // If deleted pinboard was active, switch to history
-        if (this.activePinboardId === id) {
+        if (this.activePinboardId && !this.pinboards.some((p) => p.id === this.activePinboardId)) {
           this.activePinboardId = null;
         }
-        return true;
+        return outcome;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to delete pinboard:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Nest a pinboard under another, or move it back to the root with null
+     */
+    async setPinboardParent(id: string, parentId: string | null): Promise<boolean> {
+      try {
+        await invoke('set_pinboard_parent', { id, parentId });
+        await this.fetchPinboards();
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to move pinboard:', e);
         return false;
       }
     },
 
     /**
-     * Reorder pinboards
+     * Reorder the pinboards nested under parentId (null for the root level)
      */
-    async reorderPinboards(pinboardIds: string[]): Promise<boolean> {
+    async reorderPinboards(pinboardIds: string[], parentId: string | null = null): Promise<boolean> {
       try {
-        await invoke('reorder_pinboards', { pinboardIds });
+        await invoke('reorder_pinboards', { parentId, pinboardIds });
         // Update local positions
         pinboardIds.forEach((id, index) => {
           const pinboard = this.pinboards.find((p) => p.id === id);
@@ -153,7 +228,7 @@ export const usePinboardStore = defineStore('pinboards', {
         });
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to reorder pinboards:', e);
         return false;
       }
@@ -187,12 +262,39 @@ export const usePinboardStore = defineStore('pinboards', {
 
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to add item to pinboard:', e);
         return false;
       }
     },
 
+    /**
+     * Add a file from disk to a pinboard (images are imported with a thumbnail)
+     * @param keepMtime Date the item with the file's modification time
+     */
+    async addFileToPinboard(
+      path: string,
+      pinboardId: string,
+      keepMtime = false
+    ): Promise<ClipboardItem | null> {
+      try {
+        const item = await invoke<ClipboardItem>('add_file_to_pinboard', {
+          path,
+          pinboardId,
+          keepMtime,
+        });
+        // Show it right away when viewing the target pinboard
+        if (this.activePinboardId === pinboardId) {
+          useClipboardStore().addItem(item);
+        }
+        return item;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to add file to pinboard:', e);
+        return null;
+      }
+    },
+
     /**
      * Remove item from its pinboard
      */
@@ -208,12 +310,25 @@ export const usePinboardStore = defineStore('pinboards', {
         }
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to remove item from pinboard:', e);
         return false;
       }
     },
 
+    /**
+     * Most copied-back items of a pinboard, or of all unlocked items when pinboardId is null
+     */
+    async fetchTopUsed(pinboardId: string | null, limit = 10): Promise<ClipboardItem[]> {
+      try {
+        return await invoke<ClipboardItem[]>('get_top_used', { pinboardId, limit });
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to fetch most used items:', e);
+        return [];
+      }
+    },
+
     /**
      * Clear error state
      */
@@ -1,7 +1,17 @@
 import { defineStore } from 'pinia';
 import { invoke } from '@tauri-apps/api/core';
+import {
+  errorMessage,
+  type PanelPlacementSettings,
+  type PasteActivation,
+  type PasteTarget,
+  type Placement,
+} from '@/types';
 
 export type Theme = 'light' | 'dark' | 'system';
+export type NormalizeText = 'off' | 'line_endings' | 'full';
+export type ThumbnailFormat = 'png' | 'jpeg' | 'webp';
+export type DedupeScope = 'global' | 'per_app' | 'off';
 
 export interface AppSettings {
   shortcut: string;
@@ -9,6 +19,49 @@ export interface AppSettings {
   start_hidden: boolean;
   theme: Theme;
   show_menu_bar_icon: boolean;
+  mirror_favorites_to_pinboard: boolean;
+  strip_image_metadata: boolean;
+  /** Save images with slower, stronger PNG compression (about half the size) */
+  compact_png_files: boolean;
+  quick_cycle_shortcut: string;
+  normalize_text: NormalizeText;
+  large_text_threshold_bytes: number;
+  compress_large_text: boolean;
+  spotlight_indexing: boolean;
+  /** 'off', 'daily_at(HH:MM)' or 'on_quit'; set through set_auto_clear_schedule */
+  auto_clear_schedule: string;
+  /** Below this much free disk space (MB), images are not saved */
+  min_free_space_mb: number;
+  /** Store the focused window's title with each capture (privacy: off by default) */
+  capture_window_titles: boolean;
+  /** Encoding of image thumbnails; JPEG/WebP are smaller for photos */
+  thumbnail_format: ThumbnailFormat;
+  /** Time a single Quick Look thumbnail run may take (ms); PDF/PPTX/MOV retry once at double */
+  quicklook_timeout_ms: number;
+  /** Five most recent text items in the Windows jump list / macOS Dock menu (stored on disk) */
+  recent_items_os_menu: boolean;
+  /** Also capture Cmd+F search text from the macOS find pasteboard */
+  capture_find_pasteboard: boolean;
+  /** Clipboard capture is paused (kept across restarts); set through set_capture_paused */
+  capture_paused: boolean;
+  /** Keep copied-back items out of the Win+V history (locked pinboards always are) */
+  exclude_from_os_history: boolean;
+  /** Which earlier copies a new copy replaces: any, only from the same app, or none */
+  dedupe_scope: DedupeScope;
+  /** Captured images are downscaled to fit within this many pixels per side (0 = keep original) */
+  max_image_dimension: number;
+  /** Images larger than this many MB on the clipboard are not captured (0 = no limit) */
+  max_image_capture_mb: number;
+  /** Write per-capture tracing to ~/.clipster/logs (previews only, never full content) */
+  debug_capture_logging: boolean;
+  /** Clearing history and deleting pinboards ask for a confirmation token first */
+  require_destructive_confirmation: boolean;
+  /** Join text copied from one app in quick succession (terminal selections) into one item */
+  merge_rapid_text_captures: boolean;
+  /** Longest gap between two copies that are joined (ms) */
+  merge_window_ms: number;
+  /** Back up the database into the profile's backups directory this often (hours, 0 = never) */
+  backup_interval_hours: number;
 }
 
 interface SettingsState {
@@ -24,6 +77,31 @@ const defaultSettings: AppSettings = {
   start_hidden: false,
   theme: 'dark',
   show_menu_bar_icon: true,
+  mirror_favorites_to_pinboard: false,
+  strip_image_metadata: false,
+  compact_png_files: false,
+  quick_cycle_shortcut: 'Ctrl+Alt+V',
+  normalize_text: 'off',
+  large_text_threshold_bytes: 262144,
+  compress_large_text: false,
+  spotlight_indexing: false,
+  auto_clear_schedule: 'off',
+  min_free_space_mb: 200,
+  capture_window_titles: false,
+  thumbnail_format: 'png',
+  quicklook_timeout_ms: 3000,
+  recent_items_os_menu: true,
+  capture_find_pasteboard: false,
+  capture_paused: false,
+  exclude_from_os_history: false,
+  dedupe_scope: 'global',
+  max_image_dimension: 0,
+  max_image_capture_mb: 100,
+  debug_capture_logging: false,
+  require_destructive_confirmation: true,
+  merge_rapid_text_captures: false,
+  merge_window_ms: 500,
+  backup_interval_hours: 0,
 };
 
 export const useSettingsStore = defineStore('settings', {
@@ -54,7 +132,7 @@ export const useSettingsStore = defineStore('settings', {
         const settings = await invoke<AppSettings>('get_settings');
         this.settings = settings;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to fetch settings:', e);
       } finally {
         this.loading = false;
@@ -86,7 +164,7 @@ export const useSettingsStore = defineStore('settings', {
 
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to update setting:', e);
         return false;
       }
@@ -101,12 +179,151 @@ export const useSettingsStore = defineStore('settings', {
         this.settings.history_limit = limit;
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to set history limit:', e);
         return false;
       }
     },
 
+    /**
+     * Pause or resume clipboard capture (restored at the next launch)
+     */
+    async setCapturePaused(paused: boolean): Promise<boolean> {
+      try {
+        await invoke('set_capture_paused', { paused });
+        this.settings.capture_paused = paused;
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to set capture paused:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Capture the next clipboard change even from a rate-limited app
+     * Returns when the unused override lapses (ISO 8601); the backend emits
+     * override-consumed once a capture uses it
+     */
+    async allowNextCapture(): Promise<string | null> {
+      try {
+        return await invoke<string>('allow_next_capture');
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to allow next capture:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Get the per-pinboard palette shortcuts (pinboard ID -> shortcut)
+     */
+    async getPinboardShortcuts(): Promise<Record<string, string>> {
+      try {
+        return await invoke<Record<string, string>>('get_pinboard_shortcuts');
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get pinboard shortcuts:', e);
+        return {};
+      }
+    },
+
+    /**
+     * Save and register the per-pinboard palette shortcuts
+     */
+    async setPinboardShortcuts(shortcuts: Record<string, string>): Promise<boolean> {
+      try {
+        await invoke('set_pinboard_shortcuts', { shortcuts });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to set pinboard shortcuts:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Get the global panel placement and the current monitor's own
+     */
+    async getPanelPlacement(): Promise<PanelPlacementSettings | null> {
+      try {
+        return await invoke<PanelPlacementSettings>('get_panel_placement');
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get panel placement:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Set the panel placement globally, or for the monitor under the cursor only
+     * A null placement resets the global one, or makes the monitor follow it again
+     */
+    async setPanelPlacement(placement: Placement | null, thisMonitor = false): Promise<boolean> {
+      try {
+        await invoke('set_panel_placement', { placement, thisMonitor });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to set panel placement:', e);
+        return false;
+      }
+    },
+
+    /**
+     * App and window focus goes back to when the panel closes
+     */
+    async getPasteTarget(): Promise<PasteTarget | null> {
+      try {
+        return await invoke<PasteTarget | null>('get_paste_target');
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to get paste target:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Hide the panel and give focus back to the window it was summoned from
+     */
+    async focusPasteTarget(): Promise<PasteActivation | null> {
+      try {
+        return await invoke<PasteActivation | null>('focus_paste_target');
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to focus paste target:', e);
+        return null;
+      }
+    },
+
+    /**
+     * Export every setting to a JSON file (history is not included)
+     */
+    async exportSettings(path: string): Promise<boolean> {
+      try {
+        await invoke<number>('export_settings', { path });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to export settings:', e);
+        return false;
+      }
+    },
+
+    /**
+     * Import a settings file; nothing changes if any value is invalid
+     */
+    async importSettings(path: string): Promise<boolean> {
+      try {
+        this.settings = await invoke<AppSettings>('import_settings', { path });
+        return true;
+      } catch (e) {
+        this.error = errorMessage(e);
+        console.error('Failed to import settings:', e);
+        return false;
+      }
+    },
+
     /**
      * Show settings modal
      */
@@ -126,14 +343,10 @@ export const useSettingsStore = defineStore('settings', {
      */
     async resetToDefaults(): Promise<boolean> {
       try {
-        await this.updateSetting('shortcut', defaultSettings.shortcut);
-        await this.setHistoryLimit(defaultSettings.history_limit);
-        await this.updateSetting('start_hidden', defaultSettings.start_hidden);
-        await this.updateSetting('theme', defaultSettings.theme);
-        await this.updateSetting('show_menu_bar_icon', defaultSettings.show_menu_bar_icon);
+        this.settings = await invoke<AppSettings>('reset_settings_to_defaults');
         return true;
       } catch (e) {
-        this.error = e instanceof Error ? e.message : String(e);
+        this.error = errorMessage(e);
         console.error('Failed to reset settings:', e);
         return false;
       }
@@ -4,6 +4,12 @@
  */
 export type ContentType = 'text' | 'image' | 'files' | 'link' | 'audio' | 'documents';
 
+/**
+ * Structured text recognized at capture (vCard contact, iCalendar event)
+ * Matches Rust enum: crate::models::ContentSubtype
+ */
+export type ContentSubtype = 'contact' | 'event';
+
 /**
  * Clipboard item structure
  * Matches Rust struct: crate::models::ClipboardItem
@@ -13,12 +19,80 @@ export interface ClipboardItem {
   content_type: ContentType;
   content_text: string | null;
   thumbnail_base64: string | null;
+  /** Link thumbnail made from the site icon (the page has no OG image) */
+  thumbnail_is_favicon: boolean;
   image_path: string | null;
   source_app: string | null;
   source_app_icon: string | null;
+  /** Focused window title at capture time (capture_window_titles setting) */
+  source_window_title?: string;
+  /** Generated title, e.g. "Screenshot 2024-05-12 14:03" for macOS screenshots */
+  title?: string;
+  /** Set for items captured from the macOS find pasteboard (capture_find_pasteboard setting) */
+  source_pasteboard?: 'find';
+  /** Last time the content was copied (move-to-top renews it) */
   created_at: string;
+  /** First time the content was copied, kept across move-to-top */
+  first_copied_at: string;
   pinboard_id: string | null;
   is_favorite: boolean;
+  /** Pinboard the item was auto-filed into (per-app default) while still in history */
+  auto_pinboard_id?: string;
+  /** Full text file for large text items (content_text then holds a preview) */
+  text_path?: string;
+  /**
+   * Multi-file copies: "40 files from ~/Photos/Trip — 38 jpg, 2 mov"
+   * Copied folders: "Folder Trip — 124 items, 3.2 MB"
+   */
+  files_summary?: string;
+  /** File copy of a single folder */
+  is_directory: boolean;
+  /** Image size as copied, when the stored image was downscaled (or couldn't be) */
+  original_width?: number;
+  original_height?: number;
+  /** Over max_image_dimension but stored as copied (it couldn't be decoded) */
+  downscale_skipped: boolean;
+  /** Times the item was copied back to the clipboard */
+  use_count: number;
+  /** Duration and tags of a single audio file, filled in after capture */
+  media_metadata?: MediaMetadata;
+  /** ISO 639-3 code of the language of text items (`eng`, `fra`), detected after capture */
+  language?: string;
+  /** Dominant colors of image items (`#rrggbb`, most common first), extracted after capture */
+  palette?: string[];
+  /** Text items holding a vCard or iCalendar payload; `title` is its summary */
+  content_subtype?: ContentSubtype;
+  /** When the item deletes itself (set_item_expiry); absent keeps it */
+  expires_at?: string;
+  /** One-line preview (line breaks shown as ␤, at most 100 characters) */
+  preview: string;
+}
+
+/**
+ * Duration and tags read from an audio file
+ * Matches Rust struct: crate::models::MediaMetadata
+ */
+export interface MediaMetadata {
+  duration_ms?: number;
+  title?: string;
+  artist?: string;
+  album?: string;
+}
+
+/**
+ * Order of get_clipboard_history (newest first)
+ * Matches Rust enum: crate::storage::database::HistorySort
+ */
+export type HistorySort = 'created_at' | 'first_copied_at';
+
+/**
+ * Smart pinboard rules (all set predicates must match)
+ * Matches Rust struct: crate::models::PinboardRules
+ */
+export interface PinboardRules {
+  source_app?: string;
+  content_type?: ContentType;
+  text_contains?: string;
 }
 
 /**
@@ -29,10 +103,29 @@ export interface Pinboard {
   id: string;
   name: string;
   icon: string | null;
+  /** Hex color (#RRGGBB); a palette color when none was chosen */
+  color: string;
+  description?: string;
+  /** Pinboard this one is nested under; absent at the root */
+  parent_id?: string;
+  /** Position among pinboards with the same parent */
   position: number;
   created_at: string;
+  rules?: PinboardRules;
+  /** Transform applied when its items are copied back */
+  default_transform?: PasteTransform;
+  archived: boolean;
+  is_locked: boolean;
+  item_count: number;
 }
 
+/**
+ * Transform of the text written by copy_to_clipboard; the stored item is
+ * left unchanged. An explicit transform wins over the pinboard's default
+ * Matches Rust enum: crate::clipboard::transforms::Transform
+ */
+export type PasteTransform = 'plain_text' | 'trim' | 'lowercase' | 'lowercase_trimmed';
+
 /**
  * Payload for clipboard-changed event
  * Matches Rust struct: ClipboardChangedPayload
@@ -41,6 +134,63 @@ export interface ClipboardChangedPayload {
   item: ClipboardItem;
   /** If this item replaced an existing one (move to top), contains the old item's ID */
   replaced_item_id?: string;
+  /** Change-log seq after this capture; use get_changes_since to fill gaps */
+  seq: number;
+}
+
+/**
+ * Capture inside a clipboard-changed-batch event
+ * Matches Rust struct: CapturedItem
+ */
+export interface CapturedItem {
+  item: ClipboardItem;
+  replaced_item_id?: string;
+}
+
+/**
+ * Payload for clipboard-changed-batch event (captures less than 150ms apart)
+ * Matches Rust struct: ClipboardChangedBatchPayload
+ */
+export interface ClipboardChangedBatchPayload {
+  /** Oldest first; apply in order so the newest ends up on top */
+  items: CapturedItem[];
+  seq: number;
+}
+
+/**
+ * Latest change of a single item
+ * Matches Rust struct: ItemChange
+ */
+export interface ItemChange {
+  seq: number;
+  item_id: string;
+  /** 'hidden': the item is in a locked pinboard, drop it like a deletion */
+  kind: 'insert' | 'update' | 'delete' | 'hidden';
+  item?: ClipboardItem;
+}
+
+/**
+ * Result of get_changes_since
+ * Matches Rust struct: ChangeSet
+ */
+export interface ChangeSet {
+  latest_seq: number;
+  /** The requested range was trimmed; refetch everything */
+  reset: boolean;
+  changes: ItemChange[];
+}
+
+/**
+ * Previous text of an edited item (at most 10 kept per item)
+ * Matches Rust struct: crate::models::ItemRevision
+ */
+export interface ItemRevision {
+  item_id: string;
+  /** Highest number is the most recent revision */
+  revision_no: number;
+  content_text: string;
+  /** When this text was replaced */
+  edited_at: string;
 }
 
 /**
@@ -50,4 +200,684 @@ export interface ClipboardChangedPayload {
 export interface ThumbnailUpdatedPayload {
   id: string;
   thumbnail_base64: string;
+  thumbnail_is_favicon: boolean;
+}
+
+/**
+ * Payload for item-updated event: fields of an item already shown that
+ * changed (the source app icon extracted in the background on Windows, the
+ * content type set by reclassify_items, the metadata read from an audio file,
+ * the detected language of a text item or the palette of an image)
+ * Matches Rust struct: ItemUpdatedPayload
+ */
+export interface ItemUpdatedPayload {
+  id: string;
+  source_app_icon?: string;
+  content_type?: ContentType;
+  media_metadata?: MediaMetadata;
+  language?: string;
+  palette?: string[];
+  /** Text grown by a merged rapid capture, with its new preview */
+  content_text?: string;
+  preview?: string;
+}
+
+/**
+ * Payload for item-deleted event: an item the backend deleted on its own
+ * (its expiry passed)
+ * Matches Rust struct: ItemDeletedPayload
+ */
+export interface ItemDeletedPayload {
+  id: string;
+}
+
+/**
+ * Operation applied to every selected item by bulk_update_items
+ * Matches Rust enum: BulkOp
+ */
+export type BulkOp =
+  | { type: 'move_to_pinboard'; pinboard_id: string }
+  | { type: 'remove_from_pinboard' }
+  | { type: 'favorite'; favorite: boolean }
+  | { type: 'delete' };
+
+/**
+ * Outcome of a bulk operation for one item
+ * Matches Rust struct: BulkItemResult
+ */
+export interface BulkItemResult {
+  id: string;
+  ok: boolean;
+  /** Why the item was skipped (e.g. it no longer exists) */
+  error?: string;
+}
+
+/**
+ * Payload for items-updated event, sent once per bulk operation
+ * Matches Rust struct: ItemsUpdatedPayload
+ */
+export interface ItemsUpdatedPayload {
+  /** Items as they are after the operation */
+  updated: ClipboardItem[];
+  deleted: string[];
+}
+
+/**
+ * Every event payload arrives with the version of its format next to its own
+ * fields; the version is bumped when a field changes meaning or goes away
+ * Matches Rust struct: Versioned
+ */
+export type Versioned<T> = T & { payload_version: number };
+
+/**
+ * JSON schema of one event's payload, as returned by get_event_schema
+ * Matches Rust struct: EventSchema
+ */
+export interface EventSchema {
+  name: string;
+  payload_version: number;
+  /** JSON Schema (draft 7) of the payload, payload_version included */
+  schema: Record<string, unknown>;
+}
+
+/**
+ * Output format for export_item_image
+ * Matches Rust enum: ExportFormat
+ */
+export type ExportFormat = 'png' | 'jpeg' | 'webp';
+
+/**
+ * Error rejected by Tauri commands
+ * Matches Rust enum: ClipsterError (serialized as { code, message })
+ */
+export interface ClipsterError {
+  code:
+    | 'not_found'
+    | 'database'
+    | 'busy'
+    | 'io'
+    | 'image_decode'
+    | 'clipboard_access'
+    | 'validation'
+    | 'permission_denied';
+  message: string;
+}
+
+/** Message of a rejected command, whether a ClipsterError or anything else thrown */
+export function errorMessage(e: unknown): string {
+  if (e instanceof Error) return e.message;
+  if (typeof e === 'object' && e !== null && 'message' in e) return String(e.message);
+  return String(e);
+}
+
+/**
+ * Data URL of base64 image data, with the MIME type of the format it is in
+ * Thumbnails follow the thumbnail_format setting, so PNG can't be assumed
+ */
+export function imageDataUrl(base64: string): string {
+  let mime = 'image/png';
+  if (base64.startsWith('/9j/')) {
+    mime = 'image/jpeg';
+  } else if (base64.startsWith('R0lGOD')) {
+    mime = 'image/gif';
+  } else if (base64.startsWith('UklGR')) {
+    mime = 'image/webp';
+  } else if (base64.startsWith('SUkq') || base64.startsWith('TU0AK')) {
+    mime = 'image/tiff';
+  }
+  return `data:${mime};base64,${base64}`;
+}
+
+/**
+ * Error returned by export_item_image
+ * Matches Rust enum: ImageExportError
+ */
+export type ImageExportError =
+  | { kind: 'item_not_found' }
+  | { kind: 'not_an_image' }
+  | { kind: 'source_missing'; message: string }
+  | { kind: 'failed'; message: string };
+
+/**
+ * Item that could not be exported by export_items_zip
+ * Matches Rust struct: ManifestError
+ */
+export interface ZipExportError {
+  id: string;
+  error: string;
+}
+
+/**
+ * Result of export_items_zip
+ * Matches Rust struct: ZipExportSummary
+ */
+export interface ZipExportSummary {
+  path: string;
+  exported: number;
+  errors: ZipExportError[];
+}
+
+/**
+ * Result of archive_items_older_than
+ * Matches Rust struct: ArchiveReport
+ */
+export interface ArchiveReport {
+  /** Items moved into the archive, oldest first */
+  archived_ids: string[];
+  images_moved: number;
+  /** Space the history database shrank by */
+  bytes_reclaimed: number;
+}
+
+/**
+ * Error saving a captured image
+ * Matches Rust enum: ImageSaveError
+ */
+export type ImageSaveError =
+  | { kind: 'disk_full'; message: string }
+  | { kind: 'failed'; message: string };
+
+/**
+ * Payload for storage-error event (a capture was lost)
+ * Matches Rust struct: StorageErrorPayload
+ */
+export interface StorageErrorPayload {
+  item_id: string;
+  error: ImageSaveError;
+}
+
+/**
+ * Payload for cycle-selection-changed event
+ * Matches Rust struct: CycleSelectionPayload
+ */
+export interface CycleSelectionPayload {
+  item: ClipboardItem;
+  index: number;
+  total: number;
+}
+
+/**
+ * Outcome of a single health check
+ * Matches Rust enum: Check<T>
+ */
+export type HealthCheck<T> = { status: 'ok'; value: T } | { status: 'failed'; error: string };
+
+/**
+ * Result of run_health_check
+ * Matches Rust struct: HealthReport
+ */
+export interface HealthReport {
+  integrity: HealthCheck<string>;
+  db_size_bytes: HealthCheck<number>;
+  wal_size_bytes: HealthCheck<number>;
+  images_dir: HealthCheck<{ path: string; exists: boolean; writable: boolean }>;
+  missing_image_files: HealthCheck<number>;
+  orphaned_image_files: HealthCheck<number>;
+  invalid_settings: HealthCheck<string[]>;
+  monitor_heartbeat_age_ms: number | null;
+}
+
+/**
+ * Result of get_stats
+ * Matches Rust struct: StorageStats
+ */
+export interface StorageStats {
+  total_items: number;
+  compressed_items: number;
+  uncompressed_bytes: number;
+  compressed_bytes: number;
+  saved_bytes: number;
+  /** Lifetime usage counters by name (captures, captures.<type>, pastes, items_pruned) */
+  counters: Record<string, number>;
+  /** Text items by detected language (ISO 639-3 code) */
+  languages: Record<string, number>;
+}
+
+/**
+ * Payload of the backup-progress event
+ * Matches Rust struct: BackupProgress
+ */
+export interface BackupProgress {
+  copied_pages: number;
+  total_pages: number;
+}
+
+/**
+ * Result of backup_now
+ * Matches Rust struct: BackupReport
+ */
+export interface BackupReport {
+  path: string;
+  size_bytes: number;
+  total_pages: number;
+}
+
+/**
+ * Result of get_monitoring_status
+ * Matches Rust struct: MonitoringStatus
+ */
+export interface MonitoringStatus {
+  running: boolean;
+  heartbeat_age_ms: number | null;
+  /** Captures dropped by app_rate_limits, per bundle identifier or executable path */
+  dropped_captures: Record<string, number>;
+  total_dropped: number;
+  /** Next scheduled history clear (ISO 8601), null unless a daily schedule is set */
+  next_auto_clear: string | null;
+  /**
+   * Free disk space is below min_free_space_mb (images are not being saved),
+   * or the images directory is unwritable and images go to a temporary
+   * fallback directory until repair_storage
+   */
+  storage_degraded: boolean;
+  /** Captures that failed since launch, per pipeline stage */
+  capture_failures: Partial<Record<CaptureStage, number>>;
+  total_capture_failures: number;
+  /** Source app lookups served from the cache since launch */
+  source_app_cache: { hits: number; misses: number };
+  /** Frontend events waiting for the emitter task */
+  queued_events: number;
+  /** Frontend events dropped because too many were waiting */
+  dropped_events: number;
+  /** Startup phases in the order they ended (deferred tasks as `deferred.<name>`) */
+  startup_phases: PhaseTiming[];
+}
+
+/**
+ * How long one startup phase took
+ * Matches Rust struct: PhaseTiming
+ */
+export interface PhaseTiming {
+  phase: string;
+  duration_ms: number;
+}
+
+/**
+ * Step of the capture pipeline that failed
+ * Matches Rust enum: CaptureStage
+ */
+export type CaptureStage = 'read' | 'decode' | 'store' | 'insert' | 'notify';
+
+/**
+ * Payload of the capture-failure event, also returned by get_capture_failures
+ * (never includes the content)
+ * Matches Rust struct: CaptureFailure
+ */
+export interface CaptureFailure {
+  timestamp: string;
+  stage: CaptureStage;
+  code:
+    | 'clipboard_locked'
+    | 'unreadable'
+    | 'still_writing'
+    | 'too_large'
+    | 'decode_failed'
+    | 'disk_full'
+    | 'write_failed'
+    | 'database_busy'
+    | 'database_error'
+    | 'event_queue_closed';
+  content_type: ContentType | null;
+}
+
+/**
+ * Payload of the capture-skipped-too-large event
+ * Matches Rust struct: TooLarge
+ */
+export interface CaptureTooLargePayload {
+  /** Size of the clipboard image as announced by the OS */
+  size_bytes: number;
+  limit_bytes: number;
+}
+
+/**
+ * State of a type_item run
+ * Matches Rust enum: TypingStatus
+ */
+export type TypingStatus = 'typing' | 'done' | 'cancelled' | 'failed';
+
+/**
+ * Payload of the typing-progress event
+ * Matches Rust struct: TypingProgress
+ */
+export interface TypingProgress {
+  item_id: string;
+  /** Keystrokes sent so far */
+  typed: number;
+  total: number;
+  status: TypingStatus;
+}
+
+/**
+ * Payload for storage-low event (sent once each time space runs low)
+ * Matches Rust struct: StorageLowPayload
+ */
+export interface StorageLowPayload {
+  /** null when a write failed with a full disk before a reading was taken */
+  free_bytes: number | null;
+  min_free_bytes: number;
+}
+
+/**
+ * Payload for storage-relocated event: the images directory could not be
+ * written (deleted or unmounted), so images are saved to fallback_dir until
+ * repair_storage moves them back
+ * Matches Rust struct: StorageRelocatedPayload
+ */
+export interface StorageRelocatedPayload {
+  images_dir: string;
+  fallback_dir: string;
+}
+
+/**
+ * How the panel is laid out: full-width bar along the bottom, or centered
+ */
+export type PlacementMode = 'bottom_bar' | 'centered_palette';
+
+/**
+ * Panel mode and size in logical points (omitted sizes use the mode's default)
+ * Matches Rust struct: Placement
+ */
+export interface Placement {
+  mode: PlacementMode;
+  width?: number;
+  height?: number;
+}
+
+/**
+ * Global panel placement and the one saved for the monitor under the cursor
+ * Matches Rust struct: PanelPlacementSettings
+ */
+export interface PanelPlacementSettings {
+  default: Placement;
+  monitor_id: string | null;
+  /** null when this monitor uses the global placement */
+  monitor: Placement | null;
 }
+
+/**
+ * Window that had focus when the panel was shown
+ * Matches Rust struct: PasteTarget
+ */
+export interface PasteTarget {
+  app_name: string | null;
+  pid: number;
+  /** CGWindowID on macOS, HWND on Windows */
+  window_id: number | null;
+  captured_at: string;
+}
+
+/**
+ * How focus_paste_target handed focus back
+ * Matches Rust enum: Activation
+ */
+export type PasteActivation = 'window' | 'app' | 'gone';
+
+/**
+ * Payload of the app-ready event, also returned by get_initial_state
+ * (the event is sent before the window loads)
+ * Matches Rust struct: InitialState
+ */
+export interface InitialState {
+  /** The panel was shown at launch (start_hidden is off) */
+  panel_visible: boolean;
+  /** Clipboard capture was left paused */
+  capture_paused: boolean;
+  menu_bar_icon_visible: boolean;
+  /** Panel view saved when it was last hidden */
+  view_state: ViewState | null;
+}
+
+/**
+ * Panel view saved by set_view_state when the panel hides (JSON, at most 8 KB)
+ */
+export interface ViewState {
+  /** null = All history */
+  active_pinboard_id: string | null;
+  /** Content type filter, null = all types */
+  type_filter?: ContentType | null;
+  /** Item selected in the timeline */
+  anchor_item_id?: string | null;
+}
+
+/**
+ * Payload of the database-recovered event, also returned by
+ * get_database_recovery (the event is sent before the window loads)
+ * Matches Rust struct: RecoveryReport
+ */
+export interface DatabaseRecovered {
+  /** Where the corrupt database was moved, kept for manual inspection */
+  corrupt_path: string;
+  reason: string;
+  items_recovered: number;
+  pinboards_recovered: number;
+}
+
+/**
+ * Separate clipboard history (e.g. work and personal)
+ * Matches Rust struct: Profile
+ * Also the payload of the 'profile-changed' event
+ */
+export interface Profile {
+  name: string;
+  data_dir: string;
+  is_active: boolean;
+}
+
+/**
+ * Result of import_images_to_pinboard
+ * Matches Rust struct: ImportSummary
+ */
+export interface ImportSummary {
+  total: number;
+  imported: number;
+  skipped_unsupported: number;
+  skipped_unreadable: number;
+  cancelled: boolean;
+}
+
+/**
+ * How find_duplicates compares items
+ * Matches Rust enum: DuplicateKind
+ */
+export type DuplicateKind = 'exact' | 'images';
+
+/**
+ * History items captured from one source app (get_history_by_app)
+ * Matches Rust struct: AppGroup
+ */
+export interface AppGroup {
+  /** Source app name, or "Unknown" for items without one */
+  name: string;
+  is_known: boolean;
+  icon?: string;
+  /** History items from this app, including those not in items */
+  total_count: number;
+  /** Most recent items, newest first */
+  items: ClipboardItem[];
+}
+
+/**
+ * Search results from one pinboard, or from history (search_grouped)
+ * Matches Rust struct: SearchGroup
+ */
+export interface SearchGroup {
+  /** null for history */
+  pinboard_id: string | null;
+  /** Pinboard name, or "History" */
+  name: string;
+  icon?: string;
+  /** Matching items in this group, including those not in items */
+  total_count: number;
+  /** Most recent matching items, newest first */
+  items: ClipboardItem[];
+}
+
+/**
+ * Items found to be duplicates of each other
+ * Matches Rust struct: DuplicateGroup
+ */
+export interface DuplicateGroup {
+  /** Newest first; the first is the suggested item to keep */
+  item_ids: string[];
+  preview: string;
+  thumbnail_base64?: string;
+}
+
+/**
+ * Number of items moving from one content type to another
+ * Matches Rust struct: TypeTransition
+ */
+export interface TypeTransition {
+  from: ContentType;
+  to: ContentType;
+  count: number;
+}
+
+/**
+ * Result of reclassify_items
+ * Matches Rust struct: ReclassifyReport
+ */
+export interface ReclassifyReport {
+  /** Nothing was written; the counts are what would change */
+  dry_run: boolean;
+  changed: number;
+  /** Most common first */
+  transitions: TypeTransition[];
+  /** Items whose detected language changed (or would change) */
+  languages_changed: number;
+}
+
+/**
+ * Field of a snippet template (`{name}` or `{name:default}`)
+ * Matches Rust struct: Placeholder
+ */
+export interface Placeholder {
+  name: string;
+  /** Used when no value is given */
+  default: string | null;
+}
+
+/**
+ * EXIF field of a stored image, formatted for display
+ * Matches Rust struct: ExifField
+ */
+export interface ExifField {
+  tag: string;
+  value: string;
+}
+
+/**
+ * Metadata of an image item's stored file (get_item_image_info)
+ * Fields are null when the file can't be decoded far enough to read them
+ * Matches Rust struct: ImageInfo
+ */
+export interface ImageInfo {
+  width: number | null;
+  height: number | null;
+  /** Detected format, e.g. "Png" or "Jpeg" */
+  format: string | null;
+  /** Decoded color type, e.g. "Rgba8" */
+  color_type: string | null;
+  /** Bits per channel */
+  bit_depth: number | null;
+  file_size: number;
+  dpi_x: number | null;
+  dpi_y: number | null;
+  /** EXIF capture date in the camera's local time, without a time zone */
+  captured_at: string | null;
+  exif: ExifField[];
+  /** What could not be read, if anything */
+  decode_warning: string | null;
+}
+
+/** Derived state rebuilt by reindex_all */
+export type ReindexComponent = 'canonical_urls' | 'files_summaries' | 'thumbnails' | 'app_icons';
+
+/**
+ * Payload for reindex-progress event
+ * Matches Rust struct: ReindexProgress
+ */
+export interface ReindexProgress {
+  component: ReindexComponent;
+  processed: number;
+  total: number;
+}
+
+/**
+ * Result of reindex_all
+ * Matches Rust struct: ReindexReport
+ */
+export interface ReindexReport {
+  /** Rows changed per component; 0 everywhere when nothing had drifted */
+  updated: Partial<Record<ReindexComponent, number>>;
+  cancelled: boolean;
+}
+
+/**
+ * Payload for image-import-progress event
+ * Matches Rust struct: ImportProgress
+ */
+export interface ImportProgress {
+  processed: number;
+  total: number;
+  imported: number;
+}
+
+/**
+ * What a destructive command would delete
+ * Matches Rust struct: DeletionSummary
+ */
+export interface DeletionSummary {
+  items: number;
+  /** Text in the database plus stored image and text files */
+  bytes: number;
+  pinboards: number;
+  /** Items moved back to history because their pinboard is deleted */
+  items_unpinned: number;
+}
+
+/**
+ * Token to confirm a destructive command with, valid for 30 seconds
+ * Matches Rust struct: Confirmation
+ */
+export interface Confirmation {
+  confirm_token: string;
+  summary: DeletionSummary;
+  expires_at: string;
+}
+
+/**
+ * Result of clear_clipboard_history and delete_pinboard
+ * Matches Rust enum: Guarded
+ */
+export type Guarded<T> =
+  | ({ status: 'confirmation_required' } & Confirmation)
+  | { status: 'done'; result: T };
+
+/**
+ * A macOS permission features depend on
+ * Matches Rust enum: PermissionKind
+ */
+export type PermissionKind = 'accessibility' | 'screen_recording' | 'full_disk_access';
+
+/**
+ * Matches Rust enum: GrantState ("unknown" when it can't be told without using it)
+ */
+export type GrantState = 'granted' | 'denied' | 'unknown';
+
+/**
+ * Result of check_permissions and payload of the permissions-changed event
+ * Matches Rust struct: PermissionStatus
+ */
+export interface PermissionStatus {
+  accessibility: GrantState;
+  screen_recording: GrantState;
+  full_disk_access: GrantState;
+}
+
+/**
+ * What request_permission did
+ * Matches Rust enum: RequestOutcome
+ */
+export type RequestOutcome = 'already_granted' | 'prompted' | 'opened_settings';